@@ -0,0 +1,196 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Benchmark of the allocations made to deliver a message.
+//!
+//! Sends messages from one node to every other node of a memory network and decodes them, once
+//! sharing the encoded message between the recipients and once copying it for each of them, like
+//! networks did before they passed messages around as `Bytes`. Reports the allocations and
+//! throughput per message in both cases.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use bytes::Bytes;
+use clap::Parser;
+use hotshot::traits::implementations::{MasterMap, MemoryNetwork};
+use hotshot_example_types::node_types::{TestTypes, TestVersions};
+use hotshot_types::{
+    message::{Message, MessageKind, UpgradeLock},
+    signature_key::BLSPubKey,
+    traits::{
+        network::{ConnectedNetwork, Topic},
+        signature_key::SignatureKey,
+    },
+};
+use serde::Serialize;
+
+/// The number of allocations made so far
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The number of bytes allocated so far
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting allocations
+struct CountingAllocator;
+
+// SAFETY: every call is forwarded to the system allocator
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+/// The allocator of the benchmark
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+/// Benchmark the allocations made to deliver a message
+struct Args {
+    /// The number of nodes receiving each message
+    #[arg(long, default_value_t = 10)]
+    recipients: usize,
+
+    /// The number of messages to send
+    #[arg(long, default_value_t = 1000)]
+    messages: usize,
+
+    /// The size of the payload of each message, in bytes
+    #[arg(long, default_value_t = 100_000)]
+    size: usize,
+
+    /// A file to also write the results to
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// The results of sending the messages one way
+#[derive(Debug, Serialize)]
+struct AllocationResult {
+    /// whether the recipients shared the encoded message, or each got a copy
+    mode: &'static str,
+    /// the number of recipients of each message
+    recipients: usize,
+    /// the size of the encoded messages
+    message_bytes: usize,
+    /// allocations per message sent, across all recipients
+    allocations_per_message: u64,
+    /// bytes allocated per message sent, across all recipients
+    allocated_bytes_per_message: u64,
+    /// messages sent per second
+    messages_per_sec: f64,
+}
+
+/// Send `args.messages` copies of `message` to every recipient, copying it for each of them
+/// unless `share`, and measure the allocations until all of them are decoded
+async fn run(args: &Args, message: &Bytes, share: bool) -> AllocationResult {
+    let group = MasterMap::new();
+    let key = |index| BLSPubKey::generated_from_seed_indexed([0u8; 32], index).0;
+    let sender = MemoryNetwork::new(&key(0), &group, &[Topic::Global], None);
+    let recipients: Vec<_> = (1..=args.recipients as u64)
+        .map(|index| {
+            (
+                key(index),
+                MemoryNetwork::new(&key(index), &group, &[], None),
+            )
+        })
+        .collect();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let receivers: Vec<_> = recipients
+        .iter()
+        .map(|(_, network)| {
+            let network = network.clone();
+            let messages = args.messages;
+            tokio::spawn(async move {
+                let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+                for _ in 0..messages {
+                    let bytes = network.recv_message().await.expect("The network shut down");
+                    upgrade_lock
+                        .deserialize_message(&bytes)
+                        .await
+                        .expect("Failed to decode a message");
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..args.messages {
+        for (recipient, _) in &recipients {
+            let bytes = if share {
+                message.clone()
+            } else {
+                Bytes::copy_from_slice(message)
+            };
+            sender
+                .direct_message(bytes, *recipient)
+                .await
+                .expect("Failed to send a message");
+        }
+    }
+    for receiver in receivers {
+        receiver.await.expect("A recipient failed");
+    }
+
+    let elapsed = start.elapsed();
+    let messages = args.messages.max(1) as u64;
+    #[allow(clippy::cast_precision_loss)]
+    let messages_per_sec = args.messages as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+    AllocationResult {
+        mode: if share { "shared" } else { "copied" },
+        recipients: args.recipients,
+        message_bytes: message.len(),
+        allocations_per_message: (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / messages,
+        allocated_bytes_per_message: (ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes)
+            / messages,
+        messages_per_sec,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    let message = Message::<TestTypes> {
+        sender: BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0,
+        kind: MessageKind::External(vec![0u8; args.size]),
+        namespace: 0,
+        expires_after: None,
+    };
+    let message = Bytes::from(
+        UpgradeLock::<TestTypes, TestVersions>::new()
+            .serialize(&message)
+            .await
+            .expect("Failed to encode the message"),
+    );
+
+    let results = vec![
+        run(&args, &message, true).await,
+        run(&args, &message, false).await,
+    ];
+
+    let json = serde_json::to_string_pretty(&results).expect("Failed to serialize the results");
+    println!("{json}");
+    if let Some(output) = &args.output {
+        std::fs::write(output, &json).expect("Failed to write the results");
+    }
+}
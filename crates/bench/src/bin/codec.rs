@@ -0,0 +1,177 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Benchmark of the wire codecs.
+//!
+//! Encodes and decodes the messages of a few generated views in every codec, then reports the
+//! encoded size and the time taken per message kind and codec.
+
+use std::{
+    hint::black_box,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+#[cfg(feature = "protobuf")]
+use hotshot_types::codec::ProtobufCodec;
+use hotshot_types::{
+    codec::{BincodeCodec, CborCodec, WireCodec},
+    constants::MAX_MESSAGE_SIZE,
+    message::{
+        DaConsensusMessage, GeneralConsensusMessage, Message, MessageKind, SequencingMessage,
+    },
+    signature_key::BLSPubKey,
+    traits::node_implementation::Versions,
+};
+use serde::Serialize;
+use vbs::version::StaticVersionType;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+/// Benchmark the wire codecs
+struct Args {
+    /// The number of views to generate messages from
+    #[arg(long, default_value_t = 5)]
+    views: usize,
+
+    /// How many times each message is encoded and decoded
+    #[arg(long, default_value_t = 100)]
+    iterations: u32,
+
+    /// A file to also write the results to
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// The results for one kind of message in one codec
+#[derive(Debug, Serialize)]
+struct CodecResult {
+    /// the codec
+    codec: &'static str,
+    /// the kind of message
+    message: &'static str,
+    /// the mean encoded size of the messages
+    bytes: usize,
+    /// the mean time to encode a message
+    encode_ns: u128,
+    /// the mean time to decode a message
+    decode_ns: u128,
+}
+
+/// Encode and decode each of `messages` `iterations` times in `C`
+fn bench<C: WireCodec<TestTypes>>(
+    name: &'static str,
+    messages: &[Message<TestTypes>],
+    iterations: u32,
+) -> CodecResult {
+    let version = <TestVersions as Versions>::Base::VERSION;
+    let mut encode = Duration::ZERO;
+    let mut decode = Duration::ZERO;
+    let mut bytes = 0;
+
+    for message in messages {
+        let encoded = C::encode(message, version, MAX_MESSAGE_SIZE).expect("Failed to encode");
+        bytes += encoded.len();
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(C::encode(black_box(message), version, MAX_MESSAGE_SIZE).unwrap());
+        }
+        encode += start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(C::decode(black_box(&encoded), MAX_MESSAGE_SIZE).unwrap());
+        }
+        decode += start.elapsed();
+    }
+
+    let runs = iterations * u32::try_from(messages.len().max(1)).unwrap_or(u32::MAX);
+    CodecResult {
+        codec: C::NAME,
+        message: name,
+        bytes: bytes / messages.len().max(1),
+        encode_ns: (encode / runs).as_nanos(),
+        decode_ns: (decode / runs).as_nanos(),
+    }
+}
+
+/// The consensus message `kind` sent by `sender`
+fn message(sender: BLSPubKey, kind: SequencingMessage<TestTypes>) -> Message<TestTypes> {
+    Message {
+        sender,
+        kind: MessageKind::Consensus(kind),
+        namespace: 0,
+        expires_after: None,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    hotshot::helpers::initialize_logging();
+
+    let args = Args::parse();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(1)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+
+    let mut quorum_proposals = vec![];
+    let mut da_proposals = vec![];
+    let mut vid_shares = vec![];
+    let mut da_certificates = vec![];
+    for _ in 0..args.views {
+        let view = generator.next().await.expect("The generator never ends");
+        let leader = view.leader_public_key;
+
+        quorum_proposals.push(message(
+            leader,
+            SequencingMessage::General(GeneralConsensusMessage::Proposal2(view.quorum_proposal)),
+        ));
+        da_proposals.push(message(
+            leader,
+            SequencingMessage::Da(DaConsensusMessage::DaProposal2(view.da_proposal)),
+        ));
+        vid_shares.extend(view.vid_proposal.0.into_iter().map(|share| {
+            message(
+                leader,
+                SequencingMessage::Da(DaConsensusMessage::VidDisperseMsg2(share)),
+            )
+        }));
+        da_certificates.push(message(
+            leader,
+            SequencingMessage::Da(DaConsensusMessage::DaCertificate2(view.da_certificate)),
+        ));
+    }
+    let kinds = [
+        ("quorum proposal", quorum_proposals),
+        ("DA proposal", da_proposals),
+        ("VID share", vid_shares),
+        ("DA certificate", da_certificates),
+    ];
+
+    let mut results = vec![];
+    for (name, messages) in kinds {
+        results.push(bench::<BincodeCodec>(name, &messages, args.iterations));
+        results.push(bench::<CborCodec>(name, &messages, args.iterations));
+        #[cfg(feature = "protobuf")]
+        results.push(bench::<ProtobufCodec>(name, &messages, args.iterations));
+    }
+
+    let json = serde_json::to_string_pretty(&results).expect("Failed to serialize the results");
+    println!("{json}");
+    if let Some(output) = &args.output {
+        std::fs::write(output, &json).expect("Failed to write the results");
+    }
+
+    handle.shut_down().await;
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Benchmark of how decide latency depends on the view timeout.
+//!
+//! Runs the same network of in-process nodes with each of the given view timeouts and reports the
+//! decide latency for each. Leaders propose and replicas vote as soon as a quorum responded, with
+//! the timers only as a fallback, so the latency should not grow with the timeout. A path which
+//! waits on a timer shows up as latency growing with it.
+
+use std::{path::PathBuf, time::Duration};
+
+use clap::Parser;
+use hotshot_example_types::node_types::{EpochsTestVersions, MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    overall_safety_task::OverallSafetyPropertiesDescription,
+    test_builder::{TestDescription, TimingData},
+    test_report::TestReport,
+    txn_task::TxnTaskDescription,
+    view_sync_task::ViewSyncTaskDescription,
+};
+use hotshot_types::traits::node_implementation::Versions;
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+/// Benchmark decide latency against the view timeout
+struct Args {
+    /// The view timeouts to run with, in milliseconds
+    #[arg(long, value_delimiter = ',', default_values_t = [2_000, 10_000, 30_000])]
+    timeouts_ms: Vec<u64>,
+
+    /// Run HotStuff 2 with epochs, where leaders wait for the high QCs of a quorum
+    #[arg(long)]
+    epochs: bool,
+
+    /// The number of nodes
+    #[arg(long, default_value_t = 10)]
+    nodes: usize,
+
+    /// The number of views to decide for each timeout
+    #[arg(long, default_value_t = 50)]
+    views: usize,
+
+    /// A file to also write the results to
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// The results of the run with one view timeout
+#[derive(Debug, Serialize)]
+struct TimeoutResult {
+    /// the view timeout
+    timeout_ms: u64,
+    /// views decided per second
+    views_per_sec: f64,
+    /// median time from the start of a view to its decide
+    latency_p50_ms: Option<u64>,
+    /// 90th percentile time from the start of a view to its decide
+    latency_p90_ms: Option<u64>,
+    /// what went wrong, if consensus did not keep up
+    failures: Vec<String>,
+}
+
+impl TimeoutResult {
+    /// Summarize the report of the run with `timeout_ms`
+    fn new(timeout_ms: u64, report: &TestReport) -> Self {
+        let mut latencies: Vec<u64> = report
+            .rounds
+            .values()
+            .filter_map(|round| round.decide_latency_ms)
+            .collect();
+        latencies.sort_unstable();
+        let percentile =
+            |p: usize| (!latencies.is_empty()).then(|| latencies[(latencies.len() - 1) * p / 100]);
+
+        #[allow(clippy::cast_precision_loss)]
+        let views_per_sec =
+            report.views_decided as f64 / (report.duration_ms.max(1) as f64 / 1000.0);
+
+        Self {
+            timeout_ms,
+            views_per_sec,
+            latency_p50_ms: percentile(50),
+            latency_p90_ms: percentile(90),
+            failures: report.failures.clone(),
+        }
+    }
+}
+
+/// Run the network with a view timeout of `timeout_ms`
+async fn run<V: Versions>(args: &Args, timeout_ms: u64) -> TestReport {
+    let default = TestDescription::<TestTypes, MemoryImpl, V>::default();
+    let description = TestDescription {
+        num_nodes_with_stake: args.nodes,
+        start_nodes: args.nodes,
+        num_bootstrap_nodes: args.nodes,
+        da_staked_committee_size: args.nodes,
+        epoch_height: if args.epochs { 10 } else { 0 },
+        timing_data: TimingData {
+            next_view_timeout: timeout_ms,
+            ..default.timing_data
+        },
+        overall_safety_properties: OverallSafetyPropertiesDescription {
+            num_successful_views: args.views,
+            num_failed_views: args.views,
+            ..Default::default()
+        },
+        txn_description: TxnTaskDescription::RoundRobinTimeBased(Duration::from_millis(10)),
+        completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+            TimeBasedCompletionTaskDescription {
+                // every view may wait for its timeout if progress is not event-driven
+                duration: Duration::from_millis(timeout_ms) * u32::try_from(args.views).unwrap(),
+            },
+        ),
+        view_sync_properties: ViewSyncTaskDescription::Threshold(0, args.nodes),
+        ..default
+    };
+
+    description
+        .gen_launcher(0)
+        .launch()
+        .run_test_and_report::<SimpleBuilderImplementation>()
+        .await
+}
+
+#[tokio::main]
+async fn main() {
+    hotshot::helpers::initialize_logging();
+
+    let args = Args::parse();
+
+    let mut results = Vec::with_capacity(args.timeouts_ms.len());
+    for &timeout_ms in &args.timeouts_ms {
+        let report = if args.epochs {
+            run::<EpochsTestVersions>(&args, timeout_ms).await
+        } else {
+            run::<TestVersions>(&args, timeout_ms).await
+        };
+        results.push(TimeoutResult::new(timeout_ms, &report));
+    }
+
+    let json = serde_json::to_string_pretty(&results).expect("Failed to serialize the results");
+    println!("{json}");
+    if let Some(output) = &args.output {
+        std::fs::write(output, &json).expect("Failed to write the results");
+    }
+}
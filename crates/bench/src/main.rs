@@ -0,0 +1,216 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Throughput and latency benchmark of `HotShot`.
+//!
+//! Runs a network of in-process nodes under a steady transaction load until it decides the
+//! requested number of views, then reports views per second, decide latency percentiles and,
+//! on the memory network, the bytes sent between nodes.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use hotshot_example_types::node_types::{
+    Libp2pImpl, MemoryImpl, PushCdnImpl, TestTypes, TestVersions,
+};
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    overall_safety_task::OverallSafetyPropertiesDescription,
+    test_builder::TestDescription,
+    test_report::TestReport,
+    txn_task::TxnTaskDescription,
+    view_sync_task::ViewSyncTaskDescription,
+};
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    traits::{
+        network::{Interception, MessageHook},
+        node_implementation::NodeImplementation,
+    },
+};
+use serde::Serialize;
+
+/// The network the nodes run on
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Profile {
+    /// the in-memory network
+    Memory,
+    /// libp2p, over localhost
+    Libp2p,
+    /// the Push CDN, with an in-process broker and marshal
+    Cdn,
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+/// Benchmark consensus with in-process nodes
+struct Args {
+    /// The network to run the nodes on
+    #[arg(long, value_enum, default_value_t = Profile::Memory)]
+    profile: Profile,
+
+    /// The number of nodes
+    #[arg(long, default_value_t = 10)]
+    nodes: usize,
+
+    /// The number of views to decide before stopping
+    #[arg(long, default_value_t = 100)]
+    views: usize,
+
+    /// Milliseconds between two transactions submitted to the network
+    #[arg(long, default_value_t = 10)]
+    txn_interval_ms: u64,
+
+    /// The longest to run for, in seconds, if the views are not decided sooner
+    #[arg(long, default_value_t = 600)]
+    timeout_secs: u64,
+
+    /// A file to also write the results to
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// The results of a benchmark run
+#[derive(Debug, Serialize)]
+struct BenchResult {
+    /// the network the nodes ran on
+    profile: String,
+    /// the number of nodes
+    nodes: usize,
+    /// the number of views decided
+    views_decided: usize,
+    /// how long the run took
+    duration_ms: u64,
+    /// views decided per second
+    views_per_sec: f64,
+    /// median time from the start of a view to its decide
+    latency_p50_ms: Option<u64>,
+    /// 90th percentile time from the start of a view to its decide
+    latency_p90_ms: Option<u64>,
+    /// 99th percentile time from the start of a view to its decide
+    latency_p99_ms: Option<u64>,
+    /// bytes sent between nodes, if the network can count them
+    bytes_on_wire: Option<u64>,
+    /// what went wrong, if consensus did not keep up
+    failures: Vec<String>,
+}
+
+impl BenchResult {
+    /// Summarize a test report
+    fn new(args: &Args, report: &TestReport, bytes_on_wire: Option<u64>) -> Self {
+        let mut latencies: Vec<u64> = report
+            .rounds
+            .values()
+            .filter_map(|round| round.decide_latency_ms)
+            .collect();
+        latencies.sort_unstable();
+        let percentile =
+            |p: usize| (!latencies.is_empty()).then(|| latencies[(latencies.len() - 1) * p / 100]);
+
+        #[allow(clippy::cast_precision_loss)]
+        let views_per_sec =
+            report.views_decided as f64 / (report.duration_ms.max(1) as f64 / 1000.0);
+
+        Self {
+            profile: format!("{:?}", args.profile).to_lowercase(),
+            nodes: args.nodes,
+            views_decided: report.views_decided,
+            duration_ms: report.duration_ms,
+            views_per_sec,
+            latency_p50_ms: percentile(50),
+            latency_p90_ms: percentile(90),
+            latency_p99_ms: percentile(99),
+            bytes_on_wire,
+            failures: report.failures.clone(),
+        }
+    }
+}
+
+/// Describe the benchmark as a test
+fn description<I: NodeImplementation<TestTypes>>(
+    args: &Args,
+    message_hooks: Vec<MessageHook<BLSPubKey>>,
+) -> TestDescription<TestTypes, I, TestVersions> {
+    TestDescription {
+        num_nodes_with_stake: args.nodes,
+        start_nodes: args.nodes,
+        num_bootstrap_nodes: args.nodes,
+        da_staked_committee_size: args.nodes,
+        overall_safety_properties: OverallSafetyPropertiesDescription {
+            num_successful_views: args.views,
+            // a slow network shows up in the results rather than failing the run
+            num_failed_views: args.views,
+            ..Default::default()
+        },
+        txn_description: TxnTaskDescription::RoundRobinTimeBased(Duration::from_millis(
+            args.txn_interval_ms,
+        )),
+        completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+            TimeBasedCompletionTaskDescription {
+                duration: Duration::from_secs(args.timeout_secs),
+            },
+        ),
+        view_sync_properties: ViewSyncTaskDescription::Threshold(0, args.nodes),
+        message_hooks,
+        ..TestDescription::default()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    hotshot::helpers::initialize_logging();
+
+    let args = Args::parse();
+
+    let (report, bytes_on_wire) = match args.profile {
+        Profile::Memory => {
+            // only the memory network lets us see every message between nodes
+            let bytes = Arc::new(AtomicU64::new(0));
+            let counter = Arc::clone(&bytes);
+            let hook: MessageHook<BLSPubKey> = Arc::new(move |_, _, message| {
+                counter.fetch_add(message.len() as u64, Ordering::Relaxed);
+                Interception::Deliver
+            });
+            let report = description::<MemoryImpl>(&args, vec![hook])
+                .gen_launcher(0)
+                .launch()
+                .run_test_and_report::<SimpleBuilderImplementation>()
+                .await;
+            (report, Some(bytes.load(Ordering::Relaxed)))
+        }
+        Profile::Libp2p => {
+            let report = description::<Libp2pImpl>(&args, vec![])
+                .gen_launcher(0)
+                .launch()
+                .run_test_and_report::<SimpleBuilderImplementation>()
+                .await;
+            (report, None)
+        }
+        Profile::Cdn => {
+            let report = description::<PushCdnImpl>(&args, vec![])
+                .gen_launcher(0)
+                .launch()
+                .run_test_and_report::<SimpleBuilderImplementation>()
+                .await;
+            (report, None)
+        }
+    };
+
+    let result = BenchResult::new(&args, &report, bytes_on_wire);
+    let json = serde_json::to_string_pretty(&result).expect("Failed to serialize the results");
+    println!("{json}");
+    if let Some(output) = &args.output {
+        std::fs::write(output, &json).expect("Failed to write the results");
+    }
+}
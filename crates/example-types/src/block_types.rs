@@ -14,6 +14,7 @@ use async_trait::async_trait;
 use committable::{Commitment, Committable, RawCommitmentBuilder};
 use hotshot_types::{
     data::{BlockError, Leaf2},
+    deterministic::with_rng,
     traits::{
         block_contents::{BlockHeader, BuilderFee, EncodeBytes, TestableBlock, Transaction},
         node_implementation::NodeType,
@@ -22,7 +23,7 @@ use hotshot_types::{
     utils::BuilderCommitment,
     vid::{VidCommitment, VidCommon},
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use thiserror::Error;
@@ -285,7 +286,7 @@ impl TestBlockHeader {
             timestamp = parent.timestamp;
         }
 
-        let random = thread_rng().gen_range(0..=u64::MAX);
+        let random = with_rng(|rng| rng.gen_range(0..=u64::MAX));
 
         Self {
             block_number: parent.block_number + 1,
@@ -11,19 +11,26 @@ pub use hotshot::traits::election::helpers::{
 };
 use hotshot::traits::{
     election::{
-        helpers::QuorumFilterConfig, randomized_committee::RandomizedCommittee,
+        delegated_stake_committee::DelegatedStakeCommittee, helpers::QuorumFilterConfig,
+        randomized_committee::RandomizedCommittee,
         randomized_committee_members::RandomizedCommitteeMembers,
         static_committee::StaticCommittee,
         static_committee_leader_two_views::StaticCommitteeLeaderForTwoViews,
         two_static_committees::TwoStaticCommittees,
     },
-    implementations::{CombinedNetworks, Libp2pNetwork, MemoryNetwork, PushCdnNetwork},
+    implementations::{
+        CombinedNetworks, Libp2pNetwork, MemoryNetwork, PushCdnNetwork, SplitNetwork,
+    },
     NodeImplementation,
 };
 use hotshot_types::{
     data::{EpochNumber, ViewNumber},
     signature_key::{BLSPubKey, BuilderKey},
-    traits::node_implementation::{NodeType, Versions},
+    traits::{
+        block_builder_strategy::PriorityOrdered,
+        node_implementation::{NodeType, Versions},
+        proposal_validator::AcceptAllProposals,
+    },
 };
 use serde::{Deserialize, Serialize};
 use vbs::version::StaticVersion;
@@ -199,6 +206,41 @@ impl NodeType for TestTwoStakeTablesTypes {
     type BuilderSignatureKey = BuilderKey;
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+/// filler struct to implement node type and allow us
+/// to select our traits
+///
+/// All thresholds of its membership are computed over stake, so that tests with skewed stake
+/// distributions exercise stake-weighted voting.
+pub struct TestStakeWeightedTypes;
+impl NodeType for TestStakeWeightedTypes {
+    const EPOCH_HEIGHT: u64 = 10;
+
+    type AuctionResult = TestAuctionResult;
+    type View = ViewNumber;
+    type Epoch = EpochNumber;
+    type BlockHeader = TestBlockHeader;
+    type BlockPayload = TestBlockPayload;
+    type SignatureKey = BLSPubKey;
+    type Transaction = TestTransaction;
+    type ValidatedState = TestValidatedState;
+    type InstanceState = TestInstanceState;
+    type Membership = DelegatedStakeCommittee<TestStakeWeightedTypes>;
+    type BuilderSignatureKey = BuilderKey;
+}
+
 /// The Push CDN implementation
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
 pub struct PushCdnImpl;
@@ -219,6 +261,10 @@ pub struct WebImpl;
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
 pub struct CombinedImpl;
 
+/// Memory network implementation, with DA payloads sent over a second memory network
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
+pub struct SplitImpl;
+
 /// static committee type alias
 pub type StaticMembership = StaticCommittee<TestTypes>;
 
@@ -226,24 +272,41 @@ impl<TYPES: NodeType> NodeImplementation<TYPES> for PushCdnImpl {
     type Network = PushCdnNetwork<TYPES::SignatureKey>;
     type Storage = TestStorage<TYPES>;
     type AuctionResultsProvider = TestAuctionResultsProvider<TYPES>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 impl<TYPES: NodeType> NodeImplementation<TYPES> for MemoryImpl {
     type Network = MemoryNetwork<TYPES::SignatureKey>;
     type Storage = TestStorage<TYPES>;
     type AuctionResultsProvider = TestAuctionResultsProvider<TYPES>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 impl<TYPES: NodeType> NodeImplementation<TYPES> for CombinedImpl {
     type Network = CombinedNetworks<TYPES>;
     type Storage = TestStorage<TYPES>;
     type AuctionResultsProvider = TestAuctionResultsProvider<TYPES>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
+}
+
+impl<TYPES: NodeType> NodeImplementation<TYPES> for SplitImpl {
+    type Network =
+        SplitNetwork<MemoryNetwork<TYPES::SignatureKey>, MemoryNetwork<TYPES::SignatureKey>>;
+    type Storage = TestStorage<TYPES>;
+    type AuctionResultsProvider = TestAuctionResultsProvider<TYPES>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 impl<TYPES: NodeType> NodeImplementation<TYPES> for Libp2pImpl {
     type Network = Libp2pNetwork<TYPES>;
     type Storage = TestStorage<TYPES>;
     type AuctionResultsProvider = TestAuctionResultsProvider<TYPES>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 #[derive(Clone, Debug, Copy)]
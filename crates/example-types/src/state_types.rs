@@ -131,6 +131,10 @@ impl<TYPES: NodeType> ValidatedState<TYPES> for TestValidatedState {
     fn genesis(_instance: &Self::Instance) -> (Self, Self::Delta) {
         (Self::default(), TestStateDelta {})
     }
+
+    fn state_digest(&self) -> [u8; 32] {
+        self.commit().into()
+    }
 }
 
 impl<TYPES: NodeType<BlockPayload = TestBlockPayload>> TestableState<TYPES> for TestValidatedState {
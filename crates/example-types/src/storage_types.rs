@@ -18,18 +18,26 @@ use hotshot_types::{
         DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2, VidDisperseShare,
         VidDisperseShare2,
     },
+    deterministic::with_rng,
     event::HotShotAction,
+    evidence::SignedEvidence,
     message::Proposal,
-    simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate},
+    simple_certificate::{
+        CheckpointCertificate, NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate,
+    },
     traits::{
         node_implementation::{ConsensusTime, NodeType},
         storage::Storage,
     },
     utils::View,
+    validator_metadata::SignedValidatorMetadata,
     vid::VidSchemeType,
+    view_change::ViewChangeRecord,
     vote::HasViewNumber,
+    vote_intent::VoteIntent,
 };
 use jf_vid::VidScheme;
+use rand::Rng;
 
 use crate::testable_delay::{DelayConfig, SupportedTraitTypesForAsyncDelay, TestableDelay};
 
@@ -54,6 +62,12 @@ pub struct TestStorageState<TYPES: NodeType> {
     high_qc2: Option<hotshot_types::simple_certificate::QuorumCertificate2<TYPES>>,
     next_epoch_high_qc2:
         Option<hotshot_types::simple_certificate::NextEpochQuorumCertificate2<TYPES>>,
+    evidence: Vec<SignedEvidence<TYPES>>,
+    view_changes: Vec<ViewChangeRecord>,
+    validator_metadata: BTreeMap<TYPES::SignatureKey, SignedValidatorMetadata<TYPES>>,
+    checkpoint_certificate: Option<CheckpointCertificate<TYPES>>,
+    locked_view: TYPES::View,
+    vote_intents: Vec<VoteIntent<TYPES>>,
     action: TYPES::View,
     epoch: TYPES::Epoch,
 }
@@ -70,6 +84,12 @@ impl<TYPES: NodeType> Default for TestStorageState<TYPES> {
             high_qc: None,
             next_epoch_high_qc2: None,
             high_qc2: None,
+            evidence: Vec::new(),
+            view_changes: Vec::new(),
+            validator_metadata: BTreeMap::new(),
+            checkpoint_certificate: None,
+            locked_view: TYPES::View::genesis(),
+            vote_intents: Vec::new(),
             action: TYPES::View::genesis(),
             epoch: TYPES::Epoch::genesis(),
         }
@@ -81,6 +101,8 @@ pub struct TestStorage<TYPES: NodeType> {
     inner: Arc<RwLock<TestStorageState<TYPES>>>,
     /// `should_return_err` is a testing utility to validate negative cases.
     pub should_return_err: bool,
+    /// The probability that each write fails, to test consensus on unreliable storage
+    pub write_failure_probability: f64,
     pub delay_config: DelayConfig,
     pub decided_upgrade_certificate: Arc<RwLock<Option<UpgradeCertificate<TYPES>>>>,
 }
@@ -90,6 +112,7 @@ impl<TYPES: NodeType> Default for TestStorage<TYPES> {
         Self {
             inner: Arc::new(RwLock::new(TestStorageState::default())),
             should_return_err: false,
+            write_failure_probability: 0.0,
             delay_config: DelayConfig::default(),
             decided_upgrade_certificate: Arc::new(RwLock::new(None)),
         }
@@ -107,6 +130,13 @@ impl<TYPES: NodeType> TestableDelay for TestStorage<TYPES> {
 }
 
 impl<TYPES: NodeType> TestStorage<TYPES> {
+    /// Whether the next write should fail
+    fn should_fail_write(&self) -> bool {
+        self.should_return_err
+            || (self.write_failure_probability > 0.0
+                && with_rng(|rng| rng.gen_bool(self.write_failure_probability.min(1.0))))
+    }
+
     pub async fn proposals_cloned(
         &self,
     ) -> BTreeMap<TYPES::View, Proposal<TYPES, QuorumProposal2<TYPES>>> {
@@ -121,6 +151,26 @@ impl<TYPES: NodeType> TestStorage<TYPES> {
     pub async fn decided_upgrade_certificate(&self) -> Option<UpgradeCertificate<TYPES>> {
         self.decided_upgrade_certificate.read().await.clone()
     }
+    pub async fn evidence_cloned(&self) -> Vec<SignedEvidence<TYPES>> {
+        self.inner.read().await.evidence.clone()
+    }
+    pub async fn view_changes_cloned(&self) -> Vec<ViewChangeRecord> {
+        self.inner.read().await.view_changes.clone()
+    }
+    pub async fn validator_metadata_cloned(
+        &self,
+    ) -> BTreeMap<TYPES::SignatureKey, SignedValidatorMetadata<TYPES>> {
+        self.inner.read().await.validator_metadata.clone()
+    }
+    pub async fn checkpoint_certificate_cloned(&self) -> Option<CheckpointCertificate<TYPES>> {
+        self.inner.read().await.checkpoint_certificate.clone()
+    }
+    pub async fn locked_view(&self) -> TYPES::View {
+        self.inner.read().await.locked_view
+    }
+    pub async fn vote_intents_cloned(&self) -> Vec<VoteIntent<TYPES>> {
+        self.inner.read().await.vote_intents.clone()
+    }
     pub async fn last_actioned_view(&self) -> TYPES::View {
         self.inner.read().await.action
     }
@@ -132,7 +182,7 @@ impl<TYPES: NodeType> TestStorage<TYPES> {
 #[async_trait]
 impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
     async fn append_vid(&self, proposal: &Proposal<TYPES, VidDisperseShare<TYPES>>) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -149,7 +199,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         &self,
         proposal: &Proposal<TYPES, VidDisperseShare2<TYPES>>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -167,7 +217,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         proposal: &Proposal<TYPES, DaProposal<TYPES>>,
         _vid_commit: <VidSchemeType as VidScheme>::Commit,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -182,7 +232,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         proposal: &Proposal<TYPES, DaProposal2<TYPES>>,
         _vid_commit: <VidSchemeType as VidScheme>::Commit,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -196,7 +246,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         &self,
         proposal: &Proposal<TYPES, QuorumProposal<TYPES>>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -210,7 +260,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         &self,
         proposal: &Proposal<TYPES, QuorumProposal2<TYPES>>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append VID proposal to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -221,12 +271,57 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         Ok(())
     }
 
+    async fn append_evidence(&self, evidence: &SignedEvidence<TYPES>) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to append evidence to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        inner.evidence.push(evidence.clone());
+        Ok(())
+    }
+
+    async fn append_view_change(&self, record: &ViewChangeRecord) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to append view change to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        inner.view_changes.push(record.clone());
+        Ok(())
+    }
+
+    async fn update_validator_metadata(
+        &self,
+        record: &SignedValidatorMetadata<TYPES>,
+    ) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to update validator metadata in storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        inner
+            .validator_metadata
+            .insert(record.public_key.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn record_vote_intent(&self, intent: &VoteIntent<TYPES>) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to record vote intent to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        inner.vote_intents.push(intent.clone());
+        Ok(())
+    }
+
     async fn record_action(
         &self,
         view: <TYPES as NodeType>::View,
         action: hotshot_types::event::HotShotAction,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to append Action to storage");
         }
         let mut inner = self.inner.write().await;
@@ -241,7 +336,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         &self,
         new_high_qc: hotshot_types::simple_certificate::QuorumCertificate<TYPES>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to update high qc to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -260,7 +355,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         &self,
         new_high_qc: hotshot_types::simple_certificate::QuorumCertificate2<TYPES>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to update high qc to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -280,7 +375,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
             TYPES,
         >,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to update next epoch high qc to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -294,12 +389,23 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         }
         Ok(())
     }
+    async fn update_locked_view(&self, locked_view: TYPES::View) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to update locked view to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        if locked_view > inner.locked_view {
+            inner.locked_view = locked_view;
+        }
+        Ok(())
+    }
     async fn update_undecided_state(
         &self,
         _leaves: CommitmentMap<Leaf<TYPES>>,
         _state: BTreeMap<TYPES::View, View<TYPES>>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to update high qc to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -310,7 +416,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         _leaves: CommitmentMap<Leaf2<TYPES>>,
         _state: BTreeMap<TYPES::View, View<TYPES>>,
     ) -> Result<()> {
-        if self.should_return_err {
+        if self.should_fail_write() {
             bail!("Failed to update high qc to storage");
         }
         Self::run_delay_settings_from_config(&self.delay_config).await;
@@ -325,6 +431,25 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         Ok(())
     }
 
+    async fn update_checkpoint_certificate(
+        &self,
+        checkpoint_certificate: &CheckpointCertificate<TYPES>,
+    ) -> Result<()> {
+        if self.should_fail_write() {
+            bail!("Failed to update checkpoint certificate to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        let mut inner = self.inner.write().await;
+        if let Some(ref current_checkpoint_certificate) = inner.checkpoint_certificate {
+            if checkpoint_certificate.data.height > current_checkpoint_certificate.data.height {
+                inner.checkpoint_certificate = Some(checkpoint_certificate.clone());
+            }
+        } else {
+            inner.checkpoint_certificate = Some(checkpoint_certificate.clone());
+        }
+        Ok(())
+    }
+
     async fn migrate_consensus(
         &self,
         _convert_leaf: fn(Leaf<TYPES>) -> Leaf2<TYPES>,
@@ -342,4 +467,11 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
 
         Ok(())
     }
+
+    async fn health_check(&self) -> Result<()> {
+        if self.should_return_err {
+            bail!("Storage is failing all writes");
+        }
+        Ok(())
+    }
 }
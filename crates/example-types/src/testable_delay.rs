@@ -1,6 +1,7 @@
 use std::{collections::HashMap, time::Duration};
 
 use async_trait::async_trait;
+use hotshot_types::deterministic::with_rng;
 use rand::Rng;
 use tokio::time::sleep;
 
@@ -90,9 +91,11 @@ pub trait TestableDelay {
                 sleep(Duration::from_millis(settings.fixed_time_in_milliseconds)).await;
             }
             DelayOptions::Random => {
-                let sleep_in_millis = rand::thread_rng().gen_range(
-                    settings.min_time_in_milliseconds..=settings.max_time_in_milliseconds,
-                );
+                let sleep_in_millis = with_rng(|rng| {
+                    rng.gen_range(
+                        settings.min_time_in_milliseconds..=settings.max_time_in_milliseconds,
+                    )
+                });
                 sleep(Duration::from_millis(sleep_in_millis)).await;
             }
         }
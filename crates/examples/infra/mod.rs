@@ -8,11 +8,10 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    fs,
     net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
     num::NonZeroUsize,
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use async_lock::RwLock;
@@ -25,13 +24,13 @@ use hotshot::{
     traits::{
         implementations::{
             derive_libp2p_multiaddr, derive_libp2p_peer_id, CdnMetricsValue, CdnTopic,
-            CombinedNetworks, Libp2pMetricsValue, Libp2pNetwork, PushCdnNetwork,
+            CombinedNetworks, Libp2pMetricsValue, Libp2pNetwork, PrometheusMetrics, PushCdnNetwork,
             WrappedSignatureKey,
         },
         BlockPayload, NodeImplementation,
     },
-    types::SystemContextHandle,
-    MarketplaceConfig, SystemContext,
+    types::{HealthThresholds, SystemContextHandle},
+    HotShotBuilder,
 };
 use hotshot_example_types::{
     auction_results_provider_types::TestAuctionResultsProvider,
@@ -56,6 +55,7 @@ use hotshot_types::{
     traits::{
         block_contents::{BlockHeader, TestableBlock},
         election::Membership,
+        metrics::{Metrics, NoMetrics},
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, NodeType, Versions},
         states::TestableState,
@@ -253,7 +253,7 @@ pub fn read_orchestrator_init_config<TYPES: NodeType>() -> (NetworkConfig<TYPES:
 
 /// Reads a network configuration from a given filepath
 /// # Panics
-/// if unable to convert the config file into toml
+/// if unable to read, parse or validate the config file
 /// # Note
 /// This derived config is used for initialization of orchestrator,
 /// therefore `known_nodes_with_stake` will be an initialized
@@ -262,11 +262,9 @@ pub fn read_orchestrator_init_config<TYPES: NodeType>() -> (NetworkConfig<TYPES:
 pub fn load_config_from_file<TYPES: NodeType>(
     config_file: &str,
 ) -> NetworkConfig<TYPES::SignatureKey> {
-    let config_file_as_string: String = fs::read_to_string(config_file)
-        .unwrap_or_else(|_| panic!("Could not read config file located at {config_file}"));
     let config_toml: NetworkConfigFile<TYPES::SignatureKey> =
-        toml::from_str::<NetworkConfigFile<TYPES::SignatureKey>>(&config_file_as_string)
-            .expect("Unable to convert config file to TOML");
+        NetworkConfigFile::from_file(config_file)
+            .unwrap_or_else(|e| panic!("Could not load config file located at {config_file}: {e}"));
 
     let mut config: NetworkConfig<TYPES::SignatureKey> = config_toml.into();
 
@@ -352,6 +350,7 @@ pub trait RunDa<
         validator_config: ValidatorConfig<TYPES::SignatureKey>,
         libp2p_advertise_address: Option<String>,
         membership: &Arc<RwLock<<TYPES as NodeType>::Membership>>,
+        metrics: &dyn Metrics,
     ) -> Self;
 
     /// Initializes the genesis state and HotShot instance; does not start HotShot consensus
@@ -361,12 +360,8 @@ pub trait RunDa<
     async fn initialize_state_and_hotshot(
         &self,
         membership: Arc<RwLock<<TYPES as NodeType>::Membership>>,
+        metrics: &dyn Metrics,
     ) -> SystemContextHandle<TYPES, NODE, V> {
-        let initializer =
-            hotshot::HotShotInitializer::<TYPES>::from_genesis::<V>(TestInstanceState::default())
-                .await
-                .expect("Couldn't generate genesis block");
-
         let config = self.config();
         let validator_config = self.validator_config();
 
@@ -376,27 +371,21 @@ pub trait RunDa<
 
         let network = self.network();
 
-        let marketplace_config = MarketplaceConfig {
-            auction_results_provider: TestAuctionResultsProvider::<TYPES>::default().into(),
-            // TODO: we need to pass a valid fallback builder url here somehow
-            fallback_builder_url: config.config.builder_urls.first().clone(),
-        };
-
-        SystemContext::init(
+        HotShotBuilder::new(
             pk,
             sk,
-            config.node_index,
             config.config,
             membership,
             Arc::from(network),
-            initializer,
-            ConsensusMetricsValue::default(),
             TestStorage::<TYPES>::default(),
-            marketplace_config,
+            TestAuctionResultsProvider::<TYPES>::default().into(),
+            TestInstanceState::default(),
         )
+        .node_id(config.node_index)
+        .metrics(ConsensusMetricsValue::new(metrics))
+        .build()
         .await
         .expect("Could not init hotshot")
-        .0
     }
 
     /// Starts HotShot consensus, returns when consensus has finished
@@ -483,7 +472,7 @@ pub trait RunDa<
                                     let mut timestamp_vec = timestamp.to_be_bytes().to_vec();
                                     tx.append(&mut timestamp_vec);
 
-                                    () = context
+                                    context
                                         .submit_transaction(TestTransaction::new(tx))
                                         .await
                                         .unwrap();
@@ -616,6 +605,7 @@ where
         validator_config: ValidatorConfig<TYPES::SignatureKey>,
         _libp2p_advertise_address: Option<String>,
         _membership: &Arc<RwLock<<TYPES as NodeType>::Membership>>,
+        metrics: &dyn Metrics,
     ) -> PushCdnDaRun<TYPES> {
         // Convert to the Push-CDN-compatible type
         let keypair = KeyPair {
@@ -637,7 +627,7 @@ where
                 .expect("`cdn_marshal_address` needs to be supplied for a push CDN run"),
             topics,
             keypair,
-            CdnMetricsValue::default(),
+            CdnMetricsValue::new(metrics),
         )
         .expect("failed to create network");
 
@@ -703,6 +693,7 @@ where
         validator_config: ValidatorConfig<TYPES::SignatureKey>,
         libp2p_advertise_address: Option<String>,
         membership: &Arc<RwLock<<TYPES as NodeType>::Membership>>,
+        metrics: &dyn Metrics,
     ) -> Libp2pDaRun<TYPES> {
         // Extrapolate keys for ease of use
         let public_key = &validator_config.public_key;
@@ -744,7 +735,7 @@ where
             bind_address,
             public_key,
             private_key,
-            Libp2pMetricsValue::default(),
+            Libp2pMetricsValue::new(metrics),
         )
         .await
         .expect("failed to create libp2p network");
@@ -811,6 +802,7 @@ where
         validator_config: ValidatorConfig<TYPES::SignatureKey>,
         libp2p_advertise_address: Option<String>,
         membership: &Arc<RwLock<<TYPES as NodeType>::Membership>>,
+        metrics: &dyn Metrics,
     ) -> CombinedDaRun<TYPES> {
         // Initialize our Libp2p network
         let libp2p_network: Libp2pDaRun<TYPES> = <Libp2pDaRun<TYPES> as RunDa<
@@ -823,6 +815,7 @@ where
             validator_config.clone(),
             libp2p_advertise_address.clone(),
             membership,
+            metrics,
         )
         .await;
 
@@ -837,6 +830,7 @@ where
             validator_config.clone(),
             libp2p_advertise_address,
             membership,
+            metrics,
         )
         .await;
 
@@ -980,15 +974,38 @@ pub async fn main_entry_point<
         run_config.config.known_da_nodes.clone(),
     )));
 
+    // Export metrics to Prometheus if asked to
+    let metrics: Box<dyn Metrics> = match args.metrics_address {
+        Some(metrics_address) => {
+            let metrics = PrometheusMetrics::default();
+            metrics.serve(metrics_address);
+            Box::new(metrics)
+        }
+        None => NoMetrics::boxed(),
+    };
+
     info!("Initializing networking");
     let run = RUNDA::initialize_networking(
         run_config.clone(),
         validator_config,
         args.advertise_address,
         &membership,
+        &*metrics,
     )
     .await;
-    let hotshot = run.initialize_state_and_hotshot(membership).await;
+    let hotshot = run
+        .initialize_state_and_hotshot(membership, &*metrics)
+        .await;
+
+    // Serve health probes if asked to
+    if let Some(health_address) = args.health_address {
+        hotshot.serve_health(health_address, HealthThresholds::default());
+    }
+
+    // Serve the admin interface if asked to
+    if let (Some(admin_address), Some(admin_token)) = (args.admin_address, args.admin_token) {
+        hotshot.serve_admin(admin_address, admin_token);
+    }
 
     if let Some(task) = builder_task {
         task.start(Box::new(hotshot.event_stream()));
@@ -1019,11 +1036,23 @@ pub async fn main_entry_point<
         transaction_size,
     );
 
+    let mut view_reporter = None;
     if let NetworkConfigSource::Orchestrator = source {
         info!("Waiting for the start command from orchestrator");
         orchestrator_client
             .wait_for_all_nodes_ready(peer_config)
             .await;
+
+        // report our view, so the orchestrator can tell how far the run got
+        let consensus = hotshot.hotshot.consensus();
+        let client = OrchestratorClient::new(args.url.clone());
+        view_reporter = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                let view = consensus.read().await.cur_view().u64();
+                client.post_view(node_index, view).await;
+            }
+        }));
     }
 
     info!("Starting HotShot");
@@ -1035,6 +1064,9 @@ pub async fn main_entry_point<
             (transaction_size + 8) as u64, // extra 8 bytes for transaction base, see `create_random_transaction`.
         )
         .await;
+    if let Some(view_reporter) = view_reporter {
+        view_reporter.abort();
+    }
     orchestrator_client.post_bench_results(bench_results).await;
 }
 
@@ -54,6 +54,10 @@ async fn main() {
                     advertise_address: Some(advertise_address.to_string()),
                     builder_address: Some(builder_address),
                     network_config_file: None,
+                    metrics_address: None,
+                    health_address: None,
+                    admin_address: None,
+                    admin_token: None,
                 },
             )
             .await;
@@ -0,0 +1,164 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! An example program running two independent consensus instances in one process, over one
+//! shared in memory network.
+//!
+//! The nodes of both instances are attached to the same [`MasterMap`], so every broadcast reaches
+//! the nodes of both instances. Each instance has its own `namespace` in its config, and nodes
+//! ignore the messages of other namespaces. Messages carry their namespace from the epochs
+//! version on, which the nodes run from the start.
+
+use std::{collections::HashMap, num::NonZeroUsize, sync::Arc};
+
+use async_lock::RwLock;
+use futures::{future::join_all, StreamExt};
+use hotshot::{
+    helpers::initialize_logging,
+    traits::implementations::{MasterMap, MemoryNetwork},
+    types::{EventType, SystemContextHandle},
+    HotShotBuilder,
+};
+use hotshot_example_types::{
+    auction_results_provider_types::TestAuctionResultsProvider,
+    node_types::{EpochsTestVersions, MemoryImpl, TestTypes},
+    state_types::TestInstanceState,
+    storage_types::TestStorage,
+};
+use hotshot_testing::block_builder::{SimpleBuilderImplementation, TestBuilderImplementation};
+use hotshot_types::{
+    hotshot_config_file::HotShotConfigFile,
+    traits::{election::Membership, network::Topic, node_implementation::NodeType},
+    HotShotConfig, ValidatorConfig,
+};
+use tracing::info;
+use url::Url;
+
+/// The number of consensus instances to run
+const NUM_INSTANCES: u64 = 2;
+
+/// The number of nodes in each instance
+const NUM_NODES: usize = 4;
+
+/// The number of decides each node waits for before shutting down
+const NUM_DECIDES: usize = 5;
+
+/// The handle of a node
+type Handle = SystemContextHandle<TestTypes, MemoryImpl, EpochsTestVersions>;
+
+/// Start the nodes of the instance `namespace`, attached to `master_map`
+async fn start_instance(
+    namespace: u64,
+    master_map: &Arc<MasterMap<<TestTypes as NodeType>::SignatureKey>>,
+) -> Vec<Handle> {
+    // Derive the keys from the namespace, so that the nodes of different instances are distinct
+    // peers of the network
+    let mut seed = [0u8; 32];
+    seed[..8].copy_from_slice(&namespace.to_le_bytes());
+    let validators = (0..NUM_NODES as u64)
+        .map(|index| ValidatorConfig::generated_from_seed_indexed(seed, index, 1, true))
+        .collect::<Vec<_>>();
+    let known_nodes = validators
+        .iter()
+        .map(ValidatorConfig::public_config)
+        .collect::<Vec<_>>();
+
+    let builder_port = portpicker::pick_unused_port().expect("No free ports");
+    let builder_url = Url::parse(&format!("http://localhost:{builder_port}")).expect("Invalid URL");
+    let builder_task =
+        <SimpleBuilderImplementation as TestBuilderImplementation<TestTypes>>::start(
+            NUM_NODES,
+            builder_url.clone(),
+            (),
+            HashMap::new(),
+        )
+        .await;
+
+    let mut config: HotShotConfig<_> = HotShotConfigFile::hotshot_config_5_nodes_10_da().into();
+    config.num_nodes_with_stake = NonZeroUsize::new(NUM_NODES).unwrap();
+    config.known_nodes_with_stake.clone_from(&known_nodes);
+    config.known_da_nodes.clone_from(&known_nodes);
+    config.da_staked_committee_size = NUM_NODES;
+    config.num_bootstrap = 0;
+    config.builder_urls = vec1::vec1![builder_url];
+    config.namespace = namespace;
+
+    let mut handles = vec![];
+    for (node_id, validator) in validators.into_iter().enumerate() {
+        let network = MemoryNetwork::new(
+            &validator.public_key,
+            master_map,
+            &[Topic::Global, Topic::Da],
+            None,
+        );
+        let memberships = Arc::new(RwLock::new(<TestTypes as NodeType>::Membership::new(
+            known_nodes.clone(),
+            known_nodes.clone(),
+        )));
+
+        let handle = HotShotBuilder::<TestTypes, MemoryImpl, EpochsTestVersions>::new(
+            validator.public_key,
+            validator.private_key,
+            config.clone(),
+            memberships,
+            Arc::new(network),
+            TestStorage::default(),
+            Arc::new(TestAuctionResultsProvider::default()),
+            TestInstanceState::default(),
+        )
+        .node_id(node_id as u64)
+        .build()
+        .await
+        .expect("Failed to build the node");
+        handles.push(handle);
+    }
+
+    builder_task.start(Box::new(handles[0].event_stream()));
+
+    handles
+}
+
+/// Wait until the node of `handle` decided [`NUM_DECIDES`] times, then shut it down
+async fn run_node(mut handle: Handle, namespace: u64, node_id: usize) {
+    let mut events = handle.event_stream();
+    let mut decides = 0;
+    while decides < NUM_DECIDES {
+        let Some(event) = events.next().await else {
+            break;
+        };
+        if let EventType::Decide { .. } = event.event {
+            decides += 1;
+            info!(
+                "Node {node_id} of instance {namespace} decided view {:?}",
+                event.view_number
+            );
+        }
+    }
+    drop(events);
+    handle.shut_down().await;
+}
+
+#[tokio::main]
+async fn main() {
+    initialize_logging();
+
+    let master_map = MasterMap::new();
+    let mut instances = vec![];
+    for namespace in 0..NUM_INSTANCES {
+        instances.push((namespace, start_instance(namespace, &master_map).await));
+    }
+
+    let mut nodes = vec![];
+    for (namespace, handles) in instances {
+        for handle in &handles {
+            handle.hotshot.start_consensus().await;
+        }
+        for (node_id, handle) in handles.into_iter().enumerate() {
+            nodes.push(run_node(handle, namespace, node_id));
+        }
+    }
+    join_all(nodes).await;
+}
@@ -155,6 +155,10 @@ async fn main() {
                     advertise_address: None,
                     builder_address: Some(builder_address),
                     network_config_file: None,
+                    metrics_address: None,
+                    health_address: None,
+                    admin_address: None,
+                    admin_token: None,
                 },
             )
             .await;
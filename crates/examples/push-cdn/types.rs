@@ -9,7 +9,10 @@ use hotshot_example_types::{
     auction_results_provider_types::TestAuctionResultsProvider, state_types::TestTypes,
     storage_types::TestStorage,
 };
-use hotshot_types::traits::node_implementation::NodeType;
+use hotshot_types::traits::{
+    block_builder_strategy::PriorityOrdered, node_implementation::NodeType,
+    proposal_validator::AcceptAllProposals,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::infra::PushCdnDaRun;
@@ -25,6 +28,8 @@ impl NodeImplementation<TestTypes> for NodeImpl {
     type Network = Network;
     type Storage = TestStorage<TestTypes>;
     type AuctionResultsProvider = TestAuctionResultsProvider<TestTypes>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 /// Convenience type alias
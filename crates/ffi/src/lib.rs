@@ -0,0 +1,260 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! C bindings for embedding a HotShot node in a host application which cannot link Rust, e.g.
+//! one written in Go or C++.
+//!
+//! The embedded node runs over libp2p, with the types of `hotshot-example-types`. Its network is
+//! described by a JSON network config, as written by the orchestrator. The build generates the
+//! header `include/hotshot.h` declaring the functions below.
+//!
+//! ```c
+//! void on_event(void *user_data, const char *event_json) { /* ... */ }
+//!
+//! HotShotNode *node = hotshot_node_new("config.json", 0, "0.0.0.0:8000");
+//! if (node == NULL) {
+//!     fprintf(stderr, "%s\n", hotshot_last_error());
+//! }
+//! hotshot_node_subscribe(node, on_event, NULL);
+//! hotshot_node_start(node);
+//! hotshot_node_submit_transaction(node, data, len, NULL);
+//! hotshot_node_free(node);
+//! ```
+//!
+//! Functions which fail return `NULL` or a status other than [`HotShotStatus::Ok`], and leave a
+//! description of the failure for [`hotshot_last_error`].
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr, CString},
+    ptr,
+};
+
+use crate::node::Node;
+
+mod node;
+
+/// A node embedded in the host application
+///
+/// Created by [`hotshot_node_new`], and destroyed by [`hotshot_node_free`].
+pub struct HotShotNode(Node);
+
+/// The outcome of a call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotShotStatus {
+    /// the call succeeded
+    Ok = 0,
+    /// an argument was `NULL` or malformed
+    InvalidArgument = 1,
+    /// the node failed to carry out the call
+    Error = 2,
+}
+
+/// Called with the user data passed to [`hotshot_node_subscribe`] and an event of the node,
+/// encoded as a NUL terminated JSON string which is only valid during the call
+pub type HotShotEventCallback = extern "C" fn(user_data: *mut c_void, event_json: *const c_char);
+
+thread_local! {
+    /// The description of the last failure on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record `error` as the last failure on this thread
+fn set_last_error(error: impl Into<Vec<u8>>) {
+    let mut error = error.into();
+    error.retain(|&byte| byte != 0);
+    let error = CString::new(error).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+}
+
+/// The string `s` points to, or a recorded failure if it is `NULL` or not UTF-8
+///
+/// # Safety
+/// `s` must be `NULL` or point to a NUL terminated string
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error(format!("{name} is NULL"));
+        return None;
+    }
+    let s = CStr::from_ptr(s).to_str();
+    if s.is_err() {
+        set_last_error(format!("{name} is not UTF-8"));
+    }
+    s.ok()
+}
+
+/// The description of the last failure of a call on this thread, or `NULL` if there was none
+///
+/// The string is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn hotshot_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Construct the node with index `node_index` of the network described by the JSON network
+/// config at `config_path`, listening for peers on `bind_address`, e.g. `"0.0.0.0:8000"`
+///
+/// The keys of the node are derived from the seed of the config and `node_index`. The node does
+/// not take part in consensus before [`hotshot_node_start`]. Returns `NULL` on failure.
+///
+/// # Safety
+/// `config_path` and `bind_address` must be `NULL` or point to NUL terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn hotshot_node_new(
+    config_path: *const c_char,
+    node_index: u64,
+    bind_address: *const c_char,
+) -> *mut HotShotNode {
+    let (Some(config_path), Some(bind_address)) = (
+        str_arg(config_path, "config_path"),
+        str_arg(bind_address, "bind_address"),
+    ) else {
+        return ptr::null_mut();
+    };
+
+    match Node::new(config_path, node_index, bind_address) {
+        Ok(node) => Box::into_raw(Box::new(HotShotNode(node))),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Wait until `node` is connected to its peers, then start consensus
+///
+/// # Safety
+/// `node` must be `NULL` or returned by [`hotshot_node_new`] and not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn hotshot_node_start(node: *const HotShotNode) -> HotShotStatus {
+    let Some(node) = node.as_ref() else {
+        set_last_error("node is NULL");
+        return HotShotStatus::InvalidArgument;
+    };
+    node.0.start();
+    HotShotStatus::Ok
+}
+
+/// Submit the transaction of `len` bytes at `data` to `node`
+///
+/// Unless `commitment_out` is `NULL`, the 32 byte commitment of the transaction is written to
+/// it, which identifies the transaction in the events of the node.
+///
+/// # Safety
+/// `node` must be `NULL` or returned by [`hotshot_node_new`] and not yet freed, `data` must be
+/// `NULL` or point to `len` bytes, and `commitment_out` must be `NULL` or point to 32 writable
+/// bytes
+#[no_mangle]
+pub unsafe extern "C" fn hotshot_node_submit_transaction(
+    node: *const HotShotNode,
+    data: *const u8,
+    len: usize,
+    commitment_out: *mut u8,
+) -> HotShotStatus {
+    let Some(node) = node.as_ref() else {
+        set_last_error("node is NULL");
+        return HotShotStatus::InvalidArgument;
+    };
+    if data.is_null() {
+        set_last_error("data is NULL");
+        return HotShotStatus::InvalidArgument;
+    }
+    let bytes = std::slice::from_raw_parts(data, len).to_vec();
+
+    match node.0.submit_transaction(bytes) {
+        Ok(commitment) => {
+            if !commitment_out.is_null() {
+                ptr::copy_nonoverlapping(commitment.as_ptr(), commitment_out, commitment.len());
+            }
+            HotShotStatus::Ok
+        }
+        Err(e) => {
+            set_last_error(e);
+            HotShotStatus::Error
+        }
+    }
+}
+
+/// The user data of a subscription, which the host promises may be used from any thread
+struct UserData(*mut c_void);
+
+// SAFETY: the caller of `hotshot_node_subscribe` guarantees that the user data may be used from
+// any thread
+unsafe impl Send for UserData {}
+
+impl UserData {
+    /// The pointer to the user data
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Call `callback` with `user_data` and every event `node` emits from now on, until the node is
+/// freed
+///
+/// The callback is called from threads of the node, one event at a time, and should return
+/// quickly, since events are buffered while it runs.
+///
+/// # Safety
+/// `node` must be `NULL` or returned by [`hotshot_node_new`] and not yet freed, and `callback`
+/// must be safe to call with `user_data` from any thread until the node is freed
+#[no_mangle]
+pub unsafe extern "C" fn hotshot_node_subscribe(
+    node: *mut HotShotNode,
+    callback: Option<HotShotEventCallback>,
+    user_data: *mut c_void,
+) -> HotShotStatus {
+    let (Some(node), Some(callback)) = (node.as_mut(), callback) else {
+        set_last_error("node or callback is NULL");
+        return HotShotStatus::InvalidArgument;
+    };
+
+    let user_data = UserData(user_data);
+    node.0.subscribe(move |json| {
+        // Events encoded as JSON contain no NUL bytes
+        if let Ok(json) = CString::new(json) {
+            callback(user_data.get(), json.as_ptr());
+        }
+    });
+    HotShotStatus::Ok
+}
+
+/// Shut `node` down and free it
+///
+/// No callback of the node is called once this returns.
+///
+/// # Safety
+/// `node` must be `NULL` or returned by [`hotshot_node_new`] and not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn hotshot_node_free(node: *mut HotShotNode) {
+    if !node.is_null() {
+        Box::from_raw(node).0.shut_down();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn failures_are_reported() {
+        let config_path = CString::new("/nonexistent/config.json").unwrap();
+        let bind_address = CString::new("0.0.0.0:8000").unwrap();
+        let node = unsafe { hotshot_node_new(config_path.as_ptr(), 0, bind_address.as_ptr()) };
+        assert!(node.is_null());
+        let error = unsafe { CStr::from_ptr(hotshot_last_error()) };
+        assert!(error
+            .to_str()
+            .unwrap()
+            .starts_with("Failed to read the network config"));
+
+        let status = unsafe {
+            hotshot_node_submit_transaction(ptr::null(), ptr::null(), 0, ptr::null_mut())
+        };
+        assert_eq!(status, HotShotStatus::InvalidArgument);
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! The node behind a [`HotShotNode`](crate::HotShotNode), free of raw pointers.
+
+use std::sync::Arc;
+
+use async_lock::RwLock;
+use futures::{Stream, StreamExt};
+use hotshot::{
+    traits::implementations::{
+        derive_libp2p_multiaddr, GossipConfig, Libp2pMetricsValue, Libp2pNetwork,
+        RequestResponseConfig,
+    },
+    types::{BLSPubKey, SignatureKey, SystemContextHandle},
+    HotShotBuilder,
+};
+use hotshot_example_types::{
+    auction_results_provider_types::TestAuctionResultsProvider,
+    block_types::TestTransaction,
+    node_types::{Libp2pImpl, TestTypes, TestVersions},
+    state_types::TestInstanceState,
+    storage_types::TestStorage,
+};
+use hotshot_types::{
+    event::Event,
+    network::NetworkConfig,
+    traits::{election::Membership, node_implementation::NodeType},
+};
+use tokio::{runtime::Runtime, task::JoinHandle};
+
+/// The handle of an embedded node
+type Handle = SystemContextHandle<TestTypes, Libp2pImpl, TestVersions>;
+
+/// A node over libp2p, together with the runtime driving it
+pub(crate) struct Node {
+    /// the runtime all tasks of the node run on
+    runtime: Runtime,
+    /// the handle of the node
+    handle: Handle,
+    /// the network of the node
+    network: Arc<Libp2pNetwork<TestTypes>>,
+    /// the tasks forwarding events to subscribers
+    subscribers: Vec<JoinHandle<()>>,
+}
+
+impl Node {
+    /// Construct the node with index `node_index` of the network described by the JSON config
+    /// at `config_path`, listening on `bind_address`
+    ///
+    /// The keys of the node are derived from the seed of the config and `node_index`, like the
+    /// orchestrator does.
+    pub(crate) fn new(
+        config_path: &str,
+        node_index: u64,
+        bind_address: &str,
+    ) -> Result<Self, String> {
+        let runtime = Runtime::new().map_err(|e| format!("Failed to start the runtime: {e}"))?;
+
+        let mut config = NetworkConfig::<BLSPubKey>::from_file(config_path.to_string())
+            .map_err(|e| format!("Failed to read the network config: {e}"))?;
+        config.node_index = node_index;
+        let (public_key, private_key) =
+            BLSPubKey::generated_from_seed_indexed(config.seed, node_index);
+        let bind_address = derive_libp2p_multiaddr(&bind_address.to_string())
+            .map_err(|e| format!("Invalid bind address: {e}"))?;
+
+        let (handle, network) = runtime.block_on(async {
+            let membership = Arc::new(RwLock::new(<TestTypes as NodeType>::Membership::new(
+                config.config.known_nodes_with_stake.clone(),
+                config.config.known_da_nodes.clone(),
+            )));
+            let network = Arc::new(
+                Libp2pNetwork::from_config(
+                    config.clone(),
+                    Arc::clone(&membership),
+                    GossipConfig::default(),
+                    RequestResponseConfig::default(),
+                    bind_address,
+                    &public_key,
+                    &private_key,
+                    Libp2pMetricsValue::default(),
+                )
+                .await
+                .map_err(|e| format!("Failed to create the network: {e}"))?,
+            );
+
+            let handle = HotShotBuilder::<TestTypes, Libp2pImpl, TestVersions>::new(
+                public_key,
+                private_key,
+                config.config,
+                membership,
+                Arc::clone(&network),
+                TestStorage::default(),
+                Arc::new(TestAuctionResultsProvider::default()),
+                TestInstanceState::default(),
+            )
+            .node_id(node_index)
+            .build()
+            .await
+            .map_err(|e| format!("Failed to build the node: {e}"))?;
+
+            Ok::<_, String>((handle, network))
+        })?;
+
+        Ok(Self {
+            runtime,
+            handle,
+            network,
+            subscribers: vec![],
+        })
+    }
+
+    /// Wait until the node is connected to its peers, then start consensus
+    pub(crate) fn start(&self) {
+        self.runtime.block_on(async {
+            self.network.wait_for_ready().await;
+            self.handle.hotshot.start_consensus().await;
+        });
+    }
+
+    /// Submit a transaction with the contents `bytes`, returning its commitment
+    pub(crate) fn submit_transaction(&self, bytes: Vec<u8>) -> Result<[u8; 32], String> {
+        let transaction =
+            TestTransaction::try_new(bytes).ok_or_else(|| "Transaction too long".to_string())?;
+        let receipt = self
+            .runtime
+            .block_on(self.handle.submit_transaction(transaction))
+            .map_err(|e| e.to_string())?;
+
+        let mut commitment = [0u8; 32];
+        commitment.copy_from_slice(receipt.commitment().as_ref());
+        Ok(commitment)
+    }
+
+    /// Call `on_event` with the JSON encoding of every event the node emits from now on
+    ///
+    /// `on_event` is called from the threads of the runtime, one event at a time.
+    pub(crate) fn subscribe(&mut self, mut on_event: impl FnMut(&str) + Send + 'static) {
+        let events = self.handle.event_stream();
+        let task = self.runtime.spawn(async move {
+            forward_events(events, &mut on_event).await;
+        });
+        self.subscribers.push(task);
+    }
+
+    /// Stop the subscriptions, shut the node down and wait for its tasks to finish
+    pub(crate) fn shut_down(mut self) {
+        for subscriber in self.subscribers.drain(..) {
+            subscriber.abort();
+        }
+        self.runtime.block_on(self.handle.shut_down());
+    }
+}
+
+/// Call `on_event` with the JSON encoding of each of `events`
+async fn forward_events(
+    events: impl Stream<Item = Event<TestTypes>>,
+    on_event: &mut impl FnMut(&str),
+) {
+    let mut events = std::pin::pin!(events);
+    while let Some(event) = events.next().await {
+        match serde_json::to_string(&event) {
+            Ok(json) => on_event(&json),
+            Err(e) => tracing::warn!("Failed to encode event as JSON: {e}"),
+        }
+    }
+}
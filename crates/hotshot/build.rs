@@ -0,0 +1,13 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Generates the gRPC service with the `grpc` feature, so that `protoc` is not needed without it.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/hotshot.proto")
+        .expect("Failed to compile the gRPC service definition");
+}
@@ -0,0 +1,214 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A builder for [`SystemContext`], for constructing a node without threading every part of it
+//! through [`SystemContext::init`].
+
+use std::{sync::Arc, time::Duration};
+
+use async_broadcast::broadcast;
+use async_lock::RwLock;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    constants::{EVENT_CHANNEL_SIZE, EXTERNAL_EVENT_CHANNEL_SIZE},
+    data::{Leaf2, QuorumProposal, QuorumProposal2},
+    message::convert_proposal,
+    traits::{node_implementation::NodeType, signature_key::SignatureKey, storage::Storage},
+    HotShotConfig,
+};
+use url::Url;
+
+use crate::{
+    traits::NodeImplementation, types::SystemContextHandle, HotShotError, HotShotInitializer,
+    MarketplaceConfig, SystemContext, Versions,
+};
+
+/// Where the node starts from
+enum Start<TYPES: NodeType> {
+    /// from genesis, for the given instance state
+    Genesis(TYPES::InstanceState),
+    /// from an initializer, e.g. one restored from storage
+    Initializer(HotShotInitializer<TYPES>),
+}
+
+/// Builds a [`SystemContext`] and spawns its tasks
+///
+/// Everything [`HotShotBuilder::new`] takes is required. Unless set otherwise, the node starts
+/// from genesis with node id 0, fresh metrics, the first builder of the config as fallback
+/// builder, and the default event channel capacities.
+pub struct HotShotBuilder<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
+    /// the public key of the node
+    public_key: TYPES::SignatureKey,
+    /// the private key of the node
+    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// the id of the node, used in logs
+    node_id: u64,
+    /// the config of the node
+    config: HotShotConfig<TYPES::SignatureKey>,
+    /// the memberships of the committees
+    memberships: Arc<RwLock<TYPES::Membership>>,
+    /// the network of the node
+    network: Arc<I::Network>,
+    /// the storage of the node
+    storage: I::Storage,
+    /// what the node starts from
+    start: Start<TYPES>,
+    /// the metrics of the node
+    metrics: ConsensusMetricsValue,
+    /// the auction results provider
+    auction_results_provider: Arc<I::AuctionResultsProvider>,
+    /// the fallback builder, if not the first builder of the config
+    fallback_builder_url: Option<Url>,
+    /// the capacity of the internal event channel
+    internal_channel_capacity: usize,
+    /// the capacity of the external event channel
+    external_channel_capacity: usize,
+    /// marker for the versions
+    _versions: std::marker::PhantomData<V>,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> HotShotBuilder<TYPES, I, V> {
+    /// A builder for a node with the given keys, config, memberships, network, storage and
+    /// auction results provider, starting from the genesis of `instance_state`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        public_key: TYPES::SignatureKey,
+        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        config: HotShotConfig<TYPES::SignatureKey>,
+        memberships: Arc<RwLock<TYPES::Membership>>,
+        network: Arc<I::Network>,
+        storage: I::Storage,
+        auction_results_provider: Arc<I::AuctionResultsProvider>,
+        instance_state: TYPES::InstanceState,
+    ) -> Self {
+        Self {
+            public_key,
+            private_key,
+            node_id: 0,
+            config,
+            memberships,
+            network,
+            storage,
+            start: Start::Genesis(instance_state),
+            metrics: ConsensusMetricsValue::default(),
+            auction_results_provider,
+            fallback_builder_url: None,
+            internal_channel_capacity: EVENT_CHANNEL_SIZE,
+            external_channel_capacity: EXTERNAL_EVENT_CHANNEL_SIZE,
+            _versions: std::marker::PhantomData,
+        }
+    }
+
+    /// Identify the node by `node_id` in logs
+    #[must_use]
+    pub fn node_id(mut self, node_id: u64) -> Self {
+        self.node_id = node_id;
+        self
+    }
+
+    /// Start the node from `initializer` rather than from genesis
+    #[must_use]
+    pub fn initializer(mut self, initializer: HotShotInitializer<TYPES>) -> Self {
+        self.start = Start::Initializer(initializer);
+        self
+    }
+
+    /// Record the metrics of the node to `metrics`
+    #[must_use]
+    pub fn metrics(mut self, metrics: ConsensusMetricsValue) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Fall back to the builder at `url` when the auction has no winner
+    #[must_use]
+    pub fn fallback_builder_url(mut self, url: Url) -> Self {
+        self.fallback_builder_url = Some(url);
+        self
+    }
+
+    /// Hold up to `internal` internal and `external` external events before dropping the oldest
+    #[must_use]
+    pub fn event_channel_capacity(mut self, internal: usize, external: usize) -> Self {
+        self.internal_channel_capacity = internal;
+        self.external_channel_capacity = external;
+        self
+    }
+
+    /// Time views out after `timeout`
+    #[must_use]
+    pub fn next_view_timeout(mut self, timeout: Duration) -> Self {
+        self.config.next_view_timeout = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Time view sync rounds out after `timeout`
+    #[must_use]
+    pub fn view_sync_timeout(mut self, timeout: Duration) -> Self {
+        self.config.view_sync_timeout = timeout;
+        self
+    }
+
+    /// Wait at most `timeout` for a block from the builder when leading
+    #[must_use]
+    pub fn builder_timeout(mut self, timeout: Duration) -> Self {
+        self.config.builder_timeout = timeout;
+        self
+    }
+
+    /// Validate the config, migrate the storage, construct the node and spawn its tasks
+    ///
+    /// The node starts out paused, call [`SystemContext::start_consensus`] on `handle.hotshot` to
+    /// start it.
+    ///
+    /// # Errors
+    /// if the config is inconsistent, the genesis state cannot be built, or the storage cannot
+    /// be migrated
+    pub async fn build(self) -> Result<SystemContextHandle<TYPES, I, V>, HotShotError<TYPES>> {
+        self.config.validate()?;
+        self.config.validate_node(&self.public_key)?;
+
+        let initializer = match self.start {
+            Start::Genesis(instance_state) => {
+                HotShotInitializer::from_genesis::<V>(instance_state).await?
+            }
+            Start::Initializer(initializer) => initializer,
+        };
+
+        self.storage
+            .migrate_consensus(
+                Into::<Leaf2<TYPES>>::into,
+                convert_proposal::<TYPES, QuorumProposal<TYPES>, QuorumProposal2<TYPES>>,
+            )
+            .await
+            .map_err(|e| HotShotError::Storage(e.context("Failed to migrate consensus storage")))?;
+
+        let fallback_builder_url = self
+            .fallback_builder_url
+            .unwrap_or_else(|| self.config.builder_urls.first().clone());
+        let marketplace_config = MarketplaceConfig {
+            auction_results_provider: self.auction_results_provider,
+            fallback_builder_url,
+        };
+
+        let hotshot = SystemContext::new_from_channels(
+            self.public_key,
+            self.private_key,
+            self.node_id,
+            self.config,
+            self.memberships,
+            self.network,
+            initializer,
+            self.metrics,
+            self.storage,
+            marketplace_config,
+            broadcast(self.internal_channel_capacity),
+            broadcast(self.external_channel_capacity),
+        );
+
+        Ok(hotshot.run_tasks().await)
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A WebSocket server exposing the event stream of a node to applications which do not link
+//! against this crate.
+//!
+//! Every connection receives the [`Event`]s of the node as JSON text frames of the form
+//! `{"type":"event","kind":"decide","event":{...}}`. A client narrows what it receives by sending
+//! a [`Subscription`], e.g. `{"kinds":["decide","view_timeout"],"transactions":["COMMIT~..."]}`,
+//! which replaces its previous one. Once a watched transaction is decided, the client also
+//! receives `{"type":"transaction_status","transaction":"COMMIT~...","status":"decided",...}`.
+//!
+//! The server never slows down consensus: every connection has a queue of
+//! [`SUBSCRIBER_QUEUE_LEN`] events, and a client which falls further behind is disconnected.
+
+use std::{collections::HashSet, net::SocketAddr, sync::Arc};
+
+use async_broadcast::Receiver;
+use futures::{SinkExt, StreamExt};
+use hotshot_types::{
+    event::{Event, EventType},
+    traits::{
+        block_contents::{BlockHeader, BlockPayload},
+        node_implementation::NodeType,
+    },
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select, spawn,
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+pub use crate::types::EventKind;
+
+/// The number of events queued for a client before it is disconnected
+pub const SUBSCRIBER_QUEUE_LEN: usize = 1024;
+
+/// What a client wants to receive
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    /// the kinds of events to receive, or every kind if not given
+    #[serde(default)]
+    pub kinds: Option<HashSet<EventKind>>,
+    /// the commitments of transactions to report the status of
+    #[serde(default)]
+    pub transactions: HashSet<String>,
+}
+
+impl Subscription {
+    /// Whether events of `kind` should be sent
+    #[must_use]
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.kinds
+            .as_ref()
+            .map_or(true, |kinds| kinds.contains(&kind))
+    }
+}
+
+/// A message sent to clients
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a, TYPES: NodeType> {
+    /// an event of the node
+    Event {
+        /// the kind of the event
+        kind: EventKind,
+        /// the event
+        event: &'a Event<TYPES>,
+    },
+    /// a watched transaction was decided
+    TransactionStatus {
+        /// the commitment of the transaction
+        transaction: String,
+        /// always `decided`, for now
+        status: &'static str,
+        /// the view of the leaf the transaction was decided in
+        view: u64,
+        /// the height of the block the transaction was decided in
+        height: u64,
+    },
+}
+
+/// The messages to send a client with `subscription` for `event`
+fn messages<TYPES: NodeType>(subscription: &Subscription, event: &Event<TYPES>) -> Vec<String> {
+    let mut messages = vec![];
+    if let Some(kind) = EventKind::of(&event.event).filter(|&kind| subscription.wants(kind)) {
+        messages.push(ServerMessage::Event { kind, event });
+    }
+
+    if let EventType::Decide { leaf_chain, .. } = &event.event {
+        if !subscription.transactions.is_empty() {
+            for info in leaf_chain.iter().rev() {
+                let Some(payload) = info.leaf.block_payload() else {
+                    continue;
+                };
+                for commitment in
+                    payload.transaction_commitments(info.leaf.block_header().metadata())
+                {
+                    let transaction = commitment.to_string();
+                    if subscription.transactions.contains(&transaction) {
+                        messages.push(ServerMessage::TransactionStatus {
+                            transaction,
+                            status: "decided",
+                            view: *info.leaf.view_number(),
+                            height: info.leaf.height(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    messages
+        .into_iter()
+        .filter_map(|message| {
+            serde_json::to_string(&message)
+                .inspect_err(|e| tracing::warn!("Failed to encode an event for a client: {e}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// Serve `events` over WebSocket at `address`, until the returned task is aborted
+pub(crate) fn serve_events<TYPES: NodeType>(
+    mut events: Receiver<Event<TYPES>>,
+    address: SocketAddr,
+) -> JoinHandle<()> {
+    spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to serve events on {address}: {e}");
+                return;
+            }
+        };
+        tracing::info!("Serving events on ws://{address}");
+
+        let mut subscribers: Vec<mpsc::Sender<Arc<Event<TYPES>>>> = vec![];
+        loop {
+            select! {
+                accepted = listener.accept() => {
+                    let stream = match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            tracing::warn!("Failed to accept an event subscriber: {e}");
+                            continue;
+                        }
+                    };
+                    let (sender, receiver) = mpsc::channel(SUBSCRIBER_QUEUE_LEN);
+                    subscribers.push(sender);
+                    spawn(serve_subscriber(stream, receiver));
+                }
+                event = events.next() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    let event = Arc::new(event);
+                    subscribers.retain(|subscriber| match subscriber.try_send(Arc::clone(&event)) {
+                        Ok(()) => true,
+                        Err(TrySendError::Full(_)) => {
+                            tracing::warn!("Disconnecting an event subscriber which fell behind");
+                            false
+                        }
+                        Err(TrySendError::Closed(_)) => false,
+                    });
+                }
+            }
+        }
+    })
+}
+
+/// Send the events from `events` a client on `stream` subscribed to, until it disconnects
+async fn serve_subscriber<TYPES: NodeType>(
+    stream: TcpStream,
+    mut events: mpsc::Receiver<Arc<Event<TYPES>>>,
+) {
+    let socket = match accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::debug!("Failed to accept a WebSocket connection: {e}");
+            return;
+        }
+    };
+    let (mut sink, mut source) = socket.split();
+    let mut subscription = Subscription::default();
+
+    loop {
+        select! {
+            event = events.recv() => {
+                let Some(event) = event else {
+                    break;
+                };
+                for message in messages(&subscription, &event) {
+                    if sink.send(Message::text(message)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            message = source.next() => {
+                match message {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                        Ok(new_subscription) => subscription = new_subscription,
+                        Err(e) => tracing::debug!("Ignoring an invalid subscription: {e}"),
+                    },
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+
+    let _ = sink.close().await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subscriptions_filter_kinds() {
+        let everything = Subscription::default();
+        assert!(everything.wants(EventKind::Decide));
+        assert!(everything.wants(EventKind::ViewTimeout));
+
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"kinds":["decide","view_timeout"],"transactions":["COMMIT~abc"]}"#,
+        )
+        .unwrap();
+        assert!(subscription.wants(EventKind::Decide));
+        assert!(subscription.wants(EventKind::ViewTimeout));
+        assert!(!subscription.wants(EventKind::Transactions));
+        assert!(subscription.transactions.contains("COMMIT~abc"));
+    }
+}
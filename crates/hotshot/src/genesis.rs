@@ -0,0 +1,347 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Tooling for the genesis ceremony of a network.
+//!
+//! The coordinator of the ceremony collects the keys and stakes of the validators, and feeds them
+//! with the chain parameters and the initial state to a [`GenesisBuilder`]. The builder produces
+//! the stake tables, the genesis leaf and the genesis QC, and one [`GenesisFile`] per validator
+//! carrying all of them. The same inputs always produce the same files, so anyone can rerun the
+//! ceremony and compare [digests](GenesisFile::digest).
+//!
+//! At startup, each node [loads](GenesisFile::load) its file and
+//! [checks](GenesisFile::into_initializer) it against its own key and initial state before
+//! starting from it.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use bincode::Options;
+use committable::{Commitment, Committable};
+use hotshot_types::{
+    data::Leaf2,
+    light_client::StateVerKey,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        node_implementation::{NodeType, Versions},
+        signature_key::{SignatureKey, StakeTableEntryType},
+        ValidatedState,
+    },
+    utils::bincode_opts,
+    HotShotConfig, PeerConfig,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::HotShotInitializer;
+
+/// A validator taking part in the genesis of a network
+struct GenesisValidator<K: SignatureKey> {
+    /// the key, stake and state verification key of the validator
+    peer: PeerConfig<K>,
+    /// whether the validator is on the DA committee
+    da: bool,
+}
+
+/// Produces the genesis of a network from its validators, chain parameters and initial state
+///
+/// Validators keep the order in which they are added, which is their index in the stake table and
+/// the order of the genesis files.
+pub struct GenesisBuilder<TYPES: NodeType> {
+    /// the chain parameters, whose stake tables are replaced by the validators
+    config: HotShotConfig<TYPES::SignatureKey>,
+    /// the initial state
+    instance_state: TYPES::InstanceState,
+    /// the validators, in stake table order
+    validators: Vec<GenesisValidator<TYPES::SignatureKey>>,
+}
+
+impl<TYPES: NodeType> GenesisBuilder<TYPES> {
+    /// A genesis with the chain parameters of `config` starting from `instance_state`, without
+    /// any validator yet
+    ///
+    /// The stake tables and committee sizes of `config` are ignored, they are derived from the
+    /// validators.
+    #[must_use]
+    pub fn new(
+        config: HotShotConfig<TYPES::SignatureKey>,
+        instance_state: TYPES::InstanceState,
+    ) -> Self {
+        Self {
+            config,
+            instance_state,
+            validators: Vec::new(),
+        }
+    }
+
+    /// Add a validator with `stake`
+    #[must_use]
+    pub fn validator(
+        mut self,
+        public_key: TYPES::SignatureKey,
+        stake: u64,
+        state_ver_key: StateVerKey,
+    ) -> Self {
+        self.push(public_key, stake, state_ver_key, false);
+        self
+    }
+
+    /// Add a validator with `stake` which is also on the DA committee
+    #[must_use]
+    pub fn da_validator(
+        mut self,
+        public_key: TYPES::SignatureKey,
+        stake: u64,
+        state_ver_key: StateVerKey,
+    ) -> Self {
+        self.push(public_key, stake, state_ver_key, true);
+        self
+    }
+
+    /// Add a validator
+    fn push(
+        &mut self,
+        public_key: TYPES::SignatureKey,
+        stake: u64,
+        state_ver_key: StateVerKey,
+        da: bool,
+    ) {
+        self.validators.push(GenesisValidator {
+            peer: PeerConfig {
+                stake_table_entry: public_key.stake_table_entry(stake),
+                state_ver_key,
+            },
+            da,
+        });
+    }
+
+    /// Produce the genesis of the network
+    ///
+    /// # Errors
+    /// if there is no validator, a validator has no stake or was added twice, or the resulting
+    /// config is inconsistent
+    pub async fn build<V: Versions>(self) -> Result<Genesis<TYPES>> {
+        ensure!(!self.validators.is_empty(), "The genesis has no validator");
+
+        let mut keys = HashSet::new();
+        for validator in &self.validators {
+            let public_key = validator.peer.stake_table_entry.public_key();
+            ensure!(
+                !validator.peer.stake_table_entry.stake().is_zero(),
+                "Validator {public_key} has no stake"
+            );
+            ensure!(
+                keys.insert(public_key.clone()),
+                "Validator {public_key} was added twice"
+            );
+        }
+
+        let mut config = self.config;
+        config.known_nodes_with_stake = self
+            .validators
+            .iter()
+            .map(|validator| validator.peer.clone())
+            .collect();
+        config.known_da_nodes = self
+            .validators
+            .iter()
+            .filter(|validator| validator.da)
+            .map(|validator| validator.peer.clone())
+            .collect();
+        config.num_nodes_with_stake = config
+            .known_nodes_with_stake
+            .len()
+            .try_into()
+            .context("The genesis has no validator")?;
+        config.da_staked_committee_size = config.known_da_nodes.len();
+        config.validate().context("Invalid genesis config")?;
+
+        let (validated_state, _) = TYPES::ValidatedState::genesis(&self.instance_state);
+        let leaf = Leaf2::genesis(&validated_state, &self.instance_state).await;
+        let qc = QuorumCertificate2::genesis::<V>(&validated_state, &self.instance_state).await;
+
+        Ok(Genesis { config, leaf, qc })
+    }
+}
+
+/// The genesis of a network, as produced by a [`GenesisBuilder`]
+#[derive(Clone, Debug)]
+pub struct Genesis<TYPES: NodeType> {
+    /// the chain parameters, with the stake tables of the genesis validators
+    pub config: HotShotConfig<TYPES::SignatureKey>,
+    /// the genesis leaf
+    pub leaf: Leaf2<TYPES>,
+    /// the genesis QC, for the genesis leaf
+    pub qc: QuorumCertificate2<TYPES>,
+}
+
+impl<TYPES: NodeType> Genesis<TYPES> {
+    /// The genesis file of each validator, in stake table order
+    #[must_use]
+    pub fn files(&self) -> Vec<GenesisFile<TYPES>> {
+        self.config
+            .known_nodes_with_stake
+            .iter()
+            .zip(0..)
+            .map(|(peer, node_index)| GenesisFile {
+                node_index,
+                public_key: peer.stake_table_entry.public_key(),
+                config: self.config.clone(),
+                leaf: self.leaf.clone(),
+                qc: self.qc.clone(),
+            })
+            .collect()
+    }
+
+    /// Write the genesis file of each validator to `dir`, as `genesis-<node index>.json`
+    ///
+    /// # Errors
+    /// if `dir` cannot be created or a file cannot be written
+    pub async fn write_files(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut paths = Vec::new();
+        for file in self.files() {
+            let path = dir.join(format!("genesis-{}.json", file.node_index));
+            file.write(&path).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// The genesis of a network as handed to one of its validators
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct GenesisFile<TYPES: NodeType> {
+    /// the index of the validator in the stake table
+    pub node_index: u64,
+    /// the public key of the validator
+    pub public_key: TYPES::SignatureKey,
+    /// the chain parameters, with the stake tables of the genesis validators
+    pub config: HotShotConfig<TYPES::SignatureKey>,
+    /// the genesis leaf
+    pub leaf: Leaf2<TYPES>,
+    /// the genesis QC, for the genesis leaf
+    pub qc: QuorumCertificate2<TYPES>,
+}
+
+impl<TYPES: NodeType> GenesisFile<TYPES> {
+    /// Read a genesis file from `path`
+    ///
+    /// The file is only parsed, check it with [`into_initializer`](Self::into_initializer)
+    /// before starting from it.
+    ///
+    /// # Errors
+    /// if the file cannot be read or parsed
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("Failed to parse the genesis file {}", path.display()))
+    }
+
+    /// Write the genesis file to `path`
+    ///
+    /// # Errors
+    /// if the file cannot be written
+    pub async fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents =
+            serde_json::to_vec_pretty(self).context("Failed to serialize the genesis file")?;
+        fs::write(path, contents)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// A digest of the genesis of the network, the same in the genesis file of every validator
+    ///
+    /// It covers the stake tables, the epoch height and the genesis leaf, so validators can
+    /// compare it out of band to make sure they start the same network.
+    ///
+    /// # Errors
+    /// if the stake tables cannot be serialized
+    pub fn digest(&self) -> Result<[u8; 32]> {
+        let leaf_commit: [u8; 32] = self.leaf.commit().into();
+        let bytes = bincode_opts()
+            .serialize(&(
+                &self.config.known_nodes_with_stake,
+                &self.config.known_da_nodes,
+                self.config.epoch_height,
+                leaf_commit,
+            ))
+            .context("Failed to serialize the genesis")?;
+        Ok(*blake3::hash(&bytes).as_bytes())
+    }
+
+    /// Check the genesis file against the key and initial state of this node, and produce the
+    /// config and initializer to start the node from
+    ///
+    /// The genesis leaf and QC are rebuilt from `instance_state`, so a node whose initial state
+    /// differs from the one of the ceremony refuses to start rather than fork off.
+    ///
+    /// # Errors
+    /// if the file is not meant for `public_key`, its config is inconsistent, or its genesis leaf
+    /// or QC do not follow from `instance_state`
+    pub async fn into_initializer<V: Versions>(
+        self,
+        public_key: &TYPES::SignatureKey,
+        instance_state: TYPES::InstanceState,
+    ) -> Result<(
+        HotShotConfig<TYPES::SignatureKey>,
+        HotShotInitializer<TYPES>,
+    )> {
+        ensure!(
+            self.public_key == *public_key,
+            "The genesis file is for validator {}, not {public_key}",
+            self.public_key
+        );
+        let index = usize::try_from(self.node_index)?;
+        match self.config.known_nodes_with_stake.get(index) {
+            Some(peer) if peer.stake_table_entry.public_key() == *public_key => {}
+            Some(_) => {
+                bail!("Validator {public_key} is not at index {index} of the genesis stake table")
+            }
+            None => bail!(
+                "Index {index} is out of the genesis stake table of {} validators",
+                self.config.known_nodes_with_stake.len()
+            ),
+        }
+        self.config
+            .validate()
+            .context("Invalid config in the genesis file")?;
+
+        let (validated_state, _) = TYPES::ValidatedState::genesis(&instance_state);
+        let leaf = Leaf2::genesis(&validated_state, &instance_state).await;
+        let qc = QuorumCertificate2::genesis::<V>(&validated_state, &instance_state).await;
+        let leaf_commit: Commitment<Leaf2<TYPES>> = self.leaf.commit();
+        ensure!(
+            leaf_commit == leaf.commit(),
+            "The genesis leaf {leaf_commit} does not follow from the initial state of this node"
+        );
+        ensure!(
+            self.qc.data.leaf_commit == leaf_commit,
+            "The genesis QC is not for the genesis leaf"
+        );
+        ensure!(
+            self.qc == qc,
+            "The genesis QC does not follow from the initial state of this node"
+        );
+
+        let initializer = HotShotInitializer::from_genesis::<V>(instance_state)
+            .await
+            .map_err(|e| anyhow!("Failed to initialize from genesis: {e}"))?;
+        Ok((self.config, initializer))
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A gRPC service exposing a node to clients which do not link against this crate.
+//!
+//! The service is defined in `proto/hotshot.proto`. Transactions and block headers are encoded as
+//! JSON, and commitments and keys as tagged base64 strings, so clients need no knowledge of the
+//! application types beyond their JSON form.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use committable::Committable;
+use futures::{stream::BoxStream, StreamExt};
+use hotshot_types::{
+    data::Leaf2,
+    error::{HotShotError, TransactionRejection},
+    traits::{
+        block_contents::{BlockHeader, BlockPayload},
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType},
+        signature_key::StakeTableEntryType,
+    },
+};
+use tokio::{spawn, task::JoinHandle};
+use tonic::{transport::Server, Request, Response, Status};
+
+use self::proto::{
+    node_server::{Node, NodeServer},
+    Block, GetBlockByViewRequest, GetLatestDecidedRequest, GetValidatorSetRequest,
+    SubmitTransactionRequest, SubmitTransactionResponse, SubscribeDecidesRequest, Validator,
+    ValidatorSet,
+};
+use crate::{types::finality_stream, SystemContext, Versions};
+
+/// The messages and service generated from `proto/hotshot.proto`
+#[allow(clippy::all, clippy::pedantic, missing_docs)]
+pub mod proto {
+    tonic::include_proto!("hotshot.v1");
+}
+
+/// The gRPC service of a node
+pub struct NodeService<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
+    /// the node to serve
+    context: Arc<SystemContext<TYPES, I, V>>,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> NodeService<TYPES, I, V> {
+    /// Create a service for `context`
+    #[must_use]
+    pub fn new(context: Arc<SystemContext<TYPES, I, V>>) -> Self {
+        Self { context }
+    }
+}
+
+/// The gRPC representation of the block of `leaf`
+fn block<TYPES: NodeType>(leaf: &Leaf2<TYPES>) -> Block {
+    let payload = leaf.block_payload();
+    Block {
+        view: *leaf.view_number(),
+        height: leaf.height(),
+        leaf_commitment: leaf.commit().to_string(),
+        header_json: serde_json::to_string(leaf.block_header()).unwrap_or_default(),
+        payload_available: payload.is_some(),
+        transaction_commitments: payload
+            .map(|payload| {
+                payload
+                    .transaction_commitments(leaf.block_header().metadata())
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+#[tonic::async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Node for NodeService<TYPES, I, V> {
+    type SubscribeDecidesStream = BoxStream<'static, Result<Block, Status>>;
+
+    async fn submit_transaction(
+        &self,
+        request: Request<SubmitTransactionRequest>,
+    ) -> Result<Response<SubmitTransactionResponse>, Status> {
+        let transaction: TYPES::Transaction =
+            serde_json::from_str(&request.into_inner().transaction_json)
+                .map_err(|e| Status::invalid_argument(format!("Invalid transaction: {e}")))?;
+        let commitment = transaction.commit().to_string();
+        self.context
+            .publish_transaction_async(transaction)
+            .await
+            .map_err(|e| match &e {
+                HotShotError::TransactionRejected(
+                    TransactionRejection::Full(_) | TransactionRejection::OverMemoryBudget,
+                ) => Status::resource_exhausted(e.to_string()),
+                HotShotError::TransactionRejected(_) => Status::invalid_argument(e.to_string()),
+                _ => Status::unavailable(e.to_string()),
+            })?;
+
+        Ok(Response::new(SubmitTransactionResponse { commitment }))
+    }
+
+    async fn get_block_by_view(
+        &self,
+        request: Request<GetBlockByViewRequest>,
+    ) -> Result<Response<Block>, Status> {
+        let view = TYPES::View::new(request.into_inner().view);
+        let consensus = self.context.consensus();
+        let consensus = consensus.read().await;
+        let leaf = consensus
+            .validated_state_map()
+            .get(&view)
+            .and_then(|view| view.view_inner.leaf_commitment())
+            .and_then(|commitment| consensus.saved_leaves().get(&commitment))
+            .ok_or_else(|| Status::not_found(format!("No block retained for view {}", *view)))?;
+
+        Ok(Response::new(block(leaf)))
+    }
+
+    async fn get_latest_decided(
+        &self,
+        _request: Request<GetLatestDecidedRequest>,
+    ) -> Result<Response<Block>, Status> {
+        Ok(Response::new(block(&self.context.decided_leaf().await)))
+    }
+
+    async fn get_validator_set(
+        &self,
+        request: Request<GetValidatorSetRequest>,
+    ) -> Result<Response<ValidatorSet>, Status> {
+        let epoch = match request.into_inner().epoch {
+            Some(epoch) => TYPES::Epoch::new(epoch),
+            None => self.context.consensus().read().await.cur_epoch(),
+        };
+        let memberships = self.context.memberships.read().await;
+        let da_members = memberships
+            .da_stake_table(epoch)
+            .iter()
+            .map(|entry| entry.public_key())
+            .collect::<Vec<TYPES::SignatureKey>>();
+        let validators = memberships
+            .stake_table(epoch)
+            .iter()
+            .map(|entry| {
+                let key: TYPES::SignatureKey = entry.public_key();
+                Validator {
+                    public_key: key.to_string(),
+                    stake: entry.stake().to_string(),
+                    da: da_members.contains(&key),
+                }
+            })
+            .collect();
+
+        Ok(Response::new(ValidatorSet {
+            epoch: *epoch,
+            validators,
+        }))
+    }
+
+    async fn subscribe_decides(
+        &self,
+        request: Request<SubscribeDecidesRequest>,
+    ) -> Result<Response<Self::SubscribeDecidesStream>, Status> {
+        let from_view = request.into_inner().from_view.map(TYPES::View::new);
+        let stream = finality_stream(Arc::clone(&self.context.finality_log), from_view)
+            .await
+            .map(|(leaf, ..)| Ok(block(&leaf)));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Serve the gRPC service of `context` at `address`, until the returned task is aborted
+pub(crate) fn serve_grpc<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: Arc<SystemContext<TYPES, I, V>>,
+    address: SocketAddr,
+) -> JoinHandle<()> {
+    spawn(async move {
+        tracing::info!("Serving gRPC on {address}");
+        if let Err(e) = Server::builder()
+            .add_service(NodeServer::new(NodeService::new(context)))
+            .serve(address)
+            .await
+        {
+            tracing::error!("Failed to serve gRPC on {address}: {e}");
+        }
+    })
+}
@@ -1,6 +1,24 @@
-use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing_subscriber::{fmt::format::FmtSpan, prelude::*, reload, EnvFilter, Layer, Registry};
+
+use crate::log_file::{LogFileConfig, RollingFileWriter};
+
+/// The largest HTTP request, headers and body, the built-in servers accept
+const MAX_HTTP_REQUEST_LEN: usize = 64 * 1024;
+
+/// The handle to the filter installed by [`initialize_logging`], to change it at runtime
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 /// Initializes logging
+///
+/// The filter is read from `RUST_LOG`, and can be changed afterwards with [`set_log_filter`].
+/// Logs are written to stderr, and also to rotating files if `RUST_LOG_DIR` is set, see
+/// [`LogFileConfig::from_env`]. `RUST_LOG_FORMAT=json` applies to both.
+/// With the `otel` feature, spans are also exported over OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT`
+/// is set.
 pub fn initialize_logging() {
     // Parse the `RUST_LOG_SPAN_EVENTS` environment variable
     let span_event_filter = match std::env::var("RUST_LOG_SPAN_EVENTS") {
@@ -20,16 +38,180 @@ pub fn initialize_logging() {
     };
 
     // Conditionally initialize in `json` mode
-    if std::env::var("RUST_LOG_FORMAT") == Ok("json".to_string()) {
-        let _ = tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::from_default_env())
-            .with_span_events(span_event_filter)
+    let json = std::env::var("RUST_LOG_FORMAT") == Ok("json".to_string());
+    let fmt_layer = if json {
+        tracing_subscriber::fmt::layer()
+            .with_span_events(span_event_filter.clone())
             .json()
-            .try_init();
+            .boxed()
     } else {
-        let _ = tracing_subscriber::fmt()
-            .with_env_filter(EnvFilter::from_default_env())
-            .with_span_events(span_event_filter)
-            .try_init();
+        tracing_subscriber::fmt::layer()
+            .with_span_events(span_event_filter.clone())
+            .boxed()
+    };
+
+    // Also write to log files if configured
+    let file_layer = LogFileConfig::from_env()
+        .and_then(|config| {
+            RollingFileWriter::open(config)
+                .inspect_err(|e| eprintln!("Failed to open the log file: {e}"))
+                .ok()
+        })
+        .map(|writer| {
+            let layer = tracing_subscriber::fmt::layer()
+                .with_span_events(span_event_filter)
+                .with_ansi(false)
+                .with_writer(Mutex::new(writer));
+            if json {
+                layer.json().boxed()
+            } else {
+                layer.boxed()
+            }
+        });
+
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(file_layer);
+
+    #[cfg(feature = "otel")]
+    let subscriber = subscriber
+        .with(otlp_tracer().map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)));
+
+    if subscriber.try_init().is_ok() {
+        let _ = LOG_FILTER.set(filter_handle);
+    }
+}
+
+/// Replace the log filter with `directives`, in the syntax of `RUST_LOG`, e.g.
+/// `info,hotshot_task_impls::consensus=trace`
+///
+/// # Errors
+/// if `directives` are invalid, or logging was not set up by [`initialize_logging`]
+pub fn set_log_filter(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(directives)
+        .with_context(|| format!("Invalid log filter `{directives}`"))?;
+    LOG_FILTER
+        .get()
+        .ok_or_else(|| anyhow!("Logging was not initialized with a reloadable filter"))?
+        .reload(filter)
+        .context("Failed to replace the log filter")?;
+    tracing::warn!("Log filter set to `{directives}`");
+    Ok(())
+}
+
+/// The current log filter, if logging was set up by [`initialize_logging`]
+#[must_use]
+pub fn log_filter() -> Option<String> {
+    LOG_FILTER.get()?.with_current(ToString::to_string).ok()
+}
+
+/// Read an HTTP request from `stream`: its headers, and its body up to its `Content-Length`
+///
+/// # Errors
+/// if reading fails, or the request is longer than [`MAX_HTTP_REQUEST_LEN`]
+pub(crate) async fn read_http_request<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> std::io::Result<String> {
+    let too_long = || {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("HTTP request longer than {MAX_HTTP_REQUEST_LEN} bytes"),
+        )
     };
+
+    let mut request = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        if let Some(headers_len) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let body_len = String::from_utf8_lossy(&request[..headers_len])
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            let len = headers_len + 4 + body_len;
+            if len > MAX_HTTP_REQUEST_LEN {
+                return Err(too_long());
+            }
+            if request.len() >= len {
+                request.truncate(len);
+                break;
+            }
+        } else if request.len() > MAX_HTTP_REQUEST_LEN {
+            return Err(too_long());
+        }
+
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            // The client closed the connection, answer what it sent
+            break;
+        }
+        request.extend_from_slice(&chunk[..read]);
+    }
+
+    Ok(String::from_utf8_lossy(&request).into_owned())
+}
+
+/// A tracer exporting spans over OTLP, if `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+#[cfg(feature = "otel")]
+fn otlp_tracer() -> Option<opentelemetry_sdk::trace::Tracer> {
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to create the OTLP span exporter: {e}");
+            return None;
+        }
+    };
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "hotshot".to_string());
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            service_name,
+        )]))
+        .build();
+    let tracer = provider.tracer("hotshot");
+    opentelemetry::global::set_tracer_provider(provider);
+
+    Some(tracer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn http_requests_are_read_up_to_their_content_length() {
+        let body = "x".repeat(10_000);
+        let request = format!(
+            "PUT /config HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let read = read_http_request(&mut format!("{request}trailing").as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(read, request);
+
+        let request = "GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n";
+        assert_eq!(
+            read_http_request(&mut request.as_bytes()).await.unwrap(),
+            request
+        );
+
+        let request = format!(
+            "PUT /config HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_HTTP_REQUEST_LEN
+        );
+        assert!(read_http_request(&mut request.as_bytes()).await.is_err());
+    }
 }
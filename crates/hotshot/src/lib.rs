@@ -11,7 +11,7 @@
 #[cfg(feature = "docs")]
 pub mod documentation;
 
-use committable::Committable;
+use committable::{Commitment, Committable};
 use futures::future::{select, Either};
 use hotshot_types::{
     message::UpgradeLock,
@@ -30,29 +30,72 @@ pub mod tasks;
 /// Contains helper functions for the crate
 pub mod helpers;
 
+/// Builds a node from its parts, with defaults for the rest
+pub mod builder;
+
+/// Writes logs to rotating files
+pub mod log_file;
+
+/// Syncs the state of a new node from the latest checkpoint snapshot
+pub mod state_sync;
+
+/// Produces and checks the genesis of a network
+pub mod genesis;
+
+/// Serves the event stream of a node over WebSocket
+#[cfg(feature = "event-server")]
+pub mod event_server;
+
+/// Serves the external gRPC API of a node
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+/// Serves a read-only HTTP API of a node
+#[cfg(feature = "rest-api")]
+pub mod rest_api;
+
 use std::{
     collections::{BTreeMap, HashMap},
     num::NonZeroUsize,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
-use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, RecvError, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
-use futures::join;
-use hotshot_task::task::{ConsensusTaskRegistry, NetworkTaskRegistry};
+use bytes::Bytes;
+use futures::{future::join_all, join};
+use hotshot_task::{
+    executor::TaskExecutor,
+    task::{ConsensusTaskRegistry, NetworkTaskRegistry},
+};
 use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
 // Internal
+pub use builder::HotShotBuilder;
 /// Reexport error type
 pub use hotshot_types::error::HotShotError;
 use hotshot_types::{
     consensus::{Consensus, ConsensusMetricsValue, OuterConsensus, View, ViewInner},
-    constants::{EVENT_CHANNEL_SIZE, EXTERNAL_EVENT_CHANNEL_SIZE},
+    constants::{
+        EVENT_CHANNEL_SIZE, EXTERNAL_EVENT_CHANNEL_SIZE, FINALITY_STREAM_CAPACITY,
+        VIEW_CHANGE_LOG_CAPACITY,
+    },
     data::{Leaf2, QuorumProposal, QuorumProposal2},
+    error::HotShotConfigError,
     event::{EventType, LeafInfo},
-    message::{convert_proposal, DataMessage, Message, MessageKind, Proposal},
-    simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate},
+    evidence::EvidenceLog,
+    memory_budget::MemoryBudget,
+    message::{
+        convert_proposal, DataMessage, Message, MessageKind, Proposal, WireEncoding, WireEncodings,
+    },
+    reconfig::ConfigUpdate,
+    simple_certificate::{
+        CheckpointCertificate, NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate,
+    },
     traits::{
         consensus_api::ConsensusApi,
         election::Membership,
@@ -64,6 +107,10 @@ use hotshot_types::{
         EncodeBytes,
     },
     utils::epoch_from_block_number,
+    validator_metadata::{SignedValidatorMetadata, ValidatorMetadataRegistry},
+    view_change::{ViewChangeLog, ViewChangeRecord},
+    vote::VerifiedVotes,
+    vote_intent::VoteIntent,
     HotShotConfig,
 };
 /// Reexport rand crate
@@ -76,7 +123,10 @@ use tracing::{debug, instrument, trace};
 use crate::{
     tasks::{add_consensus_tasks, add_network_tasks},
     traits::NodeImplementation,
-    types::{Event, SystemContextHandle},
+    types::{
+        ConsensusDump, Event, FinalityLog, HealthTracker, NodeHealth, PendingTransactions,
+        SubmissionLimits, SystemContextHandle, TxReceiptHandle,
+    },
 };
 
 /// Length, in bytes, of a 512 bit hash
@@ -152,6 +202,42 @@ pub struct SystemContext<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versi
 
     /// Marketplace config for this instance of HotShot
     pub marketplace_config: MarketplaceConfig<TYPES, I>,
+
+    /// Evidence of protocol violations observed by this node
+    pub evidence: EvidenceLog<TYPES>,
+
+    /// The most recently finalized leaves, or all of them on an archival node, from which finality
+    /// streams and leaf range requests are served
+    pub finality_log: Arc<RwLock<FinalityLog<TYPES>>>,
+
+    /// The progress of this node and of the network around it, for health probes
+    pub health: Arc<HealthTracker>,
+
+    /// The latest view changes of this node, with the reason for each
+    pub view_changes: Arc<RwLock<ViewChangeLog>>,
+
+    /// The identities validators published, see [`SystemContextHandle::set_validator_metadata`]
+    pub validator_metadata: Arc<RwLock<ValidatorMetadataRegistry<TYPES>>>,
+
+    /// Whether participation in consensus is paused, see [`SystemContextHandle::pause`]
+    pub paused: Arc<AtomicBool>,
+
+    /// Parameter changes waiting for the next view change, see [`Self::update_config`]
+    pub pending_config: Arc<RwLock<ConfigUpdate>>,
+
+    /// Transactions submitted to this node which were not decided or expired yet
+    pub(crate) pending_transactions:
+        Arc<RwLock<PendingTransactions<Commitment<TYPES::Transaction>>>>,
+
+    /// The encodings peers want direct messages in
+    pub wire_encodings: WireEncodings<TYPES::SignatureKey>,
+
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
+
+    /// The approximate memory used by queues, pending transactions, votes and leaves, against
+    /// the budgets of [`HotShotConfig::memory_budget`]
+    pub memory_budget: MemoryBudget,
 }
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Clone
     for SystemContext<TYPES, I, V>
@@ -177,6 +263,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Clone
             storage: Arc::clone(&self.storage),
             upgrade_lock: self.upgrade_lock.clone(),
             marketplace_config: self.marketplace_config.clone(),
+            evidence: Arc::clone(&self.evidence),
+            finality_log: Arc::clone(&self.finality_log),
+            health: Arc::clone(&self.health),
+            view_changes: Arc::clone(&self.view_changes),
+            validator_metadata: Arc::clone(&self.validator_metadata),
+            paused: Arc::clone(&self.paused),
+            pending_config: Arc::clone(&self.pending_config),
+            pending_transactions: Arc::clone(&self.pending_transactions),
+            wire_encodings: self.wire_encodings.clone(),
+            verified_votes: self.verified_votes.clone(),
+            memory_budget: self.memory_budget.clone(),
         }
     }
 }
@@ -333,11 +430,21 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
         } else {
             TYPES::Epoch::new(anchored_leaf.height() / config.epoch_height + 1)
         };
-        let consensus = Consensus::new(
+        let memory_budget = MemoryBudget::new(config.memory_budget, Arc::clone(&consensus_metrics));
+        // a watcher never participates, so it is paused for good
+        let paused = Arc::new(AtomicBool::new(config.watcher));
+        // an archival node keeps every finalized leaf, to serve it to nodes catching up
+        let finality_log = if config.archival {
+            FinalityLog::archive()
+        } else {
+            FinalityLog::new(FINALITY_STREAM_CAPACITY)
+        };
+        let mut consensus = Consensus::new(
             validated_state_map,
             anchored_leaf.view_number(),
             anchored_epoch,
-            anchored_leaf.view_number(),
+            // The lock never falls behind the anchor, which is decided
+            initializer.locked_view.max(anchored_leaf.view_number()),
             anchored_leaf.view_number(),
             initializer.actioned_view,
             initializer.saved_proposals,
@@ -348,6 +455,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
             Arc::clone(&consensus_metrics),
             config.epoch_height,
         );
+        consensus.set_memory_budget(memory_budget.clone());
+        consensus.restore_vote_intents(initializer.vote_intents);
+        if let Some(checkpoint_certificate) = initializer.checkpoint_certificate {
+            // The consensus is new, so there is no newer certificate to keep
+            let _ = consensus.update_checkpoint_certificate(checkpoint_certificate);
+        }
 
         let consensus = Arc::new(RwLock::new(consensus));
 
@@ -374,6 +487,19 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
             storage: Arc::new(RwLock::new(storage)),
             upgrade_lock,
             marketplace_config,
+            evidence: Arc::default(),
+            finality_log: Arc::new(RwLock::new(finality_log)),
+            health: Arc::default(),
+            view_changes: Arc::new(RwLock::new(ViewChangeLog::new(VIEW_CHANGE_LOG_CAPACITY))),
+            validator_metadata: Arc::default(),
+            paused,
+            pending_config: Arc::default(),
+            pending_transactions: Arc::new(RwLock::new(PendingTransactions::new(
+                memory_budget.clone(),
+            ))),
+            wire_encodings: WireEncodings::default(),
+            verified_votes: VerifiedVotes::new(memory_budget.clone()),
+            memory_budget,
         });
 
         inner
@@ -480,39 +606,134 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
         broadcast_event(event, &self.external_event_stream.0).await;
     }
 
-    /// Publishes a transaction asynchronously to the network.
+    /// Publishes a transaction asynchronously to the network, if it passes the
+    /// [`SubmissionLimits`] of this node.
+    ///
+    /// The returned receipt resolves once the transaction is decided or expires.
     ///
     /// # Errors
     ///
-    /// Always returns Ok; does not return an error if the transaction couldn't be published to the network
+    /// If the transaction cannot be serialized or is rejected by the [`SubmissionLimits`]; does
+    /// not return an error if the transaction couldn't be published to the network
     #[instrument(skip(self), err, target = "SystemContext", fields(id = self.id))]
     pub async fn publish_transaction_async(
         &self,
         transaction: TYPES::Transaction,
-    ) -> Result<(), HotShotError<TYPES>> {
+    ) -> Result<TxReceiptHandle<TYPES>, HotShotError<TYPES>> {
         trace!("Adding transaction to our own queue");
 
-        let api = self.clone();
+        let consensus_reader = self.consensus.read().await;
+        let view_number = consensus_reader.cur_view();
+        let epoch = consensus_reader.cur_epoch();
+        drop(consensus_reader);
 
-        let consensus_reader = api.consensus.read().await;
+        let serialized_message = self
+            .serialize_transaction(transaction.clone(), view_number)
+            .await?;
+
+        let commitment = transaction.commit();
+        let outcome = self.pending_transactions.write().await.admit(
+            commitment,
+            serialized_message.len(),
+            *view_number,
+        )?;
+
+        self.broadcast_transactions(vec![(transaction, serialized_message)], view_number, epoch);
+        Ok(TxReceiptHandle::new(commitment, outcome))
+    }
+
+    /// Publishes a batch of transactions asynchronously to the network, admitting those which
+    /// pass the [`SubmissionLimits`] of this node. The pending transactions are updated once for
+    /// the whole batch, and a single event announces every admitted transaction.
+    ///
+    /// Returns, in the order of `transactions`, a receipt for each admitted transaction or the
+    /// error it was rejected with.
+    #[instrument(
+        skip_all,
+        target = "SystemContext",
+        fields(id = self.id, count = transactions.len())
+    )]
+    pub async fn publish_transactions_async(
+        &self,
+        transactions: Vec<TYPES::Transaction>,
+    ) -> Vec<Result<TxReceiptHandle<TYPES>, HotShotError<TYPES>>> {
+        trace!("Adding a batch of transactions to our own queue");
+
+        let consensus_reader = self.consensus.read().await;
         let view_number = consensus_reader.cur_view();
         let epoch = consensus_reader.cur_epoch();
         drop(consensus_reader);
 
-        // Wrap up a message
+        // Serialize the whole batch before locking the pending transactions
+        let mut serialized_messages = Vec::with_capacity(transactions.len());
+        for transaction in &transactions {
+            serialized_messages.push(
+                self.serialize_transaction(transaction.clone(), view_number)
+                    .await,
+            );
+        }
+
+        let mut pending_transactions = self.pending_transactions.write().await;
+        let mut admitted = Vec::new();
+        let receipts = transactions
+            .into_iter()
+            .zip(serialized_messages)
+            .map(|(transaction, serialized_message)| {
+                let serialized_message = serialized_message?;
+                let commitment = transaction.commit();
+                let outcome = pending_transactions.admit(
+                    commitment,
+                    serialized_message.len(),
+                    *view_number,
+                )?;
+                admitted.push((transaction, serialized_message));
+                Ok(TxReceiptHandle::new(commitment, outcome))
+            })
+            .collect();
+        drop(pending_transactions);
+
+        if !admitted.is_empty() {
+            self.broadcast_transactions(admitted, view_number, epoch);
+        }
+        receipts
+    }
+
+    /// Wrap `transaction`, submitted in `view_number`, up in a message to the DA committee
+    async fn serialize_transaction(
+        &self,
+        transaction: TYPES::Transaction,
+        view_number: TYPES::View,
+    ) -> Result<Bytes, HotShotError<TYPES>> {
         let message_kind: DataMessage<TYPES> =
-            DataMessage::SubmitTransaction(transaction.clone(), view_number);
+            DataMessage::SubmitTransaction(transaction, view_number);
         let message = Message {
-            sender: api.public_key.clone(),
+            sender: self.public_key.clone(),
             kind: MessageKind::from(message_kind),
+            namespace: self.config.namespace,
+            expires_after: None,
         };
 
-        let serialized_message = self.upgrade_lock.serialize(&message).await.map_err(|err| {
-            HotShotError::FailedToSerialize(format!("failed to serialize transaction: {err}"))
-        })?;
+        Ok(self
+            .upgrade_lock
+            .serialize_signed(&message, WireEncoding::Bincode, &self.private_key)
+            .await
+            .map_err(|err| {
+                HotShotError::FailedToSerialize(format!("failed to serialize transaction: {err}"))
+            })?
+            .into())
+    }
 
+    /// Send the admitted `transactions`, with the messages carrying them, to the DA committee of
+    /// `view_number`, and announce them in a single event
+    fn broadcast_transactions(
+        &self,
+        transactions: Vec<(TYPES::Transaction, Bytes)>,
+        view_number: TYPES::View,
+        epoch: TYPES::Epoch,
+    ) {
+        let api = self.clone();
         spawn(async move {
-            let memberships_da_committee_members = api
+            let memberships_da_committee_members: Vec<_> = api
                 .memberships
                 .read()
                 .await
@@ -520,6 +741,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
                 .iter()
                 .cloned()
                 .collect();
+            let (transactions, serialized_messages): (Vec<_>, Vec<_>) =
+                transactions.into_iter().unzip();
 
             join! {
                 // TODO We should have a function that can return a network error if there is one
@@ -529,22 +752,25 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
                 // version <0, 1> currently fixed; this is the same as VERSION_0_1,
                 // and will be updated to be part of SystemContext. I wanted to use associated
                 // constants in NodeType, but that seems to be unavailable in the current Rust.
-                api
-                    .network.da_broadcast_message(
+                join_all(serialized_messages.into_iter().map(|serialized_message| {
+                    api.network.da_broadcast_message(
                         serialized_message,
-                        memberships_da_committee_members,
+                        memberships_da_committee_members.clone(),
                         BroadcastDelay::None,
-                    ),
+                    )
+                })),
                 api
                     .send_external_event(Event {
                         view_number,
-                        event: EventType::Transactions {
-                            transactions: vec![transaction],
-                        },
+                        event: EventType::Transactions { transactions },
                     }),
             }
         });
-        Ok(())
+    }
+
+    /// Accept transactions as `limits` say from now on
+    pub async fn set_submission_limits(&self, limits: SubmissionLimits) {
+        self.pending_transactions.write().await.set_limits(limits);
     }
 
     /// Returns a copy of the consensus struct
@@ -553,6 +779,95 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
         Arc::clone(&self.consensus.inner_consensus)
     }
 
+    /// Report the health of this node
+    pub async fn health(&self) -> NodeHealth {
+        let (current_view, last_decided_view) = {
+            let consensus = self.consensus.read().await;
+            (*consensus.cur_view(), *consensus.last_decided_view())
+        };
+        let storage_ok = match self.storage.read().await.health_check().await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Storage failed its health check: {e}");
+                false
+            }
+        };
+
+        NodeHealth {
+            current_view,
+            last_decided_view,
+            last_decide_time_ms: self.health.last_decide_ms(),
+            connected_peers: self.network.num_connected_peers(),
+            storage_ok,
+            view_lag: self.health.network_view().saturating_sub(current_view),
+            paused: self.is_paused(),
+            last_view_change: self.view_changes.read().await.latest().cloned(),
+        }
+    }
+
+    /// The latest view changes of this node, oldest first, with the reason for each
+    pub async fn view_changes(&self) -> Vec<ViewChangeRecord> {
+        self.view_changes.read().await.records().cloned().collect()
+    }
+
+    /// The latest identity published by each validator with stake, by key
+    pub async fn validator_metadata(&self) -> Vec<SignedValidatorMetadata<TYPES>> {
+        self.validator_metadata
+            .read()
+            .await
+            .records()
+            .cloned()
+            .collect()
+    }
+
+    /// Stop or resume sending votes and proposals, see [`SystemContextHandle::pause`]
+    ///
+    /// A watcher cannot be resumed.
+    pub fn set_paused(&self, paused: bool) {
+        if !paused && self.config.watcher {
+            tracing::warn!("Not resuming participation in consensus, this node is a watcher");
+            return;
+        }
+        if self.paused.swap(paused, Ordering::Relaxed) != paused {
+            if paused {
+                tracing::warn!("Pausing participation in consensus");
+            } else {
+                tracing::warn!("Resuming participation in consensus");
+            }
+        }
+    }
+
+    /// Whether participation in consensus is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Change parameters of this node from the next view change on
+    ///
+    /// Updates which have not been applied yet are combined, later values taking precedence.
+    /// Once applied, an [`EventType::ConfigUpdated`] records what changed.
+    ///
+    /// # Errors
+    /// if a timeout is zero or the log filter is invalid, in which case nothing changes
+    pub async fn update_config(&self, update: ConfigUpdate) -> Result<(), HotShotError<TYPES>> {
+        update.validate()?;
+        if let Some(directives) = &update.log_filter {
+            if tracing_subscriber::EnvFilter::try_new(directives).is_err() {
+                return Err(HotShotConfigError::InvalidLogFilter(directives.clone()).into());
+            }
+        }
+        tracing::info!("Config update queued for the next view: {update:?}");
+        self.pending_config.write().await.merge(update);
+        Ok(())
+    }
+
+    /// Take a snapshot of the consensus state of this node
+    pub async fn consensus_dump(&self) -> ConsensusDump<TYPES> {
+        let consensus = self.consensus.read().await;
+        ConsensusDump::new(&consensus, self.network.num_connected_peers())
+    }
+
     /// Returns a copy of the instance state
     pub fn instance_state(&self) -> Arc<TYPES::InstanceState> {
         Arc::clone(&self.instance_state)
@@ -661,7 +976,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
     ///
     /// For a list of which tasks are being spawned, see this module's documentation.
     pub async fn run_tasks(&self) -> SystemContextHandle<TYPES, I, V> {
-        let consensus_registry = ConsensusTaskRegistry::new();
+        let consensus_registry = ConsensusTaskRegistry::with_executor(TaskExecutor::new(
+            self.config.consensus_task_threads,
+        ));
         let network_registry = NetworkTaskRegistry::new();
 
         let output_event_stream = self.external_event_stream.clone();
@@ -712,7 +1029,8 @@ where
 
     /// Fuse two channels into a single channel
     ///
-    /// Note: the channels are fused using two async loops, whose `JoinHandle`s are dropped.
+    /// Note: the channels are fused using two async loops, whose `JoinHandle`s are dropped. Each
+    /// loop stops once the channels it reads from are closed.
     fn fuse_channels(
         &'static mut self,
         left: Channel<HotShotEvent<TYPES>>,
@@ -733,8 +1051,14 @@ where
         let _recv_loop_handle = spawn(async move {
             loop {
                 let msg = match select(left_receiver.recv(), right_receiver.recv()).await {
-                    Either::Left(msg) => Either::Left(msg.0.unwrap().as_ref().clone()),
-                    Either::Right(msg) => Either::Right(msg.0.unwrap().as_ref().clone()),
+                    Either::Left((Ok(msg), _)) => Either::Left(msg.as_ref().clone()),
+                    Either::Right((Ok(msg), _)) => Either::Right(msg.as_ref().clone()),
+                    Either::Left((Err(RecvError::Closed), _))
+                    | Either::Right((Err(RecvError::Closed), _)) => return,
+                    Either::Left((Err(e), _)) | Either::Right((Err(e), _)) => {
+                        tracing::warn!("Twins fused channel recv error: {e}");
+                        continue;
+                    }
                 };
 
                 let mut state = recv_state.write().await;
@@ -748,19 +1072,22 @@ where
 
         let _send_loop_handle = spawn(async move {
             loop {
-                if let Ok(msg) = receiver_from_network.recv().await {
-                    let mut state = send_state.write().await;
-
-                    let mut result = state.send_handler(&msg).await;
-
-                    while let Some(event) = result.pop() {
-                        match event {
-                            Either::Left(msg) => {
-                                let _ = left_sender.broadcast(msg.into()).await;
-                            }
-                            Either::Right(msg) => {
-                                let _ = right_sender.broadcast(msg.into()).await;
-                            }
+                let msg = match receiver_from_network.recv().await {
+                    Ok(msg) => msg,
+                    Err(RecvError::Closed) => return,
+                    Err(_) => continue,
+                };
+                let mut state = send_state.write().await;
+
+                let mut result = state.send_handler(&msg).await;
+
+                while let Some(event) = result.pop() {
+                    match event {
+                        Either::Left(msg) => {
+                            let _ = left_sender.broadcast(msg.into()).await;
+                        }
+                        Either::Right(msg) => {
+                            let _ = right_sender.broadcast(msg.into()).await;
                         }
                     }
                 }
@@ -819,10 +1146,14 @@ where
         .await;
 
         // create registries for both handles
-        let left_consensus_registry = ConsensusTaskRegistry::new();
+        let left_consensus_registry = ConsensusTaskRegistry::with_executor(TaskExecutor::new(
+            left_system_context.config.consensus_task_threads,
+        ));
         let left_network_registry = NetworkTaskRegistry::new();
 
-        let right_consensus_registry = ConsensusTaskRegistry::new();
+        let right_consensus_registry = ConsensusTaskRegistry::with_executor(TaskExecutor::new(
+            right_system_context.config.consensus_task_threads,
+        ));
         let right_network_registry = NetworkTaskRegistry::new();
 
         // create external channels for both handles
@@ -1010,6 +1341,10 @@ pub struct HotShotInitializer<TYPES: NodeType> {
     /// The view we last performed an action in.  An action is Proposing or voting for
     /// Either the quorum or DA.
     actioned_view: TYPES::View,
+    /// The view of the locked QC, so that a restarting node does not vote against its lock.
+    locked_view: TYPES::View,
+    /// The votes persisted as intended, so that a restarting node does not send conflicting ones.
+    vote_intents: Vec<VoteIntent<TYPES>>,
     /// Highest QC that was seen, for genesis it's the genesis QC.  It should be for a view greater
     /// than `inner`s view number for the non genesis case because we must have seen higher QCs
     /// to decide on the leaf.
@@ -1018,6 +1353,9 @@ pub struct HotShotInitializer<TYPES: NodeType> {
     next_epoch_high_qc: Option<NextEpochQuorumCertificate2<TYPES>>,
     /// Previously decided upgrade certificate; this is necessary if an upgrade has happened and we are not restarting with the new version
     decided_upgrade_certificate: Option<UpgradeCertificate<TYPES>>,
+    /// The latest checkpoint certificate, so that a restarting node keeps serving its checkpoint
+    /// and does not collect votes for checkpoints it already certified.
+    checkpoint_certificate: Option<CheckpointCertificate<TYPES>>,
     /// Undecided leaves that were seen, but not yet decided on.  These allow a restarting node
     /// to vote and propose right away if they didn't miss anything while down.
     undecided_leaves: Vec<Leaf2<TYPES>>,
@@ -1044,10 +1382,13 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
             start_view: TYPES::View::new(0),
             start_epoch: TYPES::Epoch::new(0),
             actioned_view: TYPES::View::new(0),
+            locked_view: TYPES::View::new(0),
+            vote_intents: Vec::new(),
             saved_proposals: BTreeMap::new(),
             high_qc,
             next_epoch_high_qc: None,
             decided_upgrade_certificate: None,
+            checkpoint_certificate: None,
             undecided_leaves: Vec::new(),
             undecided_state: BTreeMap::new(),
             instance_state,
@@ -1061,6 +1402,11 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
     ///     after restart.
     /// * `validated_state` - Optional validated state that if given, will be used to construct the
     ///     `SystemContext`.
+    /// * `locked_view` - The view of the locked QC, as persisted by
+    ///     [`Storage::update_locked_view`].
+    /// * `vote_intents` - The votes persisted by [`Storage::record_vote_intent`].
+    /// * `checkpoint_certificate` - The checkpoint certificate persisted by
+    ///     [`Storage::update_checkpoint_certificate`].
     #[allow(clippy::too_many_arguments)]
     pub fn from_reload(
         anchor_leaf: Leaf2<TYPES>,
@@ -1069,10 +1415,13 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
         start_view: TYPES::View,
         start_epoch: TYPES::Epoch,
         actioned_view: TYPES::View,
+        locked_view: TYPES::View,
+        vote_intents: Vec<VoteIntent<TYPES>>,
         saved_proposals: BTreeMap<TYPES::View, Proposal<TYPES, QuorumProposal2<TYPES>>>,
         high_qc: QuorumCertificate2<TYPES>,
         next_epoch_high_qc: Option<NextEpochQuorumCertificate2<TYPES>>,
         decided_upgrade_certificate: Option<UpgradeCertificate<TYPES>>,
+        checkpoint_certificate: Option<CheckpointCertificate<TYPES>>,
         undecided_leaves: Vec<Leaf2<TYPES>>,
         undecided_state: BTreeMap<TYPES::View, View<TYPES>>,
     ) -> Self {
@@ -1084,10 +1433,13 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
             start_view,
             start_epoch,
             actioned_view,
+            locked_view,
+            vote_intents,
             saved_proposals,
             high_qc,
             next_epoch_high_qc,
             decided_upgrade_certificate,
+            checkpoint_certificate,
             undecided_leaves,
             undecided_state,
         }
@@ -0,0 +1,302 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Log files which rotate by size and time, so long-running nodes need no external log plumbing.
+//!
+//! [`initialize_logging`](crate::helpers::initialize_logging) writes logs to a file as well as to
+//! stderr if `RUST_LOG_DIR` is set, see [`LogFileConfig::from_env`]. Logs go to `hotshot.log` in
+//! that directory. Once the file grows past [`LogFileConfig::max_file_bytes`], or a new
+//! [`Rotation`] period starts, it is rotated to `hotshot.1.log`, the previous `hotshot.1.log` to
+//! `hotshot.2.log`, and so on, keeping at most [`LogFileConfig::max_files`] files. With
+//! [`LogFileConfig::compress`], rotated files are gzipped to `hotshot.1.log.gz` and so on.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+/// When to start a new log file regardless of its size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    /// only rotate by size
+    Never,
+    /// at the start of every hour, UTC
+    Hourly,
+    /// at the start of every day, UTC
+    Daily,
+}
+
+impl Rotation {
+    /// The length of a rotation period in seconds, if files rotate by time
+    fn period_secs(self) -> Option<u64> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(60 * 60),
+            Self::Daily => Some(24 * 60 * 60),
+        }
+    }
+
+    /// The index of the rotation period we are in, if files rotate by time
+    fn current_period(self) -> Option<u64> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.period_secs().map(|period| now / period)
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            _ => Err(format!("Unknown log rotation `{s}`")),
+        }
+    }
+}
+
+/// Configuration of the log files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFileConfig {
+    /// The directory the log files are written to
+    pub dir: PathBuf,
+    /// The size at which the current log file is rotated
+    pub max_file_bytes: u64,
+    /// When the current log file is rotated regardless of its size
+    pub rotation: Rotation,
+    /// The number of log files to keep, including the current one
+    pub max_files: usize,
+    /// Whether rotated log files are gzipped
+    pub compress: bool,
+}
+
+impl LogFileConfig {
+    /// Log files in `dir`, rotated daily or at 64 MiB, keeping 8 uncompressed files
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_file_bytes: 64 << 20,
+            rotation: Rotation::Daily,
+            max_files: 8,
+            compress: false,
+        }
+    }
+
+    /// Rotate the current log file once it grows past `max_file_bytes`
+    #[must_use]
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Rotate the current log file at the start of every `rotation` period
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Keep at most `max_files` log files
+    #[must_use]
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Gzip rotated log files if `compress` is set
+    #[must_use]
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// The configuration given by the environment, if `RUST_LOG_DIR` is set
+    ///
+    /// `RUST_LOG_FILE_MAX_BYTES`, `RUST_LOG_FILE_ROTATION` (`never`, `hourly` or `daily`),
+    /// `RUST_LOG_FILE_MAX_FILES` and `RUST_LOG_FILE_COMPRESS` (`true` or `false`) override the
+    /// defaults of [`LogFileConfig::new`]. Values which do not parse are reported and ignored.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        /// The value of the environment variable `name`, if it is set and parses
+        fn var<T: FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            let parsed = value.parse().ok();
+            if parsed.is_none() {
+                eprintln!("Ignoring invalid {name}={value}");
+            }
+            parsed
+        }
+
+        let mut config = Self::new(std::env::var("RUST_LOG_DIR").ok()?);
+        if let Some(max_file_bytes) = var("RUST_LOG_FILE_MAX_BYTES") {
+            config.max_file_bytes = max_file_bytes;
+        }
+        if let Some(rotation) = var("RUST_LOG_FILE_ROTATION") {
+            config.rotation = rotation;
+        }
+        if let Some(max_files) = var("RUST_LOG_FILE_MAX_FILES") {
+            config.max_files = max_files;
+        }
+        if let Some(compress) = var("RUST_LOG_FILE_COMPRESS") {
+            config.compress = compress;
+        }
+        Some(config)
+    }
+}
+
+/// The path of the `index`th log file, where 0 is the current one
+fn log_file(dir: &Path, index: usize, compressed: bool) -> PathBuf {
+    match (index, compressed) {
+        (0, _) => dir.join("hotshot.log"),
+        (_, false) => dir.join(format!("hotshot.{index}.log")),
+        (_, true) => dir.join(format!("hotshot.{index}.log.gz")),
+    }
+}
+
+/// Writes logs to the current log file, rotating it as configured
+///
+/// Rotation, including compression, happens on the write which triggers it, so that write takes
+/// longer.
+#[derive(Debug)]
+pub struct RollingFileWriter {
+    /// the configuration of the log files
+    config: LogFileConfig,
+    /// the current log file
+    file: File,
+    /// the size of the current log file
+    file_bytes: u64,
+    /// the rotation period the current log file was started in
+    period: Option<u64>,
+}
+
+impl RollingFileWriter {
+    /// Open the log files described by `config`, appending to the current one
+    ///
+    /// # Errors
+    /// if the directory cannot be created or the current file cannot be opened
+    pub fn open(config: LogFileConfig) -> io::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+        let file =
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file(&config.dir, 0, false))?;
+        let file_bytes = file.metadata()?.len();
+        let period = config.rotation.current_period();
+        Ok(Self {
+            config,
+            file,
+            file_bytes,
+            period,
+        })
+    }
+
+    /// Shift every rotated log file one place back, dropping the oldest, and start a new current
+    /// file
+    fn rotate(&mut self) -> io::Result<()> {
+        let dir = &self.config.dir;
+        let compress = self.config.compress;
+        let oldest = self.config.max_files.max(1) - 1;
+        if oldest == 0 {
+            self.file.set_len(0)?;
+            self.file_bytes = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(log_file(dir, oldest, compress));
+        for index in (1..oldest).rev() {
+            let from = log_file(dir, index, compress);
+            if from.exists() {
+                std::fs::rename(from, log_file(dir, index + 1, compress))?;
+            }
+        }
+
+        self.file.flush()?;
+        let current = log_file(dir, 0, false);
+        if compress {
+            let mut encoder = GzEncoder::new(
+                File::create(log_file(dir, 1, true))?,
+                Compression::default(),
+            );
+            io::copy(&mut File::open(&current)?, &mut encoder)?;
+            encoder.finish()?;
+            std::fs::remove_file(&current)?;
+        } else {
+            std::fs::rename(&current, log_file(dir, 1, false))?;
+        }
+
+        self.file = OpenOptions::new().create(true).append(true).open(current)?;
+        self.file_bytes = 0;
+        Ok(())
+    }
+}
+
+impl Write for RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let period = self.config.rotation.current_period();
+        if self.file_bytes >= self.config.max_file_bytes || period != self.period {
+            self.period = period;
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.file_bytes += u64::try_from(written).unwrap_or(u64::MAX);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn log_files_rotate_by_size() {
+        let dir = std::env::temp_dir().join(format!("hotshot-log-file-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = LogFileConfig::new(&dir)
+            .with_max_file_bytes(100)
+            .with_rotation(Rotation::Never)
+            .with_max_files(3)
+            .with_compression(true);
+
+        let mut writer = RollingFileWriter::open(config).unwrap();
+        for line in 0..20 {
+            writer
+                .write_all(format!("log line number {line:04}\n").as_bytes())
+                .unwrap();
+        }
+
+        assert!(log_file(&dir, 2, true).exists());
+        assert!(!log_file(&dir, 3, true).exists());
+        let mut rotated = String::new();
+        GzDecoder::new(File::open(log_file(&dir, 1, true)).unwrap())
+            .read_to_string(&mut rotated)
+            .unwrap();
+        let current = std::fs::read_to_string(log_file(&dir, 0, false)).unwrap();
+        assert!(rotated.ends_with("0014\n"));
+        assert!(current.starts_with("log line number 0015"));
+        assert!(current.ends_with("0019\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A read-only HTTP API for explorers and monitoring dashboards.
+//!
+//! The routes are defined in `api/node.toml` and served under `/node`. They answer from what the
+//! node holds in memory, so only views the node still retains can be looked up. HotShot keeps no
+//! mempool of its own, since transactions are forwarded to builders, so there is no mempool size
+//! to report.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use async_lock::RwLock;
+use futures::FutureExt;
+use hotshot_types::{
+    data::Leaf2,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::HasViewNumber,
+};
+use serde::Serialize;
+use tide_disco::{api::ApiError, error::ServerError, Api, App, StatusCode, Url};
+use tokio::{spawn, task::JoinHandle};
+use vbs::version::{StaticVersion, StaticVersionType};
+
+use crate::{SystemContext, Versions};
+
+/// The version of the node API
+pub type NodeApiVersion = StaticVersion<0, 1>;
+
+/// The state the node API is served from
+type State<TYPES, I, V> = RwLock<Arc<SystemContext<TYPES, I, V>>>;
+
+/// The membership of an epoch
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub struct MembershipInfo<TYPES: NodeType> {
+    /// the epoch
+    pub epoch: TYPES::Epoch,
+    /// the stake table of the epoch
+    pub stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+    /// the DA stake table of the epoch
+    pub da_stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+}
+
+/// The error for a view this node does not retain
+fn not_retained<TYPES: NodeType>(what: &str, view: TYPES::View) -> ServerError {
+    ServerError {
+        status: StatusCode::NOT_FOUND,
+        message: format!("No {what} retained for view {}", *view),
+    }
+}
+
+/// The leaf proposed in `view`, if `context` still retains it
+async fn retained_leaf<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &SystemContext<TYPES, I, V>,
+    view: TYPES::View,
+) -> Option<Leaf2<TYPES>> {
+    let consensus = context.consensus();
+    let consensus = consensus.read().await;
+    consensus
+        .validated_state_map()
+        .get(&view)
+        .and_then(|view| view.view_inner.leaf_commitment())
+        .and_then(|commitment| consensus.saved_leaves().get(&commitment))
+        .cloned()
+}
+
+/// The QC formed in `view`, if `context` still retains it
+async fn retained_qc<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &SystemContext<TYPES, I, V>,
+    view: TYPES::View,
+) -> Option<QuorumCertificate2<TYPES>> {
+    let consensus = context.consensus();
+    let consensus = consensus.read().await;
+    if consensus.high_qc().view_number() == view {
+        return Some(consensus.high_qc().clone());
+    }
+    consensus
+        .saved_leaves()
+        .values()
+        .map(Leaf2::justify_qc)
+        .find(|qc| qc.view_number() == view)
+}
+
+/// Define the routes of the node API
+fn define_api<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+) -> Result<Api<State<TYPES, I, V>, ServerError, NodeApiVersion>, ApiError> {
+    let api_toml = toml::from_str::<toml::Value>(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/api/node.toml"
+    )))
+    .expect("API file is not valid toml");
+    let mut api = Api::<State<TYPES, I, V>, ServerError, NodeApiVersion>::new(api_toml)?;
+    api.get("anchor", |_req, context| {
+        async move { Ok(context.decided_leaf().await) }.boxed()
+    })?
+    .get("view", |req, context| {
+        async move {
+            let view = TYPES::View::new(req.integer_param("view")?);
+            retained_leaf(context, view)
+                .await
+                .ok_or_else(|| not_retained::<TYPES>("leaf", view))
+        }
+        .boxed()
+    })?
+    .get("view_qc", |req, context| {
+        async move {
+            let view = TYPES::View::new(req.integer_param("view")?);
+            retained_qc(context, view)
+                .await
+                .ok_or_else(|| not_retained::<TYPES>("QC", view))
+        }
+        .boxed()
+    })?
+    .get("membership", |_req, context| {
+        async move {
+            let epoch = context.consensus().read().await.cur_epoch();
+            let memberships = context.memberships.read().await;
+            Ok(MembershipInfo::<TYPES> {
+                epoch,
+                stake_table: memberships.stake_table(epoch),
+                da_stake_table: memberships.da_stake_table(epoch),
+            })
+        }
+        .boxed()
+    })?
+    .get("validator_metadata", |_req, context| {
+        async move { Ok(context.validator_metadata().await) }.boxed()
+    })?
+    .get("status", |_req, context| {
+        async move { Ok(context.health().await) }.boxed()
+    })?
+    .get("view_changes", |_req, context| {
+        async move { Ok(context.view_changes().await) }.boxed()
+    })?;
+
+    Ok(api)
+}
+
+/// Serve the node API of `context` at `address`, until the returned task is aborted
+pub(crate) fn serve_rest_api<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: Arc<SystemContext<TYPES, I, V>>,
+    address: SocketAddr,
+) -> JoinHandle<()> {
+    spawn(async move {
+        let api = match define_api::<TYPES, I, V>() {
+            Ok(api) => api,
+            Err(e) => {
+                tracing::error!("Failed to define the node API: {e}");
+                return;
+            }
+        };
+        let mut app = App::<State<TYPES, I, V>, ServerError>::with_state(RwLock::new(context));
+        if let Err(e) = app.register_module::<ServerError, NodeApiVersion>("node", api) {
+            tracing::error!("Failed to register the node API: {e}");
+            return;
+        }
+
+        let url: Url = format!("http://{address}")
+            .parse()
+            .expect("A socket address is a valid URL authority");
+        tracing::info!("Serving the node API on {url}");
+        if let Err(e) = app.serve(url, NodeApiVersion::instance()).await {
+            tracing::error!("Failed to serve the node API on {address}: {e}");
+        }
+    })
+}
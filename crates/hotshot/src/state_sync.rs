@@ -0,0 +1,603 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Snapshot-based state sync for nodes joining without any state.
+//!
+//! Rather than replaying the chain from genesis, a new node fetches the latest checkpoint
+//! certificate, downloads the snapshot of the state it certifies and checks the assembled state
+//! against the certified state digest. The resulting [`HotShotInitializer`] starts the node from
+//! the checkpointed leaf, with the QC for that leaf as its high QC, and the views decided since are
+//! caught up on the usual way once the node is running.
+//!
+//! Chunks are kept on disk as they arrive, so an interrupted sync resumes where it stopped instead
+//! of downloading the whole snapshot again.
+
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use committable::Committable;
+use hotshot_types::{
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_certificate::{CheckpointCertificate, QuorumCertificate2},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType, Versions},
+        state_sync::{split_snapshot, SnapshotManifest, SnapshotSource},
+        ValidatedState,
+    },
+    vote::{Certificate, HasViewNumber},
+};
+use serde::de::DeserializeOwned;
+use tokio::fs;
+use url::Url;
+
+use crate::HotShotInitializer;
+
+/// Number of progress events buffered for slow subscribers before the oldest are dropped
+const PROGRESS_CAPACITY: usize = 256;
+
+/// Progress of a state sync, for operators and dashboards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateSyncEvent {
+    /// A valid checkpoint certificate was found
+    CheckpointFound {
+        /// Height of the checkpointed block
+        height: u64,
+        /// Epoch of the checkpointed block
+        epoch: u64,
+    },
+    /// Chunks of this snapshot were already on disk from an interrupted sync
+    Resumed {
+        /// Height of the checkpointed block
+        height: u64,
+        /// Number of chunks which do not need to be downloaded again
+        chunks: usize,
+        /// Total number of chunks in the snapshot
+        total: usize,
+    },
+    /// A chunk of the snapshot was downloaded and checked against the manifest
+    ChunkDownloaded {
+        /// Height of the checkpointed block
+        height: u64,
+        /// Index of the chunk
+        index: usize,
+        /// Number of chunks on disk so far
+        downloaded: usize,
+        /// Total number of chunks in the snapshot
+        total: usize,
+    },
+    /// The assembled state matches the state digest of the checkpoint certificate
+    StateVerified {
+        /// Height of the checkpointed block
+        height: u64,
+    },
+    /// The node can start from the checkpoint, and catch up on the views decided since
+    Completed {
+        /// Height of the checkpointed block
+        height: u64,
+        /// View of the checkpointed leaf
+        view: u64,
+    },
+}
+
+/// Downloads the state at the latest checkpoint from a set of [`SnapshotSource`]s.
+pub struct StateSync<TYPES: NodeType, V: Versions> {
+    /// Where snapshots are downloaded from, tried in order
+    sources: Vec<Arc<dyn SnapshotSource<TYPES>>>,
+
+    /// Membership, whose stake tables checkpoint certificates are checked against
+    membership: Arc<RwLock<TYPES::Membership>>,
+
+    /// Lock for a decided upgrade, which determines how certificates are committed to
+    upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Chunks downloaded so far
+    store: SnapshotStore,
+
+    /// Progress of the sync
+    progress: Sender<StateSyncEvent>,
+
+    /// Keeps the progress channel open while nobody is subscribed
+    progress_receiver: InactiveReceiver<StateSyncEvent>,
+}
+
+impl<TYPES: NodeType, V: Versions> StateSync<TYPES, V> {
+    /// Create a state sync from `sources`, keeping partial downloads in `download_dir`.
+    ///
+    /// `membership` must know the stake table of the checkpoint's epoch.
+    pub fn new(
+        sources: Vec<Arc<dyn SnapshotSource<TYPES>>>,
+        membership: Arc<RwLock<TYPES::Membership>>,
+        upgrade_lock: UpgradeLock<TYPES, V>,
+        download_dir: impl Into<PathBuf>,
+    ) -> Self {
+        let (mut progress, progress_receiver) = broadcast(PROGRESS_CAPACITY);
+        progress.set_overflow(true);
+        progress.set_await_active(false);
+
+        Self {
+            sources,
+            membership,
+            upgrade_lock,
+            store: SnapshotStore {
+                dir: download_dir.into(),
+            },
+            progress,
+            progress_receiver: progress_receiver.deactivate(),
+        }
+    }
+
+    /// Subscribe to the progress of the sync.
+    pub fn progress(&self) -> Receiver<StateSyncEvent> {
+        self.progress_receiver.activate_cloned()
+    }
+
+    /// Report progress to subscribers, if there are any.
+    fn report(&self, event: StateSyncEvent) {
+        tracing::info!("State sync: {event:?}");
+        let _ = self.progress.try_broadcast(event);
+    }
+
+    /// Download and verify the state at the latest checkpoint, and build an initializer which
+    /// starts the node from it.
+    ///
+    /// # Errors
+    /// If no source has a valid checkpoint, or the snapshot could not be downloaded or does not
+    /// match the checkpoint. Chunks already downloaded are kept, so calling this again resumes.
+    pub async fn sync(
+        &self,
+        instance_state: TYPES::InstanceState,
+    ) -> Result<HotShotInitializer<TYPES>> {
+        let certificate = self.latest_checkpoint().await?;
+        let height = certificate.data.height;
+        self.report(StateSyncEvent::CheckpointFound {
+            height,
+            epoch: *certificate.data.epoch,
+        });
+
+        let leaf = self.fetch_leaf(&certificate).await?;
+        let high_qc = self.fetch_qc(&leaf).await?;
+        let state = self.download_state(&certificate).await?;
+        self.report(StateSyncEvent::StateVerified { height });
+
+        self.store.clear().await?;
+
+        let view = leaf.view_number();
+        let decided_upgrade_certificate = self
+            .upgrade_lock
+            .decided_upgrade_certificate
+            .read()
+            .await
+            .clone();
+        self.report(StateSyncEvent::Completed {
+            height,
+            view: *view,
+        });
+
+        Ok(HotShotInitializer::from_reload(
+            leaf,
+            instance_state,
+            Some(Arc::new(state)),
+            view,
+            certificate.data.epoch,
+            view,
+            view,
+            Vec::new(),
+            BTreeMap::new(),
+            high_qc,
+            None,
+            decided_upgrade_certificate,
+            Some(certificate),
+            Vec::new(),
+            BTreeMap::new(),
+        ))
+    }
+
+    /// Whether `certificate` is signed by a quorum of its epoch's stake table.
+    async fn is_valid_checkpoint(&self, certificate: &CheckpointCertificate<TYPES>) -> bool {
+        // Genesis certificates are accepted without signatures, so they prove nothing here
+        if certificate.view_number() == TYPES::View::genesis() {
+            return false;
+        }
+
+        let epoch = certificate.data.epoch;
+        let membership = self.membership.read().await;
+        let stake_table = membership.stake_table(epoch);
+        let threshold = membership.success_threshold(epoch);
+        drop(membership);
+
+        certificate
+            .is_valid_cert(stake_table, threshold, &self.upgrade_lock)
+            .await
+    }
+
+    /// The highest valid checkpoint certificate offered by any source.
+    async fn latest_checkpoint(&self) -> Result<CheckpointCertificate<TYPES>> {
+        let mut latest: Option<CheckpointCertificate<TYPES>> = None;
+
+        for source in &self.sources {
+            let certificate = match source.latest_checkpoint().await {
+                Ok(certificate) => certificate,
+                Err(e) => {
+                    tracing::warn!("Failed to fetch checkpoint certificate: {e:#}");
+                    continue;
+                }
+            };
+            if latest
+                .as_ref()
+                .is_some_and(|latest| latest.data.height >= certificate.data.height)
+            {
+                continue;
+            }
+            if !self.is_valid_checkpoint(&certificate).await {
+                tracing::warn!(
+                    "Ignoring invalid checkpoint certificate for height {}",
+                    certificate.data.height
+                );
+                continue;
+            }
+            latest = Some(certificate);
+        }
+
+        latest.context("No source offered a valid checkpoint certificate")
+    }
+
+    /// The leaf certified by `certificate`, from the first source which has it.
+    async fn fetch_leaf(&self, certificate: &CheckpointCertificate<TYPES>) -> Result<Leaf2<TYPES>> {
+        let height = certificate.data.height;
+
+        for source in &self.sources {
+            match source.fetch_leaf(height).await {
+                Ok(leaf) if leaf.commit() == certificate.data.leaf_commit => return Ok(leaf),
+                Ok(_) => tracing::warn!("Source returned the wrong leaf for checkpoint {height}"),
+                Err(e) => tracing::warn!("Failed to fetch leaf for checkpoint {height}: {e:#}"),
+            }
+        }
+
+        bail!("No source has the leaf for checkpoint {height}")
+    }
+
+    /// A valid QC for `leaf`, from the first source which has one.
+    ///
+    /// The node starts with it as its high QC, so that it extends the checkpointed leaf rather
+    /// than its parent.
+    async fn fetch_qc(&self, leaf: &Leaf2<TYPES>) -> Result<QuorumCertificate2<TYPES>> {
+        let height = leaf.height();
+
+        for source in &self.sources {
+            let qc = match source.fetch_qc(height).await {
+                Ok(qc) => qc,
+                Err(e) => {
+                    tracing::warn!("Failed to fetch QC for checkpoint {height}: {e:#}");
+                    continue;
+                }
+            };
+            if qc.data.leaf_commit != leaf.commit() || qc.view_number() != leaf.view_number() {
+                tracing::warn!("Source returned a QC for another leaf than checkpoint {height}");
+                continue;
+            }
+
+            let epoch = qc.data.epoch;
+            let membership = self.membership.read().await;
+            let stake_table = membership.stake_table(epoch);
+            let threshold = membership.success_threshold(epoch);
+            drop(membership);
+            if qc
+                .is_valid_cert(stake_table, threshold, &self.upgrade_lock)
+                .await
+            {
+                return Ok(qc);
+            }
+            tracing::warn!("Source returned an invalid QC for checkpoint {height}");
+        }
+
+        bail!("No source has a valid QC for checkpoint {height}")
+    }
+
+    /// The manifest of the snapshot certified by `certificate`, resuming from disk if possible.
+    async fn manifest(
+        &self,
+        certificate: &CheckpointCertificate<TYPES>,
+    ) -> Result<SnapshotManifest> {
+        let height = certificate.data.height;
+        let matches = |manifest: &SnapshotManifest| {
+            manifest.height == height && manifest.state_digest == certificate.data.state_digest
+        };
+
+        if let Some(manifest) = self.store.manifest().await {
+            if matches(&manifest) {
+                return Ok(manifest);
+            }
+        }
+
+        for source in &self.sources {
+            match source.fetch_manifest(height).await {
+                Ok(manifest) if matches(&manifest) => {
+                    self.store.reset(&manifest).await?;
+                    return Ok(manifest);
+                }
+                Ok(_) => {
+                    tracing::warn!("Source returned the wrong manifest for checkpoint {height}")
+                }
+                Err(e) => tracing::warn!("Failed to fetch manifest for checkpoint {height}: {e:#}"),
+            }
+        }
+
+        bail!("No source has a snapshot for checkpoint {height}")
+    }
+
+    /// Fetch the chunk at `index`, spreading chunks over the sources.
+    async fn fetch_chunk(&self, manifest: &SnapshotManifest, index: usize) -> Result<Vec<u8>> {
+        let height = manifest.height;
+
+        for offset in 0..self.sources.len() {
+            let source = &self.sources[(index + offset) % self.sources.len()];
+            match source.fetch_chunk(height, index).await {
+                Ok(chunk) if manifest.is_valid_chunk(index, &chunk) => return Ok(chunk),
+                Ok(_) => tracing::warn!("Source returned a corrupt chunk {index} of {height}"),
+                Err(e) => tracing::warn!("Failed to fetch chunk {index} of {height}: {e:#}"),
+            }
+        }
+
+        bail!("No source has chunk {index} of the snapshot for checkpoint {height}")
+    }
+
+    /// Download the snapshot certified by `certificate`, and check the state it decodes to.
+    async fn download_state(
+        &self,
+        certificate: &CheckpointCertificate<TYPES>,
+    ) -> Result<TYPES::ValidatedState> {
+        let manifest = self.manifest(certificate).await?;
+        let height = manifest.height;
+        let total = manifest.num_chunks();
+
+        let mut missing = Vec::new();
+        for index in 0..total {
+            if !self.store.has_chunk(&manifest, index).await {
+                missing.push(index);
+            }
+        }
+        let mut downloaded = total - missing.len();
+        if downloaded > 0 {
+            self.report(StateSyncEvent::Resumed {
+                height,
+                chunks: downloaded,
+                total,
+            });
+        }
+
+        for index in missing {
+            let chunk = self.fetch_chunk(&manifest, index).await?;
+            self.store.save_chunk(index, &chunk).await?;
+            downloaded += 1;
+            self.report(StateSyncEvent::ChunkDownloaded {
+                height,
+                index,
+                downloaded,
+                total,
+            });
+        }
+
+        let bytes = self.store.assemble(&manifest).await?;
+        let state = (bytes.len() as u64 == manifest.len)
+            .then(|| bincode::deserialize::<TYPES::ValidatedState>(&bytes).ok())
+            .flatten()
+            .filter(|state| state.state_digest() == certificate.data.state_digest);
+
+        match state {
+            Some(state) => Ok(state),
+            None => {
+                // Every chunk matched the manifest, so the manifest itself was bad
+                self.store.clear().await?;
+                bail!("Snapshot for checkpoint {height} does not match the certified state")
+            }
+        }
+    }
+}
+
+/// The chunks of a snapshot downloaded so far, kept on disk to resume from.
+struct SnapshotStore {
+    /// Directory holding the manifest and chunks
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Path of the manifest of the snapshot being downloaded
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join("manifest")
+    }
+
+    /// Path of the chunk at `index`
+    fn chunk_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("chunk-{index}"))
+    }
+
+    /// The manifest of the snapshot being downloaded, if any.
+    async fn manifest(&self) -> Option<SnapshotManifest> {
+        let bytes = fs::read(self.manifest_path()).await.ok()?;
+        bincode::deserialize(&bytes).ok()
+    }
+
+    /// Start downloading the snapshot described by `manifest`, dropping any other.
+    async fn reset(&self, manifest: &SnapshotManifest) -> Result<()> {
+        self.clear().await?;
+        fs::create_dir_all(&self.dir)
+            .await
+            .context("Failed to create snapshot download directory")?;
+        write_atomic(&self.manifest_path(), &bincode::serialize(manifest)?).await
+    }
+
+    /// Whether the chunk at `index` is on disk and intact.
+    async fn has_chunk(&self, manifest: &SnapshotManifest, index: usize) -> bool {
+        fs::read(self.chunk_path(index))
+            .await
+            .is_ok_and(|chunk| manifest.is_valid_chunk(index, &chunk))
+    }
+
+    /// Keep the chunk at `index`.
+    async fn save_chunk(&self, index: usize, chunk: &[u8]) -> Result<()> {
+        write_atomic(&self.chunk_path(index), chunk).await
+    }
+
+    /// Concatenate the chunks of the snapshot.
+    async fn assemble(&self, manifest: &SnapshotManifest) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        for index in 0..manifest.num_chunks() {
+            bytes.extend(
+                fs::read(self.chunk_path(index))
+                    .await
+                    .with_context(|| format!("Failed to read chunk {index}"))?,
+            );
+        }
+        Ok(bytes)
+    }
+
+    /// Remove everything downloaded.
+    async fn clear(&self) -> Result<()> {
+        match fs::remove_dir_all(&self.dir).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).context("Failed to clear snapshot download directory")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Write `bytes` to `path` so that a crash never leaves a partial file behind.
+async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to move {} into place", tmp.display()))
+}
+
+/// Write the snapshot of `state` at the checkpoint certified by `certificate` to `dir`, in the
+/// layout read by [`HttpSnapshotSource`].
+///
+/// The directory can be served by a peer or uploaded to an object storage bucket as is.
+///
+/// # Errors
+/// If `leaf` is not the certified leaf, `qc` is not for `leaf`, `state` does not match the
+/// certified state digest, or the snapshot cannot be written.
+pub async fn export_snapshot<TYPES: NodeType>(
+    dir: &Path,
+    certificate: &CheckpointCertificate<TYPES>,
+    leaf: &Leaf2<TYPES>,
+    qc: &QuorumCertificate2<TYPES>,
+    state: &TYPES::ValidatedState,
+    chunk_size: usize,
+) -> Result<SnapshotManifest> {
+    ensure!(
+        leaf.commit() == certificate.data.leaf_commit,
+        "Leaf is not the checkpointed leaf"
+    );
+    ensure!(
+        qc.data.leaf_commit == leaf.commit(),
+        "QC is not for the checkpointed leaf"
+    );
+    ensure!(
+        state.state_digest() == certificate.data.state_digest,
+        "State does not match the checkpointed state digest"
+    );
+
+    let height = certificate.data.height;
+    let (manifest, chunks) = split_snapshot::<TYPES>(state, height, chunk_size)?;
+
+    let snapshot_dir = dir.join(height.to_string());
+    fs::create_dir_all(&snapshot_dir)
+        .await
+        .context("Failed to create snapshot directory")?;
+    for (index, chunk) in chunks.iter().enumerate() {
+        write_atomic(&snapshot_dir.join(index.to_string()), chunk).await?;
+    }
+    write_atomic(&snapshot_dir.join("leaf"), &bincode::serialize(leaf)?).await?;
+    write_atomic(&snapshot_dir.join("qc"), &bincode::serialize(qc)?).await?;
+    write_atomic(
+        &snapshot_dir.join("manifest"),
+        &bincode::serialize(&manifest)?,
+    )
+    .await?;
+
+    // Written last, so that the advertised checkpoint is always complete
+    write_atomic(&dir.join("checkpoint"), &bincode::serialize(certificate)?).await?;
+
+    Ok(manifest)
+}
+
+/// Downloads snapshots over HTTP, from a peer or an object storage bucket holding the layout
+/// written by [`export_snapshot`].
+#[derive(Clone, Debug)]
+pub struct HttpSnapshotSource {
+    /// URL of the directory holding the snapshots; it must end with a `/`
+    base: Url,
+
+    /// Client used for every request
+    client: reqwest::Client,
+}
+
+impl HttpSnapshotSource {
+    /// Create a source serving the snapshots under `base`.
+    #[must_use]
+    pub fn new(base: Url) -> Self {
+        Self {
+            base,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch the object at `path`, relative to the base URL.
+    async fn get(&self, path: &str) -> Result<Vec<u8>> {
+        let url = self.base.join(path)?;
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to fetch {url}"))?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Fetch and decode the object at `path`, relative to the base URL.
+    async fn get_decoded<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(bincode::deserialize(&self.get(path).await?)?)
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> SnapshotSource<TYPES> for HttpSnapshotSource {
+    async fn latest_checkpoint(&self) -> Result<CheckpointCertificate<TYPES>> {
+        self.get_decoded("checkpoint").await
+    }
+
+    async fn fetch_leaf(&self, height: u64) -> Result<Leaf2<TYPES>> {
+        self.get_decoded(&format!("{height}/leaf")).await
+    }
+
+    async fn fetch_qc(&self, height: u64) -> Result<QuorumCertificate2<TYPES>> {
+        self.get_decoded(&format!("{height}/qc")).await
+    }
+
+    async fn fetch_manifest(&self, height: u64) -> Result<SnapshotManifest> {
+        self.get_decoded(&format!("{height}/manifest")).await
+    }
+
+    async fn fetch_chunk(&self, height: u64, index: usize) -> Result<Vec<u8>> {
+        self.get(&format!("{height}/{index}")).await
+    }
+}
@@ -8,21 +8,43 @@
 
 /// Provides trait to create task states from a `SystemContextHandle`
 pub mod task_state;
-use std::{collections::BTreeMap, fmt::Debug, num::NonZeroUsize, sync::Arc, time::Duration};
+/// Tells why the node changes view
+mod view_changes;
+/// Times the phases of each view
+mod view_timing;
+/// Detects stalled consensus
+mod watchdog;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    num::NonZeroUsize,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_broadcast::{broadcast, RecvError};
 use async_lock::RwLock;
 use async_trait::async_trait;
+use bytes::Bytes;
+use committable::Committable;
+use either::Either;
 use futures::{
-    future::{BoxFuture, FutureExt},
+    future::{self, BoxFuture, FutureExt},
     stream, StreamExt,
 };
-use hotshot_task::task::Task;
+use hotshot_task::{
+    executor::{TaskExecutor, TaskPriority},
+    task::Task,
+};
 #[cfg(feature = "rewind")]
 use hotshot_task_impls::rewind::RewindTaskState;
 use hotshot_task_impls::{
+    attestation::AttestationTaskState,
+    checkpoint::CheckpointTaskState,
     da::DaTaskState,
     events::HotShotEvent,
+    evidence::EvidenceTaskState,
+    helpers::broadcast_event,
     network::{NetworkEventTaskState, NetworkMessageTaskState},
     request::NetworkRequestState,
     response::{run_response_task, NetworkResponseState},
@@ -30,22 +52,42 @@ use hotshot_task_impls::{
     upgrade::UpgradeTaskState,
     vid::VidTaskState,
     view_sync::ViewSyncTaskState,
+    vote_verification::verify_votes,
 };
 use hotshot_types::{
     consensus::{Consensus, OuterConsensus},
-    constants::EVENT_CHANNEL_SIZE,
-    message::{Message, UpgradeLock},
+    constants::{
+        ARCHIVE_MAX_RANGE, ARCHIVE_RATE_LIMITED_PEERS, ARCHIVE_REQUESTS_PER_SECOND,
+        ARCHIVE_REQUEST_BURST, EVENT_CHANNEL_SIZE, VALIDATOR_METADATA_INTERVAL,
+        VOTE_VERIFICATION_CONCURRENCY,
+    },
+    data::{Leaf2, QuorumProposal2},
+    event::{Event, EventType},
+    journal::{JournalEntry, JournalRecord, JournalWriter},
+    memory_budget::MemoryComponent,
+    message::{SenderAuth, UpgradeLock},
+    rate_limit::RateLimiter,
+    trace_context::attach_to_view,
     traits::{
-        network::ConnectedNetwork,
+        block_contents::{BlockHeader, BlockPayload},
+        election::Membership,
+        network::{ConnectedNetwork, RequestKind},
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
+        storage::Storage,
     },
+    view_change::ViewChangeReason,
+    vote::{HasViewNumber, Vote},
 };
 use tokio::{spawn, time::sleep};
+use tracing::Instrument;
 use vbs::version::StaticVersionType;
 
+use self::{view_changes::ViewChangeTracker, view_timing::ViewTimer, watchdog::Watchdog};
 use crate::{
-    tasks::task_state::CreateTaskState, types::SystemContextHandle, ConsensusApi,
-    ConsensusMetricsValue, ConsensusTaskRegistry, HotShotConfig, HotShotInitializer,
+    helpers::set_log_filter,
+    tasks::task_state::CreateTaskState,
+    types::{now_ms, SystemContextHandle},
+    ConsensusApi, ConsensusMetricsValue, ConsensusTaskRegistry, HotShotConfig, HotShotInitializer,
     MarketplaceConfig, NetworkTaskRegistry, SignatureKey, SystemContext, Versions,
 };
 
@@ -117,6 +159,692 @@ pub fn add_queue_len_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Vers
     handle.network_registry.register(task_handle);
 }
 
+/// Add a task which records decided leaves in the finality log, from which finality streams are served
+pub fn add_finality_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let finality_log = Arc::clone(&handle.hotshot.finality_log);
+    let mut event_stream = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = event_stream.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    if let EventType::Decide { leaf_chain, qc, .. } = event.event {
+                        finality_log.write().await.record(&leaf_chain, &qc);
+                    }
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task which serves ranges of the finalized leaves of an archival node to nodes catching
+/// up, at most [`ARCHIVE_REQUESTS_PER_SECOND`] requests per second to each
+pub fn add_archive_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let finality_log = Arc::clone(&handle.hotshot.finality_log);
+    let public_key = handle.hotshot.public_key.clone();
+    let internal_event_sender = handle.internal_event_stream.0.clone();
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        let mut rate_limiter = RateLimiter::new(
+            ARCHIVE_REQUESTS_PER_SECOND,
+            ARCHIVE_REQUEST_BURST,
+            ARCHIVE_RATE_LIMITED_PEERS,
+        );
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    let HotShotEvent::LeafRangeRequestRecv(request, sender) = event.as_ref() else {
+                        continue;
+                    };
+                    let RequestKind::LeafRange(from_height, count) = request.request else {
+                        continue;
+                    };
+                    if !sender.validate(&request.signature, request.request.commit().as_ref()) {
+                        tracing::warn!("Invalid signature on the leaf range request of {sender}");
+                        continue;
+                    }
+                    if !rate_limiter.allow(sender, Instant::now()) {
+                        tracing::debug!("Rate limiting the leaf range requests of {sender}");
+                        continue;
+                    }
+                    #[allow(clippy::cast_possible_truncation)]
+                    let count = count.min(ARCHIVE_MAX_RANGE) as usize;
+                    let leaves = finality_log
+                        .read()
+                        .await
+                        .range(from_height, count)
+                        .into_iter()
+                        .map(|(leaf, qc, _)| (leaf, qc))
+                        .collect();
+                    broadcast_event(
+                        Arc::new(HotShotEvent::LeafRangeResponseSend(
+                            public_key.clone(),
+                            sender.clone(),
+                            leaves,
+                        )),
+                        &internal_event_sender,
+                    )
+                    .await;
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task resolving the receipts of submitted transactions as they are decided or expire
+pub fn add_transaction_receipt_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let pending_transactions = Arc::clone(&handle.hotshot.pending_transactions);
+    let mut event_stream = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    pending_transactions.write().await.clear();
+                    return;
+                },
+                event = event_stream.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    let EventType::Decide { leaf_chain, .. } = event.event else {
+                        continue;
+                    };
+                    let mut pending = pending_transactions.write().await;
+                    for info in leaf_chain.iter() {
+                        let Some(payload) = info.leaf.block_payload() else {
+                            continue;
+                        };
+                        let header = info.leaf.block_header();
+                        for commitment in payload.transaction_commitments(header.metadata()) {
+                            pending.include(
+                                &commitment,
+                                *info.leaf.view_number(),
+                                header.block_number(),
+                            );
+                        }
+                    }
+                    if let Some(info) = leaf_chain.first() {
+                        pending.expire(*info.leaf.view_number());
+                    }
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task which tracks the progress of this node and of the network around it, for health
+/// probes
+pub fn add_health_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let health = Arc::clone(&handle.hotshot.health);
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let mut external_events = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    // Only validated proposals and certificates tell us how far the network has
+                    // got, as any single node could claim a view far ahead
+                    if matches!(
+                        event.as_ref(),
+                        HotShotEvent::QuorumProposalPreliminarilyValidated(_)
+                            | HotShotEvent::QuorumProposalValidated(..)
+                            | HotShotEvent::DaCertificateValidated(_)
+                            | HotShotEvent::Qc2Formed(_)
+                    ) {
+                        if let Some(view) = event.view_number() {
+                            health.record_network_view(*view);
+                        }
+                    }
+                },
+                event = external_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    if let EventType::Decide { .. } = event.event {
+                        health.record_decide();
+                    }
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task recording why this node changes view, in its view change log, its storage and its
+/// metrics
+pub fn add_view_change_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let mut tracker = ViewChangeTracker::new(*handle.hotshot.start_view);
+    let view_changes = Arc::clone(&handle.hotshot.view_changes);
+    let storage = Arc::clone(&handle.storage);
+    let metrics = Arc::clone(&handle.hotshot.metrics);
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            let event = futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    event
+                }
+            };
+
+            let record = match event.as_ref() {
+                HotShotEvent::QuorumProposalRecv(proposal, _) => {
+                    tracker.on_proposal(
+                        *proposal.data.view_number(),
+                        *proposal.data.justify_qc.view_number(),
+                    );
+                    continue;
+                }
+                HotShotEvent::Timeout(view, _) => {
+                    tracker.on_timeout(**view);
+                    continue;
+                }
+                HotShotEvent::ViewSyncFinalizeCertificateRecv(cert)
+                | HotShotEvent::ViewSyncFinalizeCertificateSend(cert, _) => {
+                    tracker.on_view_sync(*cert.view_number());
+                    continue;
+                }
+                HotShotEvent::ViewChange(view, epoch) => {
+                    match tracker.on_view_change(**view, **epoch, now_ms()) {
+                        Some(record) => record,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            tracing::debug!(
+                "Left view {} for view {}: {}",
+                record.from_view,
+                record.to_view,
+                record.reason
+            );
+            match record.reason {
+                ViewChangeReason::QuorumCertificate => {
+                    metrics.number_of_view_changes_by_qc.add(1);
+                }
+                ViewChangeReason::Timeout => metrics.number_of_view_changes_by_timeout.add(1),
+                ViewChangeReason::ViewSync => metrics.number_of_view_changes_by_view_sync.add(1),
+                ViewChangeReason::LeaderOffline => {
+                    metrics.number_of_view_changes_by_leader_offline.add(1);
+                }
+            }
+            if let Err(e) = storage.read().await.append_view_change(&record).await {
+                tracing::warn!(
+                    "Failed to store the view change to view {}: {e:#}",
+                    record.to_view
+                );
+            }
+            view_changes.write().await.record(record);
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task keeping the identities validators with stake gossip, and gossiping the identity of
+/// this node again every [`VALIDATOR_METADATA_INTERVAL`]
+pub fn add_validator_metadata_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let hotshot = Arc::clone(&handle.hotshot);
+    let storage = Arc::clone(&handle.storage);
+    let internal_event_sender = handle.internal_event_stream.0.clone();
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        let mut interval = tokio::time::interval(VALIDATOR_METADATA_INTERVAL);
+        // The first tick completes immediately, before this node can have published anything
+        interval.tick().await;
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    let HotShotEvent::ValidatorMetadataRecv(record) = event.as_ref() else {
+                        continue;
+                    };
+                    let epoch = hotshot.consensus().read().await.cur_epoch();
+                    if !hotshot
+                        .memberships
+                        .read()
+                        .await
+                        .has_stake(&record.public_key, epoch)
+                    {
+                        tracing::debug!(
+                            "Ignoring the metadata of {}, which has no stake",
+                            record.public_key
+                        );
+                        continue;
+                    }
+                    if !hotshot.validator_metadata.write().await.insert(record.clone()) {
+                        continue;
+                    }
+                    if let Err(e) = storage.read().await.update_validator_metadata(record).await {
+                        tracing::warn!(
+                            "Failed to store the metadata of {}: {e:#}",
+                            record.public_key
+                        );
+                    }
+                },
+                _ = interval.tick().fuse() => {
+                    let own = hotshot
+                        .validator_metadata
+                        .read()
+                        .await
+                        .get(&hotshot.public_key)
+                        .cloned();
+                    if let Some(record) = own {
+                        broadcast_event(
+                            Arc::new(HotShotEvent::ValidatorMetadataSend(record)),
+                            &internal_event_sender,
+                        )
+                        .await;
+                    }
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task timing the phases of each view, and recording them as histograms
+pub fn add_view_timing_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let mut timer = ViewTimer::<TYPES>::new(Arc::clone(&handle.hotshot.metrics));
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let mut external_events = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    timer.observe(&event, Instant::now());
+                },
+                event = external_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    if let EventType::Decide { leaf_chain, .. } = event.event {
+                        let now = Instant::now();
+                        for info in leaf_chain.iter().rev() {
+                            timer.observe_decide(info.leaf.view_number(), now);
+                        }
+                    }
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// How often the watchdog checks for stalls
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Add a task raising [`EventType::Alert`]s when consensus stalls, if the config asks for it
+pub fn add_watchdog_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let Some(config) = handle.hotshot.config.watchdog.clone() else {
+        return;
+    };
+    let mut watchdog = Watchdog::new(config, Instant::now());
+    let hotshot = Arc::clone(&handle.hotshot);
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let mut external_events = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+        loop {
+            let alerts = futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    match event.as_ref() {
+                        HotShotEvent::Timeout(view, _) => watchdog.on_timeout(**view).into_iter().collect(),
+                        HotShotEvent::QuorumProposalValidated(proposal, _) => {
+                            watchdog.on_progress();
+                            watchdog.on_certificate(*proposal.data.justify_qc.view_number);
+                            vec![]
+                        }
+                        // Only certificates tell us how far the network has got, as any single
+                        // node could claim a view far ahead
+                        HotShotEvent::DaCertificateValidated(_) | HotShotEvent::Qc2Formed(_) => {
+                            if let Some(view) = event.view_number() {
+                                watchdog.on_certificate(*view);
+                            }
+                            vec![]
+                        }
+                        _ => vec![],
+                    }
+                },
+                event = external_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    if let EventType::Decide { leaf_chain, .. } = &event.event {
+                        if let Some(info) = leaf_chain.first() {
+                            watchdog.on_decide(*info.leaf.view_number(), Instant::now());
+                        }
+                    }
+                    vec![]
+                },
+                _ = interval.tick().fuse() => {
+                    let view = *hotshot.consensus().read().await.cur_view();
+                    watchdog.check(view, Instant::now())
+                }
+            };
+
+            for alert in alerts {
+                tracing::warn!("Watchdog alert: {alert:?}");
+                let view = *hotshot.consensus().read().await.cur_view();
+                broadcast_event(
+                    Event {
+                        view_number: TYPES::View::new(view),
+                        event: EventType::Alert { alert },
+                    },
+                    &hotshot.external_event_stream.0,
+                )
+                .await;
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// Add a task applying the parameter changes queued by [`SystemContext::update_config`] whenever
+/// the node changes view
+pub fn add_reconfiguration_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let pending_config = Arc::clone(&handle.hotshot.pending_config);
+    let internal_sender = handle.internal_event_stream.0.clone();
+    let external_sender = handle.hotshot.external_event_stream.0.clone();
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            let view = futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    let HotShotEvent::ViewChange(view, _) = event.as_ref() else {
+                        continue;
+                    };
+                    *view
+                }
+            };
+
+            let update = std::mem::take(&mut *pending_config.write().await);
+            if update.is_empty() {
+                continue;
+            }
+            tracing::warn!("Applying config update from view {view:?}: {update:?}");
+            if let Some(directives) = &update.log_filter {
+                if let Err(e) = set_log_filter(directives) {
+                    tracing::error!("Failed to apply the log filter of a config update: {e:#}");
+                }
+            }
+            broadcast_event(
+                Arc::new(HotShotEvent::ConfigUpdated(update.clone())),
+                &internal_sender,
+            )
+            .await;
+            broadcast_event(
+                Event {
+                    view_number: view,
+                    event: EventType::ConfigUpdated { update },
+                },
+                &external_sender,
+            )
+            .await;
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
+/// The journal record of an internal event, if it is a consensus decision
+fn journal_record<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> Option<JournalRecord> {
+    let proposal_entry = |proposal: &QuorumProposal2<TYPES>| {
+        (
+            Leaf2::from_quorum_proposal(proposal).commit().to_string(),
+            proposal.block_header.block_number(),
+        )
+    };
+    let (view, entry) = match event {
+        HotShotEvent::QuorumProposalSend(proposal, _) => {
+            let (leaf_commit, height) = proposal_entry(&proposal.data);
+            (
+                proposal.data.view_number,
+                JournalEntry::ProposalSent {
+                    leaf_commit,
+                    height,
+                },
+            )
+        }
+        HotShotEvent::QuorumProposalRecv(proposal, sender) => {
+            let (leaf_commit, height) = proposal_entry(&proposal.data);
+            (
+                proposal.data.view_number,
+                JournalEntry::ProposalReceived {
+                    sender: sender.to_string(),
+                    leaf_commit,
+                    height,
+                },
+            )
+        }
+        HotShotEvent::QuorumVoteSend(vote) | HotShotEvent::ExtendedQuorumVoteSend(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteSent {
+                kind: "quorum".to_string(),
+            },
+        ),
+        HotShotEvent::DaVoteSend(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteSent {
+                kind: "da".to_string(),
+            },
+        ),
+        HotShotEvent::TimeoutVoteSend(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteSent {
+                kind: "timeout".to_string(),
+            },
+        ),
+        HotShotEvent::QuorumVoteRecv(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteReceived {
+                kind: "quorum".to_string(),
+                voter: vote.signing_key().to_string(),
+            },
+        ),
+        HotShotEvent::DaVoteRecv(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteReceived {
+                kind: "da".to_string(),
+                voter: vote.signing_key().to_string(),
+            },
+        ),
+        HotShotEvent::TimeoutVoteRecv(vote) => (
+            vote.view_number(),
+            JournalEntry::VoteReceived {
+                kind: "timeout".to_string(),
+                voter: vote.signing_key().to_string(),
+            },
+        ),
+        HotShotEvent::Qc2Formed(Either::Left(qc)) => (
+            qc.view_number(),
+            JournalEntry::CertificateFormed {
+                kind: "quorum".to_string(),
+            },
+        ),
+        HotShotEvent::Qc2Formed(Either::Right(tc)) => (
+            tc.view_number(),
+            JournalEntry::CertificateFormed {
+                kind: "timeout".to_string(),
+            },
+        ),
+        HotShotEvent::DacSend(cert, _) => (
+            cert.view_number(),
+            JournalEntry::CertificateFormed {
+                kind: "da".to_string(),
+            },
+        ),
+        HotShotEvent::Timeout(view, _) => (*view, JournalEntry::ViewTimeout),
+        _ => return None,
+    };
+    Some(JournalRecord::now(*view, entry))
+}
+
+/// Add a task which appends the consensus decisions of this node to its journal, if it keeps one
+///
+/// The journal is written on a dedicated thread, so that writes and file rotation do not block
+/// the async runtime.
+pub fn add_journal_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &mut SystemContextHandle<TYPES, I, V>,
+) {
+    let Some(config) = handle.hotshot.config.journal.clone() else {
+        return;
+    };
+    let mut writer = match JournalWriter::open(config) {
+        Ok(writer) => writer,
+        Err(e) => {
+            tracing::error!("Failed to open the consensus journal: {e}");
+            return;
+        }
+    };
+    let (records_tx, mut records_rx) =
+        tokio::sync::mpsc::channel::<JournalRecord>(EVENT_CHANNEL_SIZE);
+    if let Err(e) = std::thread::Builder::new()
+        .name("consensus-journal".to_string())
+        .spawn(move || {
+            // Runs until the task below drops the sender
+            while let Some(record) = records_rx.blocking_recv() {
+                if let Err(e) = writer.append(&record) {
+                    tracing::error!("Failed to write to the consensus journal: {e}");
+                }
+            }
+        })
+    {
+        tracing::error!("Failed to start the consensus journal writer: {e}");
+        return;
+    }
+    let mut internal_events = handle.internal_event_stream.1.activate_cloned();
+    let mut external_events = handle.hotshot.external_event_stream.1.activate_cloned();
+    let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
+    let task_handle = spawn(async move {
+        futures::pin_mut!(shutdown_signal);
+        loop {
+            let records = futures::select! {
+                () = shutdown_signal => {
+                    return;
+                },
+                event = internal_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    journal_record(&event).into_iter().collect()
+                },
+                event = external_events.next().fuse() => {
+                    let Some(event) = event else {
+                        return;
+                    };
+                    match event.event {
+                        EventType::Decide { leaf_chain, .. } => leaf_chain
+                            .iter()
+                            .rev()
+                            .map(|info| {
+                                JournalRecord::now(
+                                    *info.leaf.view_number(),
+                                    JournalEntry::Decided {
+                                        leaf_commit: info.leaf.commit().to_string(),
+                                        height: info.leaf.height(),
+                                    },
+                                )
+                            })
+                            .collect(),
+                        _ => vec![],
+                    }
+                }
+            };
+            for record in records {
+                if records_tx.send(record).await.is_err() {
+                    tracing::error!("The consensus journal writer stopped");
+                    return;
+                }
+            }
+        }
+    });
+    handle.network_registry.register(task_handle);
+}
+
 /// Add the network task to handle messages and publish events.
 #[allow(clippy::missing_panics_doc)]
 pub fn add_network_message_task<
@@ -133,15 +861,114 @@ pub fn add_network_message_task<
         external_event_stream: handle.output_event_stream.0.clone(),
         public_key: handle.public_key().clone(),
         transactions_cache: lru::LruCache::new(NonZeroUsize::new(100_000).unwrap()),
+        namespace: handle.hotshot.config.namespace,
     };
 
     let upgrade_lock = handle.hotshot.upgrade_lock.clone();
+    let wire_encodings = handle.hotshot.wire_encodings.clone();
+    let verified_votes = handle.hotshot.verified_votes.clone();
+    let memory_budget = handle.hotshot.memory_budget.clone();
+    let consensus = handle.consensus();
+    let metrics = Arc::clone(&handle.hotshot.metrics);
+    let namespace = handle.hotshot.config.namespace;
 
     let network = Arc::clone(channel);
     let mut state = network_state.clone();
     let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
     let task_handle = spawn(async move {
-        futures::pin_mut!(shutdown_signal);
+        // Deserialize the messages and check the signatures of the votes among them, several at
+        // a time, but handle them in the order they were received
+        let messages = stream::unfold(
+            (network, memory_budget.clone()),
+            |(network, memory_budget)| async move {
+                // Leave messages queued in the network while those received use up their budget
+                memory_budget
+                    .wait_for_room(MemoryComponent::NetworkQueue)
+                    .await;
+                let message = network.recv_message().await;
+                if let Ok(message) = &message {
+                    memory_budget.reserve(MemoryComponent::NetworkQueue, message.len());
+                }
+                Some((message, (network, memory_budget)))
+            },
+        )
+        .map(|message| {
+            let upgrade_lock = upgrade_lock.clone();
+            let wire_encodings = wire_encodings.clone();
+            let verified_votes = verified_votes.clone();
+            let memory_budget = memory_budget.clone();
+            let consensus = Arc::clone(&consensus);
+            let metrics = Arc::clone(&metrics);
+            let size = message.as_ref().map_or(0, Bytes::len);
+            let message = async move {
+                // Make sure the message did not fail
+                let message = match message {
+                    Ok(message) => message,
+                    Err(e) => {
+                        tracing::error!("Failed to receive message: {:?}", e);
+                        return None;
+                    }
+                };
+
+                // Deserialize the message and check that its sender signed it, before anything
+                // else is done with it
+                let (mut message, encoding, sender_auth) =
+                    match upgrade_lock.deserialize_signed(message).await {
+                        Ok(message) => message,
+                        Err(e) => {
+                            tracing::error!("Failed to deserialize message: {:?}", e);
+                            return None;
+                        }
+                    };
+                // The versions from before epochs do not send the instance a message belongs to,
+                // so their messages are taken to be of ours
+                if sender_auth == SenderAuth::Unsigned {
+                    message.namespace = namespace;
+                }
+
+                // Drop the message if it is too old to be of use, before checking its votes
+                let current_view = consensus.read().await.cur_view();
+                if message.is_expired(current_view) {
+                    tracing::debug!(
+                        "Dropping message which expired after view {:?}, we are in view {:?}",
+                        message.expires_after,
+                        current_view
+                    );
+                    metrics.expired_messages.add(1);
+                    return None;
+                }
+
+                if !verify_votes(
+                    &message,
+                    current_view,
+                    &upgrade_lock,
+                    &verified_votes,
+                    sender_auth,
+                )
+                .await
+                {
+                    return None;
+                }
+
+                // Remember which encoding the sender wants, unless the message is authenticated
+                // only by its vote, whose signature does not cover the encoding
+                if sender_auth != SenderAuth::Vote {
+                    wire_encodings.record(&message.sender, encoding).await;
+                }
+                Some(message)
+            };
+            async move {
+                let message = message.await;
+                if message.is_none() {
+                    memory_budget.release(MemoryComponent::NetworkQueue, size);
+                }
+                message.map(|message| (message, size))
+            }
+        })
+        .buffered(VOTE_VERIFICATION_CONCURRENCY)
+        .filter_map(future::ready)
+        .fuse();
+        futures::pin_mut!(shutdown_signal, messages);
 
         loop {
             // Wait for one of the following to resolve:
@@ -153,27 +980,17 @@ pub fn add_network_message_task<
                 }
 
                 // Wait for a message from the network
-                message = network.recv_message().fuse() => {
-                    // Make sure the message did not fail
-                    let message = match message {
-                        Ok(message) => message,
-                        Err(e) => {
-                            tracing::error!("Failed to receive message: {:?}", e);
-                            continue;
-                        }
-                    };
-
-                    // Deserialize the message
-                    let deserialized_message: Message<TYPES> = match upgrade_lock.deserialize(&message).await {
-                        Ok(message) => message,
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize message: {:?}", e);
-                            continue;
-                        }
+                message = messages.next() => {
+                    let Some((message, size)) = message else {
+                        return;
                     };
 
-                    // Handle the message
-                    state.handle_message(deserialized_message).await;
+                    // Handle the message, as part of the trace of its view
+                    let view = *message.kind.view_number();
+                    let span = tracing::info_span!("receive_message", view);
+                    attach_to_view(&span, message.namespace, view);
+                    state.handle_message(message).instrument(span).await;
+                    memory_budget.release(MemoryComponent::NetworkQueue, size);
                 }
             }
         }
@@ -201,6 +1018,11 @@ pub fn add_network_event_task<
         consensus: OuterConsensus::new(handle.consensus()),
         upgrade_lock: handle.hotshot.upgrade_lock.clone(),
         transmit_tasks: BTreeMap::new(),
+        paused: Arc::clone(&handle.hotshot.paused),
+        namespace: handle.hotshot.config.namespace,
+        wire_encodings: handle.hotshot.wire_encodings.clone(),
+        private_key: handle.private_key().clone(),
+        message_ttl: handle.hotshot.config.message_ttl,
     };
     let task = Task::new(
         network_state,
@@ -214,10 +1036,19 @@ pub fn add_network_event_task<
 pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
     handle: &mut SystemContextHandle<TYPES, I, V>,
 ) {
-    handle.add_task(ViewSyncTaskState::<TYPES, V>::create_from(handle).await);
-    handle.add_task(VidTaskState::<TYPES, I>::create_from(handle).await);
-    handle.add_task(DaTaskState::<TYPES, I, V>::create_from(handle).await);
-    handle.add_task(TransactionTaskState::<TYPES, I, V>::create_from(handle).await);
+    // A watcher is never a leader or a DA committee member, so it does without their tasks.
+    // Being paused for good, it sends none of the votes its other tasks produce.
+    let watcher = handle.hotshot.config.watcher;
+
+    handle.add_task_with_priority(
+        ViewSyncTaskState::<TYPES, V>::create_from(handle).await,
+        TaskPriority::Critical,
+    );
+    if !watcher {
+        handle.add_task(VidTaskState::<TYPES, I>::create_from(handle).await);
+        handle.add_task(DaTaskState::<TYPES, I, V>::create_from(handle).await);
+        handle.add_task(TransactionTaskState::<TYPES, I, V>::create_from(handle).await);
+    }
 
     {
         let mut upgrade_certificate_lock = handle
@@ -247,12 +1078,46 @@ pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>,
             quorum_proposal_recv::QuorumProposalRecvTaskState, quorum_vote::QuorumVoteTaskState,
         };
 
-        handle.add_task(QuorumProposalTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(QuorumVoteTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(QuorumProposalRecvTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(ConsensusTaskState::<TYPES, I, V>::create_from(handle).await);
+        // These tasks are on the critical path of every view, so they run ahead of the rest
+        if !watcher {
+            handle.add_task_with_priority(
+                QuorumProposalTaskState::<TYPES, I, V>::create_from(handle).await,
+                TaskPriority::Critical,
+            );
+        }
+        handle.add_task_with_priority(
+            QuorumVoteTaskState::<TYPES, I, V>::create_from(handle).await,
+            TaskPriority::Critical,
+        );
+        handle.add_task_with_priority(
+            QuorumProposalRecvTaskState::<TYPES, I, V>::create_from(handle).await,
+            TaskPriority::Critical,
+        );
+        handle.add_task_with_priority(
+            ConsensusTaskState::<TYPES, I, V>::create_from(handle).await,
+            TaskPriority::Critical,
+        );
+    }
+    handle.add_task(EvidenceTaskState::<TYPES, I, V>::create_from(handle).await);
+    if handle.hotshot.config.checkpoint_interval != 0 {
+        handle.add_task(CheckpointTaskState::<TYPES, I, V>::create_from(handle).await);
+    }
+    if handle.hotshot.config.attestation_window != 0 {
+        handle.add_task(AttestationTaskState::<TYPES, I, V>::create_from(handle).await);
     }
     add_queue_len_task(handle);
+    add_finality_task(handle);
+    if handle.hotshot.config.archival {
+        add_archive_task(handle);
+    }
+    add_transaction_receipt_task(handle);
+    add_journal_task(handle);
+    add_health_task(handle);
+    add_view_change_task(handle);
+    add_validator_metadata_task(handle);
+    add_view_timing_task(handle);
+    add_watchdog_task(handle);
+    add_reconfiguration_task(handle);
     #[cfg(feature = "rewind")]
     handle.add_task(RewindTaskState::<TYPES>::create_from(&handle).await);
 }
@@ -260,7 +1125,8 @@ pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>,
 /// Creates a monitor for shutdown events.
 ///
 /// # Returns
-/// A `BoxFuture<'static, ()>` that resolves when a `HotShotEvent::Shutdown` is detected.
+/// A `BoxFuture<'static, ()>` that resolves when a `HotShotEvent::Shutdown` is detected, or the
+/// network task registry of `handle` is shut down.
 ///
 /// # Usage
 /// Use in `select!` macros or similar constructs for graceful shutdowns:
@@ -270,9 +1136,10 @@ pub fn create_shutdown_event_monitor<TYPES: NodeType, I: NodeImplementation<TYPE
 ) -> BoxFuture<'static, ()> {
     // Activate the cloned internal event stream
     let mut event_stream = handle.internal_event_stream.1.activate_cloned();
+    let cancel = handle.network_registry.cancellation_token();
 
     // Create a future that completes when the `HotShotEvent::Shutdown` is received
-    async move {
+    let shutdown_event = async move {
         loop {
             match event_stream.recv_direct().await {
                 Ok(event) => {
@@ -288,6 +1155,13 @@ pub fn create_shutdown_event_monitor<TYPES: NodeType, I: NodeImplementation<TYPE
                 }
             }
         }
+    };
+
+    async move {
+        tokio::select! {
+            () = shutdown_event => {}
+            () = cancel.cancelled() => {}
+        }
     }
     .boxed()
 }
@@ -342,7 +1216,9 @@ where
             marketplace_config,
         )
         .await;
-        let consensus_registry = ConsensusTaskRegistry::new();
+        let consensus_registry = ConsensusTaskRegistry::with_executor(TaskExecutor::new(
+            hotshot.config.consensus_task_threads,
+        ));
         let network_registry = NetworkTaskRegistry::new();
 
         let output_event_stream = hotshot.external_event_stream.clone();
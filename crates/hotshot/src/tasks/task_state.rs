@@ -5,16 +5,19 @@
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{atomic::AtomicBool, Arc},
 };
 
 use async_trait::async_trait;
 use chrono::Utc;
 use hotshot_task_impls::{
+    attestation::{AttestationTaskState, ATTESTATION_NONCES},
     builder::BuilderClient,
+    checkpoint::CheckpointTaskState,
     consensus::ConsensusTaskState,
     da::DaTaskState,
+    evidence::EvidenceTaskState,
     quorum_proposal::QuorumProposalTaskState,
     quorum_proposal_recv::QuorumProposalRecvTaskState,
     quorum_vote::{drb_computations::DrbComputations, QuorumVoteTaskState},
@@ -26,6 +29,7 @@ use hotshot_task_impls::{
     view_sync::ViewSyncTaskState,
 };
 use hotshot_types::{
+    attestation::AttestationNonces,
     consensus::OuterConsensus,
     traits::{
         consensus_api::ConsensusApi,
@@ -92,6 +96,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             start_voting_time: handle.hotshot.config.start_voting_time,
             stop_voting_time: handle.hotshot.config.stop_voting_time,
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
         };
 
         #[cfg(feature = "example-upgrade")]
@@ -114,6 +119,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             start_voting_time: 0,
             stop_voting_time: u64::MAX,
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
         };
     }
 }
@@ -155,6 +161,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             id: handle.hotshot.id,
             storage: Arc::clone(&handle.storage),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
+            chunked_dispersal: handle.hotshot.config.da_chunked_dispersal,
+            payload_validation: handle.hotshot.config.da_payload_validation,
+            instance_state: handle.hotshot.instance_state(),
+            payload_recoveries: BTreeMap::new(),
         }
     }
 }
@@ -171,6 +182,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             next_view: cur_view,
             cur_epoch: handle.cur_epoch().await,
             membership: Arc::clone(&handle.hotshot.memberships),
+            consensus: OuterConsensus::new(handle.hotshot.consensus()),
             public_key: handle.public_key().clone(),
             private_key: handle.private_key().clone(),
             num_timeouts_tracked: 0,
@@ -181,7 +193,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             view_sync_timeout: handle.hotshot.config.view_sync_timeout,
             id: handle.hotshot.id,
             last_garbage_collected_view: TYPES::View::new(0),
+            gossiped_certificates: Arc::default(),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
         }
     }
 }
@@ -248,6 +262,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             storage: Arc::clone(&handle.storage),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
             epoch_height: handle.hotshot.config.epoch_height,
+            checkpoint_interval: handle.hotshot.config.checkpoint_interval,
+            attestation_window: handle.hotshot.config.attestation_window,
             consensus_metrics,
         }
     }
@@ -330,7 +346,74 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             consensus: OuterConsensus::new(consensus),
             id: handle.hotshot.id,
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
+            epoch_height: handle.hotshot.config.epoch_height,
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState<TYPES, I, V>
+    for EvidenceTaskState<TYPES, I, V>
+{
+    async fn create_from(handle: &SystemContextHandle<TYPES, I, V>) -> Self {
+        Self {
+            public_key: handle.public_key().clone(),
+            private_key: handle.private_key().clone(),
+            membership: Arc::clone(&handle.hotshot.memberships),
+            upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            storage: Arc::clone(&handle.storage),
+            output_event_stream: handle.hotshot.external_event_stream.0.clone(),
+            evidence: Arc::clone(&handle.hotshot.evidence),
+            proposals: BTreeMap::new(),
+            votes: BTreeMap::new(),
+            reported: HashSet::new(),
+            cur_view: handle.cur_view().await,
+            epoch_height: handle.hotshot.config.epoch_height,
+            id: handle.hotshot.id,
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState<TYPES, I, V>
+    for CheckpointTaskState<TYPES, I, V>
+{
+    async fn create_from(handle: &SystemContextHandle<TYPES, I, V>) -> Self {
+        Self {
+            public_key: handle.public_key().clone(),
+            private_key: handle.private_key().clone(),
+            consensus: OuterConsensus::new(handle.hotshot.consensus()),
+            membership: Arc::clone(&handle.hotshot.memberships),
+            upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            verified_votes: handle.hotshot.verified_votes.clone(),
+            storage: Arc::clone(&handle.storage),
+            output_event_stream: handle.hotshot.external_event_stream.0.clone(),
+            accumulators: BTreeMap::new(),
+            checkpoint_interval: handle.hotshot.config.checkpoint_interval,
+            epoch_height: handle.hotshot.config.epoch_height,
+            id: handle.hotshot.id,
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState<TYPES, I, V>
+    for AttestationTaskState<TYPES, I, V>
+{
+    async fn create_from(handle: &SystemContextHandle<TYPES, I, V>) -> Self {
+        Self {
+            public_key: handle.public_key().clone(),
+            private_key: handle.private_key().clone(),
+            membership: Arc::clone(&handle.hotshot.memberships),
+            upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            instance_state: handle.hotshot.instance_state(),
+            decided: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            nonces: AttestationNonces::new(ATTESTATION_NONCES),
+            attestation_window: handle.hotshot.config.attestation_window,
             epoch_height: handle.hotshot.config.epoch_height,
+            id: handle.hotshot.id,
         }
     }
 }
@@ -0,0 +1,169 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Classification of the view changes of a node by their [`ViewChangeReason`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use hotshot_types::view_change::{ViewChangeReason, ViewChangeRecord};
+
+/// The most views ahead of the current one whose events are tracked, in case a peer sends
+/// proposals for views far in the future
+const MAX_VIEWS_AHEAD: u64 = 1000;
+
+/// Tells why the node changed view, from what happened in the views it left
+#[derive(Debug, Default)]
+pub(crate) struct ViewChangeTracker {
+    /// the view the node is in
+    view: u64,
+    /// the view of the justify QC of each proposal received, by view of the proposal
+    proposals: BTreeMap<u64, u64>,
+    /// the views which timed out
+    timeouts: BTreeSet<u64>,
+    /// the views which view sync moved the node to
+    view_syncs: BTreeSet<u64>,
+}
+
+impl ViewChangeTracker {
+    /// A tracker for a node in `view`
+    pub(crate) fn new(view: u64) -> Self {
+        Self {
+            view,
+            ..Self::default()
+        }
+    }
+
+    /// Whether events of `view` are worth tracking
+    fn tracks(&self, view: u64) -> bool {
+        view >= self.view && view <= self.view.saturating_add(MAX_VIEWS_AHEAD)
+    }
+
+    /// Record that a proposal for `view` justified by a QC of `justify_view` was received
+    pub(crate) fn on_proposal(&mut self, view: u64, justify_view: u64) {
+        if self.tracks(view) {
+            self.proposals.insert(view, justify_view);
+        }
+    }
+
+    /// Record that `view` timed out
+    pub(crate) fn on_timeout(&mut self, view: u64) {
+        if self.tracks(view) {
+            self.timeouts.insert(view);
+        }
+    }
+
+    /// Record that view sync finalized a move to `view`
+    pub(crate) fn on_view_sync(&mut self, view: u64) {
+        if self.tracks(view) {
+            self.view_syncs.insert(view);
+        }
+    }
+
+    /// Record that the node entered `view` of `epoch` at `time_ms`, returning the view change if
+    /// it moved the node forward
+    pub(crate) fn on_view_change(
+        &mut self,
+        view: u64,
+        epoch: u64,
+        time_ms: u64,
+    ) -> Option<ViewChangeRecord> {
+        if view <= self.view {
+            return None;
+        }
+        let previous = view - 1;
+
+        let reason = if self.view_syncs.contains(&view) {
+            ViewChangeReason::ViewSync
+        } else if self.timeouts.contains(&previous) {
+            if self.proposals.contains_key(&previous) {
+                ViewChangeReason::Timeout
+            } else {
+                ViewChangeReason::LeaderOffline
+            }
+        } else if self
+            .proposals
+            .get(&view)
+            .is_some_and(|justify_view| *justify_view < previous)
+        {
+            // We entered the view on a proposal which the rest of the network made after the
+            // previous view timed out
+            ViewChangeReason::Timeout
+        } else {
+            ViewChangeReason::QuorumCertificate
+        };
+
+        let record = ViewChangeRecord {
+            from_view: self.view,
+            to_view: view,
+            epoch,
+            reason,
+            time_ms,
+        };
+        self.view = view;
+        self.proposals = self.proposals.split_off(&view);
+        self.timeouts = self.timeouts.split_off(&view);
+        self.view_syncs = self.view_syncs.split_off(&view);
+
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn view_changes_are_classified() {
+        let mut tracker = ViewChangeTracker::new(1);
+        let reason = |record: Option<ViewChangeRecord>| record.map(|record| record.reason);
+
+        // The leader of view 1 proposed, and its QC moved us on
+        tracker.on_proposal(1, 0);
+        assert_eq!(
+            reason(tracker.on_view_change(2, 0, 0)),
+            Some(ViewChangeReason::QuorumCertificate)
+        );
+        // A view change we already made, or an older one, is not a view change
+        assert_eq!(tracker.on_view_change(2, 0, 0), None);
+
+        // The leader of view 2 proposed, but the view timed out anyway
+        tracker.on_proposal(2, 1);
+        tracker.on_timeout(2);
+        assert_eq!(
+            reason(tracker.on_view_change(3, 0, 0)),
+            Some(ViewChangeReason::Timeout)
+        );
+
+        // Nothing came from the leader of view 3
+        tracker.on_timeout(3);
+        assert_eq!(
+            reason(tracker.on_view_change(4, 0, 0)),
+            Some(ViewChangeReason::LeaderOffline)
+        );
+
+        // The network timed out in view 4 without us, and the leader of view 5 told us so
+        tracker.on_proposal(5, 3);
+        assert_eq!(
+            reason(tracker.on_view_change(5, 0, 0)),
+            Some(ViewChangeReason::Timeout)
+        );
+
+        // View sync moved us from view 5 to view 9
+        tracker.on_timeout(5);
+        tracker.on_view_sync(9);
+        let record = tracker.on_view_change(9, 1, 42).unwrap();
+        assert_eq!(
+            record,
+            ViewChangeRecord {
+                from_view: 5,
+                to_view: 9,
+                epoch: 1,
+                reason: ViewChangeReason::ViewSync,
+                time_ms: 42,
+            }
+        );
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Timing of the phases of each view, recorded as histograms of [`ConsensusMetricsValue`].
+//!
+//! * `proposal_build_duration`: from entering a view to sending our proposal for it, as leader;
+//! * `da_certificate_duration`: from the DA proposal of a view being sent or received to its DA
+//!   certificate being formed or received;
+//! * `vote_collection_duration`: from the first quorum vote for a view reaching us to its QC
+//!   being formed, as next leader;
+//! * `decide_duration`: from entering a view to deciding its leaf.
+//!
+//! Storage writes on the voting path are timed where they happen, in `storage_write_duration`.
+
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
+
+use either::Either;
+use hotshot_task_impls::events::HotShotEvent;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue, traits::node_implementation::NodeType, vote::HasViewNumber,
+};
+
+/// The most views whose phases are tracked at once, in case nothing is decided for a long time
+const MAX_TRACKED_VIEWS: usize = 1000;
+
+/// When the phases of one view started
+#[derive(Clone, Copy, Debug, Default)]
+struct ViewMarks {
+    /// when we entered the view
+    entered: Option<Instant>,
+    /// when the DA proposal of the view was sent or received
+    da_proposed: Option<Instant>,
+    /// when the first quorum vote for the view reached us
+    first_vote: Option<Instant>,
+}
+
+/// Times the phases of views from the events they produce
+pub(crate) struct ViewTimer<TYPES: NodeType> {
+    /// the histograms to record to
+    metrics: Arc<ConsensusMetricsValue>,
+    /// the phases started in views which have not been decided yet
+    views: BTreeMap<TYPES::View, ViewMarks>,
+}
+
+impl<TYPES: NodeType> ViewTimer<TYPES> {
+    /// A timer recording to `metrics`
+    pub(crate) fn new(metrics: Arc<ConsensusMetricsValue>) -> Self {
+        Self {
+            metrics,
+            views: BTreeMap::new(),
+        }
+    }
+
+    /// The marks of `view`, tracking it if it is not yet
+    fn marks(&mut self, view: TYPES::View) -> &mut ViewMarks {
+        if self.views.len() >= MAX_TRACKED_VIEWS && !self.views.contains_key(&view) {
+            self.views.pop_first();
+        }
+        self.views.entry(view).or_default()
+    }
+
+    /// Record the phase `event` starts or ends, which happened at `now`
+    pub(crate) fn observe(&mut self, event: &HotShotEvent<TYPES>, now: Instant) {
+        match event {
+            HotShotEvent::ViewChange(view, _) => {
+                self.marks(*view).entered.get_or_insert(now);
+            }
+            HotShotEvent::QuorumProposalSend(proposal, _) => {
+                if let Some(entered) = self
+                    .views
+                    .get(&proposal.data.view_number())
+                    .and_then(|marks| marks.entered)
+                {
+                    self.metrics
+                        .proposal_build_duration
+                        .add_point((now - entered).as_secs_f64());
+                }
+            }
+            HotShotEvent::DaProposalSend(proposal, _)
+            | HotShotEvent::DaProposalRecv(proposal, _) => {
+                self.marks(proposal.data.view_number())
+                    .da_proposed
+                    .get_or_insert(now);
+            }
+            HotShotEvent::DaChunksSend(..) | HotShotEvent::DaChunkRecv(..) => {
+                if let Some(view) = event.view_number() {
+                    self.marks(view).da_proposed.get_or_insert(now);
+                }
+            }
+            HotShotEvent::DacSend(cert, _) | HotShotEvent::DaCertificateRecv(cert) => {
+                if let Some(da_proposed) = self
+                    .views
+                    .get_mut(&cert.view_number())
+                    .and_then(|marks| marks.da_proposed.take())
+                {
+                    self.metrics
+                        .da_certificate_duration
+                        .add_point((now - da_proposed).as_secs_f64());
+                }
+            }
+            HotShotEvent::QuorumVoteRecv(vote) => {
+                self.marks(vote.view_number()).first_vote.get_or_insert(now);
+            }
+            HotShotEvent::Qc2Formed(Either::Left(qc)) => {
+                if let Some(first_vote) = self
+                    .views
+                    .get_mut(&qc.view_number())
+                    .and_then(|marks| marks.first_vote.take())
+                {
+                    self.metrics
+                        .vote_collection_duration
+                        .add_point((now - first_vote).as_secs_f64());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Record that the leaf of `view` was decided at `now`, and stop tracking it and older views
+    pub(crate) fn observe_decide(&mut self, view: TYPES::View, now: Instant) {
+        if let Some(entered) = self.views.get(&view).and_then(|marks| marks.entered) {
+            self.metrics
+                .decide_duration
+                .add_point((now - entered).as_secs_f64());
+        }
+        self.views.retain(|&tracked, _| tracked > view);
+    }
+}
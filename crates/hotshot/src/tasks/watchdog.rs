@@ -0,0 +1,155 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Detection of the conditions of a [`WatchdogConfig`].
+
+use std::time::Instant;
+
+use hotshot_types::watchdog::{Alert, WatchdogConfig};
+
+/// Watches the progress of consensus, and raises each alert once when its condition starts to hold
+pub(crate) struct Watchdog {
+    /// when to raise alerts
+    config: WatchdogConfig,
+    /// the number of views in a row which timed out
+    consecutive_timeouts: u64,
+    /// when we last decided, or started
+    last_decide: Instant,
+    /// the last decided view
+    last_decided_view: u64,
+    /// whether the current decide stall was alerted on
+    stall_alerted: bool,
+    /// the highest view of a validated certificate, which no single node can forge
+    network_view: u64,
+    /// whether the current view lag was alerted on
+    lag_alerted: bool,
+}
+
+impl Watchdog {
+    /// A watchdog raising alerts as `config` says, started at `now`
+    pub(crate) fn new(config: WatchdogConfig, now: Instant) -> Self {
+        Self {
+            config,
+            consecutive_timeouts: 0,
+            last_decide: now,
+            last_decided_view: 0,
+            stall_alerted: false,
+            network_view: 0,
+            lag_alerted: false,
+        }
+    }
+
+    /// Record that `view` timed out
+    pub(crate) fn on_timeout(&mut self, view: u64) -> Option<Alert> {
+        self.consecutive_timeouts += 1;
+        (self.config.max_consecutive_timeouts == Some(self.consecutive_timeouts)).then_some(
+            Alert::ConsecutiveTimeouts {
+                count: self.consecutive_timeouts,
+                view,
+            },
+        )
+    }
+
+    /// Record that a view made progress, ending a run of timeouts
+    pub(crate) fn on_progress(&mut self) {
+        self.consecutive_timeouts = 0;
+    }
+
+    /// Record that the leaf of `view` was decided at `now`
+    pub(crate) fn on_decide(&mut self, view: u64, now: Instant) {
+        self.on_progress();
+        self.last_decide = now;
+        self.last_decided_view = view;
+        self.stall_alerted = false;
+    }
+
+    /// Record a validated certificate for `view`
+    pub(crate) fn on_certificate(&mut self, view: u64) {
+        self.network_view = self.network_view.max(view);
+    }
+
+    /// The alerts due at `now`, for a node in `view`
+    pub(crate) fn check(&mut self, view: u64, now: Instant) -> Vec<Alert> {
+        let mut alerts = vec![];
+
+        let secs_since_decide = now.saturating_duration_since(self.last_decide).as_secs();
+        let stalled = self
+            .config
+            .max_secs_since_decide
+            .is_some_and(|max| secs_since_decide > max);
+        if stalled && !self.stall_alerted {
+            self.stall_alerted = true;
+            alerts.push(Alert::DecideStalled {
+                secs_since_decide,
+                last_decided_view: self.last_decided_view,
+            });
+        }
+
+        let lagging = self
+            .config
+            .max_view_lag
+            .is_some_and(|max| self.network_view.saturating_sub(view) > max);
+        if lagging && !self.lag_alerted {
+            alerts.push(Alert::FallingBehind {
+                view,
+                network_view: self.network_view,
+            });
+        }
+        self.lag_alerted = lagging;
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn alerts_fire_once_per_episode() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(
+            WatchdogConfig {
+                max_consecutive_timeouts: Some(2),
+                max_secs_since_decide: Some(10),
+                max_view_lag: Some(5),
+            },
+            start,
+        );
+
+        assert_eq!(watchdog.on_timeout(1), None);
+        assert!(watchdog.on_timeout(2).is_some());
+        assert_eq!(watchdog.on_timeout(3), None);
+        watchdog.on_progress();
+        assert_eq!(watchdog.on_timeout(5), None);
+
+        watchdog.on_certificate(10);
+        assert!(watchdog.check(5, start).is_empty());
+        let later = start + Duration::from_secs(11);
+        watchdog.on_certificate(11);
+        assert_eq!(
+            watchdog.check(5, later),
+            vec![
+                Alert::DecideStalled {
+                    secs_since_decide: 11,
+                    last_decided_view: 0,
+                },
+                Alert::FallingBehind {
+                    view: 5,
+                    network_view: 11,
+                },
+            ]
+        );
+        assert!(watchdog.check(5, later).is_empty());
+
+        watchdog.on_decide(11, later);
+        assert!(watchdog.check(11, later).is_empty());
+        watchdog.on_certificate(20);
+        assert_eq!(watchdog.check(11, later + Duration::from_secs(11)).len(), 2);
+    }
+}
@@ -6,8 +6,10 @@
 
 /// Sortition trait
 pub mod election;
+mod metrics;
 mod networking;
 mod node_implementation;
+mod storage;
 
 pub use hotshot_types::traits::{BlockPayload, ValidatedState};
 pub use libp2p_networking::network::NetworkNodeConfigBuilder;
@@ -16,16 +18,20 @@ pub use node_implementation::{NodeImplementation, TestableNodeImplementation};
 
 /// Module for publicly usable implementations of the traits
 pub mod implementations {
+    pub use super::metrics::PrometheusMetrics;
     pub use super::networking::{
         combined_network::{CombinedNetworks, UnderlyingCombinedNetworks},
         libp2p_network::{
-            derive_libp2p_keypair, derive_libp2p_multiaddr, derive_libp2p_peer_id, GossipConfig,
-            Libp2pMetricsValue, Libp2pNetwork, PeerInfoVec, RequestResponseConfig,
+            derive_libp2p_keypair, derive_libp2p_multiaddr, derive_libp2p_peer_id,
+            ConnectionTimeouts, GossipConfig, Libp2pMetricsValue, Libp2pNetwork, PeerInfoVec,
+            RequestResponseConfig,
         },
         memory_network::{MasterMap, MemoryNetwork},
         push_cdn_network::{
             CdnMetricsValue, KeyPair, ProductionDef, PushCdnNetwork, TestingDef, Topic as CdnTopic,
             WrappedSignatureKey,
         },
+        split_network::SplitNetwork,
     };
+    pub use super::storage::WriteBehindStorage;
 }
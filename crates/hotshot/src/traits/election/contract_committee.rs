@@ -0,0 +1,326 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    cmp::max,
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU64,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{ensure, Context};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use hotshot_types::{
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    PeerConfig,
+};
+use tokio::{spawn, task::JoinHandle, time::sleep};
+use utils::anytrace::Result;
+
+use super::static_committee::StaticCommittee;
+
+/// Read access to a contract which defines the validator set, e.g. an Ethereum contract read
+/// over JSON-RPC.
+///
+/// Implementations are configured with the RPC endpoint and contract address by the application.
+#[async_trait]
+pub trait StakeTableContract<TYPES: NodeType>: Send + Sync + 'static {
+    /// The latest epoch for which the contract defines a stake table
+    async fn latest_epoch(&self) -> anyhow::Result<TYPES::Epoch>;
+
+    /// Read the stake table the contract defines for `epoch`
+    async fn stake_table(
+        &self,
+        epoch: TYPES::Epoch,
+    ) -> anyhow::Result<Vec<PeerConfig<TYPES::SignatureKey>>>;
+}
+
+/// The committee in effect from some epoch on
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct EpochCommittee<T: NodeType> {
+    /// The committee built from the stake table
+    committee: StaticCommittee<T>,
+
+    /// The sum of the stake of all members
+    total_stake: u128,
+}
+
+impl<TYPES: NodeType> EpochCommittee<TYPES> {
+    /// Build the committee of `stake_table`, with the configured DA committee
+    fn new(
+        stake_table: Vec<PeerConfig<TYPES::SignatureKey>>,
+        da_members: Vec<PeerConfig<TYPES::SignatureKey>>,
+    ) -> Self {
+        let total_stake = sum_stake(&stake_table);
+        Self {
+            committee: StaticCommittee::new(stake_table, da_members),
+            total_stake,
+        }
+    }
+}
+
+/// The total stake of `peers`, saturating at `u64::MAX`
+fn sum_stake<K: SignatureKey>(peers: &[PeerConfig<K>]) -> u128 {
+    let total = peers.iter().fold(0u64, |total, peer| {
+        total.saturating_add(u64::try_from(peer.stake_table_entry.stake()).unwrap_or(u64::MAX))
+    });
+    u128::from(total)
+}
+
+/// Convert a stake threshold to the type used by [`Membership`], saturating on overflow.
+fn to_threshold(stake: u128) -> NonZeroU64 {
+    NonZeroU64::new(u64::try_from(stake).unwrap_or(u64::MAX)).unwrap_or(NonZeroU64::MIN)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A committee whose stake table is read from a contract each epoch.
+///
+/// The configured committee is used until the stake table of an epoch has been fetched, and an
+/// epoch without a fetched stake table uses the latest earlier one. The DA committee is always the
+/// configured one. Use [`spawn_refresh_task`](Self::spawn_refresh_task) to keep the cache up to
+/// date. Thresholds are fractions of the total stake, not of the number of members.
+pub struct ContractCommittee<T: NodeType> {
+    /// The committee to use before any stake table was fetched
+    genesis: EpochCommittee<T>,
+
+    /// The committees of the epochs whose stake table has been fetched
+    epochs: BTreeMap<T::Epoch, EpochCommittee<T>>,
+
+    /// The configured DA committee
+    da_members: Vec<PeerConfig<T::SignatureKey>>,
+
+    /// The total stake of the DA committee
+    da_total_stake: u128,
+}
+
+impl<TYPES: NodeType> ContractCommittee<TYPES> {
+    /// The committee and total stake in effect in `epoch`
+    fn epoch_committee(&self, epoch: TYPES::Epoch) -> &EpochCommittee<TYPES> {
+        self.epochs
+            .range(..=epoch)
+            .next_back()
+            .map_or(&self.genesis, |(_, committee)| committee)
+    }
+
+    /// The committee in effect in `epoch`
+    fn committee(&self, epoch: TYPES::Epoch) -> &StaticCommittee<TYPES> {
+        &self.epoch_committee(epoch).committee
+    }
+
+    /// The total stake in effect in `epoch`, as used for the thresholds
+    fn total_stake(&self, epoch: TYPES::Epoch) -> u128 {
+        self.epoch_committee(epoch).total_stake
+    }
+
+    /// The latest epoch whose stake table has been fetched
+    #[must_use]
+    pub fn latest_fetched_epoch(&self) -> Option<TYPES::Epoch> {
+        self.epochs.last_key_value().map(|(epoch, _)| *epoch)
+    }
+
+    /// Cache the stake table of `epoch`.
+    pub fn insert_stake_table(
+        &mut self,
+        epoch: TYPES::Epoch,
+        stake_table: Vec<PeerConfig<TYPES::SignatureKey>>,
+    ) {
+        let committee = EpochCommittee::new(stake_table, self.da_members.clone());
+        self.epochs.insert(epoch, committee);
+    }
+
+    /// Fetch the stake tables of all epochs after the latest fetched one from `contract`.
+    ///
+    /// # Errors
+    /// Returns an error if the contract cannot be read, or if a stake table is empty. Stake tables
+    /// fetched before the error are kept.
+    pub async fn refresh<C: StakeTableContract<TYPES>>(
+        membership: &RwLock<Self>,
+        contract: &C,
+    ) -> anyhow::Result<()> {
+        let latest_epoch = contract
+            .latest_epoch()
+            .await
+            .context("Failed to read the latest epoch from the stake table contract")?;
+        let mut epoch = match membership.read().await.latest_fetched_epoch() {
+            Some(fetched) => TYPES::Epoch::new(*fetched + 1),
+            None => latest_epoch,
+        };
+
+        while epoch <= latest_epoch {
+            let stake_table = contract
+                .stake_table(epoch)
+                .await
+                .with_context(|| format!("Failed to read the stake table for epoch {epoch}"))?;
+            ensure!(
+                !stake_table.is_empty(),
+                "Stake table for epoch {epoch} is empty"
+            );
+
+            membership
+                .write()
+                .await
+                .insert_stake_table(epoch, stake_table);
+            tracing::info!("Fetched the stake table for epoch {epoch} from the contract");
+
+            epoch = TYPES::Epoch::new(*epoch + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Spawn a task which refreshes the stake tables from `contract` every `interval`.
+    #[must_use]
+    pub fn spawn_refresh_task<C: StakeTableContract<TYPES>>(
+        membership: Arc<RwLock<Self>>,
+        contract: C,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        spawn(async move {
+            loop {
+                if let Err(e) = Self::refresh(&membership, &contract).await {
+                    tracing::warn!("Failed to refresh the stake table: {e:#}");
+                }
+                sleep(interval).await;
+            }
+        })
+    }
+}
+
+impl<TYPES: NodeType> Membership<TYPES> for ContractCommittee<TYPES> {
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election, using the configured committee until a stake table is fetched
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        Self {
+            genesis: EpochCommittee::new(committee_members, da_members.clone()),
+            epochs: BTreeMap::new(),
+            da_total_stake: sum_stake(&da_members),
+            da_members,
+        }
+    }
+
+    fn stake_table(
+        &self,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).stake_table(epoch)
+    }
+
+    fn da_stake_table(
+        &self,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).da_stake_table(epoch)
+    }
+
+    fn committee_members(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch).committee_members(view_number, epoch)
+    }
+
+    fn da_committee_members(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch)
+            .da_committee_members(view_number, epoch)
+    }
+
+    fn committee_leaders(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch).committee_leaders(view_number, epoch)
+    }
+
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).stake(pub_key, epoch)
+    }
+
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).da_stake(pub_key, epoch)
+    }
+
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.committee(epoch).has_stake(pub_key, epoch)
+    }
+
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.committee(epoch).has_da_stake(pub_key, epoch)
+    }
+
+    fn lookup_leader(
+        &self,
+        view_number: TYPES::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Result<TYPES::SignatureKey> {
+        self.committee(epoch).lookup_leader(view_number, epoch)
+    }
+
+    /// Only the stake tables which have been fetched are known
+    fn has_stake_table(&self, epoch: <TYPES as NodeType>::Epoch) -> bool {
+        self.epochs.contains_key(&epoch)
+    }
+
+    fn total_nodes(&self, epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.committee(epoch).total_nodes(epoch)
+    }
+
+    fn da_total_nodes(&self, epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.committee(epoch).da_total_nodes(epoch)
+    }
+
+    /// Get the stake needed for a quorum, more than two thirds of the total stake
+    fn success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold((self.total_stake(epoch) * 2) / 3 + 1)
+    }
+
+    /// Get the stake needed for a DA certificate, more than two thirds of the DA stake
+    fn da_success_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold((self.da_total_stake * 2) / 3 + 1)
+    }
+
+    /// Get the stake needed for a timeout, more than a third of the total stake
+    fn failure_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold(self.total_stake(epoch) / 3 + 1)
+    }
+
+    /// Get the stake needed for an upgrade
+    fn upgrade_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        let total = self.total_stake(epoch);
+        to_threshold(max((total * 9) / 10, (total * 2) / 3 + 1))
+    }
+}
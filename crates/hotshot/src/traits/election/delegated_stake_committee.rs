@@ -0,0 +1,385 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    cmp::max,
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU64,
+};
+
+use hotshot_types::{
+    stake_table::Delegation,
+    traits::{
+        election::Membership,
+        node_implementation::NodeType,
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    PeerConfig,
+};
+use primitive_types::U256;
+use utils::anytrace::*;
+
+/// The delegations and resulting stake table in effect from some epoch on
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct EpochStake<T: NodeType> {
+    /// The amount delegated by each token holder to each operator
+    delegations: BTreeMap<(Vec<u8>, T::SignatureKey), u64>,
+
+    /// The operators with non-zero effective stake, in the order they were configured
+    stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The operators with non-zero effective stake, indexed by public key
+    indexed_stake_table:
+        BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The sum of the effective stake of all operators
+    total_stake: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A static committee whose voting weight includes stake delegated to operators by token holders.
+///
+/// Delegations are submitted by the application with
+/// [`submit_delegation_updates`](Self::submit_delegation_updates) and take effect at an epoch
+/// boundary. The effective stake of an operator is its own stake plus everything delegated to it,
+/// and all thresholds are computed from the total effective stake.
+pub struct DelegatedStakeCommittee<T: NodeType> {
+    /// The operators which may receive delegations, with their own stake, in configured order
+    operators: Vec<(T::SignatureKey, u64)>,
+
+    /// The delegations in effect from each epoch on. Epochs before the first entry use `genesis`.
+    epochs: BTreeMap<T::Epoch, EpochStake<T>>,
+
+    /// The stake table before any delegation
+    genesis: EpochStake<T>,
+
+    /// The nodes on the DA committee and their stake
+    da_stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The nodes on the DA committee and their stake, indexed by public key
+    indexed_da_stake_table:
+        BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+}
+
+impl<TYPES: NodeType> DelegatedStakeCommittee<TYPES> {
+    /// Compute the effective stake table of the operators under `delegations`.
+    fn build_epoch_stake(
+        operators: &[(TYPES::SignatureKey, u64)],
+        delegations: BTreeMap<(Vec<u8>, TYPES::SignatureKey), u64>,
+    ) -> EpochStake<TYPES> {
+        let mut delegated: BTreeMap<&TYPES::SignatureKey, u64> = BTreeMap::new();
+        for ((_, operator), amount) in &delegations {
+            let total = delegated.entry(operator).or_default();
+            *total = total.saturating_add(*amount);
+        }
+
+        let stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> = operators
+            .iter()
+            .map(|(operator, own_stake)| {
+                let delegated = delegated.get(operator).copied().unwrap_or_default();
+                operator.stake_table_entry(own_stake.saturating_add(delegated))
+            })
+            .filter(|entry| entry.stake() > U256::zero())
+            .collect();
+
+        let indexed_stake_table = stake_table
+            .iter()
+            .map(|entry| (TYPES::SignatureKey::public_key(entry), entry.clone()))
+            .collect();
+
+        let total_stake = stake_table.iter().fold(0u64, |total, entry| {
+            total.saturating_add(u64::try_from(entry.stake()).unwrap_or(u64::MAX))
+        });
+
+        EpochStake {
+            delegations,
+            stake_table,
+            indexed_stake_table,
+            total_stake,
+        }
+    }
+
+    /// The delegations and stake table in effect in `epoch`.
+    fn epoch_stake(&self, epoch: TYPES::Epoch) -> &EpochStake<TYPES> {
+        self.epochs
+            .range(..=epoch)
+            .next_back()
+            .map_or(&self.genesis, |(_, stake)| stake)
+    }
+
+    /// Apply delegation updates from `epoch` on.
+    ///
+    /// Each update replaces the amount its delegator has delegated to its operator, and an amount
+    /// of zero removes the delegation. Updates should be submitted before `epoch` starts, and
+    /// epochs must be submitted in non-decreasing order; several submissions for the same epoch
+    /// are applied on top of each other.
+    ///
+    /// # Errors
+    /// Returns an error, without applying any update, if updates for a later epoch have already
+    /// been submitted or if an update delegates to a key which is not a configured operator.
+    pub fn submit_delegation_updates(
+        &mut self,
+        epoch: TYPES::Epoch,
+        updates: Vec<Delegation<TYPES::SignatureKey>>,
+    ) -> Result<()> {
+        if let Some((latest_epoch, _)) = self.epochs.last_key_value() {
+            ensure!(
+                epoch >= *latest_epoch,
+                "Delegation updates for epoch {epoch} arrived after updates for epoch {latest_epoch}"
+            );
+        }
+        for update in &updates {
+            ensure!(
+                self.operators
+                    .iter()
+                    .any(|(operator, _)| *operator == update.operator),
+                "Cannot delegate to {}, which is not an operator",
+                update.operator
+            );
+        }
+
+        let mut delegations = self.epoch_stake(epoch).delegations.clone();
+        for update in updates {
+            let key = (update.delegator, update.operator);
+            if update.amount == 0 {
+                delegations.remove(&key);
+            } else {
+                delegations.insert(key, update.amount);
+            }
+        }
+
+        let epoch_stake = Self::build_epoch_stake(&self.operators, delegations);
+        self.epochs.insert(epoch, epoch_stake);
+
+        Ok(())
+    }
+
+    /// The total amount delegated to `operator` in `epoch`.
+    #[must_use]
+    pub fn delegated_stake(&self, operator: &TYPES::SignatureKey, epoch: TYPES::Epoch) -> u64 {
+        self.epoch_stake(epoch)
+            .delegations
+            .iter()
+            .filter(|((_, delegate), _)| delegate == operator)
+            .fold(0u64, |total, (_, amount)| total.saturating_add(*amount))
+    }
+
+    /// The delegations in effect in `epoch`.
+    #[must_use]
+    pub fn delegations(
+        &self,
+        epoch: TYPES::Epoch,
+    ) -> impl Iterator<Item = Delegation<TYPES::SignatureKey>> + '_ {
+        self.epoch_stake(epoch)
+            .delegations
+            .iter()
+            .map(|((delegator, operator), amount)| Delegation {
+                delegator: delegator.clone(),
+                operator: operator.clone(),
+                amount: *amount,
+            })
+    }
+
+    /// The total effective stake in `epoch`, as used for the thresholds
+    fn total_stake(&self, epoch: TYPES::Epoch) -> u128 {
+        u128::from(self.epoch_stake(epoch).total_stake)
+    }
+
+    /// The total stake of the DA committee, which delegations do not change
+    fn da_total_stake(&self) -> u128 {
+        self.da_stake_table
+            .iter()
+            .map(|entry| u128::try_from(entry.stake()).unwrap_or(u128::MAX))
+            .fold(0, u128::saturating_add)
+    }
+}
+
+/// Convert a stake threshold to the type used by [`Membership`], saturating on overflow.
+pub(super) fn to_threshold(stake: u128) -> NonZeroU64 {
+    NonZeroU64::new(u64::try_from(stake).unwrap_or(u64::MAX)).unwrap_or(NonZeroU64::MIN)
+}
+
+impl<TYPES: NodeType> Membership<TYPES> for DelegatedStakeCommittee<TYPES> {
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        // Every configured member is an operator, even without stake of its own
+        let operators: Vec<(TYPES::SignatureKey, u64)> = committee_members
+            .iter()
+            .map(|member| {
+                let entry = &member.stake_table_entry;
+                (
+                    TYPES::SignatureKey::public_key(entry),
+                    u64::try_from(entry.stake()).unwrap_or(u64::MAX),
+                )
+            })
+            .collect();
+
+        let genesis = Self::build_epoch_stake(&operators, BTreeMap::new());
+
+        // For each DA member, get the stake table entry
+        let da_stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> =
+            da_members
+                .iter()
+                .map(|member| member.stake_table_entry.clone())
+                .filter(|entry| entry.stake() > U256::zero())
+                .collect();
+
+        // Index the DA stake table by public key
+        let indexed_da_stake_table = da_stake_table
+            .iter()
+            .map(|entry| (TYPES::SignatureKey::public_key(entry), entry.clone()))
+            .collect();
+
+        Self {
+            operators,
+            epochs: BTreeMap::new(),
+            genesis,
+            da_stake_table,
+            indexed_da_stake_table,
+        }
+    }
+
+    /// Get the effective stake table for an epoch
+    fn stake_table(
+        &self,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_stake(epoch).stake_table.clone()
+    }
+
+    /// Get the DA stake table
+    fn da_stake_table(
+        &self,
+        _epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.da_stake_table.clone()
+    }
+
+    /// Get all operators with effective stake in an epoch
+    fn committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.epoch_stake(epoch)
+            .indexed_stake_table
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Get all members of the DA committee
+    fn da_committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        _epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.indexed_da_stake_table.keys().cloned().collect()
+    }
+
+    /// Get all eligible leaders in an epoch
+    fn committee_leaders(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee_members(view_number, epoch)
+    }
+
+    /// Get the effective stake table entry for a public key
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_stake(epoch)
+            .indexed_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    /// Get the DA stake table entry for a public key
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        _epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.indexed_da_stake_table.get(pub_key).cloned()
+    }
+
+    /// Check if a node has effective stake in an epoch
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.epoch_stake(epoch)
+            .indexed_stake_table
+            .contains_key(pub_key)
+    }
+
+    /// Check if a node has stake in the DA committee
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        _epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.indexed_da_stake_table.contains_key(pub_key)
+    }
+
+    /// Index the operators with effective stake with the current view number
+    fn lookup_leader(
+        &self,
+        view_number: TYPES::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Result<TYPES::SignatureKey> {
+        let stake_table = &self.epoch_stake(epoch).stake_table;
+        ensure!(
+            !stake_table.is_empty(),
+            "No operator has stake in epoch {epoch}"
+        );
+
+        #[allow(clippy::cast_possible_truncation)]
+        let index = *view_number as usize % stake_table.len();
+        Ok(TYPES::SignatureKey::public_key(&stake_table[index]))
+    }
+
+    /// Get the number of operators with effective stake
+    fn total_nodes(&self, epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.epoch_stake(epoch).stake_table.len()
+    }
+
+    /// Get the total number of DA nodes in the committee
+    fn da_total_nodes(&self, _epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.da_stake_table.len()
+    }
+
+    /// Get the stake needed for a quorum, more than two thirds of the effective stake
+    fn success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold((self.total_stake(epoch) * 2) / 3 + 1)
+    }
+
+    /// Get the stake needed for a DA certificate, more than two thirds of the DA stake
+    fn da_success_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold((self.da_total_stake() * 2) / 3 + 1)
+    }
+
+    /// Get the stake needed for a timeout, more than a third of the effective stake
+    fn failure_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        to_threshold(self.total_stake(epoch) / 3 + 1)
+    }
+
+    /// Get the stake needed for an upgrade
+    fn upgrade_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        let total = self.total_stake(epoch);
+        to_threshold(max((total * 9) / 10, (total * 2) / 3 + 1))
+    }
+}
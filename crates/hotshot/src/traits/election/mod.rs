@@ -6,12 +6,21 @@
 
 //! elections used for consensus
 
+/// committee read from a stake table contract every epoch
+pub mod contract_committee;
+
+/// static committee weighted by stake delegated to operators
+pub mod delegated_stake_committee;
+
 /// leader completely randomized every view
 pub mod randomized_committee;
 
 /// quorum randomized every view, with configurable overlap
 pub mod randomized_committee_members;
 
+/// sub-committees sampled from the validator set by stake-weighted sortition
+pub mod sampled_committee;
+
 /// static (round robin) committee election
 pub mod static_committee;
 
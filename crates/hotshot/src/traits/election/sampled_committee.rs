@@ -0,0 +1,390 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU64,
+};
+
+use hotshot_types::{
+    drb::{sample_committee, DrbResult, INITIAL_DRB_RESULT},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    PeerConfig,
+};
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use utils::anytrace::Result;
+
+use super::static_committee::StaticCommittee;
+
+/// Domain separator for sampling the DA committee
+const DA_COMMITTEE_DOMAIN: &[u8] = b"DA_COMMITTEE";
+
+/// Domain separator for sampling the view sync committee
+const VIEW_SYNC_COMMITTEE_DOMAIN: &[u8] = b"VIEW_SYNC_COMMITTEE";
+
+/// Parameters of the sub-committee sampling
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CommitteeSamplingConfig {
+    /// Number of members sampled into the DA committee
+    pub da_committee_size: usize,
+    /// Number of members sampled into the view sync committee
+    pub view_sync_committee_size: usize,
+    /// Percentage of a sampled committee's stake needed for a certificate.
+    ///
+    /// A sample may contain a larger share of faulty nodes than the whole validator set, so this
+    /// should be set above 67 for small committees.
+    pub success_threshold_percent: u64,
+}
+
+impl Default for CommitteeSamplingConfig {
+    fn default() -> Self {
+        Self {
+            da_committee_size: 100,
+            view_sync_committee_size: 100,
+            success_threshold_percent: 67,
+        }
+    }
+}
+
+/// A committee sampled for one task in one epoch
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct SampledSubCommittee<T: NodeType> {
+    /// The sampled members and their stake
+    stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The sampled members and their stake, indexed by public key
+    indexed_stake_table:
+        BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The stake needed for a certificate
+    success_threshold: NonZeroU64,
+
+    /// The stake which includes at least one honest member, the remainder of the success threshold
+    failure_threshold: NonZeroU64,
+}
+
+impl<T: NodeType> SampledSubCommittee<T> {
+    /// Sample a sub-committee of `size` members from `stake_table`.
+    fn sample(
+        stake_table: &[<T::SignatureKey as SignatureKey>::StakeTableEntry],
+        drb_result: DrbResult,
+        domain: &[u8],
+        size: usize,
+        success_threshold_percent: u64,
+    ) -> Self {
+        let stake_table = sample_committee::<T>(stake_table, drb_result, domain, size);
+        let indexed_stake_table = stake_table
+            .iter()
+            .map(|entry| (T::SignatureKey::public_key(entry), entry.clone()))
+            .collect();
+
+        let total_stake = stake_table
+            .iter()
+            .fold(U256::zero(), |total, entry| total + entry.stake());
+        let threshold_of = |percent: u64| {
+            let threshold = total_stake * U256::from(percent) / U256::from(100) + 1;
+            let threshold = std::cmp::min(threshold, total_stake.max(U256::one()));
+            NonZeroU64::new(u64::try_from(threshold).unwrap_or(u64::MAX)).unwrap_or(NonZeroU64::MIN)
+        };
+
+        Self {
+            stake_table,
+            indexed_stake_table,
+            success_threshold: threshold_of(success_threshold_percent),
+            failure_threshold: threshold_of(100 - success_threshold_percent.min(100)),
+        }
+    }
+}
+
+/// The sub-committees sampled for one epoch
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct EpochSample<T: NodeType> {
+    /// The DA committee
+    da: SampledSubCommittee<T>,
+
+    /// The view sync committee
+    view_sync: SampledSubCommittee<T>,
+}
+
+impl<T: NodeType> EpochSample<T> {
+    /// Sample the sub-committees of an epoch from `stake_table` with its DRB result.
+    fn new(
+        stake_table: &[<T::SignatureKey as SignatureKey>::StakeTableEntry],
+        config: &CommitteeSamplingConfig,
+        drb_result: DrbResult,
+    ) -> Self {
+        Self {
+            da: SampledSubCommittee::sample(
+                stake_table,
+                drb_result,
+                DA_COMMITTEE_DOMAIN,
+                config.da_committee_size,
+                config.success_threshold_percent,
+            ),
+            view_sync: SampledSubCommittee::sample(
+                stake_table,
+                drb_result,
+                VIEW_SYNC_COMMITTEE_DOMAIN,
+                config.view_sync_committee_size,
+                config.success_threshold_percent,
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A committee which samples small sub-committees from a large validator set.
+///
+/// The quorum is the whole validator set. Each epoch, the DA and view sync committees are sampled
+/// from the validator set by stake-weighted sortition, seeded with the epoch's DRB result. DA and
+/// view sync commit and finalize certificates need
+/// [`CommitteeSamplingConfig::success_threshold_percent`] of the sampled stake, view sync
+/// pre-commit certificates the rest of it.
+///
+/// The DRB result of an epoch is added once the last block of the previous epoch, which carries
+/// it, is decided, so every node samples with the same result. Epochs whose DRB result was not
+/// decided use [`INITIAL_DRB_RESULT`].
+pub struct SampledCommittee<T: NodeType> {
+    /// The whole validator set
+    quorum: StaticCommittee<T>,
+
+    /// The sampling parameters
+    config: CommitteeSamplingConfig,
+
+    /// The DRB results which have been added
+    drb_results: BTreeMap<T::Epoch, DrbResult>,
+
+    /// The sub-committees of the epochs whose DRB result has been added
+    epochs: BTreeMap<T::Epoch, EpochSample<T>>,
+
+    /// The sub-committees sampled with the initial DRB result
+    initial: EpochSample<T>,
+}
+
+impl<TYPES: NodeType> SampledCommittee<TYPES> {
+    /// Sample the sub-committees with `drb_result`
+    fn sample(&self, drb_result: DrbResult) -> EpochSample<TYPES> {
+        // The validator set is the same in every epoch
+        let stake_table = self.quorum.stake_table(TYPES::Epoch::genesis());
+        EpochSample::new(&stake_table, &self.config, drb_result)
+    }
+
+    /// The sub-committees of `epoch`
+    fn epoch_sample(&self, epoch: TYPES::Epoch) -> &EpochSample<TYPES> {
+        self.epochs.get(&epoch).unwrap_or(&self.initial)
+    }
+
+    /// Set the sampling parameters, resampling the sub-committees of all known epochs.
+    pub fn set_sampling_config(&mut self, config: CommitteeSamplingConfig) {
+        self.config = config;
+        self.initial = self.sample(INITIAL_DRB_RESULT);
+        self.epochs = self
+            .drb_results
+            .iter()
+            .map(|(epoch, drb_result)| (*epoch, self.sample(*drb_result)))
+            .collect();
+    }
+}
+
+impl<TYPES: NodeType> Membership<TYPES> for SampledCommittee<TYPES> {
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election with the default sampling parameters.
+    ///
+    /// The DA committee is sampled from the committee members, so `da_members` is ignored.
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        _da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        let quorum = StaticCommittee::new(committee_members.clone(), committee_members);
+        let config = CommitteeSamplingConfig::default();
+        let initial = EpochSample::new(
+            &quorum.stake_table(TYPES::Epoch::genesis()),
+            &config,
+            INITIAL_DRB_RESULT,
+        );
+
+        Self {
+            quorum,
+            config,
+            drb_results: BTreeMap::new(),
+            epochs: BTreeMap::new(),
+            initial,
+        }
+    }
+
+    fn stake_table(
+        &self,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.quorum.stake_table(epoch)
+    }
+
+    /// Get the sampled DA committee of an epoch
+    fn da_stake_table(
+        &self,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_sample(epoch).da.stake_table.clone()
+    }
+
+    fn committee_members(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.quorum.committee_members(view_number, epoch)
+    }
+
+    /// Get the members of the sampled DA committee of an epoch
+    fn da_committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.epoch_sample(epoch)
+            .da
+            .indexed_stake_table
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn committee_leaders(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.quorum.committee_leaders(view_number, epoch)
+    }
+
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.quorum.stake(pub_key, epoch)
+    }
+
+    /// Get the stake of a member of the sampled DA committee
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_sample(epoch)
+            .da
+            .indexed_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.quorum.has_stake(pub_key, epoch)
+    }
+
+    /// Check if a node is in the sampled DA committee
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> bool {
+        self.epoch_sample(epoch)
+            .da
+            .indexed_stake_table
+            .contains_key(pub_key)
+    }
+
+    fn lookup_leader(
+        &self,
+        view_number: TYPES::View,
+        epoch: <TYPES as NodeType>::Epoch,
+    ) -> Result<TYPES::SignatureKey> {
+        self.quorum.lookup_leader(view_number, epoch)
+    }
+
+    fn total_nodes(&self, epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.quorum.total_nodes(epoch)
+    }
+
+    /// Get the size of the sampled DA committee
+    fn da_total_nodes(&self, epoch: <TYPES as NodeType>::Epoch) -> usize {
+        self.epoch_sample(epoch).da.stake_table.len()
+    }
+
+    fn success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.quorum.success_threshold(epoch)
+    }
+
+    /// Get the stake needed for a certificate of the sampled DA committee
+    fn da_success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.epoch_sample(epoch).da.success_threshold
+    }
+
+    fn failure_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.quorum.failure_threshold(epoch)
+    }
+
+    fn upgrade_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.quorum.upgrade_threshold(epoch)
+    }
+
+    /// Get the sampled view sync committee of an epoch
+    fn view_sync_stake_table(
+        &self,
+        epoch: TYPES::Epoch,
+    ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_sample(epoch).view_sync.stake_table.clone()
+    }
+
+    /// Get the stake of a member of the sampled view sync committee
+    fn view_sync_stake(
+        &self,
+        pub_key: &TYPES::SignatureKey,
+        epoch: TYPES::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.epoch_sample(epoch)
+            .view_sync
+            .indexed_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    /// Get the size of the sampled view sync committee
+    fn view_sync_total_nodes(&self, epoch: TYPES::Epoch) -> usize {
+        self.epoch_sample(epoch).view_sync.stake_table.len()
+    }
+
+    /// Get the stake needed for a view sync commit or finalize certificate of the sampled committee
+    fn view_sync_success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.epoch_sample(epoch).view_sync.success_threshold
+    }
+
+    /// Get the stake needed for a view sync pre-commit certificate of the sampled committee
+    fn view_sync_failure_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.epoch_sample(epoch).view_sync.failure_threshold
+    }
+
+    /// Sample the sub-committees of `epoch` with its DRB result
+    fn add_drb_result(&mut self, epoch: TYPES::Epoch, drb_result: DrbResult) {
+        if self.drb_results.get(&epoch) == Some(&drb_result) {
+            return;
+        }
+
+        let sample = self.sample(drb_result);
+        self.drb_results.insert(epoch, drb_result);
+        self.epochs.insert(epoch, sample);
+    }
+}
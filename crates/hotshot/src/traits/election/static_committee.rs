@@ -17,6 +17,8 @@ use hotshot_types::{
 use primitive_types::U256;
 use utils::anytrace::Result;
 
+use super::delegated_stake_committee::to_threshold;
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 /// The static committee election
 pub struct StaticCommittee<T: NodeType> {
@@ -38,6 +40,20 @@ pub struct StaticCommittee<T: NodeType> {
     /// The nodes on the committee and their stake, indexed by public key
     indexed_da_stake_table:
         BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The sum of the stake of the committee, which the thresholds are computed over
+    total_stake: u128,
+
+    /// The sum of the stake of the DA committee, which the DA threshold is computed over
+    da_total_stake: u128,
+}
+
+/// The sum of the stake of `entries`, saturating on overflow
+fn total_stake<K: SignatureKey>(entries: &[K::StakeTableEntry]) -> u128 {
+    entries
+        .iter()
+        .map(|entry| u128::try_from(entry.stake()).unwrap_or(u128::MAX))
+        .fold(0, u128::saturating_add)
 }
 
 impl<TYPES: NodeType> Membership<TYPES> for StaticCommittee<TYPES> {
@@ -91,6 +107,8 @@ impl<TYPES: NodeType> Membership<TYPES> for StaticCommittee<TYPES> {
 
         Self {
             eligible_leaders,
+            total_stake: total_stake::<TYPES::SignatureKey>(&members),
+            da_total_stake: total_stake::<TYPES::SignatureKey>(&da_members),
             stake_table: members,
             da_stake_table: da_members,
             indexed_stake_table,
@@ -216,22 +234,22 @@ impl<TYPES: NodeType> Membership<TYPES> for StaticCommittee<TYPES> {
 
     /// Get the voting success threshold for the committee
     fn success_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
-        NonZeroU64::new(((self.stake_table.len() as u64 * 2) / 3) + 1).unwrap()
+        to_threshold((self.total_stake * 2) / 3 + 1)
     }
 
     /// Get the voting success threshold for the committee
     fn da_success_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
-        NonZeroU64::new(((self.da_stake_table.len() as u64 * 2) / 3) + 1).unwrap()
+        to_threshold((self.da_total_stake * 2) / 3 + 1)
     }
 
     /// Get the voting failure threshold for the committee
     fn failure_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
-        NonZeroU64::new(((self.stake_table.len() as u64) / 3) + 1).unwrap()
+        to_threshold(self.total_stake / 3 + 1)
     }
 
     /// Get the voting upgrade threshold for the committee
     fn upgrade_threshold(&self, _epoch: TYPES::Epoch) -> NonZeroU64 {
-        let len = self.stake_table.len();
-        NonZeroU64::new(max((len as u64 * 9) / 10, ((len as u64 * 2) / 3) + 1)).unwrap()
+        let total = self.total_stake;
+        to_threshold(max((total * 9) / 10, (total * 2) / 3 + 1))
     }
 }
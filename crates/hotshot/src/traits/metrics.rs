@@ -0,0 +1,404 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A [`Metrics`] implementation exporting to Prometheus.
+//!
+//! [`PrometheusMetrics`] registers every metric created through it in a Prometheus registry,
+//! which can be rendered in the text exposition format or served over HTTP on `/metrics` with
+//! [`PrometheusMetrics::serve`]. A metric Prometheus rejects, e.g. for an invalid name or the
+//! wrong number of label values, is logged and replaced by a [`NoMetrics`] no-op, so that it
+//! cannot bring down the node.
+
+use std::{fmt, net::SocketAddr};
+
+use hotshot_types::traits::metrics::{
+    Counter, CounterFamily, Gauge, GaugeFamily, Histogram, HistogramFamily, Metrics, MetricsFamily,
+    NoMetrics, TextFamily,
+};
+use prometheus::{
+    core::Collector, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    spawn,
+    task::JoinHandle,
+};
+
+use crate::helpers::read_http_request;
+
+/// The path metrics are served on
+const METRICS_PATH: &str = "/metrics";
+
+/// Metrics registered in a Prometheus registry
+#[derive(Clone, Default)]
+pub struct PrometheusMetrics {
+    /// the registry every metric is registered in, shared by all subgroups
+    registry: Registry,
+    /// the prefix of the names of the metrics in this group
+    prefix: String,
+}
+
+impl fmt::Debug for PrometheusMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrometheusMetrics")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PrometheusMetrics {
+    /// Create metrics registered in `registry`, e.g. to export them alongside those of the
+    /// application
+    #[must_use]
+    pub fn new(registry: Registry) -> Self {
+        Self {
+            registry,
+            prefix: String::new(),
+        }
+    }
+
+    /// The registry the metrics are registered in
+    #[must_use]
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    #[must_use]
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to encode metrics: {e}");
+                String::new()
+            })
+    }
+
+    /// Serve the metrics on `GET /metrics` at `address`, until the returned task is aborted
+    pub fn serve(&self, address: SocketAddr) -> JoinHandle<()> {
+        let metrics = self.clone();
+        spawn(async move {
+            let listener = match TcpListener::bind(address).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!("Failed to serve metrics on {address}: {e}");
+                    return;
+                }
+            };
+            tracing::info!("Serving metrics on http://{address}{METRICS_PATH}");
+
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept a metrics connection: {e}");
+                        continue;
+                    }
+                };
+                let metrics = metrics.clone();
+                spawn(async move {
+                    if let Err(e) = metrics.respond(stream).await {
+                        tracing::debug!("Failed to respond to a metrics request: {e}");
+                    }
+                });
+            }
+        })
+    }
+
+    /// Answer a single HTTP request, with the metrics if it asks for them
+    async fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let request = read_http_request(&mut stream).await?;
+        let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+
+        let response = match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some(METRICS_PATH)) => {
+                let body = self.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: \
+                     {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// The full name of the metric `name` in this group, with its unit as a suffix
+    fn metric_name(&self, name: &str, unit_label: Option<&str>) -> String {
+        [Some(self.prefix.as_str()), Some(name), unit_label]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("_")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    /// Register `metric`, returning it. A metric which cannot be registered, e.g. because its
+    /// name is taken, still works but is not exported.
+    fn register<M: Collector + Clone + 'static>(&self, metric: M) -> M {
+        if let Err(e) = self.registry.register(Box::new(metric.clone())) {
+            tracing::warn!("Failed to register metric: {e}");
+        }
+        metric
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn create_counter(&self, name: String, unit_label: Option<String>) -> Box<dyn Counter> {
+        let name = self.metric_name(&name, unit_label.as_deref());
+        match IntCounter::new(name.clone(), name.clone()) {
+            Ok(counter) => Box::new(PrometheusCounter(self.register(counter))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn create_gauge(&self, name: String, unit_label: Option<String>) -> Box<dyn Gauge> {
+        let name = self.metric_name(&name, unit_label.as_deref());
+        match IntGauge::new(name.clone(), name.clone()) {
+            Ok(gauge) => Box::new(PrometheusGauge(self.register(gauge))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn create_histogram(&self, name: String, unit_label: Option<String>) -> Box<dyn Histogram> {
+        let name = self.metric_name(&name, unit_label.as_deref());
+        match prometheus::Histogram::with_opts(HistogramOpts::new(name.clone(), name.clone())) {
+            Ok(histogram) => Box::new(PrometheusHistogram(self.register(histogram))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn create_text(&self, name: String) {
+        self.create_gauge(name, None).set(1);
+    }
+
+    fn counter_family(&self, name: String, labels: Vec<String>) -> Box<dyn CounterFamily> {
+        let name = self.metric_name(&name, None);
+        match IntCounterVec::new(
+            Opts::new(name.clone(), name.clone()),
+            &label_values(&labels),
+        ) {
+            Ok(family) => Box::new(PrometheusFamily(self.register(family))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn gauge_family(&self, name: String, labels: Vec<String>) -> Box<dyn GaugeFamily> {
+        let name = self.metric_name(&name, None);
+        match IntGaugeVec::new(
+            Opts::new(name.clone(), name.clone()),
+            &label_values(&labels),
+        ) {
+            Ok(family) => Box::new(PrometheusFamily(self.register(family))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn histogram_family(&self, name: String, labels: Vec<String>) -> Box<dyn HistogramFamily> {
+        let name = self.metric_name(&name, None);
+        match HistogramVec::new(
+            HistogramOpts::new(name.clone(), name.clone()),
+            &label_values(&labels),
+        ) {
+            Ok(family) => Box::new(PrometheusFamily(self.register(family))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn text_family(&self, name: String, labels: Vec<String>) -> Box<dyn TextFamily> {
+        let name = self.metric_name(&name, None);
+        match IntGaugeVec::new(
+            Opts::new(name.clone(), name.clone()),
+            &label_values(&labels),
+        ) {
+            Ok(family) => Box::new(PrometheusTextFamily(self.register(family))),
+            Err(e) => no_op(&name, &e),
+        }
+    }
+
+    fn subgroup(&self, subgroup_name: String) -> Box<dyn Metrics> {
+        Box::new(Self {
+            registry: self.registry.clone(),
+            prefix: self.metric_name(&subgroup_name, None),
+        })
+    }
+}
+
+/// A no-op in place of the metric `name`, which Prometheus rejected with `error`
+fn no_op(name: &str, error: &prometheus::Error) -> Box<NoMetrics> {
+    tracing::error!("Failed to create metric {name}, it will not be exported: {error}");
+    Box::new(NoMetrics)
+}
+
+/// A Prometheus counter
+#[derive(Clone)]
+struct PrometheusCounter(IntCounter);
+
+impl fmt::Debug for PrometheusCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrometheusCounter({})", self.0.get())
+    }
+}
+
+impl Counter for PrometheusCounter {
+    fn add(&self, amount: usize) {
+        self.0.inc_by(u64::try_from(amount).unwrap_or(u64::MAX));
+    }
+}
+
+/// A Prometheus gauge
+#[derive(Clone)]
+struct PrometheusGauge(IntGauge);
+
+impl fmt::Debug for PrometheusGauge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrometheusGauge({})", self.0.get())
+    }
+}
+
+impl Gauge for PrometheusGauge {
+    fn set(&self, amount: usize) {
+        self.0.set(i64::try_from(amount).unwrap_or(i64::MAX));
+    }
+
+    fn update(&self, delta: i64) {
+        self.0.add(delta);
+    }
+}
+
+/// A Prometheus histogram
+#[derive(Clone)]
+struct PrometheusHistogram(prometheus::Histogram);
+
+impl fmt::Debug for PrometheusHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PrometheusHistogram({} points)",
+            self.0.get_sample_count()
+        )
+    }
+}
+
+impl Histogram for PrometheusHistogram {
+    fn add_point(&self, point: f64) {
+        self.0.observe(point);
+    }
+}
+
+/// A family of Prometheus counters, gauges or histograms
+#[derive(Clone)]
+struct PrometheusFamily<V>(V);
+
+impl<V> fmt::Debug for PrometheusFamily<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrometheusFamily")
+    }
+}
+
+/// The label values of a family member, as Prometheus expects them
+fn label_values(labels: &[String]) -> Vec<&str> {
+    labels.iter().map(String::as_str).collect()
+}
+
+impl MetricsFamily<Box<dyn Counter>> for PrometheusFamily<IntCounterVec> {
+    fn create(&self, labels: Vec<String>) -> Box<dyn Counter> {
+        match self.0.get_metric_with_label_values(&label_values(&labels)) {
+            Ok(counter) => Box::new(PrometheusCounter(counter)),
+            Err(e) => no_op(&labels.join(","), &e),
+        }
+    }
+}
+
+impl MetricsFamily<Box<dyn Gauge>> for PrometheusFamily<IntGaugeVec> {
+    fn create(&self, labels: Vec<String>) -> Box<dyn Gauge> {
+        match self.0.get_metric_with_label_values(&label_values(&labels)) {
+            Ok(gauge) => Box::new(PrometheusGauge(gauge)),
+            Err(e) => no_op(&labels.join(","), &e),
+        }
+    }
+}
+
+impl MetricsFamily<Box<dyn Histogram>> for PrometheusFamily<HistogramVec> {
+    fn create(&self, labels: Vec<String>) -> Box<dyn Histogram> {
+        match self.0.get_metric_with_label_values(&label_values(&labels)) {
+            Ok(histogram) => Box::new(PrometheusHistogram(histogram)),
+            Err(e) => no_op(&labels.join(","), &e),
+        }
+    }
+}
+
+/// A family of text metrics, exported as gauges set to 1
+#[derive(Clone)]
+struct PrometheusTextFamily(IntGaugeVec);
+
+impl fmt::Debug for PrometheusTextFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrometheusTextFamily")
+    }
+}
+
+impl MetricsFamily<()> for PrometheusTextFamily {
+    fn create(&self, labels: Vec<String>) {
+        match self.0.get_metric_with_label_values(&label_values(&labels)) {
+            Ok(gauge) => gauge.set(1),
+            Err(e) => {
+                no_op(&labels.join(","), &e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders_metrics_of_subgroups_and_families() {
+        let metrics = PrometheusMetrics::default();
+
+        metrics.create_counter("views_decided".into(), None).add(3);
+        metrics
+            .subgroup("libp2p".into())
+            .create_gauge("num_connected_peers".into(), None)
+            .set(7);
+        metrics
+            .create_histogram("storage_write_duration".into(), Some("seconds".into()))
+            .add_point(0.5);
+        metrics
+            .counter_family("messages".into(), vec!["kind".into()])
+            .create(vec!["vote".into()])
+            .add(2);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("views_decided 3"));
+        assert!(rendered.contains("libp2p_num_connected_peers 7"));
+        assert!(rendered.contains("storage_write_duration_seconds_count 1"));
+        assert!(rendered.contains("messages{kind=\"vote\"} 2"));
+    }
+
+    #[test]
+    fn invalid_metrics_are_no_ops() {
+        let metrics = PrometheusMetrics::default();
+
+        // A name Prometheus rejects, and a family member with too many label values
+        metrics.create_counter(String::new(), None).add(1);
+        metrics
+            .counter_family("messages".into(), vec!["kind".into()])
+            .create(vec!["vote".into(), "extra".into()])
+            .add(1);
+
+        assert!(!metrics.render().contains("messages{"));
+    }
+}
@@ -10,11 +10,13 @@
 //! trait. Currently this includes
 //! - [`MemoryNetwork`](memory_network::MemoryNetwork), an in memory testing-only implementation
 //! - [`Libp2pNetwork`](libp2p_network::Libp2pNetwork), a production-ready networking implementation built on top of libp2p-rs.
+//! - [`SplitNetwork`](split_network::SplitNetwork), which sends DA payloads over a different network than votes.
 
 pub mod combined_network;
 pub mod libp2p_network;
 pub mod memory_network;
 /// The Push CDN network
 pub mod push_cdn_network;
+pub mod split_network;
 
 pub use hotshot_types::traits::network::{NetworkError, NetworkReliability};
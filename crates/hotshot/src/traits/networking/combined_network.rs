@@ -21,6 +21,7 @@ use std::{
 use async_broadcast::{broadcast, InactiveReceiver, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::{join, select, FutureExt};
 #[cfg(feature = "hotshot-testing")]
 use hotshot_types::traits::network::{
@@ -131,7 +132,7 @@ impl<TYPES: NodeType> CombinedNetworks<TYPES> {
     /// a helper function to send messages through both networks (possibly delayed)
     async fn send_both_networks(
         &self,
-        _message: Vec<u8>,
+        _message: Bytes,
         primary_future: impl Future<Output = Result<(), NetworkError>> + Send + 'static,
         secondary_future: impl Future<Output = Result<(), NetworkError>> + Send + 'static,
         broadcast_delay: BroadcastDelay,
@@ -356,7 +357,7 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
 
     async fn broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         topic: Topic,
         broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -384,7 +385,7 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
 
     async fn da_broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipients: Vec<TYPES::SignatureKey>,
         broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -412,7 +413,7 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
 
     async fn direct_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipient: TYPES::SignatureKey,
     ) -> Result<(), NetworkError> {
         let primary = self.primary().clone();
@@ -435,7 +436,7 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
 
     async fn vid_broadcast_message(
         &self,
-        messages: HashMap<TYPES::SignatureKey, Vec<u8>>,
+        messages: HashMap<TYPES::SignatureKey, Bytes>,
     ) -> Result<(), NetworkError> {
         self.networks.0.vid_broadcast_message(messages).await
     }
@@ -444,7 +445,7 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
     ///
     /// # Errors
     /// Does not error
-    async fn recv_message(&self) -> Result<Vec<u8>, NetworkError> {
+    async fn recv_message(&self) -> Result<Bytes, NetworkError> {
         loop {
             // Receive from both networks
             let mut primary_fut = self.primary().recv_message().fuse();
@@ -509,4 +510,12 @@ impl<TYPES: NodeType> ConnectedNetwork<TYPES::SignatureKey> for CombinedNetworks
     fn is_primary_down(&self) -> bool {
         self.primary_down.load(Ordering::Relaxed)
     }
+
+    fn num_connected_peers(&self) -> Option<usize> {
+        self.secondary().num_connected_peers()
+    }
+
+    async fn disconnect_peer(&self, peer: &TYPES::SignatureKey) -> Result<(), NetworkError> {
+        self.secondary().disconnect_peer(peer).await
+    }
 }
@@ -16,7 +16,7 @@ use std::{
     net::{IpAddr, ToSocketAddrs},
     num::NonZeroUsize,
     sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -26,6 +26,7 @@ use anyhow::{anyhow, Context};
 use async_lock::RwLock;
 use async_trait::async_trait;
 use bimap::BiHashMap;
+use bytes::Bytes;
 use futures::future::join_all;
 #[cfg(feature = "hotshot-testing")]
 use hotshot_types::traits::network::{
@@ -33,8 +34,9 @@ use hotshot_types::traits::network::{
 };
 use hotshot_types::{
     boxed_sync,
-    constants::LOOK_AHEAD,
+    constants::{INBOUND_MESSAGES_PER_PEER, INBOUND_MESSAGES_TOTAL, LOOK_AHEAD},
     data::ViewNumber,
+    fair_queue::FairQueue,
     network::NetworkConfig,
     traits::{
         election::Membership,
@@ -49,7 +51,7 @@ use libp2p_identity::{
     ed25519::{self, SecretKey},
     Keypair, PeerId,
 };
-pub use libp2p_networking::network::{GossipConfig, RequestResponseConfig};
+pub use libp2p_networking::network::{ConnectionTimeouts, GossipConfig, RequestResponseConfig};
 use libp2p_networking::{
     network::{
         behaviours::dht::record::{Namespace, RecordKey, RecordValue},
@@ -84,6 +86,10 @@ pub struct Libp2pMetricsValue {
     pub num_failed_messages: Box<dyn Counter>,
     /// Whether or not the network is considered ready
     pub is_ready: Box<dyn Gauge>,
+    /// The number of bytes sent to other nodes
+    pub bytes_sent: Box<dyn Counter>,
+    /// The number of bytes received from other nodes
+    pub bytes_received: Box<dyn Counter>,
 }
 
 impl Libp2pMetricsValue {
@@ -97,6 +103,8 @@ impl Libp2pMetricsValue {
             num_connected_peers: subgroup.create_gauge("num_connected_peers".into(), None),
             num_failed_messages: subgroup.create_counter("num_failed_messages".into(), None),
             is_ready: subgroup.create_gauge("is_ready".into(), None),
+            bytes_sent: subgroup.create_counter("bytes_sent".into(), None),
+            bytes_received: subgroup.create_counter("bytes_received".into(), None),
         }
     }
 }
@@ -147,9 +155,9 @@ struct Libp2pNetworkInner<T: NodeType> {
     /// handle to control the network
     handle: Arc<NetworkNodeHandle<T>>,
     /// Message Receiver
-    receiver: Mutex<Receiver<Vec<u8>>>,
+    receiver: Mutex<Receiver<Bytes>>,
     /// Sender for broadcast messages
-    sender: Sender<Vec<u8>>,
+    sender: Sender<Bytes>,
     /// Sender for node lookup (relevant view number, key of node) (None for shutdown)
     node_lookup_send: Sender<Option<(ViewNumber, T::SignatureKey)>>,
     /// this is really cheating to enable local tests
@@ -163,6 +171,8 @@ struct Libp2pNetworkInner<T: NodeType> {
     is_bootstrapped: Arc<AtomicBool>,
     /// The Libp2p metrics we're managing
     metrics: Libp2pMetricsValue,
+    /// the number of peers we are connected to
+    num_connected_peers: AtomicUsize,
     /// The list of topics we're subscribed to
     subscribed_topics: HashSet<String>,
     /// the latest view number (for node lookup purposes)
@@ -455,6 +465,24 @@ impl<T: NodeType> Libp2pNetwork<T> {
             .into_iter()
             .choose_multiple(&mut StdRng::from_entropy(), gossip_config.mesh_n);
         config_builder.to_connect_addrs(HashSet::from_iter(bootstrap_nodes.clone()));
+        config_builder
+            .max_connected_peers(libp2p_config.max_connected_peers)
+            .peer_regions(libp2p_config.peer_regions)
+            .peer_store_file_path(libp2p_config.peer_store_file_path);
+
+        // Set the connection timeouts, keeping the defaults for those not configured
+        let default_timeouts = ConnectionTimeouts::default();
+        config_builder.connection_timeouts(ConnectionTimeouts {
+            connect: libp2p_config
+                .connect_timeout
+                .unwrap_or(default_timeouts.connect),
+            handshake: libp2p_config
+                .handshake_timeout
+                .unwrap_or(default_timeouts.handshake),
+            identify: libp2p_config
+                .identify_timeout
+                .unwrap_or(default_timeouts.identify),
+        });
 
         // Build the node's configuration
         let node_config = config_builder.build()?;
@@ -552,6 +580,7 @@ impl<T: NodeType> Libp2pNetwork<T> {
                 dht_timeout: config.dht_timeout.unwrap_or(Duration::from_secs(120)),
                 is_bootstrapped: Arc::new(AtomicBool::new(false)),
                 metrics,
+                num_connected_peers: AtomicUsize::new(0),
                 subscribed_topics,
                 node_lookup_send,
                 // Start the latest view from 0. "Latest" refers to "most recent view we are polling for
@@ -660,24 +689,29 @@ impl<T: NodeType> Libp2pNetwork<T> {
         });
     }
 
-    /// Handle events
+    /// Handle events, buffering received messages in the inbound queue of the peer they came from
     fn handle_recvd_events(
         &self,
         msg: NetworkEvent,
-        sender: &Sender<Vec<u8>>,
+        inbound: &mut FairQueue<PeerId, Bytes>,
     ) -> Result<(), NetworkError> {
         match msg {
-            GossipMsg(msg) => {
-                sender.try_send(msg).map_err(|err| {
-                    NetworkError::ChannelSendError(format!("failed to send gossip message: {err}"))
-                })?;
+            GossipMsg(msg, pid) => {
+                if !inbound.push(pid, Bytes::from(msg)) {
+                    return Err(NetworkError::ChannelSendError(format!(
+                        "inbound queue of {pid} is full, dropping gossip message"
+                    )));
+                }
             }
-            DirectRequest(msg, _pid, chan) => {
-                sender.try_send(msg).map_err(|err| {
-                    NetworkError::ChannelSendError(format!(
-                        "failed to send direct request message: {err}"
-                    ))
-                })?;
+            DirectRequest(msg, pid, chan) => {
+                if !inbound.push(pid, Bytes::from(msg)) {
+                    // Flooding us with direct messages is misbehavior. Gossip is not held against
+                    // the peer, which may only be relaying it
+                    let _ = self.inner.handle.report_misbehavior(pid);
+                    return Err(NetworkError::ChannelSendError(format!(
+                        "inbound queue of {pid} is full, dropping direct request message"
+                    )));
+                }
                 if self
                     .inner
                     .handle
@@ -705,7 +739,10 @@ impl<T: NodeType> Libp2pNetwork<T> {
 
     /// task to propagate messages to handlers
     /// terminates on shut down of network
-    fn handle_event_generator(&self, sender: Sender<Vec<u8>>, mut network_rx: NetworkNodeReceiver) {
+    ///
+    /// Received messages are buffered per peer and drained round-robin into the shared queue, so
+    /// that a peer sending a lot cannot crowd out the messages of the others.
+    fn handle_event_generator(&self, sender: Sender<Bytes>, mut network_rx: NetworkNodeReceiver) {
         let handle = self.clone();
         let is_bootstrapped = Arc::clone(&self.inner.is_bootstrapped);
         spawn(async move {
@@ -716,8 +753,19 @@ impl<T: NodeType> Libp2pNetwork<T> {
                 return;
             };
 
+            let mut inbound = FairQueue::new(INBOUND_MESSAGES_PER_PEER, INBOUND_MESSAGES_TOTAL);
             loop {
                 select! {
+                    permit = sender.reserve(), if !inbound.is_empty() => {
+                        let Ok(permit) = permit else {
+                            warn!("Network receiver shut down!");
+                            return;
+                        };
+                        if let Some((_, message)) = inbound.pop() {
+                            permit.send(message);
+                        }
+                    }
+
                     msg = network_rx.recv() => {
                         let Ok(message) = msg else {
                             warn!("Network receiver shut down!");
@@ -728,11 +776,17 @@ impl<T: NodeType> Libp2pNetwork<T> {
                             NetworkEvent::IsBootstrapped => {
                                 is_bootstrapped.store(true, Ordering::Relaxed);
                             }
-                            GossipMsg(_) | DirectRequest(_, _, _) | DirectResponse(_, _) => {
-                                let _ = handle.handle_recvd_events(message, &sender);
+                            GossipMsg(_, _) | DirectRequest(_, _, _) | DirectResponse(_, _) => {
+                                if let Err(err) = handle.handle_recvd_events(message, &mut inbound) {
+                                    trace!("{err}");
+                                }
                             }
                             NetworkEvent::ConnectedPeersUpdate(num_peers) => {
                                 handle.inner.metrics.num_connected_peers.set(num_peers);
+                                handle
+                                    .inner
+                                    .num_connected_peers
+                                    .store(num_peers, Ordering::Relaxed);
                             }
                         }
                     }
@@ -779,7 +833,7 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
     #[instrument(name = "Libp2pNetwork::broadcast_message", skip_all)]
     async fn broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         topic: Topic,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -799,6 +853,8 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             })?;
         }
 
+        self.inner.metrics.bytes_sent.add(message.len());
+
         // NOTE: metrics is threadsafe, so clone is fine (and lightweight)
         #[cfg(feature = "hotshot-testing")]
         {
@@ -808,12 +864,12 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
 
                 let fut = config.clone().chaos_send_msg(
                     message,
-                    Arc::new(move |msg: Vec<u8>| {
+                    Arc::new(move |msg: Bytes| {
                         let topic_2 = topic.clone();
                         let handle_2 = Arc::clone(&handle);
                         let metrics_2 = metrics.clone();
                         boxed_sync(async move {
-                            if let Err(e) = handle_2.gossip_no_serialize(topic_2, msg) {
+                            if let Err(e) = handle_2.gossip_no_serialize(topic_2, Vec::from(msg)) {
                                 metrics_2.num_failed_messages.add(1);
                                 warn!("Failed to broadcast to libp2p: {:?}", e);
                             }
@@ -825,7 +881,13 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             }
         }
 
-        if let Err(e) = self.inner.handle.gossip(topic, &message) {
+        // libp2p takes ownership of a `Vec`, which is only copied out of `message` if it is still
+        // shared, e.g. with the copy we sent ourselves
+        if let Err(e) = self
+            .inner
+            .handle
+            .gossip_no_serialize(topic, Vec::from(message))
+        {
             self.inner.metrics.num_failed_messages.add(1);
             return Err(e);
         }
@@ -836,7 +898,7 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
     #[instrument(name = "Libp2pNetwork::da_broadcast_message", skip_all)]
     async fn da_broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipients: Vec<T::SignatureKey>,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -869,7 +931,7 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
     #[instrument(name = "Libp2pNetwork::direct_message", skip_all)]
     async fn direct_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipient: T::SignatureKey,
     ) -> Result<(), NetworkError> {
         // If we're not ready, return an error
@@ -903,6 +965,8 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             }
         };
 
+        self.inner.metrics.bytes_sent.add(message.len());
+
         #[cfg(feature = "hotshot-testing")]
         {
             let metrics = self.inner.metrics.clone();
@@ -911,11 +975,13 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
 
                 let fut = config.clone().chaos_send_msg(
                     message,
-                    Arc::new(move |msg: Vec<u8>| {
+                    Arc::new(move |msg: Bytes| {
                         let handle_2 = Arc::clone(&handle);
                         let metrics_2 = metrics.clone();
                         boxed_sync(async move {
-                            if let Err(e) = handle_2.direct_request_no_serialize(pid, msg) {
+                            if let Err(e) =
+                                handle_2.direct_request_no_serialize(pid, Vec::from(msg))
+                            {
                                 metrics_2.num_failed_messages.add(1);
                                 warn!("Failed to broadcast to libp2p: {:?}", e);
                             }
@@ -927,7 +993,11 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             }
         }
 
-        match self.inner.handle.direct_request(pid, &message) {
+        match self
+            .inner
+            .handle
+            .direct_request_no_serialize(pid, Vec::from(message))
+        {
             Ok(()) => Ok(()),
             Err(e) => {
                 self.inner.metrics.num_failed_messages.add(1);
@@ -941,7 +1011,7 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
     /// # Errors
     /// If there is a network-related failure.
     #[instrument(name = "Libp2pNetwork::recv_message", skip_all)]
-    async fn recv_message(&self) -> Result<Vec<u8>, NetworkError> {
+    async fn recv_message(&self) -> Result<Bytes, NetworkError> {
         let result = self
             .inner
             .receiver
@@ -951,6 +1021,7 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             .await
             .ok_or(NetworkError::ShutDown)?;
 
+        self.inner.metrics.bytes_received.add(result.len());
         Ok(result)
     }
 
@@ -1003,6 +1074,22 @@ impl<T: NodeType> ConnectedNetwork<T::SignatureKey> for Libp2pNetwork<T> {
             .queue_node_lookup(ViewNumber::new(*future_view), future_leader)
             .map_err(|err| tracing::warn!("failed to process node lookup request: {err}"));
     }
+
+    fn num_connected_peers(&self) -> Option<usize> {
+        Some(self.inner.num_connected_peers.load(Ordering::Relaxed))
+    }
+
+    async fn disconnect_peer(&self, peer: &T::SignatureKey) -> Result<(), NetworkError> {
+        let pid = self
+            .inner
+            .handle
+            .lookup_node(&peer.to_bytes(), self.inner.dht_timeout)
+            .await
+            .map_err(|err| {
+                NetworkError::LookupError(format!("failed to look up node to disconnect: {err}"))
+            })?;
+        self.inner.handle.prune_peer(pid)
+    }
 }
 
 #[cfg(test)]
@@ -11,6 +11,7 @@
 
 use core::time::Duration;
 use std::{
+    collections::HashMap,
     fmt::Debug,
     sync::{
         atomic::{AtomicUsize, Ordering},
@@ -20,13 +21,14 @@ use std::{
 
 use async_lock::{Mutex, RwLock};
 use async_trait::async_trait;
+use bytes::Bytes;
 use dashmap::DashMap;
 use hotshot_types::{
     boxed_sync,
     traits::{
         network::{
-            AsyncGenerator, BroadcastDelay, ConnectedNetwork, TestableNetworkingImplementation,
-            Topic,
+            AsyncGenerator, BroadcastDelay, ConnectedNetwork, Interception, MessageHook,
+            TestableNetworkingImplementation, Topic,
         },
         node_implementation::NodeType,
         signature_key::SignatureKey,
@@ -36,6 +38,7 @@ use hotshot_types::{
 use tokio::{
     spawn,
     sync::mpsc::{channel, error::SendError, Receiver, Sender},
+    time::{sleep, timeout_at, Instant},
 };
 use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument};
 
@@ -53,6 +56,17 @@ pub struct MasterMap<K: SignatureKey> {
 
     /// The list of `MemoryNetwork`s aggregated by topic
     subscribed_map: DashMap<Topic, Vec<(K, MemoryNetwork<K>)>>,
+
+    /// The group of each node while the network is partitioned
+    partition: parking_lot::RwLock<Option<HashMap<K, usize>>>,
+
+    /// The reliability of individual links, by sender and recipient
+    #[debug(skip)]
+    links: parking_lot::RwLock<HashMap<(K, K), Box<dyn NetworkReliability>>>,
+
+    /// Hooks deciding what happens to each message
+    #[debug(skip)]
+    hooks: parking_lot::RwLock<Vec<MessageHook<K>>>,
 }
 
 impl<K: SignatureKey> MasterMap<K> {
@@ -62,17 +76,75 @@ impl<K: SignatureKey> MasterMap<K> {
         Arc::new(MasterMap {
             map: DashMap::new(),
             subscribed_map: DashMap::new(),
+            partition: parking_lot::RwLock::new(None),
+            links: parking_lot::RwLock::new(HashMap::new()),
+            hooks: parking_lot::RwLock::new(Vec::new()),
         })
     }
+
+    /// Partition the network, so that nodes can only reach the nodes in their own group. Nodes
+    /// which are not in any group form one more group.
+    pub fn partition(&self, groups: Vec<Vec<K>>) {
+        let partition = groups
+            .into_iter()
+            .enumerate()
+            .flat_map(|(group, keys)| keys.into_iter().map(move |key| (key, group)))
+            .collect();
+        *self.partition.write() = Some(partition);
+    }
+
+    /// Remove the partition of the network, if any.
+    pub fn heal_partition(&self) {
+        *self.partition.write() = None;
+    }
+
+    /// Apply `reliability` to the messages sent from `from` to `to`, instead of the reliability
+    /// of the sender.
+    pub fn set_link_reliability(&self, from: K, to: K, reliability: Box<dyn NetworkReliability>) {
+        self.links.write().insert((from, to), reliability);
+    }
+
+    /// The reliability of the link from `sender` to `recipient`, if it was set
+    fn link_reliability(&self, sender: &K, recipient: &K) -> Option<Box<dyn NetworkReliability>> {
+        self.links
+            .read()
+            .get(&(sender.clone(), recipient.clone()))
+            .cloned()
+    }
+
+    /// Add a hook which sees every message, see [`MessageHook`]
+    pub fn add_message_hook(&self, hook: MessageHook<K>) {
+        self.hooks.write().push(hook);
+    }
+
+    /// Decide what happens to a message from `sender` to `recipient`
+    fn intercept(&self, sender: &K, recipient: &K, message: &[u8]) -> Interception {
+        self.hooks
+            .read()
+            .iter()
+            .map(|hook| hook(sender, recipient, message))
+            .find(|interception| *interception != Interception::Deliver)
+            .unwrap_or(Interception::Deliver)
+    }
+
+    /// Whether a message from `sender` can reach `recipient` under the current partition
+    fn can_reach(&self, sender: &K, recipient: &K) -> bool {
+        match &*self.partition.read() {
+            Some(partition) => partition.get(sender) == partition.get(recipient),
+            None => true,
+        }
+    }
 }
 
 /// Internal state for a `MemoryNetwork` instance
 #[derive(Debug)]
 struct MemoryNetworkInner<K: SignatureKey> {
+    /// The public key of the node
+    pub_key: K,
     /// Input for messages
-    input: RwLock<Option<Sender<Vec<u8>>>>,
+    input: RwLock<Option<Sender<Bytes>>>,
     /// Output for messages
-    output: Mutex<Receiver<Vec<u8>>>,
+    output: Mutex<Receiver<Bytes>>,
     /// The master map
     master_map: Arc<MasterMap<K>>,
 
@@ -140,6 +212,7 @@ impl<K: SignatureKey> MemoryNetwork<K> {
         trace!("Task spawned, creating MemoryNetwork");
         let mn = MemoryNetwork {
             inner: Arc::new(MemoryNetworkInner {
+                pub_key: pub_key.clone(),
                 input: RwLock::new(Some(input)),
                 output: Mutex::new(output),
                 master_map: Arc::clone(master_map),
@@ -149,7 +222,11 @@ impl<K: SignatureKey> MemoryNetwork<K> {
         };
         // Insert our public key into the master map
         master_map.map.insert(pub_key.clone(), mn.clone());
-        // Insert our subscribed topics into the master map
+        // Insert our subscribed topics into the master map, replacing the network of a previous
+        // run of this node if it was restarted
+        for mut subscribers in master_map.subscribed_map.iter_mut() {
+            subscribers.retain(|(key, _)| key != pub_key);
+        }
         for topic in subscribed_topics {
             master_map
                 .subscribed_map
@@ -161,8 +238,53 @@ impl<K: SignatureKey> MemoryNetwork<K> {
         mn
     }
 
-    /// Send a [`Vec<u8>`] message to the inner `input`
-    async fn input(&self, message: Vec<u8>) -> Result<(), SendError<Vec<u8>>> {
+    /// Whether our messages can reach `recipient` under the current partition
+    fn can_reach(&self, recipient: &K) -> bool {
+        self.inner
+            .master_map
+            .can_reach(&self.inner.pub_key, recipient)
+    }
+
+    /// The reliability of the link to `recipient`, falling back to the reliability of this node
+    fn reliability_config(&self, recipient: &K) -> Option<Box<dyn NetworkReliability>> {
+        self.inner
+            .master_map
+            .link_reliability(&self.inner.pub_key, recipient)
+            .or_else(|| self.inner.reliability_config.clone())
+    }
+
+    /// Apply the message hooks to a message for `node`.
+    ///
+    /// Returns `true` if a hook took care of the message, and `false` if it should be delivered
+    /// as usual.
+    fn intercept(&self, recipient: &K, node: &MemoryNetwork<K>, message: &Bytes) -> bool {
+        let (delay, messages) =
+            match self
+                .inner
+                .master_map
+                .intercept(&self.inner.pub_key, recipient, message)
+            {
+                Interception::Deliver => return false,
+                Interception::Drop => {
+                    trace!(?recipient, "Message dropped by hook");
+                    return true;
+                }
+                Interception::Delay(delay) => (delay, vec![message.clone()]),
+                Interception::Duplicate(count) => (Duration::ZERO, vec![message.clone(); count]),
+                Interception::Mutate(mutated) => (Duration::ZERO, vec![mutated]),
+            };
+        let node = node.clone();
+        spawn(async move {
+            sleep(delay).await;
+            for message in messages {
+                let _res = node.input(message).await;
+            }
+        });
+        true
+    }
+
+    /// Send a message to the inner `input`
+    async fn input(&self, message: Bytes) -> Result<(), SendError<Bytes>> {
         self.inner
             .in_flight_message_count
             .fetch_add(1, Ordering::Relaxed);
@@ -214,6 +336,29 @@ impl<TYPES: NodeType> TestableNetworkingImplementation<TYPES>
     fn in_flight_message_count(&self) -> Option<usize> {
         Some(self.inner.in_flight_message_count.load(Ordering::Relaxed))
     }
+
+    fn partition(&self, groups: Vec<Vec<TYPES::SignatureKey>>) {
+        self.inner.master_map.partition(groups);
+    }
+
+    fn heal_partition(&self) {
+        self.inner.master_map.heal_partition();
+    }
+
+    fn set_link_reliability(
+        &self,
+        from: TYPES::SignatureKey,
+        to: TYPES::SignatureKey,
+        reliability: Box<dyn NetworkReliability>,
+    ) {
+        self.inner
+            .master_map
+            .set_link_reliability(from, to, reliability);
+    }
+
+    fn add_message_hook(&self, hook: MessageHook<TYPES::SignatureKey>) {
+        self.inner.master_map.add_message_hook(hook);
+    }
 }
 
 // TODO instrument these functions
@@ -245,7 +390,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
     #[instrument(name = "MemoryNetwork::broadcast_message")]
     async fn broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         topic: Topic,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -260,13 +405,20 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
         {
             // TODO delay/drop etc here
             let (key, node) = node;
+            if !self.can_reach(key) {
+                trace!(?key, "Dropping message to node in another partition");
+                continue;
+            }
+            if self.intercept(key, node, &message) {
+                continue;
+            }
             trace!(?key, "Sending message to node");
-            if let Some(ref config) = &self.inner.reliability_config {
+            if let Some(config) = self.reliability_config(key) {
                 {
                     let node2 = node.clone();
                     let fut = config.chaos_send_msg(
                         message.clone(),
-                        Arc::new(move |msg: Vec<u8>| {
+                        Arc::new(move |msg: Bytes| {
                             let node3 = (node2).clone();
                             boxed_sync(async move {
                                 let _res = node3.input(msg).await;
@@ -295,7 +447,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
     #[instrument(name = "MemoryNetwork::da_broadcast_message")]
     async fn da_broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipients: Vec<K>,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -314,13 +466,20 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
             }
             // TODO delay/drop etc here
             let (key, node) = node;
+            if !self.can_reach(key) {
+                trace!(?key, "Dropping message to node in another partition");
+                continue;
+            }
+            if self.intercept(key, node, &message) {
+                continue;
+            }
             trace!(?key, "Sending message to node");
-            if let Some(ref config) = &self.inner.reliability_config {
+            if let Some(config) = self.reliability_config(key) {
                 {
                     let node2 = node.clone();
                     let fut = config.chaos_send_msg(
                         message.clone(),
-                        Arc::new(move |msg: Vec<u8>| {
+                        Arc::new(move |msg: Bytes| {
                             let node3 = (node2).clone();
                             boxed_sync(async move {
                                 let _res = node3.input(msg).await;
@@ -347,17 +506,24 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
     }
 
     #[instrument(name = "MemoryNetwork::direct_message")]
-    async fn direct_message(&self, message: Vec<u8>, recipient: K) -> Result<(), NetworkError> {
+    async fn direct_message(&self, message: Bytes, recipient: K) -> Result<(), NetworkError> {
         // debug!(?message, ?recipient, "Sending direct message");
         // Bincode the message
         trace!("Message bincoded, finding recipient");
+        if !self.can_reach(&recipient) {
+            trace!(?recipient, "Dropping message to node in another partition");
+            return Ok(());
+        }
         if let Some(node) = self.inner.master_map.map.get(&recipient) {
             let node = node.value().clone();
-            if let Some(ref config) = &self.inner.reliability_config {
+            if self.intercept(&recipient, &node, &message) {
+                return Ok(());
+            }
+            if let Some(config) = self.reliability_config(&recipient) {
                 {
                     let fut = config.chaos_send_msg(
                         message.clone(),
-                        Arc::new(move |msg: Vec<u8>| {
+                        Arc::new(move |msg: Bytes| {
                             let node2 = node.clone();
                             boxed_sync(async move {
                                 let _res = node2.input(msg).await;
@@ -393,7 +559,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
     /// # Errors
     /// If the other side of the channel is closed
     #[instrument(name = "MemoryNetwork::recv_messages", skip_all)]
-    async fn recv_message(&self) -> Result<Vec<u8>, NetworkError> {
+    async fn recv_message(&self) -> Result<Bytes, NetworkError> {
         let ret = self
             .inner
             .output
@@ -407,4 +573,40 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for MemoryNetwork<K> {
             .fetch_sub(1, Ordering::Relaxed);
         Ok(ret)
     }
+
+    /// Receive up to `max` messages: wait until `deadline` for the first one, then take those
+    /// which already arrived.
+    ///
+    /// # Errors
+    /// If the other side of the channel is closed before any message was received
+    #[instrument(name = "MemoryNetwork::recv_messages", skip_all)]
+    async fn recv_messages(
+        &self,
+        max: usize,
+        deadline: Instant,
+    ) -> Result<Vec<Bytes>, NetworkError> {
+        let mut messages = Vec::new();
+        if max == 0 {
+            return Ok(messages);
+        }
+        let Ok(mut output) = timeout_at(deadline, self.inner.output.lock()).await else {
+            return Ok(messages);
+        };
+        match timeout_at(deadline, output.recv()).await {
+            Ok(Some(message)) => messages.push(message),
+            Ok(None) => return Err(NetworkError::ShutDown),
+            Err(_) => return Ok(messages),
+        }
+        // Take what already arrived without waiting again
+        while messages.len() < max {
+            let Ok(message) = output.try_recv() else {
+                break;
+            };
+            messages.push(message);
+        }
+        self.inner
+            .in_flight_message_count
+            .fetch_sub(messages.len(), Ordering::Relaxed);
+        Ok(messages)
+    }
 }
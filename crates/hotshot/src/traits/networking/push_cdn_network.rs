@@ -12,6 +12,7 @@ use std::{path::Path, time::Duration};
 
 use async_trait::async_trait;
 use bincode::config::Options;
+use bytes::Bytes;
 use cdn_broker::reexports::{
     connection::protocols::{Tcp, TcpTls},
     def::{hook::NoMessageHook, ConnectionDef, RunDef, Topic as TopicTrait},
@@ -58,6 +59,10 @@ use super::NetworkError;
 pub struct CdnMetricsValue {
     /// The number of failed messages
     pub num_failed_messages: Box<dyn Counter>,
+    /// The number of bytes sent to the CDN
+    pub bytes_sent: Box<dyn Counter>,
+    /// The number of bytes received from the CDN
+    pub bytes_received: Box<dyn Counter>,
 }
 
 impl CdnMetricsValue {
@@ -69,6 +74,8 @@ impl CdnMetricsValue {
         // Create the CDN-specific metrics
         Self {
             num_failed_messages: subgroup.create_counter("num_failed_messages".into(), None),
+            bytes_sent: subgroup.create_counter("bytes_sent".into(), None),
+            bytes_received: subgroup.create_counter("bytes_received".into(), None),
         }
     }
 }
@@ -250,17 +257,18 @@ impl<K: SignatureKey + 'static> PushCdnNetwork<K> {
     /// # Errors
     /// - If we fail to serialize the message
     /// - If we fail to send the broadcast message.
-    async fn broadcast_message(&self, message: Vec<u8>, topic: Topic) -> Result<(), NetworkError> {
+    async fn broadcast_message(&self, message: Bytes, topic: Topic) -> Result<(), NetworkError> {
         // If we're paused, don't send the message
         #[cfg(feature = "hotshot-testing")]
         if self.is_paused.load(Ordering::Relaxed) {
             return Ok(());
         }
 
-        // Send the message
+        // Send the message. The client takes ownership of a `Vec`, which is only copied out of
+        // `message` if it is still shared.
         if let Err(err) = self
             .client
-            .send_broadcast_message(vec![topic as u8], message)
+            .send_broadcast_message(vec![topic as u8], Vec::from(message))
             .await
         {
             return Err(NetworkError::MessageReceiveError(format!(
@@ -483,7 +491,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
     /// - If we fail to send the broadcast message.
     async fn broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         topic: HotShotTopic,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -492,6 +500,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
         if self.is_paused.load(Ordering::Relaxed) {
             return Ok(());
         }
+        self.metrics.bytes_sent.add(message.len());
         self.broadcast_message(message, topic.into())
             .await
             .inspect_err(|_e| {
@@ -506,7 +515,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
     /// - If we fail to send the broadcast message.
     async fn da_broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         _recipients: Vec<K>,
         _broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError> {
@@ -515,6 +524,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
         if self.is_paused.load(Ordering::Relaxed) {
             return Ok(());
         }
+        self.metrics.bytes_sent.add(message.len());
         self.broadcast_message(message, Topic::Da)
             .await
             .inspect_err(|_e| {
@@ -526,7 +536,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
     ///
     /// - If we fail to serialize the message
     /// - If we fail to send the direct message
-    async fn direct_message(&self, message: Vec<u8>, recipient: K) -> Result<(), NetworkError> {
+    async fn direct_message(&self, message: Bytes, recipient: K) -> Result<(), NetworkError> {
         // If we're paused, don't send the message
         #[cfg(feature = "hotshot-testing")]
         if self.is_paused.load(Ordering::Relaxed) {
@@ -534,9 +544,10 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
         }
 
         // Send the message
+        self.metrics.bytes_sent.add(message.len());
         if let Err(e) = self
             .client
-            .send_direct_message(&WrappedSignatureKey(recipient), message)
+            .send_direct_message(&WrappedSignatureKey(recipient), Vec::from(message))
             .await
         {
             self.metrics.num_failed_messages.add(1);
@@ -553,7 +564,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
     ///
     /// # Errors
     /// - If we fail to receive messages. Will trigger a retry automatically.
-    async fn recv_message(&self) -> Result<Vec<u8>, NetworkError> {
+    async fn recv_message(&self) -> Result<Bytes, NetworkError> {
         // Receive a message
         let message = self.client.receive_message().await;
 
@@ -561,7 +572,7 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
         #[cfg(feature = "hotshot-testing")]
         if self.is_paused.load(Ordering::Relaxed) {
             sleep(Duration::from_millis(100)).await;
-            return Ok(vec![]);
+            return Ok(Bytes::new());
         }
 
         // If it was an error, wait a bit and retry
@@ -581,10 +592,11 @@ impl<K: SignatureKey + 'static> ConnectedNetwork<K> for PushCdnNetwork<K> {
             recipient: _,
         })) = message
         else {
-            return Ok(vec![]);
+            return Ok(Bytes::new());
         };
 
-        Ok(message)
+        self.metrics.bytes_received.add(message.len());
+        Ok(Bytes::from(message))
     }
 
     /// Do nothing here, as we don't need to look up nodes.
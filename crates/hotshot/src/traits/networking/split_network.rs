@@ -0,0 +1,271 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Networking implementation which sends DA payloads over a different network than everything
+//! else, so that bulk data transfer cannot delay votes.
+//!
+//! DA proposals, VID shares, DA chunks and transactions, which are sent to the DA committee with
+//! [`ConnectedNetwork::da_broadcast_message`] and [`ConnectedNetwork::vid_broadcast_message`], go
+//! over the DA network. Votes, quorum proposals, certificates and all other messages go over the
+//! consensus network. Messages are received from both, preferring the consensus network when both
+//! have messages waiting.
+//!
+//! To use it, set the network of a [`NodeImplementation`](crate::traits::NodeImplementation) to a
+//! `SplitNetwork`, e.g. `SplitNetwork<PushCdnNetwork<K>, Libp2pNetwork<TYPES>>` to send votes
+//! through the CDN and payloads over libp2p.
+
+#[cfg(feature = "hotshot-testing")]
+use std::time::Duration;
+use std::{collections::HashMap, sync::Arc};
+
+use async_lock::RwLock;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{join, select_biased, FutureExt};
+#[cfg(feature = "hotshot-testing")]
+use hotshot_types::traits::network::{
+    AsyncGenerator, MessageHook, NetworkReliability, TestableNetworkingImplementation,
+};
+use hotshot_types::{
+    boxed_sync,
+    data::ViewNumber,
+    traits::{
+        network::{BroadcastDelay, ConnectedNetwork, Topic},
+        node_implementation::NodeType,
+        signature_key::SignatureKey,
+    },
+    BoxSyncFuture,
+};
+use tokio::sync::mpsc::error::TrySendError;
+
+use super::NetworkError;
+
+/// A network made of a consensus network and a separate network for DA payloads
+#[derive(Clone)]
+pub struct SplitNetwork<CONSENSUS, DA> {
+    /// The network votes, proposals and every other message which is not a DA payload go over
+    consensus: CONSENSUS,
+
+    /// The network DA payloads go over
+    da: DA,
+}
+
+impl<CONSENSUS, DA> SplitNetwork<CONSENSUS, DA> {
+    /// Send DA payloads over `da` and everything else over `consensus`
+    #[must_use]
+    pub fn new(consensus: CONSENSUS, da: DA) -> Self {
+        Self { consensus, da }
+    }
+
+    /// Get a ref to the consensus network
+    #[must_use]
+    pub fn consensus(&self) -> &CONSENSUS {
+        &self.consensus
+    }
+
+    /// Get a ref to the DA network
+    #[must_use]
+    pub fn da(&self) -> &DA {
+        &self.da
+    }
+}
+
+#[cfg(feature = "hotshot-testing")]
+impl<TYPES, CONSENSUS, DA> TestableNetworkingImplementation<TYPES> for SplitNetwork<CONSENSUS, DA>
+where
+    TYPES: NodeType,
+    CONSENSUS: TestableNetworkingImplementation<TYPES> + Clone + 'static,
+    DA: TestableNetworkingImplementation<TYPES> + Clone + 'static,
+{
+    fn generator(
+        expected_node_count: usize,
+        num_bootstrap: usize,
+        network_id: usize,
+        da_committee_size: usize,
+        reliability_config: Option<Box<dyn NetworkReliability>>,
+        secondary_network_delay: Duration,
+    ) -> AsyncGenerator<Arc<Self>> {
+        let consensus_generator = CONSENSUS::generator(
+            expected_node_count,
+            num_bootstrap,
+            network_id,
+            da_committee_size,
+            reliability_config,
+            secondary_network_delay,
+        );
+        let da_generator = DA::generator(
+            expected_node_count,
+            num_bootstrap,
+            network_id,
+            da_committee_size,
+            None,
+            secondary_network_delay,
+        );
+        Box::pin(move |node_id| {
+            let consensus = consensus_generator(node_id);
+            let da = da_generator(node_id);
+
+            Box::pin(async move {
+                let (consensus, da) = join!(consensus, da);
+                Arc::new(Self::new(
+                    Arc::unwrap_or_clone(consensus),
+                    Arc::unwrap_or_clone(da),
+                ))
+            })
+        })
+    }
+
+    fn in_flight_message_count(&self) -> Option<usize> {
+        Some(self.consensus.in_flight_message_count()? + self.da.in_flight_message_count()?)
+    }
+
+    fn partition(&self, groups: Vec<Vec<TYPES::SignatureKey>>) {
+        self.consensus.partition(groups.clone());
+        self.da.partition(groups);
+    }
+
+    fn heal_partition(&self) {
+        self.consensus.heal_partition();
+        self.da.heal_partition();
+    }
+
+    fn set_link_reliability(
+        &self,
+        from: TYPES::SignatureKey,
+        to: TYPES::SignatureKey,
+        reliability: Box<dyn NetworkReliability>,
+    ) {
+        self.da
+            .set_link_reliability(from.clone(), to.clone(), reliability.clone());
+        self.consensus.set_link_reliability(from, to, reliability);
+    }
+
+    fn add_message_hook(&self, hook: MessageHook<TYPES::SignatureKey>) {
+        self.consensus.add_message_hook(Arc::clone(&hook));
+        self.da.add_message_hook(hook);
+    }
+}
+
+#[async_trait]
+impl<K, CONSENSUS, DA> ConnectedNetwork<K> for SplitNetwork<CONSENSUS, DA>
+where
+    K: SignatureKey + 'static,
+    CONSENSUS: ConnectedNetwork<K>,
+    DA: ConnectedNetwork<K>,
+{
+    fn pause(&self) {
+        self.consensus.pause();
+        self.da.pause();
+    }
+
+    fn resume(&self) {
+        self.consensus.resume();
+        self.da.resume();
+    }
+
+    async fn wait_for_ready(&self) {
+        join!(self.consensus.wait_for_ready(), self.da.wait_for_ready());
+    }
+
+    fn shut_down<'a, 'b>(&'a self) -> BoxSyncFuture<'b, ()>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        let closure = async move {
+            join!(self.consensus.shut_down(), self.da.shut_down());
+        };
+        boxed_sync(closure)
+    }
+
+    async fn broadcast_message(
+        &self,
+        message: Bytes,
+        topic: Topic,
+        broadcast_delay: BroadcastDelay,
+    ) -> Result<(), NetworkError> {
+        self.consensus
+            .broadcast_message(message, topic, broadcast_delay)
+            .await
+    }
+
+    async fn da_broadcast_message(
+        &self,
+        message: Bytes,
+        recipients: Vec<K>,
+        broadcast_delay: BroadcastDelay,
+    ) -> Result<(), NetworkError> {
+        self.da
+            .da_broadcast_message(message, recipients, broadcast_delay)
+            .await
+    }
+
+    async fn vid_broadcast_message(&self, messages: HashMap<K, Bytes>) -> Result<(), NetworkError> {
+        self.da.vid_broadcast_message(messages).await
+    }
+
+    async fn direct_message(&self, message: Bytes, recipient: K) -> Result<(), NetworkError> {
+        self.consensus.direct_message(message, recipient).await
+    }
+
+    /// Receive a message from either network, taking those from the consensus network first
+    ///
+    /// # Errors
+    /// If receiving fails on the network a message was to be received from
+    async fn recv_message(&self) -> Result<Bytes, NetworkError> {
+        let mut consensus_fut = self.consensus.recv_message().fuse();
+        let mut da_fut = self.da.recv_message().fuse();
+
+        select_biased! {
+            message = consensus_fut => message,
+            message = da_fut => message,
+        }
+    }
+
+    fn queue_node_lookup(
+        &self,
+        view_number: ViewNumber,
+        pk: K,
+    ) -> Result<(), TrySendError<Option<(ViewNumber, K)>>> {
+        self.consensus.queue_node_lookup(view_number, pk.clone())?;
+        self.da.queue_node_lookup(view_number, pk)
+    }
+
+    async fn update_view<'a, TYPES>(
+        &'a self,
+        view: u64,
+        epoch: u64,
+        membership: Arc<RwLock<TYPES::Membership>>,
+    ) where
+        TYPES: NodeType<SignatureKey = K> + 'a,
+    {
+        join!(
+            self.consensus
+                .update_view::<TYPES>(view, epoch, Arc::clone(&membership)),
+            self.da.update_view::<TYPES>(view, epoch, membership)
+        );
+    }
+
+    fn is_primary_down(&self) -> bool {
+        self.consensus.is_primary_down()
+    }
+
+    fn num_connected_peers(&self) -> Option<usize> {
+        self.consensus.num_connected_peers()
+    }
+
+    /// Drop the connections to `peer` on both networks
+    ///
+    /// # Errors
+    /// If the peer could be disconnected on neither network
+    async fn disconnect_peer(&self, peer: &K) -> Result<(), NetworkError> {
+        let (consensus, da) = join!(
+            self.consensus.disconnect_peer(peer),
+            self.da.disconnect_peer(peer)
+        );
+        consensus.or(da)
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Write-behind storage, which takes the writes to a storage off the consensus hot path.
+//!
+//! [`WriteBehindStorage`] wraps another [`Storage`] and, instead of waiting for each write,
+//! queues it to a task which applies the writes in order. Consensus waits for the writes queued
+//! so far when it flushes the storage, which it does before announcing a decide, so decided views
+//! are durable before anyone hears of them.
+//!
+//! Actions, vote intents, the high QC and the locked view are still written before consensus goes
+//! on: a node which forgot that it voted in a view could vote twice in it after a restart, one
+//! which forgot its high QC could propose on an older one, and one which forgot its lock could
+//! vote for a proposal conflicting with it. These writes first wait for the writes queued before
+//! them, so the wrapped storage never holds a high QC or a vote without the proposals and leaves
+//! written before it. Other writes of an undecided view may be lost in a crash, as they can be if
+//! the node crashes before writing them at all.
+
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use hotshot_types::{
+    consensus::{CommitmentMap, View},
+    constants::STORAGE_WRITE_QUEUE_CAPACITY,
+    data::{
+        DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2, VidDisperseShare,
+        VidDisperseShare2,
+    },
+    event::HotShotAction,
+    evidence::SignedEvidence,
+    message::Proposal,
+    simple_certificate::{
+        CheckpointCertificate, NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2,
+        UpgradeCertificate,
+    },
+    traits::{node_implementation::NodeType, storage::Storage},
+    validator_metadata::SignedValidatorMetadata,
+    vid::VidCommitment,
+    view_change::ViewChangeRecord,
+    vote_intent::VoteIntent,
+};
+use tokio::{
+    spawn,
+    sync::{mpsc, oneshot},
+};
+
+/// A write to apply to the wrapped storage
+type Write<S> = Box<dyn FnOnce(S) -> BoxFuture<'static, Result<()>> + Send>;
+
+/// An entry in the queue of a [`WriteBehindStorage`]
+enum Queued<S> {
+    /// A write to apply
+    Write(Write<S>),
+    /// A request to be told once every write queued before it was applied, with the first write
+    /// which failed since the last flush
+    Flush(oneshot::Sender<Option<anyhow::Error>>),
+}
+
+/// A storage which applies its writes to `S` in the background, see the [module
+/// documentation](self)
+#[derive(Clone)]
+pub struct WriteBehindStorage<TYPES: NodeType, S: Storage<TYPES>> {
+    /// the wrapped storage
+    inner: S,
+
+    /// the queue of the task applying the writes
+    queue: mpsc::Sender<Queued<S>>,
+
+    /// the node types of the storage
+    _pd: PhantomData<TYPES>,
+}
+
+impl<TYPES: NodeType, S: Storage<TYPES> + 'static> WriteBehindStorage<TYPES, S> {
+    /// Wrap `inner`, spawning the task which applies the writes. The task stops once every clone
+    /// of the storage is dropped.
+    #[must_use]
+    pub fn new(inner: S) -> Self {
+        let (queue, mut receiver) = mpsc::channel::<Queued<S>>(STORAGE_WRITE_QUEUE_CAPACITY);
+        let storage = inner.clone();
+        spawn(async move {
+            let mut failure = None;
+            while let Some(queued) = receiver.recv().await {
+                match queued {
+                    Queued::Write(write) => {
+                        if let Err(e) = write(storage.clone()).await {
+                            tracing::error!("Queued storage write failed: {e:?}");
+                            failure.get_or_insert(e);
+                        }
+                    }
+                    Queued::Flush(done) => {
+                        let _ = done.send(failure.take());
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner,
+            queue,
+            _pd: PhantomData,
+        }
+    }
+
+    /// The wrapped storage
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Queue `write`, waiting if the queue is full
+    async fn queue<F>(&self, write: F) -> Result<()>
+    where
+        F: FnOnce(S) -> BoxFuture<'static, Result<()>> + Send + 'static,
+    {
+        self.queue
+            .send(Queued::Write(Box::new(write)))
+            .await
+            .map_err(|_| anyhow!("The storage write task stopped"))
+    }
+
+    /// Wait until every write queued so far was applied
+    ///
+    /// # Errors
+    /// if one of them failed
+    async fn drain(&self) -> Result<()> {
+        let (done, applied) = oneshot::channel();
+        self.queue
+            .send(Queued::Flush(done))
+            .await
+            .map_err(|_| anyhow!("The storage write task stopped"))?;
+        match applied.await {
+            Ok(None) => Ok(()),
+            Ok(Some(e)) => Err(e.context("A queued storage write failed")),
+            Err(_) => Err(anyhow!("The storage write task stopped")),
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, S: Storage<TYPES> + 'static> Storage<TYPES> for WriteBehindStorage<TYPES, S> {
+    async fn append_vid(&self, proposal: &Proposal<TYPES, VidDisperseShare<TYPES>>) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| Box::pin(async move { storage.append_vid(&proposal).await }))
+            .await
+    }
+
+    async fn append_vid2(
+        &self,
+        proposal: &Proposal<TYPES, VidDisperseShare2<TYPES>>,
+    ) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| Box::pin(async move { storage.append_vid2(&proposal).await }))
+            .await
+    }
+
+    async fn append_da(
+        &self,
+        proposal: &Proposal<TYPES, DaProposal<TYPES>>,
+        vid_commit: VidCommitment,
+    ) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| {
+            Box::pin(async move { storage.append_da(&proposal, vid_commit).await })
+        })
+        .await
+    }
+
+    async fn append_da2(
+        &self,
+        proposal: &Proposal<TYPES, DaProposal2<TYPES>>,
+        vid_commit: VidCommitment,
+    ) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| {
+            Box::pin(async move { storage.append_da2(&proposal, vid_commit).await })
+        })
+        .await
+    }
+
+    async fn append_proposal(
+        &self,
+        proposal: &Proposal<TYPES, QuorumProposal<TYPES>>,
+    ) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| Box::pin(async move { storage.append_proposal(&proposal).await }))
+            .await
+    }
+
+    async fn append_proposal2(
+        &self,
+        proposal: &Proposal<TYPES, QuorumProposal2<TYPES>>,
+    ) -> Result<()> {
+        let proposal = proposal.clone();
+        self.queue(move |storage| {
+            Box::pin(async move { storage.append_proposal2(&proposal).await })
+        })
+        .await
+    }
+
+    async fn append_evidence(&self, evidence: &SignedEvidence<TYPES>) -> Result<()> {
+        let evidence = evidence.clone();
+        self.queue(move |storage| Box::pin(async move { storage.append_evidence(&evidence).await }))
+            .await
+    }
+
+    async fn append_view_change(&self, record: &ViewChangeRecord) -> Result<()> {
+        let record = record.clone();
+        self.queue(move |storage| {
+            Box::pin(async move { storage.append_view_change(&record).await })
+        })
+        .await
+    }
+
+    async fn update_validator_metadata(
+        &self,
+        record: &SignedValidatorMetadata<TYPES>,
+    ) -> Result<()> {
+        let record = record.clone();
+        self.queue(move |storage| {
+            Box::pin(async move { storage.update_validator_metadata(&record).await })
+        })
+        .await
+    }
+
+    async fn record_vote_intent(&self, intent: &VoteIntent<TYPES>) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner.record_vote_intent(intent).await
+    }
+
+    async fn record_action(&self, view: TYPES::View, action: HotShotAction) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner.record_action(view, action).await
+    }
+
+    async fn update_high_qc(&self, high_qc: QuorumCertificate<TYPES>) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner.update_high_qc(high_qc).await
+    }
+
+    async fn update_high_qc2(&self, high_qc: QuorumCertificate2<TYPES>) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner.update_high_qc2(high_qc).await
+    }
+
+    async fn update_next_epoch_high_qc2(
+        &self,
+        next_epoch_high_qc: NextEpochQuorumCertificate2<TYPES>,
+    ) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner
+            .update_next_epoch_high_qc2(next_epoch_high_qc)
+            .await
+    }
+
+    async fn update_locked_view(&self, locked_view: TYPES::View) -> Result<()> {
+        // Written through, see the module documentation
+        self.drain().await?;
+        self.inner.update_locked_view(locked_view).await
+    }
+
+    async fn update_undecided_state(
+        &self,
+        leaves: CommitmentMap<Leaf<TYPES>>,
+        state: BTreeMap<TYPES::View, View<TYPES>>,
+    ) -> Result<()> {
+        self.queue(move |storage| {
+            Box::pin(async move { storage.update_undecided_state(leaves, state).await })
+        })
+        .await
+    }
+
+    async fn update_undecided_state2(
+        &self,
+        leaves: CommitmentMap<Leaf2<TYPES>>,
+        state: BTreeMap<TYPES::View, View<TYPES>>,
+    ) -> Result<()> {
+        self.queue(move |storage| {
+            Box::pin(async move { storage.update_undecided_state2(leaves, state).await })
+        })
+        .await
+    }
+
+    async fn update_decided_upgrade_certificate(
+        &self,
+        decided_upgrade_certificate: Option<UpgradeCertificate<TYPES>>,
+    ) -> Result<()> {
+        self.queue(move |storage| {
+            Box::pin(async move {
+                storage
+                    .update_decided_upgrade_certificate(decided_upgrade_certificate)
+                    .await
+            })
+        })
+        .await
+    }
+
+    async fn update_checkpoint_certificate(
+        &self,
+        checkpoint_certificate: &CheckpointCertificate<TYPES>,
+    ) -> Result<()> {
+        // A checkpoint is an anchor for nodes syncing from it, so it is durable once written
+        self.drain().await?;
+        self.inner
+            .update_checkpoint_certificate(checkpoint_certificate)
+            .await?;
+        self.inner.flush().await
+    }
+
+    async fn migrate_consensus(
+        &self,
+        convert_leaf: fn(Leaf<TYPES>) -> Leaf2<TYPES>,
+        convert_proposal: fn(
+            Proposal<TYPES, QuorumProposal<TYPES>>,
+        ) -> Proposal<TYPES, QuorumProposal2<TYPES>>,
+    ) -> Result<()> {
+        self.drain().await?;
+        self.inner
+            .migrate_consensus(convert_leaf, convert_proposal)
+            .await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn compact(&self) -> Result<()> {
+        self.inner.compact().await
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.drain().await?;
+        self.inner.flush().await
+    }
+}
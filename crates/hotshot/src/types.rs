@@ -4,13 +4,24 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
+mod admin;
+mod dump;
 mod event;
+mod finality;
 mod handle;
+mod health;
+mod receipts;
 
-pub use event::{Event, EventType};
+pub use dump::{ConsensusDump, PeerDump};
+pub use event::{decide_batches, DecideBatch, Event, EventKind, EventType, Overflow};
+pub use finality::{finality_stream, FinalityLog, FinalityStream};
 pub use handle::SystemContextHandle;
+pub(crate) use health::now_ms;
+pub use health::{HealthThresholds, HealthTracker, NodeHealth};
 pub use hotshot_types::{
     message::Message,
     signature_key::{BLSPrivKey, BLSPubKey},
     traits::signature_key::SignatureKey,
 };
+pub(crate) use receipts::PendingTransactions;
+pub use receipts::{SubmissionLimits, TransactionOutcome, TxReceiptHandle};
@@ -0,0 +1,310 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! An admin interface for operators to intervene in a running node without restarting it.
+//!
+//! [`SystemContextHandle::serve_admin`](crate::types::SystemContextHandle::serve_admin) answers
+//! HTTP requests on a loopback address. Every request must carry the configured token as
+//! `Authorization: Bearer <token>`, or it is rejected with `401 Unauthorized`.
+//!
+//! * `GET /consensus` returns a [`ConsensusDump`](crate::types::ConsensusDump) as JSON;
+//! * `POST /view-sync` starts view sync for the view after the current one;
+//! * `POST /pause` stops the node from voting and proposing, and `POST /resume` lets it again,
+//!   see [`SystemContextHandle::pause`](crate::types::SystemContextHandle::pause);
+//! * `POST /storage/compact` asks the storage to reclaim space, see
+//!   [`Storage::compact`](hotshot_types::traits::storage::Storage::compact);
+//! * `POST /peers/<key>/disconnect` drops the connection to the node with the tagged base64 key
+//!   `<key>`, if the network supports it;
+//! * `GET /log-filter` returns the current log filter, and `PUT /log-filter` replaces it with the
+//!   directives in the body, e.g. `info,hotshot_task_impls::consensus=trace`, see
+//!   [`set_log_filter`];
+//! * `PUT /config` queues the [`ConfigUpdate`] in the JSON body for the next view, see
+//!   [`SystemContext::update_config`].
+//!
+//! [`SystemContextHandle::watch_config_file`](crate::types::SystemContextHandle::watch_config_file)
+//! queues the same updates from a TOML file instead.
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use async_broadcast::Sender;
+use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
+use hotshot_types::{
+    reconfig::ConfigUpdate,
+    traits::{network::ConnectedNetwork, node_implementation::NodeType, storage::Storage},
+};
+use tagged_base64::TaggedBase64;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    spawn,
+    task::JoinHandle,
+};
+
+use crate::{
+    helpers::{log_filter, read_http_request, set_log_filter},
+    traits::NodeImplementation,
+    SystemContext, Versions,
+};
+
+/// The status line and JSON body of a response
+type Response = (&'static str, String);
+
+/// Serve the admin interface of `context` at `address`, until the returned task is aborted
+///
+/// Requests are only answered if they carry `token`. `internal_events` is used to reach the
+/// consensus tasks.
+pub(crate) fn serve_admin<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: Arc<SystemContext<TYPES, I, V>>,
+    internal_events: Sender<Arc<HotShotEvent<TYPES>>>,
+    address: SocketAddr,
+    token: String,
+) -> JoinHandle<()> {
+    spawn(async move {
+        if !address.ip().is_loopback() {
+            tracing::error!("Refusing to serve the admin interface on non-loopback {address}");
+            return;
+        }
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to serve the admin interface on {address}: {e}");
+                return;
+            }
+        };
+        tracing::info!("Serving the admin interface on http://{address}");
+
+        let token = Arc::new(token);
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept an admin request: {e}");
+                    continue;
+                }
+            };
+            let context = Arc::clone(&context);
+            let internal_events = internal_events.clone();
+            let token = Arc::clone(&token);
+            spawn(async move {
+                if let Err(e) = respond(&context, &internal_events, &token, stream).await {
+                    tracing::debug!("Failed to respond to an admin request: {e}");
+                }
+            });
+        }
+    })
+}
+
+/// Queue the parameter changes in the TOML file at `path` for `context` whenever the file is
+/// modified, checking every `interval` until the returned task is aborted
+pub(crate) fn watch_config_file<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: Arc<SystemContext<TYPES, I, V>>,
+    path: PathBuf,
+    interval: Duration,
+) -> JoinHandle<()> {
+    spawn(async move {
+        let mut last_modified = None;
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            let modified = match std::fs::metadata(&path).and_then(|meta| meta.modified()) {
+                Ok(modified) => modified,
+                Err(e) => {
+                    tracing::debug!("Failed to check config file {}: {e}", path.display());
+                    continue;
+                }
+            };
+            if last_modified.replace(modified) == Some(modified) {
+                continue;
+            }
+            let update = match std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    toml::from_str::<ConfigUpdate>(&contents).map_err(|e| e.to_string())
+                }) {
+                Ok(update) => update,
+                Err(e) => {
+                    tracing::error!("Ignoring config file {}: {e}", path.display());
+                    continue;
+                }
+            };
+            if let Err(e) = context.update_config(update).await {
+                tracing::error!("Ignoring config file {}: {e}", path.display());
+            }
+        }
+    })
+}
+
+/// Whether the headers of `request` carry `token`
+fn is_authorized(request: &str, token: &str) -> bool {
+    request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .any(|(name, value)| {
+            name.eq_ignore_ascii_case("authorization")
+                && value.trim().strip_prefix("Bearer ").is_some_and(|given| {
+                    // Compare every byte, so the time taken does not reveal the matching prefix
+                    given.len() == token.len()
+                        && given
+                            .bytes()
+                            .zip(token.bytes())
+                            .fold(0, |diff, (a, b)| diff | (a ^ b))
+                            == 0
+                })
+        })
+}
+
+/// Answer a single admin request
+async fn respond<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &SystemContext<TYPES, I, V>,
+    internal_events: &Sender<Arc<HotShotEvent<TYPES>>>,
+    token: &str,
+    mut stream: TcpStream,
+) -> std::io::Result<()> {
+    let request = read_http_request(&mut stream).await?;
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let body = request
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+
+    let (status, body) = if is_authorized(&request, token) {
+        route(context, internal_events, method, path, body).await
+    } else {
+        tracing::warn!("Rejected an unauthorized admin request for {method} {path}");
+        ("401 Unauthorized", String::new())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Perform the operation `method` `path` asks for, with the request body `body`
+async fn route<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &SystemContext<TYPES, I, V>,
+    internal_events: &Sender<Arc<HotShotEvent<TYPES>>>,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> Response {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        ("GET", ["consensus"]) => {
+            let dump = context.consensus_dump().await;
+            ("200 OK", serde_json::to_string(&dump).unwrap_or_default())
+        }
+        ("POST", ["view-sync"]) => {
+            let view = context.consensus().read().await.cur_view() + 1;
+            tracing::warn!("Admin triggered view sync for view {view:?}");
+            broadcast_event(
+                Arc::new(HotShotEvent::ViewSyncTrigger(view)),
+                internal_events,
+            )
+            .await;
+            ("202 Accepted", format!("{{\"view\":{}}}", *view))
+        }
+        ("POST", ["pause"]) => {
+            context.set_paused(true);
+            ("200 OK", String::new())
+        }
+        ("POST", ["resume"]) => {
+            context.set_paused(false);
+            ("200 OK", String::new())
+        }
+        ("POST", ["storage", "compact"]) => {
+            tracing::warn!("Admin triggered storage compaction");
+            match context.storage.read().await.compact().await {
+                Ok(()) => ("200 OK", String::new()),
+                Err(e) => failure(&e),
+            }
+        }
+        ("POST", ["peers", key, "disconnect"]) => {
+            let Some(peer) = TaggedBase64::parse(key)
+                .ok()
+                .and_then(|key| TYPES::SignatureKey::try_from(&key).ok())
+            else {
+                return ("400 Bad Request", error_body(&format!("Invalid key {key}")));
+            };
+            tracing::warn!("Admin disconnected peer {peer}");
+            match context.network.disconnect_peer(&peer).await {
+                Ok(()) => ("200 OK", String::new()),
+                Err(e) => failure(&e),
+            }
+        }
+        ("GET", ["log-filter"]) => match log_filter() {
+            Some(filter) => (
+                "200 OK",
+                serde_json::json!({ "filter": filter }).to_string(),
+            ),
+            None => failure(&"Logging was not initialized with a reloadable filter"),
+        },
+        ("PUT", ["log-filter"]) => match set_log_filter(body.trim()) {
+            Ok(()) => ("200 OK", String::new()),
+            Err(e) => ("400 Bad Request", error_body(&format!("{e:#}"))),
+        },
+        ("PUT", ["config"]) => {
+            let update = match serde_json::from_str::<ConfigUpdate>(body) {
+                Ok(update) => update,
+                Err(e) => {
+                    return (
+                        "400 Bad Request",
+                        error_body(&format!("Invalid update: {e}")),
+                    )
+                }
+            };
+            match context.update_config(update).await {
+                Ok(()) => ("202 Accepted", String::new()),
+                Err(e) => ("400 Bad Request", error_body(&e.to_string())),
+            }
+        }
+        _ => ("404 Not Found", String::new()),
+    }
+}
+
+/// The response to an operation which failed with `error`
+fn failure(error: &impl std::fmt::Display) -> Response {
+    ("500 Internal Server Error", error_body(&error.to_string()))
+}
+
+/// A JSON body reporting `message`
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn requests_need_the_token() {
+        let request = |auth: &str| format!("GET /consensus HTTP/1.1\r\nHost: x\r\n{auth}\r\n\r\n");
+        assert!(is_authorized(
+            &request("Authorization: Bearer secret"),
+            "secret"
+        ));
+        assert!(is_authorized(
+            &request("authorization:  Bearer secret"),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &request("Authorization: Bearer secreT"),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &request("Authorization: Bearer secret2"),
+            "secret"
+        ));
+        assert!(!is_authorized(&request("X-Token: secret"), "secret"));
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Snapshots of the in-memory consensus state, for debugging a node after the fact.
+//!
+//! [`SystemContextHandle::dump_state`](crate::types::SystemContextHandle::dump_state) writes a
+//! [`ConsensusDump`] on demand.
+//! [`SystemContextHandle::dump_state_on_panic`](crate::types::SystemContextHandle::dump_state_on_panic)
+//! installs a panic hook which writes one before the panic unwinds. Votes still being collected
+//! are held by the vote collection tasks rather than the consensus state, so they are not part of
+//! a dump.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hotshot_types::{
+    consensus::Consensus,
+    fork_tree::ForkTree,
+    liveness::LivenessRecord,
+    simple_certificate::{CheckpointCertificate, NextEpochQuorumCertificate2, QuorumCertificate2},
+    traits::{network::ConnectedNetwork, node_implementation::NodeType},
+};
+use serde::Serialize;
+
+use crate::{traits::NodeImplementation, SystemContext, Versions};
+
+/// What this node has observed of a peer
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerDump {
+    /// the key of the peer
+    pub key: String,
+    /// how reliably the peer has participated in consensus
+    pub liveness: LivenessRecord,
+}
+
+/// A snapshot of the consensus state of a node
+#[derive(Clone, Debug, Serialize)]
+#[serde(bound = "")]
+pub struct ConsensusDump<TYPES: NodeType> {
+    /// the view this node is in
+    pub cur_view: TYPES::View,
+    /// the epoch this node is in
+    pub cur_epoch: TYPES::Epoch,
+    /// the locked view
+    pub locked_view: TYPES::View,
+    /// the last decided view
+    pub last_decided_view: TYPES::View,
+    /// the highest QC this node has seen
+    pub high_qc: QuorumCertificate2<TYPES>,
+    /// the highest QC for the next epoch this node has seen, if any
+    pub next_epoch_high_qc: Option<NextEpochQuorumCertificate2<TYPES>>,
+    /// the certificate of the latest checkpoint, if any
+    pub checkpoint_certificate: Option<CheckpointCertificate<TYPES>>,
+    /// the views this node holds state for and has not decided yet
+    pub undecided_views: Vec<TYPES::View>,
+    /// the views this node holds a DA certificate for
+    pub da_certificate_views: Vec<TYPES::View>,
+    /// the leaves this node holds, with the QCs they carry, and which branch it prefers
+    pub fork_tree: ForkTree<TYPES>,
+    /// the number of peers this node is connected to, if the network can tell
+    pub connected_peers: Option<usize>,
+    /// the peers this node has observed participating in consensus
+    pub peers: Vec<PeerDump>,
+}
+
+impl<TYPES: NodeType> ConsensusDump<TYPES> {
+    /// Take a snapshot of `consensus`, on a node connected to `connected_peers` peers
+    #[must_use]
+    pub fn new(consensus: &Consensus<TYPES>, connected_peers: Option<usize>) -> Self {
+        let mut da_certificate_views = consensus
+            .saved_da_certs()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        da_certificate_views.sort();
+        let mut peers = consensus
+            .liveness()
+            .records()
+            .iter()
+            .map(|(key, record)| PeerDump {
+                key: key.to_string(),
+                liveness: *record,
+            })
+            .collect::<Vec<_>>();
+        peers.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Self {
+            cur_view: consensus.cur_view(),
+            cur_epoch: consensus.cur_epoch(),
+            locked_view: consensus.locked_view(),
+            last_decided_view: consensus.last_decided_view(),
+            high_qc: consensus.high_qc().clone(),
+            next_epoch_high_qc: consensus.next_epoch_high_qc().cloned(),
+            checkpoint_certificate: consensus.checkpoint_certificate().cloned(),
+            undecided_views: consensus
+                .validated_state_map()
+                .keys()
+                .filter(|&&view| view > consensus.last_decided_view())
+                .copied()
+                .collect(),
+            da_certificate_views,
+            fork_tree: consensus.fork_tree(),
+            connected_peers,
+            peers,
+        }
+    }
+
+    /// Write the snapshot to `path` as JSON
+    ///
+    /// # Errors
+    /// if the file cannot be written
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Install a panic hook writing a [`ConsensusDump`] of `context` to `dir` before any previously
+/// installed hook runs
+///
+/// The hook only holds a weak reference, so it does not keep `context` alive. If the consensus
+/// state is locked for writing when the panic happens, e.g. by the panicking thread, no dump is
+/// written.
+pub(crate) fn dump_state_on_panic<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &Arc<SystemContext<TYPES, I, V>>,
+    dir: PathBuf,
+) {
+    let context: Weak<SystemContext<TYPES, I, V>> = Arc::downgrade(context);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(context) = context.upgrade() {
+            let timestamp_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis())
+                .unwrap_or_default();
+            let path = dir.join(format!("consensus-{}-{timestamp_ms}.json", context.id));
+            match context.consensus.try_read() {
+                Some(consensus) => {
+                    let dump =
+                        ConsensusDump::new(&consensus, context.network.num_connected_peers());
+                    match std::fs::create_dir_all(&dir).and_then(|()| dump.write(&path)) {
+                        Ok(()) => eprintln!("Wrote a consensus dump to {}", path.display()),
+                        Err(e) => eprintln!("Failed to write a consensus dump: {e}"),
+                    }
+                }
+                None => eprintln!("Consensus is locked, not writing a consensus dump"),
+            }
+        }
+        previous(info);
+    }));
+}
@@ -6,4 +6,173 @@
 
 //! Events that a [`SystemContext`](crate::SystemContext) instance can emit
 
-pub use hotshot_types::event::{Event, EventType};
+use std::{collections::HashSet, sync::Arc};
+
+use async_broadcast::{broadcast, Receiver};
+use committable::Committable;
+use futures::{stream, FutureExt, Stream, StreamExt};
+pub use hotshot_types::event::{DecideBatch, Event, EventType};
+use hotshot_types::traits::node_implementation::NodeType;
+use serde::{Deserialize, Serialize};
+use tokio::spawn;
+
+/// The kinds of events a node emits, for subscribing to some of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// leaves were decided
+    Decide,
+    /// a view was interrupted by an error
+    Error,
+    /// a view finished
+    ViewFinished,
+    /// a view timed out
+    ViewTimeout,
+    /// transactions were received or submitted
+    Transactions,
+    /// a DA proposal was received or sent
+    DaProposal,
+    /// a quorum proposal was received or sent
+    QuorumProposal,
+    /// a quorum proposal was rejected by the application
+    QuorumProposalRejected,
+    /// a DA payload was rejected by the application
+    DaPayloadRejected,
+    /// an upgrade proposal was received or sent
+    UpgradeProposal,
+    /// a message for external listeners was received
+    ExternalMessageReceived,
+    /// a protocol violation was observed
+    ByzantineEvidence,
+    /// a checkpoint was certified
+    CheckpointCertified,
+    /// the watchdog raised an alert
+    Alert,
+    /// parameters of the node were changed at runtime
+    ConfigUpdated,
+}
+
+impl EventKind {
+    /// The kind of `event`, if it is one clients can subscribe to
+    #[must_use]
+    pub fn of<TYPES: NodeType>(event: &EventType<TYPES>) -> Option<Self> {
+        Some(match event {
+            EventType::Decide { .. } => Self::Decide,
+            EventType::Error { .. } => Self::Error,
+            EventType::ViewFinished { .. } => Self::ViewFinished,
+            EventType::ViewTimeout { .. } | EventType::ReplicaViewTimeout { .. } => {
+                Self::ViewTimeout
+            }
+            EventType::Transactions { .. } => Self::Transactions,
+            EventType::DaProposal { .. } => Self::DaProposal,
+            EventType::QuorumProposal { .. } => Self::QuorumProposal,
+            EventType::QuorumProposalRejected { .. } => Self::QuorumProposalRejected,
+            EventType::DaPayloadRejected { .. } => Self::DaPayloadRejected,
+            EventType::UpgradeProposal { .. } => Self::UpgradeProposal,
+            EventType::ExternalMessageReceived { .. } => Self::ExternalMessageReceived,
+            EventType::ByzantineEvidence { .. } => Self::ByzantineEvidence,
+            EventType::CheckpointCertified { .. } => Self::CheckpointCertified,
+            EventType::Alert { .. } => Self::Alert,
+            EventType::ConfigUpdated { .. } => Self::ConfigUpdated,
+            _ => return None,
+        })
+    }
+}
+
+/// What an event stream does once its consumer falls `capacity` events behind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// drop the oldest buffered event to make room for the next one
+    #[default]
+    DropOldest,
+    /// stop taking events from the node until the consumer catches up
+    ///
+    /// Consensus is never held up by a slow consumer: once the buffer of the node itself is
+    /// full, the node drops its oldest events as with [`Overflow::DropOldest`].
+    Block,
+}
+
+/// The events of `events` whose kinds are among `kinds`, or all of them if `kinds` is not
+/// given, buffered up to `capacity` events and overflowing as `overflow` says
+pub(crate) fn filtered_event_stream<TYPES: NodeType>(
+    mut events: Receiver<Event<TYPES>>,
+    kinds: Option<HashSet<EventKind>>,
+    capacity: usize,
+    overflow: Overflow,
+) -> Receiver<Event<TYPES>> {
+    let (mut sender, receiver) = broadcast(capacity.max(1));
+    sender.set_overflow(overflow == Overflow::DropOldest);
+    spawn(async move {
+        while let Some(event) = events.next().await {
+            let wanted = kinds.as_ref().map_or(true, |kinds| {
+                EventKind::of(&event.event).is_some_and(|kind| kinds.contains(&kind))
+            });
+            // fails once the consumer dropped the stream
+            if wanted && sender.broadcast_direct(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// The decide events of `events`, each merged with the decides already waiting behind it into
+/// one [`DecideBatch`] of at most `max_leaves` leaves (but never less than one decide).
+///
+/// A consumer which keeps up gets one batch per decide, and one which falls behind gets the
+/// backlog in few, large batches. A batch ends early where its leaves would stop being
+/// contiguous, e.g. after decides were dropped by the event stream or skipped during catchup,
+/// so every batch can be applied on its own.
+pub fn decide_batches<TYPES: NodeType>(
+    events: impl Stream<Item = Event<TYPES>> + Send + Unpin + 'static,
+    max_leaves: usize,
+) -> impl Stream<Item = DecideBatch<TYPES>> + Send {
+    stream::unfold((events, None), move |(mut events, held_back)| async move {
+        let mut batch = match held_back {
+            Some(batch) => batch,
+            None => loop {
+                if let Some(batch) = into_decide_batch(events.next().await?) {
+                    break batch;
+                }
+            },
+        };
+
+        let mut held_back = None;
+        // only take the decides which are already waiting
+        while let Some(Some(event)) = events.next().now_or_never() {
+            let Some(next) = into_decide_batch(event) else {
+                continue;
+            };
+            let contiguous = match (batch.leaf_chain.last(), next.leaf_chain.first()) {
+                (Some(newest), Some(oldest)) => {
+                    oldest.leaf.parent_commitment() == newest.leaf.commit()
+                }
+                _ => false,
+            };
+            if !contiguous || batch.leaf_chain.len() + next.leaf_chain.len() > max_leaves {
+                held_back = Some(next);
+                break;
+            }
+            batch.leaf_chain.extend(next.leaf_chain);
+            batch.qc = next.qc;
+            batch.decides += 1;
+        }
+
+        Some((batch, (events, held_back)))
+    })
+}
+
+/// `event` as a batch of one decide, if it is a decide
+fn into_decide_batch<TYPES: NodeType>(event: Event<TYPES>) -> Option<DecideBatch<TYPES>> {
+    let EventType::Decide { leaf_chain, qc, .. } = event.event else {
+        return None;
+    };
+    let mut leaf_chain = Arc::unwrap_or_clone(leaf_chain);
+    leaf_chain.reverse();
+
+    Some(DecideBatch {
+        leaf_chain,
+        qc,
+        decides: 1,
+    })
+}
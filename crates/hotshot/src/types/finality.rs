@@ -0,0 +1,227 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Provides a stream of finalized leaves, each paired with the quorum certificate which
+//! certifies it and a compact proof of that certificate.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use async_lock::RwLock;
+use committable::Commitment;
+use futures::stream::{self, BoxStream, StreamExt};
+use hotshot_types::{
+    data::Leaf2,
+    event::LeafInfo,
+    finality::{FinalityProof, FinalizedLeaf},
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        block_contents::{BlockHeader, BlockPayload},
+        node_implementation::NodeType,
+    },
+};
+use tokio::sync::watch;
+
+/// A stream of finalized leaves, in increasing view order.
+///
+/// The stream is pull-based: leaves are buffered in a bounded [`FinalityLog`] and only
+/// cloned out when the consumer polls, so a slow consumer never slows down consensus. A
+/// consumer which falls so far behind that the leaves it has not yet read are evicted from the
+/// log will see the stream end, and should reopen it from the view it last processed.
+pub type FinalityStream<TYPES> = BoxStream<'static, FinalizedLeaf<TYPES>>;
+
+/// A bounded log of the most recently finalized leaves, from which [`FinalityStream`]s are served.
+///
+/// An archival node keeps every leaf instead, and also indexes the leaves by the transactions in
+/// their blocks.
+pub struct FinalityLog<TYPES: NodeType> {
+    /// The finalized leaves, oldest first
+    entries: VecDeque<FinalizedLeaf<TYPES>>,
+
+    /// The sequence number of the first entry in `entries`
+    first_sequence: u64,
+
+    /// The maximum number of entries to keep
+    capacity: usize,
+
+    /// Notifies streams of the sequence number which the next finalized leaf will get
+    notifier: watch::Sender<u64>,
+
+    /// The block height each finalized transaction was included at, kept by archival nodes only
+    transactions: Option<HashMap<Commitment<TYPES::Transaction>, u64>>,
+}
+
+impl<TYPES: NodeType> FinalityLog<TYPES> {
+    /// Create an empty log which keeps at most `capacity` leaves.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let (notifier, _) = watch::channel(0);
+
+        Self {
+            entries: VecDeque::new(),
+            first_sequence: 0,
+            capacity,
+            notifier,
+            transactions: None,
+        }
+    }
+
+    /// Create an empty log for an archival node, which keeps every leaf and indexes their
+    /// transactions.
+    #[must_use]
+    pub fn archive() -> Self {
+        Self {
+            transactions: Some(HashMap::new()),
+            ..Self::new(usize::MAX)
+        }
+    }
+
+    /// Whether the log keeps every leaf.
+    #[must_use]
+    pub fn is_archive(&self) -> bool {
+        self.transactions.is_some()
+    }
+
+    /// The sequence number which the next finalized leaf will get.
+    fn next_sequence(&self) -> u64 {
+        self.first_sequence + self.entries.len() as u64
+    }
+
+    /// Record the leaves of a decide event.
+    ///
+    /// `leaf_chain` is newest first, and `qc` certifies the newest leaf. Each older leaf is
+    /// certified by the justify QC of the leaf after it.
+    pub fn record(&mut self, leaf_chain: &[LeafInfo<TYPES>], qc: &QuorumCertificate2<TYPES>) {
+        let last_view = self.entries.back().map(|(leaf, ..)| leaf.view_number());
+
+        let mut certifying_qc = qc.clone();
+        let mut finalized = Vec::with_capacity(leaf_chain.len());
+        for info in leaf_chain {
+            let justify_qc = info.leaf.justify_qc();
+            finalized.push((info.leaf.clone(), certifying_qc));
+            certifying_qc = justify_qc;
+        }
+
+        for (leaf, qc) in finalized.into_iter().rev() {
+            if last_view.is_some_and(|view| leaf.view_number() <= view) {
+                continue;
+            }
+            if let (Some(transactions), Some(payload)) =
+                (&mut self.transactions, leaf.block_payload())
+            {
+                let header = leaf.block_header();
+                for commitment in payload.transaction_commitments(header.metadata()) {
+                    transactions.insert(commitment, header.block_number());
+                }
+            }
+            let proof = FinalityProof::from_qc(&qc);
+            self.entries.push_back((leaf, qc, proof));
+        }
+
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+            self.first_sequence += 1;
+        }
+
+        self.notifier.send_replace(self.next_sequence());
+    }
+
+    /// The sequence number of the first retained leaf with a view of at least `view`, or of the
+    /// next finalized leaf if there is none.
+    fn sequence_from_view(&self, view: TYPES::View) -> u64 {
+        // the entries are in increasing view order
+        let index = self
+            .entries
+            .partition_point(|(leaf, ..)| leaf.view_number() < view);
+        self.first_sequence + index as u64
+    }
+
+    /// The retained leaf finalized in `view`, if any.
+    #[must_use]
+    pub fn leaf(&self, view: TYPES::View) -> Option<&Leaf2<TYPES>> {
+        let index = self
+            .entries
+            .partition_point(|(leaf, ..)| leaf.view_number() < view);
+        self.entries
+            .get(index)
+            .map(|(leaf, ..)| leaf)
+            .filter(|leaf| leaf.view_number() == view)
+    }
+
+    /// The retained leaves with block heights from `from_height`, at most `count` of them, oldest
+    /// first.
+    #[must_use]
+    pub fn range(&self, from_height: u64, count: usize) -> Vec<FinalizedLeaf<TYPES>> {
+        // the entries are in increasing height order too
+        let start = self
+            .entries
+            .partition_point(|(leaf, ..)| leaf.height() < from_height);
+        self.entries.range(start..).take(count).cloned().collect()
+    }
+
+    /// The block height at which the transaction with `commitment` was finalized, if this is an
+    /// archival log and it was.
+    #[must_use]
+    pub fn transaction_height(&self, commitment: &Commitment<TYPES::Transaction>) -> Option<u64> {
+        self.transactions.as_ref()?.get(commitment).copied()
+    }
+
+    /// Get the leaf with the given sequence number.
+    ///
+    /// Returns `Err(())` if the leaf has already been evicted from the log.
+    fn get(&self, sequence: u64) -> Result<Option<FinalizedLeaf<TYPES>>, ()> {
+        if sequence < self.first_sequence {
+            return Err(());
+        }
+        let index = usize::try_from(sequence - self.first_sequence).map_err(|_| ())?;
+        Ok(self.entries.get(index).cloned())
+    }
+}
+
+/// Open a stream over `log`.
+///
+/// If `from_view` is given, the stream first replays the retained leaves with a view of at least
+/// `from_view`; otherwise it starts with the next leaf to be finalized.
+pub async fn finality_stream<TYPES: NodeType>(
+    log: Arc<RwLock<FinalityLog<TYPES>>>,
+    from_view: Option<TYPES::View>,
+) -> FinalityStream<TYPES> {
+    let (sequence, notifications) = {
+        let log_reader = log.read().await;
+        let sequence = match from_view {
+            Some(view) => log_reader.sequence_from_view(view),
+            None => log_reader.next_sequence(),
+        };
+        (sequence, log_reader.notifier.subscribe())
+    };
+
+    stream::unfold(
+        (log, sequence, notifications),
+        |(log, sequence, mut notifications)| async move {
+            loop {
+                let next = log.read().await.get(sequence);
+                match next {
+                    Ok(Some(finalized)) => {
+                        return Some((finalized, (log, sequence + 1, notifications)));
+                    }
+                    Ok(None) => {
+                        // Nothing new yet, wait for the next decide
+                        notifications.changed().await.ok()?;
+                    }
+                    Err(()) => {
+                        tracing::warn!(
+                            "Finality stream fell behind the finality log, closing the stream"
+                        );
+                        return None;
+                    }
+                }
+            }
+        },
+    )
+    .boxed()
+}
@@ -6,36 +6,73 @@
 
 //! Provides an event-streaming handle for a [`SystemContext`] running in the background
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    marker::PhantomData,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::{anyhow, Context, Ok, Result};
+use anyhow::{anyhow, ensure, Context, Ok, Result};
 use async_broadcast::{InactiveReceiver, Receiver, Sender};
 use async_lock::RwLock;
+use bytes::Bytes;
 use committable::{Commitment, Committable};
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use hotshot_task::{
     dependency::{Dependency, EventDependency},
+    executor::TaskPriority,
     task::{ConsensusTaskRegistry, NetworkTaskRegistry, Task, TaskState},
 };
 use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
 use hotshot_types::{
+    attestation::{message_digest, Attestation, AttestationRequest},
     consensus::Consensus,
-    data::{Leaf2, QuorumProposal2},
+    data::{DaChunk, Leaf2, QuorumProposal2},
     error::HotShotError,
-    message::{Message, MessageKind, Proposal, RecipientList},
+    evidence::SignedEvidence,
+    finality::{FinalityProof, FinalizedLeaf},
+    message::{Message, MessageKind, Proposal, RecipientList, WireEncoding},
+    reconfig::ConfigUpdate,
     request_response::ProposalRequestPayload,
+    simple_certificate::{AttestationCertificate, CheckpointCertificate},
+    simple_vote::{AttestationData, AttestationVote},
     traits::{
         consensus_api::ConsensusApi,
         election::Membership,
-        network::{BroadcastDelay, ConnectedNetwork, Topic},
-        node_implementation::NodeType,
+        network::{BroadcastDelay, ConnectedNetwork, DataRequest, RequestKind, Topic},
+        node_implementation::{ConsensusTime, NodeType},
         signature_key::SignatureKey,
+        states::ValidatedState,
+        storage::Storage,
     },
-    vote::HasViewNumber,
+    utils::epoch_from_block_number,
+    validator_metadata::{SignedValidatorMetadata, ValidatorMetadata},
+    view_change::ViewChangeRecord,
+    vote::{HasViewNumber, VoteAccumulator},
+    watchdog::Alert,
+};
+use tokio::{
+    spawn,
+    task::{spawn_blocking, JoinHandle},
 };
 use tracing::instrument;
 
-use crate::{traits::NodeImplementation, types::Event, SystemContext, Versions};
+use crate::{
+    traits::NodeImplementation,
+    types::{
+        admin::{serve_admin, watch_config_file},
+        dump::dump_state_on_panic,
+        event::{decide_batches, filtered_event_stream},
+        finality_stream,
+        health::serve_health,
+        now_ms, ConsensusDump, DecideBatch, Event, EventKind, EventType, FinalityStream,
+        HealthThresholds, NodeHealth, Overflow, SubmissionLimits, TxReceiptHandle,
+    },
+    SystemContext, Versions,
+};
 
 /// Event streaming handle for a [`SystemContext`] instance running in the background
 ///
@@ -80,13 +117,29 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
 {
     /// Adds a hotshot consensus-related task to the `SystemContextHandle`.
     pub fn add_task<S: TaskState<Event = HotShotEvent<TYPES>> + 'static>(&mut self, task_state: S) {
+        self.add_task_with_priority(task_state, TaskPriority::Normal);
+    }
+
+    /// Adds a hotshot consensus-related task to the `SystemContextHandle`, run with `priority`.
+    pub fn add_task_with_priority<S: TaskState<Event = HotShotEvent<TYPES>> + 'static>(
+        &mut self,
+        task_state: S,
+        priority: TaskPriority,
+    ) {
         let task = Task::new(
             task_state,
             self.internal_event_stream.0.clone(),
             self.internal_event_stream.1.activate_cloned(),
         );
 
-        self.consensus_registry.run_task(task);
+        self.consensus_registry
+            .run_task_with_priority(task, priority);
+    }
+
+    /// The number of consensus-related tasks of `priority` running, for diagnostics
+    #[must_use]
+    pub fn running_tasks(&self, priority: TaskPriority) -> usize {
+        self.consensus_registry.executor().running(priority)
     }
 
     /// obtains a stream to expose to the user
@@ -94,6 +147,29 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
         self.output_event_stream.1.activate_cloned()
     }
 
+    /// A stream of the events of the given `kinds`, or of every kind if not given, which buffers
+    /// up to `capacity` events for a slow consumer and then overflows as `overflow` says
+    pub fn filtered_event_stream(
+        &self,
+        kinds: Option<HashSet<EventKind>>,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> impl Stream<Item = Event<TYPES>> {
+        filtered_event_stream(
+            self.output_event_stream.1.activate_cloned(),
+            kinds,
+            capacity,
+            overflow,
+        )
+    }
+
+    /// A stream of the decides only, delivered as contiguous [`DecideBatch`]es of up to
+    /// `max_leaves` leaves each, for applications which would rather apply a backlog of decides
+    /// at once than one event at a time
+    pub fn decide_batch_stream(&self, max_leaves: usize) -> impl Stream<Item = DecideBatch<TYPES>> {
+        decide_batches(self.output_event_stream.1.activate_cloned(), max_leaves)
+    }
+
     /// Message other participants with a serialized message from the application
     /// Receivers of this message will get an `Event::ExternalMessageReceived` via
     /// the event stream.
@@ -108,8 +184,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
         let message = Message {
             sender: self.public_key().clone(),
             kind: MessageKind::External(msg),
+            namespace: self.hotshot.config.namespace,
+            expires_after: None,
         };
-        let serialized_message = self.hotshot.upgrade_lock.serialize(&message).await?;
+        let serialized_message = Bytes::from(
+            self.hotshot
+                .upgrade_lock
+                .serialize_signed(&message, WireEncoding::Bincode, self.private_key())
+                .await?,
+        );
 
         match recipients {
             RecipientList::Broadcast => {
@@ -205,6 +288,325 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
         })
     }
 
+    /// Recover the payload of `view` from the chunks held by the DA committee, for a view in which
+    /// the DA leader dispersed chunks instead of the whole payload. Asks every other member for its
+    /// chunk and resolves once enough valid chunks arrived, saving the recovered payload for the
+    /// view. If too few members answer this will block forever.
+    ///
+    /// # Errors
+    /// Errors if signing the request fails, or if the recovered payload does not match the payload
+    /// commitment the committee voted on
+    pub fn recover_da_payload(
+        &self,
+        view: TYPES::View,
+        epoch: TYPES::Epoch,
+    ) -> Result<impl futures::Future<Output = Result<Arc<[u8]>>>> {
+        let request = RequestKind::DaChunk(view);
+        let signature = TYPES::SignatureKey::sign(self.private_key(), request.commit().as_ref())?;
+        let data_request = DataRequest {
+            request,
+            view,
+            signature,
+        };
+
+        let mem = Arc::clone(&self.memberships);
+        let consensus = self.hotshot.consensus();
+        let public_key = self.public_key().clone();
+        let mut receiver = self.internal_event_stream.1.activate_cloned();
+        let sender = self.internal_event_stream.0.clone();
+        Ok(async move {
+            let mem_reader = mem.read().await;
+            let leader = mem_reader.leader(view, epoch)?;
+            let da_committee = mem_reader.da_committee_members(view, epoch);
+            let num_nodes = mem_reader.total_nodes(epoch);
+            drop(mem_reader);
+            ensure!(
+                !da_committee.is_empty(),
+                "The DA committee of view {view:?} is empty"
+            );
+
+            let mut chunks = BTreeMap::new();
+            if let Some(chunk) = consensus.read().await.da_chunks().get(&view) {
+                chunks.insert(public_key.clone(), chunk.data.clone());
+            }
+            for member in da_committee.iter().filter(|member| **member != public_key) {
+                broadcast_event(
+                    HotShotEvent::DaChunkRequestSend(
+                        data_request.clone(),
+                        public_key.clone(),
+                        member.clone(),
+                    )
+                    .into(),
+                    &sender,
+                )
+                .await;
+            }
+
+            while !DaChunk::can_recover(chunks.values(), num_nodes) {
+                let event = receiver
+                    .recv_direct()
+                    .await
+                    .context("The event stream closed")?;
+                let HotShotEvent::DaChunkResponseRecv(member, chunk) = event.as_ref() else {
+                    continue;
+                };
+                if chunk.data.view_number() != view
+                    || chunk.data.recipient_key != *member
+                    || !da_committee.contains(member)
+                    || chunks.contains_key(member)
+                {
+                    continue;
+                }
+                let (proposal, leader, committee) =
+                    (chunk.clone(), leader.clone(), da_committee.clone());
+                if spawn_blocking(move || {
+                    DaChunk::is_valid(&proposal, &leader, &committee, num_nodes)
+                })
+                .await?
+                {
+                    chunks.insert(member.clone(), chunk.data.clone());
+                } else {
+                    tracing::warn!("Invalid DA chunk for view {view:?} from {member}");
+                }
+            }
+
+            let chunks: Vec<_> = chunks.into_values().collect();
+            let payload_commitment = chunks[0].payload_commitment;
+            let payload: Arc<[u8]> =
+                spawn_blocking(move || DaChunk::recover_payload(&chunks, num_nodes))
+                    .await??
+                    .into();
+
+            let mut consensus_writer = consensus.write().await;
+            if let Err(e) = consensus_writer.update_saved_payloads(view, Arc::clone(&payload)) {
+                tracing::trace!("{e:?}");
+            }
+            consensus_writer.cache_payload(payload_commitment, Arc::clone(&payload));
+
+            Ok(payload)
+        })
+    }
+
+    /// Ask the archival node `archive` for up to `count` finalized leaves from block height
+    /// `from_height` on, to catch up on the history this node missed. Resolves with the leaves
+    /// the archive returned, oldest first, once each is checked to be certified by the quorum and
+    /// to extend the one before it. If the archive does not answer, for instance because it rate
+    /// limits this node, this will block forever, so callers should bound it with a timeout.
+    ///
+    /// # Errors
+    /// Errors if signing the request fails, or if the archive returned leaves which are not
+    /// finalized or not the requested range
+    pub fn request_leaf_range(
+        &self,
+        archive: TYPES::SignatureKey,
+        from_height: u64,
+        count: u64,
+    ) -> Result<impl futures::Future<Output = Result<Vec<FinalizedLeaf<TYPES>>>>> {
+        let request = RequestKind::LeafRange(from_height, count);
+        let signature = TYPES::SignatureKey::sign(self.private_key(), request.commit().as_ref())?;
+
+        let mem = Arc::clone(&self.memberships);
+        let consensus = self.hotshot.consensus();
+        let upgrade_lock = self.hotshot.upgrade_lock.clone();
+        let instance_state = self.hotshot.instance_state();
+        let public_key = self.public_key().clone();
+        let mut receiver = self.internal_event_stream.1.activate_cloned();
+        let sender = self.internal_event_stream.0.clone();
+        Ok(async move {
+            let data_request = DataRequest {
+                request,
+                view: consensus.read().await.cur_view(),
+                signature,
+            };
+            broadcast_event(
+                HotShotEvent::LeafRangeRequestSend(data_request, public_key, archive.clone())
+                    .into(),
+                &sender,
+            )
+            .await;
+
+            let leaves = loop {
+                let event = receiver
+                    .recv_direct()
+                    .await
+                    .context("The event stream closed")?;
+                if let HotShotEvent::LeafRangeResponseRecv(responder, leaves) = event.as_ref() {
+                    if *responder == archive {
+                        break leaves.clone();
+                    }
+                }
+            };
+            ensure!(
+                leaves.len() as u64 <= count,
+                "The archive returned {} leaves, more than the {count} requested",
+                leaves.len()
+            );
+
+            let (genesis_state, _) = TYPES::ValidatedState::genesis(&instance_state);
+            let genesis_leaf = Leaf2::genesis(&genesis_state, &instance_state)
+                .await
+                .commit();
+
+            let mut finalized: Vec<FinalizedLeaf<TYPES>> = Vec::with_capacity(leaves.len());
+            for (leaf, qc) in leaves {
+                let height = from_height + finalized.len() as u64;
+                ensure!(
+                    leaf.height() == height,
+                    "The archive returned the leaf at height {} instead of {height}",
+                    leaf.height()
+                );
+                if let Some((parent, ..)) = finalized.last() {
+                    ensure!(
+                        leaf.parent_commitment() == parent.commit(),
+                        "The archived leaf at height {height} does not extend its parent"
+                    );
+                }
+                let proof = FinalityProof::from_qc(&qc);
+                ensure!(
+                    proof
+                        .verify_with_membership(
+                            &leaf,
+                            genesis_leaf,
+                            &*mem.read().await,
+                            &upgrade_lock
+                        )
+                        .await,
+                    "The archived leaf at height {height} is not certified"
+                );
+                finalized.push((leaf, qc, proof));
+            }
+
+            Ok(finalized)
+        })
+    }
+
+    /// Get up to `count` of the finalized leaves this node retains from block height
+    /// `from_height` on, oldest first. An archival node retains them all.
+    pub async fn finalized_leaves(
+        &self,
+        from_height: u64,
+        count: usize,
+    ) -> Vec<FinalizedLeaf<TYPES>> {
+        self.hotshot
+            .finality_log
+            .read()
+            .await
+            .range(from_height, count)
+    }
+
+    /// Get the block height at which the transaction with `commitment` was finalized, if this is
+    /// an archival node and it was
+    pub async fn transaction_height(
+        &self,
+        commitment: &Commitment<TYPES::Transaction>,
+    ) -> Option<u64> {
+        self.hotshot
+            .finality_log
+            .read()
+            .await
+            .transaction_height(commitment)
+    }
+
+    /// Ask the quorum to attest to `message` as of the leaf this node decided in `view`, under
+    /// `nonce`, which must not have been used for another message. Resolves once members with
+    /// enough stake have signed the attestation. If too few members accept the message this will
+    /// block forever, so callers should bound it with a timeout.
+    ///
+    /// # Errors
+    /// Errors if this node no longer retains the leaf decided in `view`, or if signing the request
+    /// fails
+    pub async fn request_attestation(
+        &self,
+        view: TYPES::View,
+        nonce: u64,
+        message: Vec<u8>,
+    ) -> Result<Attestation<TYPES>> {
+        let leaf = self
+            .hotshot
+            .finality_log
+            .read()
+            .await
+            .leaf(view)
+            .cloned()
+            .with_context(|| format!("No decided leaf retained for view {view:?}"))?;
+        let epoch = TYPES::Epoch::new(epoch_from_block_number(leaf.height(), self.epoch_height));
+        let data = AttestationData {
+            height: leaf.height(),
+            leaf_commit: leaf.commit(),
+            epoch,
+            requester: self.public_key().clone(),
+            nonce,
+            message_digest: message_digest(&message),
+        };
+        let vote = AttestationVote::create_signed_vote(
+            data,
+            view,
+            self.public_key(),
+            self.private_key(),
+            &self.hotshot.upgrade_lock,
+        )
+        .await
+        .context("Failed to sign the attestation request")?;
+
+        let mut accumulator: VoteAccumulator<
+            TYPES,
+            AttestationVote<TYPES>,
+            AttestationCertificate<TYPES>,
+            V,
+        > = VoteAccumulator {
+            vote_outcomes: HashMap::new(),
+            signers: HashMap::new(),
+            phantom: PhantomData,
+            upgrade_lock: self.hotshot.upgrade_lock.clone(),
+            verified_votes: self.hotshot.verified_votes.clone(),
+        };
+        // Our own signature counts towards the attestation
+        if let Some(certificate) = accumulator
+            .accumulate(&vote, &self.memberships, epoch)
+            .await
+            .right()
+        {
+            return Ok(Attestation {
+                certificate,
+                message,
+            });
+        }
+
+        let mut receiver = self.internal_event_stream.1.activate_cloned();
+        broadcast_event(
+            HotShotEvent::AttestationRequestSend(AttestationRequest {
+                vote: vote.clone(),
+                message: message.clone(),
+            })
+            .into(),
+            &self.internal_event_stream.0,
+        )
+        .await;
+
+        loop {
+            let event = receiver
+                .recv_direct()
+                .await
+                .context("The event stream closed")?;
+            let HotShotEvent::AttestationVoteRecv(response) = event.as_ref() else {
+                continue;
+            };
+            if response.data != vote.data || response.view_number() != view {
+                continue;
+            }
+            if let Some(certificate) = accumulator
+                .accumulate(response, &self.memberships, epoch)
+                .await
+                .right()
+            {
+                return Ok(Attestation {
+                    certificate,
+                    message,
+                });
+            }
+        }
+    }
+
     /// HACK so we can know the types when running tests...
     /// there are two cleaner solutions:
     /// - make the stream generic and in nodetypes or nodeimpelmentation
@@ -270,26 +672,277 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
 
     /// Submits a transaction to the backing [`SystemContext`] instance.
     ///
-    /// The current node broadcasts the transaction to all nodes on the network.
+    /// The current node broadcasts the transaction to all nodes on the network, and returns a
+    /// receipt which resolves once the transaction is decided or expires.
     ///
     /// # Errors
     ///
-    /// Will return a [`HotShotError`] if some error occurs in the underlying
+    /// Will return a [`HotShotError`] if the transaction is rejected by the
+    /// [`SubmissionLimits`] of this node, or some error occurs in the underlying
     /// [`SystemContext`] instance.
     pub async fn submit_transaction(
         &self,
         tx: TYPES::Transaction,
-    ) -> Result<(), HotShotError<TYPES>> {
+    ) -> Result<TxReceiptHandle<TYPES>, HotShotError<TYPES>> {
         self.hotshot.publish_transaction_async(tx).await
     }
 
+    /// Submits a batch of transactions to the backing [`SystemContext`] instance, admitting them
+    /// with a single update of the pending transactions and announcing them in a single event.
+    ///
+    /// Returns, in the order of `txs`, a receipt for each admitted transaction or the
+    /// [`HotShotError`] it was rejected with.
+    pub async fn submit_transactions(
+        &self,
+        txs: Vec<TYPES::Transaction>,
+    ) -> Vec<Result<TxReceiptHandle<TYPES>, HotShotError<TYPES>>> {
+        self.hotshot.publish_transactions_async(txs).await
+    }
+
+    /// Submits the transactions of `txs` as they arrive, in batches of up to `batch_size` of
+    /// those ready at once, yielding a receipt or rejection for each in order.
+    pub fn submit_transaction_stream<'a>(
+        &'a self,
+        txs: impl Stream<Item = TYPES::Transaction> + 'a,
+        batch_size: usize,
+    ) -> impl Stream<Item = Result<TxReceiptHandle<TYPES>, HotShotError<TYPES>>> + 'a {
+        txs.ready_chunks(batch_size.max(1))
+            .then(move |batch| self.submit_transactions(batch))
+            .flat_map(futures::stream::iter)
+    }
+
+    /// Accept submitted transactions as `limits` say from now on
+    pub async fn set_submission_limits(&self, limits: SubmissionLimits) {
+        self.hotshot.set_submission_limits(limits).await;
+    }
+
     /// Get the underlying consensus state for this [`SystemContext`]
     #[must_use]
     pub fn consensus(&self) -> Arc<RwLock<Consensus<TYPES>>> {
         self.hotshot.consensus()
     }
 
+    /// Obtain a stream of finalized leaves, each with the QC which certifies it and a compact
+    /// proof of that QC, in increasing view order.
+    ///
+    /// If `from_view` is given, the stream first replays the finalized leaves this node still
+    /// retains from that view onwards. See [`FinalityStream`] for how slow consumers are handled.
+    pub async fn finality_stream(&self, from_view: Option<TYPES::View>) -> FinalityStream<TYPES> {
+        finality_stream(Arc::clone(&self.hotshot.finality_log), from_view).await
+    }
+
+    /// Get the evidence of protocol violations this node has observed so far
+    pub async fn collected_evidence(&self) -> Vec<SignedEvidence<TYPES>> {
+        self.hotshot.evidence.read().await.clone()
+    }
+
+    /// Get the certificate of the latest checkpoint of the application state, if any
+    pub async fn checkpoint_certificate(&self) -> Option<CheckpointCertificate<TYPES>> {
+        self.hotshot
+            .consensus()
+            .read()
+            .await
+            .checkpoint_certificate()
+            .cloned()
+    }
+
+    /// Get the liveness score of a validator, between 0 and 1
+    pub async fn liveness_score(&self, key: &TYPES::SignatureKey) -> f64 {
+        self.hotshot.consensus().read().await.liveness().score(key)
+    }
+
+    /// Report the health of this node: its view, when it last decided, how many peers it is
+    /// connected to, whether its storage works, how far it lags behind the network, whether it
+    /// is paused and why it last changed view
+    pub async fn health(&self) -> NodeHealth {
+        self.hotshot.health().await
+    }
+
+    /// The latest view changes of this node, oldest first, with the reason for each
+    pub async fn view_changes(&self) -> Vec<ViewChangeRecord> {
+        self.hotshot.view_changes().await
+    }
+
+    /// Publish the identity of this node to every node, replacing the one it published before
+    ///
+    /// Nodes only keep the identities of validators with stake. The identity is gossiped again
+    /// every [`VALIDATOR_METADATA_INTERVAL`](hotshot_types::constants::VALIDATOR_METADATA_INTERVAL)
+    /// for the nodes joining later, but not across restarts, so publish it again at startup.
+    ///
+    /// # Errors
+    /// Errors if a field of `metadata` is too long or contains control characters, if signing
+    /// fails, or if the identity cannot be stored
+    pub async fn set_validator_metadata(
+        &self,
+        metadata: ValidatorMetadata,
+    ) -> Result<SignedValidatorMetadata<TYPES>> {
+        let public_key = self.hotshot.public_key.clone();
+        // Sequence numbers follow the clock, so that they keep increasing across restarts
+        let sequence = self
+            .hotshot
+            .validator_metadata
+            .read()
+            .await
+            .get(&public_key)
+            .map_or(0, |current| current.sequence + 1)
+            .max(now_ms());
+        let record =
+            SignedValidatorMetadata::new(metadata, sequence, public_key, &self.hotshot.private_key)
+                .map_err(|e| anyhow!("Invalid validator metadata: {e}"))?;
+
+        self.storage
+            .read()
+            .await
+            .update_validator_metadata(&record)
+            .await
+            .context("Failed to store the validator metadata")?;
+        self.hotshot
+            .validator_metadata
+            .write()
+            .await
+            .insert(record.clone());
+        broadcast_event(
+            HotShotEvent::ValidatorMetadataSend(record.clone()).into(),
+            &self.internal_event_stream.0,
+        )
+        .await;
+        Ok(record)
+    }
+
+    /// The latest identity published by each validator with stake, by key
+    pub async fn validator_metadata(&self) -> Vec<SignedValidatorMetadata<TYPES>> {
+        self.hotshot.validator_metadata().await
+    }
+
+    /// Stop sending votes and proposals, e.g. during maintenance
+    ///
+    /// The node stays connected, keeps following the chain, and keeps serving data to its peers.
+    pub fn pause(&self) {
+        self.hotshot.set_paused(true);
+    }
+
+    /// Resume sending votes and proposals after [`pause`](Self::pause), unless this node is a
+    /// watcher
+    pub fn resume(&self) {
+        self.hotshot.set_paused(false);
+    }
+
+    /// Whether participation in consensus is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.hotshot.is_paused()
+    }
+
+    /// Change parameters of this node from the next view change on, see
+    /// [`SystemContext::update_config`]
+    ///
+    /// # Errors
+    /// if a timeout is zero or the log filter is invalid
+    pub async fn update_config(&self, update: ConfigUpdate) -> Result<(), HotShotError<TYPES>> {
+        self.hotshot.update_config(update).await
+    }
+
+    /// Check the TOML file at `path` for parameter changes every `interval`, until the returned
+    /// task is aborted
+    ///
+    /// Whenever the file is modified, it is read as a [`ConfigUpdate`] and queued as by
+    /// [`update_config`](Self::update_config). Files which cannot be read or are invalid are
+    /// logged and skipped until they are modified again.
+    pub fn watch_config_file(&self, path: PathBuf, interval: Duration) -> JoinHandle<()> {
+        watch_config_file(Arc::clone(&self.hotshot), path, interval)
+    }
+
+    /// Serve health probes for this node on `address`, until the returned task is aborted.
+    ///
+    /// `GET /livez` succeeds while the node can report its health, `GET /readyz` while it is also
+    /// within `thresholds`, and `GET /health` returns its [`NodeHealth`] as JSON.
+    pub fn serve_health(
+        &self,
+        address: SocketAddr,
+        thresholds: HealthThresholds,
+    ) -> JoinHandle<()> {
+        serve_health(Arc::clone(&self.hotshot), address, thresholds)
+    }
+
+    /// Take a snapshot of the consensus state of this node
+    pub async fn consensus_dump(&self) -> ConsensusDump<TYPES> {
+        self.hotshot.consensus_dump().await
+    }
+
+    /// Call `hook` with every alert the watchdog raises, until the returned task is aborted
+    ///
+    /// The watchdog only runs if [`HotShotConfig::watchdog`](hotshot_types::HotShotConfig::watchdog)
+    /// is set.
+    pub fn on_alert(&self, hook: impl Fn(&Alert) + Send + 'static) -> JoinHandle<()> {
+        let mut events = self.event_stream_known_impl();
+        spawn(async move {
+            while let Some(event) = events.next().await {
+                if let EventType::Alert { alert } = &event.event {
+                    hook(alert);
+                }
+            }
+        })
+    }
+
+    /// Write a snapshot of the consensus state of this node to `path` as JSON
+    ///
+    /// # Errors
+    /// if the file cannot be written
+    pub async fn dump_state(&self, path: &Path) -> std::io::Result<()> {
+        self.consensus_dump().await.write(path)
+    }
+
+    /// Write a snapshot of the consensus state of this node to a new file in `dir` if the process
+    /// panics, so the crash can be debugged afterwards
+    pub fn dump_state_on_panic(&self, dir: PathBuf) {
+        dump_state_on_panic(&self.hotshot, dir);
+    }
+
+    /// Serve the admin interface of this node on the loopback `address`, until the returned task
+    /// is aborted. Only requests carrying `token` as a bearer token are answered.
+    ///
+    /// `GET /consensus` returns a [`ConsensusDump`], `POST /view-sync` starts view sync,
+    /// `POST /storage/compact` compacts the storage, `POST /peers/<key>/disconnect`
+    /// disconnects the peer with the tagged base64 `<key>`, and `GET`/`PUT /log-filter` read and
+    /// replace the log filter.
+    pub fn serve_admin(&self, address: SocketAddr, token: String) -> JoinHandle<()> {
+        serve_admin(
+            Arc::clone(&self.hotshot),
+            self.internal_event_stream.0.clone(),
+            address,
+            token,
+        )
+    }
+
+    /// Serve the event stream of this node over WebSocket on `address`, until the returned task
+    /// is aborted. See [`event_server`](crate::event_server) for the protocol.
+    #[cfg(feature = "event-server")]
+    pub fn serve_events(&self, address: SocketAddr) -> JoinHandle<()> {
+        crate::event_server::serve_events(self.event_stream_known_impl(), address)
+    }
+
+    /// Serve the gRPC API of this node on `address`, until the returned task is aborted. See
+    /// [`grpc`](crate::grpc) for the service.
+    #[cfg(feature = "grpc")]
+    pub fn serve_grpc(&self, address: SocketAddr) -> JoinHandle<()> {
+        crate::grpc::serve_grpc(Arc::clone(&self.hotshot), address)
+    }
+
+    /// Serve the read-only HTTP API of this node on `address`, until the returned task is
+    /// aborted. See [`rest_api`](crate::rest_api) for the routes.
+    #[cfg(feature = "rest-api")]
+    pub fn serve_rest_api(&self, address: SocketAddr) -> JoinHandle<()> {
+        crate::rest_api::serve_rest_api(Arc::clone(&self.hotshot), address)
+    }
+
     /// Shut down the the inner hotshot and wait until all background threads are closed.
+    ///
+    /// The consensus tasks are stopped first, so nothing is written to storage afterwards. The
+    /// storage is then flushed, and only then the network and its tasks are shut down.
+    ///
+    /// Once this returns, a new node can be started in the same process from a clone of
+    /// [`storage`](Self::storage), with an initializer built from what it persisted by
+    /// [`HotShotInitializer::from_reload`](crate::HotShotInitializer::from_reload).
     pub async fn shut_down(&mut self) {
         // this is required because `SystemContextHandle` holds an inactive receiver and
         // `broadcast_direct` below can wait indefinitely
@@ -301,14 +954,19 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
             .await
             .inspect_err(|err| tracing::error!("Failed to send shutdown event: {err}"));
 
+        tracing::error!("Shutting down consensus!");
+        self.consensus_registry.shutdown().await;
+
+        tracing::error!("Flushing storage!");
+        if let Err(e) = self.storage.read().await.flush().await {
+            tracing::error!("Failed to flush storage: {e}");
+        }
+
         tracing::error!("Shutting down the network!");
         self.hotshot.network.shut_down().await;
 
         tracing::error!("Shutting down network tasks!");
         self.network_registry.shutdown().await;
-
-        tracing::error!("Shutting down consensus!");
-        self.consensus_registry.shutdown().await;
     }
 
     /// return the timeout for a view of the underlying `SystemContext`
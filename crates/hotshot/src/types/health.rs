@@ -0,0 +1,318 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Health of a running node, for liveness and readiness probes.
+//!
+//! [`SystemContextHandle::health`](crate::types::SystemContextHandle::health) reports a
+//! [`NodeHealth`]. [`SystemContextHandle::serve_health`](crate::types::SystemContextHandle::serve_health)
+//! answers HTTP probes on `/livez`, `/readyz` and `/health`:
+//!
+//! * `/livez` succeeds as long as the node can report its health in time, i.e. consensus is not
+//!   wedged;
+//! * `/readyz` succeeds if the node is also within the [`HealthThresholds`];
+//! * `/health` returns the [`NodeHealth`] as JSON.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use hotshot_types::{traits::node_implementation::NodeType, view_change::ViewChangeRecord};
+use serde::Serialize;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    spawn,
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::{helpers::read_http_request, traits::NodeImplementation, SystemContext, Versions};
+
+/// How long a probe waits for the health of the node before failing
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the highest view the network was seen in counts, unless it is seen again or exceeded
+const NETWORK_VIEW_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// The health of a node at one point in time
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeHealth {
+    /// the view this node is in
+    pub current_view: u64,
+    /// the view of the last leaf this node decided
+    pub last_decided_view: u64,
+    /// when this node last decided, in milliseconds since the Unix epoch, if it has yet
+    pub last_decide_time_ms: Option<u64>,
+    /// the number of peers this node is connected to, if the network can tell
+    pub connected_peers: Option<usize>,
+    /// whether the storage of this node passes its health check
+    pub storage_ok: bool,
+    /// how many views the rest of the network is ahead of this node, judging by the proposals and
+    /// certificates it validated recently
+    pub view_lag: u64,
+    /// whether participation in consensus is paused, see
+    /// [`SystemContextHandle::pause`](crate::types::SystemContextHandle::pause)
+    pub paused: bool,
+    /// the latest view change of this node, with the reason for it
+    pub last_view_change: Option<ViewChangeRecord>,
+}
+
+impl NodeHealth {
+    /// Whether the node is within `thresholds`, and so ready to serve
+    #[must_use]
+    pub fn is_ready(&self, thresholds: &HealthThresholds) -> bool {
+        let decided_recently = thresholds.max_time_since_decide.map_or(true, |max| {
+            self.last_decide_time_ms.is_some_and(|time_ms| {
+                now_ms().saturating_sub(time_ms)
+                    <= u64::try_from(max.as_millis()).unwrap_or(u64::MAX)
+            })
+        });
+
+        self.storage_ok
+            && self.view_lag <= thresholds.max_view_lag
+            && self.connected_peers.unwrap_or(usize::MAX) >= thresholds.min_connected_peers
+            && decided_recently
+    }
+}
+
+/// The bounds within which a node is ready
+#[derive(Clone, Debug)]
+pub struct HealthThresholds {
+    /// the most views the node may lag behind the network
+    pub max_view_lag: u64,
+    /// the longest the node may go without deciding, if bounded
+    pub max_time_since_decide: Option<Duration>,
+    /// the fewest peers the node must be connected to, if the network can tell
+    pub min_connected_peers: usize,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_view_lag: 10,
+            max_time_since_decide: None,
+            min_connected_peers: 0,
+        }
+    }
+}
+
+/// The progress of a node and of the network around it, as seen by the health task
+#[derive(Debug, Default)]
+pub struct HealthTracker {
+    /// the highest view of a validated proposal or certificate, and when it was last seen
+    network_view: Mutex<Option<(u64, Instant)>>,
+    /// when this node last decided, in milliseconds since the Unix epoch, or 0 if it has not yet
+    last_decide_ms: AtomicU64,
+}
+
+impl HealthTracker {
+    /// Record that the network reached `view`, as shown by a validated proposal or certificate
+    pub fn record_network_view(&self, view: u64) {
+        self.record_network_view_at(view, Instant::now());
+    }
+
+    /// Record that the network reached `view` at `now`. A lower view replaces the highest one once
+    /// that is older than `NETWORK_VIEW_MAX_AGE`.
+    fn record_network_view_at(&self, view: u64, now: Instant) {
+        let mut network_view = self
+            .network_view
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let replace = (*network_view).map_or(true, |(highest, seen)| {
+            view >= highest || now.saturating_duration_since(seen) > NETWORK_VIEW_MAX_AGE
+        });
+        if replace {
+            *network_view = Some((view, now));
+        }
+    }
+
+    /// Record that this node decided just now
+    pub fn record_decide(&self) {
+        self.last_decide_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// The highest view of a proposal or certificate validated within the last
+    /// `NETWORK_VIEW_MAX_AGE`, or 0 if there was none
+    #[must_use]
+    pub fn network_view(&self) -> u64 {
+        self.network_view_at(Instant::now())
+    }
+
+    /// The highest view of a proposal or certificate validated within `NETWORK_VIEW_MAX_AGE`
+    /// before `now`, or 0 if there was none
+    fn network_view_at(&self, now: Instant) -> u64 {
+        let network_view = *self
+            .network_view
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        network_view
+            .filter(|(_, seen)| now.saturating_duration_since(*seen) <= NETWORK_VIEW_MAX_AGE)
+            .map_or(0, |(view, _)| view)
+    }
+
+    /// When this node last decided, in milliseconds since the Unix epoch, if it has yet
+    #[must_use]
+    pub fn last_decide_ms(&self) -> Option<u64> {
+        Some(self.last_decide_ms.load(Ordering::Relaxed)).filter(|&time_ms| time_ms != 0)
+    }
+}
+
+/// Milliseconds since the Unix epoch
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+/// Serve the health probes of `context` at `address`, until the returned task is aborted
+pub(crate) fn serve_health<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: Arc<SystemContext<TYPES, I, V>>,
+    address: SocketAddr,
+    thresholds: HealthThresholds,
+) -> JoinHandle<()> {
+    spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to serve health probes on {address}: {e}");
+                return;
+            }
+        };
+        tracing::info!("Serving health probes on http://{address}");
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Failed to accept a health probe: {e}");
+                    continue;
+                }
+            };
+            let context = Arc::clone(&context);
+            let thresholds = thresholds.clone();
+            spawn(async move {
+                if let Err(e) = respond(&context, &thresholds, stream).await {
+                    tracing::debug!("Failed to respond to a health probe: {e}");
+                }
+            });
+        }
+    })
+}
+
+/// Answer a single health probe
+async fn respond<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    context: &SystemContext<TYPES, I, V>,
+    thresholds: &HealthThresholds,
+    mut stream: TcpStream,
+) -> std::io::Result<()> {
+    let request = read_http_request(&mut stream).await?;
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(path @ ("/livez" | "/readyz" | "/health"))) => {
+            match timeout(PROBE_TIMEOUT, context.health()).await {
+                Err(_) => ("503 Service Unavailable", String::new()),
+                Ok(health) => {
+                    let ok = path != "/readyz" || health.is_ready(thresholds);
+                    let status = if ok {
+                        "200 OK"
+                    } else {
+                        "503 Service Unavailable"
+                    };
+                    let body = if path == "/health" {
+                        serde_json::to_string(&health).unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    (status, body)
+                }
+            }
+        }
+        _ => ("404 Not Found", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn readiness_follows_thresholds() {
+        let health = NodeHealth {
+            current_view: 100,
+            last_decided_view: 98,
+            last_decide_time_ms: Some(now_ms()),
+            connected_peers: Some(4),
+            storage_ok: true,
+            view_lag: 3,
+            paused: false,
+            last_view_change: None,
+        };
+        let thresholds = HealthThresholds {
+            max_view_lag: 5,
+            max_time_since_decide: Some(Duration::from_secs(60)),
+            min_connected_peers: 4,
+        };
+        assert!(health.is_ready(&thresholds));
+
+        assert!(!NodeHealth {
+            view_lag: 6,
+            ..health.clone()
+        }
+        .is_ready(&thresholds));
+        assert!(!NodeHealth {
+            storage_ok: false,
+            ..health.clone()
+        }
+        .is_ready(&thresholds));
+        assert!(!NodeHealth {
+            connected_peers: Some(3),
+            ..health.clone()
+        }
+        .is_ready(&thresholds));
+        assert!(!NodeHealth {
+            last_decide_time_ms: None,
+            ..health.clone()
+        }
+        .is_ready(&thresholds));
+        assert!(NodeHealth {
+            last_decide_time_ms: None,
+            ..health
+        }
+        .is_ready(&HealthThresholds::default()));
+    }
+
+    #[test]
+    fn network_view_expires() {
+        let tracker = HealthTracker::default();
+        let start = Instant::now();
+        assert_eq!(tracker.network_view_at(start), 0);
+
+        tracker.record_network_view_at(100, start);
+        tracker.record_network_view_at(50, start);
+        assert_eq!(tracker.network_view_at(start), 100);
+
+        // Once the highest view is no longer seen, it stops counting and lower views replace it
+        let later = start + NETWORK_VIEW_MAX_AGE + Duration::from_secs(1);
+        assert_eq!(tracker.network_view_at(later), 0);
+        tracker.record_network_view_at(50, later);
+        assert_eq!(tracker.network_view_at(later), 50);
+    }
+}
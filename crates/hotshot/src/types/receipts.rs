@@ -0,0 +1,286 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Admission control for submitted transactions, and receipts to follow them until they are
+//! decided.
+//!
+//! [`SystemContextHandle::submit_transaction`](crate::types::SystemContextHandle::submit_transaction)
+//! rejects a transaction if it is larger than the [`SubmissionLimits`] allow, if it is already
+//! pending, or if too many transactions are pending or they use up their memory budget. Otherwise it returns a [`TxReceiptHandle`]
+//! resolving to the [`TransactionOutcome`] of the transaction.
+//!
+//! Inclusion is only noticed in blocks whose payload this node holds, i.e. on DA nodes. On other
+//! nodes transactions expire instead.
+
+use std::{collections::HashMap, hash::Hash};
+
+use committable::Commitment;
+use hotshot_types::{
+    error::TransactionRejection,
+    memory_budget::{MemoryBudget, MemoryComponent},
+    traits::node_implementation::NodeType,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// Which transactions a node accepts for submission
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionLimits {
+    /// the largest message carrying a transaction, in bytes
+    pub max_transaction_size: usize,
+    /// the most transactions pending at once
+    pub capacity: usize,
+    /// how many views after its submission a transaction expires if it was not decided
+    pub expiry_views: u64,
+}
+
+impl Default for SubmissionLimits {
+    fn default() -> Self {
+        Self {
+            max_transaction_size: 1024 * 1024,
+            capacity: 10_000,
+            expiry_views: 100,
+        }
+    }
+}
+
+/// What became of a submitted transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionOutcome {
+    /// the transaction was included in a decided block
+    Included {
+        /// the view of the leaf of the block
+        view: u64,
+        /// the height of the block
+        block_height: u64,
+    },
+    /// the transaction was not decided within [`SubmissionLimits::expiry_views`] views
+    Expired,
+    /// the node shut down before the transaction was decided or expired
+    Dropped,
+}
+
+/// A receipt for a submitted transaction, resolving once its outcome is known
+pub struct TxReceiptHandle<TYPES: NodeType> {
+    /// the commitment of the transaction
+    commitment: Commitment<TYPES::Transaction>,
+    /// receives the outcome of the transaction
+    outcome: oneshot::Receiver<TransactionOutcome>,
+}
+
+impl<TYPES: NodeType> TxReceiptHandle<TYPES> {
+    /// A receipt for the transaction with `commitment`
+    pub(crate) fn new(
+        commitment: Commitment<TYPES::Transaction>,
+        outcome: oneshot::Receiver<TransactionOutcome>,
+    ) -> Self {
+        Self {
+            commitment,
+            outcome,
+        }
+    }
+
+    /// The commitment of the transaction
+    #[must_use]
+    pub fn commitment(&self) -> Commitment<TYPES::Transaction> {
+        self.commitment
+    }
+
+    /// Wait until the transaction is decided, expires, or the node shuts down
+    pub async fn outcome(self) -> TransactionOutcome {
+        self.outcome.await.unwrap_or(TransactionOutcome::Dropped)
+    }
+}
+
+/// The transactions submitted to a node which have not been decided or expired, by commitment
+pub(crate) struct PendingTransactions<K> {
+    /// which transactions are accepted
+    limits: SubmissionLimits,
+    /// the view each pending transaction was submitted in, the size of the message carrying it,
+    /// and where its outcome goes
+    pending: HashMap<K, (u64, usize, oneshot::Sender<TransactionOutcome>)>,
+    /// accounts for the memory of the pending transactions
+    memory_budget: MemoryBudget,
+}
+
+impl<K> Default for PendingTransactions<K> {
+    fn default() -> Self {
+        Self::new(MemoryBudget::default())
+    }
+}
+
+impl<K> PendingTransactions<K> {
+    /// No pending transactions, which may use up to their budget in `memory_budget`
+    pub(crate) fn new(memory_budget: MemoryBudget) -> Self {
+        Self {
+            limits: SubmissionLimits::default(),
+            pending: HashMap::new(),
+            memory_budget,
+        }
+    }
+}
+
+impl<K: Eq + Hash> PendingTransactions<K> {
+    /// Accept transactions as `limits` say from now on; already pending ones stay pending
+    pub(crate) fn set_limits(&mut self, limits: SubmissionLimits) {
+        self.limits = limits;
+    }
+
+    /// Accept the transaction `key`, carried in a message of `size` bytes, in `view`
+    ///
+    /// # Errors
+    /// if the transaction is too large, already pending, or too many transactions are pending or
+    /// they use up their memory budget
+    pub(crate) fn admit(
+        &mut self,
+        key: K,
+        size: usize,
+        view: u64,
+    ) -> Result<oneshot::Receiver<TransactionOutcome>, TransactionRejection> {
+        if size > self.limits.max_transaction_size {
+            return Err(TransactionRejection::TooLarge {
+                size,
+                max: self.limits.max_transaction_size,
+            });
+        }
+        if self.pending.contains_key(&key) {
+            return Err(TransactionRejection::Duplicate);
+        }
+        if self.pending.len() >= self.limits.capacity {
+            return Err(TransactionRejection::Full(self.limits.capacity));
+        }
+        if !self
+            .memory_budget
+            .try_reserve(MemoryComponent::PendingTransactions, size)
+        {
+            return Err(TransactionRejection::OverMemoryBudget);
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.pending.insert(key, (view, size, sender));
+        Ok(receiver)
+    }
+
+    /// Record that the transaction `key` was included in the block at `block_height`, decided in
+    /// `view`
+    pub(crate) fn include(&mut self, key: &K, view: u64, block_height: u64) {
+        if let Some((_, size, sender)) = self.pending.remove(key) {
+            self.memory_budget
+                .release(MemoryComponent::PendingTransactions, size);
+            let _ = sender.send(TransactionOutcome::Included { view, block_height });
+        }
+    }
+
+    /// Expire the transactions which were not decided by the time `view` was
+    pub(crate) fn expire(&mut self, view: u64) {
+        let expiry_views = self.limits.expiry_views;
+        let (expired, pending): (HashMap<_, _>, HashMap<_, _>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(_, (submitted, ..))| view.saturating_sub(*submitted) > expiry_views);
+        self.pending = pending;
+        for (_, (_, size, sender)) in expired {
+            self.memory_budget
+                .release(MemoryComponent::PendingTransactions, size);
+            let _ = sender.send(TransactionOutcome::Expired);
+        }
+    }
+
+    /// Give up on every pending transaction, e.g. on shutdown
+    pub(crate) fn clear(&mut self) {
+        let size = self.pending.values().map(|(_, size, _)| size).sum();
+        self.pending.clear();
+        self.memory_budget
+            .release(MemoryComponent::PendingTransactions, size);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hotshot_types::memory_budget::MemoryBudgetConfig;
+
+    use super::*;
+
+    #[test]
+    fn transactions_are_admitted_within_limits() {
+        let mut pending = PendingTransactions::<u32>::default();
+        pending.set_limits(SubmissionLimits {
+            max_transaction_size: 100,
+            capacity: 2,
+            expiry_views: 10,
+        });
+
+        assert_eq!(
+            pending.admit(1, 101, 0).unwrap_err(),
+            TransactionRejection::TooLarge {
+                size: 101,
+                max: 100
+            }
+        );
+        let mut included = pending.admit(1, 100, 0).unwrap();
+        assert_eq!(
+            pending.admit(1, 10, 0).unwrap_err(),
+            TransactionRejection::Duplicate
+        );
+        let mut expired = pending.admit(2, 10, 5).unwrap();
+        assert_eq!(
+            pending.admit(3, 10, 5).unwrap_err(),
+            TransactionRejection::Full(2)
+        );
+
+        pending.include(&1, 3, 2);
+        assert_eq!(
+            included.try_recv().unwrap(),
+            TransactionOutcome::Included {
+                view: 3,
+                block_height: 2
+            }
+        );
+        let mut dropped = pending.admit(3, 10, 10).unwrap();
+
+        pending.expire(15);
+        assert!(expired.try_recv().is_err());
+        pending.expire(16);
+        assert_eq!(expired.try_recv().unwrap(), TransactionOutcome::Expired);
+
+        pending.clear();
+        assert!(matches!(
+            dropped.try_recv(),
+            Err(oneshot::error::TryRecvError::Closed)
+        ));
+    }
+
+    #[test]
+    fn transactions_are_admitted_within_their_memory_budget() {
+        let memory_budget = MemoryBudget::new(
+            MemoryBudgetConfig {
+                pending_transactions: Some(150),
+                ..MemoryBudgetConfig::default()
+            },
+            Arc::default(),
+        );
+        let mut pending = PendingTransactions::<u32>::new(memory_budget.clone());
+
+        let _first = pending.admit(1, 100, 0).unwrap();
+        assert_eq!(
+            pending.admit(2, 100, 0).unwrap_err(),
+            TransactionRejection::OverMemoryBudget
+        );
+        let _second = pending.admit(2, 50, 0).unwrap();
+        assert_eq!(
+            memory_budget.usage(MemoryComponent::PendingTransactions),
+            150
+        );
+
+        pending.include(&1, 1, 1);
+        assert_eq!(
+            memory_budget.usage(MemoryComponent::PendingTransactions),
+            50
+        );
+        pending.clear();
+        assert_eq!(memory_budget.usage(MemoryComponent::PendingTransactions), 0);
+    }
+}
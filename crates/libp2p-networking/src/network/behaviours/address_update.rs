@@ -0,0 +1,157 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::num::NonZeroUsize;
+
+use anyhow::{ensure, Context, Result};
+use libp2p::{
+    core::{PeerRecord as SignedPeerRecord, SignedEnvelope},
+    identity::Keypair,
+    multiaddr::Protocol,
+    Multiaddr,
+};
+use libp2p_identity::PeerId;
+use lru::LruCache;
+
+/// The gossip topic nodes announce their new addresses on
+pub const ADDRESS_UPDATE_TOPIC: &str = "address_updates";
+
+/// The most peers whose newest address update is remembered, forgetting the least recently
+/// updated beyond it
+pub const MAX_ADDRESS_UPDATE_PEERS: usize = 4096;
+
+/// The most seconds since an address update was signed for it to be accepted.
+///
+/// The sequence number of an update is the time it was signed at, in seconds since the Unix
+/// epoch. We do not remember the newest update of a peer across a restart, or once it is
+/// forgotten, so only recent updates are accepted to keep an old one from being replayed then.
+pub const MAX_ADDRESS_UPDATE_AGE_SECS: u64 = 600;
+
+/// The most seconds an address update may seem to be signed in the future, for peers whose clock
+/// is ahead of ours
+pub const MAX_ADDRESS_UPDATE_CLOCK_SKEW_SECS: u64 = 60;
+
+/// Sign an announcement that the node with `keypair` is now reachable at `addresses`, replacing
+/// any address it announced before
+///
+/// # Errors
+/// If signing fails
+pub fn sign_address_update(keypair: &Keypair, addresses: Vec<Multiaddr>) -> Result<Vec<u8>> {
+    let record = SignedPeerRecord::new(keypair, addresses)
+        .with_context(|| "Failed to sign address update")?;
+    Ok(record.into_signed_envelope().into_protobuf_encoding())
+}
+
+/// `address` without the peer ID it may end in, to compare addresses of the same peer
+#[must_use]
+pub fn without_peer_id(address: &Multiaddr) -> Multiaddr {
+    address
+        .iter()
+        .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+        .collect()
+}
+
+/// Verifies the address updates announced by peers, accepting only the newest one from each
+#[derive(Debug)]
+pub struct AddressUpdates {
+    /// The sequence number of the newest update accepted from each peer
+    newest: LruCache<PeerId, u64>,
+}
+
+impl Default for AddressUpdates {
+    fn default() -> Self {
+        Self {
+            newest: LruCache::new(
+                NonZeroUsize::new(MAX_ADDRESS_UPDATE_PEERS).unwrap_or(NonZeroUsize::MIN),
+            ),
+        }
+    }
+}
+
+impl AddressUpdates {
+    /// Verify an address update announced at `now`, in seconds since the Unix epoch, returning
+    /// the peer which announced it and its new addresses
+    ///
+    /// # Errors
+    /// - If the update cannot be decoded
+    /// - If it is not signed by the key of the peer it is for
+    /// - If that peer is not one `is_known` accepts updates from
+    /// - If it was not signed recently, or is not newer than the last update accepted from that
+    ///   peer, so that an old update cannot be replayed to move a peer back to an address it left
+    pub fn accept(
+        &mut self,
+        update: &[u8],
+        is_known: impl FnOnce(&PeerId) -> bool,
+        now: u64,
+    ) -> Result<(PeerId, Vec<Multiaddr>)> {
+        let envelope = SignedEnvelope::from_protobuf_encoding(update)
+            .with_context(|| "Failed to decode address update")?;
+        let record = SignedPeerRecord::from_signed_envelope(envelope)
+            .with_context(|| "Address update is not signed by the peer it is for")?;
+
+        let peer = record.peer_id();
+        ensure!(is_known(&peer), "Address update of unknown peer {peer}");
+
+        let seq = record.seq();
+        ensure!(
+            seq.saturating_add(MAX_ADDRESS_UPDATE_AGE_SECS) >= now
+                && seq <= now.saturating_add(MAX_ADDRESS_UPDATE_CLOCK_SKEW_SECS),
+            "Address update {seq} of {peer} was not signed recently"
+        );
+        let newest = self.newest.get(&peer).copied().unwrap_or_default();
+        ensure!(
+            seq > newest,
+            "Address update {seq} of {peer} is not newer than {newest}"
+        );
+        self.newest.put(peer, seq);
+
+        Ok((peer, record.addresses().to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::network::behaviours::peer_store::unix_now;
+
+    #[test]
+    fn only_newest_signed_update_is_accepted() {
+        let keypair = Keypair::generate_ed25519();
+        let addresses: Vec<Multiaddr> = vec!["/ip4/10.1.2.3/udp/9000/quic-v1".parse().unwrap()];
+        let update = sign_address_update(&keypair, addresses.clone()).unwrap();
+        let now = unix_now();
+        let known = |_: &PeerId| true;
+
+        let mut updates = AddressUpdates::default();
+        assert_eq!(
+            updates.accept(&update, known, now).unwrap(),
+            (keypair.public().to_peer_id(), addresses)
+        );
+        // replayed
+        assert!(updates.accept(&update, known, now).is_err());
+        // replayed after a restart, once it is too old
+        assert!(AddressUpdates::default()
+            .accept(&update, known, now + MAX_ADDRESS_UPDATE_AGE_SECS + 1)
+            .is_err());
+        // from a peer we do not know
+        let unknown = sign_address_update(&Keypair::generate_ed25519(), Vec::new()).unwrap();
+        assert!(updates.accept(&unknown, |_| false, now).is_err());
+
+        // tampered with after signing
+        let mut tampered = sign_address_update(&Keypair::generate_ed25519(), Vec::new()).unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(updates.accept(&tampered, known, now).is_err());
+
+        let address: Multiaddr = format!("/ip4/10.1.2.3/udp/9000/quic-v1/p2p/{}", PeerId::random())
+            .parse()
+            .unwrap();
+        assert_eq!(
+            without_peer_id(&address),
+            "/ip4/10.1.2.3/udp/9000/quic-v1".parse().unwrap()
+        );
+    }
+}
@@ -4,6 +4,9 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
+/// Signed announcements of the new addresses of a node which moved
+pub mod address_update;
+
 /// Wrapper around `RequestResponse`
 pub mod direct_message;
 
@@ -12,3 +15,9 @@ pub mod exponential_backoff;
 
 /// Wrapper around Kademlia
 pub mod dht;
+
+/// Ranking peers by round trip time and region
+pub mod peer_selection;
+
+/// Remembering peers and their reputation across restarts
+pub mod peer_store;
@@ -0,0 +1,183 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::HashMap, time::Duration};
+
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use libp2p_identity::PeerId;
+
+/// The round trip time assumed for a peer which was not measured yet
+pub const UNMEASURED_RTT: Duration = Duration::from_millis(250);
+
+/// The weight of a new round trip time measurement in the smoothed round trip time of a peer
+const RTT_SMOOTHING: f64 = 0.25;
+
+/// What is known about a peer to rank it
+#[derive(Clone, Debug, Default)]
+struct PeerScore {
+    /// the smoothed round trip time to the peer, once measured
+    rtt: Option<Duration>,
+    /// the region the peer is in, if known
+    region: Option<String>,
+}
+
+/// Ranks peers by round trip time and region, to choose which peers to stay connected to when
+/// there are more than needed.
+///
+/// Peers with a lower round trip time are preferred, but each peer already chosen in a region
+/// counts against the next one there, so that the chosen peers stay spread over the regions.
+#[derive(Clone, Debug, Default)]
+pub struct PeerSelection {
+    /// what is known about each peer
+    peers: HashMap<PeerId, PeerScore>,
+}
+
+impl PeerSelection {
+    /// Create a selection where the peers in `regions` are known to be in the given region. The
+    /// region of any other peer is derived from its address.
+    #[must_use]
+    pub fn new(regions: HashMap<PeerId, String>) -> Self {
+        Self {
+            peers: regions
+                .into_iter()
+                .map(|(peer, region)| {
+                    let score = PeerScore {
+                        rtt: None,
+                        region: Some(region),
+                    };
+                    (peer, score)
+                })
+                .collect(),
+        }
+    }
+
+    /// Record a round trip time measured to `peer`
+    pub fn record_rtt(&mut self, peer: PeerId, rtt: Duration) {
+        let score = self.peers.entry(peer).or_default();
+        score.rtt = Some(score.rtt.map_or(rtt, |smoothed| {
+            smoothed.mul_f64(1.0 - RTT_SMOOTHING) + rtt.mul_f64(RTT_SMOOTHING)
+        }));
+    }
+
+    /// Record an address `peer` is reachable at, which gives its region unless already known
+    pub fn record_address(&mut self, peer: PeerId, address: &Multiaddr) {
+        let score = self.peers.entry(peer).or_default();
+        if score.region.is_none() {
+            score.region = address_region(address);
+        }
+    }
+
+    /// The smoothed round trip time to `peer`, if it was measured
+    #[must_use]
+    pub fn rtt(&self, peer: &PeerId) -> Option<Duration> {
+        self.peers.get(peer)?.rtt
+    }
+
+    /// The region of `peer`, if known
+    #[must_use]
+    pub fn region(&self, peer: &PeerId) -> Option<&str> {
+        self.peers.get(peer)?.region.as_deref()
+    }
+
+    /// Order `candidates` from most to least preferred
+    #[must_use]
+    pub fn rank(&self, candidates: impl IntoIterator<Item = PeerId>) -> Vec<PeerId> {
+        let mut remaining: Vec<_> = candidates
+            .into_iter()
+            .map(|peer| {
+                let rtt = self.rtt(&peer).unwrap_or(UNMEASURED_RTT);
+                (peer, rtt, self.region(&peer))
+            })
+            .collect();
+        // Break ties by peer ID, so that every node ranks the same peers the same way
+        remaining.sort_by_key(|(peer, rtt, _)| (*rtt, *peer));
+
+        let mut chosen_in_region: HashMap<&str, u32> = HashMap::new();
+        let mut ranked = Vec::with_capacity(remaining.len());
+        loop {
+            // Peers in unknown regions cannot be told apart, so they are not penalised
+            let cost = |(_, rtt, region): &(PeerId, Duration, Option<&str>)| {
+                let chosen = region.map_or(0, |region| {
+                    chosen_in_region.get(region).copied().unwrap_or(0)
+                });
+                rtt.saturating_mul(1 + chosen)
+            };
+            let Some((index, _)) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, candidate)| cost(candidate))
+            else {
+                break;
+            };
+            let (peer, _, region) = remaining.remove(index);
+            if let Some(region) = region {
+                *chosen_in_region.entry(region).or_default() += 1;
+            }
+            ranked.push(peer);
+        }
+        ranked
+    }
+}
+
+/// The region a peer reachable at `address` is assumed to be in, without better knowledge: its
+/// IPv4 /16 or IPv6 /32 network, or the domain its DNS name is in.
+#[must_use]
+pub fn address_region(address: &Multiaddr) -> Option<String> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => {
+            let [a, b, ..] = ip.octets();
+            Some(format!("{a}.{b}.0.0/16"))
+        }
+        Protocol::Ip6(ip) => {
+            let [a, b, ..] = ip.segments();
+            Some(format!("{a:x}:{b:x}::/32"))
+        }
+        Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => {
+            let labels: Vec<_> = name.split('.').collect();
+            Some(labels[labels.len().saturating_sub(2)..].join("."))
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefers_low_rtt_across_regions() {
+        let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+        let mut selection = PeerSelection::new(HashMap::from([(peers[3], "far".to_string())]));
+
+        // Three nearby peers in one network and one far away peer elsewhere
+        for (peer, ms) in peers.iter().zip([10, 20, 30]) {
+            selection.record_address(*peer, &"/ip4/10.1.2.3/udp/9000/quic-v1".parse().unwrap());
+            selection.record_rtt(*peer, Duration::from_millis(ms));
+        }
+        selection.record_address(peers[3], &"/ip4/10.1.9.9/udp/9000/quic-v1".parse().unwrap());
+        selection.record_rtt(peers[3], Duration::from_millis(50));
+        assert_eq!(selection.region(&peers[0]), Some("10.1.0.0/16"));
+        assert_eq!(selection.region(&peers[3]), Some("far"));
+
+        // The far peer beats the third nearby peer, which counts triple
+        assert_eq!(
+            selection.rank(peers.clone()),
+            vec![peers[0], peers[1], peers[3], peers[2]]
+        );
+        assert_eq!(
+            selection.rank([peers[1], peers[2]]),
+            vec![peers[1], peers[2]]
+        );
+
+        // Measurements are smoothed
+        selection.record_rtt(peers[0], Duration::from_millis(50));
+        let rtt = selection.rtt(&peers[0]).unwrap();
+        assert!(rtt > Duration::from_millis(19) && rtt < Duration::from_millis(21));
+
+        let unknown = PeerId::random();
+        assert_eq!(selection.rank([unknown, peers[3]]), vec![peers[3], unknown]);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use libp2p::Multiaddr;
+use libp2p_identity::PeerId;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// The most addresses remembered for a single peer, dropping the oldest beyond it
+const MAX_ADDRESSES_PER_PEER: usize = 8;
+
+/// The number of misbehaviors after which we no longer reconnect to a peer on our own
+pub const MAX_MISBEHAVIORS: u32 = 5;
+
+/// Misbehaviors of a peer closer together than this many seconds count only once, so that a
+/// single burst is not held against it as repeated misbehavior
+const MISBEHAVIOR_COOLDOWN_SECS: u64 = 60;
+
+/// One misbehavior of a peer is forgiven every this many seconds it behaves
+const MISBEHAVIOR_FORGIVENESS_SECS: u64 = 60 * 60;
+
+/// The number of changes to the store after which it is saved to its file
+const MAX_CHANGES_BEFORE_SAVE: u64 = 20;
+
+/// What we remember about a peer across restarts
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// The addresses the peer was reachable at, oldest first
+    pub addresses: Vec<Multiaddr>,
+    /// When we first heard of the peer, in seconds since the Unix epoch
+    pub first_seen_unix_secs: u64,
+    /// The total number of seconds we were connected to the peer
+    pub uptime_secs: u64,
+    /// The number of times the peer misbehaved, less those forgiven
+    pub misbehaviors: u32,
+    /// When the peer last misbehaved, in seconds since the Unix epoch
+    pub last_misbehavior_unix_secs: Option<u64>,
+}
+
+impl PeerRecord {
+    /// The misbehaviors of the peer which are not yet forgiven at `now`
+    fn misbehaviors_at(&self, now: u64) -> u32 {
+        let Some(last) = self.last_misbehavior_unix_secs else {
+            return self.misbehaviors;
+        };
+        let forgiven = now.saturating_sub(last) / MISBEHAVIOR_FORGIVENESS_SECS;
+        self.misbehaviors
+            .saturating_sub(u32::try_from(forgiven).unwrap_or(u32::MAX))
+    }
+
+    /// The reputation of the peer at `now`, between 0 and 1: the share of the time since we
+    /// first heard of it that we were connected to it, halved for every unforgiven misbehavior
+    fn reputation_at(&self, now: u64) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let uptime =
+            self.uptime_secs as f64 / now.saturating_sub(self.first_seen_unix_secs).max(1) as f64;
+        uptime.min(1.0) / 2f64.powi(i32::try_from(self.misbehaviors_at(now)).unwrap_or(i32::MAX))
+    }
+}
+
+/// The seconds since the Unix epoch
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// The peers we know of, with their addresses, uptime and misbehaviors, optionally kept in a file
+/// so that they outlive a restart
+#[derive(Debug, Default)]
+pub struct PeerStore {
+    /// What we know of each peer
+    peers: HashMap<PeerId, PeerRecord>,
+    /// Since when we are connected to each peer we are connected to, in seconds since the Unix
+    /// epoch
+    connected_since: HashMap<PeerId, u64>,
+    /// The file the store is kept in, if any
+    path: Option<String>,
+    /// The number of changes since the store was last saved
+    changes: u64,
+}
+
+impl PeerStore {
+    /// Create a store kept in the file at `path`, if any, restoring the peers already in it
+    #[must_use]
+    pub fn new(path: Option<String>) -> Self {
+        let mut store = Self {
+            path,
+            ..Self::default()
+        };
+
+        // If restoring fails, warn and start with an empty store
+        if let Err(err) = store.restore_from_file() {
+            warn!("Failed to restore peer store from file: {err:?}. Starting with empty store");
+        }
+
+        store
+    }
+
+    /// Record an address `peer` is reachable at
+    pub fn record_address(&mut self, peer: PeerId, address: &Multiaddr, now: u64) {
+        let record = self.entry(peer, now);
+        if record.addresses.contains(address) {
+            return;
+        }
+        if record.addresses.len() >= MAX_ADDRESSES_PER_PEER {
+            record.addresses.remove(0);
+        }
+        record.addresses.push(address.clone());
+        self.changed();
+    }
+
+    /// Record that we connected to `peer`
+    pub fn record_connected(&mut self, peer: PeerId, now: u64) {
+        self.entry(peer, now);
+        self.connected_since.entry(peer).or_insert(now);
+    }
+
+    /// Record that we are no longer connected to `peer`, adding the time we were to its uptime
+    pub fn record_disconnected(&mut self, peer: PeerId, now: u64) {
+        let Some(since) = self.connected_since.remove(&peer) else {
+            return;
+        };
+        self.entry(peer, now).uptime_secs += now.saturating_sub(since);
+        self.changed();
+    }
+
+    /// Record that `peer` misbehaved. Returns whether it has now misbehaved too often to
+    /// reconnect to.
+    pub fn record_misbehavior(&mut self, peer: PeerId, now: u64) -> bool {
+        let record = self.entry(peer, now);
+        let recent = record
+            .last_misbehavior_unix_secs
+            .is_some_and(|last| now.saturating_sub(last) < MISBEHAVIOR_COOLDOWN_SECS);
+        if !recent {
+            record.misbehaviors = record.misbehaviors_at(now).saturating_add(1);
+            record.last_misbehavior_unix_secs = Some(now);
+            debug!(
+                "Peer {peer:?} misbehaved, {} times unforgiven",
+                record.misbehaviors
+            );
+            self.changed();
+        }
+        self.is_banned(&peer, now)
+    }
+
+    /// Whether we know of `peer`
+    #[must_use]
+    pub fn knows(&self, peer: &PeerId) -> bool {
+        self.peers.contains_key(peer)
+    }
+
+    /// Whether `peer` misbehaved too often for us to reconnect to it on our own
+    #[must_use]
+    pub fn is_banned(&self, peer: &PeerId, now: u64) -> bool {
+        self.peers
+            .get(peer)
+            .is_some_and(|record| record.misbehaviors_at(now) >= MAX_MISBEHAVIORS)
+    }
+
+    /// The reputation of `peer` between 0 and 1, or `None` if we do not know it
+    #[must_use]
+    pub fn reputation(&self, peer: &PeerId, now: u64) -> Option<f64> {
+        let record = self.peers.get(peer)?;
+        let mut record = record.clone();
+        if let Some(since) = self.connected_since.get(peer) {
+            record.uptime_secs += now.saturating_sub(*since);
+        }
+        Some(record.reputation_at(now))
+    }
+
+    /// Drop the banned peers from `ranked`, and move the peers which misbehaved behind those
+    /// which did not, keeping the order otherwise
+    #[must_use]
+    pub fn deprioritize_misbehaving(&self, ranked: Vec<PeerId>, now: u64) -> Vec<PeerId> {
+        let mut kept: Vec<_> = ranked
+            .into_iter()
+            .filter(|peer| !self.is_banned(peer, now))
+            .collect();
+        kept.sort_by_key(|peer| {
+            self.peers
+                .get(peer)
+                .map_or(0, |record| record.misbehaviors_at(now))
+        });
+        kept
+    }
+
+    /// The known peers which are not banned with their addresses, best reputation first
+    #[must_use]
+    pub fn known_peers(&self, now: u64) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        let mut known: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(peer, record)| !record.addresses.is_empty() && !self.is_banned(peer, now))
+            .map(|(peer, record)| (*peer, record.reputation_at(now), record.addresses.clone()))
+            .collect();
+        known.sort_by(|(a, a_reputation, _), (b, b_reputation, _)| {
+            b_reputation.total_cmp(a_reputation).then(a.cmp(b))
+        });
+        known
+            .into_iter()
+            .map(|(peer, _, addresses)| (peer, addresses))
+            .collect()
+    }
+
+    /// Attempt to save the store to its file, counting the time connected so far to the uptime
+    /// of the peers we are connected to
+    ///
+    /// # Errors
+    /// - If we fail to serialize the store
+    /// - If we fail to write the serialized store to the file
+    pub fn save_to_file(&mut self, now: u64) -> anyhow::Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        for (peer, since) in &mut self.connected_since {
+            if let Some(record) = self.peers.get_mut(peer) {
+                record.uptime_secs += now.saturating_sub(*since);
+                *since = now;
+            }
+        }
+
+        let contents =
+            bincode::serialize(&self.peers).with_context(|| "Failed to serialize peer store")?;
+        std::fs::write(path, contents).with_context(|| "Failed to write peer store to file")?;
+        self.changes = 0;
+
+        debug!("Saved {} peers to file", self.peers.len());
+        Ok(())
+    }
+
+    /// Attempt to restore the store from its file
+    ///
+    /// # Errors
+    /// - If we fail to read the file
+    /// - If we fail to deserialize the file
+    fn restore_from_file(&mut self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = std::fs::read(path).with_context(|| "Failed to read peer store file")?;
+        self.peers =
+            bincode::deserialize(&contents).with_context(|| "Failed to parse peer store file")?;
+
+        debug!("Restored {} peers from file", self.peers.len());
+        Ok(())
+    }
+
+    /// The record of `peer`, created if we did not know it yet
+    fn entry(&mut self, peer: PeerId, now: u64) -> &mut PeerRecord {
+        self.peers.entry(peer).or_insert_with(|| PeerRecord {
+            first_seen_unix_secs: now,
+            ..PeerRecord::default()
+        })
+    }
+
+    /// Count a change to the store, saving it once there were enough
+    fn changed(&mut self) {
+        self.changes += 1;
+        if self.changes > MAX_CHANGES_BEFORE_SAVE {
+            if let Err(err) = self.save_to_file(unix_now()) {
+                warn!("Failed to save peer store to file: {err:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reputation_survives_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("test_peer_store_{}.bin", rand::random::<u64>()))
+            .to_string_lossy()
+            .to_string();
+        let (good, flaky, bad) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let address: Multiaddr = "/ip4/10.1.2.3/udp/9000/quic-v1".parse().unwrap();
+        let start = 1_000_000;
+
+        let mut store = PeerStore::new(Some(path.clone()));
+        for peer in [good, flaky, bad] {
+            store.record_address(peer, &address, start);
+            store.record_connected(peer, start);
+        }
+        store.record_disconnected(flaky, start + 10);
+
+        // Misbehaviors in quick succession count once
+        store.record_misbehavior(flaky, start + 10);
+        store.record_misbehavior(flaky, start + 20);
+        let mut banned = false;
+        for i in 0..u64::from(MAX_MISBEHAVIORS) {
+            banned = store.record_misbehavior(bad, start + i * MISBEHAVIOR_COOLDOWN_SECS);
+        }
+        assert!(banned);
+        store.save_to_file(start + 100).unwrap();
+
+        // After a restart, the banned peer is neither primed nor redialed, and the one which
+        // misbehaved comes after the one which did not
+        let restored = PeerStore::new(Some(path.clone()));
+        let now = start + 100;
+        assert!(restored.reputation(&good, now).unwrap() > 0.99);
+        assert!(restored.reputation(&flaky, now).unwrap() < 0.06);
+        assert_eq!(
+            restored
+                .known_peers(now)
+                .into_iter()
+                .map(|(peer, _)| peer)
+                .collect::<Vec<_>>(),
+            vec![good, flaky]
+        );
+        assert_eq!(
+            restored.deprioritize_misbehaving(vec![bad, flaky, good], now),
+            vec![good, flaky]
+        );
+
+        // Behaving for long enough earns forgiveness
+        let later = now + u64::from(MAX_MISBEHAVIORS) * MISBEHAVIOR_FORGIVENESS_SECS;
+        assert!(!restored.is_banned(&bad, later));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -10,6 +10,7 @@ use libp2p::{
     gossipsub::{Behaviour as GossipBehaviour, Event as GossipEvent, IdentTopic},
     identify::{Behaviour as IdentifyBehaviour, Event as IdentifyEvent},
     kad::store::MemoryStore,
+    ping,
     request_response::{OutboundRequestId, ResponseChannel},
     Multiaddr,
 };
@@ -54,6 +55,10 @@ pub struct NetworkDef<K: SignatureKey + 'static> {
     /// by which address
     #[debug(skip)]
     pub autonat: libp2p::autonat::Behaviour,
+
+    /// purpose: measuring the round trip time to connected peers
+    #[debug(skip)]
+    ping: ping::Behaviour,
 }
 
 impl<K: SignatureKey + 'static> NetworkDef<K> {
@@ -65,6 +70,7 @@ impl<K: SignatureKey + 'static> NetworkDef<K> {
         identify: IdentifyBehaviour,
         direct_message: super::cbor::Behaviour<Vec<u8>, Vec<u8>>,
         autonat: autonat::Behaviour,
+        ping: ping::Behaviour,
     ) -> NetworkDef<K> {
         Self {
             gossipsub,
@@ -72,6 +78,7 @@ impl<K: SignatureKey + 'static> NetworkDef<K> {
             identify,
             direct_message,
             autonat,
+            ping,
         }
     }
 }
@@ -92,11 +99,13 @@ impl<K: SignatureKey + 'static> NetworkDef<K> {
 
 /// Gossip functions
 impl<K: SignatureKey + 'static> NetworkDef<K> {
-    /// Publish a given gossip
-    pub fn publish_gossip(&mut self, topic: IdentTopic, contents: Vec<u8>) {
+    /// Publish a given gossip, returning whether it was published
+    pub fn publish_gossip(&mut self, topic: IdentTopic, contents: Vec<u8>) -> bool {
         if let Err(e) = self.gossipsub.publish(topic, contents) {
             tracing::warn!("Failed to publish gossip message. Error: {:?}", e);
+            return false;
         }
+        true
     }
     /// Subscribe to a given topic
     pub fn subscribe_gossip(&mut self, t: &str) {
@@ -154,3 +163,9 @@ impl From<libp2p::autonat::Event> for NetworkEventInternal {
         Self::AutonatEvent(event)
     }
 }
+
+impl From<ping::Event> for NetworkEventInternal {
+    fn from(event: ping::Event) -> Self {
+        Self::PingEvent(event)
+    }
+}
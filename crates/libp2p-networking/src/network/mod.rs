@@ -40,9 +40,9 @@ use transport::StakeTableAuthentication;
 pub use self::{
     def::NetworkDef,
     node::{
-        spawn_network_node, GossipConfig, NetworkNode, NetworkNodeConfig, NetworkNodeConfigBuilder,
-        NetworkNodeConfigBuilderError, NetworkNodeHandle, NetworkNodeReceiver,
-        RequestResponseConfig, DEFAULT_REPLICATION_FACTOR,
+        spawn_network_node, ConnectionTimeouts, GossipConfig, NetworkNode, NetworkNodeConfig,
+        NetworkNodeConfigBuilder, NetworkNodeConfigBuilderError, NetworkNodeHandle,
+        NetworkNodeReceiver, RequestResponseConfig, DEFAULT_REPLICATION_FACTOR,
     },
 };
 
@@ -72,6 +72,8 @@ pub enum ClientRequest {
     DirectResponse(ResponseChannel<Vec<u8>>, Vec<u8>),
     /// prune a peer
     Prune(PeerId),
+    /// report that a peer misbehaved
+    ReportMisbehavior(PeerId),
     /// add vec of known peers or addresses
     AddKnownPeers(Vec<(PeerId, Multiaddr)>),
     /// Ignore peers. Only here for debugging purposes.
@@ -110,8 +112,8 @@ pub enum ClientRequest {
 /// to relay to the client
 #[derive(Debug)]
 pub enum NetworkEvent {
-    /// Recv-ed a broadcast
-    GossipMsg(Vec<u8>),
+    /// Recv-ed a broadcast, relayed to us by the given peer
+    GossipMsg(Vec<u8>, PeerId),
     /// Recv-ed a direct message from a node
     DirectRequest(Vec<u8>, PeerId, ResponseChannel<Vec<u8>>),
     /// Recv-ed a direct response from a node (that hopefully was initiated by this node)
@@ -137,6 +139,8 @@ pub enum NetworkEventInternal {
     DMEvent(libp2p::request_response::Event<Vec<u8>, Vec<u8>>),
     /// a autonat event
     AutonatEvent(libp2p::autonat::Event),
+    /// a ping event, with the round trip time to a peer
+    PingEvent(libp2p::ping::Event),
 }
 
 /// Bind all interfaces on port `port`
@@ -162,17 +166,18 @@ pub async fn gen_transport<T: NodeType>(
     identity: Keypair,
     stake_table: Option<Arc<RwLock<T::Membership>>>,
     auth_message: Option<Vec<u8>>,
+    timeouts: ConnectionTimeouts,
 ) -> Result<BoxedTransport, NetworkError> {
     // Create the initial `Quic` transport
     let transport = {
         let mut config = quic::Config::new(&identity);
-        config.handshake_timeout = std::time::Duration::from_secs(20);
+        config.handshake_timeout = timeouts.connect;
         QuicTransport::new(config)
     };
 
     // Require authentication against the stake table
     let transport: StakeTableAuthentication<_, T, _> =
-        StakeTableAuthentication::new(transport, stake_table, auth_message);
+        StakeTableAuthentication::new(transport, stake_table, auth_message, timeouts);
 
     // Support DNS resolution
     let transport = {
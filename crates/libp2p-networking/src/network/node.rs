@@ -15,7 +15,7 @@ use std::{
     collections::{HashMap, HashSet},
     iter,
     num::{NonZeroU32, NonZeroUsize},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use futures::{channel::mpsc, SinkExt, StreamExt};
@@ -27,7 +27,8 @@ use libp2p::{
     core::transport::ListenerId,
     gossipsub::{
         Behaviour as Gossipsub, ConfigBuilder as GossipsubConfigBuilder, Event as GossipEvent,
-        Message as GossipsubMessage, MessageAuthenticity, MessageId, Topic, ValidationMode,
+        IdentTopic, Message as GossipsubMessage, MessageAuthenticity, MessageId, Topic,
+        ValidationMode,
     },
     identify::{
         Behaviour as IdentifyBehaviour, Config as IdentifyConfig, Event as IdentifyEvent,
@@ -35,10 +36,14 @@ use libp2p::{
     },
     identity::Keypair,
     kad::{store::MemoryStore, Behaviour, Config, Mode, Record},
+    ping,
     request_response::{
         Behaviour as RequestResponse, Config as Libp2pRequestResponseConfig, ProtocolSupport,
     },
-    swarm::SwarmEvent,
+    swarm::{
+        dial_opts::{DialOpts, PeerCondition},
+        ConnectionId, SwarmEvent,
+    },
     Multiaddr, StreamProtocol, Swarm, SwarmBuilder,
 };
 use libp2p_identity::PeerId;
@@ -46,13 +51,14 @@ use rand::{prelude::SliceRandom, thread_rng};
 use tokio::{
     select, spawn,
     sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::interval,
 };
 use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 
 pub use self::{
     config::{
-        GossipConfig, NetworkNodeConfig, NetworkNodeConfigBuilder, NetworkNodeConfigBuilderError,
-        RequestResponseConfig, DEFAULT_REPLICATION_FACTOR,
+        ConnectionTimeouts, GossipConfig, NetworkNodeConfig, NetworkNodeConfigBuilder,
+        NetworkNodeConfigBuilderError, RequestResponseConfig, DEFAULT_REPLICATION_FACTOR,
     },
     handle::{spawn_network_node, NetworkNodeHandle, NetworkNodeReceiver},
 };
@@ -66,9 +72,12 @@ use super::{
     NetworkEventInternal,
 };
 use crate::network::behaviours::{
+    address_update::{sign_address_update, without_peer_id, AddressUpdates, ADDRESS_UPDATE_TOPIC},
     dht::{DHTBehaviour, DHTProgress, KadPutQuery, NUM_REPLICATED_TO_TRUST},
     direct_message::{DMBehaviour, DMRequest},
     exponential_backoff::ExponentialBackoff,
+    peer_selection::PeerSelection,
+    peer_store::{unix_now, PeerStore},
 };
 
 /// Maximum size of a message
@@ -80,11 +89,20 @@ pub const ESTABLISHED_LIMIT: NonZeroU32 =
 /// Number of connections to a single peer before logging an error
 pub const ESTABLISHED_LIMIT_UNWR: u32 = 10;
 
+/// How often to check for connected peers which did not identify themselves in time
+const IDENTIFY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often to check whether our addresses changed, announcing the new ones if so
+const ADDRESS_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Network definition
 #[derive(derive_more::Debug)]
 pub struct NetworkNode<T: NodeType> {
     /// peer id of network node
     peer_id: PeerId,
+    /// the keypair of the node, which signs the announcements of its addresses
+    #[debug(skip)]
+    keypair: Keypair,
     /// the swarm of networkbehaviours
     #[debug(skip)]
     swarm: Swarm<NetworkDef<T::SignatureKey>>,
@@ -96,6 +114,24 @@ pub struct NetworkNode<T: NodeType> {
     dht_handler: DHTBehaviour<T::SignatureKey>,
     /// Channel to resend requests, set to Some when we call `spawn_listeners`
     resend_tx: Option<UnboundedSender<ClientRequest>>,
+    /// Ranks peers by round trip time and region
+    peer_selection: PeerSelection,
+    /// The most peers to stay connected to, if limited
+    max_connected_peers: Option<usize>,
+    /// Remembers the peers we know of and their reputation
+    peer_store: PeerStore,
+    /// The time a connected peer has to identify itself before we disconnect from it
+    identify_timeout: Duration,
+    /// The connected peers which have not identified themselves yet, with when we connected
+    unidentified: HashMap<PeerId, Instant>,
+    /// The open connections to each peer, with the address of the peer on each
+    connections: HashMap<PeerId, HashMap<ConnectionId, Multiaddr>>,
+    /// Verifies the address updates announced by peers
+    address_updates: AddressUpdates,
+    /// The addresses we last announced
+    announced_addresses: Vec<Multiaddr>,
+    /// The peers which moved and we are connecting to at their new addresses, with those
+    migrating: HashMap<PeerId, Vec<Multiaddr>>,
 }
 
 impl<T: NodeType> NetworkNode<T> {
@@ -109,6 +145,188 @@ impl<T: NodeType> NetworkNode<T> {
         self.swarm.connected_peers().copied().collect()
     }
 
+    /// Disconnect from the least preferred peers beyond the connection limit. Only peers whose
+    /// round trip time was measured are disconnected, so that new peers get the chance to show
+    /// they are close.
+    fn enforce_connection_limit(&mut self) {
+        let Some(max_connected_peers) = self.max_connected_peers else {
+            return;
+        };
+        let connected = self.connected_pids();
+        if connected.len() <= max_connected_peers {
+            return;
+        }
+        for peer in self
+            .peer_selection
+            .rank(connected)
+            .into_iter()
+            .skip(max_connected_peers)
+        {
+            if self.peer_selection.rtt(&peer).is_some() {
+                debug!("Disconnecting from {peer:?}, beyond the connection limit");
+                let _ = self.swarm.disconnect_peer_id(peer);
+            }
+        }
+    }
+
+    /// Disconnect from the peers which did not identify themselves within the identify timeout
+    /// of connecting
+    fn disconnect_unidentified(&mut self) {
+        let identify_timeout = self.identify_timeout;
+        let timed_out: Vec<PeerId> = self
+            .unidentified
+            .iter()
+            .filter(|(_, connected)| connected.elapsed() > identify_timeout)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in timed_out {
+            self.unidentified.remove(&peer);
+            let err = NetworkError::IdentifyTimeout(format!(
+                "{peer:?} did not identify itself within {identify_timeout:?}"
+            ));
+            warn!("Disconnecting: {err}");
+            let _ = self.swarm.disconnect_peer_id(peer);
+        }
+    }
+
+    /// Announce our addresses to all peers if they changed since we last did. At startup this
+    /// tells the peers where we are if we moved while we were down.
+    fn announce_addresses(&mut self) {
+        let mut addresses: Vec<Multiaddr> = self.swarm.external_addresses().cloned().collect();
+        if addresses.is_empty() {
+            addresses = self.swarm.listeners().cloned().collect();
+        }
+        addresses.sort();
+        if addresses.is_empty()
+            || addresses == self.announced_addresses
+            || self.num_connected() == 0
+        {
+            return;
+        }
+
+        match sign_address_update(&self.keypair, addresses.clone()) {
+            Ok(update) => {
+                if self
+                    .swarm
+                    .behaviour_mut()
+                    .publish_gossip(IdentTopic::new(ADDRESS_UPDATE_TOPIC), update)
+                {
+                    debug!("Announced our addresses {addresses:?}");
+                    self.announced_addresses = addresses;
+                }
+            }
+            Err(e) => {
+                warn!("Failed to announce our addresses: {e:?}");
+            }
+        }
+    }
+
+    /// Handle the address update announced by a peer which moved: replace its addresses in
+    /// the routing table, and if we are connected to it at an address it left, connect to it at
+    /// a new one. The connections at the old addresses are only closed once the new one is
+    /// established.
+    ///
+    /// Updates are only taken from peers we are connected to, which the transport checked
+    /// against the stake table if it has one, or know from before.
+    fn handle_address_update(&mut self, update: &[u8]) {
+        let now = unix_now();
+        let (connections, peer_store) = (&self.connections, &self.peer_store);
+        let is_known = |peer: &PeerId| connections.contains_key(peer) || peer_store.knows(peer);
+        let (peer, addresses) = match self.address_updates.accept(update, is_known, now) {
+            Ok(update) => update,
+            Err(e) => {
+                debug!("Ignoring address update: {e:?}");
+                return;
+            }
+        };
+        if peer == self.peer_id || addresses.is_empty() {
+            return;
+        }
+        info!("Peer {peer:?} announced its addresses {addresses:?}");
+
+        let behaviour = self.swarm.behaviour_mut();
+        let _ = behaviour.dht.remove_peer(&peer);
+        for address in &addresses {
+            behaviour.dht.add_address(&peer, address.clone());
+            self.peer_selection.record_address(peer, address);
+            self.peer_store.record_address(peer, address, now);
+        }
+
+        let Some(connections) = self.connections.get(&peer) else {
+            return;
+        };
+        if connections
+            .values()
+            .any(|address| is_one_of(address, &addresses))
+        {
+            self.close_stale_connections(peer, &addresses);
+            return;
+        }
+        let opts = DialOpts::peer_id(peer)
+            .addresses(addresses.clone())
+            .condition(PeerCondition::Always)
+            .build();
+        match self.swarm.dial(opts) {
+            Ok(()) => {
+                self.migrating.insert(peer, addresses);
+            }
+            Err(e) => {
+                warn!("Failed to dial {peer:?} at its new addresses: {e:?}");
+            }
+        }
+    }
+
+    /// Close the connections to `peer` at any address other than `addresses`. Direct messages in
+    /// flight on them fail and are retried on the remaining connections.
+    fn close_stale_connections(&mut self, peer: PeerId, addresses: &[Multiaddr]) {
+        let Some(connections) = self.connections.get(&peer) else {
+            return;
+        };
+        let stale: Vec<ConnectionId> = connections
+            .iter()
+            .filter(|(_, address)| !is_one_of(address, addresses))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in stale {
+            debug!("Closing connection {id:?} to {peer:?} at an address it left");
+            self.swarm.close_connection(id);
+        }
+    }
+
+    /// Dial the most preferred peers in the routing table we are not connected to, while we are
+    /// connected to fewer peers than the connection limit
+    fn dial_preferred_peers(&mut self) {
+        let Some(max_connected_peers) = self.max_connected_peers else {
+            return;
+        };
+        let connected = self.connected_pids();
+        let missing = max_connected_peers.saturating_sub(connected.len());
+        if missing == 0 {
+            return;
+        }
+        let known: Vec<PeerId> = self
+            .swarm
+            .behaviour_mut()
+            .dht
+            .kbuckets()
+            .flat_map(|bucket| {
+                bucket
+                    .iter()
+                    .map(|entry| *entry.node.key.preimage())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|peer| *peer != self.peer_id && !connected.contains(peer))
+            .collect();
+        let ranked = self
+            .peer_store
+            .deprioritize_misbehaving(self.peer_selection.rank(known), unix_now());
+        for peer in ranked.into_iter().take(missing) {
+            if let Err(e) = self.swarm.dial(peer) {
+                debug!("Failed to dial {peer:?}: {e:?}");
+            }
+        }
+    }
+
     /// starts the swarm listening on `listen_addr`
     /// and optionally dials into peer `known_peer`
     /// returns the address the swarm is listening upon
@@ -173,6 +391,7 @@ impl<T: NodeType> NetworkNode<T> {
             keypair.clone(),
             config.stake_table.clone(),
             config.auth_message.clone(),
+            config.connection_timeouts,
         )
         .await?;
 
@@ -301,6 +520,7 @@ impl<T: NodeType> NetworkNode<T> {
                 identify,
                 direct_message,
                 autonat::Behaviour::new(peer_id, autonat_config),
+                ping::Behaviour::new(ping::Config::new()),
             );
 
             // build swarm
@@ -314,14 +534,30 @@ impl<T: NodeType> NetworkNode<T> {
                 .unwrap()
                 .build()
         };
+        let mut peer_selection = PeerSelection::new(config.peer_regions.clone());
         for (peer, addr) in &config.to_connect_addrs {
             if peer != swarm.local_peer_id() {
                 swarm.behaviour_mut().add_address(peer, addr.clone());
+                peer_selection.record_address(*peer, addr);
+            }
+        }
+
+        // Prime the DHT with the peers we knew of before the last shutdown
+        let peer_store = PeerStore::new(config.peer_store_file_path.clone());
+        for (peer, addresses) in peer_store.known_peers(unix_now()) {
+            if peer != *swarm.local_peer_id() {
+                for addr in addresses {
+                    swarm.behaviour_mut().add_address(&peer, addr.clone());
+                    peer_selection.record_address(peer, &addr);
+                }
             }
         }
 
+        swarm.behaviour_mut().subscribe_gossip(ADDRESS_UPDATE_TOPIC);
+
         Ok(Self {
             peer_id,
+            keypair,
             swarm,
             listener_id: None,
             direct_message_state: DMBehaviour::default(),
@@ -332,6 +568,15 @@ impl<T: NodeType> NetworkNode<T> {
                     .unwrap_or(NonZeroUsize::new(4).unwrap()),
             ),
             resend_tx: None,
+            peer_selection,
+            max_connected_peers: config.max_connected_peers,
+            peer_store,
+            identify_timeout: config.connection_timeouts.identify,
+            unidentified: HashMap::new(),
+            connections: HashMap::new(),
+            address_updates: AddressUpdates::default(),
+            announced_addresses: Vec::new(),
+            migrating: HashMap::new(),
         })
     }
 
@@ -440,6 +685,9 @@ impl<T: NodeType> NetworkNode<T> {
                         if let Some(listener_id) = self.listener_id {
                             self.swarm.remove_listener(listener_id);
                         }
+                        if let Err(e) = self.peer_store.save_to_file(unix_now()) {
+                            warn!("Failed to save peer store to file: {:?}", e);
+                        }
 
                         return Ok(true);
                     }
@@ -488,6 +736,12 @@ impl<T: NodeType> NetworkNode<T> {
                             warn!("Could not disconnect from {:?}", pid);
                         }
                     }
+                    ClientRequest::ReportMisbehavior(pid) => {
+                        if self.peer_store.record_misbehavior(pid, unix_now()) {
+                            warn!("Disconnecting from {:?}, which misbehaved repeatedly", pid);
+                            let _ = self.swarm.disconnect_peer_id(pid);
+                        }
+                    }
                 }
             }
             None => {
@@ -511,7 +765,7 @@ impl<T: NodeType> NetworkNode<T> {
         #[allow(deprecated)]
         match event {
             SwarmEvent::ConnectionEstablished {
-                connection_id: _,
+                connection_id,
                 peer_id,
                 endpoint,
                 num_established,
@@ -529,6 +783,36 @@ impl<T: NodeType> NetworkNode<T> {
                         peer_id, endpoint, concurrent_dial_errors
                     );
                 }
+                self.peer_selection
+                    .record_address(peer_id, endpoint.get_remote_address());
+                let now = unix_now();
+                if endpoint.is_dialer() {
+                    // Only the address we dialed is one the peer listens on
+                    self.peer_store
+                        .record_address(peer_id, endpoint.get_remote_address(), now);
+                }
+                self.peer_store.record_connected(peer_id, now);
+                if num_established.get() == 1 {
+                    self.unidentified.insert(peer_id, Instant::now());
+                }
+                let address = endpoint.get_remote_address();
+                self.connections
+                    .entry(peer_id)
+                    .or_default()
+                    .insert(connection_id, address.clone());
+                // Once connected to a peer which moved at its new address, leave the old ones
+                if let Some(addresses) = self.migrating.get(&peer_id) {
+                    if is_one_of(address, addresses) {
+                        let addresses = addresses.clone();
+                        self.migrating.remove(&peer_id);
+                        self.close_stale_connections(peer_id, &addresses);
+                    }
+                }
+                if self.peer_store.is_banned(&peer_id, now) {
+                    debug!("Disconnecting from {peer_id:?}, which misbehaved repeatedly");
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                }
+                self.enforce_connection_limit();
 
                 // Send the number of connected peers to the client
                 send_to_client
@@ -536,7 +820,7 @@ impl<T: NodeType> NetworkNode<T> {
                     .map_err(|err| NetworkError::ChannelSendError(err.to_string()))?;
             }
             SwarmEvent::ConnectionClosed {
-                connection_id: _,
+                connection_id,
                 peer_id,
                 endpoint,
                 num_established,
@@ -553,6 +837,15 @@ impl<T: NodeType> NetworkNode<T> {
                         peer_id, endpoint, cause
                     );
                 }
+                if let Some(connections) = self.connections.get_mut(&peer_id) {
+                    connections.remove(&connection_id);
+                }
+                if num_established == 0 {
+                    self.peer_store.record_disconnected(peer_id, unix_now());
+                    self.unidentified.remove(&peer_id);
+                    self.connections.remove(&peer_id);
+                }
+                self.dial_preferred_peers();
 
                 // Send the number of connected peers to the client
                 send_to_client
@@ -606,21 +899,32 @@ impl<T: NodeType> NetworkNode<T> {
                             connection_id: _,
                         } = *e
                         {
+                            self.unidentified.remove(&peer_id);
                             let behaviour = self.swarm.behaviour_mut();
+                            let now = unix_now();
 
                             // into hashset to delete duplicates (I checked: there are duplicates)
                             for addr in listen_addrs.iter().collect::<HashSet<_>>() {
                                 behaviour.dht.add_address(&peer_id, addr.clone());
+                                self.peer_selection.record_address(peer_id, addr);
+                                self.peer_store.record_address(peer_id, addr, now);
                             }
                         }
                         None
                     }
                     NetworkEventInternal::GossipEvent(e) => match *e {
                         GossipEvent::Message {
-                            propagation_source: _peer_id,
+                            propagation_source,
                             message_id: _id,
                             message,
-                        } => Some(NetworkEvent::GossipMsg(message.data)),
+                        } => {
+                            if message.topic == IdentTopic::new(ADDRESS_UPDATE_TOPIC).hash() {
+                                self.handle_address_update(&message.data);
+                                None
+                            } else {
+                                Some(NetworkEvent::GossipMsg(message.data, propagation_source))
+                            }
+                        }
                         GossipEvent::Subscribed { peer_id, topic } => {
                             debug!("Peer {:?} subscribed to topic {:?}", peer_id, topic);
                             None
@@ -660,6 +964,18 @@ impl<T: NodeType> NetworkNode<T> {
                         };
                         None
                     }
+                    NetworkEventInternal::PingEvent(e) => {
+                        match e.result {
+                            Ok(rtt) => {
+                                self.peer_selection.record_rtt(e.peer, rtt);
+                                self.enforce_connection_limit();
+                            }
+                            Err(error) => {
+                                debug!("Ping to {:?} failed: {:?}", e.peer, error);
+                            }
+                        }
+                        None
+                    }
                 };
 
                 if let Some(event) = maybe_event {
@@ -675,6 +991,10 @@ impl<T: NodeType> NetworkNode<T> {
                 error,
             } => {
                 warn!("Outgoing connection error to {:?}: {:?}", peer_id, error);
+                if let Some(peer_id) = peer_id {
+                    // The new address of a peer which moved is not reachable, so stay where we are
+                    self.migrating.remove(&peer_id);
+                }
             }
             SwarmEvent::IncomingConnectionError {
                 connection_id: _,
@@ -731,8 +1051,16 @@ impl<T: NodeType> NetworkNode<T> {
         DHTBootstrapTask::run(bootstrap_rx, s_input.clone());
         spawn(
             async move {
+                let mut identify_check = interval(IDENTIFY_CHECK_INTERVAL);
+                let mut address_check = interval(ADDRESS_ANNOUNCE_INTERVAL);
                 loop {
                     select! {
+                        _ = identify_check.tick() => {
+                            self.disconnect_unidentified();
+                        },
+                        _ = address_check.tick() => {
+                            self.announce_addresses();
+                        },
                         event = self.swarm.next() => {
                             debug!("peerid {:?}\t\thandling maybe event {:?}", self.peer_id, event);
                             if let Some(event) = event {
@@ -762,3 +1090,11 @@ impl<T: NodeType> NetworkNode<T> {
         self.peer_id
     }
 }
+
+/// Whether `address` is one of `addresses`, ignoring the peer ID either may end in
+fn is_one_of(address: &Multiaddr, addresses: &[Multiaddr]) -> bool {
+    let address = without_peer_id(address);
+    addresses
+        .iter()
+        .any(|candidate| without_peer_id(candidate) == address)
+}
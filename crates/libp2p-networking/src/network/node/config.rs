@@ -4,7 +4,12 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::HashSet, num::NonZeroUsize, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_lock::RwLock;
 use hotshot_types::traits::node_implementation::NodeType;
@@ -40,6 +45,10 @@ pub struct NetworkNodeConfig<T: NodeType> {
     /// Configuration for `RequestResponse`
     pub request_response_config: RequestResponseConfig,
 
+    #[builder(default)]
+    /// The timeouts for each stage of establishing a connection
+    pub connection_timeouts: ConnectionTimeouts,
+
     /// list of addresses to connect to at initialization
     pub to_connect_addrs: HashSet<(PeerId, Multiaddr)>,
 
@@ -68,6 +77,21 @@ pub struct NetworkNodeConfig<T: NodeType> {
     #[builder(default)]
     /// The timeout for DHT lookups.
     pub dht_timeout: Option<Duration>,
+
+    /// The most peers to stay connected to. Beyond it the peers with the highest round trip
+    /// times are disconnected, keeping some in every region. Unlimited if not supplied
+    #[builder(default)]
+    pub max_connected_peers: Option<usize>,
+
+    /// The regions of the peers whose region is known. Other peers are grouped by the network
+    /// their address is in
+    #[builder(default)]
+    pub peer_regions: HashMap<PeerId, String>,
+
+    /// The path to the file to keep known peers and their reputation in. Peers are only
+    /// remembered until shutdown if not supplied
+    #[builder(default)]
+    pub peer_store_file_path: Option<String>,
 }
 
 impl<T: NodeType> Clone for NetworkNodeConfig<T> {
@@ -78,6 +102,7 @@ impl<T: NodeType> Clone for NetworkNodeConfig<T> {
             replication_factor: self.replication_factor,
             gossip_config: self.gossip_config.clone(),
             request_response_config: self.request_response_config.clone(),
+            connection_timeouts: self.connection_timeouts,
             to_connect_addrs: self.to_connect_addrs.clone(),
             republication_interval: self.republication_interval,
             ttl: self.ttl,
@@ -85,6 +110,9 @@ impl<T: NodeType> Clone for NetworkNodeConfig<T> {
             dht_file_path: self.dht_file_path.clone(),
             auth_message: self.auth_message.clone(),
             dht_timeout: self.dht_timeout,
+            max_connected_peers: self.max_connected_peers,
+            peer_regions: self.peer_regions.clone(),
+            peer_store_file_path: self.peer_store_file_path.clone(),
         }
     }
 }
@@ -201,3 +229,24 @@ impl Default for RequestResponseConfig {
         }
     }
 }
+
+/// Timeouts for the stages of establishing a connection, after which the connection is given up
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectionTimeouts {
+    /// The timeout for connecting to the peer, including the QUIC handshake
+    pub connect: Duration,
+    /// The timeout for the authentication handshake on top of a new connection
+    pub handshake: Duration,
+    /// The timeout for the peer to identify itself once connected
+    pub identify: Duration,
+}
+
+impl Default for ConnectionTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(20),
+            handshake: Duration::from_secs(5),
+            identify: Duration::from_secs(10),
+        }
+    }
+}
@@ -379,6 +379,15 @@ impl<T: NodeType> NetworkNodeHandle<T> {
         self.send_request(req)
     }
 
+    /// Report that a peer misbehaved, which lowers its reputation. A peer which misbehaves
+    /// repeatedly is disconnected and not reconnected to
+    /// # Errors
+    /// If the channel is closed somehow
+    pub fn report_misbehavior(&self, pid: PeerId) -> Result<(), NetworkError> {
+        let req = ClientRequest::ReportMisbehavior(pid);
+        self.send_request(req)
+    }
+
     /// Gossip a message to peers
     /// # Errors
     /// - Will return [`NetworkError::ChannelSendError`] when underlying `NetworkNode` has been killed
@@ -11,6 +11,7 @@ use async_lock::RwLock;
 use futures::{future::poll_fn, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use hotshot_types::traits::{
     election::Membership,
+    network::NetworkError,
     node_implementation::{ConsensusTime, NodeType},
     signature_key::SignatureKey,
 };
@@ -28,15 +29,12 @@ use serde::{Deserialize, Serialize};
 use tokio::time::timeout;
 use tracing::warn;
 
+use super::ConnectionTimeouts;
+
 /// The maximum size of an authentication message. This is used to prevent
 /// DoS attacks by sending large messages.
 const MAX_AUTH_MESSAGE_SIZE: usize = 1024;
 
-/// The timeout for the authentication handshake. This is used to prevent
-/// attacks that keep connections open indefinitely by half-finishing the
-/// handshake.
-const AUTH_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
-
 /// A wrapper for a `Transport` that bidirectionally authenticates connections
 /// by performing a handshake that checks if the remote peer is present in the
 /// stake table.
@@ -52,6 +50,10 @@ pub struct StakeTableAuthentication<T: Transport, Types: NodeType, C: StreamMuxe
     /// A pre-signed message that we send to the remote peer for authentication
     pub auth_message: Arc<Option<Vec<u8>>>,
 
+    /// The timeouts for connecting and for the authentication handshake. The handshake timeout
+    /// prevents attacks that keep connections open indefinitely by half-finishing the handshake.
+    pub timeouts: ConnectionTimeouts,
+
     /// Phantom data for the connection type
     pd: std::marker::PhantomData<C>,
 }
@@ -67,11 +69,13 @@ impl<T: Transport, Types: NodeType, C: StreamMuxer + Unpin> StakeTableAuthentica
         inner: T,
         stake_table: Option<Arc<RwLock<Types::Membership>>>,
         auth_message: Option<Vec<u8>>,
+        timeouts: ConnectionTimeouts,
     ) -> Self {
         Self {
             inner,
             stake_table: Arc::from(stake_table),
             auth_message: Arc::from(auth_message),
+            timeouts,
             pd: std::marker::PhantomData,
         }
     }
@@ -153,11 +157,15 @@ impl<T: Transport, Types: NodeType, C: StreamMuxer + Unpin> StakeTableAuthentica
     ///
     /// `outgoing` is a boolean that indicates if the connection is incoming or outgoing.
     /// This is needed because the flow of the handshake is different for each.
+    ///
+    /// Connecting and the handshake time out separately, failing with a
+    /// [`NetworkError::ConnectTimeout`] or [`NetworkError::HandshakeTimeout`] respectively.
     fn gen_handshake<F: Future<Output = Result<T::Output, T::Error>> + Send + 'static>(
         original_future: F,
         outgoing: bool,
         stake_table: Arc<Option<Arc<RwLock<Types::Membership>>>>,
         auth_message: Arc<Option<Vec<u8>>>,
+        timeouts: ConnectionTimeouts,
     ) -> UpgradeFuture<T>
     where
         T::Error: From<<C as StreamMuxer>::Error> + From<IoError>,
@@ -168,10 +176,19 @@ impl<T: Transport, Types: NodeType, C: StreamMuxer + Unpin> StakeTableAuthentica
         // Create a new upgrade that performs the authentication handshake on top
         Box::pin(async move {
             // Wait for the original future to resolve
-            let mut stream = original_future.await?;
+            let mut stream = timeout(timeouts.connect, original_future)
+                .await
+                .map_err(|_| {
+                    let err = NetworkError::ConnectTimeout(format!(
+                        "no connection after {:?}",
+                        timeouts.connect
+                    ));
+                    warn!("{err}");
+                    IoError::new(IoErrorKind::TimedOut, err)
+                })??;
 
             // Time out the authentication block
-            timeout(AUTH_HANDSHAKE_TIMEOUT, async {
+            timeout(timeouts.handshake, async {
                 // Open a substream for the handshake.
                 // The handshake order depends on whether the connection is incoming or outgoing.
                 let mut substream = if outgoing {
@@ -225,9 +242,13 @@ impl<T: Transport, Types: NodeType, C: StreamMuxer + Unpin> StakeTableAuthentica
                 Ok(stream)
             })
             .await
-            .map_err(|e| {
-                warn!("Timed out performing authentication handshake: {:?}", e);
-                IoError::new(IoErrorKind::TimedOut, e)
+            .map_err(|_| {
+                let err = NetworkError::HandshakeTimeout(format!(
+                    "authentication not finished after {:?}",
+                    timeouts.handshake
+                ));
+                warn!("{err}");
+                IoError::new(IoErrorKind::TimedOut, err)
             })?
         })
     }
@@ -334,7 +355,13 @@ where
 
         // If the dial was successful, perform the authentication handshake on top
         match res {
-            Ok(dial) => Ok(Self::gen_handshake(dial, true, stake_table, auth_message)),
+            Ok(dial) => Ok(Self::gen_handshake(
+                dial,
+                true,
+                stake_table,
+                auth_message,
+                self.timeouts,
+            )),
             Err(err) => Err(err),
         }
     }
@@ -361,8 +388,13 @@ where
                     let stake_table = Arc::clone(&self.stake_table);
 
                     // Generate the handshake upgrade future (inbound)
-                    let auth_upgrade =
-                        Self::gen_handshake(upgrade, false, stake_table, auth_message);
+                    let auth_upgrade = Self::gen_handshake(
+                        upgrade,
+                        false,
+                        stake_table,
+                        auth_message,
+                        self.timeouts,
+                    );
 
                     // Return the new event
                     TransportEvent::Incoming {
@@ -0,0 +1,86 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Errors returned when a proof does not check out
+
+use thiserror::Error;
+
+/// Why the light client rejected a proof
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LightClientError {
+    /// A chain extension contained no leaves
+    #[error("Chain extension is empty")]
+    EmptyExtension,
+
+    /// A leaf does not extend the leaf before it
+    #[error("Leaf at height {height} does not extend the chain")]
+    BrokenChain {
+        /// Height of the offending leaf
+        height: u64,
+    },
+
+    /// The leaves of a chain extension are not decided by the certificates which came with them
+    #[error("Leaf at height {height} is not decided")]
+    NotDecided {
+        /// Height of the newest leaf of the extension
+        height: u64,
+    },
+
+    /// A certificate is not signed by enough of its epoch's stake
+    #[error("Invalid certificate for view {view} in epoch {epoch}")]
+    InvalidCertificate {
+        /// View of the certificate
+        view: u64,
+        /// Epoch of the certificate
+        epoch: u64,
+    },
+
+    /// A certificate is for a different epoch than the leaf it certifies
+    #[error("Certificate for height {height} is from epoch {epoch}, expected {expected}")]
+    WrongEpoch {
+        /// Height of the certified leaf
+        height: u64,
+        /// Epoch of the certificate
+        epoch: u64,
+        /// Epoch of the certified leaf
+        expected: u64,
+    },
+
+    /// The stake table of an epoch has not been verified yet
+    #[error("Unknown stake table for epoch {0}")]
+    UnknownEpoch(u64),
+
+    /// An epoch transition does not follow the latest known epoch
+    #[error("Epoch transition to {epoch} does not follow epoch {latest}")]
+    UnexpectedEpoch {
+        /// Epoch the transition is to
+        epoch: u64,
+        /// Latest epoch with a known stake table
+        latest: u64,
+    },
+
+    /// A stake table is not the one the last block of the previous epoch commits to
+    #[error("Stake table of epoch {0} does not match the commitment in the last block before it")]
+    StakeTableMismatch(u64),
+
+    /// The light client holds no header at this height
+    #[error("No header for height {0}")]
+    UnknownHeight(u64),
+
+    /// A payload does not match the commitment in its header
+    #[error("Payload does not match the header at height {0}")]
+    PayloadMismatch(u64),
+
+    /// A transaction is not in the payload at the claimed position
+    #[error("Transaction is not at index {index} of the block at height {height}")]
+    TransactionNotIncluded {
+        /// Height of the block
+        height: u64,
+        /// Claimed position of the transaction
+        index: usize,
+    },
+}
@@ -0,0 +1,374 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A light client for HotShot.
+//!
+//! The [`LightClient`] follows the chain without running a node: it keeps only block headers, the
+//! latest quorum certificate and the stake table of each epoch. Starting from a trusted anchor
+//! leaf, it accepts newly decided leaves only if they extend the chain and are certified by the
+//! quorum of their epoch, and it accepts the stake table of a new epoch only once that quorum has
+//! taken over the chain. Bridges and wallets can then check that a transaction was included in a
+//! decided block with [`LightClient::verify_inclusion`].
+//!
+//! The light client does not derive stake tables itself. The stake table of a new epoch has to
+//! come from wherever the application defines it, such as a stake table contract; the light client
+//! checks that the header of the last block of the previous epoch, decided by the previous quorum,
+//! commits to it, and that the new quorum certified that block. The stake needed for a certificate
+//! is always computed from the stake table.
+
+use std::collections::BTreeMap;
+
+use committable::{Commitment, Committable};
+use hotshot_types::{
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        block_contents::{vid_commitment, BlockHeader, GENESIS_VID_NUM_STORAGE_NODES},
+        node_implementation::{ConsensusTime, NodeType, Versions},
+        BlockPayload,
+    },
+    utils::epoch_from_block_number,
+    vote::{Certificate, HasViewNumber},
+};
+
+mod error;
+mod proof;
+
+pub use error::LightClientError;
+pub use proof::{ChainExtension, EpochStakeTable, EpochTransition, TxInclusionProof};
+
+/// Number of blocks after the last block of an epoch which the quorum of that epoch still
+/// certifies, while the quorum of the next epoch takes over
+const EPOCH_TRANSITION_BLOCKS: u64 = 3;
+
+/// A block header the light client has verified to be decided
+#[derive(Clone, Debug)]
+pub struct VerifiedHeader<TYPES: NodeType> {
+    /// The header
+    pub header: TYPES::BlockHeader,
+    /// Commitment to the leaf the header was proposed in
+    pub leaf_commit: Commitment<Leaf2<TYPES>>,
+    /// View the leaf was proposed in
+    pub view_number: TYPES::View,
+}
+
+/// Follows HotShot through headers, quorum certificates and epoch stake tables.
+pub struct LightClient<TYPES: NodeType, V: Versions> {
+    /// Decided headers, by height
+    headers: BTreeMap<u64, VerifiedHeader<TYPES>>,
+
+    /// Height of the latest decided header
+    latest_height: u64,
+
+    /// Certificate of the latest decided leaf
+    latest_qc: QuorumCertificate2<TYPES>,
+
+    /// Verified stake tables, by epoch
+    stake_tables: BTreeMap<TYPES::Epoch, EpochStakeTable<TYPES>>,
+
+    /// Number of blocks in an epoch, zero means there are no epochs
+    epoch_height: u64,
+
+    /// Lock for a decided upgrade, which determines how certificates are committed to
+    upgrade_lock: UpgradeLock<TYPES, V>,
+}
+
+impl<TYPES: NodeType, V: Versions> LightClient<TYPES, V> {
+    /// Create a light client which trusts `anchor`, such as the genesis leaf or a leaf obtained
+    /// out of band, and the stake tables in `stake_tables`.
+    ///
+    /// # Errors
+    /// If the stake table of the anchor's epoch is not given.
+    pub fn new(
+        anchor: &Leaf2<TYPES>,
+        stake_tables: BTreeMap<TYPES::Epoch, EpochStakeTable<TYPES>>,
+        epoch_height: u64,
+        upgrade_lock: UpgradeLock<TYPES, V>,
+    ) -> Result<Self, LightClientError> {
+        let epoch = TYPES::Epoch::new(epoch_from_block_number(anchor.height(), epoch_height));
+        if !stake_tables.contains_key(&epoch) {
+            return Err(LightClientError::UnknownEpoch(*epoch));
+        }
+
+        let mut headers = BTreeMap::new();
+        headers.insert(anchor.height(), Self::verified_header(anchor));
+
+        Ok(Self {
+            headers,
+            latest_height: anchor.height(),
+            latest_qc: anchor.justify_qc(),
+            stake_tables,
+            epoch_height,
+            upgrade_lock,
+        })
+    }
+
+    /// The part of `leaf` the light client keeps.
+    fn verified_header(leaf: &Leaf2<TYPES>) -> VerifiedHeader<TYPES> {
+        VerifiedHeader {
+            header: leaf.block_header().clone(),
+            leaf_commit: leaf.commit(),
+            view_number: leaf.view_number(),
+        }
+    }
+
+    /// The epoch of the block at `height`.
+    fn epoch_of(&self, height: u64) -> TYPES::Epoch {
+        TYPES::Epoch::new(epoch_from_block_number(height, self.epoch_height))
+    }
+
+    /// The latest decided header.
+    #[must_use]
+    pub fn latest_header(&self) -> Option<&VerifiedHeader<TYPES>> {
+        self.headers.get(&self.latest_height)
+    }
+
+    /// Height of the latest decided header.
+    #[must_use]
+    pub fn latest_height(&self) -> u64 {
+        self.latest_height
+    }
+
+    /// The certificate of the latest decided leaf.
+    #[must_use]
+    pub fn latest_qc(&self) -> &QuorumCertificate2<TYPES> {
+        &self.latest_qc
+    }
+
+    /// The decided header at `height`, if it has not been pruned.
+    #[must_use]
+    pub fn header(&self, height: u64) -> Option<&VerifiedHeader<TYPES>> {
+        self.headers.get(&height)
+    }
+
+    /// The verified stake table of `epoch`, if any.
+    #[must_use]
+    pub fn stake_table(&self, epoch: TYPES::Epoch) -> Option<&EpochStakeTable<TYPES>> {
+        self.stake_tables.get(&epoch)
+    }
+
+    /// The latest epoch whose stake table has been verified.
+    #[must_use]
+    pub fn latest_epoch(&self) -> Option<TYPES::Epoch> {
+        self.stake_tables.keys().next_back().copied()
+    }
+
+    /// Drop the headers below `height`, keeping the latest one.
+    ///
+    /// Pruning the last block of an epoch before the next epoch's transition has been verified
+    /// makes that transition unverifiable.
+    pub fn prune_headers(&mut self, height: u64) {
+        self.headers = self.headers.split_off(&height.min(self.latest_height));
+    }
+
+    /// Check that `qc` certifies the leaf at `height` and is signed by the quorum of its epoch.
+    async fn verify_qc(
+        &self,
+        qc: &QuorumCertificate2<TYPES>,
+        height: u64,
+    ) -> Result<(), LightClientError> {
+        let epoch = qc.data.epoch;
+        let expected = self.epoch_of(height);
+        // During a transition, the quorum of the ending epoch still certifies the first leaves
+        // after its last block, but no later ones
+        let last_block = (*epoch).saturating_mul(self.epoch_height);
+        let in_transition =
+            epoch + 1 == expected && height <= last_block.saturating_add(EPOCH_TRANSITION_BLOCKS);
+        if epoch != expected && !in_transition {
+            return Err(LightClientError::WrongEpoch {
+                height,
+                epoch: *epoch,
+                expected: *expected,
+            });
+        }
+
+        let stake_table = self
+            .stake_tables
+            .get(&epoch)
+            .ok_or(LightClientError::UnknownEpoch(*epoch))?;
+
+        // Only the genesis certificate is accepted without signatures, and it never decides
+        // anything past the anchor
+        if qc.view_number() == TYPES::View::genesis()
+            || !qc
+                .is_valid_cert(
+                    stake_table.stake_table.clone(),
+                    stake_table.threshold(),
+                    &self.upgrade_lock,
+                )
+                .await
+        {
+            return Err(LightClientError::InvalidCertificate {
+                view: *qc.view_number(),
+                epoch: *epoch,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the leaves of `extension` are decided and extend the chain, and keep their
+    /// headers.
+    ///
+    /// # Errors
+    /// If the leaves do not extend the latest decided leaf, are not decided by the certificates
+    /// in the extension, or are in an epoch whose stake table has not been verified.
+    pub async fn extend(
+        &mut self,
+        extension: &ChainExtension<TYPES>,
+    ) -> Result<(), LightClientError> {
+        let ChainExtension {
+            leaves,
+            child,
+            child_qc,
+        } = extension;
+        let last = leaves.last().ok_or(LightClientError::EmptyExtension)?;
+
+        let mut parent = self
+            .latest_header()
+            .map(|header| header.leaf_commit)
+            .ok_or(LightClientError::UnknownHeight(self.latest_height))?;
+        let mut parent_height = self.latest_height;
+        for leaf in leaves {
+            if leaf.parent_commitment() != parent || leaf.height() != parent_height + 1 {
+                return Err(LightClientError::BrokenChain {
+                    height: leaf.height(),
+                });
+            }
+            parent = leaf.commit();
+            parent_height = leaf.height();
+        }
+
+        // The two-chain rule: the last leaf is decided by a certified child in the next view
+        let last_qc = child.justify_qc();
+        if child.parent_commitment() != parent
+            || last_qc.data.leaf_commit != parent
+            || child.view_number() != last.view_number() + 1
+            || child_qc.data.leaf_commit != child.commit()
+        {
+            return Err(LightClientError::NotDecided {
+                height: last.height(),
+            });
+        }
+        self.verify_qc(&last_qc, last.height()).await?;
+        self.verify_qc(child_qc, child.height()).await?;
+
+        for leaf in leaves {
+            self.headers
+                .insert(leaf.height(), Self::verified_header(leaf));
+        }
+        self.latest_height = parent_height;
+        self.latest_qc = last_qc;
+
+        tracing::debug!("Light client extended to height {parent_height}");
+
+        Ok(())
+    }
+
+    /// Verify that the quorum of `transition.epoch` took over the chain, and accept its stake
+    /// table.
+    ///
+    /// # Errors
+    /// If the transition does not follow the latest verified epoch, the last block of the previous
+    /// epoch has not been decided yet or does not commit to the new stake table, or the
+    /// certificate does not check out against the new stake table.
+    pub async fn transition_epoch(
+        &mut self,
+        transition: EpochTransition<TYPES>,
+    ) -> Result<(), LightClientError> {
+        let EpochTransition {
+            epoch,
+            stake_table,
+            qc,
+        } = transition;
+
+        let latest = self
+            .latest_epoch()
+            .ok_or(LightClientError::UnknownEpoch(*epoch))?;
+        if self.epoch_height == 0 || epoch != latest + 1 || qc.data.epoch != latest {
+            return Err(LightClientError::UnexpectedEpoch {
+                epoch: *epoch,
+                latest: *latest,
+            });
+        }
+
+        let height = *latest * self.epoch_height;
+        let header = self
+            .headers
+            .get(&height)
+            .ok_or(LightClientError::UnknownHeight(height))?;
+        // The previous quorum decided this header, so it vouches for the stake table it commits to
+        if header.header.next_stake_table_commitment() != Some(stake_table.commitment()) {
+            return Err(LightClientError::StakeTableMismatch(*epoch));
+        }
+        if qc.data.leaf_commit != header.leaf_commit
+            || !qc
+                .is_valid_cert(
+                    stake_table.stake_table.clone(),
+                    stake_table.threshold(),
+                    &self.upgrade_lock,
+                )
+                .await
+        {
+            return Err(LightClientError::InvalidCertificate {
+                view: *qc.view_number(),
+                epoch: *epoch,
+            });
+        }
+
+        tracing::info!("Light client verified the stake table of epoch {}", *epoch);
+        self.stake_tables.insert(epoch, stake_table);
+
+        Ok(())
+    }
+
+    /// Verify that `proof.transaction` is in the decided block at `proof.height`.
+    ///
+    /// # Errors
+    /// If the header at that height is not known, the payload does not match it, or the
+    /// transaction is not at the claimed position in the payload.
+    pub fn verify_inclusion(
+        &self,
+        proof: &TxInclusionProof<TYPES>,
+    ) -> Result<(), LightClientError> {
+        let height = proof.height;
+        let header = &self
+            .headers
+            .get(&height)
+            .ok_or(LightClientError::UnknownHeight(height))?
+            .header;
+
+        let num_storage_nodes = if height == 0 {
+            GENESIS_VID_NUM_STORAGE_NODES
+        } else {
+            let epoch = self.epoch_of(height);
+            self.stake_tables
+                .get(&epoch)
+                .ok_or(LightClientError::UnknownEpoch(*epoch))?
+                .stake_table
+                .len()
+        };
+        if vid_commitment(&proof.payload, num_storage_nodes) != header.payload_commitment() {
+            return Err(LightClientError::PayloadMismatch(height));
+        }
+
+        let metadata = header.metadata();
+        let payload = TYPES::BlockPayload::from_bytes(&proof.payload, metadata);
+        let included = payload
+            .transactions(metadata)
+            .nth(proof.index)
+            .is_some_and(|transaction| transaction.commit() == proof.transaction.commit());
+        if !included {
+            return Err(LightClientError::TransactionNotIncluded {
+                height,
+                index: proof.index,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! The proofs a full node serves to light clients
+
+use std::num::NonZeroU64;
+
+use hotshot_types::{
+    data::Leaf2,
+    simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2},
+    stake_table::stake_table_commitment,
+    traits::{
+        block_contents::BlockPayload,
+        election::Membership,
+        node_implementation::NodeType,
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// The stake table of an epoch.
+///
+/// The stake needed to certify a leaf is computed from the table, never taken from whoever
+/// supplied it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct EpochStakeTable<TYPES: NodeType> {
+    /// Stake of every member of the quorum
+    pub stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+}
+
+impl<TYPES: NodeType> EpochStakeTable<TYPES> {
+    /// The quorum stake table of `epoch` according to `membership`.
+    #[must_use]
+    pub fn from_membership(membership: &TYPES::Membership, epoch: TYPES::Epoch) -> Self {
+        Self {
+            stake_table: membership.stake_table(epoch),
+        }
+    }
+
+    /// The stake needed for a certificate: over two thirds of the total stake.
+    #[must_use]
+    pub fn threshold(&self) -> NonZeroU64 {
+        let total = self
+            .stake_table
+            .iter()
+            .map(|entry| u128::try_from(entry.stake()).unwrap_or(u128::MAX))
+            .fold(0, u128::saturating_add);
+        NonZeroU64::new(u64::try_from(total * 2 / 3 + 1).unwrap_or(u64::MAX))
+            .unwrap_or(NonZeroU64::MIN)
+    }
+
+    /// The commitment to this stake table, as found in the header of the last block of the
+    /// previous epoch.
+    #[must_use]
+    pub fn commitment(&self) -> [u8; 32] {
+        stake_table_commitment::<TYPES::SignatureKey>(&self.stake_table)
+    }
+}
+
+/// Leaves decided since the latest leaf the light client knows of.
+///
+/// A leaf is decided once a leaf in the next view certifies it and is itself certified, so the
+/// extension ends with that pair: `child` carries the certificate of the last leaf in `leaves` as
+/// its justify QC, and `child_qc` certifies `child`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ChainExtension<TYPES: NodeType> {
+    /// The newly decided leaves, oldest first
+    pub leaves: Vec<Leaf2<TYPES>>,
+    /// The leaf proposed in the view after the last decided leaf
+    pub child: Leaf2<TYPES>,
+    /// The certificate of `child`
+    pub child_qc: QuorumCertificate2<TYPES>,
+}
+
+/// The handover of the chain to the quorum of a new epoch.
+///
+/// The header of the last block of an epoch commits to the stake table of the next one, and that
+/// block is certified by the quorums of both the epoch ending and the epoch starting. The light
+/// client accepts the new stake table once that block is decided, the stake table matches the
+/// commitment in its header, and `qc`, its certificate by the new quorum, checks out against it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct EpochTransition<TYPES: NodeType> {
+    /// The epoch starting
+    pub epoch: TYPES::Epoch,
+    /// The quorum stake table of `epoch`
+    pub stake_table: EpochStakeTable<TYPES>,
+    /// The certificate of the last block of the previous epoch by the quorum of `epoch`
+    pub qc: NextEpochQuorumCertificate2<TYPES>,
+}
+
+/// A proof that a transaction is part of a decided block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TxInclusionProof<TYPES: NodeType> {
+    /// Height of the block
+    pub height: u64,
+    /// The encoded payload of the block
+    pub payload: Vec<u8>,
+    /// Position of the transaction in the block
+    pub index: usize,
+    /// The transaction
+    pub transaction: <TYPES::BlockPayload as BlockPayload<TYPES>>::Transaction,
+}
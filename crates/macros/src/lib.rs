@@ -48,6 +48,10 @@ struct CrossTestData {
 
     /// whether or not to ignore
     ignore: LitBool,
+
+    /// whether to run as a deterministic simulation
+    #[builder(default = "syn::parse_str(\"false\").unwrap()")]
+    deterministic: LitBool,
 }
 
 impl CrossTestDataBuilder {
@@ -86,6 +90,9 @@ struct TestData {
 
     /// whether or not to ignore the test
     ignore: LitBool,
+
+    /// whether to run the test as a deterministic simulation
+    deterministic: LitBool,
 }
 
 /// trait make a string lower and snake case
@@ -190,6 +197,7 @@ impl TestData {
             metadata,
             ignore,
             builder_impl,
+            deterministic,
         } = self;
 
         let slow_attribute = if ignore.value() {
@@ -198,13 +206,25 @@ impl TestData {
         } else {
             quote! {}
         };
+        let (runtime_attribute, seed_simulation) = if deterministic.value() {
+            (
+                quote! { #[tokio::test(flavor = "current_thread", start_paused = true)] },
+                quote! { hotshot_testing::simulation::seed_simulation(); },
+            )
+        } else {
+            (
+                quote! { #[tokio::test(flavor = "multi_thread")] },
+                quote! {},
+            )
+        };
         quote! {
             #[cfg(test)]
             #slow_attribute
-            #[tokio::test(flavor = "multi_thread")]
+            #runtime_attribute
             #[tracing::instrument]
             async fn #test_name() {
                 hotshot::helpers::initialize_logging();
+                #seed_simulation
 
                 hotshot_testing::test_builder::TestDescription::<#ty, #imply, #version>::gen_launcher((#metadata), 0).launch().run_test::<#builder_impl>().await;
             }
@@ -221,6 +241,7 @@ mod keywords {
     syn::custom_keyword!(Impls);
     syn::custom_keyword!(BuilderImpls);
     syn::custom_keyword!(Versions);
+    syn::custom_keyword!(Deterministic);
 }
 
 impl Parse for TypePathBracketedArray {
@@ -251,7 +272,8 @@ impl Parse for CrossTestData {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         let mut description = CrossTestDataBuilder::create_empty();
 
-        while !description.is_ready() {
+        // keep parsing after the required keywords, which may be followed by optional ones
+        while !description.is_ready() || !input.is_empty() {
             if input.peek(keywords::Types) {
                 let _ = input.parse::<keywords::Types>()?;
                 input.parse::<Token![:]>()?;
@@ -287,9 +309,14 @@ impl Parse for CrossTestData {
                 input.parse::<Token![:]>()?;
                 let ignore = input.parse::<LitBool>()?;
                 description.ignore(ignore);
+            } else if input.peek(keywords::Deterministic) {
+                let _ = input.parse::<keywords::Deterministic>()?;
+                input.parse::<Token![:]>()?;
+                let deterministic = input.parse::<LitBool>()?;
+                description.deterministic(deterministic);
             } else {
                 panic!(
-                    "Unexpected token. Expected one of: Metadata, Ignore, Impls, BuilderImpls, Versions, Types, Testname"
+                    "Unexpected token. Expected one of: Metadata, Ignore, Impls, BuilderImpls, Versions, Types, Testname, Deterministic"
                 );
             }
             if input.peek(Token![,]) {
@@ -339,6 +366,7 @@ fn cross_tests_internal(test_spec: CrossTestData) -> TokenStream {
                         .test_name(test_spec.test_name.clone())
                         .metadata(test_spec.metadata.clone())
                         .ignore(test_spec.ignore.clone())
+                        .deterministic(test_spec.deterministic.clone())
                         .version(version.clone())
                         .imply(imp.clone())
                         .builder_impl(builder_impl.clone())
@@ -384,6 +412,8 @@ fn cross_tests_internal(test_spec: CrossTestData) -> TokenStream {
 /// - `Types: []` - a list types that implement `NodeImplementation` over the types in `Impls`
 /// - `TestName: example_test` - the name of the test
 /// - `Ignore`: whether or not this set of tests are ignored
+/// - `Deterministic`: optionally, whether to run the tests as deterministic simulations, see
+///   `hotshot_testing::simulation`
 ///   Example usage: see tests in this module
 #[proc_macro]
 pub fn cross_tests(input: TokenStream) -> TokenStream {
@@ -4,7 +4,10 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    net::SocketAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use clap::Parser;
 use futures::{Future, FutureExt};
@@ -81,6 +84,25 @@ impl BenchResults {
     }
 }
 
+/// The progress of a run, as the orchestrator sees it
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunStatus {
+    /// The number of nodes the run is for
+    pub expected_nodes: u64,
+    /// The number of nodes which registered their public key
+    pub registered_nodes: u64,
+    /// The number of nodes which posted they are ready to start
+    pub ready_nodes: u64,
+    /// When the run starts, in milliseconds since the Unix epoch, once all nodes are ready
+    pub start_time_ms: Option<u64>,
+    /// The lowest view reported by a node, if any reported one
+    pub lowest_view: Option<u64>,
+    /// The highest view reported by a node, if any reported one
+    pub highest_view: Option<u64>,
+    /// The number of nodes which posted their results
+    pub nodes_posted_results: u64,
+}
+
 /// Struct describing a benchmark result needed for download, also include the config
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct BenchResultsDownloadConfig {
@@ -145,6 +167,18 @@ pub struct ValidatorArgs {
     /// Allows for rejoining the network on a complete state loss
     #[arg(short, long)]
     pub network_config_file: Option<String>,
+    /// Optional address to serve Prometheus metrics on, at `/metrics`
+    #[arg(long)]
+    pub metrics_address: Option<SocketAddr>,
+    /// Optional address to serve health probes on, at `/livez`, `/readyz` and `/health`
+    #[arg(long)]
+    pub health_address: Option<SocketAddr>,
+    /// Optional loopback address to serve the admin interface on
+    #[arg(long, requires = "admin_token")]
+    pub admin_address: Option<SocketAddr>,
+    /// The bearer token admin requests must carry
+    #[arg(long, env = "HOTSHOT_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
 }
 
 /// arguments to run multiple validators
@@ -226,6 +260,10 @@ impl ValidatorArgs {
             network_config_file: multi_args
                 .network_config_file
                 .map(|s| format!("{s}-{node_index}")),
+            metrics_address: None,
+            health_address: None,
+            admin_address: None,
+            admin_token: None,
         }
     }
 }
@@ -455,7 +493,8 @@ impl OrchestratorClient {
     }
 
     /// Tells the orchestrator this validator is ready to start
-    /// Blocks until the orchestrator indicates all nodes are ready to start
+    /// Blocks until the orchestrator indicates all nodes are ready to start, and then until the
+    /// start time it scheduled, so all nodes start the run together
     /// # Panics
     /// Panics if unable to post.
     #[instrument(skip(self), name = "orchestrator ready signal")]
@@ -480,8 +519,21 @@ impl OrchestratorClient {
         let wait_for_all_nodes_ready_f = |client: Client<ClientError, OrchestratorVersion>| {
             async move { client.get("api/start").send().await }.boxed()
         };
-        self.wait_for_fn_from_orchestrator(wait_for_all_nodes_ready_f)
-            .await
+        let start = self
+            .wait_for_fn_from_orchestrator(wait_for_all_nodes_ready_f)
+            .await;
+
+        let get_start_time_f = |client: Client<ClientError, OrchestratorVersion>| {
+            async move { client.get("api/start_time").send().await }.boxed()
+        };
+        let start_time_ms: u64 = self.wait_for_fn_from_orchestrator(get_start_time_f).await;
+        let start_time = UNIX_EPOCH + Duration::from_millis(start_time_ms);
+        if let Ok(remaining) = start_time.duration_since(SystemTime::now()) {
+            info!("Starting the run in {remaining:?}");
+            sleep(remaining).await;
+        }
+
+        start
     }
 
     /// Sends the benchmark metrics to the orchestrator
@@ -499,6 +551,24 @@ impl OrchestratorClient {
             .inspect_err(|err| tracing::warn!("{err}"));
     }
 
+    /// Reports the view the node with `node_index` is in
+    #[instrument(skip(self), name = "orchestrator view")]
+    pub async fn post_view(&self, node_index: u64, view: u64) {
+        let _send_view_f: Result<(), ClientError> = self
+            .client
+            .post(&format!("api/view/{node_index}/{view}"))
+            .send()
+            .await
+            .inspect_err(|err| tracing::debug!("{err}"));
+    }
+
+    /// Gets the progress of the run
+    /// # Errors
+    /// if the orchestrator cannot be reached
+    pub async fn get_status(&self) -> Result<RunStatus, ClientError> {
+        self.client.get("api/status").send().await
+    }
+
     /// Generic function that waits for the orchestrator to return a non-error
     /// Returns whatever type the given function returns
     #[instrument(skip_all, name = "waiting for orchestrator")]
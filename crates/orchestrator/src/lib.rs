@@ -14,11 +14,11 @@ use std::{
     fs,
     fs::OpenOptions,
     io::{self, ErrorKind},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use async_lock::RwLock;
-use client::{BenchResults, BenchResultsDownloadConfig};
+use client::{BenchResults, BenchResultsDownloadConfig, RunStatus};
 use csv::Writer;
 use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 use hotshot_types::{
@@ -84,12 +84,16 @@ struct OrchestratorState<KEY: SignatureKey> {
     /// Whether nodes should start their HotShot instances
     /// Will be set to true once all nodes post they are ready to start
     start: bool,
+    /// When the run starts, in milliseconds since the Unix epoch, once it is known
+    start_time_ms: Option<u64>,
     /// The total nodes that have posted they are ready to start
     nodes_connected: HashSet<PeerConfig<KEY>>,
     /// The results of the benchmarks
     bench_results: BenchResults,
     /// The number of nodes that have posted their results
     nodes_post_results: u64,
+    /// The view each node last reported being in, by node index
+    node_views: HashMap<u64, u64>,
     /// Whether the orchestrator can be started manually
     manual_start_allowed: bool,
     /// Whether we are still accepting new keys for registration
@@ -128,8 +132,10 @@ impl<KEY: SignatureKey + 'static> OrchestratorState<KEY> {
             pub_posted: HashMap::new(),
             nodes_connected: HashSet::new(),
             start: false,
+            start_time_ms: None,
             bench_results: BenchResults::default(),
             nodes_post_results: 0,
+            node_views: HashMap::new(),
             manual_start_allowed: true,
             accepting_new_keys: true,
             builders,
@@ -213,6 +219,10 @@ pub trait OrchestratorApi<KEY: SignatureKey> {
     /// # Errors
     /// if unable to serve
     fn get_start(&self) -> Result<bool, ServerError>;
+    /// get endpoint for when the run starts, in milliseconds since the Unix epoch
+    /// # Errors
+    /// if the network is not ready to start yet
+    fn get_start_time(&self) -> Result<u64, ServerError>;
     /// post endpoint for the results of the run
     /// # Errors
     /// if unable to serve
@@ -233,12 +243,34 @@ pub trait OrchestratorApi<KEY: SignatureKey> {
     /// # Errors
     /// if not all builders are registered yet
     fn get_builders(&self) -> Result<Vec<Url>, ServerError>;
+    /// post endpoint for a node to report the view it is in
+    /// # Errors
+    /// if the node is not part of the run
+    fn post_view(&mut self, node_index: u64, view: u64) -> Result<(), ServerError>;
+    /// get endpoint for the progress of the run
+    /// # Errors
+    /// if unable to serve
+    fn get_status(&self) -> Result<RunStatus, ServerError>;
 }
 
 impl<KEY> OrchestratorState<KEY>
 where
     KEY: serde::Serialize + Clone + SignatureKey + 'static,
 {
+    /// Start the run `start_delay` from now, unless it was started already
+    fn schedule_start(&mut self) {
+        self.start = true;
+        if self.start_time_ms.is_none() {
+            let start_time = SystemTime::now() + self.config.start_delay;
+            let start_time_ms = start_time
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+                .unwrap_or_default();
+            tracing::error!("All nodes are ready, starting the run at {start_time_ms}ms");
+            self.start_time_ms = Some(start_time_ms);
+        }
+    }
+
     /// register a node with an unknown public key.
     /// this method should be used when we don't have a fixed stake table
     fn register_unknown(
@@ -264,7 +296,12 @@ where
         let node_index = self.pub_posted.len() as u64;
 
         // Deserialize the public key
-        let staked_pubkey = PeerConfig::<KEY>::from_bytes(pubkey).unwrap();
+        let Some(staked_pubkey) = PeerConfig::<KEY>::from_bytes(pubkey) else {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BAD_REQUEST,
+                message: "Malformed public key".to_string(),
+            });
+        };
 
         self.config
             .config
@@ -333,7 +370,12 @@ where
         }
 
         // Deserialize the public key
-        let staked_pubkey = PeerConfig::<KEY>::from_bytes(pubkey).unwrap();
+        let Some(staked_pubkey) = PeerConfig::<KEY>::from_bytes(pubkey) else {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BAD_REQUEST,
+                message: "Malformed public key".to_string(),
+            });
+        };
 
         // Check if the node is allowed to connect, returning its index and config entry if so.
         let Some((node_index, node_config)) =
@@ -491,6 +533,13 @@ where
         Ok(self.start)
     }
 
+    fn get_start_time(&self) -> Result<u64, ServerError> {
+        self.start_time_ms.ok_or(ServerError {
+            status: tide_disco::StatusCode::BAD_REQUEST,
+            message: "Network is not ready to start".to_string(),
+        })
+    }
+
     // Assumes nodes do not post 'ready' twice
     fn post_ready(&mut self, peer_config: &PeerConfig<KEY>) -> Result<(), ServerError> {
         // If we have not disabled registration verification.
@@ -522,7 +571,7 @@ where
         {
             self.accepting_new_keys = false;
             self.manual_start_allowed = false;
-            self.start = true;
+            self.schedule_start();
         }
 
         Ok(())
@@ -567,7 +616,7 @@ where
         self.accepting_new_keys = false;
         self.manual_start_allowed = false;
         self.peer_pub_ready = true;
-        self.start = true;
+        self.schedule_start();
 
         Ok(())
     }
@@ -653,6 +702,33 @@ where
         }
         Ok(self.builders.clone())
     }
+
+    fn post_view(&mut self, node_index: u64, view: u64) -> Result<(), ServerError> {
+        if !self
+            .pub_posted
+            .values()
+            .any(|(registered, _)| *registered == node_index)
+        {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::FORBIDDEN,
+                message: format!("Node {node_index} is not registered with the orchestrator"),
+            });
+        }
+        self.node_views.insert(node_index, view);
+        Ok(())
+    }
+
+    fn get_status(&self) -> Result<RunStatus, ServerError> {
+        Ok(RunStatus {
+            expected_nodes: self.config.config.num_nodes_with_stake.get() as u64,
+            registered_nodes: self.pub_posted.len() as u64,
+            ready_nodes: self.nodes_connected.len() as u64,
+            start_time_ms: self.start_time_ms,
+            lowest_view: self.node_views.values().min().copied(),
+            highest_view: self.node_views.values().max().copied(),
+            nodes_posted_results: self.nodes_post_results,
+        })
+    }
 }
 
 /// Sets up all API routes
@@ -759,6 +835,9 @@ where
     .get("get_start", |_req, state| {
         async move { state.get_start() }.boxed()
     })?
+    .get("get_start_time", |_req, state| {
+        async move { state.get_start_time() }.boxed()
+    })?
     .post("post_results", |req, state| {
         async move {
             let metrics: Result<BenchResults, RequestError> = req.body_json();
@@ -808,6 +887,17 @@ where
     })?
     .get("get_builders", |_req, state| {
         async move { state.get_builders() }.boxed()
+    })?
+    .post("post_view", |req, state| {
+        async move {
+            let node_index = req.integer_param("node_index")?;
+            let view = req.integer_param("view")?;
+            state.post_view(node_index, view)
+        }
+        .boxed()
+    })?
+    .get("get_status", |_req, state| {
+        async move { state.get_status() }.boxed()
     })?;
     Ok(api)
 }
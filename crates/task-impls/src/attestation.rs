@@ -0,0 +1,284 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_broadcast::{Receiver, Sender};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use committable::Committable;
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    attestation::{AttestationNonces, AttestationRequest, NonceUse},
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_vote::AttestationVote,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        proposal_validator::ProposalValidator,
+        signature_key::SignatureKey,
+    },
+    utils::epoch_from_block_number,
+    vote::HasViewNumber,
+};
+use tracing::instrument;
+use utils::anytrace::*;
+
+use crate::{events::HotShotEvent, helpers::broadcast_event};
+
+/// Number of nonces remembered for each requester of attestations
+pub const ATTESTATION_NONCES: usize = 1024;
+
+/// Number of requests kept for views we have not decided yet
+pub const MAX_PENDING_ATTESTATION_REQUESTS: usize = 64;
+
+/// Signs attestations of application messages, bound to leaves we decided, for the other members
+/// of the quorum.
+pub struct AttestationTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
+    /// Our public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// Our private key, used to sign attestation votes
+    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// Membership, used to check that requesters and we are in the quorum
+    pub membership: Arc<RwLock<TYPES::Membership>>,
+
+    /// Lock for a decided upgrade
+    pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Immutable instance state, passed to the application's checks of the messages
+    pub instance_state: Arc<TYPES::InstanceState>,
+
+    /// The latest decided leaves, with the state after them, by view
+    pub decided: BTreeMap<TYPES::View, (Leaf2<TYPES>, Arc<TYPES::ValidatedState>)>,
+
+    /// Requests for views we have not decided yet, by view
+    pub pending: BTreeMap<TYPES::View, Vec<AttestationRequest<TYPES>>>,
+
+    /// The nonces we signed attestations for, so that we never sign two messages under a nonce
+    pub nonces: AttestationNonces<TYPES>,
+
+    /// Number of the latest decided leaves we attest to messages for
+    pub attestation_window: u64,
+
+    /// Number of blocks in an epoch, zero means there are no epochs
+    pub epoch_height: u64,
+
+    /// The node's id
+    pub id: u64,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> AttestationTaskState<TYPES, I, V> {
+    /// Remember newly decided leaves, and answer the requests which were waiting for them.
+    async fn record_decided(
+        &mut self,
+        leaves: &[(Leaf2<TYPES>, Arc<TYPES::ValidatedState>)],
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) {
+        for (leaf, state) in leaves {
+            self.decided
+                .insert(leaf.view_number(), (leaf.clone(), Arc::clone(state)));
+        }
+        let window = usize::try_from(self.attestation_window).unwrap_or(usize::MAX);
+        while self.decided.len() > window {
+            self.decided.pop_first();
+        }
+
+        let Some(latest) = self.decided.keys().next_back().copied() else {
+            return;
+        };
+        let still_pending = self.pending.split_off(&(latest + 1));
+        let ready = std::mem::replace(&mut self.pending, still_pending);
+        for request in ready.into_values().flatten() {
+            if let Err(e) = self.attest(&request, event_stream).await {
+                tracing::debug!("Not attesting to a pending request: {e}");
+            }
+        }
+    }
+
+    /// Check a request for an attestation, and either answer it or keep it until we decide its
+    /// view.
+    #[instrument(skip_all, fields(id = self.id, view = *request.vote.view_number()))]
+    async fn handle_request(
+        &mut self,
+        request: &AttestationRequest<TYPES>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        ensure!(
+            request.vote.data.requester != self.public_key,
+            debug!("Ignoring our own attestation request")
+        );
+        ensure!(
+            request.is_valid(&self.upgrade_lock).await,
+            warn!(
+                "Invalid attestation request from {}",
+                request.vote.data.requester
+            )
+        );
+
+        let data = &request.vote.data;
+        ensure!(
+            self.membership
+                .read()
+                .await
+                .has_stake(&data.requester, data.epoch),
+            warn!(
+                "Attestation requested by {}, which is not in the quorum",
+                data.requester
+            )
+        );
+
+        let view = request.vote.view_number();
+        if self.decided.contains_key(&view) {
+            return self.attest(request, event_stream).await;
+        }
+
+        let latest = self.decided.keys().next_back().copied();
+        ensure!(
+            !latest.is_some_and(|latest| view <= latest),
+            debug!("Attestation requested for view {view:?}, which we do not keep")
+        );
+        // Only keep requests for views we may decide soon, so that they are not held for long
+        let horizon = latest.unwrap_or(TYPES::View::genesis()) + self.attestation_window;
+        ensure!(
+            view <= horizon,
+            debug!("Attestation requested for view {view:?}, beyond {horizon:?}")
+        );
+        let num_pending: usize = self.pending.values().map(Vec::len).sum();
+        ensure!(
+            num_pending < MAX_PENDING_ATTESTATION_REQUESTS,
+            warn!("Too many pending attestation requests, dropping one for view {view:?}")
+        );
+        self.pending.entry(view).or_default().push(request.clone());
+
+        Ok(())
+    }
+
+    /// Sign and send a vote for the attestation `request` asks for, bound to a leaf we decided.
+    async fn attest(
+        &mut self,
+        request: &AttestationRequest<TYPES>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        let data = &request.vote.data;
+        let view = request.vote.view_number();
+        let (leaf, state) = self
+            .decided
+            .get(&view)
+            .context(debug!("We did not decide view {view:?}"))?;
+
+        ensure!(
+            data.leaf_commit == leaf.commit() && data.height == leaf.height(),
+            warn!("Attestation requested for a leaf we did not decide in view {view:?}")
+        );
+        let epoch = TYPES::Epoch::new(epoch_from_block_number(data.height, self.epoch_height));
+        ensure!(
+            data.epoch == epoch,
+            warn!(
+                "Attestation requested for height {} with the wrong epoch {:?}",
+                data.height, data.epoch
+            )
+        );
+
+        let membership_reader = self.membership.read().await;
+        ensure!(
+            membership_reader.has_stake(&data.requester, epoch),
+            warn!(
+                "Attestation requested by {}, which is not in the quorum",
+                data.requester
+            )
+        );
+        ensure!(
+            membership_reader.has_stake(&self.public_key, epoch),
+            debug!("We are not in the quorum for the attestation's epoch, not signing")
+        );
+        drop(membership_reader);
+
+        if let Err(reason) = I::ProposalValidator::validate_attestation(
+            &self.instance_state,
+            leaf,
+            state,
+            &request.message,
+        )
+        .await
+        {
+            bail!(info!("Attestation refused by the application: {reason}"));
+        }
+
+        match self.nonces.record(data) {
+            NonceUse::Fresh | NonceUse::Repeated => {}
+            NonceUse::Reused => {
+                bail!(warn!(
+                    "Nonce {} of {} was already used for another message",
+                    data.nonce, data.requester
+                ));
+            }
+            NonceUse::Expired => {
+                bail!(warn!(
+                    "Nonce {} of {} has expired",
+                    data.nonce, data.requester
+                ));
+            }
+        }
+
+        let vote = AttestationVote::create_signed_vote(
+            data.clone(),
+            view,
+            &self.public_key,
+            &self.private_key,
+            &self.upgrade_lock,
+        )
+        .await
+        .wrap()
+        .context(error!("Failed to sign attestation vote"))?;
+
+        broadcast_event(
+            Arc::new(HotShotEvent::AttestationVoteSend(vote)),
+            event_stream,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Handles a consensus event received on the event stream
+    pub async fn handle(
+        &mut self,
+        event: Arc<HotShotEvent<TYPES>>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        match event.as_ref() {
+            HotShotEvent::LeavesDecided(leaves) => {
+                self.record_decided(leaves, event_stream).await;
+                Ok(())
+            }
+            HotShotEvent::AttestationRequestRecv(request) => {
+                self.handle_request(request, event_stream).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
+    for AttestationTaskState<TYPES, I, V>
+{
+    type Event = HotShotEvent<TYPES>;
+
+    async fn handle_event(
+        &mut self,
+        event: Arc<Self::Event>,
+        sender: &Sender<Arc<Self::Event>>,
+        _receiver: &Receiver<Arc<Self::Event>>,
+    ) -> Result<()> {
+        self.handle(event, sender).await
+    }
+
+    fn cancel_subtasks(&mut self) {}
+}
@@ -0,0 +1,266 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use async_broadcast::{Receiver, Sender};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use committable::Committable;
+use either::Either;
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    consensus::OuterConsensus,
+    data::Leaf2,
+    event::{Event, EventType},
+    message::UpgradeLock,
+    simple_certificate::CheckpointCertificate,
+    simple_vote::{CheckpointData, CheckpointVote},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        signature_key::SignatureKey,
+        storage::Storage,
+        ValidatedState,
+    },
+    utils::epoch_from_block_number,
+    vote::{HasViewNumber, VerifiedVotes, Vote, VoteAccumulator},
+};
+use tracing::instrument;
+use utils::anytrace::*;
+
+use crate::{events::HotShotEvent, helpers::broadcast_event};
+
+/// Alias for the accumulator of checkpoint votes
+type CheckpointVoteAccumulator<TYPES, V> =
+    VoteAccumulator<TYPES, CheckpointVote<TYPES>, CheckpointCertificate<TYPES>, V>;
+
+/// Votes on the application state at checkpoint heights, and collects the votes of other nodes
+/// into checkpoint certificates.
+pub struct CheckpointTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
+    /// Our public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// Our private key, used to sign checkpoint votes
+    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// Reference to consensus, which holds the latest checkpoint certificate
+    pub consensus: OuterConsensus<TYPES>,
+
+    /// Membership, used to weigh checkpoint votes
+    pub membership: Arc<RwLock<TYPES::Membership>>,
+
+    /// Lock for a decided upgrade
+    pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
+
+    /// Storage in which checkpoint certificates are persisted
+    pub storage: Arc<RwLock<I::Storage>>,
+
+    /// Output events to application
+    pub output_event_stream: async_broadcast::Sender<Event<TYPES>>,
+
+    /// Vote accumulators for the checkpoints which have not been certified yet, by height
+    pub accumulators: BTreeMap<u64, CheckpointVoteAccumulator<TYPES, V>>,
+
+    /// Number of blocks between checkpoints
+    pub checkpoint_interval: u64,
+
+    /// Number of blocks in an epoch, zero means there are no epochs
+    pub epoch_height: u64,
+
+    /// The node's id
+    pub id: u64,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CheckpointTaskState<TYPES, I, V> {
+    /// The height of the latest certified checkpoint, if any.
+    async fn certified_height(&self) -> Option<u64> {
+        self.consensus
+            .read()
+            .await
+            .checkpoint_certificate()
+            .map(|certificate| certificate.data.height)
+    }
+
+    /// Sign and send a vote for a decided checkpoint.
+    #[instrument(skip_all, fields(id = self.id, height = leaf.height()))]
+    async fn vote_on_checkpoint(
+        &mut self,
+        leaf: &Leaf2<TYPES>,
+        state: &TYPES::ValidatedState,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        let epoch = TYPES::Epoch::new(epoch_from_block_number(leaf.height(), self.epoch_height));
+        ensure!(
+            self.membership
+                .read()
+                .await
+                .has_stake(&self.public_key, epoch),
+            debug!("We are not in the quorum for the checkpoint's epoch, not voting")
+        );
+
+        let vote = CheckpointVote::create_signed_vote(
+            CheckpointData {
+                height: leaf.height(),
+                leaf_commit: leaf.commit(),
+                state_digest: state.state_digest(),
+                epoch,
+            },
+            leaf.view_number(),
+            &self.public_key,
+            &self.private_key,
+            &self.upgrade_lock,
+        )
+        .await
+        .wrap()
+        .context(error!("Failed to sign checkpoint vote"))?;
+
+        broadcast_event(
+            Arc::new(HotShotEvent::CheckpointVoteSend(vote.clone())),
+            event_stream,
+        )
+        .await;
+
+        self.accumulate(&vote).await
+    }
+
+    /// Add a checkpoint vote to its accumulator, and certify the checkpoint if enough votes have
+    /// been collected.
+    ///
+    /// Only votes by staked nodes for a checkpoint height at most one checkpoint past our latest
+    /// decided leaf are considered, and an accumulator is only kept once a valid vote was counted
+    /// in it, so that invalid votes cannot make us hold accumulators for arbitrary heights.
+    async fn accumulate(&mut self, vote: &CheckpointVote<TYPES>) -> Result<()> {
+        let height = vote.data.height;
+        if self
+            .certified_height()
+            .await
+            .is_some_and(|certified| height <= certified)
+        {
+            return Ok(());
+        }
+
+        let interval = self.checkpoint_interval.max(1);
+        let decided_height = self.consensus.read().await.decided_leaf().height();
+        ensure!(
+            height % interval == 0 && height <= decided_height + interval,
+            warn!(
+                "Checkpoint vote for height {height} is not for a checkpoint near our decided \
+                 height {decided_height}"
+            )
+        );
+
+        let epoch = vote.data.epoch;
+        ensure!(
+            epoch == TYPES::Epoch::new(epoch_from_block_number(height, self.epoch_height)),
+            warn!("Checkpoint vote for height {height} has the wrong epoch {epoch:?}")
+        );
+        ensure!(
+            self.membership
+                .read()
+                .await
+                .has_stake(&vote.signing_key(), epoch),
+            warn!("Checkpoint vote for height {height} is not from a staked node")
+        );
+
+        let mut accumulator =
+            self.accumulators
+                .remove(&height)
+                .unwrap_or_else(|| VoteAccumulator {
+                    vote_outcomes: HashMap::new(),
+                    signers: HashMap::new(),
+                    phantom: PhantomData,
+                    upgrade_lock: self.upgrade_lock.clone(),
+                    verified_votes: self.verified_votes.clone(),
+                });
+
+        match accumulator.accumulate(vote, &self.membership, epoch).await {
+            Either::Right(certificate) => self.certify(certificate).await?,
+            // The vote is dropped by the accumulator if its signature does not check out
+            Either::Left(()) if !accumulator.signers.is_empty() => {
+                self.accumulators.insert(height, accumulator);
+            }
+            Either::Left(()) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Record a newly formed checkpoint certificate, and drop the votes it supersedes.
+    #[instrument(skip_all, fields(id = self.id, height = certificate.data.height))]
+    async fn certify(&mut self, certificate: CheckpointCertificate<TYPES>) -> Result<()> {
+        let height = certificate.data.height;
+        tracing::info!("Formed checkpoint certificate for height {height}");
+
+        self.accumulators = self.accumulators.split_off(&(height + 1));
+
+        self.consensus
+            .write()
+            .await
+            .update_checkpoint_certificate(certificate.clone())?;
+
+        self.storage
+            .write()
+            .await
+            .update_checkpoint_certificate(&certificate)
+            .await
+            .wrap()
+            .context(error!("Failed to persist checkpoint certificate"))?;
+
+        broadcast_event(
+            Event {
+                view_number: certificate.view_number(),
+                event: EventType::CheckpointCertified {
+                    certificate: Arc::new(certificate),
+                },
+            },
+            &self.output_event_stream,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Handles a consensus event received on the event stream
+    pub async fn handle(
+        &mut self,
+        event: Arc<HotShotEvent<TYPES>>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        match event.as_ref() {
+            HotShotEvent::CheckpointDecided(leaf, state) => {
+                self.vote_on_checkpoint(leaf, state, event_stream).await
+            }
+            HotShotEvent::CheckpointVoteRecv(vote) => self.accumulate(vote).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
+    for CheckpointTaskState<TYPES, I, V>
+{
+    type Event = HotShotEvent<TYPES>;
+
+    async fn handle_event(
+        &mut self,
+        event: Arc<Self::Event>,
+        sender: &Sender<Arc<Self::Event>>,
+        _receiver: &Receiver<Arc<Self::Event>>,
+    ) -> Result<()> {
+        self.handle(event, sender).await
+    }
+
+    fn cancel_subtasks(&mut self) {}
+}
@@ -10,6 +10,7 @@ use async_broadcast::Sender;
 use chrono::Utc;
 use hotshot_types::{
     event::{Event, EventType},
+    message::SignedHighQc,
     simple_vote::{HasEpoch, QuorumVote2, TimeoutData2, TimeoutVote2},
     traits::{
         election::Membership,
@@ -25,8 +26,10 @@ use vbs::version::StaticVersionType;
 
 use super::ConsensusTaskState;
 use crate::{
-    consensus::Versions, events::HotShotEvent, helpers::broadcast_event,
-    vote_collection::handle_vote,
+    consensus::Versions,
+    events::HotShotEvent,
+    helpers::broadcast_event,
+    vote_collection::{collect_garbage, handle_vote},
 };
 
 /// Handle a `QuorumVoteRecv` event.
@@ -74,6 +77,7 @@ pub(crate) async fn handle_quorum_vote_recv<
         &event,
         sender,
         &task_state.upgrade_lock,
+        &task_state.verified_votes,
         transition_indicator.clone(),
     )
     .await?;
@@ -95,6 +99,7 @@ pub(crate) async fn handle_quorum_vote_recv<
             &event,
             sender,
             &task_state.upgrade_lock,
+            &task_state.verified_votes,
             transition_indicator,
         )
         .await?;
@@ -138,6 +143,7 @@ pub(crate) async fn handle_timeout_vote_recv<
         &event,
         sender,
         &task_state.upgrade_lock,
+        &task_state.verified_votes,
         EpochTransitionIndicator::NotInTransition,
     )
     .await?;
@@ -161,7 +167,11 @@ pub async fn send_high_qc<TYPES: NodeType, V: Versions, I: NodeImplementation<TY
         version >= V::Epochs::VERSION,
         debug!("HotStuff 2 upgrade not yet in effect")
     );
-    let high_qc = task_state.consensus.read().await.high_qc().clone();
+    let high_qc = SignedHighQc::create(
+        task_state.consensus.read().await.high_qc().clone(),
+        new_view_number,
+        &task_state.private_key,
+    )?;
     let leader = task_state
         .membership
         .read()
@@ -229,6 +239,15 @@ pub(crate) async fn handle_view_change<
         .await
         .update_view(new_view_number)?;
 
+    // Drop the vote collectors for views which are older than the last decided view
+    let last_decided_view = task_state.consensus.read().await.last_decided_view();
+    collect_garbage(&mut task_state.vote_collectors, last_decided_view);
+    collect_garbage(
+        &mut task_state.next_epoch_vote_collectors,
+        last_decided_view,
+    );
+    collect_garbage(&mut task_state.timeout_vote_collectors, last_decided_view);
+
     // If we have a decided upgrade certificate, the protocol version may also have been upgraded.
     let decided_upgrade_certificate_read = task_state
         .upgrade_lock
@@ -22,7 +22,7 @@ use hotshot_types::{
         signature_key::SignatureKey,
     },
     utils::epoch_from_block_number,
-    vote::HasViewNumber,
+    vote::{HasViewNumber, VerifiedVotes},
 };
 use tokio::task::JoinHandle;
 use tracing::instrument;
@@ -95,6 +95,9 @@ pub struct ConsensusTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
 
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
+
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
 }
@@ -122,6 +125,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> ConsensusTaskSt
                     tracing::debug!("Failed to handle TimeoutVoteRecv event; error = {e}");
                 }
             }
+            HotShotEvent::ConfigUpdated(update) => {
+                if let Some(timeout) = update.next_view_timeout {
+                    self.timeout = timeout;
+                }
+            }
             HotShotEvent::ViewChange(new_view_number, epoch_number) => {
                 if let Err(e) =
                     handle_view_change(*new_view_number, *epoch_number, &sender, self).await
@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{marker::PhantomData, sync::Arc};
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
 
 use async_broadcast::{Receiver, Sender};
 use async_lock::RwLock;
@@ -12,7 +12,7 @@ use async_trait::async_trait;
 use hotshot_task::task::TaskState;
 use hotshot_types::{
     consensus::{Consensus, OuterConsensus},
-    data::{DaProposal2, PackedBundle},
+    data::{DaChunk, DaProposal2, PackedBundle},
     event::{Event, EventType},
     message::{Proposal, UpgradeLock},
     simple_certificate::DaCertificate2,
@@ -21,22 +21,26 @@ use hotshot_types::{
         block_contents::vid_commitment,
         election::Membership,
         network::ConnectedNetwork,
-        node_implementation::{NodeImplementation, NodeType, Versions},
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        proposal_validator::ProposalValidator,
         signature_key::SignatureKey,
         storage::Storage,
+        BlockPayload,
     },
     utils::EpochTransitionIndicator,
-    vote::HasViewNumber,
+    vid::VidCommitment,
+    vote::{HasViewNumber, VerifiedVotes},
 };
 use sha2::{Digest, Sha256};
 use tokio::{spawn, task::spawn_blocking};
 use tracing::instrument;
 use utils::anytrace::*;
+use vbs::version::StaticVersionType;
 
 use crate::{
     events::HotShotEvent,
     helpers::broadcast_event,
-    vote_collection::{handle_vote, VoteCollectorsMap},
+    vote_collection::{collect_garbage, handle_vote, VoteCollectorsMap},
 };
 
 /// Tracks state of a DA task
@@ -78,6 +82,43 @@ pub struct DaTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Version
 
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
+
+    /// Whether we send each DA committee member a chunk of the payload, rather than the whole
+    /// payload, when we lead
+    pub chunked_dispersal: bool,
+
+    /// Whether we check payloads against the application's rules before voting for them
+    pub payload_validation: bool,
+
+    /// Immutable instance state, passed to the application's checks of payloads
+    pub instance_state: Arc<TYPES::InstanceState>,
+
+    /// The chunks we collect from the rest of the DA committee, by view, to recover the payloads
+    /// we validate before voting under chunked dispersal
+    pub payload_recoveries: BTreeMap<TYPES::View, PayloadRecovery<TYPES>>,
+}
+
+/// The chunks of a payload dispersed to the DA committee, collected to recover the payload
+#[derive(Debug)]
+pub struct PayloadRecovery<TYPES: NodeType> {
+    /// Our own chunk, which we vote for once the recovered payload passes validation
+    pub own_chunk: Option<DaChunk<TYPES>>,
+
+    /// The chunks the other members shared with us, by member
+    pub chunks: BTreeMap<TYPES::SignatureKey, DaChunk<TYPES>>,
+}
+
+impl<TYPES: NodeType> PayloadRecovery<TYPES> {
+    /// A recovery without any chunks yet
+    fn new() -> Self {
+        Self {
+            own_chunk: None,
+            chunks: BTreeMap::new(),
+        }
+    }
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYPES, I, V> {
@@ -183,6 +224,20 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     spawn_blocking(move || vid_commitment(&txns, num_nodes)).await;
                 let payload_commitment = payload_commitment.unwrap();
 
+                if self.payload_validation
+                    && !self
+                        .validate_payload(
+                            view_number,
+                            &proposal.data.encoded_transactions,
+                            &proposal.data.metadata,
+                            payload_commitment,
+                            &event_stream,
+                        )
+                        .await
+                {
+                    return Ok(());
+                }
+
                 self.storage
                     .write()
                     .await
@@ -223,6 +278,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                 ) {
                     tracing::trace!("{e:?}");
                 }
+                consensus_writer.cache_payload(
+                    payload_commitment,
+                    Arc::clone(&proposal.data.encoded_transactions),
+                );
                 // Optimistically calculate and update VID if we know that the primary network is down.
                 if self.network.is_primary_down() {
                     let consensus =
@@ -258,6 +317,148 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     });
                 }
             }
+            HotShotEvent::DaChunkRecv(chunk, sender) => {
+                let view_number = chunk.data.view_number();
+                let epoch_number = chunk.data.epoch;
+                tracing::debug!("DA chunk received for view: {:?}", view_number);
+
+                // Like DA proposals, allow a chunk that is one view older.
+                ensure!(
+                    self.cur_view <= view_number + 1,
+                    "Throwing away DA chunk that is more than one view older"
+                );
+                ensure!(
+                    chunk.data.recipient_key == self.public_key,
+                    warn!("Received a DA chunk meant for another node")
+                );
+
+                let membership_reader = self.membership.read().await;
+                let view_leader_key = membership_reader.leader(view_number, epoch_number)?;
+                ensure!(
+                    view_leader_key == *sender,
+                    warn!(
+                        "DA chunk doesn't have expected leader key for view {}",
+                        *view_number
+                    )
+                );
+                ensure!(
+                    membership_reader.has_da_stake(&self.public_key, epoch_number),
+                    debug!(
+                        "We were not chosen for consensus committee for view {:?} in epoch {:?}",
+                        view_number, epoch_number
+                    )
+                );
+                let da_committee =
+                    membership_reader.da_committee_members(view_number, epoch_number);
+                let num_nodes = membership_reader.total_nodes(epoch_number);
+                drop(membership_reader);
+
+                let (proposal, committee) = (chunk.clone(), da_committee.clone());
+                let valid = spawn_blocking(move || {
+                    DaChunk::is_valid(&proposal, &view_leader_key, &committee, num_nodes)
+                })
+                .await
+                .unwrap_or(false);
+                ensure!(valid, warn!("Could not verify DA chunk."));
+
+                // Keep the chunk we are about to vote for, so that we can serve it to those
+                // recovering the payload.
+                if let Err(e) = self.consensus.write().await.update_da_chunk(chunk.clone()) {
+                    tracing::trace!("{e:?}");
+                }
+
+                if !self.payload_validation {
+                    return self.vote_for_chunk(&chunk.data, &event_stream).await;
+                }
+
+                // Share our chunk with the rest of the committee, and vote once we have recovered
+                // the payload from theirs and it passes validation
+                for member in da_committee
+                    .iter()
+                    .filter(|member| **member != self.public_key)
+                {
+                    broadcast_event(
+                        Arc::new(HotShotEvent::DaChunkResponseSend(
+                            self.public_key.clone(),
+                            member.clone(),
+                            chunk.clone(),
+                        )),
+                        &event_stream,
+                    )
+                    .await;
+                }
+                self.payload_recoveries
+                    .entry(view_number)
+                    .or_insert_with(PayloadRecovery::new)
+                    .own_chunk = Some(chunk.data.clone());
+                self.recover_payload(view_number, &event_stream).await?;
+            }
+            HotShotEvent::DaChunkResponseRecv(member, chunk) => {
+                if !self.payload_validation {
+                    return Ok(());
+                }
+                let view_number = chunk.data.view_number();
+                let epoch_number = chunk.data.epoch;
+
+                // Members share their chunks as soon as they receive them, which may be before we
+                // enter the view the chunks are for.
+                ensure!(
+                    self.cur_view <= view_number + 1 && view_number <= self.cur_view + 2,
+                    debug!(
+                        "Ignoring a DA chunk for view {view_number:?}, which we are not recovering"
+                    )
+                );
+                ensure!(
+                    *member != self.public_key && chunk.data.recipient_key == *member,
+                    warn!("Received a DA chunk from {member} which was meant for another node")
+                );
+                ensure!(
+                    !self
+                        .payload_recoveries
+                        .get(&view_number)
+                        .is_some_and(|recovery| recovery.chunks.contains_key(member)),
+                    debug!("Already have the DA chunk of {member} for view {view_number:?}")
+                );
+
+                let membership_reader = self.membership.read().await;
+                let view_leader_key = membership_reader.leader(view_number, epoch_number)?;
+                let da_committee =
+                    membership_reader.da_committee_members(view_number, epoch_number);
+                let num_nodes = membership_reader.total_nodes(epoch_number);
+                drop(membership_reader);
+                ensure!(
+                    da_committee.contains(member),
+                    warn!("Received a DA chunk from {member}, which is not in the DA committee")
+                );
+
+                let proposal = chunk.clone();
+                let valid = spawn_blocking(move || {
+                    DaChunk::is_valid(&proposal, &view_leader_key, &da_committee, num_nodes)
+                })
+                .await
+                .unwrap_or(false);
+                ensure!(valid, warn!("Could not verify the DA chunk of {member}"));
+
+                self.payload_recoveries
+                    .entry(view_number)
+                    .or_insert_with(PayloadRecovery::new)
+                    .chunks
+                    .insert(member.clone(), chunk.data.clone());
+                self.recover_payload(view_number, &event_stream).await?;
+            }
+            HotShotEvent::DaPayloadRejected(view_number, payload_commitment, reason) => {
+                broadcast_event(
+                    Event {
+                        view_number: *view_number,
+                        event: EventType::DaPayloadRejected {
+                            payload_commitment: *payload_commitment,
+                            reason: reason.clone(),
+                        },
+                    },
+                    &self.output_event_stream,
+                )
+                .await;
+            }
             HotShotEvent::DaVoteRecv(ref vote) => {
                 tracing::debug!("DA vote recv, Main Task {:?}", vote.view_number());
                 // Check if we are the leader and the vote is from the sender.
@@ -285,6 +486,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     &event,
                     &event_stream,
                     &self.upgrade_lock,
+                    &self.verified_votes,
                     EpochTransitionIndicator::NotInTransition,
                 )
                 .await?;
@@ -304,6 +506,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     tracing::info!("View changed by more than 1 going to view {:?}", view);
                 }
                 self.cur_view = view;
+                self.payload_recoveries = self
+                    .payload_recoveries
+                    .split_off(&TYPES::View::new(view.saturating_sub(1)));
+
+                let last_decided_view = self.consensus.read().await.last_decided_view();
+                collect_garbage(&mut self.vote_collectors, last_decided_view);
             }
             HotShotEvent::BlockRecv(packed_bundle) => {
                 let PackedBundle::<TYPES> {
@@ -330,6 +538,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     );
                     return Ok(());
                 }
+                if self.chunked_dispersal
+                    && self.upgrade_lock.version_infallible(view_number).await >= V::Epochs::VERSION
+                {
+                    self.send_chunks(packed_bundle, epoch, &event_stream)
+                        .await?;
+                    return Ok(());
+                }
+
                 let data: DaProposal2<TYPES> = DaProposal2 {
                     encoded_transactions: Arc::clone(encoded_transactions),
                     metadata: metadata.clone(),
@@ -368,6 +584,180 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
     }
 }
 
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYPES, I, V> {
+    /// Check the payload proposed in `view` against the application's rules, reporting it if the
+    /// application rejects it. Returns whether the payload passed.
+    async fn validate_payload(
+        &self,
+        view: TYPES::View,
+        encoded_transactions: &[u8],
+        metadata: &<TYPES::BlockPayload as BlockPayload<TYPES>>::Metadata,
+        payload_commitment: VidCommitment,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> bool {
+        let payload = TYPES::BlockPayload::from_bytes(encoded_transactions, metadata);
+        let Err(reason) =
+            I::ProposalValidator::validate_payload(&self.instance_state, view, &payload, metadata)
+                .await
+        else {
+            return true;
+        };
+
+        tracing::warn!("Payload rejected by the application, not voting: {reason}");
+        broadcast_event(
+            Arc::new(HotShotEvent::DaPayloadRejected(
+                view,
+                payload_commitment,
+                reason,
+            )),
+            event_stream,
+        )
+        .await;
+        false
+    }
+
+    /// Recover the payload of `view` once we hold our own chunk and enough chunks of the other
+    /// members, then vote for our chunk if the payload passes validation
+    async fn recover_payload(
+        &mut self,
+        view: TYPES::View,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        let Some(recovery) = self.payload_recoveries.get(&view) else {
+            return Ok(());
+        };
+        let Some(own_chunk) = recovery.own_chunk.clone() else {
+            return Ok(());
+        };
+        // Chunks of another payload, which only an equivocating leader signs, do not count
+        let chunks: Vec<_> = std::iter::once(own_chunk.clone())
+            .chain(recovery.chunks.values().cloned())
+            .filter(|chunk| chunk.payload_commitment == own_chunk.payload_commitment)
+            .collect();
+
+        let num_nodes = self.membership.read().await.total_nodes(own_chunk.epoch);
+        if !DaChunk::can_recover(&chunks, num_nodes) {
+            return Ok(());
+        }
+        self.payload_recoveries.remove(&view);
+
+        let payload = spawn_blocking(move || DaChunk::recover_payload(&chunks, num_nodes))
+            .await
+            .wrap()??;
+        let payload_commitment = own_chunk.payload_commitment;
+        if !self
+            .validate_payload(
+                view,
+                &payload,
+                &own_chunk.metadata,
+                payload_commitment,
+                event_stream,
+            )
+            .await
+        {
+            return Ok(());
+        }
+
+        let payload: Arc<[u8]> = payload.into();
+        {
+            let mut consensus_writer = self.consensus.write().await;
+            if let Err(e) = consensus_writer.update_saved_payloads(view, Arc::clone(&payload)) {
+                tracing::trace!("{e:?}");
+            }
+            consensus_writer.cache_payload(payload_commitment, payload);
+        }
+
+        self.vote_for_chunk(&own_chunk, event_stream).await
+    }
+
+    /// Vote for the payload our `chunk` is of, committing to make it available
+    async fn vote_for_chunk(
+        &self,
+        chunk: &DaChunk<TYPES>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        let view_number = chunk.view_number();
+        let epoch_number = chunk.epoch;
+        let payload_commitment = chunk.payload_commitment;
+        if let Err(e) = self.consensus.write().await.update_da_view(
+            view_number,
+            epoch_number,
+            payload_commitment,
+        ) {
+            tracing::trace!("{e:?}");
+        }
+
+        let vote = DaVote2::create_signed_vote(
+            DaData2 {
+                payload_commit: payload_commitment,
+                epoch: epoch_number,
+            },
+            view_number,
+            &self.public_key,
+            &self.private_key,
+            &self.upgrade_lock,
+        )
+        .await?;
+
+        tracing::debug!("Sending vote to the DA leader {:?}", vote.view_number());
+
+        broadcast_event(Arc::new(HotShotEvent::DaVoteSend(vote)), event_stream).await;
+
+        Ok(())
+    }
+
+    /// Send each DA committee member its chunk of the payload of `packed_bundle`, instead of the
+    /// whole payload
+    async fn send_chunks(
+        &self,
+        packed_bundle: &PackedBundle<TYPES>,
+        epoch: TYPES::Epoch,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<()> {
+        let view_number = packed_bundle.view_number;
+        let membership_reader = self.membership.read().await;
+        let da_committee = membership_reader.da_committee_members(view_number, epoch);
+        let num_nodes = membership_reader.total_nodes(epoch);
+        drop(membership_reader);
+
+        let payload = Arc::clone(&packed_bundle.encoded_transactions);
+        let metadata = packed_bundle.metadata.clone();
+        let chunks = spawn_blocking(move || {
+            DaChunk::<TYPES>::from_payload(
+                &payload,
+                &metadata,
+                view_number,
+                epoch,
+                &da_committee,
+                num_nodes,
+            )
+        })
+        .await
+        .wrap()??;
+        let chunks = chunks
+            .into_iter()
+            .filter_map(|chunk| chunk.to_proposal(&self.private_key))
+            .collect();
+
+        broadcast_event(
+            Arc::new(HotShotEvent::DaChunksSend(chunks, self.public_key.clone())),
+            event_stream,
+        )
+        .await;
+        // Save the payload early because we might need it to calculate VID for the next epoch nodes.
+        if let Err(e) = self
+            .consensus
+            .write()
+            .await
+            .update_saved_payloads(view_number, Arc::clone(&packed_bundle.encoded_transactions))
+        {
+            tracing::trace!("{e:?}");
+        }
+
+        Ok(())
+    }
+}
+
 #[async_trait]
 /// task state implementation for DA Task
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
@@ -4,17 +4,20 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use async_broadcast::Sender;
 use either::Either;
 use hotshot_task::task::TaskEvent;
 use hotshot_types::{
+    attestation::AttestationRequest,
     data::{
-        DaProposal2, Leaf2, PackedBundle, QuorumProposal2, UpgradeProposal, VidDisperse,
+        DaChunk, DaProposal2, Leaf2, PackedBundle, QuorumProposal2, UpgradeProposal, VidDisperse,
         VidDisperseShare2,
     },
-    message::Proposal,
+    evidence::Evidence,
+    message::{Proposal, SignedHighQc},
+    reconfig::ConfigUpdate,
     request_response::ProposalRequestPayload,
     simple_certificate::{
         DaCertificate2, NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2,
@@ -22,14 +25,15 @@ use hotshot_types::{
         ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
     },
     simple_vote::{
-        DaVote2, QuorumVote2, TimeoutVote2, UpgradeVote, ViewSyncCommitVote2,
-        ViewSyncFinalizeVote2, ViewSyncPreCommitVote2,
+        AttestationVote, CheckpointVote, DaVote2, QuorumVote2, TimeoutVote2, UpgradeVote,
+        ViewSyncCommitVote2, ViewSyncFinalizeVote2, ViewSyncPreCommitVote2,
     },
     traits::{
         block_contents::BuilderFee, network::DataRequest, node_implementation::NodeType,
         signature_key::SignatureKey, BlockPayload,
     },
     utils::BuilderCommitment,
+    validator_metadata::SignedValidatorMetadata,
     vid::VidCommitment,
     vote::HasViewNumber,
 };
@@ -83,6 +87,10 @@ pub enum HotShotEvent<TYPES: NodeType> {
     DaProposalRecv(Proposal<TYPES, DaProposal2<TYPES>>, TYPES::SignatureKey),
     /// A DA proposal has been validated; handled by the DA task and VID task
     DaProposalValidated(Proposal<TYPES, DaProposal2<TYPES>>, TYPES::SignatureKey),
+    /// The payload with the given commitment, proposed to the DA committee in the given view, was
+    /// rejected by the application's proposal validator, so we did not vote for it; emitted by
+    /// the DA task
+    DaPayloadRejected(TYPES::View, VidCommitment, String),
     /// A DA vote has been received by the network; handled by the DA task
     DaVoteRecv(DaVote2<TYPES>),
     /// A Data Availability Certificate (DAC) has been received by the network; handled by the consensus task
@@ -102,6 +110,9 @@ pub enum HotShotEvent<TYPES: NodeType> {
     /// 3. The justify QC is valid
     /// 4. The proposal passes either liveness or safety check.
     QuorumProposalValidated(Proposal<TYPES, QuorumProposal2<TYPES>>, Leaf2<TYPES>),
+    /// A validated quorum proposal was rejected by the application's proposal validator, so we
+    /// did not vote for it; emitted by the quorum vote task
+    QuorumProposalRejected(Proposal<TYPES, QuorumProposal2<TYPES>>, String),
     /// A quorum proposal is missing for a view that we need.
     QuorumProposalRequestSend(
         ProposalRequestPayload<TYPES>,
@@ -193,6 +204,11 @@ pub enum HotShotEvent<TYPES: NodeType> {
     ),
     /// VID share data is validated.
     VidShareValidated(Proposal<TYPES, VidDisperseShare2<TYPES>>),
+    /// Send each DA committee member its chunk of the payload; emitted by the DA leader instead
+    /// of [`HotShotEvent::DaProposalSend`] when the payload is dispersed in chunks
+    DaChunksSend(Vec<Proposal<TYPES, DaChunk<TYPES>>>, TYPES::SignatureKey),
+    /// A chunk of the payload has been received from the DA leader; handled by the DA task
+    DaChunkRecv(Proposal<TYPES, DaChunk<TYPES>>, TYPES::SignatureKey),
     /// Upgrade proposal has been received from the network
     UpgradeProposalRecv(Proposal<TYPES, UpgradeProposal<TYPES>>, TYPES::SignatureKey),
     /// Upgrade proposal has been sent to the network
@@ -240,15 +256,102 @@ pub enum HotShotEvent<TYPES: NodeType> {
         Proposal<TYPES, VidDisperseShare2<TYPES>>,
     ),
 
+    /// Ask a DA committee member for its chunk of the payload of a view, to recover the payload.
+    /// Includes the data request, our public key, and the public key of the member.
+    DaChunkRequestSend(
+        DataRequest<TYPES>,
+        // Sender
+        TYPES::SignatureKey,
+        // Recipient
+        TYPES::SignatureKey,
+    ),
+
+    /// Receive a request for our chunk of the payload of a view; received by a DA committee
+    /// member. Includes the data request and the public key of the requester.
+    DaChunkRequestRecv(DataRequest<TYPES>, TYPES::SignatureKey),
+
+    /// Send our chunk of the payload of a view to the node which asked for it
+    DaChunkResponseSend(
+        /// Sender key
+        TYPES::SignatureKey,
+        /// Recipient key
+        TYPES::SignatureKey,
+        Proposal<TYPES, DaChunk<TYPES>>,
+    ),
+
+    /// Receive a chunk of the payload of a view which we asked a DA committee member for
+    DaChunkResponseRecv(TYPES::SignatureKey, Proposal<TYPES, DaChunk<TYPES>>),
+
+    /// Ask an archival node for a range of finalized leaves, to catch up.
+    /// Includes the data request, our public key, and the public key of the archival node.
+    LeafRangeRequestSend(
+        DataRequest<TYPES>,
+        // Sender
+        TYPES::SignatureKey,
+        // Recipient
+        TYPES::SignatureKey,
+    ),
+
+    /// Receive a request for a range of finalized leaves; received by an archival node.
+    /// Includes the data request and the public key of the requester.
+    LeafRangeRequestRecv(DataRequest<TYPES>, TYPES::SignatureKey),
+
+    /// Send a range of finalized leaves, oldest first, to the node which asked for it
+    LeafRangeResponseSend(
+        /// Sender key
+        TYPES::SignatureKey,
+        /// Recipient key
+        TYPES::SignatureKey,
+        Vec<(Leaf2<TYPES>, QuorumCertificate2<TYPES>)>,
+    ),
+
+    /// Receive a range of finalized leaves which we asked an archival node for
+    LeafRangeResponseRecv(
+        TYPES::SignatureKey,
+        Vec<(Leaf2<TYPES>, QuorumCertificate2<TYPES>)>,
+    ),
+
     /// A replica send us a High QC
-    HighQcRecv(QuorumCertificate2<TYPES>, TYPES::SignatureKey),
+    HighQcRecv(SignedHighQc<TYPES>, TYPES::SignatureKey),
+
+    /// A replica running a version from before signed high QCs sent us its High QC
+    UnsignedHighQcRecv(QuorumCertificate2<TYPES>, TYPES::SignatureKey),
 
     /// Send our HighQc to the next leader, should go to the same leader as our vote
     HighQcSend(
-        QuorumCertificate2<TYPES>,
+        SignedHighQc<TYPES>,
         TYPES::SignatureKey,
         TYPES::SignatureKey,
     ),
+
+    /// A task observed a protocol violation by another node; handled by the evidence task
+    ProtocolViolation(Evidence<TYPES>),
+
+    /// A leaf at a checkpoint height was decided, along with the state after applying it
+    CheckpointDecided(Leaf2<TYPES>, Arc<TYPES::ValidatedState>),
+    /// Send a checkpoint vote to the network; emitted by the checkpoint task
+    CheckpointVoteSend(CheckpointVote<TYPES>),
+    /// A checkpoint vote has been received from the network
+    CheckpointVoteRecv(CheckpointVote<TYPES>),
+
+    /// Leaves were decided, newest first, each along with the state after applying it
+    LeavesDecided(Vec<(Leaf2<TYPES>, Arc<TYPES::ValidatedState>)>),
+    /// Ask the quorum to attest to a message; emitted by the handle
+    AttestationRequestSend(AttestationRequest<TYPES>),
+    /// A request for an attestation has been received from the network
+    AttestationRequestRecv(AttestationRequest<TYPES>),
+    /// Send an attestation vote to its requester; emitted by the attestation task
+    AttestationVoteSend(AttestationVote<TYPES>),
+    /// An attestation vote for one of our requests has been received from the network
+    AttestationVoteRecv(AttestationVote<TYPES>),
+
+    /// Gossip the identity of a validator to every node; emitted by the validator metadata task
+    ValidatorMetadataSend(SignedValidatorMetadata<TYPES>),
+    /// The identity of a validator has been received from the network
+    ValidatorMetadataRecv(SignedValidatorMetadata<TYPES>),
+
+    /// Parameters of this node were changed at runtime; tasks holding them switch to the new values
+    ConfigUpdated(ConfigUpdate),
 }
 
 impl<TYPES: NodeType> HotShotEvent<TYPES> {
@@ -263,6 +366,7 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             HotShotEvent::QuorumProposalRecv(proposal, _)
             | HotShotEvent::QuorumProposalSend(proposal, _)
             | HotShotEvent::QuorumProposalValidated(proposal, _)
+            | HotShotEvent::QuorumProposalRejected(proposal, _)
             | HotShotEvent::QuorumProposalResponseRecv(proposal)
             | HotShotEvent::QuorumProposalResponseSend(_, proposal)
             | HotShotEvent::QuorumProposalPreliminarilyValidated(proposal) => {
@@ -274,9 +378,22 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             HotShotEvent::DaProposalRecv(proposal, _)
             | HotShotEvent::DaProposalValidated(proposal, _)
             | HotShotEvent::DaProposalSend(proposal, _) => Some(proposal.data.view_number()),
+            HotShotEvent::DaPayloadRejected(view_number, _, _) => Some(*view_number),
             HotShotEvent::DaVoteRecv(vote) | HotShotEvent::DaVoteSend(vote) => {
                 Some(vote.view_number())
             }
+            HotShotEvent::DaChunksSend(chunks, _) => {
+                chunks.first().map(|chunk| chunk.data.view_number())
+            }
+            HotShotEvent::DaChunkRecv(chunk, _)
+            | HotShotEvent::DaChunkResponseSend(_, _, chunk)
+            | HotShotEvent::DaChunkResponseRecv(_, chunk) => Some(chunk.data.view_number()),
+            HotShotEvent::DaChunkRequestSend(request, _, _)
+            | HotShotEvent::DaChunkRequestRecv(request, _) => Some(request.view),
+            HotShotEvent::LeafRangeRequestSend(..)
+            | HotShotEvent::LeafRangeRequestRecv(..)
+            | HotShotEvent::LeafRangeResponseSend(..)
+            | HotShotEvent::LeafRangeResponseRecv(..) => None,
             HotShotEvent::QcFormed(cert) => match cert {
                 either::Left(qc) => Some(qc.view_number()),
                 either::Right(tc) => Some(tc.view_number()),
@@ -307,7 +424,10 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             HotShotEvent::BlockRecv(packed_bundle) => Some(packed_bundle.view_number),
             HotShotEvent::Shutdown
             | HotShotEvent::TransactionSend(_, _)
-            | HotShotEvent::TransactionsRecv(_) => None,
+            | HotShotEvent::TransactionsRecv(_)
+            | HotShotEvent::ConfigUpdated(_)
+            | HotShotEvent::ValidatorMetadataSend(_)
+            | HotShotEvent::ValidatorMetadataRecv(_) => None,
             HotShotEvent::VidDisperseSend(proposal, _) => Some(proposal.data.view_number()),
             HotShotEvent::VidShareRecv(_, proposal) | HotShotEvent::VidShareValidated(proposal) => {
                 Some(proposal.data.view_number())
@@ -332,8 +452,22 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             | HotShotEvent::VidRequestRecv(request, _) => Some(request.view),
             HotShotEvent::VidResponseSend(_, _, proposal)
             | HotShotEvent::VidResponseRecv(_, proposal) => Some(proposal.data.view_number),
-            HotShotEvent::HighQcRecv(qc, _) | HotShotEvent::HighQcSend(qc, ..) => {
-                Some(qc.view_number())
+            HotShotEvent::HighQcRecv(high_qc, _) | HotShotEvent::HighQcSend(high_qc, ..) => {
+                Some(high_qc.qc.view_number())
+            }
+            HotShotEvent::UnsignedHighQcRecv(qc, _) => Some(qc.view_number()),
+            HotShotEvent::ProtocolViolation(evidence) => Some(evidence.view_number),
+            HotShotEvent::CheckpointDecided(leaf, _) => Some(leaf.view_number()),
+            HotShotEvent::CheckpointVoteSend(vote) | HotShotEvent::CheckpointVoteRecv(vote) => {
+                Some(vote.view_number())
+            }
+            HotShotEvent::LeavesDecided(leaves) => {
+                leaves.first().map(|(leaf, _)| leaf.view_number())
+            }
+            HotShotEvent::AttestationRequestSend(request)
+            | HotShotEvent::AttestationRequestRecv(request) => Some(request.vote.view_number()),
+            HotShotEvent::AttestationVoteSend(vote) | HotShotEvent::AttestationVoteRecv(vote) => {
+                Some(vote.view_number())
             }
         }
     }
@@ -375,6 +509,10 @@ impl<TYPES: NodeType> Display for HotShotEvent<TYPES> {
                 "DaProposalValidated(view_number={:?})",
                 proposal.data.view_number()
             ),
+            HotShotEvent::DaPayloadRejected(view_number, _, reason) => write!(
+                f,
+                "DaPayloadRejected(view_number={view_number:?}, reason={reason})"
+            ),
             HotShotEvent::DaVoteRecv(vote) => {
                 write!(f, "DaVoteRecv(view_number={:?})", vote.view_number())
             }
@@ -399,6 +537,11 @@ impl<TYPES: NodeType> Display for HotShotEvent<TYPES> {
                 "QuorumProposalValidated(view_number={:?})",
                 proposal.data.view_number()
             ),
+            HotShotEvent::QuorumProposalRejected(proposal, reason) => write!(
+                f,
+                "QuorumProposalRejected(view_number={:?}, reason={reason})",
+                proposal.data.view_number()
+            ),
             HotShotEvent::DaProposalSend(proposal, _) => write!(
                 f,
                 "DaProposalSend(view_number={:?})",
@@ -607,12 +750,120 @@ impl<TYPES: NodeType> Display for HotShotEvent<TYPES> {
                     proposal.data.view_number
                 )
             }
-            HotShotEvent::HighQcRecv(qc, _) => {
-                write!(f, "HighQcRecv(view_number={:?}", qc.view_number())
+            HotShotEvent::DaChunksSend(chunks, _) => write!(
+                f,
+                "DaChunksSend(view_number={:?})",
+                chunks.first().map(|chunk| chunk.data.view_number())
+            ),
+            HotShotEvent::DaChunkRecv(chunk, _) => {
+                write!(f, "DaChunkRecv(view_number={:?})", chunk.data.view_number())
+            }
+            HotShotEvent::DaChunkRequestSend(request, _, _) => {
+                write!(f, "DaChunkRequestSend(view_number={:?})", request.view)
+            }
+            HotShotEvent::DaChunkRequestRecv(request, _) => {
+                write!(f, "DaChunkRequestRecv(view_number={:?})", request.view)
+            }
+            HotShotEvent::DaChunkResponseSend(_, _, chunk) => write!(
+                f,
+                "DaChunkResponseSend(view_number={:?})",
+                chunk.data.view_number()
+            ),
+            HotShotEvent::DaChunkResponseRecv(_, chunk) => write!(
+                f,
+                "DaChunkResponseRecv(view_number={:?})",
+                chunk.data.view_number()
+            ),
+            HotShotEvent::LeafRangeRequestSend(request, _, _) => {
+                write!(f, "LeafRangeRequestSend({:?})", request.request)
             }
-            HotShotEvent::HighQcSend(qc, ..) => {
-                write!(f, "HighQcSend(view_number={:?}", qc.view_number())
+            HotShotEvent::LeafRangeRequestRecv(request, _) => {
+                write!(f, "LeafRangeRequestRecv({:?})", request.request)
             }
+            HotShotEvent::LeafRangeResponseSend(_, _, leaves) => {
+                write!(f, "LeafRangeResponseSend(leaves={})", leaves.len())
+            }
+            HotShotEvent::LeafRangeResponseRecv(_, leaves) => {
+                write!(f, "LeafRangeResponseRecv(leaves={})", leaves.len())
+            }
+            HotShotEvent::HighQcRecv(high_qc, _) => {
+                write!(f, "HighQcRecv(view_number={:?}", high_qc.qc.view_number())
+            }
+            HotShotEvent::HighQcSend(high_qc, ..) => {
+                write!(f, "HighQcSend(view_number={:?}", high_qc.qc.view_number())
+            }
+            HotShotEvent::UnsignedHighQcRecv(qc, _) => {
+                write!(f, "UnsignedHighQcRecv(view_number={:?}", qc.view_number())
+            }
+            HotShotEvent::ProtocolViolation(evidence) => {
+                write!(
+                    f,
+                    "ProtocolViolation(view_number={:?}, violation={})",
+                    evidence.view_number,
+                    evidence.violation.kind()
+                )
+            }
+            HotShotEvent::CheckpointDecided(leaf, _) => write!(
+                f,
+                "CheckpointDecided(view_number={:?}, height={})",
+                leaf.view_number(),
+                leaf.height()
+            ),
+            HotShotEvent::CheckpointVoteSend(vote) => {
+                write!(
+                    f,
+                    "CheckpointVoteSend(view_number={:?})",
+                    vote.view_number()
+                )
+            }
+            HotShotEvent::CheckpointVoteRecv(vote) => {
+                write!(
+                    f,
+                    "CheckpointVoteRecv(view_number={:?})",
+                    vote.view_number()
+                )
+            }
+            HotShotEvent::LeavesDecided(leaves) => write!(
+                f,
+                "LeavesDecided(view_number={:?}, leaves={})",
+                leaves.first().map(|(leaf, _)| leaf.view_number()),
+                leaves.len()
+            ),
+            HotShotEvent::AttestationRequestSend(request) => write!(
+                f,
+                "AttestationRequestSend(view_number={:?}, nonce={})",
+                request.vote.view_number(),
+                request.vote.data.nonce
+            ),
+            HotShotEvent::AttestationRequestRecv(request) => write!(
+                f,
+                "AttestationRequestRecv(view_number={:?}, nonce={})",
+                request.vote.view_number(),
+                request.vote.data.nonce
+            ),
+            HotShotEvent::AttestationVoteSend(vote) => write!(
+                f,
+                "AttestationVoteSend(view_number={:?}, nonce={})",
+                vote.view_number(),
+                vote.data.nonce
+            ),
+            HotShotEvent::AttestationVoteRecv(vote) => write!(
+                f,
+                "AttestationVoteRecv(view_number={:?}, nonce={})",
+                vote.view_number(),
+                vote.data.nonce
+            ),
+            HotShotEvent::ValidatorMetadataSend(record) => write!(
+                f,
+                "ValidatorMetadataSend(key={}, sequence={})",
+                record.public_key, record.sequence
+            ),
+            HotShotEvent::ValidatorMetadataRecv(record) => write!(
+                f,
+                "ValidatorMetadataRecv(key={}, sequence={})",
+                record.public_key, record.sequence
+            ),
+            HotShotEvent::ConfigUpdated(update) => write!(f, "ConfigUpdated({update:?})"),
         }
     }
 }
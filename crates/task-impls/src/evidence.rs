@@ -0,0 +1,293 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_broadcast::{Receiver, Sender};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use committable::{Commitment, Committable};
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    data::{Leaf2, QuorumProposal2},
+    event::{Event, EventType},
+    evidence::{Evidence, EvidenceLog, SignedEvidence, Violation},
+    message::{Proposal, UpgradeLock},
+    simple_vote::{QuorumVote2, VersionedVoteData},
+    traits::{
+        block_contents::BlockHeader,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        signature_key::SignatureKey,
+        storage::Storage,
+    },
+    utils::epoch_from_block_number,
+    vote::{HasViewNumber, Vote},
+};
+use tracing::instrument;
+use utils::anytrace::*;
+
+use crate::{events::HotShotEvent, helpers::broadcast_event};
+
+/// The number of views behind and ahead of the current view for which we keep proposals and votes
+/// around.
+const EVIDENCE_RETENTION_VIEWS: u64 = 10;
+
+/// Tracks proposals and votes in order to detect protocol violations by other nodes, and
+/// reports them as signed evidence.
+pub struct EvidenceTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
+    /// Our public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// Our private key, used to sign evidence
+    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// Membership, used to validate proposal signatures
+    pub membership: Arc<RwLock<TYPES::Membership>>,
+
+    /// Lock for a decided upgrade, used to validate vote signatures
+    pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Storage in which evidence is persisted
+    pub storage: Arc<RwLock<I::Storage>>,
+
+    /// Output events to application
+    pub output_event_stream: async_broadcast::Sender<Event<TYPES>>,
+
+    /// Evidence collected so far, shared with the node's handle
+    pub evidence: EvidenceLog<TYPES>,
+
+    /// The first validly signed proposal we saw for each view
+    pub proposals: BTreeMap<TYPES::View, Proposal<TYPES, QuorumProposal2<TYPES>>>,
+
+    /// The first vote we saw from each node, for each view
+    pub votes: BTreeMap<TYPES::View, HashMap<TYPES::SignatureKey, QuorumVote2<TYPES>>>,
+
+    /// Commitments of the evidence we have already reported
+    pub reported: HashSet<Commitment<Evidence<TYPES>>>,
+
+    /// The current view
+    pub cur_view: TYPES::View,
+
+    /// Number of blocks in an epoch, zero means there are no epochs
+    pub epoch_height: u64,
+
+    /// The node's id
+    pub id: u64,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> EvidenceTaskState<TYPES, I, V> {
+    /// Whether proposals and votes for `view` are kept around.
+    fn is_retained(&self, view: TYPES::View) -> bool {
+        (self.cur_view.saturating_sub(EVIDENCE_RETENTION_VIEWS)
+            ..=self.cur_view.saturating_add(EVIDENCE_RETENTION_VIEWS))
+            .contains(&*view)
+    }
+
+    /// Check whether a vote carries a valid signature from its signing key.
+    async fn is_valid_vote(&self, vote: &QuorumVote2<TYPES>) -> bool {
+        match VersionedVoteData::new(vote.date().clone(), vote.view_number(), &self.upgrade_lock)
+            .await
+        {
+            Ok(data) => vote
+                .signing_key()
+                .validate(&vote.signature(), data.commit().as_ref()),
+            Err(_) => false,
+        }
+    }
+
+    /// Check a received proposal against the proposals we have already seen for its view.
+    async fn check_proposal(
+        &mut self,
+        proposal: &Proposal<TYPES, QuorumProposal2<TYPES>>,
+    ) -> Option<Evidence<TYPES>> {
+        let view_number = proposal.data.view_number();
+        if !self.is_retained(view_number) {
+            return None;
+        }
+
+        let membership_reader = self.membership.read().await;
+        if proposal
+            .validate_signature(&membership_reader, self.epoch_height)
+            .is_err()
+        {
+            return None;
+        }
+        let proposal_epoch = TYPES::Epoch::new(epoch_from_block_number(
+            proposal.data.block_header.block_number(),
+            self.epoch_height,
+        ));
+        let leader = membership_reader.leader(view_number, proposal_epoch).ok()?;
+        drop(membership_reader);
+
+        let Some(first) = self.proposals.get(&view_number) else {
+            self.proposals.insert(view_number, proposal.clone());
+            return None;
+        };
+
+        if Leaf2::from_quorum_proposal(&first.data).commit()
+            == Leaf2::from_quorum_proposal(&proposal.data).commit()
+        {
+            return None;
+        }
+
+        Some(Evidence {
+            offender: leader,
+            view_number,
+            violation: Violation::EquivocatingProposal {
+                first: first.clone(),
+                second: proposal.clone(),
+            },
+        })
+    }
+
+    /// Check a received vote against the votes we have already seen from the same node. Only
+    /// validly signed votes from nodes with stake in the vote's epoch are kept.
+    async fn check_vote(&mut self, vote: &QuorumVote2<TYPES>) -> Option<Evidence<TYPES>> {
+        let view_number = vote.view_number();
+        if !self.is_retained(view_number) {
+            return None;
+        }
+
+        let key = vote.signing_key();
+        if !self
+            .membership
+            .read()
+            .await
+            .has_stake(&key, vote.date().epoch)
+            || !self.is_valid_vote(vote).await
+        {
+            return None;
+        }
+
+        let first = self
+            .votes
+            .get(&view_number)
+            .and_then(|votes| votes.get(&key))
+            .cloned();
+
+        let Some(first) = first else {
+            self.votes
+                .entry(view_number)
+                .or_default()
+                .insert(key, vote.clone());
+            return None;
+        };
+
+        if first.data.leaf_commit == vote.data.leaf_commit {
+            return None;
+        }
+
+        Some(Evidence {
+            offender: key,
+            view_number,
+            violation: Violation::DoubleVote {
+                first,
+                second: vote.clone(),
+            },
+        })
+    }
+
+    /// Sign, persist and publish a piece of evidence, unless it has already been reported.
+    #[instrument(skip_all, fields(id = self.id, view = *evidence.view_number))]
+    async fn report(&mut self, evidence: Evidence<TYPES>) -> Result<()> {
+        if !self.reported.insert(evidence.commit()) {
+            return Ok(());
+        }
+
+        tracing::warn!(
+            "Observed {} by {} in view {:?}",
+            evidence.violation.kind(),
+            evidence.offender,
+            evidence.view_number
+        );
+
+        let view_number = evidence.view_number;
+        let signed_evidence =
+            SignedEvidence::sign(evidence, self.public_key.clone(), &self.private_key)?;
+
+        self.storage
+            .write()
+            .await
+            .append_evidence(&signed_evidence)
+            .await
+            .wrap()
+            .context(error!("Failed to persist evidence"))?;
+
+        self.evidence.write().await.push(signed_evidence.clone());
+
+        broadcast_event(
+            Event {
+                view_number,
+                event: EventType::ByzantineEvidence {
+                    evidence: Arc::new(signed_evidence),
+                },
+            },
+            &self.output_event_stream,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Drop the proposals and votes for views outside of the retained window around the current
+    /// view.
+    fn collect_garbage(&mut self) {
+        let oldest_view = TYPES::View::new(self.cur_view.saturating_sub(EVIDENCE_RETENTION_VIEWS));
+        self.proposals = self.proposals.split_off(&oldest_view);
+        self.votes = self.votes.split_off(&oldest_view);
+
+        if let Some(after_newest) = self.cur_view.checked_add(EVIDENCE_RETENTION_VIEWS + 1) {
+            let after_newest = TYPES::View::new(after_newest);
+            drop(self.proposals.split_off(&after_newest));
+            drop(self.votes.split_off(&after_newest));
+        }
+    }
+
+    /// Handles a consensus event received on the event stream
+    pub async fn handle(&mut self, event: Arc<HotShotEvent<TYPES>>) -> Result<()> {
+        let evidence = match event.as_ref() {
+            HotShotEvent::QuorumProposalRecv(proposal, _) => self.check_proposal(proposal).await,
+            HotShotEvent::QuorumVoteRecv(vote) => self.check_vote(vote).await,
+            HotShotEvent::ProtocolViolation(evidence) => Some(evidence.clone()),
+            HotShotEvent::ViewChange(view_number, _) => {
+                if *view_number > self.cur_view {
+                    self.cur_view = *view_number;
+                    self.collect_garbage();
+                }
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(evidence) = evidence {
+            self.report(evidence).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
+    for EvidenceTaskState<TYPES, I, V>
+{
+    type Event = HotShotEvent<TYPES>;
+
+    async fn handle_event(
+        &mut self,
+        event: Arc<Self::Event>,
+        _sender: &Sender<Arc<Self::Event>>,
+        _receiver: &Receiver<Arc<Self::Event>>,
+    ) -> Result<()> {
+        self.handle(event).await
+    }
+
+    fn cancel_subtasks(&mut self) {}
+}
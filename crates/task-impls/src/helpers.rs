@@ -28,7 +28,10 @@ use hotshot_types::{
         signature_key::SignatureKey,
         BlockPayload, ValidatedState,
     },
-    utils::{epoch_from_block_number, is_last_block_in_epoch, Terminator, View, ViewInner},
+    utils::{
+        epoch_from_block_number, is_last_block_in_epoch, LeafCommitment, Terminator, View,
+        ViewInner,
+    },
     vote::{Certificate, HasViewNumber},
 };
 use tokio::time::timeout;
@@ -38,19 +41,17 @@ use utils::anytrace::*;
 use crate::{events::HotShotEvent, quorum_proposal_recv::ValidationInfo, request::REQUEST_TIMEOUT};
 
 /// Trigger a request to the network for a proposal for a view and wait for the response or timeout.
-#[instrument(skip_all)]
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn fetch_proposal<TYPES: NodeType, V: Versions>(
+async fn request_proposal<TYPES: NodeType, V: Versions>(
     view_number: TYPES::View,
     event_sender: Sender<Arc<HotShotEvent<TYPES>>>,
     event_receiver: Receiver<Arc<HotShotEvent<TYPES>>>,
     membership: Arc<RwLock<TYPES::Membership>>,
-    consensus: OuterConsensus<TYPES>,
     sender_public_key: TYPES::SignatureKey,
     sender_private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
     upgrade_lock: &UpgradeLock<TYPES, V>,
     epoch_height: u64,
-) -> Result<(Leaf2<TYPES>, View<TYPES>)> {
+) -> Result<Proposal<TYPES, QuorumProposal2<TYPES>>> {
     // We need to be able to sign this request before submitting it to the network. Compute the
     // payload first.
     let signed_proposal_request = ProposalRequestPayload {
@@ -143,8 +144,48 @@ pub(crate) async fn fetch_proposal<TYPES: NodeType, V: Versions>(
     {
         bail!("Invalid justify_qc in proposal for view {}", *view_number);
     }
+
+    Ok(proposal)
+}
+
+/// Find the proposal of the leaf with commitment `leaf_commit`, for a view, in the proposal cache,
+/// or else request it from the network and wait for the response or timeout.
+#[instrument(skip_all)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_proposal<TYPES: NodeType, V: Versions>(
+    view_number: TYPES::View,
+    leaf_commit: LeafCommitment<TYPES>,
+    event_sender: Sender<Arc<HotShotEvent<TYPES>>>,
+    event_receiver: Receiver<Arc<HotShotEvent<TYPES>>>,
+    membership: Arc<RwLock<TYPES::Membership>>,
+    consensus: OuterConsensus<TYPES>,
+    sender_public_key: TYPES::SignatureKey,
+    sender_private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    upgrade_lock: &UpgradeLock<TYPES, V>,
+    epoch_height: u64,
+) -> Result<(Leaf2<TYPES>, View<TYPES>)> {
+    // A proposal in the cache was validated when it was first seen
+    let cached_proposal = consensus.read().await.cached_proposal(&leaf_commit);
+    let proposal = match cached_proposal {
+        Some(proposal) => proposal,
+        None => {
+            request_proposal(
+                view_number,
+                event_sender,
+                event_receiver,
+                membership,
+                sender_public_key,
+                sender_private_key,
+                upgrade_lock,
+                epoch_height,
+            )
+            .await?
+        }
+    };
+
     let mut consensus_writer = consensus.write().await;
     let leaf = Leaf2::from_quorum_proposal(&proposal.data);
+    consensus_writer.cache_proposal(leaf.commit(), proposal.clone());
     let state = Arc::new(
         <TYPES::ValidatedState as ValidatedState<TYPES>>::from_header(&proposal.data.block_header),
     );
@@ -259,12 +300,11 @@ pub async fn decide_from_proposal_2<TYPES: NodeType>(
         res.leaf_views.push(info.clone());
         // If the block payload is available for this leaf, include it in
         // the leaf chain that we send to the client.
-        if let Some(encoded_txns) = consensus_reader
-            .saved_payloads()
-            .get(&info.leaf.view_number())
+        if let Some(encoded_txns) =
+            consensus_reader.payload(info.leaf.view_number(), &info.leaf.payload_commitment())
         {
             let payload =
-                BlockPayload::from_bytes(encoded_txns, info.leaf.block_header().metadata());
+                BlockPayload::from_bytes(&encoded_txns, info.leaf.block_header().metadata());
 
             info.leaf.fill_block_payload_unchecked(payload);
         }
@@ -388,10 +428,10 @@ pub async fn decide_from_proposal<TYPES: NodeType>(
                 // If the block payload is available for this leaf, include it in
                 // the leaf chain that we send to the client.
                 if let Some(encoded_txns) =
-                    consensus_reader.saved_payloads().get(&leaf.view_number())
+                    consensus_reader.payload(leaf.view_number(), &leaf.payload_commitment())
                 {
                     let payload =
-                        BlockPayload::from_bytes(encoded_txns, leaf.block_header().metadata());
+                        BlockPayload::from_bytes(&encoded_txns, leaf.block_header().metadata());
 
                     leaf.fill_block_payload_unchecked(payload);
                 }
@@ -449,11 +489,13 @@ pub(crate) async fn parent_leaf_and_state<TYPES: NodeType, V: Versions>(
     let vsm_contains_parent_view = consensus_reader
         .validated_state_map()
         .contains_key(&parent_view_number);
+    let parent_leaf_commit = consensus_reader.high_qc().data().leaf_commit;
     drop(consensus_reader);
 
     if !vsm_contains_parent_view {
         let _ = fetch_proposal(
             parent_view_number,
+            parent_leaf_commit,
             event_sender.clone(),
             event_receiver.clone(),
             membership,
@@ -695,8 +737,11 @@ pub(crate) async fn validate_proposal_view_and_certs<
                 drop(membership_reader);
 
                 ensure!(
-                    timeout_cert
+                    validation_info
+                        .consensus
                         .is_valid_cert(
+                            &timeout_cert,
+                            timeout_cert_epoch,
                             membership_stake_table,
                             membership_success_threshold,
                             &validation_info.upgrade_lock
@@ -717,15 +762,19 @@ pub(crate) async fn validate_proposal_view_and_certs<
                 let view_sync_cert_epoch = view_sync_cert.data().epoch();
 
                 let membership_reader = validation_info.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(view_sync_cert_epoch);
+                let membership_stake_table =
+                    membership_reader.view_sync_stake_table(view_sync_cert_epoch);
                 let membership_success_threshold =
-                    membership_reader.success_threshold(view_sync_cert_epoch);
+                    membership_reader.view_sync_success_threshold(view_sync_cert_epoch);
                 drop(membership_reader);
 
                 // View sync certs must also be valid.
                 ensure!(
-                    view_sync_cert
+                    validation_info
+                        .consensus
                         .is_valid_cert(
+                            &view_sync_cert,
+                            view_sync_cert_epoch,
                             membership_stake_table,
                             membership_success_threshold,
                             &validation_info.upgrade_lock
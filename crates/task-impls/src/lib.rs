@@ -22,6 +22,15 @@ pub mod transactions;
 /// Defines the events passed between tasks
 pub mod events;
 
+/// The task which votes on and certifies checkpoints of the application state
+pub mod checkpoint;
+
+/// The task which signs attestations of application messages requested by other nodes
+pub mod attestation;
+
+/// The task which collects evidence of protocol violations
+pub mod evidence;
+
 /// The task which implements the network.
 pub mod network;
 
@@ -37,6 +46,9 @@ pub mod vid;
 /// Generic task for collecting votes
 pub mod vote_collection;
 
+/// Checks the signatures of votes as they arrive from the network
+pub mod vote_verification;
+
 /// Task for handling upgrades
 pub mod upgrade;
 
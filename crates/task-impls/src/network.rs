@@ -7,22 +7,28 @@
 use std::{
     collections::{BTreeMap, HashMap},
     hash::{DefaultHasher, Hash, Hasher},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
 };
 
 use async_broadcast::{Receiver, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
+use bytes::Bytes;
 use hotshot_task::task::TaskState;
 use hotshot_types::{
     consensus::OuterConsensus,
-    data::{VidDisperse, VidDisperseShare, VidDisperseShare2},
+    data::{DaChunk, VidDisperse, VidDisperseShare, VidDisperseShare2},
     event::{Event, EventType, HotShotAction},
     message::{
         convert_proposal, DaConsensusMessage, DataMessage, GeneralConsensusMessage, Message,
-        MessageKind, Proposal, SequencingMessage, UpgradeLock,
+        MessageKind, Proposal, SequencingMessage, UpgradeLock, WireEncoding, WireEncodings,
     },
     simple_vote::HasEpoch,
+    trace_context::attach_to_view,
     traits::{
         election::Membership,
         network::{
@@ -30,18 +36,21 @@ use hotshot_types::{
             ViewMessage,
         },
         node_implementation::{ConsensusTime, NodeType, Versions},
+        signature_key::SignatureKey,
         storage::Storage,
     },
     vote::{HasViewNumber, Vote},
+    vote_intent::{IntentCheck, VoteIntent},
 };
 use tokio::{spawn, task::JoinHandle};
-use tracing::instrument;
+use tracing::{instrument, Instrument};
 use utils::anytrace::*;
 use vbs::version::StaticVersionType;
 
 use crate::{
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::broadcast_event,
+    view_sync::broadcasts_votes,
 };
 
 /// the network message task state
@@ -58,6 +67,9 @@ pub struct NetworkMessageTaskState<TYPES: NodeType> {
 
     /// Transaction Cache to ignore previously seen transactions
     pub transactions_cache: lru::LruCache<u64, ()>,
+
+    /// The consensus instance of this node, messages of other instances are ignored
+    pub namespace: u64,
 }
 
 impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
@@ -66,6 +78,15 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
     pub async fn handle_message(&mut self, message: Message<TYPES>) {
         tracing::trace!("Received message from network:\n\n{message:?}");
 
+        if message.namespace != self.namespace {
+            tracing::trace!(
+                "Ignoring message of consensus instance {}, we are in {}",
+                message.namespace,
+                self.namespace
+            );
+            return;
+        }
+
         // Match the message kind and send the appropriate event to the internal event stream
         let sender = message.sender;
         match message.kind {
@@ -145,7 +166,21 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                             tracing::error!("Received upgrade vote!");
                             HotShotEvent::UpgradeVoteRecv(message)
                         }
-                        GeneralConsensusMessage::HighQc(qc) => HotShotEvent::HighQcRecv(qc, sender),
+                        GeneralConsensusMessage::HighQc(qc) => {
+                            HotShotEvent::UnsignedHighQcRecv(qc, sender)
+                        }
+                        GeneralConsensusMessage::HighQc2(high_qc) => {
+                            HotShotEvent::HighQcRecv(high_qc, sender)
+                        }
+                        GeneralConsensusMessage::CheckpointVote(vote) => {
+                            HotShotEvent::CheckpointVoteRecv(vote)
+                        }
+                        GeneralConsensusMessage::AttestationRequest(request) => {
+                            HotShotEvent::AttestationRequestRecv(request)
+                        }
+                        GeneralConsensusMessage::AttestationVote(vote) => {
+                            HotShotEvent::AttestationVoteRecv(vote)
+                        }
                     },
                     SequencingMessage::Da(da_message) => match da_message {
                         DaConsensusMessage::DaProposal(proposal) => {
@@ -170,6 +205,9 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                         DaConsensusMessage::DaCertificate2(cert) => {
                             HotShotEvent::DaCertificateRecv(cert)
                         }
+                        DaConsensusMessage::DaChunk(chunk) => {
+                            HotShotEvent::DaChunkRecv(chunk, sender)
+                        }
                     },
                 };
                 broadcast_event(Arc::new(event), &self.internal_event_stream).await;
@@ -189,6 +227,13 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                     )
                     .await;
                 }
+                DataMessage::DataResponse(ResponseMessage::LeafRange(leaves)) => {
+                    broadcast_event(
+                        Arc::new(HotShotEvent::LeafRangeResponseRecv(sender, leaves)),
+                        &self.internal_event_stream,
+                    )
+                    .await;
+                }
                 DataMessage::DataResponse(response) => {
                     if let ResponseMessage::Found(message) = response {
                         match message {
@@ -211,19 +256,50 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                                 )
                                 .await;
                             }
+                            SequencingMessage::Da(DaConsensusMessage::DaChunk(chunk)) => {
+                                broadcast_event(
+                                    Arc::new(HotShotEvent::DaChunkResponseRecv(sender, chunk)),
+                                    &self.internal_event_stream,
+                                )
+                                .await;
+                            }
                             _ => {}
                         }
                     }
                 }
-                DataMessage::RequestData(data) => {
-                    let req_data = data.clone();
-                    if let RequestKind::Vid(_view_number, _key) = req_data.request {
+                DataMessage::RequestData(data) => match data.request {
+                    RequestKind::Vid(_view_number, _key) => {
                         broadcast_event(
                             Arc::new(HotShotEvent::VidRequestRecv(data, sender)),
                             &self.internal_event_stream,
                         )
                         .await;
                     }
+                    RequestKind::DaChunk(_view_number) => {
+                        broadcast_event(
+                            Arc::new(HotShotEvent::DaChunkRequestRecv(data, sender)),
+                            &self.internal_event_stream,
+                        )
+                        .await;
+                    }
+                    RequestKind::LeafRange(..) => {
+                        broadcast_event(
+                            Arc::new(HotShotEvent::LeafRangeRequestRecv(data, sender)),
+                            &self.internal_event_stream,
+                        )
+                        .await;
+                    }
+                    RequestKind::DaProposal(_) | RequestKind::Proposal(_) => {}
+                },
+                DataMessage::ValidatorMetadata(record) => {
+                    if sender != record.public_key {
+                        return;
+                    }
+                    broadcast_event(
+                        Arc::new(HotShotEvent::ValidatorMetadataRecv(record)),
+                        &self.internal_event_stream,
+                    )
+                    .await;
                 }
             },
 
@@ -276,6 +352,50 @@ pub struct NetworkEventTaskState<
 
     /// map view number to transmit tasks
     pub transmit_tasks: BTreeMap<TYPES::View, Vec<JoinHandle<()>>>,
+
+    /// whether participation in consensus is paused, in which case votes and proposals are not
+    /// sent
+    pub paused: Arc<AtomicBool>,
+
+    /// The consensus instance of this node, which our messages are tagged with
+    pub namespace: u64,
+
+    /// The encodings peers want direct messages in
+    pub wire_encodings: WireEncodings<TYPES::SignatureKey>,
+
+    /// The private key every message we send is signed with
+    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// The number of views past the view it is sent in after which a message we send expires, if
+    /// ever
+    pub message_ttl: Option<u64>,
+}
+
+/// Whether sending `event` is participation in consensus, rather than serving or requesting data
+fn is_participation<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
+    matches!(
+        event,
+        HotShotEvent::QuorumProposalSend(..)
+            | HotShotEvent::QuorumVoteSend(_)
+            | HotShotEvent::ExtendedQuorumVoteSend(_)
+            | HotShotEvent::VidDisperseSend(..)
+            | HotShotEvent::DaProposalSend(..)
+            | HotShotEvent::DaChunksSend(..)
+            | HotShotEvent::DaVoteSend(_)
+            | HotShotEvent::DacSend(..)
+            | HotShotEvent::ViewSyncPreCommitVoteSend(_)
+            | HotShotEvent::ViewSyncCommitVoteSend(_)
+            | HotShotEvent::ViewSyncFinalizeVoteSend(_)
+            | HotShotEvent::ViewSyncPreCommitCertificateSend(..)
+            | HotShotEvent::ViewSyncCommitCertificateSend(..)
+            | HotShotEvent::ViewSyncFinalizeCertificateSend(..)
+            | HotShotEvent::TimeoutVoteSend(_)
+            | HotShotEvent::UpgradeProposalSend(..)
+            | HotShotEvent::UpgradeVoteSend(_)
+            | HotShotEvent::HighQcSend(..)
+            | HotShotEvent::CheckpointVoteSend(_)
+            | HotShotEvent::AttestationVoteSend(_)
+    )
 }
 
 #[async_trait]
@@ -314,6 +434,11 @@ impl<
     /// Returns the completion status.
     #[instrument(skip_all, fields(view = *self.view), name = "Network Task", level = "error")]
     pub async fn handle(&mut self, event: Arc<HotShotEvent<TYPES>>) {
+        if self.paused.load(Ordering::Relaxed) && is_participation(&event) {
+            tracing::debug!("Participation is paused, not sending {event}");
+            return;
+        }
+
         let mut maybe_action = None;
         if let Some((sender, message_kind, transmit)) =
             self.parse_event(event, &mut maybe_action).await
@@ -346,6 +471,8 @@ impl<
                     kind: MessageKind::<TYPES>::from_consensus_message(SequencingMessage::Da(
                         DaConsensusMessage::VidDisperseMsg2(proposal),
                     )),
+                    namespace: self.namespace,
+                    expires_after: self.expires_after(view),
                 }
             } else {
                 let vid_share_proposal = Proposal {
@@ -358,10 +485,17 @@ impl<
                     kind: MessageKind::<TYPES>::from_consensus_message(SequencingMessage::Da(
                         DaConsensusMessage::VidDisperseMsg(vid_share_proposal),
                     )),
+                    namespace: self.namespace,
+                    expires_after: self.expires_after(view),
                 }
             };
-            let serialized_message = match self.upgrade_lock.serialize(&message).await {
-                Ok(serialized) => serialized,
+            let encoding = self.wire_encodings.of(&recipient).await;
+            let serialized_message = match self
+                .upgrade_lock
+                .serialize_signed(&message, encoding, &self.private_key)
+                .await
+            {
+                Ok(serialized) => Bytes::from(serialized),
                 Err(e) => {
                     tracing::error!("Failed to serialize message: {}", e);
                     continue;
@@ -395,6 +529,64 @@ impl<
         None
     }
 
+    /// handle `DaChunksSend`, sending each DA committee member its chunk
+    async fn handle_da_chunks(
+        &self,
+        chunks: Vec<Proposal<TYPES, DaChunk<TYPES>>>,
+        sender: &<TYPES as NodeType>::SignatureKey,
+    ) -> Option<HotShotTaskCompleted> {
+        let view = chunks.first()?.data.view_number();
+        let mut messages = HashMap::new();
+
+        for chunk in chunks {
+            let recipient = chunk.data.recipient_key.clone();
+            let message = Message {
+                sender: sender.clone(),
+                kind: MessageKind::<TYPES>::from_consensus_message(SequencingMessage::Da(
+                    DaConsensusMessage::DaChunk(chunk),
+                )),
+                namespace: self.namespace,
+                expires_after: self.expires_after(view),
+            };
+            let encoding = self.wire_encodings.of(&recipient).await;
+            let serialized_message = match self
+                .upgrade_lock
+                .serialize_signed(&message, encoding, &self.private_key)
+                .await
+            {
+                Ok(serialized) => Bytes::from(serialized),
+                Err(e) => {
+                    tracing::error!("Failed to serialize message: {}", e);
+                    continue;
+                }
+            };
+
+            messages.insert(recipient, serialized_message);
+        }
+
+        let net = Arc::clone(&self.network);
+        let storage = Arc::clone(&self.storage);
+        let consensus = OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus));
+        spawn(async move {
+            if NetworkEventTaskState::<TYPES, V, NET, S>::maybe_record_action(
+                Some(HotShotAction::DaPropose),
+                storage,
+                consensus,
+                view,
+            )
+            .await
+            .is_err()
+            {
+                return;
+            }
+            if let Err(e) = net.vid_broadcast_message(messages).await {
+                tracing::warn!("Failed to send message from network task: {:?}", e);
+            }
+        });
+
+        None
+    }
+
     /// Record `HotShotAction` if available
     async fn maybe_record_action(
         maybe_action: Option<HotShotAction>,
@@ -412,8 +604,19 @@ impl<
             if matches!(action, HotShotAction::ViewSyncVote) {
                 action = HotShotAction::Vote;
             }
-            match storage.write().await.record_action(view, action).await {
-                Ok(()) => Ok(()),
+            let start = Instant::now();
+            let result = storage.write().await.record_action(view, action).await;
+            let metrics = Arc::clone(&consensus.read().await.metrics);
+            metrics
+                .storage_write_duration
+                .add_point(start.elapsed().as_secs_f64());
+            match result {
+                Ok(()) => {
+                    if matches!(action, HotShotAction::Vote | HotShotAction::DaVote) {
+                        metrics.number_of_votes_sent.add(1);
+                    }
+                    Ok(())
+                }
                 Err(e) => {
                     tracing::warn!("Not Sending {:?} because of storage error: {:?}", action, e);
                     Err(())
@@ -424,6 +627,58 @@ impl<
         }
     }
 
+    /// Write ahead that we are about to send the vote of `intent`, refusing to send a vote
+    /// conflicting with one we intended before, even before a restart
+    async fn write_vote_intent(
+        intent: VoteIntent<TYPES>,
+        storage: &Arc<RwLock<S>>,
+        consensus: &OuterConsensus<TYPES>,
+    ) -> std::result::Result<(), ()> {
+        if consensus.write().await.record_vote_intent(&intent) == IntentCheck::Conflict {
+            tracing::error!(
+                "Not sending a {:?} vote in view {:?}, it conflicts with the vote we intended before",
+                intent.kind,
+                intent.view
+            );
+            return Err(());
+        }
+        // Written again for repeated votes, in case the first write failed
+        if let Err(e) = storage.write().await.record_vote_intent(&intent).await {
+            tracing::warn!(
+                "Not sending a {:?} vote in view {:?} because of storage error: {:?}",
+                intent.kind,
+                intent.view,
+                e
+            );
+            return Err(());
+        }
+        Ok(())
+    }
+
+    /// Where to send a view sync vote for relay `relay` of round `round`: to the relay, or to
+    /// every node once the round has fallen back to broadcasting its votes
+    async fn view_sync_vote_recipient(
+        &self,
+        round: TYPES::View,
+        relay: u64,
+    ) -> Option<TransmitType<TYPES>> {
+        if broadcasts_votes(relay) {
+            return Some(TransmitType::Broadcast);
+        }
+        let view_number = round + relay;
+        match self.membership.read().await.leader(view_number, self.epoch) {
+            Ok(leader) => Some(TransmitType::Direct(leader)),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to calculate leader for view number {:?}. Error: {:?}",
+                    view_number,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     /// Cancel all tasks for previous views
     pub fn cancel_tasks(&mut self, view: TYPES::View) {
         let keep = self.transmit_tasks.split_off(&view);
@@ -583,6 +838,10 @@ impl<
 
                 Some((sender, message, TransmitType::DaCommitteeBroadcast))
             }
+            HotShotEvent::DaChunksSend(chunks, sender) => {
+                self.handle_da_chunks(chunks, &sender).await;
+                None
+            }
             HotShotEvent::DaVoteSend(vote) => {
                 *maybe_action = Some(HotShotAction::DaVote);
                 let view_number = vote.view_number();
@@ -633,18 +892,9 @@ impl<
                 Some((sender, message, TransmitType::Broadcast))
             }
             HotShotEvent::ViewSyncPreCommitVoteSend(vote) => {
-                let view_number = vote.view_number() + vote.date().relay;
-                let leader = match self.membership.read().await.leader(view_number, self.epoch) {
-                    Ok(l) => l,
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to calculate leader for view number {:?}. Error: {:?}",
-                            view_number,
-                            e
-                        );
-                        return None;
-                    }
-                };
+                let recipient = self
+                    .view_sync_vote_recipient(vote.view_number(), vote.date().relay)
+                    .await?;
                 let message = if self
                     .upgrade_lock
                     .version_infallible(vote.view_number())
@@ -660,22 +910,13 @@ impl<
                     ))
                 };
 
-                Some((vote.signing_key(), message, TransmitType::Direct(leader)))
+                Some((vote.signing_key(), message, recipient))
             }
             HotShotEvent::ViewSyncCommitVoteSend(vote) => {
                 *maybe_action = Some(HotShotAction::ViewSyncVote);
-                let view_number = vote.view_number() + vote.date().relay;
-                let leader = match self.membership.read().await.leader(view_number, self.epoch) {
-                    Ok(l) => l,
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to calculate leader for view number {:?}. Error: {:?}",
-                            view_number,
-                            e
-                        );
-                        return None;
-                    }
-                };
+                let recipient = self
+                    .view_sync_vote_recipient(vote.view_number(), vote.date().relay)
+                    .await?;
                 let message = if self
                     .upgrade_lock
                     .version_infallible(vote.view_number())
@@ -691,22 +932,13 @@ impl<
                     ))
                 };
 
-                Some((vote.signing_key(), message, TransmitType::Direct(leader)))
+                Some((vote.signing_key(), message, recipient))
             }
             HotShotEvent::ViewSyncFinalizeVoteSend(vote) => {
                 *maybe_action = Some(HotShotAction::ViewSyncVote);
-                let view_number = vote.view_number() + vote.date().relay;
-                let leader = match self.membership.read().await.leader(view_number, self.epoch) {
-                    Ok(l) => l,
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to calculate leader for view number {:?}. Error: {:?}",
-                            view_number,
-                            e
-                        );
-                        return None;
-                    }
-                };
+                let recipient = self
+                    .view_sync_vote_recipient(vote.view_number(), vote.date().relay)
+                    .await?;
                 let message = if self
                     .upgrade_lock
                     .version_infallible(vote.view_number())
@@ -722,7 +954,7 @@ impl<
                     ))
                 };
 
-                Some((vote.signing_key(), message, TransmitType::Direct(leader)))
+                Some((vote.signing_key(), message, recipient))
             }
             HotShotEvent::ViewSyncPreCommitCertificateSend(certificate, sender) => {
                 let view_number = certificate.view_number();
@@ -871,17 +1103,84 @@ impl<
                 };
                 Some((sender, message, TransmitType::Direct(to)))
             }
-            HotShotEvent::HighQcSend(quorum_cert, leader, sender) => Some((
+            HotShotEvent::DaChunkRequestSend(req, sender, to) => Some((
+                sender,
+                MessageKind::Data(DataMessage::RequestData(req)),
+                TransmitType::Direct(to),
+            )),
+            HotShotEvent::DaChunkResponseSend(sender, to, chunk) => Some((
+                sender,
+                MessageKind::Data(DataMessage::DataResponse(ResponseMessage::Found(
+                    SequencingMessage::Da(DaConsensusMessage::DaChunk(chunk)),
+                ))),
+                TransmitType::Direct(to),
+            )),
+            HotShotEvent::LeafRangeRequestSend(req, sender, to) => Some((
+                sender,
+                MessageKind::Data(DataMessage::RequestData(req)),
+                TransmitType::Direct(to),
+            )),
+            HotShotEvent::LeafRangeResponseSend(sender, to, leaves) => Some((
                 sender,
-                MessageKind::Consensus(SequencingMessage::General(
-                    GeneralConsensusMessage::HighQc(quorum_cert),
+                MessageKind::Data(DataMessage::DataResponse(ResponseMessage::LeafRange(
+                    leaves,
+                ))),
+                TransmitType::Direct(to),
+            )),
+            HotShotEvent::HighQcSend(high_qc, leader, sender) => {
+                let message = if self.upgrade_lock.version_infallible(high_qc.view).await
+                    >= V::Epochs::VERSION
+                {
+                    GeneralConsensusMessage::HighQc2(high_qc)
+                } else {
+                    GeneralConsensusMessage::HighQc(high_qc.qc)
+                };
+
+                Some((
+                    sender,
+                    MessageKind::Consensus(SequencingMessage::General(message)),
+                    TransmitType::Direct(leader),
+                ))
+            }
+            HotShotEvent::CheckpointVoteSend(vote) => Some((
+                vote.signing_key(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                    GeneralConsensusMessage::CheckpointVote(vote),
+                )),
+                TransmitType::Broadcast,
+            )),
+            HotShotEvent::AttestationRequestSend(request) => Some((
+                request.vote.signing_key(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                    GeneralConsensusMessage::AttestationRequest(request),
                 )),
-                TransmitType::Direct(leader),
+                TransmitType::Broadcast,
+            )),
+            HotShotEvent::AttestationVoteSend(vote) => {
+                let requester = vote.data.requester.clone();
+                Some((
+                    vote.signing_key(),
+                    MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                        GeneralConsensusMessage::AttestationVote(vote),
+                    )),
+                    TransmitType::Direct(requester),
+                ))
+            }
+            HotShotEvent::ValidatorMetadataSend(record) => Some((
+                record.public_key.clone(),
+                MessageKind::Data(DataMessage::ValidatorMetadata(record)),
+                TransmitType::Broadcast,
             )),
             _ => None,
         }
     }
 
+    /// The last view a message for `view` we send now is of use in, if it expires. Messages for
+    /// past views, such as those helping a peer catch up, expire relative to the current view.
+    fn expires_after(&self, view: TYPES::View) -> Option<TYPES::View> {
+        self.message_ttl.map(|ttl| self.view.max(view) + ttl)
+    }
+
     /// Creates a network message and spawns a task that transmits it on the wire.
     async fn spawn_transmit_task(
         &mut self,
@@ -897,11 +1196,28 @@ impl<
             ) => BroadcastDelay::View(*message_kind.view_number()),
             _ => BroadcastDelay::None,
         };
+        let expires_after = self.expires_after(message_kind.view_number());
         let message = Message {
             sender,
             kind: message_kind,
+            namespace: self.namespace,
+            expires_after,
         };
         let view_number = message.kind.view_number();
+        // Attestations and leaf ranges are for decided views and validator metadata for no view at
+        // all, and the transmit tasks of past views are cancelled by the next view change, so they
+        // are tracked under the current view
+        let task_view = match &message.kind {
+            MessageKind::Consensus(SequencingMessage::General(
+                GeneralConsensusMessage::AttestationRequest(_)
+                | GeneralConsensusMessage::AttestationVote(_),
+            ))
+            | MessageKind::Data(
+                DataMessage::ValidatorMetadata(_)
+                | DataMessage::DataResponse(ResponseMessage::LeafRange(_)),
+            ) => self.view.max(view_number),
+            _ => view_number,
+        };
         let committee_topic = Topic::Global;
         let da_committee = self
             .membership
@@ -912,7 +1228,19 @@ impl<
         let storage = Arc::clone(&self.storage);
         let consensus = OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus));
         let upgrade_lock = self.upgrade_lock.clone();
-        let handle = spawn(async move {
+        let wire_encodings = self.wire_encodings.clone();
+        let private_key = self.private_key.clone();
+        let task = async move {
+            if let Some(intent) = VoteIntent::of_message(&message.kind) {
+                if NetworkEventTaskState::<TYPES, V, NET, S>::write_vote_intent(
+                    intent, &storage, &consensus,
+                )
+                .await
+                .is_err()
+                {
+                    return;
+                }
+            }
             if NetworkEventTaskState::<TYPES, V, NET, S>::maybe_record_action(
                 maybe_action,
                 Arc::clone(&storage),
@@ -939,8 +1267,15 @@ impl<
                 }
             }
 
-            let serialized_message = match upgrade_lock.serialize(&message).await {
-                Ok(serialized) => serialized,
+            let encoding = match &transmit {
+                TransmitType::Direct(recipient) => wire_encodings.of(recipient).await,
+                _ => WireEncoding::Bincode,
+            };
+            let serialized_message = match upgrade_lock
+                .serialize_signed(&message, encoding, &private_key)
+                .await
+            {
+                Ok(serialized) => Bytes::from(serialized),
                 Err(e) => {
                     tracing::error!("Failed to serialize message: {}", e);
                     return;
@@ -971,9 +1306,14 @@ impl<
                 Ok(()) => {}
                 Err(e) => tracing::warn!("Failed to send message task: {:?}", e),
             }
-        });
+        };
+
+        // Transmit the message as part of the trace of its view
+        let span = tracing::info_span!("send_message", view = *view_number);
+        attach_to_view(&span, self.namespace, *view_number);
+        let handle = spawn(task.instrument(span));
         self.transmit_tasks
-            .entry(view_number)
+            .entry(task_view)
             .or_default()
             .push(handle);
     }
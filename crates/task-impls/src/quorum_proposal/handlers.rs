@@ -8,6 +8,7 @@
 //! initiate a proposal occurs.
 
 use std::{
+    collections::HashSet,
     marker::PhantomData,
     sync::Arc,
     time::{Duration, Instant},
@@ -32,11 +33,12 @@ use hotshot_types::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{ConsensusTime, NodeType},
-        signature_key::SignatureKey,
+        signature_key::{SignatureKey, StakeTableEntryType},
     },
     utils::epoch_from_block_number,
     vote::{Certificate, HasViewNumber},
 };
+use primitive_types::U256;
 use tracing::instrument;
 use utils::anytrace::*;
 use vbs::version::StaticVersionType;
@@ -126,37 +128,55 @@ pub struct ProposalDependencyHandle<TYPES: NodeType, V: Versions> {
 }
 
 impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
-    /// Return the next HighQc we get from the event stream
+    /// Return the next valid HighQc we get from the event stream, along with its sender if the
+    /// sender signed it for our view
+    ///
+    /// An unsigned HighQc, from a node running a version from before signed ones, still tells us
+    /// of a higher QC, but its sender does not count towards the quorum of responders.
     async fn wait_for_qc_event(
         &self,
         rx: &mut Receiver<Arc<HotShotEvent<TYPES>>>,
-    ) -> Option<QuorumCertificate2<TYPES>> {
+    ) -> Option<(QuorumCertificate2<TYPES>, Option<TYPES::SignatureKey>)> {
         while let Ok(event) = rx.recv_direct().await {
-            if let HotShotEvent::HighQcRecv(qc, _sender) = event.as_ref() {
-                let membership_reader = self.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(qc.data.epoch);
-                let membership_success_threshold =
-                    membership_reader.success_threshold(qc.data.epoch);
-                drop(membership_reader);
-
-                if qc
-                    .is_valid_cert(
-                        membership_stake_table,
-                        membership_success_threshold,
-                        &self.upgrade_lock,
-                    )
-                    .await
-                {
-                    return Some(qc.clone());
+            let (qc, responder) = match event.as_ref() {
+                HotShotEvent::HighQcRecv(high_qc, sender) => {
+                    // A HighQc counts towards the quorum of responders, so its sender must have
+                    // signed it for this view
+                    if high_qc.view != self.view_number || !high_qc.is_signed_by(sender) {
+                        tracing::warn!("Ignoring a HighQc not signed by {sender} for this view");
+                        continue;
+                    }
+                    (&high_qc.qc, Some(sender))
                 }
+                HotShotEvent::UnsignedHighQcRecv(qc, _) => (qc, None),
+                _ => continue,
+            };
+            let membership_reader = self.membership.read().await;
+            let membership_stake_table = membership_reader.stake_table(qc.data.epoch);
+            let membership_success_threshold = membership_reader.success_threshold(qc.data.epoch);
+            drop(membership_reader);
+
+            if qc
+                .is_valid_cert(
+                    membership_stake_table,
+                    membership_success_threshold,
+                    &self.upgrade_lock,
+                )
+                .await
+            {
+                return Some((qc.clone(), responder.cloned()));
             }
         }
         None
     }
-    /// Waits for the configured timeout for nodes to send HighQc messages to us.  We'll
-    /// then propose with the highest QC from among these proposals.
+    /// Waits for nodes to send HighQc messages to us, and propose with the highest QC from
+    /// among them.
+    ///
+    /// We stop waiting as soon as nodes with a quorum of stake have sent us their HighQc, since
+    /// the highest QC among a quorum is guaranteed to be at least the highest locked QC. The
+    /// configured timeout is only a fallback for when a quorum does not respond.
     async fn wait_for_highest_qc(&mut self) {
-        tracing::error!("waiting for QC");
+        tracing::debug!("waiting for QC");
         // If we haven't upgraded to Hotstuff 2 just return the high qc right away
         if self
             .upgrade_lock
@@ -168,6 +188,11 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
         }
         let wait_duration = Duration::from_millis(self.timeout / 2);
 
+        let epoch = self.consensus.read().await.cur_epoch();
+        let threshold = U256::from(self.membership.read().await.success_threshold(epoch).get());
+        let mut responders = HashSet::new();
+        let mut responded_stake = U256::zero();
+
         // TODO configure timeout
         while self.view_start_time.elapsed() < wait_duration {
             let Some(time_spent) = Instant::now().checked_duration_since(self.view_start_time)
@@ -188,12 +213,29 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
                 // we timeout out, don't wait any longer
                 return;
             };
-            let Some(qc) = maybe_qc else {
+            let Some((qc, responder)) = maybe_qc else {
                 continue;
             };
             if qc.view_number() > self.highest_qc.view_number() {
                 self.highest_qc = qc;
             }
+            let Some(sender) = responder else {
+                continue;
+            };
+
+            if !responders.insert(sender.clone()) {
+                continue;
+            }
+            if let Some(entry) = self.membership.read().await.stake(&sender, epoch) {
+                responded_stake += entry.stake();
+            }
+            if responded_stake >= threshold {
+                tracing::debug!(
+                    "Heard from a quorum after {:?}, proposing without waiting for the timeout",
+                    self.view_start_time.elapsed()
+                );
+                return;
+            }
         }
     }
     /// Gets the next epoch QC corresponding to this epoch QC, times out if it takes too long.
@@ -399,6 +441,14 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
         } else {
             None
         };
+        let drb_result = if version >= V::Epochs::VERSION {
+            self.consensus
+                .read()
+                .await
+                .drb_result_for_block(block_header.block_number())
+        } else {
+            INITIAL_DRB_RESULT
+        };
         let proposal = QuorumProposal2 {
             block_header,
             view_number: self.view_number,
@@ -407,7 +457,7 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
             upgrade_certificate,
             view_change_evidence: proposal_certificate,
             drb_seed: INITIAL_DRB_SEED_INPUT,
-            drb_result: INITIAL_DRB_RESULT,
+            drb_result,
         };
 
         let proposed_leaf = Leaf2::from_quorum_proposal(&proposal);
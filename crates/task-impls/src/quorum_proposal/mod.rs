@@ -17,7 +17,7 @@ use hotshot_task::{
 };
 use hotshot_types::{
     consensus::OuterConsensus,
-    message::UpgradeLock,
+    message::{SignedHighQc, UpgradeLock},
     simple_certificate::{QuorumCertificate2, UpgradeCertificate},
     traits::{
         election::Membership,
@@ -469,9 +469,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 let epoch_number = certificate.data.epoch;
 
                 let membership_reader = self.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(epoch_number);
+                let membership_stake_table = membership_reader.view_sync_stake_table(epoch_number);
                 let membership_success_threshold =
-                    membership_reader.success_threshold(epoch_number);
+                    membership_reader.view_sync_success_threshold(epoch_number);
                 drop(membership_reader);
 
                 ensure!(
@@ -537,6 +537,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 )
                 .await?;
             }
+            HotShotEvent::ConfigUpdated(update) => {
+                if let Some(timeout) = update.next_view_timeout {
+                    self.timeout = timeout;
+                }
+            }
             HotShotEvent::ViewChange(view, epoch) => {
                 if epoch > &self.cur_epoch {
                     self.cur_epoch = *epoch;
@@ -548,7 +553,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 let keep_view = TYPES::View::new(view.saturating_sub(1));
                 self.cancel_tasks(keep_view);
             }
-            HotShotEvent::HighQcSend(qc, ..) => {
+            HotShotEvent::HighQcSend(SignedHighQc { qc, .. }, ..) => {
                 ensure!(qc.view_number() > self.highest_qc.view_number());
                 let cert_epoch_number = qc.data.epoch;
 
@@ -14,6 +14,7 @@ use committable::Committable;
 use hotshot_types::{
     consensus::OuterConsensus,
     data::{Leaf2, QuorumProposal, QuorumProposal2},
+    evidence::{Evidence, Violation},
     message::Proposal,
     simple_certificate::QuorumCertificate,
     traits::{
@@ -24,7 +25,7 @@ use hotshot_types::{
         storage::Storage,
         ValidatedState,
     },
-    utils::{epoch_from_block_number, View, ViewInner},
+    utils::{epoch_from_block_number, LeafCommitment, View, ViewInner},
     vote::{Certificate, HasViewNumber},
 };
 use tokio::spawn;
@@ -82,7 +83,17 @@ async fn validate_proposal_liveness<TYPES: NodeType, I: NodeImplementation<TYPES
             .await
             .is_ok_and(|v| v >= V::Epochs::VERSION)
     {
-        consensus_writer.update_locked_view(proposal.data.justify_qc.clone().view_number())?;
+        let locked_view = proposal.data.justify_qc.view_number();
+        // The lock must survive a restart before we vote on top of it
+        validation_info
+            .storage
+            .write()
+            .await
+            .update_locked_view(locked_view)
+            .await
+            .wrap()
+            .context(error!("Failed to store the locked view, not voting"))?;
+        consensus_writer.update_locked_view(locked_view)?;
     }
 
     drop(consensus_writer);
@@ -98,6 +109,7 @@ async fn validate_proposal_liveness<TYPES: NodeType, I: NodeImplementation<TYPES
 #[allow(clippy::too_many_arguments)]
 fn spawn_fetch_proposal<TYPES: NodeType, V: Versions>(
     view: TYPES::View,
+    leaf_commit: LeafCommitment<TYPES>,
     event_sender: Sender<Arc<HotShotEvent<TYPES>>>,
     event_receiver: Receiver<Arc<HotShotEvent<TYPES>>>,
     membership: Arc<RwLock<TYPES::Membership>>,
@@ -112,6 +124,7 @@ fn spawn_fetch_proposal<TYPES: NodeType, V: Versions>(
 
         let _ = fetch_proposal(
             view,
+            leaf_commit,
             event_sender,
             event_receiver,
             membership,
@@ -165,10 +178,14 @@ pub(crate) async fn handle_quorum_proposal_recv<
     let membership_reader = validation_info.membership.read().await;
     let membership_stake_table = membership_reader.stake_table(justify_qc.data.epoch);
     let membership_success_threshold = membership_reader.success_threshold(justify_qc.data.epoch);
+    let knows_stake_table = membership_reader.has_stake_table(justify_qc.data.epoch);
     drop(membership_reader);
 
-    if !justify_qc
+    if !validation_info
+        .consensus
         .is_valid_cert(
+            &justify_qc,
+            justify_qc.data.epoch,
             membership_stake_table,
             membership_success_threshold,
             &validation_info.upgrade_lock,
@@ -177,6 +194,31 @@ pub(crate) async fn handle_quorum_proposal_recv<
     {
         let consensus_reader = validation_info.consensus.read().await;
         consensus_reader.metrics.invalid_qc.update(1);
+        drop(consensus_reader);
+
+        // The proposal signature was already validated, so the leader is accountable for the QC,
+        // unless we only failed to check it because we do not know the stake table it is for.
+        let leader = validation_info
+            .membership
+            .read()
+            .await
+            .leader(view_number, proposal_epoch)
+            .ok()
+            .filter(|_| knows_stake_table);
+        if let Some(leader) = leader {
+            broadcast_event(
+                Arc::new(HotShotEvent::ProtocolViolation(Evidence {
+                    offender: leader,
+                    view_number,
+                    violation: Violation::InvalidQuorumCertificate {
+                        proposal: proposal.clone(),
+                    },
+                })),
+                event_sender,
+            )
+            .await;
+        }
+
         bail!("Invalid justify_qc in proposal for view {}", *view_number);
     }
 
@@ -196,8 +238,11 @@ pub(crate) async fn handle_quorum_proposal_recv<
         drop(membership_reader);
 
         // Validate the next epoch justify qc as well
-        if !next_epoch_justify_qc
+        if !validation_info
+            .consensus
             .is_valid_cert(
+                next_epoch_justify_qc,
+                justify_qc.data.epoch + 1,
                 membership_next_stake_table,
                 membership_next_success_threshold,
                 &validation_info.upgrade_lock,
@@ -231,6 +276,7 @@ pub(crate) async fn handle_quorum_proposal_recv<
     if parent_leaf.is_none() {
         spawn_fetch_proposal(
             justify_qc.view_number(),
+            justify_qc.data.leaf_commit,
             event_sender.clone(),
             event_receiver.clone(),
             Arc::clone(&validation_info.membership),
@@ -169,6 +169,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                     Err(e) => debug!(?e, "Failed to validate the proposal"),
                 }
             }
+            HotShotEvent::ConfigUpdated(update) => {
+                if let Some(timeout) = update.next_view_timeout {
+                    self.timeout = timeout;
+                }
+            }
             HotShotEvent::ViewChange(view, epoch) => {
                 if *epoch > self.cur_epoch {
                     self.cur_epoch = *epoch;
@@ -13,6 +13,7 @@ use committable::Committable;
 use hotshot_types::{
     consensus::OuterConsensus,
     data::{Leaf2, QuorumProposal2, VidDisperseShare2},
+    drb::INITIAL_DRB_RESULT,
     event::{Event, EventType, LeafInfo},
     message::{Proposal, UpgradeLock},
     simple_vote::{QuorumData2, QuorumVote2},
@@ -56,24 +57,73 @@ async fn handle_quorum_proposal_validated_drb_calculation_start<
         TYPES::EPOCH_HEIGHT,
     ));
 
-    // Start the new task if we're in the committee for this epoch
-    if task_state
-        .membership
-        .read()
-        .await
-        .has_stake(&task_state.public_key, current_epoch_number)
+    // Every node computes the result, as every node checks the proposal which carries it
+    task_state
+        .drb_computations
+        .start_task_if_not_running(current_epoch_number + 1)
+        .await;
+
+    // Keep finished results where leaders and voters can find them. The election only learns a
+    // result once it is decided, see `add_decided_drb_results`.
+    if let Some(drb_result) = task_state
+        .drb_computations
+        .get_result(current_epoch_number + 1)
     {
         task_state
-            .drb_computations
-            .start_task_if_not_running(current_epoch_number + 1)
-            .await;
+            .consensus
+            .write()
+            .await
+            .insert_drb_result(current_epoch_number + 1, drb_result);
+    }
+}
+
+/// Check that `proposal` carries the DRB result we expect for its block, so that we never vote for
+/// a result we have not computed ourselves.
+///
+/// # Errors
+/// If the proposal carries a different result
+pub(crate) async fn check_drb_result<TYPES: NodeType>(
+    proposal: &QuorumProposal2<TYPES>,
+    consensus: &OuterConsensus<TYPES>,
+) -> Result<()> {
+    let expected = consensus
+        .read()
+        .await
+        .drb_result_for_block(proposal.block_header.block_number());
+    ensure!(
+        proposal.drb_result == expected,
+        warn!(
+            "Proposal for view {} carries a DRB result we did not compute, not voting",
+            *proposal.view_number
+        )
+    );
+
+    Ok(())
+}
+
+/// Hand the DRB results carried by newly decided leaves to the election.
+///
+/// Only decided results are used, so that every node samples with the same result no matter when
+/// its own computation finished.
+async fn add_decided_drb_results<TYPES: NodeType>(
+    leaf_views: &[LeafInfo<TYPES>],
+    membership: &RwLock<TYPES::Membership>,
+) {
+    for leaf_info in leaf_views {
+        let leaf = &leaf_info.leaf;
+        if leaf.drb_result != INITIAL_DRB_RESULT {
+            membership
+                .write()
+                .await
+                .add_drb_result(leaf.epoch() + 1, leaf.drb_result);
+        }
     }
 }
 
 /// Handles storing the seed for an upcoming DRB calculation.
 ///
 /// We store the DRB computation seed 2 epochs in advance, if the decided block is the last but
-/// third block in the current epoch and we are in the quorum committee of the next epoch.
+/// third block in the current epoch.
 ///
 /// Special cases:
 /// * Epoch 0: No DRB computation since we'll transition to epoch 1 immediately.
@@ -111,26 +161,18 @@ async fn handle_quorum_proposal_validated_drb_calculation_seed<
             .drb_computations
             .garbage_collect(current_epoch_number);
 
-        // Skip if we are not in the committee of the next epoch.
-        if task_state
-            .membership
-            .read()
-            .await
-            .has_stake(&task_state.public_key, current_epoch_number + 1)
-        {
-            let new_epoch_number = current_epoch_number + 2;
-            let Ok(drb_seed_input_vec) = bincode::serialize(&proposal.justify_qc.signatures) else {
-                bail!("Failed to serialize the QC signature.");
-            };
-            let Ok(drb_seed_input) = drb_seed_input_vec.try_into() else {
-                bail!("Failed to convert the serialized QC signature into a DRB seed input.");
-            };
-
-            // Store the drb seed input for the next calculation
-            task_state
-                .drb_computations
-                .store_seed(new_epoch_number, drb_seed_input);
-        }
+        let new_epoch_number = current_epoch_number + 2;
+        let Ok(drb_seed_input_vec) = bincode::serialize(&proposal.justify_qc.signatures) else {
+            bail!("Failed to serialize the QC signature.");
+        };
+        let Ok(drb_seed_input) = drb_seed_input_vec.try_into() else {
+            bail!("Failed to convert the serialized QC signature into a DRB seed input.");
+        };
+
+        // Store the drb seed input for the next calculation
+        task_state
+            .drb_computations
+            .store_seed(new_epoch_number, drb_seed_input);
     }
     Ok(())
 }
@@ -144,6 +186,7 @@ pub(crate) async fn handle_quorum_proposal_validated<
 >(
     proposal: &QuorumProposal2<TYPES>,
     task_state: &mut QuorumVoteTaskState<TYPES, I, V>,
+    event_sender: &Sender<Arc<HotShotEvent<TYPES>>>,
 ) -> Result<()> {
     let version = task_state
         .upgrade_lock
@@ -154,6 +197,20 @@ pub(crate) async fn handle_quorum_proposal_validated<
         handle_quorum_proposal_validated_drb_calculation_start(proposal, task_state).await;
     }
 
+    // Track which validators' votes made it into the QC the proposal extends
+    if let Some(signatures) = &proposal.justify_qc.signatures {
+        let stake_table = task_state
+            .membership
+            .read()
+            .await
+            .stake_table(proposal.justify_qc.data.epoch);
+        task_state
+            .consensus
+            .write()
+            .await
+            .record_certificate_participation(&stake_table, signatures);
+    }
+
     let LeafChainTraversalOutcome {
         new_locked_view_number,
         new_decided_view_number,
@@ -196,6 +253,18 @@ pub(crate) async fn handle_quorum_proposal_validated<
             .await;
     }
 
+    if let Some(locked_view_number) = new_locked_view_number {
+        // The lock must survive a restart before we vote on top of it
+        task_state
+            .storage
+            .write()
+            .await
+            .update_locked_view(locked_view_number)
+            .await
+            .wrap()
+            .context(error!("Failed to store the locked view, not voting"))?;
+    }
+
     let mut consensus_writer = task_state.consensus.write().await;
     if let Some(locked_view_number) = new_locked_view_number {
         consensus_writer.update_locked_view(locked_view_number)?;
@@ -226,6 +295,10 @@ pub(crate) async fn handle_quorum_proposal_validated<
             .metrics
             .number_of_views_per_decide_event
             .add_point(cur_number_of_views_per_decide_event as f64);
+        consensus_writer
+            .metrics
+            .number_of_views_decided
+            .add(leaf_views.len());
 
         tracing::debug!(
             "Sending Decide for view {:?}",
@@ -235,23 +308,60 @@ pub(crate) async fn handle_quorum_proposal_validated<
         // We don't need to hold this while we broadcast
         drop(consensus_writer);
 
-        // Send an update to everyone saying that we've reached a decide
-        broadcast_event(
-            Event {
-                view_number: decided_view_number,
-                event: EventType::Decide {
-                    leaf_chain: Arc::new(leaf_views.clone()),
-                    // This is never none if we've reached a new decide, so this is safe to unwrap.
-                    qc: Arc::new(new_decide_qc.unwrap()),
-                    block_size: included_txns.map(|txns| txns.len().try_into().unwrap()),
+        // Make what we stored durable before anyone hears of the decide. Write-behind storage
+        // only waits for its queued writes here. A decide which may not survive a restart is
+        // not announced.
+        let flushed = task_state.storage.read().await.flush().await;
+        if let Err(e) = &flushed {
+            tracing::error!(
+                "Not announcing the decide of view {decided_view_number:?}, storage failed to \
+                 flush: {e:?}"
+            );
+        } else {
+            // Send an update to everyone saying that we've reached a decide
+            broadcast_event(
+                Event {
+                    view_number: decided_view_number,
+                    event: EventType::Decide {
+                        leaf_chain: Arc::new(leaf_views.clone()),
+                        // This is never none if we've reached a new decide, so this is safe to
+                        // unwrap.
+                        qc: Arc::new(new_decide_qc.unwrap()),
+                        block_size: included_txns.map(|txns| txns.len().try_into().unwrap()),
+                    },
                 },
-            },
-            &task_state.output_event_stream,
-        )
-        .await;
-        tracing::debug!("Successfully sent decide event");
+                &task_state.output_event_stream,
+            )
+            .await;
+            tracing::debug!("Successfully sent decide event");
+
+            if task_state.attestation_window != 0 {
+                let leaves = leaf_views
+                    .iter()
+                    .map(|info| (info.leaf.clone(), Arc::clone(&info.state)))
+                    .collect();
+                broadcast_event(Arc::new(HotShotEvent::LeavesDecided(leaves)), event_sender).await;
+            }
+
+            if task_state.checkpoint_interval != 0 {
+                // Leaves are newest first, and we want to checkpoint in increasing height order
+                for leaf_info in leaf_views.iter().rev() {
+                    if leaf_info.leaf.height() % task_state.checkpoint_interval == 0 {
+                        broadcast_event(
+                            Arc::new(HotShotEvent::CheckpointDecided(
+                                leaf_info.leaf.clone(),
+                                Arc::clone(&leaf_info.state),
+                            )),
+                            event_sender,
+                        )
+                        .await;
+                    }
+                }
+            }
+        }
 
         if version >= V::Epochs::VERSION {
+            add_decided_drb_results(&leaf_views, &task_state.membership).await;
             handle_quorum_proposal_validated_drb_calculation_seed(
                 proposal,
                 task_state,
@@ -259,6 +369,10 @@ pub(crate) async fn handle_quorum_proposal_validated<
             )
             .await?;
         }
+
+        flushed
+            .wrap()
+            .context(error!("Failed to flush storage before a decide"))?;
     }
 
     Ok(())
@@ -312,6 +426,7 @@ pub(crate) async fn update_shared_state<
         None => {
             match fetch_proposal(
                 justify_qc.view_number(),
+                justify_qc.data.leaf_commit,
                 sender.clone(),
                 receiver.activate_cloned(),
                 Arc::clone(&membership),
@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
 
 use async_broadcast::{InactiveReceiver, Receiver, Sender};
 use async_lock::RwLock;
@@ -19,12 +19,13 @@ use hotshot_task::{
 use hotshot_types::{
     consensus::{ConsensusMetricsValue, OuterConsensus},
     data::{Leaf2, QuorumProposal2},
-    event::Event,
+    event::{Event, EventType},
     message::{Proposal, UpgradeLock},
     traits::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        proposal_validator::ProposalValidator,
         signature_key::SignatureKey,
         storage::Storage,
     },
@@ -41,7 +42,9 @@ use vbs::version::StaticVersionType;
 use crate::{
     events::HotShotEvent,
     helpers::broadcast_event,
-    quorum_vote::handlers::{handle_quorum_proposal_validated, submit_vote, update_shared_state},
+    quorum_vote::handlers::{
+        check_drb_result, handle_quorum_proposal_validated, submit_vote, update_shared_state,
+    },
 };
 
 /// Helper for DRB Computations
@@ -152,9 +155,41 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions> Handl
                         tracing::warn!("Proposed leaf parent commitment does not match parent leaf payload commitment. Aborting vote.");
                         return;
                     }
+                    if version >= V::Epochs::VERSION {
+                        if let Err(e) = check_drb_result(&proposal.data, &self.consensus).await {
+                            tracing::warn!("{e:#}");
+                            return;
+                        }
+                    }
+                    // Give the application a chance to reject the proposal before we vote for it
+                    if let Err(reason) = I::ProposalValidator::validate_proposal(
+                        &self.instance_state,
+                        &proposal.data,
+                        parent_leaf,
+                    )
+                    .await
+                    {
+                        tracing::warn!(
+                            "Proposal rejected by the application, not voting: {reason}"
+                        );
+                        broadcast_event(
+                            Arc::new(HotShotEvent::QuorumProposalRejected(
+                                proposal.clone(),
+                                reason,
+                            )),
+                            &self.sender,
+                        )
+                        .await;
+                        return;
+                    }
                     // Update our persistent storage of the proposal. If we cannot store the proposal return
                     // and error so we don't vote
-                    if let Err(e) = self.storage.write().await.append_proposal2(proposal).await {
+                    let start = Instant::now();
+                    let result = self.storage.write().await.append_proposal2(proposal).await;
+                    self.consensus_metrics
+                        .storage_write_duration
+                        .add_point(start.elapsed().as_secs_f64());
+                    if let Err(e) = result {
                         tracing::error!("failed to store proposal, not voting.  error = {e:#}");
                         return;
                     }
@@ -311,6 +346,12 @@ pub struct QuorumVoteTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
 
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+
+    /// Number of blocks between checkpoints, zero means there are no checkpoints
+    pub checkpoint_interval: u64,
+
+    /// Number of the latest decided leaves we attest to messages for, zero means none
+    pub attestation_window: u64,
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskState<TYPES, I, V> {
@@ -469,12 +510,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                     *proposal.data.view_number()
                 );
 
-                // Handle the event before creating the dependency task.
-                if let Err(e) = handle_quorum_proposal_validated(&proposal.data, self).await {
-                    tracing::debug!(
-                        "Failed to handle QuorumProposalValidated event; error = {e:#}"
-                    );
-                }
+                // Handle the event before creating the dependency task. We do not vote for a
+                // proposal we failed to handle, the lock it moves us to may not be stored.
+                handle_quorum_proposal_validated(&proposal.data, self, &event_sender).await?;
 
                 ensure!(
                     proposal.data.view_number() > self.latest_voted_view,
@@ -629,6 +667,19 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                 }
                 self.vote_dependencies = current_tasks;
             }
+            HotShotEvent::QuorumProposalRejected(proposal, reason) => {
+                broadcast_event(
+                    Event {
+                        view_number: proposal.data.view_number(),
+                        event: EventType::QuorumProposalRejected {
+                            proposal: proposal.clone(),
+                            reason: reason.clone(),
+                        },
+                    },
+                    &self.output_event_stream,
+                )
+                .await;
+            }
             _ => {}
         }
         Ok(())
@@ -682,9 +733,18 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
             tracing::warn!("Proposed leaf parent commitment does not match parent leaf payload commitment. Aborting vote.");
             return;
         }
+        if let Err(e) = check_drb_result(&proposal.data, &self.consensus).await {
+            tracing::warn!("{e:#}");
+            return;
+        }
         // Update our persistent storage of the proposal. If we cannot store the proposal return
         // and error so we don't vote
-        if let Err(e) = self.storage.write().await.append_proposal2(proposal).await {
+        let start = Instant::now();
+        let result = self.storage.write().await.append_proposal2(proposal).await;
+        self.consensus_metrics
+            .storage_write_duration
+            .add_point(start.elapsed().as_secs_f64());
+        if let Err(e) = result {
             tracing::error!("failed to store proposal, not voting.  error = {e:#}");
             return;
         }
@@ -16,6 +16,7 @@ use std::{
 use async_broadcast::{Receiver, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
+use committable::Committable;
 use hotshot_task::{
     dependency::{Dependency, EventDependency},
     task::TaskState,
@@ -33,7 +34,6 @@ use hotshot_types::{
     vote::HasViewNumber,
 };
 use rand::{seq::SliceRandom, thread_rng};
-use sha2::{Digest, Sha256};
 use tokio::{
     spawn,
     task::JoinHandle,
@@ -166,7 +166,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
         let request = RequestKind::Vid(view, self.public_key.clone());
 
         // First sign the request for the VID shares.
-        if let Some(signature) = self.serialize_and_sign(&request) {
+        if let Some(signature) = self.sign_request(&request) {
             self.create_vid_request_task(
                 request,
                 signature,
@@ -383,13 +383,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
         cancel
     }
 
-    /// Sign the serialized version of the request
-    fn serialize_and_sign(&self, request: &RequestKind<TYPES>) -> Option<Signature<TYPES>> {
-        let Ok(data) = bincode::serialize(&request) else {
-            tracing::error!("Failed to serialize request!");
-            return None;
-        };
-        let Ok(signature) = TYPES::SignatureKey::sign(&self.private_key, &Sha256::digest(data))
+    /// Sign the commitment of the request
+    fn sign_request(&self, request: &RequestKind<TYPES>) -> Option<Signature<TYPES>> {
+        let Ok(signature) = TYPES::SignatureKey::sign(&self.private_key, request.commit().as_ref())
         else {
             tracing::error!("Failed to sign Data Request");
             return None;
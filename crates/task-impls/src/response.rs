@@ -98,6 +98,34 @@ impl<TYPES: NodeType> NetworkResponseState<TYPES> {
                                 .await;
                             }
                         }
+                        HotShotEvent::DaChunkRequestRecv(request, sender) => {
+                            let cur_epoch = self.consensus.read().await.cur_epoch();
+                            // Verify request is valid
+                            if !self.valid_sender(sender, cur_epoch).await
+                                || !valid_signature::<TYPES>(request, sender)
+                            {
+                                continue;
+                            }
+                            let chunk = self
+                                .consensus
+                                .read()
+                                .await
+                                .da_chunks()
+                                .get(&request.view)
+                                .cloned();
+                            if let Some(chunk) = chunk {
+                                broadcast_event(
+                                    HotShotEvent::DaChunkResponseSend(
+                                        self.pub_key.clone(),
+                                        sender.clone(),
+                                        chunk,
+                                    )
+                                    .into(),
+                                    &event_sender,
+                                )
+                                .await;
+                            }
+                        }
                         HotShotEvent::QuorumProposalRequestRecv(req, signature) => {
                             // Make sure that this request came from who we think it did
                             if !req.key.validate(signature, req.commit().as_ref()) {
@@ -190,11 +218,17 @@ impl<TYPES: NodeType> NetworkResponseState<TYPES> {
     }
 }
 
-/// Check the signature
+/// Check the signature over the commitment of the request
+///
+/// Nodes which predate signing the commitment signed the SHA-256 hash of the bincode encoding of
+/// the request, which we still accept so that they can be upgraded one by one.
 fn valid_signature<TYPES: NodeType>(
     req: &DataRequest<TYPES>,
     sender: &TYPES::SignatureKey,
 ) -> bool {
+    if sender.validate(&req.signature, req.request.commit().as_ref()) {
+        return true;
+    }
     let Ok(data) = bincode::serialize(&req.request) else {
         return false;
     };
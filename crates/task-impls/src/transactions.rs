@@ -22,6 +22,7 @@ use hotshot_types::{
     message::UpgradeLock,
     traits::{
         auction_results_provider::AuctionResultsProvider,
+        block_builder_strategy::{BlockBuilderStrategy, BlockOffer},
         block_contents::{precompute_vid_commitment, BuilderFee, EncodeBytes},
         election::Membership,
         node_implementation::{ConsensusTime, HasUrls, NodeImplementation, NodeType, Versions},
@@ -475,6 +476,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
                 )
                 .await;
             }
+            HotShotEvent::ConfigUpdated(update) => {
+                if let Some(timeout) = update.builder_timeout {
+                    self.builder_timeout = timeout;
+                }
+            }
             HotShotEvent::ViewChange(view, epoch) => {
                 let view = TYPES::View::new(std::cmp::max(1, **view));
 
@@ -508,13 +514,24 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
         block_view: TYPES::View,
         task_start_time: Instant,
     ) -> Result<(TYPES::View, VidCommitment)> {
+        let state_map_changed = self.consensus.read().await.state_map_changed();
         loop {
+            // Register for the next state map update before checking, so we can't miss it
+            let notified = state_map_changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
             match self.last_vid_commitment(block_view).await {
                 Ok((view, comm)) => break Ok((view, comm)),
                 Err(e) if task_start_time.elapsed() >= self.builder_timeout => break Err(e),
                 _ => {
-                    // We still have time, will re-try in a bit
-                    sleep(RETRY_DELAY).await;
+                    // We still have time, re-try as soon as consensus learns about a new view
+                    let _ = timeout(
+                        self.builder_timeout
+                            .saturating_sub(task_start_time.elapsed()),
+                        notified,
+                    )
+                    .await;
                     continue;
                 }
             }
@@ -653,23 +670,33 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
         let query_start = Instant::now();
         let threshold = (self.builder_clients.len() * BUILDER_MAIN_BATCH_THRESHOLD_DIVIDEND)
             .div_ceil(BUILDER_MAIN_BATCH_THRESHOLD_DIVISOR);
-        let mut tasks = tasks.take(threshold);
-        while let Some(result) = tasks.next().await {
-            results.push(result);
-            if query_start.elapsed() > BUILDER_MAIN_BATCH_CUTOFF {
-                break;
+        let collect = async {
+            let mut tasks = tasks.take(threshold);
+            while let Some(result) = tasks.next().await {
+                results.push(result);
+                if query_start.elapsed() > BUILDER_MAIN_BATCH_CUTOFF {
+                    break;
+                }
             }
-        }
-        let timeout = sleep(std::cmp::max(
-            query_start
-                .elapsed()
-                .mul_f32(BUILDER_ADDITIONAL_TIME_MULTIPLIER),
-            BUILDER_MINIMUM_QUERY_TIME.saturating_sub(query_start.elapsed()),
-        ));
-        futures::pin_mut!(timeout);
-        let mut tasks = tasks.into_inner().take_until(timeout);
-        while let Some(result) = tasks.next().await {
-            results.push(result);
+            let timeout = sleep(std::cmp::max(
+                query_start
+                    .elapsed()
+                    .mul_f32(BUILDER_ADDITIONAL_TIME_MULTIPLIER),
+                BUILDER_MINIMUM_QUERY_TIME.saturating_sub(query_start.elapsed()),
+            ));
+            futures::pin_mut!(timeout);
+            let mut tasks = tasks.into_inner().take_until(timeout);
+            while let Some(result) = tasks.next().await {
+                results.push(result);
+            }
+        };
+        match I::BlockBuilderStrategy::offer_deadline() {
+            Some(deadline) => {
+                if timeout(deadline, collect).await.is_err() {
+                    tracing::info!("Stopped waiting for available blocks at the offer deadline");
+                }
+            }
+            None => collect.await,
         }
         results
             .into_iter()
@@ -685,8 +712,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
     }
 
     /// Get a block from builder.
-    /// Queries the sufficiently fast builders for available blocks and chooses the one with the
-    /// best fee/byte ratio, re-trying with the next best one in case of failure.
+    /// Queries the sufficiently fast builders for available blocks and claims the one preferred by
+    /// the block builder strategy, re-trying with the next one in case of failure.
     ///
     /// # Errors
     /// If none of the builder reports any available blocks or claiming block fails for all of the
@@ -698,23 +725,21 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
         view_number: TYPES::View,
         parent_comm_sig: &<<TYPES as NodeType>::SignatureKey as SignatureKey>::PureAssembledSignatureType,
     ) -> Result<BuilderResponse<TYPES>> {
-        let mut available_blocks = self
+        let available_blocks = self
             .get_available_blocks(parent_comm, view_number, parent_comm_sig)
             .await;
 
-        available_blocks.sort_by(|(l, _), (r, _)| {
-            // We want the block with the highest fee per byte of data we're going to have to
-            // process, thus our comparison function is:
-            //      (l.offered_fee / l.block_size) < (r.offered_fee / r.block_size)
-            // To avoid floating point math (which doesn't even have an `Ord` impl) we multiply
-            // through by the denominators to get
-            //      l.offered_fee * r.block_size < r.offered_fee * l.block_size
-            // We cast up to u128 to avoid overflow.
-            (u128::from(l.offered_fee) * u128::from(r.block_size))
-                .cmp(&(u128::from(r.offered_fee) * u128::from(l.block_size)))
-        });
-
-        if available_blocks.is_empty() {
+        let offers: Vec<_> = available_blocks
+            .iter()
+            .map(|(block_info, _)| BlockOffer {
+                builder: block_info.sender.clone(),
+                block_size: block_info.block_size,
+                offered_fee: block_info.offered_fee,
+            })
+            .collect();
+        let ranking = I::BlockBuilderStrategy::rank_offers(view_number, &offers);
+
+        if ranking.is_empty() {
             bail!("No available blocks");
         }
 
@@ -725,7 +750,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
             }
         };
 
-        for (block_info, builder_idx) in available_blocks {
+        // Each offer is claimed at most once, even if the strategy ranks it twice
+        let mut available_blocks: Vec<_> = available_blocks.into_iter().map(Some).collect();
+        for (block_info, builder_idx) in ranking
+            .into_iter()
+            .filter_map(|idx| available_blocks.get_mut(idx).and_then(Option::take))
+        {
             // Verify signature over chosen block.
             if !block_info.sender.validate_block_info_signature(
                 &block_info.signature,
@@ -27,7 +27,7 @@ use hotshot_types::{
         signature_key::SignatureKey,
     },
     utils::EpochTransitionIndicator,
-    vote::HasViewNumber,
+    vote::{HasViewNumber, VerifiedVotes},
 };
 use tracing::instrument;
 use utils::anytrace::*;
@@ -91,6 +91,9 @@ pub struct UpgradeTaskState<TYPES: NodeType, V: Versions> {
 
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
 }
 
 impl<TYPES: NodeType, V: Versions> UpgradeTaskState<TYPES, V> {
@@ -245,6 +248,7 @@ impl<TYPES: NodeType, V: Versions> UpgradeTaskState<TYPES, V> {
                     &event,
                     &tx,
                     &self.upgrade_lock,
+                    &self.verified_votes,
                     EpochTransitionIndicator::NotInTransition,
                 )
                 .await?;
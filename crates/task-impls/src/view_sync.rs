@@ -5,7 +5,7 @@
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     sync::Arc,
     time::Duration,
@@ -16,6 +16,11 @@ use async_lock::RwLock;
 use async_trait::async_trait;
 use hotshot_task::task::TaskState;
 use hotshot_types::{
+    consensus::OuterConsensus,
+    constants::{
+        VIEW_SYNC_BROADCAST_AFTER_RELAYS, VIEW_SYNC_BROADCAST_RELAY_SLACK,
+        VIEW_SYNC_MAX_BROADCAST_RELAYS, VIEW_SYNC_MAX_TIMEOUT_BACKOFF,
+    },
     message::UpgradeLock,
     simple_certificate::{
         ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
@@ -30,7 +35,7 @@ use hotshot_types::{
         signature_key::SignatureKey,
     },
     utils::EpochTransitionIndicator,
-    vote::{Certificate, HasViewNumber, Vote},
+    vote::{Certificate, HasViewNumber, VerifiedVotes, Vote},
 };
 use tokio::{spawn, task::JoinHandle, time::sleep};
 use tracing::instrument;
@@ -56,12 +61,25 @@ pub enum ViewSyncPhase {
     Finalize,
 }
 
+/// Whether votes for relay `relay` are broadcast to every node rather than sent to the relay.
+///
+/// Once `VIEW_SYNC_BROADCAST_AFTER_RELAYS` relays have failed in a row, every node collects the
+/// votes of the round and may form and broadcast its certificates. The relay index is part of the
+/// signed vote data, so all nodes agree on which votes are broadcast.
+#[must_use]
+pub fn broadcasts_votes(relay: u64) -> bool {
+    relay >= VIEW_SYNC_BROADCAST_AFTER_RELAYS
+}
+
 /// Type alias for a map from View Number to Relay to Vote Task
 type RelayMap<TYPES, VOTE, CERT, V> = HashMap<
     <TYPES as NodeType>::View,
     BTreeMap<u64, VoteCollectionTaskState<TYPES, VOTE, CERT, V>>,
 >;
 
+/// Type alias for the set of (round, phase) pairs for which we have already gossiped a certificate
+type GossipedCertificates<TYPES> = Arc<RwLock<HashSet<(<TYPES as NodeType>::View, ViewSyncPhase)>>>;
+
 /// Main view sync task state
 pub struct ViewSyncTaskState<TYPES: NodeType, V: Versions> {
     /// View HotShot is currently in
@@ -76,6 +94,9 @@ pub struct ViewSyncTaskState<TYPES: NodeType, V: Versions> {
     /// Membership for the quorum
     pub membership: Arc<RwLock<TYPES::Membership>>,
 
+    /// Reference to consensus, which tracks the liveness of relays
+    pub consensus: OuterConsensus<TYPES>,
+
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
@@ -111,8 +132,14 @@ pub struct ViewSyncTaskState<TYPES: NodeType, V: Versions> {
     /// Last view we garbage collected old tasks
     pub last_garbage_collected_view: TYPES::View,
 
+    /// Certificates we have already re-broadcast, shared with the replica tasks
+    pub gossiped_certificates: GossipedCertificates<TYPES>,
+
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// Votes whose signatures were checked as they arrived from the network
+    pub verified_votes: VerifiedVotes<TYPES>,
 }
 
 #[async_trait]
@@ -163,12 +190,18 @@ pub struct ViewSyncReplicaTaskState<TYPES: NodeType, V: Versions> {
     /// Membership for the quorum
     pub membership: Arc<RwLock<TYPES::Membership>>,
 
+    /// Reference to consensus, which tracks the liveness of relays
+    pub consensus: OuterConsensus<TYPES>,
+
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
     /// Our Private Key
     pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
 
+    /// Certificates we have already re-broadcast, shared with the main task
+    pub gossiped_certificates: GossipedCertificates<TYPES>,
+
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
 }
@@ -192,6 +225,88 @@ impl<TYPES: NodeType, V: Versions> TaskState for ViewSyncReplicaTaskState<TYPES,
 }
 
 impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
+    /// Check that we should collect the votes for relay `relay` of round `round`, either because
+    /// we are the relay or because its votes are broadcast.
+    ///
+    /// For broadcast relays, we stop collecting once we have relayed a certificate for the round
+    /// and phase, and only collect for relays at most `VIEW_SYNC_BROADCAST_RELAY_SLACK` past the
+    /// one our replica task is on. We keep collectors for at most `VIEW_SYNC_MAX_BROADCAST_RELAYS`
+    /// relays, dropping the lowest relay to make room for a higher one, but never the collector
+    /// for our own relay.
+    async fn check_relay_collector<T>(
+        &self,
+        relay_map: &mut BTreeMap<u64, T>,
+        round: TYPES::View,
+        relay: u64,
+        phase: ViewSyncPhase,
+    ) -> Result<()> {
+        if !broadcasts_votes(relay) {
+            ensure!(
+                self.membership
+                    .read()
+                    .await
+                    .leader(round + relay, self.cur_epoch)?
+                    == self.public_key,
+                debug!("View sync vote sent to wrong leader")
+            );
+            return Ok(());
+        }
+
+        ensure!(
+            !self
+                .gossiped_certificates
+                .read()
+                .await
+                .contains(&(round, phase)),
+            debug!(
+                "Already relayed a view sync certificate for round {}",
+                *round
+            )
+        );
+
+        let own_relay = self
+            .replica_task_map
+            .read()
+            .await
+            .get(&round)
+            .map_or(0, |replica_task| replica_task.relay);
+        ensure!(
+            relay <= own_relay.saturating_add(VIEW_SYNC_BROADCAST_RELAY_SLACK),
+            debug!(
+                "Not collecting votes for broadcast relay {relay} of round {}, we are on relay {own_relay}",
+                *round
+            )
+        );
+
+        let broadcast_relays = relay_map.range(VIEW_SYNC_BROADCAST_AFTER_RELAYS..).count();
+        if broadcast_relays >= VIEW_SYNC_MAX_BROADCAST_RELAYS {
+            let lowest = relay_map
+                .range(VIEW_SYNC_BROADCAST_AFTER_RELAYS..)
+                .map(|(relay, _)| *relay)
+                .find(|lowest| *lowest != own_relay);
+            let lowest = lowest.filter(|lowest| relay == own_relay || relay > *lowest);
+            let Some(lowest) = lowest else {
+                bail!(debug!(
+                    "Not collecting votes for broadcast relay {relay} of round {}",
+                    *round
+                ));
+            };
+            relay_map.remove(&lowest);
+        }
+        Ok(())
+    }
+
+    /// Note that we formed the certificate for relay `relay` of round `round`. Every node may form
+    /// the certificate of a broadcast relay, so our replica task must not re-broadcast it.
+    async fn certificate_formed(&self, round: TYPES::View, relay: u64, phase: ViewSyncPhase) {
+        if broadcasts_votes(relay) {
+            self.gossiped_certificates
+                .write()
+                .await
+                .insert((round, phase));
+        }
+    }
+
     #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "View Sync Main Task", level = "error")]
     #[allow(clippy::type_complexity)]
     /// Handles incoming events for the main view sync task
@@ -236,10 +351,12 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
             sent_view_change_event: false,
             timeout_task: None,
             membership: Arc::clone(&self.membership),
+            consensus: OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus)),
             public_key: self.public_key.clone(),
             private_key: self.private_key.clone(),
             view_sync_timeout: self.view_sync_timeout,
             id: self.id,
+            gossiped_certificates: Arc::clone(&self.gossiped_certificates),
             upgrade_lock: self.upgrade_lock.clone(),
         };
 
@@ -288,6 +405,14 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                 self.send_to_or_create_replica(event, view, &event_stream)
                     .await;
             }
+            HotShotEvent::ViewSyncTrigger(view) => {
+                // Triggered from outside consensus, e.g. by an operator; timeouts trigger view
+                // sync through the replica task directly
+                tracing::warn!("View sync triggered for view {:?}", view);
+                let view = *view;
+                self.send_to_or_create_replica(event, view, &event_stream)
+                    .await;
+            }
 
             HotShotEvent::ViewSyncPreCommitVoteRecv(ref vote) => {
                 let mut map = self.pre_commit_relay_map.write().await;
@@ -304,20 +429,16 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                         .is_some()
                     {
                         map.remove(&vote_view);
+                        self.certificate_formed(vote_view, relay, ViewSyncPhase::PreCommit)
+                            .await;
                     }
 
                     return Ok(());
                 }
 
                 // We do not have a relay task already running, so start one
-                ensure!(
-                    self.membership
-                        .read()
-                        .await
-                        .leader(vote_view + relay, self.cur_epoch)?
-                        == self.public_key,
-                    "View sync vote sent to wrong leader"
-                );
+                self.check_relay_collector(relay_map, vote_view, relay, ViewSyncPhase::PreCommit)
+                    .await?;
 
                 let info = AccumulatorInfo {
                     public_key: self.public_key.clone(),
@@ -325,6 +446,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                     view: vote_view,
                     id: self.id,
                     epoch: vote.data.epoch,
+                    verified_votes: self.verified_votes.clone(),
                 };
                 let vote_collector = create_vote_accumulator(
                     &info,
@@ -353,20 +475,16 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                         .is_some()
                     {
                         map.remove(&vote_view);
+                        self.certificate_formed(vote_view, relay, ViewSyncPhase::Commit)
+                            .await;
                     }
 
                     return Ok(());
                 }
 
                 // We do not have a relay task already running, so start one
-                ensure!(
-                    self.membership
-                        .read()
-                        .await
-                        .leader(vote_view + relay, self.cur_epoch)?
-                        == self.public_key,
-                    debug!("View sync vote sent to wrong leader")
-                );
+                self.check_relay_collector(relay_map, vote_view, relay, ViewSyncPhase::Commit)
+                    .await?;
 
                 let info = AccumulatorInfo {
                     public_key: self.public_key.clone(),
@@ -374,6 +492,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                     view: vote_view,
                     id: self.id,
                     epoch: vote.data.epoch,
+                    verified_votes: self.verified_votes.clone(),
                 };
 
                 let vote_collector = create_vote_accumulator(
@@ -402,20 +521,16 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                         .is_some()
                     {
                         map.remove(&vote_view);
+                        self.certificate_formed(vote_view, relay, ViewSyncPhase::Finalize)
+                            .await;
                     }
 
                     return Ok(());
                 }
 
                 // We do not have a relay task already running, so start one
-                ensure!(
-                    self.membership
-                        .read()
-                        .await
-                        .leader(vote_view + relay, self.cur_epoch)?
-                        == self.public_key,
-                    debug!("View sync vote sent to wrong leader")
-                );
+                self.check_relay_collector(relay_map, vote_view, relay, ViewSyncPhase::Finalize)
+                    .await?;
 
                 let info = AccumulatorInfo {
                     public_key: self.public_key.clone(),
@@ -423,6 +538,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                     view: vote_view,
                     id: self.id,
                     epoch: vote.data.epoch,
+                    verified_votes: self.verified_votes.clone(),
                 };
                 let vote_collector = create_vote_accumulator(
                     &info,
@@ -437,6 +553,12 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                 }
             }
 
+            HotShotEvent::ConfigUpdated(update) => {
+                if let Some(timeout) = update.view_sync_timeout {
+                    self.view_sync_timeout = timeout;
+                }
+            }
+
             &HotShotEvent::ViewChange(new_view, epoch) => {
                 if epoch > self.cur_epoch {
                     self.cur_epoch = epoch;
@@ -476,6 +598,12 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
                     }
 
                     self.last_garbage_collected_view = self.cur_view - 1;
+
+                    let cur_view = self.cur_view;
+                    self.gossiped_certificates
+                        .write()
+                        .await
+                        .retain(|(view, _)| *view >= cur_view);
                 }
             }
             &HotShotEvent::Timeout(view_number, ..) => {
@@ -530,6 +658,82 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
 }
 
 impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
+    /// Send our view sync vote, unless we are not in the view sync committee, which is the only
+    /// one whose votes are counted.
+    async fn send_vote(
+        &self,
+        vote: HotShotEvent<TYPES>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) {
+        if !self
+            .membership
+            .read()
+            .await
+            .has_view_sync_stake(&self.public_key, self.cur_epoch)
+        {
+            tracing::debug!("Not in the view sync committee, not voting");
+            return;
+        }
+
+        broadcast_event(Arc::new(vote), event_stream).await;
+    }
+
+    /// The timeout for the current relay. It doubles with every relay we rotate through, so that
+    /// a round can still complete when the network is slower than `view_sync_timeout` assumes.
+    fn round_timeout(&self) -> Duration {
+        let exponent = u32::try_from(self.relay)
+            .unwrap_or(u32::MAX)
+            .min(VIEW_SYNC_MAX_TIMEOUT_BACKOFF);
+        self.view_sync_timeout.saturating_mul(2u32.pow(exponent))
+    }
+
+    /// The node acting as relay `relay` for this round.
+    async fn relay_key(&self, relay: u64) -> Option<TYPES::SignatureKey> {
+        self.membership
+            .read()
+            .await
+            .leader(self.next_view + relay, self.cur_epoch)
+            .ok()
+    }
+
+    /// Record whether relay `relay` completed this round, unless it is a broadcast relay.
+    ///
+    /// The outcome only feeds the liveness score we report. Relays are never skipped based on
+    /// it: every node must use the same relay in a round, and liveness scores are local.
+    async fn record_relay_outcome(&self, relay: u64, succeeded: bool) {
+        if broadcasts_votes(relay) {
+            return;
+        }
+        if let Some(key) = self.relay_key(relay).await {
+            self.consensus
+                .write()
+                .await
+                .record_relay_outcome(&key, succeeded);
+        }
+    }
+
+    /// Re-broadcast a certificate the first time we accept it for this round, so that nodes
+    /// which missed the relay's broadcast (e.g. because the relay is faulty) still receive it.
+    async fn gossip_certificate(
+        &self,
+        phase: ViewSyncPhase,
+        event: HotShotEvent<TYPES>,
+        event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) {
+        if self
+            .gossiped_certificates
+            .write()
+            .await
+            .insert((self.next_view, phase))
+        {
+            tracing::debug!(
+                "Gossiping view sync certificate for round {}",
+                *self.next_view
+            );
+            broadcast_event(Arc::new(event), event_stream).await;
+        }
+    }
+
     #[instrument(skip_all, fields(id = self.id, view = *self.cur_view, epoch = *self.cur_epoch), name = "View Sync Replica Task", level = "error")]
     /// Handle incoming events for the view sync replica task
     pub async fn handle(
@@ -549,14 +753,18 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                 }
 
                 let membership_reader = self.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(self.cur_epoch);
+                let membership_stake_table =
+                    membership_reader.view_sync_stake_table(self.cur_epoch);
                 let membership_failure_threshold =
-                    membership_reader.failure_threshold(self.cur_epoch);
+                    membership_reader.view_sync_failure_threshold(self.cur_epoch);
                 drop(membership_reader);
 
                 // If certificate is not valid, return current state
-                if !certificate
+                if !self
+                    .consensus
                     .is_valid_cert(
+                        certificate,
+                        self.cur_epoch,
                         membership_stake_table,
                         membership_failure_threshold,
                         &self.upgrade_lock,
@@ -595,11 +803,8 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     return None;
                 };
 
-                broadcast_event(
-                    Arc::new(HotShotEvent::ViewSyncCommitVoteSend(vote)),
-                    &event_stream,
-                )
-                .await;
+                self.send_vote(HotShotEvent::ViewSyncCommitVoteSend(vote), &event_stream)
+                    .await;
 
                 if let Some(timeout_task) = self.timeout_task.take() {
                     timeout_task.abort();
@@ -610,7 +815,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     let phase = last_seen_certificate;
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.round_timeout();
                     async move {
                         sleep(timeout).await;
                         tracing::warn!("Vote sending timed out in ViewSyncPreCommitCertificateRecv, Relay = {}", relay);
@@ -639,14 +844,18 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                 }
 
                 let membership_reader = self.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(self.cur_epoch);
+                let membership_stake_table =
+                    membership_reader.view_sync_stake_table(self.cur_epoch);
                 let membership_success_threshold =
-                    membership_reader.success_threshold(self.cur_epoch);
+                    membership_reader.view_sync_success_threshold(self.cur_epoch);
                 drop(membership_reader);
 
                 // If certificate is not valid, return current state
-                if !certificate
+                if !self
+                    .consensus
                     .is_valid_cert(
+                        certificate,
+                        self.cur_epoch,
                         membership_stake_table,
                         membership_success_threshold,
                         &self.upgrade_lock,
@@ -668,6 +877,16 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     self.relay = certificate.data().relay;
                 }
 
+                self.gossip_certificate(
+                    ViewSyncPhase::Commit,
+                    HotShotEvent::ViewSyncCommitCertificateSend(
+                        certificate.clone(),
+                        self.public_key.clone(),
+                    ),
+                    &event_stream,
+                )
+                .await;
+
                 let Ok(vote) = ViewSyncFinalizeVote2::<TYPES>::create_signed_vote(
                     ViewSyncFinalizeData2 {
                         relay: certificate.data().relay,
@@ -685,11 +904,8 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     return None;
                 };
 
-                broadcast_event(
-                    Arc::new(HotShotEvent::ViewSyncFinalizeVoteSend(vote)),
-                    &event_stream,
-                )
-                .await;
+                self.send_vote(HotShotEvent::ViewSyncFinalizeVoteSend(vote), &event_stream)
+                    .await;
 
                 tracing::info!(
                     "View sync protocol has received view sync evidence to update the view to {}",
@@ -711,7 +927,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     let phase = last_seen_certificate;
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.round_timeout();
                     async move {
                         sleep(timeout).await;
                         tracing::warn!(
@@ -740,14 +956,18 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                 }
 
                 let membership_reader = self.membership.read().await;
-                let membership_stake_table = membership_reader.stake_table(self.cur_epoch);
+                let membership_stake_table =
+                    membership_reader.view_sync_stake_table(self.cur_epoch);
                 let membership_success_threshold =
-                    membership_reader.success_threshold(self.cur_epoch);
+                    membership_reader.view_sync_success_threshold(self.cur_epoch);
                 drop(membership_reader);
 
                 // If certificate is not valid, return current state
-                if !certificate
+                if !self
+                    .consensus
                     .is_valid_cert(
+                        certificate,
+                        self.cur_epoch,
                         membership_stake_table,
                         membership_success_threshold,
                         &self.upgrade_lock,
@@ -769,6 +989,19 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     self.relay = certificate.data().relay;
                 }
 
+                self.gossip_certificate(
+                    ViewSyncPhase::Finalize,
+                    HotShotEvent::ViewSyncFinalizeCertificateSend(
+                        certificate.clone(),
+                        self.public_key.clone(),
+                    ),
+                    &event_stream,
+                )
+                .await;
+
+                self.record_relay_outcome(certificate.data().relay, true)
+                    .await;
+
                 if let Some(timeout_task) = self.timeout_task.take() {
                     timeout_task.abort();
                 }
@@ -792,7 +1025,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                 let epoch = self.cur_epoch;
                 let Ok(vote) = ViewSyncPreCommitVote2::<TYPES>::create_signed_vote(
                     ViewSyncPreCommitData2 {
-                        relay: 0,
+                        relay: self.relay,
                         round: view_number,
                         epoch,
                     },
@@ -807,17 +1040,14 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     return None;
                 };
 
-                broadcast_event(
-                    Arc::new(HotShotEvent::ViewSyncPreCommitVoteSend(vote)),
-                    &event_stream,
-                )
-                .await;
+                self.send_vote(HotShotEvent::ViewSyncPreCommitVoteSend(vote), &event_stream)
+                    .await;
 
                 self.timeout_task = Some(spawn({
                     let stream = event_stream.clone();
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.round_timeout();
                     async move {
                         sleep(timeout).await;
                         tracing::warn!("Vote sending timed out in ViewSyncTrigger");
@@ -843,6 +1073,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     if let Some(timeout_task) = self.timeout_task.take() {
                         timeout_task.abort();
                     }
+                    self.record_relay_outcome(self.relay, false).await;
                     self.relay += 1;
                     match last_seen_certificate {
                         ViewSyncPhase::None | ViewSyncPhase::PreCommit | ViewSyncPhase::Commit => {
@@ -863,8 +1094,8 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                                 return None;
                             };
 
-                            broadcast_event(
-                                Arc::new(HotShotEvent::ViewSyncPreCommitVoteSend(vote)),
+                            self.send_vote(
+                                HotShotEvent::ViewSyncPreCommitVoteSend(vote),
                                 &event_stream,
                             )
                             .await;
@@ -879,7 +1110,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                         let stream = event_stream.clone();
                         let relay = self.relay;
                         let next_view = self.next_view;
-                        let timeout = self.view_sync_timeout;
+                        let timeout = self.round_timeout();
                         let last_cert = last_seen_certificate.clone();
                         async move {
                             sleep(timeout).await;
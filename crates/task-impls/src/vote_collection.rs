@@ -31,11 +31,11 @@ use hotshot_types::{
         node_implementation::{NodeType, Versions},
     },
     utils::EpochTransitionIndicator,
-    vote::{Certificate, HasViewNumber, Vote, VoteAccumulator},
+    vote::{Certificate, HasViewNumber, VerifiedVotes, Vote, VoteAccumulator},
 };
 use utils::anytrace::*;
 
-use crate::{events::HotShotEvent, helpers::broadcast_event};
+use crate::{events::HotShotEvent, helpers::broadcast_event, view_sync::broadcasts_votes};
 
 /// Alias for a map of Vote Collectors
 pub type VoteCollectorsMap<TYPES, VOTE, CERT, V> =
@@ -89,6 +89,12 @@ pub trait AggregatableVote<
 
     /// return the Hotshot event for the completion of this CERT
     fn make_cert_event(certificate: CERT, key: &TYPES::SignatureKey) -> HotShotEvent<TYPES>;
+
+    /// whether this vote is broadcast to every node rather than sent to its leader, so that any
+    /// node may collect it
+    fn is_broadcast(&self) -> bool {
+        false
+    }
 }
 
 impl<
@@ -114,7 +120,8 @@ impl<
             matches!(
                 self.transition_indicator,
                 EpochTransitionIndicator::InTransition
-            ) || vote.leader(&*self.membership.read().await, self.epoch)? == self.public_key,
+            ) || vote.is_broadcast()
+                || vote.leader(&*self.membership.read().await, self.epoch)? == self.public_key,
             info!("Received vote for a view in which we were not the leader.")
         );
 
@@ -190,6 +197,9 @@ pub struct AccumulatorInfo<TYPES: NodeType> {
 
     /// This nodes id
     pub id: u64,
+
+    /// Votes whose signatures were already checked
+    pub verified_votes: VerifiedVotes<TYPES>,
 }
 
 /// Generic function for spawning a vote task.  Returns the event stream id of the spawned task if created
@@ -226,6 +236,7 @@ where
         signers: HashMap::new(),
         phantom: PhantomData,
         upgrade_lock,
+        verified_votes: info.verified_votes.clone(),
     };
 
     let mut state = VoteCollectionTaskState::<TYPES, VOTE, CERT, V> {
@@ -267,6 +278,7 @@ pub async fn handle_vote<
     event: &Arc<HotShotEvent<TYPES>>,
     event_stream: &Sender<Arc<HotShotEvent<TYPES>>>,
     upgrade_lock: &UpgradeLock<TYPES, V>,
+    verified_votes: &VerifiedVotes<TYPES>,
     transition_indicator: EpochTransitionIndicator,
 ) -> Result<()>
 where
@@ -281,6 +293,7 @@ where
                 view: vote.view_number(),
                 epoch,
                 id,
+                verified_votes: verified_votes.clone(),
             };
             let collector = create_vote_accumulator(
                 &info,
@@ -312,6 +325,22 @@ where
     }
 }
 
+/// Drop the vote collectors for all views older than `anchor_view`.
+///
+/// Votes for views before the anchor can no longer produce a useful certificate, so their
+/// collectors would otherwise only be removed if they happened to reach a threshold.
+pub fn collect_garbage<
+    TYPES: NodeType,
+    VOTE: Vote<TYPES>,
+    CERT: Certificate<TYPES, VOTE::Commitment, Voteable = VOTE::Commitment> + Debug,
+    V: Versions,
+>(
+    collectors: &mut VoteCollectorsMap<TYPES, VOTE, CERT, V>,
+    anchor_view: TYPES::View,
+) {
+    *collectors = collectors.split_off(&anchor_view);
+}
+
 /// Alias for Quorum vote accumulator
 type QuorumVoteState<TYPES, V> =
     VoteCollectionTaskState<TYPES, QuorumVote2<TYPES>, QuorumCertificate2<TYPES>, V>;
@@ -479,6 +508,9 @@ impl<TYPES: NodeType>
     ) -> HotShotEvent<TYPES> {
         HotShotEvent::ViewSyncCommitCertificateSend(certificate, key.clone())
     }
+    fn is_broadcast(&self) -> bool {
+        broadcasts_votes(self.date().relay)
+    }
 }
 
 impl<TYPES: NodeType>
@@ -498,6 +530,9 @@ impl<TYPES: NodeType>
     ) -> HotShotEvent<TYPES> {
         HotShotEvent::ViewSyncPreCommitCertificateSend(certificate, key.clone())
     }
+    fn is_broadcast(&self) -> bool {
+        broadcasts_votes(self.date().relay)
+    }
 }
 
 impl<TYPES: NodeType>
@@ -517,6 +552,9 @@ impl<TYPES: NodeType>
     ) -> HotShotEvent<TYPES> {
         HotShotEvent::ViewSyncFinalizeCertificateSend(certificate, key.clone())
     }
+    fn is_broadcast(&self) -> bool {
+        broadcasts_votes(self.date().relay)
+    }
 }
 
 // Handlers for all vote accumulators
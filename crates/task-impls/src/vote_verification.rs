@@ -0,0 +1,196 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Checks the signatures of votes as they arrive from the network.
+//!
+//! Checking a signature is most of the work of collecting a vote, and vote collectors handle
+//! their votes one at a time. [`verify_votes`] checks the signatures of the votes in a message on
+//! a blocking worker and records the valid ones in [`VerifiedVotes`], so that the network message
+//! task can check many votes at once while the collectors only add up stake. Votes for views far
+//! from the current one are left to their collectors, see [`VerifiedVotes::is_tracked`], unless
+//! the vote is all that authenticates the sender of its message, see [`SenderAuth::Vote`].
+
+use committable::Committable;
+use hotshot_types::{
+    message::{
+        DaConsensusMessage, GeneralConsensusMessage, Message, MessageKind, SenderAuth,
+        SequencingMessage, UpgradeLock,
+    },
+    simple_vote::VersionedVoteData,
+    traits::{
+        node_implementation::{NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+    vote::{VerifiedVotes, Vote},
+};
+use tokio::task::spawn_blocking;
+
+/// Check the signature of `vote` on a blocking worker, and record it in `verified_votes` if it is
+/// valid. Returns whether the signature is valid.
+///
+/// With `authenticates_sender`, the vote is checked even if it is left to its collector.
+async fn verify_vote<TYPES: NodeType, VOTE: Vote<TYPES>, V: Versions>(
+    vote: &VOTE,
+    current_view: TYPES::View,
+    upgrade_lock: &UpgradeLock<TYPES, V>,
+    verified_votes: &VerifiedVotes<TYPES>,
+    authenticates_sender: bool,
+) -> bool {
+    let is_tracked = VerifiedVotes::<TYPES>::is_tracked(current_view, vote.view_number());
+    if !is_tracked && !authenticates_sender {
+        // Leave the vote to its collector, if it still has one
+        return true;
+    }
+
+    let vote_commitment =
+        match VersionedVoteData::new(vote.date().clone(), vote.view_number(), upgrade_lock).await {
+            Ok(data) => data.commit().as_ref().to_vec(),
+            // Leave the vote to its collector, which rejects it the same way
+            Err(_) => return !authenticates_sender,
+        };
+
+    let key = vote.signing_key();
+    let signature = vote.signature();
+    let valid = {
+        let (key, signature, vote_commitment) =
+            (key.clone(), signature.clone(), vote_commitment.clone());
+        spawn_blocking(move || key.validate(&signature, &vote_commitment))
+            .await
+            .unwrap_or(false)
+    };
+
+    if valid && is_tracked {
+        verified_votes
+            .record(
+                current_view,
+                vote.view_number(),
+                key,
+                &vote_commitment,
+                signature,
+            )
+            .await;
+    } else if !valid {
+        tracing::warn!("Dropping vote with an invalid signature from {key}");
+    }
+    valid
+}
+
+/// Check the signature of the vote in `message`, if it carries one, recording it in
+/// `verified_votes` if it is valid. `current_view` is the view this node is in.
+///
+/// Returns whether the message should be handled, that is unless it is a vote with an invalid
+/// signature. Votes in the formats from before epochs are left to their collectors. `sender_auth`
+/// is how the sender of the message was authenticated; a message authenticated only by its vote
+/// is dropped unless the vote is valid.
+pub async fn verify_votes<TYPES: NodeType, V: Versions>(
+    message: &Message<TYPES>,
+    current_view: TYPES::View,
+    upgrade_lock: &UpgradeLock<TYPES, V>,
+    verified_votes: &VerifiedVotes<TYPES>,
+    sender_auth: SenderAuth,
+) -> bool {
+    let authenticates_sender = sender_auth == SenderAuth::Vote;
+    let MessageKind::Consensus(message) = &message.kind else {
+        return !authenticates_sender;
+    };
+
+    match message {
+        SequencingMessage::General(message) => match message {
+            GeneralConsensusMessage::Vote2(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::TimeoutVote2(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::ViewSyncPreCommitVote2(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::ViewSyncCommitVote2(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::ViewSyncFinalizeVote2(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::UpgradeVote(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::CheckpointVote(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            GeneralConsensusMessage::AttestationVote(vote) => {
+                verify_vote(
+                    vote,
+                    current_view,
+                    upgrade_lock,
+                    verified_votes,
+                    authenticates_sender,
+                )
+                .await
+            }
+            _ => !authenticates_sender,
+        },
+        SequencingMessage::Da(DaConsensusMessage::DaVote2(vote)) => {
+            verify_vote(
+                vote,
+                current_view,
+                upgrade_lock,
+                verified_votes,
+                authenticates_sender,
+            )
+            .await
+        }
+        SequencingMessage::Da(_) => !authenticates_sender,
+    }
+}
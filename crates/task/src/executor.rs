@@ -0,0 +1,198 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use tokio::{
+    runtime::{Builder, Handle, Runtime},
+    task::JoinHandle,
+};
+
+/// How urgently a task needs to run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TaskPriority {
+    /// Tasks on the critical path of consensus, which should not wait behind other work
+    Critical,
+    /// All other tasks
+    Normal,
+}
+
+impl TaskPriority {
+    /// Index of the priority in the running task counts
+    fn index(self) -> usize {
+        match self {
+            TaskPriority::Critical => 0,
+            TaskPriority::Normal => 1,
+        }
+    }
+}
+
+/// A runtime owned by an executor, shut down without blocking when the executor is dropped, since
+/// that may happen inside another runtime
+struct DedicatedRuntime {
+    /// handle to spawn tasks on the runtime
+    handle: Handle,
+    /// the runtime, taken when dropped
+    runtime: Option<Runtime>,
+}
+
+impl Drop for DedicatedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Spawns tasks by priority, and counts the tasks of each priority running
+///
+/// Critical tasks can be pinned to worker threads of their own, so that heavy DA or networking
+/// work on the shared runtime does not delay them. Without dedicated threads every task is
+/// spawned on the shared runtime.
+#[derive(Clone, Default)]
+pub struct TaskExecutor {
+    /// the runtime for critical tasks, if they have dedicated threads
+    critical: Option<Arc<DedicatedRuntime>>,
+    /// the number of tasks of each priority running
+    running: Arc<[AtomicUsize; 2]>,
+}
+
+impl std::fmt::Debug for TaskExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskExecutor")
+            .field("dedicated", &self.critical.is_some())
+            .field("critical", &self.running(TaskPriority::Critical))
+            .field("normal", &self.running(TaskPriority::Normal))
+            .finish()
+    }
+}
+
+impl TaskExecutor {
+    /// An executor running critical tasks on `critical_threads` dedicated threads, or on the
+    /// shared runtime if zero
+    ///
+    /// If the dedicated threads cannot be started, critical tasks fall back to the shared
+    /// runtime.
+    #[must_use]
+    pub fn new(critical_threads: usize) -> Self {
+        if critical_threads == 0 {
+            return Self::default();
+        }
+
+        match Builder::new_multi_thread()
+            .worker_threads(critical_threads)
+            .thread_name("hotshot-consensus")
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => Self {
+                critical: Some(Arc::new(DedicatedRuntime {
+                    handle: runtime.handle().clone(),
+                    runtime: Some(runtime),
+                })),
+                running: Arc::default(),
+            },
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to start dedicated consensus threads, running on the shared runtime: {e}"
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether critical tasks run on dedicated threads
+    #[must_use]
+    pub fn has_dedicated_threads(&self) -> bool {
+        self.critical.is_some()
+    }
+
+    /// The number of tasks of `priority` running
+    #[must_use]
+    pub fn running(&self, priority: TaskPriority) -> usize {
+        self.running[priority.index()].load(Ordering::Relaxed)
+    }
+
+    /// Spawn `future` with `priority`
+    pub fn spawn<F>(&self, priority: TaskPriority, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let guard = RunningGuard::new(Arc::clone(&self.running), priority);
+        let future = async move {
+            let _guard = guard;
+            future.await
+        };
+
+        match (priority, &self.critical) {
+            (TaskPriority::Critical, Some(runtime)) => runtime.handle.spawn(future),
+            _ => tokio::spawn(future),
+        }
+    }
+}
+
+/// Counts a task as running until dropped, when the task finishes or is aborted
+struct RunningGuard {
+    /// the counts to update
+    running: Arc<[AtomicUsize; 2]>,
+    /// the priority of the task
+    priority: TaskPriority,
+}
+
+impl RunningGuard {
+    /// Count a task of `priority` as running
+    fn new(running: Arc<[AtomicUsize; 2]>, priority: TaskPriority) -> Self {
+        running[priority.index()].fetch_add(1, Ordering::Relaxed);
+        Self { running, priority }
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.running[self.priority.index()].fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn critical_tasks_run_on_dedicated_threads_and_are_counted() {
+        let executor = TaskExecutor::new(1);
+        assert!(executor.has_dedicated_threads());
+
+        let (release, released) = oneshot::channel::<()>();
+        let critical = executor.spawn(TaskPriority::Critical, async move {
+            let _ = released.await;
+            std::thread::current().name().map(str::to_owned)
+        });
+        let normal = executor.spawn(TaskPriority::Normal, async {});
+        normal.await.unwrap();
+
+        assert_eq!(executor.running(TaskPriority::Critical), 1);
+        assert_eq!(executor.running(TaskPriority::Normal), 0);
+
+        release.send(()).unwrap();
+        let thread_name = tokio::time::timeout(Duration::from_secs(5), critical)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(thread_name.as_deref(), Some("hotshot-consensus"));
+        assert_eq!(executor.running(TaskPriority::Critical), 0);
+    }
+}
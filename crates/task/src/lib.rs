@@ -10,5 +10,7 @@
 pub mod dependency;
 /// Task which can uses dependencies
 pub mod dependency_task;
+/// Spawning tasks by priority
+pub mod executor;
 /// Basic task types
 pub mod task;
@@ -4,14 +4,23 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_broadcast::{Receiver, RecvError, Sender};
 use async_trait::async_trait;
-use futures::future::try_join_all;
-use tokio::task::{spawn, JoinHandle};
+use futures::future::{join_all, try_join_all};
+use tokio::{
+    task::{spawn, JoinHandle},
+    time::{timeout, timeout_at, Instant},
+};
+pub use tokio_util::sync::CancellationToken;
 use utils::anytrace::Result;
 
+use crate::executor::{TaskExecutor, TaskPriority};
+
+/// How long registries wait for their tasks to finish on shutdown, before aborting them
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Trait for events that long-running tasks handle
 pub trait TaskEvent: PartialEq {
     /// The shutdown signal for this event type
@@ -71,31 +80,43 @@ impl<S: TaskState + Send + 'static> Task<S> {
 
     /// Spawn the task loop, consuming self.  Will continue until
     /// the task reaches some shutdown condition
-    pub fn run(mut self) -> JoinHandle<Box<dyn TaskState<Event = S::Event>>> {
-        spawn(async move {
-            loop {
-                match self.receiver.recv_direct().await {
-                    Ok(input) => {
-                        if *input == S::Event::shutdown_event() {
-                            self.state.cancel_subtasks();
-
-                            break self.boxed_state();
-                        }
-
-                        let _ =
-                            S::handle_event(&mut self.state, input, &self.sender, &self.receiver)
-                                .await
-                                .inspect_err(|e| tracing::debug!("{e}"));
-                    }
-                    Err(RecvError::Closed) => {
+    pub fn run(self) -> JoinHandle<Box<dyn TaskState<Event = S::Event>>> {
+        spawn(self.event_loop())
+    }
+
+    /// Spawn the task loop with `priority` on `executor`, consuming self.  Will continue until
+    /// the task reaches some shutdown condition
+    pub fn run_on(
+        self,
+        executor: &TaskExecutor,
+        priority: TaskPriority,
+    ) -> JoinHandle<Box<dyn TaskState<Event = S::Event>>> {
+        executor.spawn(priority, self.event_loop())
+    }
+
+    /// Handle events until the shutdown event, or the event stream closes
+    async fn event_loop(mut self) -> Box<dyn TaskState<Event = S::Event>> {
+        loop {
+            match self.receiver.recv_direct().await {
+                Ok(input) => {
+                    if *input == S::Event::shutdown_event() {
+                        self.state.cancel_subtasks();
+
                         break self.boxed_state();
                     }
-                    Err(e) => {
-                        tracing::error!("Failed to receive from event stream Error: {}", e);
-                    }
+
+                    let _ = S::handle_event(&mut self.state, input, &self.sender, &self.receiver)
+                        .await
+                        .inspect_err(|e| tracing::debug!("{e}"));
+                }
+                Err(RecvError::Closed) => {
+                    break self.boxed_state();
+                }
+                Err(e) => {
+                    tracing::error!("Failed to receive from event stream Error: {}", e);
                 }
             }
-        })
+        }
     }
 }
 
@@ -104,32 +125,56 @@ impl<S: TaskState + Send + 'static> Task<S> {
 pub struct ConsensusTaskRegistry<EVENT> {
     /// Tasks this registry controls
     task_handles: Vec<JoinHandle<Box<dyn TaskState<Event = EVENT>>>>,
+    /// Spawns the tasks this registry runs
+    executor: TaskExecutor,
 }
 
 impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
     #[must_use]
     /// Create a new task registry
     pub fn new() -> Self {
+        Self::with_executor(TaskExecutor::default())
+    }
+
+    #[must_use]
+    /// Create a new task registry, running its tasks on `executor`
+    pub fn with_executor(executor: TaskExecutor) -> Self {
         ConsensusTaskRegistry {
             task_handles: vec![],
+            executor,
         }
     }
+
+    /// The executor running the tasks of this registry, which counts them by priority
+    #[must_use]
+    pub fn executor(&self) -> &TaskExecutor {
+        &self.executor
+    }
+
     /// Add a task to the registry
     pub fn register(&mut self, handle: JoinHandle<Box<dyn TaskState<Event = EVENT>>>) {
         self.task_handles.push(handle);
     }
-    /// Try to cancel/abort the task this registry has
+    /// Wait for the tasks this registry has to stop after the shutdown event, and cancel their
+    /// subtasks
+    ///
+    /// Tasks which have not stopped within [`SHUTDOWN_TIMEOUT`] are aborted.
     ///
     /// # Panics
     ///
     /// Should not panic, unless awaiting on the JoinHandle in tokio fails.
     pub async fn shutdown(&mut self) {
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
         let handles = &mut self.task_handles;
 
-        while let Some(handle) = handles.pop() {
-            let mut task_state = handle.await.unwrap();
-
-            task_state.cancel_subtasks();
+        while let Some(mut handle) = handles.pop() {
+            match timeout_at(deadline, &mut handle).await {
+                Ok(task_state) => task_state.unwrap().cancel_subtasks(),
+                Err(_) => {
+                    tracing::warn!("A consensus task did not stop in time, aborting it");
+                    handle.abort();
+                }
+            }
         }
     }
     /// Take a task, run it, and register it
@@ -137,7 +182,15 @@ impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
     where
         S: TaskState<Event = EVENT> + Send + 'static,
     {
-        self.register(task.run());
+        self.run_task_with_priority(task, TaskPriority::Normal);
+    }
+
+    /// Take a task, run it with `priority`, and register it
+    pub fn run_task_with_priority<S>(&mut self, task: Task<S>, priority: TaskPriority)
+    where
+        S: TaskState<Event = EVENT> + Send + 'static,
+    {
+        self.register(task.run_on(&self.executor, priority));
     }
 
     /// Wait for the results of all the tasks registered
@@ -153,29 +206,61 @@ impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
 pub struct NetworkTaskRegistry {
     /// Tasks this registry controls
     pub handles: Vec<JoinHandle<()>>,
+    /// Cancelled when the registry shuts down, tasks are handed child tokens of it
+    cancel: CancellationToken,
 }
 
 impl NetworkTaskRegistry {
     #[must_use]
     /// Create a new task registry
     pub fn new() -> Self {
-        NetworkTaskRegistry { handles: vec![] }
+        NetworkTaskRegistry {
+            handles: vec![],
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// A token which is cancelled when this registry shuts down
+    ///
+    /// Tasks registered here should stop once it is cancelled.
+    #[must_use]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.child_token()
     }
 
-    #[allow(clippy::unused_async)]
     /// Shuts down all tasks managed by this instance.
     ///
-    /// This function waits for all tasks to complete before returning.
+    /// This function cancels the tokens handed out by [`Self::cancellation_token`], and waits up
+    /// to [`SHUTDOWN_TIMEOUT`] for all tasks to complete. Tasks which have not completed by then
+    /// are aborted.
     ///
     /// # Panics
     ///
     /// When using the tokio executor, this function will panic if any of the
     /// tasks being joined return an error.
     pub async fn shutdown(&mut self) {
+        self.cancel.cancel();
         let handles = std::mem::take(&mut self.handles);
-        try_join_all(handles)
-            .await
-            .expect("Failed to join all tasks during shutdown");
+        let abort_handles = handles
+            .iter()
+            .map(JoinHandle::abort_handle)
+            .collect::<Vec<_>>();
+        match timeout(SHUTDOWN_TIMEOUT, join_all(handles)).await {
+            Ok(results) => {
+                results
+                    .into_iter()
+                    .collect::<std::result::Result<Vec<()>, _>>()
+                    .expect("Failed to join all tasks during shutdown");
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "Network tasks did not stop within {SHUTDOWN_TIMEOUT:?}, aborting them"
+                );
+                for handle in abort_handles {
+                    handle.abort();
+                }
+            }
+        }
     }
 
     /// Add a task to the registry
@@ -183,3 +268,37 @@ impl NetworkTaskRegistry {
         self.handles.push(handle);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use tokio::time::sleep;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn network_tasks_are_cancelled_or_aborted_on_shutdown() {
+        let mut registry = NetworkTaskRegistry::new();
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let cancel = registry.cancellation_token();
+        let stopped_in_task = Arc::clone(&stopped);
+        registry.register(spawn(async move {
+            cancel.cancelled().await;
+            stopped_in_task.store(true, Ordering::SeqCst);
+        }));
+
+        // a task ignoring its token is aborted rather than waited on forever
+        let stuck = spawn(sleep(Duration::from_secs(3600)));
+        let stuck_abort = stuck.abort_handle();
+        registry.register(stuck);
+
+        timeout(SHUTDOWN_TIMEOUT * 2, registry.shutdown())
+            .await
+            .expect("Shutdown did not finish in time");
+        assert!(stopped.load(Ordering::SeqCst));
+        sleep(Duration::from_millis(100)).await;
+        assert!(stuck_abort.is_finished());
+    }
+}
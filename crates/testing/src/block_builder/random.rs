@@ -27,6 +27,7 @@ use hotshot_builder_api::v0_1::{
 };
 use hotshot_example_types::block_types::TestTransaction;
 use hotshot_types::{
+    deterministic::with_rng,
     network::RandomBuilderConfig,
     traits::{node_implementation::NodeType, signature_key::BuilderSignatureKey},
     utils::BuilderCommitment,
@@ -119,7 +120,7 @@ impl<TYPES: NodeType<Transaction = TestTransaction>> RandomBuilderTask<TYPES> {
     ) where
         <TYPES as NodeType>::InstanceState: Default,
     {
-        let mut rng = SmallRng::from_entropy();
+        let mut rng = SmallRng::seed_from_u64(with_rng(|rng| rng.next_u64()));
         let time_per_block = Duration::from_secs(1) / options.blocks_per_second;
         loop {
             let start = std::time::Instant::now();
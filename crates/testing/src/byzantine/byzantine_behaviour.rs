@@ -6,6 +6,7 @@ use std::{
 use anyhow::Context;
 use async_lock::RwLock;
 use async_trait::async_trait;
+use committable::Committable;
 use hotshot::{
     tasks::EventTransformerState,
     types::{SignatureKey, SystemContextHandle},
@@ -19,10 +20,14 @@ use hotshot_task_impls::{
 };
 use hotshot_types::{
     consensus::{Consensus, OuterConsensus},
-    data::QuorumProposal2,
+    data::{Leaf2, QuorumProposal2},
     message::{Proposal, UpgradeLock},
+    simple_certificate::QuorumCertificate2,
     simple_vote::QuorumVote2,
-    traits::node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+    traits::{
+        consensus_api::ConsensusApi,
+        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+    },
 };
 
 #[derive(Debug)]
@@ -350,6 +355,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + std::fmt::Debug, V: Version
             consensus: OuterConsensus::new(handle.consensus()),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
             transmit_tasks: BTreeMap::new(),
+            paused: Arc::default(),
+            namespace: handle.hotshot.config.namespace,
+            wire_encodings: handle.hotshot.wire_encodings.clone(),
+            private_key: handle.private_key().clone(),
+            message_ttl: handle.hotshot.config.message_ttl,
         };
         let modified_network_state = NetworkEventTaskStateModifier {
             network_event_task_state: network_state,
@@ -430,3 +440,144 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + std::fmt::Debug, V: Version
         vec![event.clone()]
     }
 }
+
+#[derive(Debug)]
+/// An `EventHandlerState` that equivocates as leader: alongside each proposal it sends a second,
+/// validly signed proposal for the same view which extends the parent of the last proposal it validated
+pub struct EquivocatingLeader<TYPES: NodeType> {
+    /// The justify QC of the last proposal we validated
+    pub last_validated_justify_qc: Option<QuorumCertificate2<TYPES>>,
+}
+
+impl<TYPES: NodeType> Default for EquivocatingLeader<TYPES> {
+    fn default() -> Self {
+        Self {
+            last_validated_justify_qc: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES> + std::fmt::Debug, V: Versions>
+    EventTransformerState<TYPES, I, V> for EquivocatingLeader<TYPES>
+{
+    async fn recv_handler(&mut self, event: &HotShotEvent<TYPES>) -> Vec<HotShotEvent<TYPES>> {
+        vec![event.clone()]
+    }
+
+    async fn send_handler(
+        &mut self,
+        event: &HotShotEvent<TYPES>,
+        _public_key: &TYPES::SignatureKey,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        _upgrade_lock: &UpgradeLock<TYPES, V>,
+        _consensus: Arc<RwLock<Consensus<TYPES>>>,
+    ) -> Vec<HotShotEvent<TYPES>> {
+        match event {
+            HotShotEvent::QuorumProposalValidated(proposal, _) => {
+                self.last_validated_justify_qc = Some(proposal.data.justify_qc.clone());
+            }
+            HotShotEvent::QuorumProposalSend(proposal, sender) => {
+                let Some(justify_qc) = self.last_validated_justify_qc.clone() else {
+                    return vec![event.clone()];
+                };
+                if justify_qc == proposal.data.justify_qc {
+                    return vec![event.clone()];
+                }
+
+                // Build a sibling of our parent and sign it like an honest proposal
+                let mut conflicting_proposal = proposal.clone();
+                conflicting_proposal.data.justify_qc = justify_qc;
+                let conflicting_leaf = Leaf2::from_quorum_proposal(&conflicting_proposal.data);
+                let Ok(signature) =
+                    TYPES::SignatureKey::sign(private_key, conflicting_leaf.commit().as_ref())
+                else {
+                    return vec![event.clone()];
+                };
+                conflicting_proposal.signature = signature;
+
+                tracing::debug!(
+                    "Equivocating in view {:?}",
+                    conflicting_proposal.data.view_number
+                );
+                return vec![
+                    event.clone(),
+                    HotShotEvent::QuorumProposalSend(conflicting_proposal, sender.clone()),
+                ];
+            }
+            _ => {}
+        }
+        vec![event.clone()]
+    }
+}
+
+#[derive(Debug)]
+/// An `EventHandlerState` that withholds all of the node's votes, including timeout and view sync votes
+pub struct WithholdVotes;
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES> + std::fmt::Debug, V: Versions>
+    EventTransformerState<TYPES, I, V> for WithholdVotes
+{
+    async fn recv_handler(&mut self, event: &HotShotEvent<TYPES>) -> Vec<HotShotEvent<TYPES>> {
+        vec![event.clone()]
+    }
+
+    async fn send_handler(
+        &mut self,
+        event: &HotShotEvent<TYPES>,
+        _public_key: &TYPES::SignatureKey,
+        _private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        _upgrade_lock: &UpgradeLock<TYPES, V>,
+        _consensus: Arc<RwLock<Consensus<TYPES>>>,
+    ) -> Vec<HotShotEvent<TYPES>> {
+        match event {
+            HotShotEvent::QuorumVoteSend(_)
+            | HotShotEvent::ExtendedQuorumVoteSend(_)
+            | HotShotEvent::DaVoteSend(_)
+            | HotShotEvent::TimeoutVoteSend(_)
+            | HotShotEvent::ViewSyncPreCommitVoteSend(_)
+            | HotShotEvent::ViewSyncCommitVoteSend(_)
+            | HotShotEvent::ViewSyncFinalizeVoteSend(_) => vec![],
+            _ => vec![event.clone()],
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An `EventHandlerState` that sends quorum votes whose signatures do not match the vote, by signing the vote for the next view
+pub struct InvalidVoteSignatures;
+
+#[async_trait]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES> + std::fmt::Debug, V: Versions>
+    EventTransformerState<TYPES, I, V> for InvalidVoteSignatures
+{
+    async fn recv_handler(&mut self, event: &HotShotEvent<TYPES>) -> Vec<HotShotEvent<TYPES>> {
+        vec![event.clone()]
+    }
+
+    async fn send_handler(
+        &mut self,
+        event: &HotShotEvent<TYPES>,
+        public_key: &TYPES::SignatureKey,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+        _consensus: Arc<RwLock<Consensus<TYPES>>>,
+    ) -> Vec<HotShotEvent<TYPES>> {
+        if let HotShotEvent::QuorumVoteSend(vote) = event {
+            let mut bad_vote = QuorumVote2::<TYPES>::create_signed_vote(
+                vote.data.clone(),
+                vote.view_number + 1,
+                public_key,
+                private_key,
+                upgrade_lock,
+            )
+            .await
+            .context("Failed to sign vote")
+            .unwrap();
+            bad_vote.view_number = vote.view_number;
+            return vec![HotShotEvent::QuorumVoteSend(bad_vote)];
+        }
+        vec![event.clone()]
+    }
+}
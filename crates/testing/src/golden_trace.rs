@@ -0,0 +1,163 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Golden traces of the messages exchanged in a test.
+//!
+//! A test with a [`GoldenTraceDescription`] records every message between its nodes, up to a
+//! view, as one line of `<view> <sender> -> <recipient> <kind>`. The trace is compared with the
+//! golden trace stored at the given path, and the test fails if the message flow changed. Only
+//! deterministic tests produce stable traces.
+//!
+//! A missing golden trace fails the test like a different one. Set [`UPDATE_GOLDEN_ENV_VAR`] to
+//! record a new golden trace, or to overwrite one after an intended change to the message flow,
+//! and commit the file.
+//!
+//! [`check_golden`] compares any other deterministic output with a golden file in the same way.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use hotshot_types::{
+    message::Message,
+    traits::{
+        network::{Interception, MessageHook},
+        node_implementation::{NodeType, Versions},
+    },
+    vote::HasViewNumber,
+};
+
+use crate::message_hook::message_hook;
+
+/// Environment variable which, when set, overwrites golden files with the recorded output
+pub const UPDATE_GOLDEN_ENV_VAR: &str = "HOTSHOT_UPDATE_GOLDEN";
+
+/// Compare `actual` with the golden file at `path`, recording it instead if
+/// [`UPDATE_GOLDEN_ENV_VAR`] is set.
+///
+/// # Errors
+/// if the contents differ, naming the first line at which they do, or the golden file does not
+/// exist or cannot be read or written
+pub fn check_golden(path: &Path, actual: &str) -> Result<(), String> {
+    if std::env::var(UPDATE_GOLDEN_ENV_VAR).is_ok() {
+        tracing::warn!("Recording the golden file {}", path.display());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        return std::fs::write(path, actual).map_err(|e| e.to_string());
+    }
+    if !path.exists() {
+        return Err(format!(
+            "The golden file {} does not exist. Set {UPDATE_GOLDEN_ENV_VAR} to record it.",
+            path.display()
+        ));
+    }
+
+    let golden = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if golden == actual {
+        return Ok(());
+    }
+    let mut golden_lines = golden.lines();
+    let mut lines = actual.lines();
+    let difference = (1..)
+        .map(|line_number| (line_number, golden_lines.next(), lines.next()))
+        .take_while(|(_, expected, actual)| expected.is_some() || actual.is_some())
+        .find(|(_, expected, actual)| expected != actual);
+    match difference {
+        Some((line_number, expected, actual)) => Err(format!(
+            "Output differs from the golden file {} at line {line_number}: expected \
+             {expected:?}, got {actual:?}. Set {UPDATE_GOLDEN_ENV_VAR} to record the new output \
+             if the change is intended.",
+            path.display()
+        )),
+        // only the line endings differ
+        None => Ok(()),
+    }
+}
+
+/// Describes the golden trace a test is compared with
+#[derive(Clone, Debug)]
+pub struct GoldenTraceDescription {
+    /// the file the golden trace is stored in
+    pub path: PathBuf,
+    /// the last view whose messages are part of the trace
+    pub last_view: u64,
+}
+
+/// The name of a message's kind, e.g. `Consensus::General::Proposal2`
+fn kind_name(kind: &impl Debug) -> String {
+    format!("{kind:?}")
+        .split('(')
+        .take(3)
+        .take_while(|name| name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Records the messages between test nodes
+#[derive(Clone, Debug, Default)]
+pub struct TraceRecorder {
+    /// the recorded messages of each view, in the order they were sent
+    views: Arc<Mutex<BTreeMap<u64, Vec<String>>>>,
+}
+
+impl TraceRecorder {
+    /// A hook recording the messages of views up to `last_view` between the first `num_nodes`
+    /// nodes
+    #[must_use]
+    pub fn hook<TYPES: NodeType, V: Versions>(
+        &self,
+        num_nodes: usize,
+        last_view: u64,
+    ) -> MessageHook<TYPES::SignatureKey> {
+        let views = Arc::clone(&self.views);
+        message_hook::<TYPES, V>(
+            num_nodes,
+            move |sender, recipient, message: &Message<TYPES>| {
+                let view = *message.view_number();
+                if view <= last_view {
+                    views.lock().unwrap().entry(view).or_default().push(format!(
+                        "{view} {sender} -> {recipient} {}",
+                        kind_name(&message.kind)
+                    ));
+                }
+                Interception::Deliver
+            },
+        )
+    }
+
+    /// The recorded trace.
+    ///
+    /// The messages of each view are sorted, since the order in which a broadcast reaches its
+    /// recipients is not stable even in a deterministic test.
+    #[must_use]
+    pub fn trace(&self) -> String {
+        self.views
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|messages| {
+                let mut messages = messages.clone();
+                messages.sort();
+                messages
+            })
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    /// Compare the recorded trace with the golden trace of `description`, recording it instead if
+    /// [`UPDATE_GOLDEN_ENV_VAR`] is set.
+    ///
+    /// # Errors
+    /// if the traces differ, naming the first line at which they do, or the golden trace does not
+    /// exist or cannot be read or written
+    pub fn check(&self, description: &GoldenTraceDescription) -> Result<(), String> {
+        check_golden(&description.path, &self.trace())
+    }
+}
@@ -90,17 +90,42 @@ pub async fn build_system_handle_from_launcher<
     Sender<Arc<HotShotEvent<TYPES>>>,
     Receiver<Arc<HotShotEvent<TYPES>>>,
 ) {
-    let network = (launcher.resource_generator.channel_generator)(node_id).await;
     let storage = (launcher.resource_generator.storage)(node_id);
-    let marketplace_config = (launcher.resource_generator.marketplace_config)(node_id);
-    let config = launcher.resource_generator.config.clone();
-
     let initializer = HotShotInitializer::<TYPES>::from_genesis::<V>(TestInstanceState::new(
         launcher.metadata.async_delay_config.clone(),
     ))
     .await
     .unwrap();
 
+    build_system_handle_with_initializer(node_id, launcher, storage, initializer).await
+}
+
+/// create the [`SystemContextHandle`] from a node id and `TestLauncher`, starting from
+/// `initializer` with `storage`, e.g. to restart a node from what it persisted
+/// # Panics
+/// if cannot create a [`SystemContext`]
+pub async fn build_system_handle_with_initializer<
+    TYPES: NodeType<InstanceState = TestInstanceState>,
+    I: NodeImplementation<
+            TYPES,
+            Storage = TestStorage<TYPES>,
+            AuctionResultsProvider = TestAuctionResultsProvider<TYPES>,
+        > + TestableNodeImplementation<TYPES>,
+    V: Versions,
+>(
+    node_id: u64,
+    launcher: &TestLauncher<TYPES, I, V>,
+    storage: TestStorage<TYPES>,
+    initializer: HotShotInitializer<TYPES>,
+) -> (
+    SystemContextHandle<TYPES, I, V>,
+    Sender<Arc<HotShotEvent<TYPES>>>,
+    Receiver<Arc<HotShotEvent<TYPES>>>,
+) {
+    let network = (launcher.resource_generator.channel_generator)(node_id).await;
+    let marketplace_config = (launcher.resource_generator.marketplace_config)(node_id);
+    let config = launcher.resource_generator.config.clone();
+
     // See whether or not we should be DA
     let is_da = node_id < config.da_staked_committee_size as u64;
 
@@ -247,7 +272,8 @@ pub async fn build_assembled_sig<
         &real_qc_pp,
         signers.as_bitslice(),
         &sig_lists[..],
-    );
+    )
+    .expect("Failed to assemble the certificate");
 
     real_qc_sig
 }
@@ -0,0 +1,320 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Safety and liveness invariants, checked after every round of a test.
+//!
+//! A test lists the [`Invariant`]s which apply to it in
+//! [`TestDescription::invariants`](crate::test_builder::TestDescription::invariants). Each decide
+//! event of an honest node is checked against them as it arrives, and the test fails with every
+//! [`InvariantViolation`], each naming the invariant, node and view at fault.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use committable::{Commitment, Committable};
+use hotshot_types::{
+    data::Leaf2,
+    event::{Event, EventType},
+    traits::{
+        election::Membership,
+        node_implementation::NodeType,
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    vid::VidCommitment,
+};
+use tokio::time::Instant;
+
+use crate::test_task::{TestResult, TestTaskState};
+
+/// An invariant which must hold throughout a test
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Invariant {
+    /// no two nodes decide different leaves for the same view
+    NoConflictingDecides,
+    /// every node decides views in increasing order, and never decides a view twice
+    MonotonicDecides,
+    /// all honest nodes decide the same block in each view
+    AgreeOnBlocks,
+    /// the network decides at least this many views per second, over the whole test
+    MinThroughput(f64),
+    /// the thresholds of the membership, in every epoch in which a view is decided, are computed
+    /// over the stake of the nodes rather than their number
+    ThresholdsOverStake,
+}
+
+/// A violation of an [`Invariant`]
+#[derive(Clone, Debug)]
+pub struct InvariantViolation<TYPES: NodeType> {
+    /// the invariant which was violated
+    pub invariant: Invariant,
+    /// the node at fault, if the violation is specific to one node
+    pub node: Option<usize>,
+    /// the view in which the violation happened, if any
+    pub view: Option<TYPES::View>,
+    /// what went wrong
+    pub message: String,
+}
+
+impl<TYPES: NodeType> fmt::Display for InvariantViolation<TYPES> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} violated", self.invariant)?;
+        if let Some(node) = self.node {
+            write!(f, " by node {node}")?;
+        }
+        if let Some(view) = self.view {
+            write!(f, " in view {}", *view)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The first leaf decided in a view
+struct DecidedLeaf<TYPES: NodeType> {
+    /// the node which decided it first
+    node: usize,
+    /// commitment to the leaf
+    leaf: Commitment<Leaf2<TYPES>>,
+    /// commitment to the block of the leaf
+    payload: VidCommitment,
+}
+
+/// Task checking the invariants of a test
+pub struct InvariantTask<TYPES: NodeType> {
+    /// the invariants to check
+    pub invariants: Vec<Invariant>,
+    /// byzantine nodes, whose decides are not checked
+    pub byzantine_nodes: HashSet<usize>,
+    /// the membership of the test, as configured
+    membership: TYPES::Membership,
+    /// the epochs whose thresholds were checked
+    checked_epochs: HashSet<TYPES::Epoch>,
+    /// the first leaf decided in each view
+    decided: BTreeMap<TYPES::View, DecidedLeaf<TYPES>>,
+    /// the latest view decided by each node
+    latest_decided: HashMap<usize, TYPES::View>,
+    /// the violations found so far
+    violations: Vec<InvariantViolation<TYPES>>,
+    /// when the task started
+    start: Instant,
+}
+
+impl<TYPES: NodeType> InvariantTask<TYPES> {
+    /// Create a task checking `invariants` on the decides of every node but `byzantine_nodes`,
+    /// with the membership `membership`
+    #[must_use]
+    pub fn new(
+        invariants: Vec<Invariant>,
+        byzantine_nodes: HashSet<usize>,
+        membership: TYPES::Membership,
+    ) -> Self {
+        Self {
+            invariants,
+            byzantine_nodes,
+            membership,
+            checked_epochs: HashSet::new(),
+            decided: BTreeMap::new(),
+            latest_decided: HashMap::new(),
+            violations: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Record a violation of `invariant`
+    fn violation(
+        &mut self,
+        invariant: Invariant,
+        node: Option<usize>,
+        view: Option<TYPES::View>,
+        message: String,
+    ) {
+        tracing::error!("Invariant {invariant:?} violated: {message}");
+        self.violations.push(InvariantViolation {
+            invariant,
+            node,
+            view,
+            message,
+        });
+    }
+
+    /// Check that the thresholds of `epoch` are computed over stake, the first time a view of the
+    /// epoch is decided
+    fn check_thresholds(&mut self, epoch: TYPES::Epoch) {
+        if !self.checked_epochs.insert(epoch) {
+            return;
+        }
+
+        let total = total_stake::<TYPES>(&self.membership.stake_table(epoch));
+        let da_total = total_stake::<TYPES>(&self.membership.da_stake_table(epoch));
+        let two_thirds = total.saturating_mul(2) / 3 + 1;
+        let expected = [
+            (
+                "success",
+                self.membership.success_threshold(epoch),
+                two_thirds,
+            ),
+            (
+                "failure",
+                self.membership.failure_threshold(epoch),
+                total / 3 + 1,
+            ),
+            (
+                "upgrade",
+                self.membership.upgrade_threshold(epoch),
+                (total.saturating_mul(9) / 10).max(two_thirds),
+            ),
+            (
+                "DA success",
+                self.membership.da_success_threshold(epoch),
+                da_total.saturating_mul(2) / 3 + 1,
+            ),
+        ];
+        for (name, threshold, expected) in expected {
+            if u128::from(threshold.get()) != expected {
+                self.violation(
+                    Invariant::ThresholdsOverStake,
+                    None,
+                    None,
+                    format!(
+                        "the {name} threshold of epoch {} is {threshold}, expected {expected} \
+                         from the stake table",
+                        *epoch
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Check the leaves decided by `node`, in increasing view order
+    fn check_decide(&mut self, node: usize, leaves: &[Leaf2<TYPES>]) {
+        for leaf in leaves {
+            let view = leaf.view_number();
+
+            if self.invariants.contains(&Invariant::ThresholdsOverStake) {
+                self.check_thresholds(leaf.epoch());
+            }
+
+            if self.invariants.contains(&Invariant::MonotonicDecides) {
+                if let Some(latest) = self.latest_decided.get(&node).copied() {
+                    if view <= latest {
+                        self.violation(
+                            Invariant::MonotonicDecides,
+                            Some(node),
+                            Some(view),
+                            format!("decided view {} after view {}", *view, *latest),
+                        );
+                    }
+                }
+            }
+            let latest = self.latest_decided.entry(node).or_insert(view);
+            *latest = (*latest).max(view);
+
+            let commitment = leaf.commit();
+            let payload = leaf.payload_commitment();
+            let Some(first) = self.decided.get(&view) else {
+                self.decided.insert(
+                    view,
+                    DecidedLeaf {
+                        node,
+                        leaf: commitment,
+                        payload,
+                    },
+                );
+                continue;
+            };
+            let first_node = first.node;
+            let conflicting_leaf = first.leaf != commitment;
+            let conflicting_block = first.payload != payload;
+
+            if conflicting_leaf && self.invariants.contains(&Invariant::NoConflictingDecides) {
+                self.violation(
+                    Invariant::NoConflictingDecides,
+                    Some(node),
+                    Some(view),
+                    format!("decided a different leaf than node {first_node}"),
+                );
+            }
+            if conflicting_block && self.invariants.contains(&Invariant::AgreeOnBlocks) {
+                self.violation(
+                    Invariant::AgreeOnBlocks,
+                    Some(node),
+                    Some(view),
+                    format!("decided a different block than node {first_node}"),
+                );
+            }
+        }
+    }
+}
+
+/// The total stake of `stake_table`, saturating on overflow
+fn total_stake<TYPES: NodeType>(
+    stake_table: &[<TYPES::SignatureKey as SignatureKey>::StakeTableEntry],
+) -> u128 {
+    stake_table
+        .iter()
+        .map(|entry| u128::try_from(entry.stake()).unwrap_or(u128::MAX))
+        .fold(0, u128::saturating_add)
+}
+
+#[async_trait]
+impl<TYPES: NodeType> TestTaskState for InvariantTask<TYPES> {
+    type Event = Event<TYPES>;
+
+    async fn handle_event(&mut self, (message, id): (Self::Event, usize)) -> Result<()> {
+        if self.byzantine_nodes.contains(&id) {
+            return Ok(());
+        }
+
+        if let EventType::Decide { leaf_chain, .. } = message.event {
+            // the leaf chain is ordered from the newest leaf to the oldest
+            let leaves: Vec<_> = leaf_chain
+                .iter()
+                .rev()
+                .map(|leaf_info| leaf_info.leaf.clone())
+                .collect();
+            self.check_decide(id, &leaves);
+        }
+
+        Ok(())
+    }
+
+    async fn check(&self) -> TestResult {
+        let mut violations = self.violations.clone();
+
+        for invariant in &self.invariants {
+            if let Invariant::MinThroughput(min_views_per_sec) = *invariant {
+                #[allow(clippy::cast_precision_loss)]
+                let views_per_sec =
+                    self.decided.len() as f64 / self.start.elapsed().as_secs_f64().max(1.0);
+                if views_per_sec < min_views_per_sec {
+                    violations.push(InvariantViolation {
+                        invariant: *invariant,
+                        node: None,
+                        view: None,
+                        message: format!(
+                            "decided {views_per_sec:.2} views per second, expected at least \
+                             {min_views_per_sec}"
+                        ),
+                    });
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            TestResult::Pass
+        } else {
+            TestResult::Fail(Box::new(
+                violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            ))
+        }
+    }
+}
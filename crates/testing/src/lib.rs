@@ -24,6 +24,12 @@ pub mod overall_safety_task;
 /// task that checks leaves received across all nodes from decide events for consistency
 pub mod consistency_task;
 
+/// task that checks the safety and liveness invariants declared by a test
+pub mod invariant_task;
+
+/// task that checks the resources used by a test against its budget
+pub mod resource_task;
+
 /// task that's submitting transactions to the stream
 pub mod txn_task;
 
@@ -36,6 +42,9 @@ pub mod spinning_task;
 /// the `TestTask` struct and associated trait/functions
 pub mod test_task;
 
+/// machine-readable test results
+pub mod test_report;
+
 /// task for checking if view sync got activated
 pub mod view_sync_task;
 
@@ -53,3 +62,12 @@ pub mod view_generator;
 
 /// byzantine framework for tests
 pub mod byzantine;
+
+/// deterministic simulation runs
+pub mod simulation;
+
+/// hooks to intercept messages between test nodes
+pub mod message_hook;
+
+/// golden traces of the messages between test nodes
+pub mod golden_trace;
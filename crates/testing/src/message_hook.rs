@@ -0,0 +1,107 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::HashMap, fmt::Write, path::PathBuf, sync::Arc};
+
+use hotshot_types::{
+    message::Message,
+    traits::{
+        network::{Interception, MessageHook},
+        node_implementation::{NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+};
+use sha2::{Digest, Sha256};
+use vbs::{version::StaticVersionType, BinarySerializer, Serializer};
+
+/// Build a [`MessageHook`] from a hook on decoded messages between test nodes.
+///
+/// `hook` is called with the index of the sender, the index of the recipient, and the message.
+/// Messages which cannot be decoded, or which involve nodes outside of the first `num_nodes`, are
+/// delivered as usual. A mutated message is encoded with the version of the original one.
+pub fn message_hook<TYPES: NodeType, V: Versions>(
+    num_nodes: usize,
+    hook: impl Fn(usize, usize, &Message<TYPES>) -> Interception<Message<TYPES>> + Send + Sync + 'static,
+) -> MessageHook<TYPES::SignatureKey> {
+    let indices: HashMap<TYPES::SignatureKey, usize> = (0..num_nodes)
+        .map(|idx| {
+            let key = TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], idx as u64).0;
+            (key, idx)
+        })
+        .collect();
+
+    Arc::new(move |sender, recipient, bytes| {
+        let (Some(sender), Some(recipient)) = (indices.get(sender), indices.get(recipient)) else {
+            return Interception::Deliver;
+        };
+        let Ok(version) = Message::<TYPES>::protocol_version(bytes) else {
+            return Interception::Deliver;
+        };
+        let message: Message<TYPES> = if version == V::Base::VERSION {
+            match Serializer::<V::Base>::deserialize(bytes) {
+                Ok(message) => message,
+                Err(_) => return Interception::Deliver,
+            }
+        } else if version == V::Upgrade::VERSION {
+            match Serializer::<V::Upgrade>::deserialize(bytes) {
+                Ok(message) => message,
+                Err(_) => return Interception::Deliver,
+            }
+        } else {
+            return Interception::Deliver;
+        };
+
+        match hook(*sender, *recipient, &message) {
+            Interception::Deliver => Interception::Deliver,
+            Interception::Drop => Interception::Drop,
+            Interception::Delay(delay) => Interception::Delay(delay),
+            Interception::Duplicate(count) => Interception::Duplicate(count),
+            Interception::Mutate(mutated) => {
+                let encoded = if version == V::Base::VERSION {
+                    Serializer::<V::Base>::serialize(&mutated)
+                } else {
+                    Serializer::<V::Upgrade>::serialize(&mutated)
+                };
+                match encoded {
+                    Ok(encoded) => Interception::Mutate(encoded.into()),
+                    Err(e) => {
+                        tracing::error!("Failed to encode a mutated message: {e}");
+                        Interception::Deliver
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Build a [`MessageHook`] which saves every distinct message to `dir`, named by its SHA-256
+/// hash, and delivers it as usual.
+///
+/// The saved messages can be used as the corpus of the message decoding fuzz tests.
+pub fn record_corpus<K>(dir: PathBuf) -> MessageHook<K> {
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        tracing::error!(
+            "Failed to create the corpus directory {}: {e}",
+            dir.display()
+        );
+    }
+
+    Arc::new(move |_sender, _recipient, bytes| {
+        let name = Sha256::digest(bytes)
+            .iter()
+            .fold(String::new(), |mut name, byte| {
+                let _ = write!(name, "{byte:02x}");
+                name
+            });
+        let path = dir.join(name);
+        if !path.exists() {
+            if let Err(e) = std::fs::write(&path, bytes) {
+                tracing::error!("Failed to save a message to {}: {e}", path.display());
+            }
+        }
+        Interception::Deliver
+    })
+}
@@ -0,0 +1,166 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Resource budgets for long-running tests.
+//!
+//! A test with a [`ResourceBudget`] samples the resident memory, open file descriptors and live
+//! tokio tasks of the process while it runs, and fails if any of them ever exceeds its ceiling.
+//! The samples cover the whole process, so budgeted tests should run on their own, e.g. with
+//! `--test-threads=1`. Memory and file descriptors are only sampled on Linux.
+
+use std::{marker::PhantomData, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use hotshot_types::{event::Event, traits::node_implementation::NodeType};
+use tokio::{runtime::Handle, time::Instant};
+
+use crate::test_task::{TestResult, TestTaskState};
+
+/// Ceilings on the resources used by a test
+#[derive(Clone, Debug)]
+pub struct ResourceBudget {
+    /// the most resident memory the process may use, in bytes
+    pub max_rss_bytes: Option<u64>,
+    /// the most file descriptors the process may have open
+    pub max_open_fds: Option<usize>,
+    /// the most tokio tasks which may be alive at once
+    pub max_tasks: Option<usize>,
+    /// the time between two samples
+    pub sample_interval: Duration,
+}
+
+impl Default for ResourceBudget {
+    fn default() -> Self {
+        Self {
+            max_rss_bytes: None,
+            max_open_fds: None,
+            max_tasks: None,
+            sample_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The resources used by the process at one point in time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResourceSample {
+    /// resident memory, in bytes
+    pub rss_bytes: Option<u64>,
+    /// open file descriptors
+    pub open_fds: Option<usize>,
+    /// live tokio tasks
+    pub tasks: usize,
+}
+
+impl ResourceSample {
+    /// Sample the resources used by the process now
+    #[must_use]
+    pub fn now() -> Self {
+        Self {
+            rss_bytes: rss_bytes(),
+            open_fds: std::fs::read_dir("/proc/self/fd")
+                .ok()
+                .map(|entries| entries.count()),
+            tasks: Handle::current().metrics().num_alive_tasks(),
+        }
+    }
+}
+
+/// The resident memory of the process, read from `/proc/self/status`
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Task sampling the resources used by a test and checking them against its budget
+pub struct ResourceTask<TYPES: NodeType> {
+    /// the ceilings to check
+    pub budget: ResourceBudget,
+    /// the largest usage of each resource seen so far
+    peak: ResourceSample,
+    /// when the resources were last sampled
+    last_sample: Option<Instant>,
+    /// phantom data for the node types
+    _pd: PhantomData<TYPES>,
+}
+
+impl<TYPES: NodeType> ResourceTask<TYPES> {
+    /// Create a task checking `budget`
+    #[must_use]
+    pub fn new(budget: ResourceBudget) -> Self {
+        Self {
+            budget,
+            peak: ResourceSample::default(),
+            last_sample: None,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Sample the resources, if the sample interval has passed, and record the peaks
+    fn sample(&mut self) {
+        if self
+            .last_sample
+            .is_some_and(|last| last.elapsed() < self.budget.sample_interval)
+        {
+            return;
+        }
+        self.last_sample = Some(Instant::now());
+
+        let sample = ResourceSample::now();
+        self.peak.rss_bytes = self.peak.rss_bytes.max(sample.rss_bytes);
+        self.peak.open_fds = self.peak.open_fds.max(sample.open_fds);
+        self.peak.tasks = self.peak.tasks.max(sample.tasks);
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> TestTaskState for ResourceTask<TYPES> {
+    type Event = Event<TYPES>;
+
+    async fn handle_event(&mut self, _: (Self::Event, usize)) -> Result<()> {
+        self.sample();
+        Ok(())
+    }
+
+    async fn check(&self) -> TestResult {
+        let ResourceSample {
+            rss_bytes,
+            open_fds,
+            tasks,
+        } = self.peak;
+        let mut exceeded = vec![];
+
+        if let (Some(max), Some(peak)) = (self.budget.max_rss_bytes, rss_bytes) {
+            if peak > max {
+                exceeded.push(format!(
+                    "Resident memory peaked at {peak} bytes, over the budget of {max}"
+                ));
+            }
+        }
+        if let (Some(max), Some(peak)) = (self.budget.max_open_fds, open_fds) {
+            if peak > max {
+                exceeded.push(format!(
+                    "{peak} file descriptors were open at once, over the budget of {max}"
+                ));
+            }
+        }
+        if let Some(max) = self.budget.max_tasks {
+            if tasks > max {
+                exceeded.push(format!(
+                    "{tasks} tasks were alive at once, over the budget of {max}"
+                ));
+            }
+        }
+
+        if exceeded.is_empty() {
+            TestResult::Pass
+        } else {
+            TestResult::Fail(Box::new(exceeded))
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Deterministic simulation runs.
+//!
+//! A test generated with `Deterministic: true` by `cross_tests!` runs on a single-threaded
+//! runtime with a paused clock, which tokio advances to the next pending timer whenever every
+//! task is idle. All tasks are polled on one thread in a fixed order, and the randomness of the
+//! test environment is drawn from [`hotshot_types::deterministic::with_rng`] seeded by
+//! [`seed_simulation`], so a failing run can be replayed by setting [`SEED_ENV_VAR`] to the seed
+//! it logged.
+//!
+//! Work moved to the blocking thread pool, such as VID dispersal, does not advance virtual time
+//! while it runs, but any randomness it uses is not seeded.
+
+use hotshot_types::deterministic::seed_thread_rng;
+
+/// Environment variable with the seed to replay a deterministic run with
+pub const SEED_ENV_VAR: &str = "HOTSHOT_TEST_SEED";
+
+/// Seed the randomness of a deterministic run on the current thread, and return the seed.
+///
+/// The seed is read from [`SEED_ENV_VAR`] if it is set, or chosen at random otherwise.
+///
+/// # Panics
+/// If [`SEED_ENV_VAR`] is set but is not a valid `u64`.
+pub fn seed_simulation() -> u64 {
+    let seed = match std::env::var(SEED_ENV_VAR) {
+        Ok(seed) => seed
+            .parse()
+            .unwrap_or_else(|_| panic!("{SEED_ENV_VAR} must be a u64, got {seed}")),
+        Err(_) => rand::random(),
+    };
+    seed_thread_rng(seed);
+    tracing::error!(
+        "Running deterministic test with seed {seed}, set {SEED_ENV_VAR}={seed} to replay it"
+    );
+
+    seed
+}
@@ -5,7 +5,7 @@
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -25,6 +25,7 @@ use hotshot_example_types::{
     testable_delay::DelayConfig,
 };
 use hotshot_types::{
+    consensus::Consensus,
     constants::EVENT_CHANNEL_SIZE,
     data::Leaf2,
     event::Event,
@@ -32,10 +33,12 @@ use hotshot_types::{
     traits::{
         network::{AsyncGenerator, ConnectedNetwork},
         node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        signature_key::SignatureKey,
     },
     vote::HasViewNumber,
     ValidatorConfig,
 };
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     test_launcher::Network,
@@ -46,6 +49,10 @@ use crate::{
 /// convenience type for state and block
 pub type StateAndBlock<S, B> = (Vec<S>, Vec<B>);
 
+/// Number of views the network needs to decide past the restart of a node before the node is
+/// expected to have caught up
+const RESTART_CATCHUP_VIEWS: u64 = 5;
+
 /// Spinning task state
 pub struct SpinningTask<
     TYPES: NodeType,
@@ -73,6 +80,18 @@ pub struct SpinningTask<
     pub(crate) restart_contexts: HashMap<usize, RestartContext<TYPES, N, I, V>>,
     /// Generate network channel for restart nodes
     pub(crate) channel_generator: AsyncGenerator<Network<TYPES, I>>,
+    /// Network partitions to apply, view -> groups of node indices, or `None` to heal
+    pub(crate) partitions: BTreeMap<TYPES::View, Option<Vec<Vec<usize>>>>,
+    /// The latest view at which a partition is healed
+    pub(crate) heal_view: Option<TYPES::View>,
+    /// Restarted nodes, and the view in which they were started back up
+    pub(crate) restarted_nodes: HashMap<usize, TYPES::View>,
+    /// Restarted nodes which decided a leaf after being started back up
+    pub(crate) caught_up_nodes: HashSet<usize>,
+    /// The view in which node churn ends, if there is any
+    pub(crate) churn_end_view: Option<TYPES::View>,
+    /// The most views of state each node may retain in memory at the end of the test
+    pub(crate) max_retained_views: Option<usize>,
 }
 
 #[async_trait]
@@ -97,7 +116,7 @@ where
 {
     type Event = Event<TYPES>;
 
-    async fn handle_event(&mut self, (message, _id): (Self::Event, usize)) -> Result<()> {
+    async fn handle_event(&mut self, (message, id): (Self::Event, usize)) -> Result<()> {
         let Event { view_number, event } = message;
 
         if let EventType::Decide {
@@ -107,6 +126,13 @@ where
         } = event
         {
             let leaf = leaf_chain.first().unwrap().leaf.clone();
+            if self
+                .restarted_nodes
+                .get(&id)
+                .is_some_and(|restart_view| leaf.view_number() > *restart_view)
+            {
+                self.caught_up_nodes.insert(id);
+            }
             if leaf.view_number() > self.last_decided_leaf.view_number() {
                 self.last_decided_leaf = leaf;
             }
@@ -160,10 +186,13 @@ where
                                             TYPES::View::genesis(),
                                             TYPES::Epoch::genesis(),
                                             TYPES::View::genesis(),
+                                            TYPES::View::genesis(),
+                                            Vec::new(),
                                             BTreeMap::new(),
                                             self.high_qc.clone(),
                                             self.next_epoch_high_qc.clone(),
                                             None,
+                                            None,
                                             Vec::new(),
                                             BTreeMap::new(),
                                         );
@@ -244,6 +273,8 @@ where
                                     read_storage.last_actioned_view().await,
                                     read_storage.last_actioned_epoch().await,
                                     read_storage.last_actioned_view().await,
+                                    read_storage.locked_view().await,
+                                    read_storage.vote_intents_cloned().await,
                                     read_storage.proposals_cloned().await,
                                     read_storage.high_qc_cloned().await.unwrap_or(
                                         QuorumCertificate2::genesis::<V>(
@@ -254,6 +285,7 @@ where
                                     ),
                                     read_storage.next_epoch_high_qc_cloned().await,
                                     read_storage.decided_upgrade_certificate().await,
+                                    read_storage.checkpoint_certificate_cloned().await,
                                     Vec::new(),
                                     BTreeMap::new(),
                                 );
@@ -286,6 +318,8 @@ where
                                 if delay_views == 0 {
                                     new_nodes.push((context, idx));
                                     new_networks.push(generated_network.clone());
+                                    self.restarted_nodes.insert(idx, view_number);
+                                    self.caught_up_nodes.remove(&idx);
                                 } else {
                                     let up_view = view_number + delay_views;
                                     let change = ChangeNode {
@@ -305,6 +339,8 @@ where
                             if let Some(ctx) = self.restart_contexts.remove(&idx) {
                                 new_nodes.push((ctx.context, idx));
                                 new_networks.push(ctx.network.clone());
+                                self.restarted_nodes.insert(idx, view_number);
+                                self.caught_up_nodes.remove(&idx);
                             }
                         }
                         NodeAction::NetworkUp => {
@@ -357,6 +393,40 @@ where
                 tracing::info!("Nodes all started");
             }
 
+            // partition or heal the network
+            while let Some(entry) = self.partitions.first_entry() {
+                if *entry.key() > view_number {
+                    break;
+                }
+                let groups = entry.remove();
+                if let Some(node) = self.handles.read().await.first() {
+                    match groups {
+                        Some(groups) => {
+                            tracing::info!("Partitioning the network into {:?}", groups);
+                            let groups = groups
+                                .into_iter()
+                                .map(|group| {
+                                    group
+                                        .into_iter()
+                                        .map(|idx| {
+                                            TYPES::SignatureKey::generated_from_seed_indexed(
+                                                [0u8; 32], idx as u64,
+                                            )
+                                            .0
+                                        })
+                                        .collect()
+                                })
+                                .collect();
+                            I::partition_network(&node.network, groups);
+                        }
+                        None => {
+                            tracing::info!("Healing the network partition");
+                            I::heal_network_partition(&node.network);
+                        }
+                    }
+                }
+            }
+
             // update our latest view
             self.latest_view = Some(view_number);
         }
@@ -365,10 +435,66 @@ where
     }
 
     async fn check(&self) -> TestResult {
+        if let Some(heal_view) = self.heal_view {
+            // consensus must make progress again once the partition is healed
+            if self.last_decided_leaf.view_number() <= heal_view {
+                return TestResult::Fail(Box::new(format!(
+                    "No decide after the network partition was healed in view {heal_view:?}"
+                )));
+            }
+        }
+
+        if let Some(churn_end_view) = self.churn_end_view {
+            // consensus must survive the churn
+            if self.last_decided_leaf.view_number() <= churn_end_view {
+                return TestResult::Fail(Box::new(format!(
+                    "No decide after the node churn ended in view {churn_end_view:?}"
+                )));
+            }
+        }
+
+        // state of decided views must be garbage collected, however much the nodes churned
+        if let Some(max_retained_views) = self.max_retained_views {
+            for node in self.handles.read().await.iter() {
+                let consensus = node.handle.consensus();
+                let retained = retained_views(&*consensus.read().await);
+                if retained > max_retained_views {
+                    return TestResult::Fail(Box::new(format!(
+                        "Node {} retains {retained} views of state, expected at most \
+                         {max_retained_views}",
+                        node.node_id
+                    )));
+                }
+            }
+        }
+
+        // a restarted node must catch up from its storage and decide again, once the rest of
+        // the network has had time to decide past its restart
+        let last_decided_view = self.last_decided_leaf.view_number();
+        for (idx, restart_view) in &self.restarted_nodes {
+            if !self.caught_up_nodes.contains(idx)
+                && last_decided_view > *restart_view + RESTART_CATCHUP_VIEWS
+            {
+                return TestResult::Fail(Box::new(format!(
+                    "Node {idx} did not decide after restarting in view {restart_view:?}"
+                )));
+            }
+        }
+
         TestResult::Pass
     }
 }
 
+/// The most views of any kind of state retained by `consensus`
+fn retained_views<TYPES: NodeType>(consensus: &Consensus<TYPES>) -> usize {
+    consensus
+        .validated_state_map()
+        .len()
+        .max(consensus.saved_leaves().len())
+        .max(consensus.saved_payloads().len())
+        .max(consensus.saved_da_certs().len())
+}
+
 #[derive(Clone)]
 pub(crate) struct RestartContext<
     TYPES: NodeType,
@@ -407,6 +533,94 @@ pub struct ChangeNode {
     pub updown: NodeAction,
 }
 
+/// A network partition, during which nodes can only reach the nodes in their own group.
+///
+/// Nodes which are not in any group form one more group. For views to keep advancing during the
+/// partition, at least one group must be able to form a quorum on its own.
+#[derive(Clone, Debug)]
+pub struct PartitionDescription {
+    /// the indices of the nodes in each group
+    pub groups: Vec<Vec<usize>>,
+    /// the view in which the network is partitioned
+    pub start_view: u64,
+    /// the view in which the partition is healed
+    pub heal_view: u64,
+}
+
+/// Node churn over a long stretch of views.
+///
+/// Every `interval` views one of `nodes` is taken offline and restarted from its storage
+/// `offline_views` later, with at most `max_offline` of them offline at once. Nodes which do not
+/// start with the test join the network one at a time, spread evenly over the churn. For the
+/// network to stay live, the offline nodes plus the nodes yet to join must stay within its fault
+/// tolerance.
+#[derive(Clone, Debug)]
+pub struct ChurnDescription {
+    /// the nodes which may be taken offline, all of which must start with the test
+    pub nodes: Vec<usize>,
+    /// the most nodes which may be offline at once
+    pub max_offline: usize,
+    /// the view in which the churn starts
+    pub start_view: u64,
+    /// the view in which the churn ends
+    pub end_view: u64,
+    /// the number of views between taking two nodes offline
+    pub interval: u64,
+    /// the number of views a node stays offline
+    pub offline_views: u64,
+    /// seed for choosing which node to take offline
+    pub seed: u64,
+    /// the most views of state each node may retain in memory at the end of the test, if bounded
+    pub max_retained_views: Option<usize>,
+}
+
+impl ChurnDescription {
+    /// The node changes making up the churn, for a test starting `start_nodes` of `num_nodes`
+    #[must_use]
+    pub fn node_changes(
+        &self,
+        start_nodes: usize,
+        num_nodes: usize,
+    ) -> Vec<(u64, Vec<ChangeNode>)> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut changes: BTreeMap<u64, Vec<ChangeNode>> = BTreeMap::new();
+        // the view in which each offline node comes back up
+        let mut offline: HashMap<usize, u64> = HashMap::new();
+
+        let mut view = self.start_view;
+        while view < self.end_view {
+            offline.retain(|_, up_view| *up_view > view);
+            let online: Vec<usize> = self
+                .nodes
+                .iter()
+                .copied()
+                .filter(|idx| *idx < start_nodes && !offline.contains_key(idx))
+                .collect();
+            if offline.len() < self.max_offline && !online.is_empty() {
+                let idx = online[rng.gen_range(0..online.len())];
+                offline.insert(idx, view + self.offline_views);
+                changes.entry(view).or_default().push(ChangeNode {
+                    idx,
+                    updown: NodeAction::RestartDown(self.offline_views),
+                });
+            }
+            view += self.interval.max(1);
+        }
+
+        let joining = num_nodes.saturating_sub(start_nodes) as u64;
+        for (i, idx) in (start_nodes..num_nodes).enumerate() {
+            let view = self.start_view
+                + self.end_view.saturating_sub(self.start_view) * (i as u64 + 1) / (joining + 1);
+            changes.entry(view).or_default().push(ChangeNode {
+                idx,
+                updown: NodeAction::Up,
+            });
+        }
+
+        changes.into_iter().collect()
+    }
+}
+
 /// description of the spinning task
 /// (used to build a spinning task)
 #[derive(Clone, Debug)]
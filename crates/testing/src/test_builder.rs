@@ -20,7 +20,12 @@ use hotshot_example_types::{
 };
 use hotshot_types::{
     consensus::ConsensusMetricsValue,
-    traits::node_implementation::{NodeType, Versions},
+    memory_budget::MemoryBudgetConfig,
+    traits::{
+        network::MessageHook,
+        node_implementation::{NodeType, Versions},
+        signature_key::SignatureKey,
+    },
     HotShotConfig, ValidatorConfig,
 };
 use tide_disco::Url;
@@ -32,7 +37,10 @@ use super::{
     txn_task::TxnTaskDescription,
 };
 use crate::{
-    spinning_task::SpinningTaskDescription,
+    golden_trace::GoldenTraceDescription,
+    invariant_task::Invariant,
+    resource_task::ResourceBudget,
+    spinning_task::{ChurnDescription, PartitionDescription, SpinningTaskDescription},
     test_launcher::{Network, ResourceGenerators, TestLauncher},
     test_task::TestTaskStateSeed,
     view_sync_task::ViewSyncTaskDescription,
@@ -55,11 +63,48 @@ pub struct TimingData {
     pub view_sync_timeout: Duration,
 }
 
+/// Settings of a single node, overriding those of the test.
+///
+/// All nodes share the network implementation of the test. To make individual nodes slower to
+/// reach, set their [`network_links`](TestDescription::network_links).
+#[derive(Clone, Debug, Default)]
+pub struct NodeOverride {
+    /// the stake of the node, instead of 1
+    pub stake: Option<u64>,
+    /// the timing of the node, instead of the test's `timing_data`; the secondary network delay
+    /// is shared by all nodes
+    pub timing_data: Option<TimingData>,
+    /// delays injected into the node's storage, instead of the test's `async_delay_config`
+    pub storage_delay_config: Option<DelayConfig>,
+    /// the probability that each write to the node's storage fails
+    pub storage_write_failure_probability: Option<f64>,
+}
+
+/// How the stake of a test is spread over its nodes.
+///
+/// The stake of a node is the number of votes its vote counts for, so a node with a stake of 5
+/// weighs as much in a certificate as five nodes with a stake of 1. Tests with skewed stake should
+/// check [`Invariant::ThresholdsOverStake`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StakeDistribution {
+    /// every node has a stake of 1
+    Uniform,
+    /// node 0 is a whale with stake `whale_stake`, every other node has a stake of 1
+    Whale {
+        /// the stake of node 0
+        whale_stake: u64,
+    },
+    /// node `i` has stake `stakes[i]`, nodes past the end have a stake of 1
+    Weighted(Vec<u64>),
+}
+
 /// metadata describing a test
 #[derive(Clone)]
 pub struct TestDescription<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> {
     /// Total number of staked nodes in the test
     pub num_nodes_with_stake: usize,
+    /// Number of watchers following the staked nodes, which get the indices after theirs
+    pub num_watchers: usize,
     /// nodes available at start
     pub start_nodes: usize,
     /// Whether to skip initializing nodes that will start late, which will catch up later with
@@ -73,6 +118,10 @@ pub struct TestDescription<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Ver
     pub overall_safety_properties: OverallSafetyPropertiesDescription<TYPES>,
     /// spinning properties
     pub spinning_properties: SpinningTaskDescription,
+    /// network partitions to apply and heal during the test
+    pub partitions: Vec<PartitionDescription>,
+    /// nodes going offline and coming online throughout the test
+    pub churn: Option<ChurnDescription>,
     /// txns timing
     pub txn_description: TxnTaskDescription,
     /// completion task
@@ -81,6 +130,12 @@ pub struct TestDescription<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Ver
     pub timing_data: TimingData,
     /// unrelabile networking metadata
     pub unreliable_network: Option<Box<dyn NetworkReliability>>,
+    /// reliability of individual directed links, by sender and recipient index, overriding
+    /// `unreliable_network` (memory network only)
+    pub network_links: HashMap<(usize, usize), Box<dyn NetworkReliability>>,
+    /// hooks deciding what happens to each message (memory network only), see
+    /// [`message_hook`](crate::message_hook::message_hook)
+    pub message_hooks: Vec<MessageHook<TYPES::SignatureKey>>,
     /// view sync check task
     pub view_sync_properties: ViewSyncTaskDescription,
     /// description of builders to run
@@ -101,6 +156,14 @@ pub struct TestDescription<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Ver
     pub validate_transactions: TransactionValidator,
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+    /// invariants checked after every round
+    pub invariants: Vec<Invariant>,
+    /// settings of individual nodes, by index, overriding those of the test
+    pub node_overrides: HashMap<usize, NodeOverride>,
+    /// the golden trace to compare the messages between nodes with (memory network only)
+    pub golden_trace: Option<GoldenTraceDescription>,
+    /// ceilings on the memory, file descriptors and tasks the test may use
+    pub resource_budget: Option<ResourceBudget>,
 }
 
 pub fn nonempty_block_threshold(threshold: (u64, u64)) -> TransactionValidator {
@@ -280,6 +343,16 @@ pub struct FakeSolverApiDescription {
     pub error_pct: f32,
 }
 
+impl TimingData {
+    /// Apply the timing to a node's `config`
+    pub fn apply_to<KEY: SignatureKey>(&self, config: &mut HotShotConfig<KEY>) {
+        config.next_view_timeout = self.next_view_timeout;
+        config.builder_timeout = self.builder_timeout;
+        config.data_request_delay = self.data_request_delay;
+        config.view_sync_timeout = self.view_sync_timeout;
+    }
+}
+
 impl Default for TimingData {
     fn default() -> Self {
         Self {
@@ -293,6 +366,39 @@ impl Default for TimingData {
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TestDescription<TYPES, I, V> {
+    /// Spread the stake of the test according to `distribution`, overriding the stake of every
+    /// node
+    #[must_use]
+    pub fn with_stake_distribution(mut self, distribution: StakeDistribution) -> Self {
+        for idx in 0..self.num_nodes_with_stake {
+            let stake = match &distribution {
+                StakeDistribution::Uniform => 1,
+                StakeDistribution::Whale { whale_stake } if idx == 0 => *whale_stake,
+                StakeDistribution::Whale { .. } => 1,
+                StakeDistribution::Weighted(stakes) => stakes.get(idx).copied().unwrap_or(1),
+            };
+            self.node_overrides.entry(idx).or_default().stake = Some(stake);
+        }
+        self
+    }
+
+    /// The total stake of the nodes of the test
+    #[must_use]
+    pub fn total_stake(&self) -> u64 {
+        (0..self.num_nodes_with_stake)
+            .map(|idx| self.node_stake(idx))
+            .sum()
+    }
+
+    /// The stake of node `idx`
+    #[must_use]
+    pub fn node_stake(&self, idx: usize) -> u64 {
+        self.node_overrides
+            .get(&idx)
+            .and_then(|node| node.stake)
+            .unwrap_or(1)
+    }
+
     /// the default metadata for a stress test
     #[must_use]
     #[allow(clippy::redundant_field_names)]
@@ -389,6 +495,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Default
         Self {
             timing_data: TimingData::default(),
             num_nodes_with_stake,
+            num_watchers: 0,
             start_nodes: num_nodes_with_stake,
             skip_late: false,
             num_bootstrap_nodes: num_nodes_with_stake,
@@ -396,6 +503,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Default
             spinning_properties: SpinningTaskDescription {
                 node_changes: vec![],
             },
+            partitions: vec![],
+            churn: None,
             overall_safety_properties: OverallSafetyPropertiesDescription::default(),
             // arbitrary, haven't done the math on this
             txn_description: TxnTaskDescription::RoundRobinTimeBased(Duration::from_millis(100)),
@@ -406,6 +515,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Default
                 },
             ),
             unreliable_network: None,
+            network_links: HashMap::new(),
+            message_hooks: vec![],
             view_sync_properties: ViewSyncTaskDescription::Threshold(0, num_nodes_with_stake),
             builders: vec1::vec1![BuilderDescription::default(), BuilderDescription::default(),],
             fallback_builder: BuilderDescription::default(),
@@ -419,6 +530,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Default
             start_solver: true,
             validate_transactions: Arc::new(|_| Ok(())),
             epoch_height: 0,
+            invariants: vec![],
+            node_overrides: HashMap::new(),
+            golden_trace: None,
+            resource_budget: None,
         }
     }
 }
@@ -469,7 +584,7 @@ where
                     ValidatorConfig::generated_from_seed_indexed(
                         [0u8; 32],
                         node_id_ as u64,
-                        1,
+                        self.node_stake(node_id_),
                         node_id_ < da_staked_committee_size,
                     );
 
@@ -485,7 +600,7 @@ where
         let validator_config = ValidatorConfig::<TYPES::SignatureKey>::generated_from_seed_indexed(
             [0u8; 32],
             node_id,
-            1,
+            self.node_stake(node_id.try_into().unwrap()),
             // This is the config for node 0
             0 < da_staked_committee_size,
         );
@@ -514,22 +629,21 @@ where
             start_voting_time: u64::MAX,
             stop_voting_time: 0,
             epoch_height,
+            checkpoint_interval: 0,
+            attestation_window: 0,
+            journal: None,
+            watchdog: None,
+            namespace: 0,
+            da_chunked_dispersal: false,
+            da_payload_validation: false,
+            consensus_task_threads: 0,
+            memory_budget: MemoryBudgetConfig::default(),
+            watcher: false,
+            archival: false,
+            message_ttl: None,
         };
-        let TimingData {
-            next_view_timeout,
-            builder_timeout,
-            data_request_delay,
-            secondary_network_delay,
-            view_sync_timeout,
-        } = timing_data;
-        let mod_config =
-            // TODO this should really be using the timing config struct
-            |a: &mut HotShotConfig<TYPES::SignatureKey>| {
-                a.next_view_timeout = next_view_timeout;
-                a.builder_timeout = builder_timeout;
-                a.data_request_delay = data_request_delay;
-                a.view_sync_timeout = view_sync_timeout;
-            };
+        let secondary_network_delay = timing_data.secondary_network_delay;
+        let mod_config = |a: &mut HotShotConfig<TYPES::SignatureKey>| timing_data.apply_to(a);
 
         let metadata = self.clone();
         TestLauncher {
@@ -541,10 +655,19 @@ where
                     unreliable_network,
                     secondary_network_delay,
                 ),
-                storage: Box::new(move |_| {
+                storage: Box::new(move |node_id| {
+                    let node = metadata
+                        .node_overrides
+                        .get(&usize::try_from(node_id).unwrap())
+                        .cloned()
+                        .unwrap_or_default();
                     let mut storage = TestStorage::<TYPES>::default();
                     // update storage impl to use settings delay option
-                    storage.delay_config = metadata.async_delay_config.clone();
+                    storage.delay_config = node
+                        .storage_delay_config
+                        .unwrap_or_else(|| metadata.async_delay_config.clone());
+                    storage.write_failure_probability =
+                        node.storage_write_failure_probability.unwrap_or(0.0);
                     storage
                 }),
                 config,
@@ -0,0 +1,175 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Machine-readable test results.
+//!
+//! When [`REPORT_DIR_ENV_VAR`] is set, every test run writes a [`TestReport`] to a JSON file in
+//! that directory, named after the test, so CI and benchmarking dashboards can track results
+//! across runs.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_lock::RwLock;
+use async_trait::async_trait;
+use hotshot_types::{
+    event::{Event, EventType},
+    traits::node_implementation::NodeType,
+    vote::HasViewNumber,
+};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::test_task::{TestResult, TestTaskState};
+
+/// Environment variable naming the directory test reports are written to
+pub const REPORT_DIR_ENV_VAR: &str = "HOTSHOT_TEST_REPORT_DIR";
+
+/// The results of one round of a test
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RoundReport {
+    /// the view of the round
+    pub view: u64,
+    /// the nodes which decided the leaf of this view
+    pub decided_nodes: BTreeSet<usize>,
+    /// the nodes which reported an error or timed out in this view
+    pub failed_nodes: BTreeSet<usize>,
+    /// time from the first event of this view to the first decide of its leaf, if it was decided
+    pub decide_latency_ms: Option<u64>,
+}
+
+/// The results of a test run
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TestReport {
+    /// the name of the test
+    pub name: String,
+    /// whether the test passed
+    pub passed: bool,
+    /// how long the test ran
+    pub duration_ms: u64,
+    /// the number of views in which a leaf was decided
+    pub views_decided: usize,
+    /// the results of each round, by view
+    pub rounds: BTreeMap<u64, RoundReport>,
+    /// the failures reported by the test tasks, including invariant violations
+    pub failures: Vec<String>,
+}
+
+impl TestReport {
+    /// Write the report to `dir`, as `<name>.json`
+    ///
+    /// # Errors
+    /// if the directory cannot be created or the file cannot be written
+    pub fn write_to(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let file_name = format!("{}.json", self.name.replace("::", "-"));
+        std::fs::write(dir.join(file_name), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// The directory to write test reports to, if reporting is enabled
+#[must_use]
+pub fn report_dir() -> Option<PathBuf> {
+    std::env::var(REPORT_DIR_ENV_VAR).ok().map(PathBuf::from)
+}
+
+/// The name of the running test, taken from the name of its thread
+#[must_use]
+pub fn current_test_name() -> String {
+    std::thread::current()
+        .name()
+        .unwrap_or("unnamed_test")
+        .to_string()
+}
+
+/// Task recording the results of each round into a shared [`TestReport`]
+pub struct ReportTask<TYPES: NodeType> {
+    /// the report being built, shared with the test runner
+    pub report: Arc<RwLock<TestReport>>,
+    /// when each view was first seen
+    view_start: HashMap<TYPES::View, Instant>,
+    /// when the task started
+    start: Instant,
+}
+
+impl<TYPES: NodeType> ReportTask<TYPES> {
+    /// Create a task recording into `report`
+    #[must_use]
+    pub fn new(report: Arc<RwLock<TestReport>>) -> Self {
+        Self {
+            report,
+            view_start: HashMap::new(),
+            start: Instant::now(),
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> TestTaskState for ReportTask<TYPES> {
+    type Event = Event<TYPES>;
+
+    async fn handle_event(&mut self, (message, id): (Self::Event, usize)) -> Result<()> {
+        let Event { view_number, event } = message;
+        let now = Instant::now();
+        self.view_start.entry(view_number).or_insert(now);
+
+        let mut report = self.report.write().await;
+        match event {
+            EventType::Decide { leaf_chain, .. } => {
+                for leaf_info in leaf_chain.iter() {
+                    let view = leaf_info.leaf.view_number();
+                    let round = report.rounds.entry(*view).or_insert_with(|| RoundReport {
+                        view: *view,
+                        ..RoundReport::default()
+                    });
+                    if round.decide_latency_ms.is_none() {
+                        round.decide_latency_ms = self
+                            .view_start
+                            .get(&view)
+                            .map(|start| duration_ms(now.duration_since(*start)));
+                    }
+                    round.decided_nodes.insert(id);
+                }
+            }
+            EventType::Error { .. } | EventType::ReplicaViewTimeout { .. } => {
+                report
+                    .rounds
+                    .entry(*view_number)
+                    .or_insert_with(|| RoundReport {
+                        view: *view_number,
+                        ..RoundReport::default()
+                    })
+                    .failed_nodes
+                    .insert(id);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn check(&self) -> TestResult {
+        let mut report = self.report.write().await;
+        report.views_decided = report
+            .rounds
+            .values()
+            .filter(|round| !round.decided_nodes.is_empty())
+            .count();
+        report.duration_ms = duration_ms(self.start.elapsed());
+        TestResult::Pass
+    }
+}
+
+/// Convert a duration to whole milliseconds, saturating
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
@@ -36,6 +36,7 @@ use hotshot_types::{
         election::Membership,
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
+        signature_key::SignatureKey,
     },
     HotShotConfig, ValidatorConfig,
 };
@@ -53,9 +54,13 @@ use super::{
 use crate::{
     block_builder::{BuilderTask, TestBuilderImplementation},
     completion_task::CompletionTaskDescription,
+    golden_trace::TraceRecorder,
+    invariant_task::InvariantTask,
+    resource_task::ResourceTask,
     spinning_task::{ChangeNode, NodeAction, SpinningTask},
-    test_builder::create_test_handle,
+    test_builder::{create_test_handle, Behaviour},
     test_launcher::{Network, TestLauncher},
+    test_report::{current_test_name, report_dir, ReportTask, TestReport},
     test_task::{TestResult, TestTask},
     txn_task::TxnTaskDescription,
     view_sync_task::ViewSyncTask,
@@ -87,15 +92,45 @@ where
     ///
     /// # Panics
     /// if the test fails
+    pub async fn run_test<B: TestBuilderImplementation<TYPES>>(self) {
+        let report = self.run_test_and_report::<B>().await;
+
+        assert!(
+            report.passed,
+            "{}",
+            report
+                .failures
+                .iter()
+                .fold("TEST FAILED! Results:".to_string(), |acc, error| {
+                    format!("{acc}\n\n{error}")
+                })
+        );
+    }
+
+    /// execute test, returning its results instead of panicking if it fails.
+    ///
+    /// The results are also written to [`REPORT_DIR_ENV_VAR`](crate::test_report::REPORT_DIR_ENV_VAR)
+    /// if it is set.
     #[allow(clippy::too_many_lines)]
-    pub async fn run_test<B: TestBuilderImplementation<TYPES>>(mut self) {
+    pub async fn run_test_and_report<B: TestBuilderImplementation<TYPES>>(mut self) -> TestReport {
         let (test_sender, test_receiver) = broadcast(EVENT_CHANNEL_SIZE);
-        let spinning_changes = self
+        // the report is named after the test, so capture the name while on the test's thread
+        let report = Arc::new(RwLock::new(TestReport {
+            name: current_test_name(),
+            ..TestReport::default()
+        }));
+        let mut spinning_changes = self
             .launcher
             .metadata
             .spinning_properties
             .node_changes
             .clone();
+        if let Some(churn) = &self.launcher.metadata.churn {
+            spinning_changes.extend(churn.node_changes(
+                self.launcher.metadata.start_nodes,
+                self.launcher.metadata.num_nodes_with_stake,
+            ));
+        }
 
         let mut late_start_nodes: HashSet<u64> = HashSet::new();
         let mut restart_nodes: HashSet<u64> = HashSet::new();
@@ -111,7 +146,7 @@ where
         }
 
         self.add_nodes::<B>(
-            self.launcher.metadata.num_nodes_with_stake,
+            self.launcher.metadata.num_nodes_with_stake + self.launcher.metadata.num_watchers,
             &late_start_nodes,
             &restart_nodes,
         )
@@ -174,6 +209,33 @@ where
                 .append(&mut change);
         }
 
+        let network_links = launcher.metadata.network_links.clone();
+        let mut message_hooks = launcher.metadata.message_hooks.clone();
+        let golden_trace = launcher.metadata.golden_trace.clone().map(|description| {
+            let recorder = TraceRecorder::default();
+            message_hooks.push(recorder.hook::<TYPES, V>(
+                launcher.metadata.num_nodes_with_stake,
+                description.last_view,
+            ));
+            (description, recorder)
+        });
+
+        // map partitions to the views in which they start and heal
+        let mut partitions = BTreeMap::new();
+        for partition in &launcher.metadata.partitions {
+            partitions.insert(
+                TYPES::View::new(partition.start_view),
+                Some(partition.groups.clone()),
+            );
+            partitions.insert(TYPES::View::new(partition.heal_view), None);
+        }
+        let heal_view = launcher
+            .metadata
+            .partitions
+            .iter()
+            .map(|partition| TYPES::View::new(partition.heal_view))
+            .max();
+
         let spinning_task_state = SpinningTask {
             handles: Arc::clone(&handles),
             late_start,
@@ -193,6 +255,20 @@ where
             async_delay_config: launcher.metadata.async_delay_config,
             restart_contexts: HashMap::new(),
             channel_generator: launcher.resource_generator.channel_generator,
+            partitions,
+            heal_view,
+            restarted_nodes: HashMap::new(),
+            caught_up_nodes: HashSet::new(),
+            churn_end_view: launcher
+                .metadata
+                .churn
+                .as_ref()
+                .map(|churn| TYPES::View::new(churn.end_view)),
+            max_retained_views: launcher
+                .metadata
+                .churn
+                .as_ref()
+                .and_then(|churn| churn.max_retained_views),
         };
         let spinning_task = TestTask::<SpinningTask<TYPES, N, I, V>>::new(
             spinning_task_state,
@@ -228,6 +304,52 @@ where
             test_receiver.clone(),
         );
 
+        // add invariant task, ignoring the decides of byzantine nodes
+        let invariant_task = if launcher.metadata.invariants.is_empty() {
+            None
+        } else {
+            let byzantine_nodes = (0..launcher.metadata.num_nodes_with_stake)
+                .filter(|id| {
+                    !matches!(
+                        (launcher.metadata.behaviour)(*id as u64),
+                        Behaviour::Standard
+                    )
+                })
+                .collect();
+            Some(TestTask::<InvariantTask<TYPES>>::new(
+                InvariantTask::new(
+                    launcher.metadata.invariants.clone(),
+                    byzantine_nodes,
+                    TYPES::Membership::new(
+                        launcher
+                            .resource_generator
+                            .config
+                            .known_nodes_with_stake
+                            .clone(),
+                        launcher.resource_generator.config.known_da_nodes.clone(),
+                    ),
+                ),
+                event_rxs.clone(),
+                test_receiver.clone(),
+            ))
+        };
+
+        // add resource budget task
+        let resource_task = launcher.metadata.resource_budget.clone().map(|budget| {
+            TestTask::<ResourceTask<TYPES>>::new(
+                ResourceTask::new(budget),
+                event_rxs.clone(),
+                test_receiver.clone(),
+            )
+        });
+
+        // add report task
+        let report_task = TestTask::<ReportTask<TYPES>>::new(
+            ReportTask::new(Arc::clone(&report)),
+            event_rxs.clone(),
+            test_receiver.clone(),
+        );
+
         // add view sync task
         let view_sync_task_state = ViewSyncTask {
             hit_view_sync: HashSet::new(),
@@ -248,6 +370,19 @@ where
             node.network.wait_for_ready().await;
         }
 
+        // set the reliability of individual links and the message hooks
+        if let Some(node) = nodes.first() {
+            for ((from, to), reliability) in network_links {
+                let from =
+                    TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], from as u64).0;
+                let to = TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], to as u64).0;
+                I::set_network_link_reliability(&node.network, from, to, reliability);
+            }
+            for hook in message_hooks {
+                I::add_network_message_hook(&node.network, hook);
+            }
+        }
+
         // Start hotshot
         for node in &*nodes {
             if !late_start_nodes.contains(&node.node_id) {
@@ -268,6 +403,13 @@ where
 
         task_futs.push(overall_safety_task.run());
         task_futs.push(consistency_task.run());
+        if let Some(invariant_task) = invariant_task {
+            task_futs.push(invariant_task.run());
+        }
+        if let Some(resource_task) = resource_task {
+            task_futs.push(resource_task.run());
+        }
+        task_futs.push(report_task.run());
         task_futs.push(view_sync_task.run());
         task_futs.push(spinning_task.run());
 
@@ -311,15 +453,25 @@ where
 
         completion_handle.abort();
 
-        assert!(
-            error_list.is_empty(),
-            "{}",
-            error_list
-                .iter()
-                .fold("TEST FAILED! Results:".to_string(), |acc, error| {
-                    format!("{acc}\n\n{error:?}")
-                })
-        );
+        if let Some((description, recorder)) = golden_trace {
+            if let Err(e) = recorder.check(&description) {
+                error_list.push(Box::new(e));
+            }
+        }
+
+        let mut report = report.read().await.clone();
+        report.passed = error_list.is_empty();
+        report.failures = error_list
+            .iter()
+            .map(|error| format!("{error:?}"))
+            .collect();
+        if let Some(dir) = report_dir() {
+            if let Err(e) = report.write_to(&dir) {
+                tracing::error!("Failed to write the test report to {}: {e}", dir.display());
+            }
+        }
+
+        report
     }
 
     pub async fn init_builders<B: TestBuilderImplementation<TYPES>>(
@@ -425,6 +577,17 @@ where
             self.next_node_id += 1;
             tracing::debug!("launch node {}", i);
 
+            if let Some(timing_data) = self
+                .launcher
+                .metadata
+                .node_overrides
+                .get(&usize::try_from(node_id).unwrap())
+                .and_then(|node| node.timing_data)
+            {
+                timing_data.apply_to(&mut config);
+            }
+            config.watcher = node_id >= self.launcher.metadata.num_nodes_with_stake as u64;
+
             //let memberships =Arc::new(RwLock::new(<TYPES as NodeType>::Membership::new(
             //config.known_nodes_with_stake.clone(),
             //config.known_da_nodes.clone(),
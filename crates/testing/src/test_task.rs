@@ -169,6 +169,7 @@ pub async fn add_network_message_test_task<
         external_event_stream: external_event_stream.clone(),
         public_key,
         transactions_cache: lru::LruCache::new(NonZeroUsize::new(100_000).unwrap()),
+        namespace: 0,
     };
 
     let network = Arc::clone(&net);
@@ -185,10 +186,10 @@ pub async fn add_network_message_test_task<
                 }
             };
 
-            // Deserialize the message
+            // Deserialize the message, checking that its sender signed it
             let deserialized_message: Message<TYPES> =
-                match upgrade_lock.deserialize(&message).await {
-                    Ok(message) => message,
+                match upgrade_lock.deserialize_signed(message).await {
+                    Ok((message, ..)) => message,
                     Err(e) => {
                         tracing::error!("Failed to deserialize message: {:?}", e);
                         continue;
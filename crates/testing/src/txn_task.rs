@@ -9,8 +9,10 @@ use std::{sync::Arc, time::Duration};
 use async_broadcast::Receiver;
 use async_lock::RwLock;
 use hotshot::traits::TestableNodeImplementation;
-use hotshot_types::traits::node_implementation::{NodeType, Versions};
-use rand::thread_rng;
+use hotshot_types::{
+    deterministic::with_rng,
+    traits::node_implementation::{NodeType, Versions},
+};
 use tokio::{spawn, task::JoinHandle, time::sleep};
 
 use crate::{test_runner::Node, test_task::TestEvent};
@@ -59,7 +61,7 @@ impl<TYPES: NodeType, I: TestableNodeImplementation<TYPES>, V: Versions> TxnTask
                     // If they don't match, this is probably fine since
                     // it should be caught by an assertion (and the txn will be rejected anyway)
                     let leaf = node.handle.decided_leaf().await;
-                    let txn = I::leaf_create_random_transaction(&leaf, &mut thread_rng(), 0);
+                    let txn = with_rng(|rng| I::leaf_create_random_transaction(&leaf, rng, 0));
                     node.handle
                         .submit_transaction(txn.clone())
                         .await
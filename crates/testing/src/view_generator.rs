@@ -26,6 +26,7 @@ use hotshot_types::{
         DaProposal2, EpochNumber, Leaf2, QuorumProposal2, VidDisperse, VidDisperseShare2,
         ViewChangeEvidence, ViewNumber,
     },
+    deterministic::with_rng,
     drb::{INITIAL_DRB_RESULT, INITIAL_DRB_SEED_INPUT},
     message::{Proposal, UpgradeLock},
     simple_certificate::{
@@ -42,7 +43,7 @@ use hotshot_types::{
         BlockPayload,
     },
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use sha2::{Digest, Sha256};
 
 use crate::helpers::{
@@ -359,7 +360,7 @@ impl TestView {
             view_sync_certificate.map(ViewChangeEvidence::ViewSync)
         };
 
-        let random = thread_rng().gen_range(0..=u64::MAX);
+        let random = with_rng(|rng| rng.gen_range(0..=u64::MAX));
 
         let block_header = TestBlockHeader {
             block_number: *next_view,
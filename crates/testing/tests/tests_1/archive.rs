@@ -0,0 +1,188 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::types::FinalityLog;
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
+use hotshot_testing::{
+    helpers::{build_system_handle_from_launcher, key_pair_for_id},
+    test_builder::TestDescription,
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    constants::ARCHIVE_REQUEST_BURST,
+    data::ViewNumber,
+    event::LeafInfo,
+    finality::FinalizedLeaf,
+    signature_key::BLSPubKey,
+    traits::{
+        network::{DataRequest, RequestKind},
+        node_implementation::ConsensusTime,
+        signature_key::SignatureKey,
+    },
+};
+use tokio::time::timeout;
+
+/// The heights of the leaves in `range`
+fn heights(range: &[FinalizedLeaf<TestTypes>]) -> Vec<u64> {
+    range.iter().map(|(leaf, ..)| leaf.height()).collect()
+}
+
+/// A request for `count` leaves from `from_height`, signed by the node with id `node_id`
+fn leaf_range_request(node_id: u64, from_height: u64, count: u64) -> DataRequest<TestTypes> {
+    let (private_key, _) = key_pair_for_id::<TestTypes>(node_id);
+    let request = RequestKind::LeafRange(from_height, count);
+    let signature = BLSPubKey::sign(&private_key, request.commit().as_ref()).unwrap();
+    DataRequest {
+        request,
+        view: ViewNumber::new(1),
+        signature,
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_archival_node_serves_history() {
+    hotshot::helpers::initialize_logging();
+
+    let mut launcher =
+        TestDescription::<TestTypes, MemoryImpl, TestVersions>::default_multiple_rounds()
+            .gen_launcher(0);
+    launcher.resource_generator.config.archival = true;
+    let handle =
+        build_system_handle_from_launcher::<TestTypes, MemoryImpl, TestVersions>(2, &launcher)
+            .await
+            .0;
+
+    // Leaves at heights 1 to 5, with a transaction at height 2
+    let transaction = TestTransaction::new(vec![1, 2, 3]);
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let mut leaves = (&mut generator)
+        .take(1)
+        .map(|view| view.leaf)
+        .collect::<Vec<_>>()
+        .await;
+    generator.add_transactions(vec![transaction.clone()]);
+    leaves.extend(
+        (&mut generator)
+            .take(1)
+            .map(|view| view.leaf)
+            .collect::<Vec<_>>()
+            .await,
+    );
+    generator.add_transactions(Vec::new());
+    leaves.extend(
+        (&mut generator)
+            .take(3)
+            .map(|view| view.leaf)
+            .collect::<Vec<_>>()
+            .await,
+    );
+
+    let leaf_chain: Vec<_> = leaves[..4]
+        .iter()
+        .rev()
+        .map(|leaf| {
+            LeafInfo::new(
+                leaf.clone(),
+                Arc::new(TestValidatedState::default()),
+                None,
+                None,
+            )
+        })
+        .collect();
+    handle
+        .hotshot
+        .finality_log
+        .write()
+        .await
+        .record(&leaf_chain, &leaves[4].justify_qc());
+
+    // The archive is indexed by height and by transaction
+    assert_eq!(
+        heights(&handle.finalized_leaves(2, 10).await),
+        vec![2, 3, 4]
+    );
+    assert_eq!(
+        handle.transaction_height(&transaction.commit()).await,
+        Some(leaves[1].height())
+    );
+    let mut pruned = FinalityLog::new(10);
+    pruned.record(&leaf_chain, &leaves[4].justify_qc());
+    assert!(!pruned.is_archive());
+    assert_eq!(pruned.transaction_height(&transaction.commit()), None);
+
+    // The archive serves requests with a valid signature, within the rate limit
+    let (_, requester) = key_pair_for_id::<TestTypes>(1);
+    let mut events = handle.internal_event_stream_receiver_known_impl();
+    // signed by another node, so ignored without taking from the requester's rate limit
+    let forged = leaf_range_request(3, 1, 1);
+    for request in std::iter::once(forged)
+        .chain((0..ARCHIVE_REQUEST_BURST + 5).map(|_| leaf_range_request(1, 1, 2)))
+    {
+        broadcast_event(
+            Arc::new(HotShotEvent::LeafRangeRequestRecv(
+                request,
+                requester.clone(),
+            )),
+            &handle.internal_event_stream_sender(),
+        )
+        .await;
+    }
+    let mut served = 0;
+    while let Ok(Ok(event)) = timeout(Duration::from_millis(500), events.recv_direct()).await {
+        if let HotShotEvent::LeafRangeResponseSend(_, to, range) = event.as_ref() {
+            assert_eq!(*to, requester);
+            assert_eq!(
+                range
+                    .iter()
+                    .map(|(leaf, _)| leaf.height())
+                    .collect::<Vec<_>>(),
+                vec![1, 2]
+            );
+            served += 1;
+        }
+    }
+    // a token may have been refilled meanwhile
+    assert!((ARCHIVE_REQUEST_BURST..=ARCHIVE_REQUEST_BURST + 1).contains(&served));
+    drop(events);
+
+    // A node catching up accepts a certified chain of the requested heights only
+    let range: Vec<_> = handle
+        .finalized_leaves(1, 3)
+        .await
+        .into_iter()
+        .map(|(leaf, qc, _)| (leaf, qc))
+        .collect();
+    for (response, valid) in [
+        (range.clone(), true),
+        (vec![range[0].clone(), range[2].clone()], false),
+        (range[1..].to_vec(), false),
+    ] {
+        let request = handle.request_leaf_range(requester.clone(), 1, 3).unwrap();
+        broadcast_event(
+            Arc::new(HotShotEvent::LeafRangeResponseRecv(
+                requester.clone(),
+                response,
+            )),
+            &handle.internal_event_stream_sender(),
+        )
+        .await;
+        let result = timeout(Duration::from_secs(5), request).await.unwrap();
+        assert_eq!(result.is_ok(), valid);
+        if valid {
+            assert_eq!(heights(&result.unwrap()), vec![1, 2, 3]);
+        }
+    }
+}
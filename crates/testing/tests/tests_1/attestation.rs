@@ -0,0 +1,289 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::tasks::task_state::CreateTaskState;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_macros::run_test;
+use hotshot_task_impls::{attestation::AttestationTaskState, events::HotShotEvent::*};
+use hotshot_testing::{
+    helpers::{build_cert, build_system_handle, key_pair_for_id},
+    predicates::event::exact,
+    script::{Expectations, InputOrder, TaskScript},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    attestation::{message_digest, Attestation, AttestationNonces, AttestationRequest, NonceUse},
+    data::{EpochNumber, Leaf2, ViewNumber},
+    message::UpgradeLock,
+    signature_key::BLSPubKey,
+    simple_certificate::AttestationCertificate,
+    simple_vote::{AttestationData, AttestationVote},
+    traits::{
+        consensus_api::ConsensusApi, node_implementation::ConsensusTime,
+        signature_key::SignatureKey,
+    },
+};
+
+/// A request by node 1 to attest to `message` as of `leaf`, under nonce 7
+async fn attestation_request(
+    leaf: &Leaf2<TestTypes>,
+    message: &[u8],
+    upgrade_lock: &UpgradeLock<TestTypes, TestVersions>,
+) -> AttestationRequest<TestTypes> {
+    let (requester, requester_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    attestation_request_by(
+        leaf,
+        leaf.view_number(),
+        message,
+        (requester, requester_key),
+        upgrade_lock,
+    )
+    .await
+}
+
+/// A request by the holder of `keys` to attest to `message` as of `leaf`, in `view`, under
+/// nonce 7
+async fn attestation_request_by(
+    leaf: &Leaf2<TestTypes>,
+    view: ViewNumber,
+    message: &[u8],
+    (requester, requester_key): (BLSPubKey, <BLSPubKey as SignatureKey>::PrivateKey),
+    upgrade_lock: &UpgradeLock<TestTypes, TestVersions>,
+) -> AttestationRequest<TestTypes> {
+    let data = AttestationData {
+        height: leaf.height(),
+        leaf_commit: leaf.commit(),
+        epoch: EpochNumber::new(0),
+        requester,
+        nonce: 7,
+        message_digest: message_digest(message),
+    };
+    let vote = AttestationVote::create_signed_vote(
+        data.clone(),
+        view,
+        &data.requester,
+        &requester_key,
+        upgrade_lock,
+    )
+    .await
+    .expect("Failed to sign attestation request");
+
+    AttestationRequest {
+        vote,
+        message: message.to_vec(),
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_attestation_task_signs_decided_messages_once_per_nonce() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(membership);
+    let leaf = (&mut generator).next().await.unwrap().leaf.clone();
+    let upgrade_lock = &handle.hotshot.upgrade_lock;
+
+    let withdrawal = attestation_request(&leaf, b"withdraw 10 to 0xabc", upgrade_lock).await;
+    let conflicting = attestation_request(&leaf, b"withdraw 99 to 0xdef", upgrade_lock).await;
+
+    let own_vote = AttestationVote::<TestTypes>::create_signed_vote(
+        withdrawal.vote.data.clone(),
+        leaf.view_number(),
+        handle.public_key(),
+        handle.private_key(),
+        upgrade_lock,
+    )
+    .await
+    .expect("Failed to sign attestation vote");
+
+    // The first request arrives before we decided its view, and waits for the decide. A second
+    // message under the same nonce is refused, while the original request is answered again.
+    let inputs = vec![InputOrder::Serial(vec![
+        AttestationRequestRecv(withdrawal.clone()),
+        LeavesDecided(vec![(
+            leaf.clone(),
+            Arc::new(TestValidatedState::default()),
+        )]),
+        AttestationRequestRecv(conflicting),
+        AttestationRequestRecv(withdrawal),
+    ])];
+
+    let expectations = vec![Expectations::from_outputs(vec![
+        exact(AttestationVoteSend(own_vote.clone())),
+        exact(AttestationVoteSend(own_vote)),
+    ])];
+
+    let mut state =
+        AttestationTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    state.attestation_window = 8;
+    let mut script = TaskScript {
+        timeout: Duration::from_millis(35),
+        state,
+        expectations,
+    };
+    run_test![inputs, script].await;
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_attestation_verification_and_replay_protection() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 3;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let leaf = (&mut generator).next().await.unwrap().leaf.clone();
+
+    let message = b"withdraw 10 to 0xabc".to_vec();
+    let (private_key, public_key) = key_pair_for_id::<TestTypes>(node_id);
+    let data = AttestationData {
+        height: leaf.height(),
+        leaf_commit: leaf.commit(),
+        epoch: EpochNumber::new(0),
+        requester: public_key,
+        nonce: 1,
+        message_digest: message_digest(&message),
+    };
+    let certificate = build_cert::<
+        TestTypes,
+        TestVersions,
+        AttestationData<TestTypes>,
+        AttestationVote<TestTypes>,
+        AttestationCertificate<TestTypes>,
+    >(
+        data.clone(),
+        &membership,
+        leaf.view_number(),
+        EpochNumber::new(0),
+        &data.requester,
+        &private_key,
+        &handle.hotshot.upgrade_lock,
+    )
+    .await;
+
+    let attestation = Attestation {
+        certificate,
+        message,
+    };
+    let upgrade_lock = &handle.hotshot.upgrade_lock;
+    assert!(
+        attestation
+            .verify_with_membership(&*membership.read().await, upgrade_lock)
+            .await
+    );
+
+    // The certificate does not carry over to another message
+    let forged = Attestation {
+        message: b"withdraw 99 to 0xdef".to_vec(),
+        ..attestation.clone()
+    };
+    assert!(
+        !forged
+            .verify_with_membership(&*membership.read().await, upgrade_lock)
+            .await
+    );
+
+    // Nor is it valid without the signatures of the quorum
+    let mut unsigned = attestation.clone();
+    unsigned.certificate.signatures = None;
+    assert!(
+        !unsigned
+            .verify_with_membership(&*membership.read().await, upgrade_lock)
+            .await
+    );
+
+    let mut nonces = AttestationNonces::<TestTypes>::new(2);
+    assert_eq!(nonces.record(attestation.data()), NonceUse::Fresh);
+    assert_eq!(nonces.record(attestation.data()), NonceUse::Repeated);
+    let other = AttestationData {
+        message_digest: message_digest(b"withdraw 99 to 0xdef"),
+        ..data.clone()
+    };
+    assert_eq!(nonces.record(&other), NonceUse::Reused);
+    for nonce in [2, 3] {
+        assert_eq!(
+            nonces.record(&AttestationData {
+                nonce,
+                ..data.clone()
+            }),
+            NonceUse::Fresh
+        );
+    }
+    assert_eq!(nonces.record(&data), NonceUse::Expired);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_attestation_task_only_keeps_requests_it_may_answer() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let leaf = (&mut generator).next().await.unwrap().leaf.clone();
+    let upgrade_lock = &handle.hotshot.upgrade_lock;
+    let message = b"withdraw 10 to 0xabc";
+
+    let mut state =
+        AttestationTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    state.attestation_window = 8;
+    let (sender, _receiver) = async_broadcast::broadcast(8);
+
+    // Someone without stake
+    let unstaked = attestation_request_by(
+        &leaf,
+        leaf.view_number(),
+        message,
+        BLSPubKey::generated_from_seed_indexed([1u8; 32], 0),
+        upgrade_lock,
+    )
+    .await;
+    assert!(state
+        .handle(Arc::new(AttestationRequestRecv(unstaked)), &sender)
+        .await
+        .is_err());
+
+    // A view further ahead than the attestation window
+    let far_ahead = attestation_request_by(
+        &leaf,
+        ViewNumber::new(9),
+        message,
+        BLSPubKey::generated_from_seed_indexed([0u8; 32], 1),
+        upgrade_lock,
+    )
+    .await;
+    assert!(state
+        .handle(Arc::new(AttestationRequestRecv(far_ahead)), &sender)
+        .await
+        .is_err());
+    assert!(state.pending.is_empty());
+
+    // A member of the quorum asking for a view within the window is kept until we decide it
+    let request = attestation_request(&leaf, message, upgrade_lock).await;
+    state
+        .handle(Arc::new(AttestationRequestRecv(request)), &sender)
+        .await
+        .unwrap();
+    assert_eq!(state.pending.len(), 1);
+}
@@ -0,0 +1,60 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::ViewNumber,
+    signature_key::BuilderKey,
+    traits::{
+        block_builder_strategy::{
+            BlockBuilderStrategy, BlockOffer, MaxFill, PriorityOrdered, TimeBoxed,
+        },
+        node_implementation::ConsensusTime,
+        signature_key::BuilderSignatureKey,
+    },
+};
+
+/// Offers of the given sizes and fees, each from a different builder
+fn offers(offers: &[(u64, u64)]) -> Vec<BlockOffer<TestTypes>> {
+    offers
+        .iter()
+        .zip(0..)
+        .map(|(&(block_size, offered_fee), i)| BlockOffer {
+            builder: BuilderKey::generated_from_seed_indexed([0u8; 32], i).0,
+            block_size,
+            offered_fee,
+        })
+        .collect()
+}
+
+/// Rank `offers` for view 1 with strategy `S`
+fn rank<S: BlockBuilderStrategy<TestTypes>>(offers: &[BlockOffer<TestTypes>]) -> Vec<usize> {
+    S::rank_offers(ViewNumber::new(1), offers)
+}
+
+#[test]
+fn test_block_builder_strategies() {
+    // (block size, offered fee)
+    let offers = offers(&[(100, 10), (400, 20), (50, 10), (400, 30)]);
+
+    // 0.1, 0.05, 0.2 and 0.075 per byte
+    assert_eq!(rank::<PriorityOrdered>(&offers), vec![2, 0, 3, 1]);
+    assert_eq!(rank::<MaxFill>(&offers), vec![3, 1, 0, 2]);
+    assert_eq!(rank::<TimeBoxed<200>>(&offers), vec![2, 0, 3, 1]);
+
+    assert_eq!(
+        <PriorityOrdered as BlockBuilderStrategy<TestTypes>>::offer_deadline(),
+        None
+    );
+    assert_eq!(
+        <TimeBoxed<200> as BlockBuilderStrategy<TestTypes>>::offer_deadline(),
+        Some(Duration::from_millis(200))
+    );
+
+    assert!(rank::<PriorityOrdered>(&[]).is_empty());
+}
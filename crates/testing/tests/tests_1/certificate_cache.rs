@@ -0,0 +1,99 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{num::NonZeroU64, sync::Arc};
+
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    certificate_cache::CertificateCache,
+    data::EpochNumber,
+    traits::{election::Membership, node_implementation::ConsensusTime},
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_certificate_cache_reuses_outcomes_only_for_the_same_check() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let views = (&mut generator).take(4).collect::<Vec<_>>().await;
+    let qc = views[3].quorum_proposal.data.justify_qc.clone();
+
+    let epoch = EpochNumber::new(0);
+    let membership_reader = membership.read().await;
+    let stake_table = membership_reader.stake_table(epoch);
+    let success_threshold = membership_reader.success_threshold(epoch);
+    drop(membership_reader);
+    // No QC carries this much stake, so checking against it always fails
+    let unreachable_threshold = NonZeroU64::MAX;
+
+    let cache = CertificateCache::<TestTypes>::default();
+    let upgrade_lock = &handle.hotshot.upgrade_lock;
+    assert!(
+        cache
+            .is_valid_cert(
+                &qc,
+                epoch,
+                stake_table.clone(),
+                success_threshold,
+                upgrade_lock
+            )
+            .await
+    );
+    assert_eq!(cache.len(), 1);
+
+    // The same check reuses the outcome
+    assert!(
+        cache
+            .is_valid_cert(
+                &qc,
+                epoch,
+                stake_table.clone(),
+                success_threshold,
+                upgrade_lock
+            )
+            .await
+    );
+    assert_eq!(cache.len(), 1);
+
+    // A check against another threshold does not reuse the outcome
+    assert!(
+        !cache
+            .is_valid_cert(
+                &qc,
+                epoch,
+                stake_table.clone(),
+                unreachable_threshold,
+                upgrade_lock
+            )
+            .await
+    );
+    assert_eq!(cache.len(), 2);
+
+    // Nor does a check against another stake table
+    assert!(
+        !cache
+            .is_valid_cert(
+                &qc,
+                epoch,
+                stake_table[..1].to_vec(),
+                success_threshold,
+                upgrade_lock
+            )
+            .await
+    );
+    assert_eq!(cache.len(), 3);
+
+    // Once the epoch changes the outcomes are forgotten
+    cache.invalidate_before(epoch + 1);
+    assert!(cache.is_empty());
+}
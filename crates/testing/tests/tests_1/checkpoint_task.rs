@@ -0,0 +1,166 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::tasks::task_state::CreateTaskState;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_macros::run_test;
+use hotshot_task_impls::{checkpoint::CheckpointTaskState, events::HotShotEvent::*};
+use hotshot_testing::{
+    helpers::build_system_handle,
+    predicates::event::exact,
+    script::{Expectations, InputOrder, TaskScript},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    data::{fake_commitment, EpochNumber, ViewNumber},
+    signature_key::BLSPubKey,
+    simple_vote::{CheckpointData, CheckpointVote},
+    traits::{
+        consensus_api::ConsensusApi, node_implementation::ConsensusTime,
+        signature_key::SignatureKey, ValidatedState,
+    },
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_checkpoint_task_forms_certificate() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(membership);
+    let leaf = (&mut generator).next().await.unwrap().leaf.clone();
+    let state = TestValidatedState::default();
+
+    let data = CheckpointData {
+        height: leaf.height(),
+        leaf_commit: leaf.commit(),
+        state_digest: <TestValidatedState as ValidatedState<TestTypes>>::state_digest(&state),
+        epoch: EpochNumber::new(0),
+    };
+
+    let mut votes = Vec::new();
+    for id in 0..10 {
+        let (public_key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], id);
+        votes.push(
+            CheckpointVote::<TestTypes>::create_signed_vote(
+                data.clone(),
+                leaf.view_number(),
+                &public_key,
+                &private_key,
+                &handle.hotshot.upgrade_lock,
+            )
+            .await
+            .expect("Failed to sign checkpoint vote"),
+        );
+    }
+    let own_vote = votes.remove(usize::try_from(node_id).unwrap());
+
+    let mut events = vec![CheckpointDecided(leaf.clone(), Arc::new(state))];
+    events.extend(votes.into_iter().map(CheckpointVoteRecv));
+    let inputs = vec![InputOrder::Serial(events)];
+
+    let expectations = vec![Expectations::from_outputs(vec![exact(CheckpointVoteSend(
+        own_vote,
+    ))])];
+
+    let state =
+        CheckpointTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    let mut script = TaskScript {
+        timeout: Duration::from_millis(35),
+        state,
+        expectations,
+    };
+    run_test![inputs, script].await;
+
+    let certificate = handle
+        .checkpoint_certificate()
+        .await
+        .expect("Checkpoint was not certified");
+    assert_eq!(certificate.data, data);
+    assert_eq!(
+        handle
+            .storage()
+            .read()
+            .await
+            .checkpoint_certificate_cloned()
+            .await,
+        Some(certificate)
+    );
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_checkpoint_task_ignores_far_and_invalid_votes() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut state =
+        CheckpointTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    let (sender, _receiver) = async_broadcast::broadcast(10);
+
+    let state_digest = <TestValidatedState as ValidatedState<TestTypes>>::state_digest(
+        &TestValidatedState::default(),
+    );
+    let vote_for = |height: u64, signer: u64| {
+        let (public_key, _) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+        let (_, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], signer);
+        let upgrade_lock = handle.hotshot.upgrade_lock.clone();
+        async move {
+            CheckpointVote::<TestTypes>::create_signed_vote(
+                CheckpointData {
+                    height,
+                    leaf_commit: fake_commitment(),
+                    state_digest,
+                    epoch: EpochNumber::new(0),
+                },
+                ViewNumber::new(height),
+                &public_key,
+                &private_key,
+                &upgrade_lock,
+            )
+            .await
+            .expect("Failed to sign checkpoint vote")
+        }
+    };
+
+    // A vote far past our decided height is rejected before any accumulator is made for it
+    let far = vote_for(1000, 0).await;
+    assert!(state
+        .handle(Arc::new(CheckpointVoteRecv(far)), &sender)
+        .await
+        .is_err());
+    assert!(state.accumulators.is_empty());
+
+    // A vote whose signature does not check out leaves no accumulator behind
+    let forged = vote_for(1, 1).await;
+    assert!(state
+        .handle(Arc::new(CheckpointVoteRecv(forged)), &sender)
+        .await
+        .is_ok());
+    assert!(state.accumulators.is_empty());
+
+    // A valid vote is kept until the checkpoint is certified
+    let valid = vote_for(1, 0).await;
+    assert!(state
+        .handle(Arc::new(CheckpointVoteRecv(valid)), &sender)
+        .await
+        .is_ok());
+    assert_eq!(state.accumulators.len(), 1);
+}
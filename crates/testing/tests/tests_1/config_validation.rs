@@ -0,0 +1,44 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_types::{
+    error::HotShotConfigError, hotshot_config_file::HotShotConfigFile, signature_key::BLSPubKey,
+    HotShotConfig,
+};
+
+#[test]
+fn inconsistent_configs_are_rejected() {
+    let config = HotShotConfigFile::<BLSPubKey>::hotshot_config_5_nodes_10_da();
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(HotShotConfig::from(config.clone()).validate(), Ok(()));
+
+    let mut da_too_large = config.clone();
+    da_too_large.staked_da_nodes = 11;
+    assert_eq!(
+        da_too_large.validate(),
+        Err(HotShotConfigError::DaCommitteeTooLarge {
+            da: 11,
+            committee: 10
+        })
+    );
+
+    let mut bad_threshold = config.clone();
+    bad_threshold.start_threshold = (3, 2);
+    assert_eq!(
+        bad_threshold.validate(),
+        Err(HotShotConfigError::InvalidStartThreshold(3, 2))
+    );
+
+    let mut missing_nodes: HotShotConfig<BLSPubKey> = config.into();
+    missing_nodes.known_nodes_with_stake.pop();
+    assert_eq!(
+        missing_nodes.validate(),
+        Err(HotShotConfigError::StakeTableSizeMismatch {
+            known: 9,
+            expected: 10
+        })
+    );
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::BTreeMap, num::NonZeroU64};
+
+use async_lock::RwLock;
+use async_trait::async_trait;
+use hotshot::traits::election::contract_committee::{ContractCommittee, StakeTableContract};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::EpochNumber,
+    signature_key::BLSPubKey,
+    traits::{
+        election::Membership, node_implementation::ConsensusTime, signature_key::SignatureKey,
+    },
+    PeerConfig,
+};
+
+/// A contract with a fixed stake table per epoch
+struct MockContract {
+    /// The stake table of each epoch
+    stake_tables: BTreeMap<EpochNumber, Vec<PeerConfig<BLSPubKey>>>,
+}
+
+#[async_trait]
+impl StakeTableContract<TestTypes> for MockContract {
+    async fn latest_epoch(&self) -> anyhow::Result<EpochNumber> {
+        Ok(*self.stake_tables.keys().last().unwrap())
+    }
+
+    async fn stake_table(&self, epoch: EpochNumber) -> anyhow::Result<Vec<PeerConfig<BLSPubKey>>> {
+        Ok(self.stake_tables[&epoch].clone())
+    }
+}
+
+/// Build the peer configs of the test keys, with the given stakes
+fn peers_with_stakes(stakes: &[u64]) -> Vec<PeerConfig<BLSPubKey>> {
+    stakes
+        .iter()
+        .zip(0..)
+        .map(|(stake, i)| PeerConfig {
+            stake_table_entry: BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .stake_table_entry(*stake),
+            ..PeerConfig::default()
+        })
+        .collect()
+}
+
+/// Build the peer configs of the first `count` test keys, with a stake of 1 each
+fn peers(count: usize) -> Vec<PeerConfig<BLSPubKey>> {
+    peers_with_stakes(&vec![1; count])
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_contract_committee_caches_stake_tables() {
+    let membership = RwLock::new(ContractCommittee::<TestTypes>::new(peers(4), peers(4)));
+    let mut contract = MockContract {
+        stake_tables: BTreeMap::from([
+            (EpochNumber::new(1), peers(5)),
+            (EpochNumber::new(2), peers(6)),
+        ]),
+    };
+
+    // The first refresh fetches only the latest epoch
+    ContractCommittee::refresh(&membership, &contract)
+        .await
+        .unwrap();
+    {
+        let membership = membership.read().await;
+        assert_eq!(membership.total_nodes(EpochNumber::new(1)), 4);
+        assert_eq!(membership.total_nodes(EpochNumber::new(2)), 6);
+        assert_eq!(membership.total_nodes(EpochNumber::new(3)), 6);
+
+        // Only the fetched stake table is known, the others are stood in for
+        assert!(!membership.has_stake_table(EpochNumber::new(1)));
+        assert!(membership.has_stake_table(EpochNumber::new(2)));
+        assert!(!membership.has_stake_table(EpochNumber::new(3)));
+    }
+
+    // An empty stake table is rejected
+    contract.stake_tables.insert(EpochNumber::new(3), vec![]);
+    assert!(ContractCommittee::refresh(&membership, &contract)
+        .await
+        .is_err());
+    assert_eq!(
+        membership.read().await.latest_fetched_epoch(),
+        Some(EpochNumber::new(2))
+    );
+    assert_eq!(membership.read().await.total_nodes(EpochNumber::new(3)), 6);
+
+    // Once the contract is fixed the missing epochs are fetched in order
+    contract.stake_tables.insert(EpochNumber::new(3), peers(7));
+    contract.stake_tables.insert(EpochNumber::new(4), peers(8));
+    ContractCommittee::refresh(&membership, &contract)
+        .await
+        .unwrap();
+    let membership = membership.read().await;
+    assert_eq!(membership.total_nodes(EpochNumber::new(3)), 7);
+    assert_eq!(membership.total_nodes(EpochNumber::new(4)), 8);
+    assert!(membership.has_stake_table(EpochNumber::new(4)));
+    assert_eq!(membership.da_total_nodes(EpochNumber::new(4)), 4);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_contract_committee_thresholds_are_over_stake() {
+    let membership = RwLock::new(ContractCommittee::<TestTypes>::new(
+        peers(4),
+        peers_with_stakes(&[10, 1, 1]),
+    ));
+    let contract = MockContract {
+        stake_tables: BTreeMap::from([(
+            EpochNumber::new(1),
+            peers_with_stakes(&[50, 30, 10, 5, 5]),
+        )]),
+    };
+    ContractCommittee::refresh(&membership, &contract)
+        .await
+        .unwrap();
+    let membership = membership.read().await;
+
+    // Before the fetched epoch the configured members have equal stake
+    let genesis = EpochNumber::new(0);
+    assert_eq!(
+        membership.success_threshold(genesis),
+        NonZeroU64::new(3).unwrap()
+    );
+    assert_eq!(
+        membership.failure_threshold(genesis),
+        NonZeroU64::new(2).unwrap()
+    );
+
+    // From the fetched epoch on, thresholds are fractions of the total stake of 100, not of the
+    // 5 members
+    let epoch = EpochNumber::new(1);
+    assert_eq!(membership.total_nodes(epoch), 5);
+    assert_eq!(
+        membership.success_threshold(epoch),
+        NonZeroU64::new(67).unwrap()
+    );
+    assert_eq!(
+        membership.failure_threshold(epoch),
+        NonZeroU64::new(34).unwrap()
+    );
+    assert_eq!(
+        membership.upgrade_threshold(epoch),
+        NonZeroU64::new(90).unwrap()
+    );
+
+    // The DA threshold is over the stake of the configured DA committee
+    assert_eq!(
+        membership.da_success_threshold(epoch),
+        NonZeroU64::new(9).unwrap()
+    );
+}
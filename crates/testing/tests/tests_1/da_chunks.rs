@@ -0,0 +1,214 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use hotshot::tasks::task_state::CreateTaskState;
+use hotshot_example_types::{
+    block_types::{TestMetadata, TestTransaction},
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+};
+use hotshot_macros::run_test;
+use hotshot_task_impls::{da::DaTaskState, events::HotShotEvent::*};
+use hotshot_testing::{
+    helpers::{build_system_handle, key_pair_for_id},
+    predicates::event::exact,
+    script::{Expectations, InputOrder, TaskScript},
+};
+use hotshot_types::{
+    data::{DaChunk, EpochNumber, ViewNumber},
+    simple_vote::{DaData2, DaVote2},
+    traits::{
+        consensus_api::ConsensusApi, election::Membership, node_implementation::ConsensusTime,
+    },
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_da_chunks_recover_payload() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let view = ViewNumber::new(2);
+    let epoch = EpochNumber::new(0);
+    let membership = handle.hotshot.memberships.read().await;
+    let da_committee = membership.da_committee_members(view, epoch);
+    let num_nodes = membership.total_nodes(epoch);
+    drop(membership);
+
+    let transactions: Vec<_> = (0..4u8)
+        .map(|i| TestTransaction::new(vec![i; 32]))
+        .collect();
+    let payload = TestTransaction::encode(&transactions);
+    let metadata = TestMetadata {
+        num_transactions: transactions.len() as u64,
+    };
+
+    let chunks: Vec<_> = DaChunk::<TestTypes>::from_payload(
+        &payload,
+        &metadata,
+        view,
+        epoch,
+        &da_committee,
+        num_nodes,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|chunk| chunk.to_proposal(handle.private_key()).unwrap())
+    .collect();
+    assert_eq!(chunks.len(), da_committee.len());
+    for chunk in &chunks {
+        assert!(DaChunk::is_valid(
+            chunk,
+            handle.public_key(),
+            &da_committee,
+            num_nodes
+        ));
+    }
+
+    // A chunk moved to another view after signing is rejected
+    let mut tampered = chunks[0].clone();
+    tampered.data.view_number = view + 1;
+    assert!(!DaChunk::is_valid(
+        &tampered,
+        handle.public_key(),
+        &da_committee,
+        num_nodes
+    ));
+
+    // So is a chunk whose shares are of another payload than the commitment voted on, even
+    // signed by the leader
+    let other_payload = TestTransaction::encode(&[TestTransaction::new(vec![9; 32])]);
+    let other_chunks = DaChunk::<TestTypes>::from_payload(
+        &other_payload,
+        &metadata,
+        view,
+        epoch,
+        &da_committee,
+        num_nodes,
+    )
+    .unwrap();
+    let mut mismatched = chunks[0].data.clone();
+    mismatched.shares.clone_from(&other_chunks[0].shares);
+    mismatched.common = other_chunks[0].common.clone();
+    assert!(!DaChunk::is_valid(
+        &mismatched.to_proposal(handle.private_key()).unwrap(),
+        handle.public_key(),
+        &da_committee,
+        num_nodes
+    ));
+
+    // And a chunk missing some of the shares its recipient is dealt
+    let mut short = chunks[0].data.clone();
+    short.shares.pop();
+    assert!(!DaChunk::is_valid(
+        &short.to_proposal(handle.private_key()).unwrap(),
+        handle.public_key(),
+        &da_committee,
+        num_nodes
+    ));
+
+    // Any set of chunks with enough shares recovers the payload
+    let mut subset = Vec::new();
+    for chunk in chunks.iter().rev() {
+        if DaChunk::can_recover(&subset, num_nodes) {
+            break;
+        }
+        subset.push(chunk.data.clone());
+    }
+    assert_eq!(
+        DaChunk::recover_payload(&subset, num_nodes).unwrap(),
+        payload
+    );
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_da_chunks_payload_validated_before_voting() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let view = ViewNumber::new(1);
+    let epoch = EpochNumber::new(0);
+    let membership = handle.hotshot.memberships.read().await;
+    let da_committee = membership.da_committee_members(view, epoch);
+    let num_nodes = membership.total_nodes(epoch);
+    let leader = membership.leader(view, epoch).unwrap();
+    drop(membership);
+    let (leader_key, _) = (0..num_nodes as u64)
+        .map(key_pair_for_id::<TestTypes>)
+        .find(|(_, public_key)| *public_key == leader)
+        .unwrap();
+
+    let transactions: Vec<_> = (0..4u8)
+        .map(|i| TestTransaction::new(vec![i; 32]))
+        .collect();
+    let payload = TestTransaction::encode(&transactions);
+    let metadata = TestMetadata {
+        num_transactions: transactions.len() as u64,
+    };
+    let chunks: Vec<_> = DaChunk::<TestTypes>::from_payload(
+        &payload,
+        &metadata,
+        view,
+        epoch,
+        &da_committee,
+        num_nodes,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|chunk| chunk.to_proposal(&leader_key).unwrap())
+    .collect();
+
+    let public_key = handle.public_key().clone();
+    let (own_chunks, other_chunks): (Vec<_>, Vec<_>) = chunks
+        .into_iter()
+        .partition(|chunk| chunk.data.recipient_key == public_key);
+    let own_chunk = own_chunks[0].clone();
+    let vote = DaVote2::<TestTypes>::create_signed_vote(
+        DaData2 {
+            payload_commit: own_chunk.data.payload_commitment,
+            epoch,
+        },
+        view,
+        &public_key,
+        handle.private_key(),
+        &handle.hotshot.upgrade_lock,
+    )
+    .await
+    .unwrap();
+
+    // Our chunk alone does not let us check the payload, so we share it with the rest of the
+    // committee and vote only once enough of their chunks arrived to recover the payload.
+    let mut inputs = vec![DaChunkRecv(own_chunk.clone(), leader)];
+    let mut received = vec![own_chunk.data.clone()];
+    for chunk in &other_chunks {
+        if DaChunk::can_recover(&received, num_nodes) {
+            break;
+        }
+        received.push(chunk.data.clone());
+        inputs.push(DaChunkResponseRecv(chunk.data.recipient_key, chunk.clone()));
+    }
+    let mut outputs: Vec<_> = da_committee
+        .iter()
+        .filter(|member| **member != public_key)
+        .map(|member| exact(DaChunkResponseSend(public_key, *member, own_chunk.clone())))
+        .collect();
+    outputs.push(exact(DaVoteSend(vote)));
+
+    let mut state = DaTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    state.payload_validation = true;
+    let mut script = TaskScript {
+        timeout: Duration::from_millis(35),
+        state,
+        expectations: vec![Expectations::from_outputs(outputs)],
+    };
+    run_test![vec![InputOrder::Serial(inputs)], script].await;
+}
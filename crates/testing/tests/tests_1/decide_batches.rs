@@ -0,0 +1,114 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::{stream, StreamExt};
+use hotshot::types::decide_batches;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    data::{Leaf2, ViewNumber},
+    event::{Event, EventType, LeafInfo},
+    traits::node_implementation::ConsensusTime,
+};
+
+/// A decide of `leaves`, given oldest first, certified by the justify QC of `next`
+fn decide(leaves: &[Leaf2<TestTypes>], next: &Leaf2<TestTypes>) -> Event<TestTypes> {
+    Event {
+        view_number: next.view_number(),
+        event: EventType::Decide {
+            leaf_chain: Arc::new(
+                leaves
+                    .iter()
+                    .rev()
+                    .map(|leaf| {
+                        LeafInfo::new(
+                            leaf.clone(),
+                            Arc::new(TestValidatedState::default()),
+                            None,
+                            None,
+                        )
+                    })
+                    .collect(),
+            ),
+            qc: Arc::new(next.justify_qc()),
+            block_size: None,
+        },
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_decide_batches() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let leaves: Vec<_> = (&mut generator)
+        .take(7)
+        .map(|view| view.leaf)
+        .collect()
+        .await;
+
+    // The decide of the fourth leaf is missing, so the chain breaks there
+    let events = || {
+        stream::iter(vec![
+            decide(&leaves[0..2], &leaves[2]),
+            Event {
+                view_number: ViewNumber::new(3),
+                event: EventType::ViewFinished {
+                    view_number: ViewNumber::new(3),
+                },
+            },
+            decide(&leaves[2..3], &leaves[3]),
+            decide(&leaves[4..5], &leaves[5]),
+            decide(&leaves[5..6], &leaves[6]),
+        ])
+    };
+    let batch_views = |max_leaves| async move {
+        decide_batches(events(), max_leaves)
+            .map(|batch| {
+                assert_eq!(
+                    batch.qc.data.leaf_commit,
+                    batch.leaf_chain.last().unwrap().leaf.commit()
+                );
+                (
+                    batch
+                        .leaf_chain
+                        .iter()
+                        .map(|info| *info.leaf.view_number())
+                        .collect::<Vec<_>>(),
+                    batch.decides,
+                )
+            })
+            .collect::<Vec<_>>()
+            .await
+    };
+
+    let views: Vec<_> = leaves.iter().map(|leaf| *leaf.view_number()).collect();
+    assert_eq!(
+        batch_views(usize::MAX).await,
+        vec![(views[0..3].to_vec(), 2), (views[4..6].to_vec(), 2)]
+    );
+    // A single decide is delivered whole even if it has more than `max_leaves` leaves
+    assert_eq!(
+        batch_views(1).await,
+        vec![
+            (views[0..2].to_vec(), 1),
+            (views[2..3].to_vec(), 1),
+            (views[4..5].to_vec(), 1),
+            (views[5..6].to_vec(), 1),
+        ]
+    );
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::traits::election::delegated_stake_committee::DelegatedStakeCommittee;
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::EpochNumber,
+    signature_key::BLSPubKey,
+    stake_table::Delegation,
+    traits::{
+        election::Membership,
+        node_implementation::ConsensusTime,
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    PeerConfig,
+};
+use primitive_types::U256;
+
+#[test]
+fn test_delegated_stake_takes_effect_at_epoch_boundary() {
+    let keys: Vec<BLSPubKey> = (0..4)
+        .map(|i| BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0)
+        .collect();
+    // Operator 3 has no stake of its own
+    let peers: Vec<_> = keys
+        .iter()
+        .zip([10, 10, 10, 0])
+        .map(|(key, stake)| PeerConfig {
+            stake_table_entry: key.stake_table_entry(stake),
+            ..PeerConfig::default()
+        })
+        .collect();
+    let mut membership = DelegatedStakeCommittee::<TestTypes>::new(peers.clone(), peers);
+
+    let epoch_1 = EpochNumber::new(1);
+    let epoch_2 = EpochNumber::new(2);
+    assert_eq!(membership.total_nodes(epoch_1), 3);
+    assert!(!membership.has_stake(&keys[3], epoch_1));
+    assert_eq!(membership.success_threshold(epoch_1).get(), 21);
+
+    membership
+        .submit_delegation_updates(
+            epoch_2,
+            vec![
+                Delegation {
+                    delegator: b"alice".to_vec(),
+                    operator: keys[3],
+                    amount: 20,
+                },
+                Delegation {
+                    delegator: b"bob".to_vec(),
+                    operator: keys[0],
+                    amount: 5,
+                },
+            ],
+        )
+        .unwrap();
+
+    // Nothing changes before the epoch boundary
+    assert_eq!(membership.total_nodes(epoch_1), 3);
+    assert_eq!(membership.success_threshold(epoch_1).get(), 21);
+
+    assert_eq!(membership.total_nodes(epoch_2), 4);
+    assert_eq!(membership.delegated_stake(&keys[3], epoch_2), 20);
+    assert_eq!(
+        membership.stake(&keys[0], epoch_2).unwrap().stake(),
+        U256::from(15)
+    );
+    assert_eq!(
+        membership.stake(&keys[3], epoch_2).unwrap().stake(),
+        U256::from(20)
+    );
+    // 55 stake in total, so a quorum needs 37 and a timeout 19
+    assert_eq!(membership.success_threshold(epoch_2).get(), 37);
+    assert_eq!(membership.failure_threshold(epoch_2).get(), 19);
+    // Later epochs keep the delegations
+    assert_eq!(membership.total_nodes(EpochNumber::new(5)), 4);
+
+    // Removing a delegation and delegating to an unknown key
+    let unknown = BLSPubKey::generated_from_seed_indexed([1u8; 32], 0).0;
+    assert!(membership
+        .submit_delegation_updates(
+            EpochNumber::new(3),
+            vec![Delegation {
+                delegator: b"carol".to_vec(),
+                operator: unknown,
+                amount: 1,
+            }],
+        )
+        .is_err());
+    membership
+        .submit_delegation_updates(
+            EpochNumber::new(3),
+            vec![Delegation {
+                delegator: b"alice".to_vec(),
+                operator: keys[3],
+                amount: 0,
+            }],
+        )
+        .unwrap();
+    assert!(!membership.has_stake(&keys[3], EpochNumber::new(3)));
+    assert_eq!(membership.delegations(EpochNumber::new(3)).count(), 1);
+
+    // Updates cannot be applied to an epoch before the latest one
+    assert!(membership
+        .submit_delegation_updates(epoch_2, Vec::new())
+        .is_err());
+}
@@ -0,0 +1,55 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::collections::HashSet;
+
+use hotshot_types::{error::HotShotConfigError, traits::network::NetworkError};
+
+#[test]
+fn network_errors_have_distinct_codes_and_classifications() {
+    let errors = [
+        NetworkError::Multiple(vec![]),
+        NetworkError::ConfigError(String::new()),
+        NetworkError::MessageSendError(String::new()),
+        NetworkError::MessageReceiveError(String::new()),
+        NetworkError::Unimplemented,
+        NetworkError::ListenError(String::new()),
+        NetworkError::ChannelSendError(String::new()),
+        NetworkError::ChannelReceiveError(String::new()),
+        NetworkError::ShutDown,
+        NetworkError::FailedToSerialize(String::new()),
+        NetworkError::FailedToDeserialize(String::new()),
+        NetworkError::Timeout(String::new()),
+        NetworkError::RequestCancelled,
+        NetworkError::NotReadyYet,
+        NetworkError::LookupError(String::new()),
+        NetworkError::ConnectTimeout(String::new()),
+        NetworkError::HandshakeTimeout(String::new()),
+        NetworkError::IdentifyTimeout(String::new()),
+    ];
+    let codes = errors
+        .iter()
+        .map(NetworkError::code)
+        .collect::<HashSet<_>>();
+    assert_eq!(codes.len(), errors.len());
+    assert!(codes.iter().all(|code| (2000..3000).contains(code)));
+
+    assert!(NetworkError::Timeout(String::new()).is_retryable());
+    assert!(!NetworkError::ShutDown.is_retryable());
+    assert!(!NetworkError::Multiple(vec![]).is_retryable());
+    assert!(NetworkError::Multiple(vec![
+        NetworkError::NotReadyYet,
+        NetworkError::RequestCancelled
+    ])
+    .is_retryable());
+    assert!(!NetworkError::Multiple(vec![
+        NetworkError::NotReadyYet,
+        NetworkError::FailedToSerialize(String::new())
+    ])
+    .is_retryable());
+
+    assert_eq!(HotShotConfigError::EmptyStakeTable.code(), 4001);
+}
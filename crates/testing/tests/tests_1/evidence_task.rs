@@ -0,0 +1,163 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::tasks::task_state::CreateTaskState;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_macros::run_test;
+use hotshot_task_impls::{events::HotShotEvent::*, evidence::EvidenceTaskState};
+use hotshot_testing::{
+    helpers::build_system_handle,
+    script::{Expectations, InputOrder, TaskScript},
+    serial,
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    evidence::Violation,
+    simple_vote::{QuorumData2, QuorumVote2},
+    traits::consensus_api::ConsensusApi,
+    vote::{HasViewNumber, Vote},
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_evidence_task_double_vote() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(membership);
+    let mut votes = Vec::new();
+    let mut leaves = Vec::new();
+    for view in (&mut generator).take(2).collect::<Vec<_>>().await {
+        votes.push(view.create_quorum_vote(&handle).await);
+        leaves.push(view.leaf.clone());
+    }
+
+    // A second vote in the first view, for a different leaf.
+    let conflicting_vote = QuorumVote2::<TestTypes>::create_signed_vote(
+        QuorumData2 {
+            leaf_commit: leaves[1].commit(),
+            epoch: votes[0].date().epoch,
+        },
+        votes[0].view_number(),
+        &handle.public_key(),
+        handle.private_key(),
+        &handle.hotshot.upgrade_lock,
+    )
+    .await
+    .expect("Failed to generate a signature on QuorumVote");
+
+    let inputs = vec![serial![
+        QuorumVoteRecv(votes[0].clone()),
+        QuorumVoteRecv(votes[0].clone()),
+        QuorumVoteRecv(conflicting_vote.clone()),
+    ]];
+
+    let expectations = vec![Expectations::from_outputs(vec![])];
+
+    let state =
+        EvidenceTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    let mut script = TaskScript {
+        timeout: Duration::from_millis(35),
+        state,
+        expectations,
+    };
+    run_test![inputs, script].await;
+
+    let evidence = handle.collected_evidence().await;
+    assert_eq!(evidence.len(), 1);
+    assert!(evidence[0].is_signed_by_reporter());
+    assert_eq!(evidence[0].evidence.offender, handle.public_key());
+    assert_eq!(
+        evidence[0].evidence.violation,
+        Violation::DoubleVote {
+            first: votes[0].clone(),
+            second: conflicting_vote,
+        }
+    );
+    assert_eq!(
+        handle.storage().read().await.evidence_cloned().await,
+        evidence
+    );
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_evidence_task_ignores_votes_it_cannot_hold_against_anyone() {
+    use hotshot_types::{
+        data::ViewNumber,
+        signature_key::BLSPubKey,
+        traits::{node_implementation::ConsensusTime, signature_key::SignatureKey},
+    };
+
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(membership);
+    let mut votes = Vec::new();
+    let mut leaves = Vec::new();
+    for view in (&mut generator).take(2).collect::<Vec<_>>().await {
+        votes.push(view.create_quorum_vote(&handle).await);
+        leaves.push(view.leaf.clone());
+    }
+
+    let (unstaked_key, unstaked_private_key) =
+        BLSPubKey::generated_from_seed_indexed([0u8; 32], 100);
+    let far_view = ViewNumber::new(1_000);
+    let mut inputs = Vec::new();
+    for leaf in &leaves {
+        let data = QuorumData2 {
+            leaf_commit: leaf.commit(),
+            epoch: votes[0].date().epoch,
+        };
+        // Double votes by a node without stake, and for a view far ahead of ours
+        inputs.push(QuorumVoteRecv(
+            QuorumVote2::<TestTypes>::create_signed_vote(
+                data.clone(),
+                votes[0].view_number(),
+                &unstaked_key,
+                &unstaked_private_key,
+                &handle.hotshot.upgrade_lock,
+            )
+            .await
+            .unwrap(),
+        ));
+        inputs.push(QuorumVoteRecv(
+            QuorumVote2::<TestTypes>::create_signed_vote(
+                data,
+                far_view,
+                &handle.public_key(),
+                handle.private_key(),
+                &handle.hotshot.upgrade_lock,
+            )
+            .await
+            .unwrap(),
+        ));
+    }
+
+    let state =
+        EvidenceTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    let mut script = TaskScript {
+        timeout: Duration::from_millis(35),
+        state,
+        expectations: vec![Expectations::from_outputs(vec![])],
+    };
+    run_test![vec![InputOrder::Serial(inputs)], script].await;
+
+    assert!(script.state.votes.is_empty());
+    assert!(handle.collected_evidence().await.is_empty());
+}
@@ -0,0 +1,32 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_types::fair_queue::FairQueue;
+
+#[test]
+fn chatty_peer_cannot_starve_others() {
+    let mut queue = FairQueue::new(3, 5);
+
+    // `a` fills its own buffer, after which its items are dropped
+    assert!((0..3).all(|i| queue.push("a", i)));
+    assert!(!queue.push("a", 3));
+    assert!(queue.push("b", 10));
+    assert!(queue.push("c", 20));
+    assert_eq!(queue.len(), 5);
+
+    // The queue is full, so `b` gets room by dropping the newest item of `a`, but `a` may not
+    // drop an item of a peer with no more buffered than itself
+    assert!(queue.push("b", 11));
+    assert!(!queue.push("a", 4));
+
+    // drained one item per peer at a time, in the order the peers arrived
+    let drained: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(
+        drained,
+        vec![("a", 0), ("b", 10), ("c", 20), ("a", 1), ("b", 11)]
+    );
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    data::Leaf2, event::LeafInfo, finality::FinalityProof, simple_certificate::QuorumCertificate2,
+    traits::states::ValidatedState,
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_finality_stream_replay() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let mut leaves = Vec::new();
+    for view in (&mut generator).take(4).collect::<Vec<_>>().await {
+        leaves.push(view.leaf.clone());
+    }
+
+    // Decide the first three leaves. The decide QC is the one certifying the newest leaf.
+    let leaf_chain: Vec<_> = leaves[..3]
+        .iter()
+        .rev()
+        .map(|leaf| {
+            LeafInfo::new(
+                leaf.clone(),
+                Arc::new(TestValidatedState::default()),
+                None,
+                None,
+            )
+        })
+        .collect();
+    let decide_qc = leaves[3].justify_qc();
+    handle
+        .hotshot
+        .finality_log
+        .write()
+        .await
+        .record(&leaf_chain, &decide_qc);
+
+    let instance_state = handle.hotshot.instance_state();
+    let (genesis_state, _) = TestValidatedState::genesis(&instance_state);
+    let genesis_leaf = Leaf2::genesis(&genesis_state, &instance_state).await;
+
+    let mut stream = handle.finality_stream(Some(leaves[1].view_number())).await;
+    for leaf in &leaves[1..3] {
+        let (finalized_leaf, qc, proof) = stream.next().await.unwrap();
+        assert_eq!(finalized_leaf, *leaf);
+        assert_eq!(qc.data.leaf_commit, leaf.commit());
+        assert!(
+            proof
+                .verify_with_membership(
+                    leaf,
+                    genesis_leaf.commit(),
+                    &*membership.read().await,
+                    &handle.hotshot.upgrade_lock
+                )
+                .await
+        );
+        assert!(
+            !proof
+                .verify_with_membership(
+                    &leaves[0],
+                    genesis_leaf.commit(),
+                    &*membership.read().await,
+                    &handle.hotshot.upgrade_lock
+                )
+                .await
+        );
+    }
+
+    // Only the genesis leaf is final without signatures
+    let genesis_proof = FinalityProof::from_qc(
+        &QuorumCertificate2::genesis::<TestVersions>(&genesis_state, &instance_state).await,
+    );
+    assert!(
+        genesis_proof
+            .verify_with_membership(
+                &genesis_leaf,
+                genesis_leaf.commit(),
+                &*membership.read().await,
+                &handle.hotshot.upgrade_lock
+            )
+            .await
+    );
+    let forged = FinalityProof {
+        leaf_commit: leaves[1].commit(),
+        ..genesis_proof
+    };
+    assert!(
+        !forged
+            .verify_with_membership(
+                &leaves[1],
+                genesis_leaf.commit(),
+                &*membership.read().await,
+                &handle.hotshot.upgrade_lock
+            )
+            .await
+    );
+}
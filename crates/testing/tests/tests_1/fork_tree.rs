@@ -0,0 +1,85 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fork_tree_shows_preferred_branch() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    // Build the chain 1 <- 2 <- 3 <- 4, and a fork 2 <- 5
+    let mut generator = TestViewGenerator::generate(membership);
+    let mut views = Vec::new();
+    for view in (&mut generator).take(4).collect::<Vec<_>>().await {
+        views.push(view);
+    }
+    generator.next_from_ancestor_view(views[1].clone()).await;
+    views.push(generator.current_view.clone().unwrap());
+
+    let consensus = handle.hotshot.consensus();
+    let mut consensus_writer = consensus.write().await;
+    for view in &views {
+        consensus_writer
+            .update_leaf(
+                view.leaf.clone(),
+                Arc::new(TestValidatedState::default()),
+                None,
+            )
+            .unwrap();
+    }
+    // The leaf of view 4 carries the QC for view 3
+    consensus_writer
+        .update_high_qc(views[3].leaf.justify_qc())
+        .unwrap();
+    let fork_tree = consensus_writer.fork_tree();
+    drop(consensus_writer);
+
+    let commits: Vec<_> = views.iter().map(|view| view.leaf.commit()).collect();
+    assert_eq!(fork_tree.preferred_tip, commits[2]);
+
+    // Views 1 to 3 are on the preferred branch; the uncertified view 4 and the fork are not
+    for commit in &commits[..3] {
+        assert!(fork_tree.node(commit).unwrap().preferred);
+    }
+    for commit in &commits[3..] {
+        assert!(!fork_tree.node(commit).unwrap().preferred);
+    }
+
+    // View 2 has two children, and only its first child is certified
+    let view_2 = fork_tree.node(&commits[1]).unwrap();
+    assert_eq!(view_2.children, vec![commits[2], commits[4]]);
+    assert!(fork_tree
+        .node(&commits[2])
+        .unwrap()
+        .certified_in_view
+        .is_some());
+    assert!(fork_tree
+        .node(&commits[4])
+        .unwrap()
+        .certified_in_view
+        .is_none());
+
+    let tips: Vec<_> = fork_tree
+        .tips()
+        .map(|node| node.leaf_commit)
+        .filter(|commit| commits.contains(commit))
+        .collect();
+    assert_eq!(tips, vec![commits[3], commits[4]]);
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::genesis::{Genesis, GenesisBuilder, GenesisFile};
+use hotshot_example_types::{
+    node_types::{TestTypes, TestVersions},
+    state_types::TestInstanceState,
+};
+use hotshot_types::{
+    data::ViewNumber, hotshot_config_file::HotShotConfigFile, signature_key::BLSPubKey,
+    traits::node_implementation::ConsensusTime, ValidatorConfig,
+};
+
+/// The validators of the ceremony, the first four of which are on the DA committee
+fn validators() -> Vec<ValidatorConfig<BLSPubKey>> {
+    (0..10)
+        .map(|node_id| {
+            ValidatorConfig::generated_from_seed_indexed([0u8; 32], node_id, 1, node_id < 4)
+        })
+        .collect()
+}
+
+/// Run the ceremony for `validators`
+async fn ceremony(validators: &[ValidatorConfig<BLSPubKey>]) -> anyhow::Result<Genesis<TestTypes>> {
+    let config = HotShotConfigFile::<BLSPubKey>::hotshot_config_5_nodes_10_da().into();
+    let mut builder = GenesisBuilder::<TestTypes>::new(config, TestInstanceState::default());
+    for validator in validators {
+        let state_ver_key = validator.state_key_pair.0.ver_key();
+        builder = if validator.is_da {
+            builder.da_validator(validator.public_key, validator.stake_value, state_ver_key)
+        } else {
+            builder.validator(validator.public_key, validator.stake_value, state_ver_key)
+        };
+    }
+    builder.build::<TestVersions>().await
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_genesis_ceremony_is_deterministic() {
+    hotshot::helpers::initialize_logging();
+
+    let validators = validators();
+    let genesis = ceremony(&validators).await.unwrap();
+    assert_eq!(genesis.config.num_nodes_with_stake.get(), 10);
+    assert_eq!(genesis.config.known_da_nodes.len(), 4);
+    assert_eq!(genesis.config.da_staked_committee_size, 4);
+    assert_eq!(
+        genesis.qc.data.leaf_commit,
+        committable::Committable::commit(&genesis.leaf)
+    );
+
+    // Rerunning the ceremony produces the same genesis for every validator
+    let files = genesis.files();
+    let rerun = ceremony(&validators).await.unwrap().files();
+    assert_eq!(files.len(), 10);
+    let digest = files[0].digest().unwrap();
+    for (file, rerun) in files.iter().zip(&rerun) {
+        assert_eq!(file.digest().unwrap(), digest);
+        assert_eq!(rerun.digest().unwrap(), digest);
+        assert_eq!(file.qc, rerun.qc);
+    }
+
+    // Validators added twice or without stake are refused
+    let mut duplicated = validators.clone();
+    duplicated.push(validators[3].clone());
+    assert!(ceremony(&duplicated).await.is_err());
+    let mut unstaked = validators.clone();
+    unstaked[5].stake_value = 0;
+    assert!(ceremony(&unstaked).await.is_err());
+    assert!(ceremony(&[]).await.is_err());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_genesis_files_are_checked_at_startup() {
+    hotshot::helpers::initialize_logging();
+
+    let validators = validators();
+    let genesis = ceremony(&validators).await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("hotshot-genesis-{}", std::process::id()));
+    let paths = genesis.write_files(&dir).await.unwrap();
+    assert_eq!(paths.len(), 10);
+
+    let file = GenesisFile::<TestTypes>::load(&paths[3]).await.unwrap();
+    assert_eq!(file.node_index, 3);
+    assert_eq!(file.digest().unwrap(), genesis.files()[3].digest().unwrap());
+
+    // Another validator cannot start from the file of validator 3
+    assert!(file
+        .clone()
+        .into_initializer::<TestVersions>(&validators[4].public_key, TestInstanceState::default())
+        .await
+        .is_err());
+
+    // Nor can validator 3 from a file placing it elsewhere in the stake table
+    let mut misplaced = file.clone();
+    misplaced.node_index = 4;
+    assert!(misplaced
+        .into_initializer::<TestVersions>(&validators[3].public_key, TestInstanceState::default())
+        .await
+        .is_err());
+
+    // Nor from a genesis QC which does not follow from the initial state
+    let mut forged = file.clone();
+    forged.qc.view_number = ViewNumber::new(1);
+    assert!(forged
+        .into_initializer::<TestVersions>(&validators[3].public_key, TestInstanceState::default())
+        .await
+        .is_err());
+
+    let (config, _initializer) = file
+        .into_initializer::<TestVersions>(&validators[3].public_key, TestInstanceState::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        config.known_nodes_with_stake,
+        genesis.config.known_nodes_with_stake
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
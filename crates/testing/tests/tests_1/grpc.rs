@@ -0,0 +1,131 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+#![cfg(feature = "grpc")]
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::grpc::{
+    proto::{node_server::Node, GetBlockByViewRequest, GetValidatorSetRequest},
+    NodeService,
+};
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::StakeTableEntryType,
+    },
+};
+use tonic::{Code, Request};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_grpc_blocks_of_retained_views() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let service = NodeService::new(Arc::clone(&handle.hotshot));
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(2).collect::<Vec<_>>().await;
+    {
+        let consensus = handle.hotshot.consensus();
+        let mut consensus_writer = consensus.write().await;
+        for view in &views {
+            consensus_writer
+                .update_leaf(
+                    view.leaf.clone(),
+                    Arc::new(TestValidatedState::default()),
+                    None,
+                )
+                .unwrap();
+        }
+    }
+
+    for view in &views {
+        let leaf = &view.leaf;
+        let block = service
+            .get_block_by_view(Request::new(GetBlockByViewRequest {
+                view: *leaf.view_number(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(block.view, *leaf.view_number());
+        assert_eq!(block.height, leaf.height());
+        assert_eq!(block.leaf_commitment, leaf.commit().to_string());
+        assert_eq!(
+            block.header_json,
+            serde_json::to_string(leaf.block_header()).unwrap()
+        );
+        assert_eq!(block.payload_available, leaf.block_payload().is_some());
+        if !block.payload_available {
+            assert!(block.transaction_commitments.is_empty());
+        }
+    }
+
+    // A view the node never saw is not found
+    let status = service
+        .get_block_by_view(Request::new(GetBlockByViewRequest { view: 1000 }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_grpc_validator_set_matches_the_stake_tables() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let service = NodeService::new(Arc::clone(&handle.hotshot));
+
+    let epoch = <TestTypes as NodeType>::Epoch::new(0);
+    let (stake_table, da_stake_table) = {
+        let memberships = handle.hotshot.memberships.read().await;
+        (
+            memberships.stake_table(epoch),
+            memberships.da_stake_table(epoch),
+        )
+    };
+
+    let validator_set = service
+        .get_validator_set(Request::new(GetValidatorSetRequest { epoch: Some(0) }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(validator_set.epoch, 0);
+    assert_eq!(validator_set.validators.len(), stake_table.len());
+    for (validator, entry) in validator_set.validators.iter().zip(&stake_table) {
+        let key: BLSPubKey = entry.public_key();
+        assert_eq!(validator.public_key, key.to_string());
+        assert_eq!(validator.stake, entry.stake().to_string());
+        assert_eq!(
+            validator.da,
+            da_stake_table
+                .iter()
+                .any(|da_entry| StakeTableEntryType::<BLSPubKey>::public_key(da_entry) == key)
+        );
+    }
+
+    // Without an epoch, the current one is used
+    let current = service
+        .get_validator_set(Request::new(GetValidatorSetRequest { epoch: None }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(current, validator_set);
+}
@@ -0,0 +1,37 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_types::journal::{
+    read_journal, JournalConfig, JournalEntry, JournalRecord, JournalWriter,
+};
+
+#[test]
+fn rotated_journal_reads_back_in_order() {
+    let dir = std::env::temp_dir().join(format!("hotshot-journal-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let config = JournalConfig {
+        dir: dir.clone(),
+        max_file_bytes: 100,
+        max_files: 3,
+    };
+
+    let mut writer = JournalWriter::open(config).unwrap();
+    for view in 0..20 {
+        writer
+            .append(&JournalRecord::now(view, JournalEntry::ViewTimeout))
+            .unwrap();
+    }
+
+    let records = read_journal(&dir).unwrap();
+    assert!(std::fs::read_dir(&dir).unwrap().count() <= 3);
+    assert!(records.len() < 20);
+    assert!(records
+        .windows(2)
+        .all(|pair| pair[0].view + 1 == pair[1].view));
+    assert_eq!(records.last().unwrap().view, 19);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use bitvec::bitvec;
+use hotshot_types::{
+    l1_verification::{QcCalldata, Word},
+    signature_key::BLSPubKey,
+    stake_table::StakeTableEntry,
+    traits::signature_key::SignatureKey,
+};
+use primitive_types::U256;
+
+/// The big endian word of `x`
+fn u256_word(x: U256) -> Word {
+    let mut word = [0u8; 32];
+    x.to_big_endian(&mut word);
+    word
+}
+
+/// An assembled signature over `message` by the keys of `signers` among four keys of one
+/// stake each, with the stake table
+fn signed(
+    message: Word,
+    signers: &bitvec::vec::BitVec,
+) -> (
+    <BLSPubKey as SignatureKey>::QcType,
+    Vec<StakeTableEntry<BLSPubKey>>,
+) {
+    let keys = (0..4)
+        .map(|index| BLSPubKey::generated_from_seed_indexed([0u8; 32], index))
+        .collect::<Vec<_>>();
+    let stake_table = keys
+        .iter()
+        .map(|(key, _)| key.stake_table_entry(1))
+        .collect::<Vec<_>>();
+    let signatures = keys
+        .iter()
+        .zip(signers.iter())
+        .filter(|(_, signed)| **signed)
+        .map(|((_, private_key), _)| BLSPubKey::sign(private_key, &message).unwrap())
+        .collect::<Vec<_>>();
+    let params = BLSPubKey::public_parameter(stake_table.clone(), U256::from(3u8));
+    let qc = BLSPubKey::assemble(&params, signers, &signatures).unwrap();
+    (qc, stake_table)
+}
+
+#[test]
+fn calldata_verifies_like_the_certificate() {
+    let message = [7u8; 32];
+    let (qc, stake_table) = signed(message, &bitvec![1, 0, 1, 1]);
+    let params = BLSPubKey::public_parameter(stake_table.clone(), U256::from(3u8));
+    assert!(BLSPubKey::check(&params, &message, &qc));
+
+    let calldata = QcCalldata::new(message, &qc, &stake_table, U256::from(3u8)).unwrap();
+    assert_eq!(calldata.signer_bitmap[0][31], 0b1101);
+    calldata.verify().unwrap();
+
+    // the signers must reach the threshold
+    let mut short = calldata.clone();
+    short.threshold = u256_word(U256::from(4u8));
+    assert!(short.verify().is_err());
+
+    // the signature must be by the signers in the bitmap
+    let mut other_signers = calldata.clone();
+    other_signers.signer_bitmap[0][31] = 0b1110;
+    assert!(other_signers.verify().is_err());
+
+    // the signature must be over the message
+    let mut other_message = calldata.clone();
+    other_message.message = [8u8; 32];
+    assert!(other_message.verify().is_err());
+
+    // coordinates must be field elements
+    let mut unreduced = calldata.clone();
+    unreduced.signature[0] = [0xff; 32];
+    assert!(unreduced.verify().is_err());
+}
+
+#[test]
+fn calldata_is_abi_encoded() {
+    let message = [7u8; 32];
+    let (qc, stake_table) = signed(message, &bitvec![1, 1, 1, 0]);
+    let calldata = QcCalldata::new(message, &qc, &stake_table, U256::from(3u8)).unwrap();
+    let encoded = calldata.encode();
+
+    // selector, 7 head words, then the bitmap, keys and stakes with their lengths
+    assert_eq!(
+        encoded.len(),
+        4 + 32 * (7 + (1 + 1) + (1 + 4 * 4) + (1 + 4))
+    );
+    assert_eq!(&encoded[4..36], &message);
+    let offset = |index: usize| U256::from_big_endian(&encoded[4 + 32 * index..][..32]);
+    assert_eq!(offset(3), U256::from(7 * 32));
+    assert_eq!(offset(4), U256::from(9 * 32));
+    assert_eq!(offset(5), U256::from(26 * 32));
+    assert_eq!(&encoded[4 + 32 * 6..][..32], &u256_word(U256::from(3u8)));
+}
@@ -11,6 +11,7 @@ use hotshot_testing::{
     block_builder::SimpleBuilderImplementation,
     completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
     overall_safety_task::OverallSafetyPropertiesDescription,
+    resource_task::ResourceBudget,
     spinning_task::{ChangeNode, NodeAction, SpinningTaskDescription},
     test_builder::{TestDescription, TimingData},
 };
@@ -102,8 +103,15 @@ async fn libp2p_network_failures_2() {
 async fn test_stress_libp2p_network() {
     hotshot::helpers::initialize_logging();
 
-    let metadata: TestDescription<TestTypes, Libp2pImpl, TestVersions> =
+    let mut metadata: TestDescription<TestTypes, Libp2pImpl, TestVersions> =
         TestDescription::default_stress();
+    // a hundred nodes should not leak connections or tasks over the run
+    metadata.resource_budget = Some(ResourceBudget {
+        max_rss_bytes: Some(8 << 30),
+        max_open_fds: Some(10_000),
+        max_tasks: Some(50_000),
+        ..ResourceBudget::default()
+    });
     metadata
         .gen_launcher(0)
         .launch()
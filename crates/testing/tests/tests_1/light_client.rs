@@ -0,0 +1,334 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_lock::RwLock;
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::traits::election::static_committee::StaticCommittee;
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_light_client::{
+    ChainExtension, EpochStakeTable, EpochTransition, LightClient, LightClientError,
+    TxInclusionProof,
+};
+use hotshot_testing::{
+    helpers::{build_cert, build_system_handle, key_pair_for_id},
+    view_generator::{TestView, TestViewGenerator},
+};
+use hotshot_types::{
+    data::{EpochNumber, Leaf2, ViewNumber},
+    signature_key::BLSPubKey,
+    simple_certificate::NextEpochQuorumCertificate2,
+    simple_vote::{NextEpochQuorumData2, NextEpochQuorumVote2, QuorumData2},
+    traits::{
+        election::Membership, node_implementation::ConsensusTime, signature_key::SignatureKey,
+    },
+    vote::Certificate,
+    PeerConfig,
+};
+
+/// A light client anchored at genesis, and the first four views after it, the second of which
+/// includes `transaction`
+async fn setup(
+    transaction: &TestTransaction,
+) -> (LightClient<TestTypes, TestVersions>, Vec<TestView>) {
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let mut views = (&mut generator).take(1).collect::<Vec<_>>().await;
+    generator.add_transactions(vec![transaction.clone()]);
+    views.extend((&mut generator).take(1).collect::<Vec<_>>().await);
+    generator.add_transactions(Vec::new());
+    views.extend((&mut generator).take(2).collect::<Vec<_>>().await);
+
+    let genesis = Leaf2::genesis(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await;
+    let stake_tables = BTreeMap::from([(
+        EpochNumber::new(0),
+        EpochStakeTable::from_membership(&*membership.read().await, EpochNumber::new(0)),
+    )]);
+    let client = LightClient::new(
+        &genesis,
+        stake_tables,
+        0,
+        handle.hotshot.upgrade_lock.clone(),
+    )
+    .unwrap();
+
+    (client, views)
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_light_client_follows_chain_and_verifies_inclusion() {
+    hotshot::helpers::initialize_logging();
+
+    let transaction = TestTransaction::new(vec![1, 2, 3]);
+    let (mut client, views) = setup(&transaction).await;
+
+    client
+        .extend(&ChainExtension {
+            leaves: vec![views[0].leaf.clone(), views[1].leaf.clone()],
+            child: views[2].leaf.clone(),
+            child_qc: views[3].leaf.justify_qc(),
+        })
+        .await
+        .expect("Decided leaves were rejected");
+
+    let height = views[1].leaf.height();
+    assert_eq!(client.latest_height(), height);
+    assert_eq!(client.latest_qc(), &views[2].leaf.justify_qc());
+
+    let proof = TxInclusionProof {
+        height,
+        payload: views[1].da_proposal.data.encoded_transactions.to_vec(),
+        index: 0,
+        transaction: transaction.clone(),
+    };
+    client.verify_inclusion(&proof).unwrap();
+
+    assert_eq!(
+        client.verify_inclusion(&TxInclusionProof {
+            index: 1,
+            ..proof.clone()
+        }),
+        Err(LightClientError::TransactionNotIncluded { height, index: 1 })
+    );
+    assert_eq!(
+        client.verify_inclusion(&TxInclusionProof {
+            transaction: TestTransaction::new(vec![4]),
+            ..proof.clone()
+        }),
+        Err(LightClientError::TransactionNotIncluded { height, index: 0 })
+    );
+    assert_eq!(
+        client.verify_inclusion(&TxInclusionProof {
+            payload: views[0].da_proposal.data.encoded_transactions.to_vec(),
+            ..proof.clone()
+        }),
+        Err(LightClientError::PayloadMismatch(height))
+    );
+
+    // The certified child is not decided yet
+    assert_eq!(
+        client.verify_inclusion(&TxInclusionProof {
+            height: views[2].leaf.height(),
+            ..proof
+        }),
+        Err(LightClientError::UnknownHeight(views[2].leaf.height()))
+    );
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_light_client_rejects_invalid_extensions() {
+    hotshot::helpers::initialize_logging();
+
+    let (mut client, views) = setup(&TestTransaction::new(vec![0])).await;
+
+    // A gap in the chain
+    assert_eq!(
+        client
+            .extend(&ChainExtension {
+                leaves: vec![views[1].leaf.clone()],
+                child: views[2].leaf.clone(),
+                child_qc: views[3].leaf.justify_qc(),
+            })
+            .await,
+        Err(LightClientError::BrokenChain {
+            height: views[1].leaf.height()
+        })
+    );
+
+    // A certified leaf without a certified child is not decided
+    assert_eq!(
+        client
+            .extend(&ChainExtension {
+                leaves: vec![views[0].leaf.clone(), views[1].leaf.clone()],
+                child: views[2].leaf.clone(),
+                child_qc: views[2].leaf.justify_qc(),
+            })
+            .await,
+        Err(LightClientError::NotDecided {
+            height: views[1].leaf.height()
+        })
+    );
+
+    // A certificate without signatures
+    let mut forged_qc = views[3].leaf.justify_qc();
+    forged_qc.signatures = None;
+    assert_eq!(
+        client
+            .extend(&ChainExtension {
+                leaves: vec![views[0].leaf.clone(), views[1].leaf.clone()],
+                child: views[2].leaf.clone(),
+                child_qc: forged_qc,
+            })
+            .await,
+        Err(LightClientError::InvalidCertificate {
+            view: *views[2].view_number,
+            epoch: 0
+        })
+    );
+
+    // Nothing was accepted
+    assert_eq!(client.latest_height(), 0);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_light_client_rejects_forged_stake_table() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let upgrade_lock = handle.hotshot.upgrade_lock.clone();
+    let epoch = EpochNumber::new(0);
+    let genesis = Leaf2::genesis(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await;
+    let stake_tables = BTreeMap::from([(
+        epoch,
+        EpochStakeTable::from_membership(&*handle.hotshot.memberships.read().await, epoch),
+    )]);
+    // With three blocks an epoch, the genesis block is the last block of epoch 0
+    let mut client = LightClient::new(&genesis, stake_tables, 3, upgrade_lock.clone()).unwrap();
+
+    // Three members of the quorum claim to be the whole quorum of the next epoch, and sign the
+    // last block of epoch 0 as such
+    let forged_peers: Vec<PeerConfig<BLSPubKey>> = (0..3)
+        .map(|i| PeerConfig {
+            stake_table_entry: BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .stake_table_entry(1),
+            ..PeerConfig::default()
+        })
+        .collect();
+    let forged_membership = Arc::new(RwLock::new(StaticCommittee::<TestTypes>::new(
+        forged_peers.clone(),
+        forged_peers,
+    )));
+    let forged = EpochStakeTable::from_membership(&*forged_membership.read().await, epoch + 1);
+    let (private_key, public_key) = key_pair_for_id::<TestTypes>(0);
+    let qc = build_cert::<
+        TestTypes,
+        TestVersions,
+        NextEpochQuorumData2<TestTypes>,
+        NextEpochQuorumVote2<TestTypes>,
+        NextEpochQuorumCertificate2<TestTypes>,
+    >(
+        QuorumData2 {
+            leaf_commit: genesis.commit(),
+            epoch,
+        }
+        .into(),
+        &forged_membership,
+        ViewNumber::new(1),
+        epoch,
+        &public_key,
+        &private_key,
+        &upgrade_lock,
+    )
+    .await;
+
+    // The certificate checks out against the forged stake table and its threshold
+    assert!(
+        qc.is_valid_cert(
+            forged.stake_table.clone(),
+            forged.threshold(),
+            &upgrade_lock
+        )
+        .await
+    );
+
+    // but the last block of epoch 0 does not commit to that stake table
+    assert_eq!(
+        client
+            .transition_epoch(EpochTransition {
+                epoch: epoch + 1,
+                stake_table: forged,
+                qc,
+            })
+            .await,
+        Err(LightClientError::StakeTableMismatch(1))
+    );
+    assert_eq!(client.latest_epoch(), Some(epoch));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_light_client_limits_the_epoch_transition() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let views = (&mut generator).take(5).collect::<Vec<_>>().await;
+
+    let genesis = Leaf2::genesis(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await;
+    let epoch = EpochNumber::new(0);
+    let stake_tables = BTreeMap::from([(
+        epoch,
+        EpochStakeTable::from_membership(&*membership.read().await, epoch),
+    )]);
+    // With four blocks an epoch, the genesis block is the last block of epoch 0, and the leaves
+    // after it are in epoch 1
+    let mut client = LightClient::new(
+        &genesis,
+        stake_tables,
+        4,
+        handle.hotshot.upgrade_lock.clone(),
+    )
+    .unwrap();
+    assert_eq!(views[3].leaf.height(), 4);
+
+    // The quorum of epoch 0 still certifies the transition blocks
+    client
+        .extend(&ChainExtension {
+            leaves: vec![views[0].leaf.clone(), views[1].leaf.clone()],
+            child: views[2].leaf.clone(),
+            child_qc: views[3].leaf.justify_qc(),
+        })
+        .await
+        .expect("Transition blocks were rejected");
+
+    // but not the blocks of epoch 1 after them
+    assert_eq!(
+        client
+            .extend(&ChainExtension {
+                leaves: vec![views[2].leaf.clone()],
+                child: views[3].leaf.clone(),
+                child_qc: views[4].leaf.justify_qc(),
+            })
+            .await,
+        Err(LightClientError::WrongEpoch {
+            height: 4,
+            epoch: 0,
+            expected: 1,
+        })
+    );
+    assert_eq!(client.latest_height(), 2);
+}
@@ -0,0 +1,45 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use bitvec::bitvec;
+use hotshot_types::{
+    liveness::LivenessTracker, signature_key::BLSPubKey, traits::signature_key::SignatureKey,
+};
+
+#[test]
+fn test_liveness_scores_track_participation() {
+    let keys: Vec<BLSPubKey> = (0..4)
+        .map(|i| BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0)
+        .collect();
+    let stake_table: Vec<_> = keys.iter().map(|key| key.stake_table_entry(1)).collect();
+
+    let mut tracker = LivenessTracker::<BLSPubKey>::default();
+    assert!((tracker.score(&keys[3]) - 1.0).abs() < f64::EPSILON);
+
+    // Node 3 never gets its vote into a certificate
+    let signers = bitvec![1, 1, 1, 0];
+    for _ in 0..10 {
+        tracker.record_certificate(&stake_table, &signers);
+    }
+    for key in &keys[..3] {
+        assert!((tracker.score(key) - 1.0).abs() < f64::EPSILON);
+        assert_eq!(tracker.record(key).unwrap().included_votes, 10);
+    }
+    assert!(tracker.score(&keys[3]) < 0.5);
+    assert_eq!(tracker.record(&keys[3]).unwrap().missed_votes, 10);
+
+    // Node 0 fails as a view sync relay a few times in a row, then recovers
+    for _ in 0..7 {
+        tracker.record_relay(&keys[0], false);
+    }
+    assert!(tracker.score(&keys[0]) < 0.5);
+    for _ in 0..10 {
+        tracker.record_relay(&keys[0], true);
+    }
+    assert!(tracker.score(&keys[0]) > 0.5);
+    assert_eq!(tracker.record(&keys[0]).unwrap().relay_failures, 7);
+    assert_eq!(tracker.record(&keys[0]).unwrap().relay_successes, 10);
+}
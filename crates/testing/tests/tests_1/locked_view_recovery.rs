@@ -0,0 +1,121 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::StreamExt;
+use hotshot::HotShotInitializer;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+    storage_types::TestStorage,
+};
+use hotshot_testing::{
+    helpers::{build_system_handle_from_launcher, build_system_handle_with_initializer},
+    test_builder::TestDescription,
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    data::{Leaf2, ViewNumber},
+    event::HotShotAction,
+    traits::{node_implementation::ConsensusTime, storage::Storage},
+};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_restart_mid_view_restores_lock_and_does_not_double_vote() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let launcher =
+        TestDescription::<TestTypes, MemoryImpl, TestVersions>::default_multiple_rounds()
+            .gen_launcher(node_id);
+    let mut handle = build_system_handle_from_launcher::<TestTypes, MemoryImpl, TestVersions>(
+        node_id, &launcher,
+    )
+    .await
+    .0;
+    let storage = handle.storage().read().await.clone();
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(4).collect::<Vec<_>>().await;
+
+    // In view 4, the node sees the QC for view 3, locks on view 2 and votes, then crashes before
+    // the view ends
+    let high_qc = views[3].quorum_proposal.data.justify_qc.clone();
+    let locked_view = views[2].quorum_proposal.data.justify_qc.view_number;
+    let vote_view = views[3].view_number;
+    storage.update_high_qc2(high_qc.clone()).await.unwrap();
+    storage.update_locked_view(locked_view).await.unwrap();
+    assert!(handle
+        .consensus()
+        .write()
+        .await
+        .update_action(HotShotAction::Vote, vote_view));
+    storage
+        .record_action(vote_view, HotShotAction::Vote)
+        .await
+        .unwrap();
+    handle.shut_down().await;
+
+    // The node restarts from what it persisted
+    let anchor_leaf = Leaf2::genesis(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await;
+    let initializer = HotShotInitializer::from_reload(
+        anchor_leaf,
+        TestInstanceState::default(),
+        None,
+        storage.last_actioned_view().await,
+        storage.last_actioned_epoch().await,
+        storage.last_actioned_view().await,
+        storage.locked_view().await,
+        storage.vote_intents_cloned().await,
+        storage.proposals_cloned().await,
+        storage.high_qc_cloned().await.unwrap(),
+        None,
+        None,
+        storage.checkpoint_certificate_cloned().await,
+        Vec::new(),
+        BTreeMap::new(),
+    );
+    let restarted = build_system_handle_with_initializer::<TestTypes, MemoryImpl, TestVersions>(
+        node_id,
+        &launcher,
+        storage,
+        initializer,
+    )
+    .await
+    .0;
+
+    let consensus = restarted.consensus();
+    let mut consensus = consensus.write().await;
+    assert_eq!(consensus.locked_view(), locked_view);
+    assert_eq!(consensus.high_qc(), &high_qc);
+
+    // Votes in the view of the crash, or before it, are refused before they are sent
+    assert!(!consensus.update_action(HotShotAction::Vote, vote_view));
+    assert!(!consensus.update_action(HotShotAction::Vote, locked_view));
+    assert!(consensus.update_action(HotShotAction::Vote, vote_view + 1));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stored_lock_never_moves_back() {
+    let storage = TestStorage::<TestTypes>::default();
+
+    storage
+        .update_locked_view(ViewNumber::new(5))
+        .await
+        .unwrap();
+    storage
+        .update_locked_view(ViewNumber::new(3))
+        .await
+        .unwrap();
+    assert_eq!(storage.locked_view().await, ViewNumber::new(5));
+}
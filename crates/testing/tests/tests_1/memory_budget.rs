@@ -0,0 +1,86 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    data::ViewNumber,
+    memory_budget::{MemoryBudget, MemoryBudgetConfig, MemoryComponent},
+    signature_key::BLSPubKey,
+    traits::{node_implementation::ConsensusTime, signature_key::SignatureKey},
+    vote::VerifiedVotes,
+};
+use tokio::time::timeout;
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_network_queue_waits_for_room_in_its_budget() {
+    let budget = MemoryBudget::new(
+        MemoryBudgetConfig {
+            network_queue: Some(100),
+            ..MemoryBudgetConfig::default()
+        },
+        Arc::new(ConsensusMetricsValue::default()),
+    );
+
+    // A single message larger than the budget is still taken
+    assert!(budget.try_reserve(MemoryComponent::NetworkQueue, 150));
+    assert!(!budget.try_reserve(MemoryComponent::NetworkQueue, 1));
+    assert!(budget.is_over_budget(MemoryComponent::NetworkQueue));
+
+    let waiting = tokio::spawn({
+        let budget = budget.clone();
+        async move { budget.wait_for_room(MemoryComponent::NetworkQueue).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiting.is_finished());
+
+    budget.release(MemoryComponent::NetworkQueue, 100);
+    timeout(Duration::from_secs(5), waiting)
+        .await
+        .expect("Releasing memory did not wake the waiter")
+        .unwrap();
+    assert_eq!(budget.usage(MemoryComponent::NetworkQueue), 50);
+
+    // Other components have no budget
+    assert!(budget.try_reserve(MemoryComponent::LeafStore, usize::MAX / 2));
+    assert!(!budget.is_over_budget(MemoryComponent::LeafStore));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_verified_votes_stay_within_their_budget() {
+    let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let signature = BLSPubKey::sign(&private_key, b"vote").unwrap();
+    let view = ViewNumber::new(1);
+
+    let budget = MemoryBudget::new(
+        MemoryBudgetConfig {
+            verified_votes: Some(1),
+            ..MemoryBudgetConfig::default()
+        },
+        Arc::new(ConsensusMetricsValue::default()),
+    );
+    let verified_votes = VerifiedVotes::<TestTypes>::new(budget.clone());
+
+    verified_votes
+        .record(view, key, b"first", signature.clone())
+        .await;
+    let used = budget.usage(MemoryComponent::VerifiedVotes);
+    assert!(used > 0);
+
+    // Once over budget signatures are left for the accumulator to check
+    verified_votes
+        .record(view, key, b"second", signature.clone())
+        .await;
+    assert_eq!(budget.usage(MemoryComponent::VerifiedVotes), used);
+    assert!(!verified_votes.take(view, &key, b"second", &signature).await);
+
+    assert!(verified_votes.take(view, &key, b"first", &signature).await);
+    assert_eq!(budget.usage(MemoryComponent::VerifiedVotes), 0);
+}
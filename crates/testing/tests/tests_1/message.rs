@@ -8,6 +8,7 @@
 use std::marker::PhantomData;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use committable::Committable;
 use hotshot_example_types::node_types::TestTypes;
 use hotshot_types::{
@@ -51,6 +52,8 @@ fn version_number_at_start_of_serialization() {
         kind: MessageKind::Consensus(SequencingMessage::General(
             GeneralConsensusMessage::ViewSyncCommitCertificate2(simple_certificate),
         )),
+        namespace: 0,
+        expires_after: None,
     };
     let serialized_message: Vec<u8> = Serializer::<TestVersion>::serialize(&message).unwrap();
     // The versions we've read from the message
@@ -138,3 +141,509 @@ async fn test_certificate2_validity() {
 
     assert!(leaf2.parent_commitment() == parent_leaf2.commit());
 }
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dual_decode_during_upgrade_transition() {
+    use hotshot_example_types::node_types::TestVersions;
+    use hotshot_types::{
+        message::UpgradeLock, simple_certificate::UpgradeCertificate,
+        simple_vote::UpgradeProposalData,
+    };
+
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let old_version = Version { major: 0, minor: 1 };
+    let new_version = Version { major: 0, minor: 2 };
+
+    let upgrade_data: UpgradeProposalData<TestTypes> = UpgradeProposalData {
+        old_version,
+        new_version,
+        decide_by: ConsensusTime::new(10),
+        new_version_hash: vec![],
+        old_version_last_view: ConsensusTime::new(19),
+        new_version_first_view: ConsensusTime::new(20),
+    };
+    let certificate: UpgradeCertificate<TestTypes> = SimpleCertificate::new(
+        upgrade_data.clone(),
+        upgrade_data.commit(),
+        ConsensusTime::new(5),
+        None,
+        PhantomData,
+    );
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::from_certificate(&Some(certificate));
+
+    let message_in_view = |view: u64| {
+        let data: ViewSyncCommitData2<TestTypes> = ViewSyncCommitData2 {
+            relay: 0,
+            round: ConsensusTime::new(view),
+            epoch: ConsensusTime::new(0),
+        };
+        Message {
+            sender,
+            kind: MessageKind::Consensus(SequencingMessage::General(
+                GeneralConsensusMessage::ViewSyncCommitCertificate2(SimpleCertificate::new(
+                    data.clone(),
+                    data.commit(),
+                    ConsensusTime::new(view),
+                    None,
+                    PhantomData,
+                )),
+            )),
+            namespace: 0,
+            expires_after: None,
+        }
+    };
+
+    // Just before the upgrade, a message already using the new version is accepted...
+    let early = Serializer::<StaticVersion<0, 2>>::serialize(&message_in_view(18)).unwrap();
+    assert_eq!(
+        Message::<TestTypes>::protocol_version(&early).unwrap(),
+        new_version
+    );
+    let decoded: Message<TestTypes> = upgrade_lock.deserialize(&early).await.unwrap();
+    assert_eq!(decoded, message_in_view(18));
+
+    // ...as is one just after the upgrade which still uses the old version.
+    let late = Serializer::<StaticVersion<0, 1>>::serialize(&message_in_view(22)).unwrap();
+    assert!(upgrade_lock
+        .deserialize::<Message<TestTypes>>(&late)
+        .await
+        .is_ok());
+
+    // Outside of the transition window, only the version of the view is accepted.
+    let stale = Serializer::<StaticVersion<0, 1>>::serialize(&message_in_view(30)).unwrap();
+    assert!(upgrade_lock
+        .deserialize::<Message<TestTypes>>(&stale)
+        .await
+        .is_err());
+    let premature = Serializer::<StaticVersion<0, 2>>::serialize(&message_in_view(10)).unwrap();
+    assert!(upgrade_lock
+        .deserialize::<Message<TestTypes>>(&premature)
+        .await
+        .is_err());
+
+    // The window spans as many views after the first view of the new version as before it
+    for (view, accepted) in [(15, true), (14, false)] {
+        let early = Serializer::<StaticVersion<0, 2>>::serialize(&message_in_view(view)).unwrap();
+        assert_eq!(
+            upgrade_lock
+                .deserialize::<Message<TestTypes>>(&early)
+                .await
+                .is_ok(),
+            accepted
+        );
+    }
+    for (view, accepted) in [(25, true), (26, false)] {
+        let late = Serializer::<StaticVersion<0, 1>>::serialize(&message_in_view(view)).unwrap();
+        assert_eq!(
+            upgrade_lock
+                .deserialize::<Message<TestTypes>>(&late)
+                .await
+                .is_ok(),
+            accepted
+        );
+    }
+}
+
+#[cfg(feature = "protobuf")]
+#[tokio::test(flavor = "multi_thread")]
+async fn protobuf_messages_round_trip() {
+    use hotshot_example_types::node_types::TestVersions;
+    use hotshot_types::{
+        message::{UpgradeLock, WireEncoding},
+        protobuf::{self, proto},
+    };
+    use prost::Message as _;
+
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let data: ViewSyncCommitData2<TestTypes> = ViewSyncCommitData2 {
+        relay: 0,
+        round: ConsensusTime::new(3),
+        epoch: ConsensusTime::new(0),
+    };
+    let message = Message {
+        sender,
+        kind: MessageKind::Consensus(SequencingMessage::General(
+            GeneralConsensusMessage::ViewSyncCommitCertificate2(SimpleCertificate::new(
+                data.clone(),
+                data.commit(),
+                ConsensusTime::new(3),
+                None,
+                PhantomData,
+            )),
+        )),
+        namespace: 7,
+        expires_after: None,
+    };
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+
+    let encoded = upgrade_lock
+        .serialize_as(&message, WireEncoding::Protobuf)
+        .await
+        .unwrap();
+    assert!(protobuf::is_protobuf(&encoded));
+
+    // Tooling can read the summary without knowing the message types...
+    let decoded = proto::Message::decode(&encoded[protobuf::MAGIC.len()..]).unwrap();
+    assert_eq!((decoded.view, decoded.namespace), (3, 7));
+    assert_eq!(
+        decoded.summary,
+        Some(proto::message::Summary::Certificate(
+            proto::CertificateSummary {
+                kind: "ViewSyncCommitCertificate2".to_string(),
+                data_commitment: data.commit().to_string(),
+            }
+        ))
+    );
+
+    // ...and nodes accept either encoding.
+    let (decoded, encoding) = upgrade_lock.deserialize_message(&encoded).await.unwrap();
+    assert_eq!(
+        (decoded, encoding),
+        (message.clone(), WireEncoding::Protobuf)
+    );
+    let bincode = upgrade_lock.serialize(&message).await.unwrap();
+    let (decoded, encoding) = upgrade_lock.deserialize_message(&bincode).await.unwrap();
+    assert_eq!((decoded, encoding), (message, WireEncoding::Bincode));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cbor_messages_round_trip() {
+    use hotshot_example_types::node_types::TestVersions;
+    use hotshot_types::{
+        codec::{CborCodec, WireCodec},
+        message::{UpgradeLock, WireEncoding},
+    };
+
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let data: ViewSyncCommitData2<TestTypes> = ViewSyncCommitData2 {
+        relay: 0,
+        round: ConsensusTime::new(3),
+        epoch: ConsensusTime::new(0),
+    };
+    let message = Message {
+        sender,
+        kind: MessageKind::Consensus(SequencingMessage::General(
+            GeneralConsensusMessage::ViewSyncCommitCertificate2(SimpleCertificate::new(
+                data.clone(),
+                data.commit(),
+                ConsensusTime::new(3),
+                None,
+                PhantomData,
+            )),
+        )),
+        namespace: 0,
+        expires_after: None,
+    };
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+
+    let encoded = upgrade_lock
+        .serialize_as(&message, WireEncoding::Cbor)
+        .await
+        .unwrap();
+    assert!(encoded.starts_with(CborCodec::MAGIC));
+    assert_eq!(WireEncoding::of::<TestTypes>(&encoded), WireEncoding::Cbor);
+
+    let (decoded, encoding) = upgrade_lock.deserialize_message(&encoded).await.unwrap();
+    assert_eq!((decoded, encoding), (message.clone(), WireEncoding::Cbor));
+
+    // Messages over the size limit are neither encoded nor decoded
+    let version = upgrade_lock.version(ConsensusTime::new(3)).await.unwrap();
+    assert!(<CborCodec as WireCodec<TestTypes>>::encode(&message, version, 16).is_err());
+    assert!(<CborCodec as WireCodec<TestTypes>>::decode(&encoded, 16).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_messages_signed_by_their_sender_are_accepted() {
+    use hotshot_example_types::node_types::EpochsTestVersions;
+    use hotshot_types::message::{SenderAuth, UpgradeLock, WireEncoding};
+
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let (other, other_private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let data: ViewSyncCommitData2<TestTypes> = ViewSyncCommitData2 {
+        relay: 0,
+        round: ConsensusTime::new(3),
+        epoch: ConsensusTime::new(0),
+    };
+    let message = Message {
+        sender,
+        kind: MessageKind::Consensus(SequencingMessage::General(
+            GeneralConsensusMessage::ViewSyncCommitCertificate2(SimpleCertificate::new(
+                data.clone(),
+                data.commit(),
+                ConsensusTime::new(3),
+                None,
+                PhantomData,
+            )),
+        )),
+        namespace: 7,
+        expires_after: None,
+    };
+    let upgrade_lock = UpgradeLock::<TestTypes, EpochsTestVersions>::new();
+
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let signed = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        let decoded = upgrade_lock
+            .deserialize_signed(signed.clone().into())
+            .await
+            .unwrap();
+        assert_eq!(decoded, (message.clone(), encoding, SenderAuth::Signed));
+
+        // Tampered with by a relay
+        let mut tampered = signed.clone();
+        tampered[signed.len() / 4] ^= 1;
+        assert!(upgrade_lock
+            .deserialize_signed(tampered.into())
+            .await
+            .is_err());
+        // Unsigned
+        let unsigned = upgrade_lock.serialize_as(&message, encoding).await.unwrap();
+        assert!(upgrade_lock
+            .deserialize_signed(unsigned.into())
+            .await
+            .is_err());
+        assert!(upgrade_lock.deserialize_signed(Bytes::new()).await.is_err());
+    }
+
+    // Signed by another node than the one it claims to be from
+    let forged = upgrade_lock
+        .serialize_signed(&message, WireEncoding::Bincode, &other_private_key)
+        .await
+        .unwrap();
+    assert!(upgrade_lock
+        .deserialize_signed(forged.into())
+        .await
+        .is_err());
+    let message = Message {
+        sender: other,
+        ..message
+    };
+    let signed = upgrade_lock
+        .serialize_signed(&message, WireEncoding::Bincode, &other_private_key)
+        .await
+        .unwrap();
+    assert!(upgrade_lock.deserialize_signed(signed.into()).await.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn messages_are_signed_from_epochs_on() {
+    use hotshot_example_types::node_types::TestVersions;
+    use hotshot_types::message::{SenderAuth, UpgradeLock, WireEncoding};
+
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let message = Message::<TestTypes> {
+        sender,
+        kind: MessageKind::External(vec![1, 2, 3]),
+        namespace: 0,
+        expires_after: None,
+    };
+
+    // Before epochs messages are sent unsigned, as older nodes expect them
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let sent = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        assert_eq!(
+            sent,
+            upgrade_lock.serialize_as(&message, encoding).await.unwrap()
+        );
+        let decoded = upgrade_lock.deserialize_signed(sent.into()).await.unwrap();
+        assert_eq!(decoded, (message.clone(), encoding, SenderAuth::Unsigned));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn votes_are_authenticated_by_their_own_signature() {
+    use hotshot_example_types::node_types::EpochsTestVersions;
+    use hotshot_types::{
+        message::{SenderAuth, UpgradeLock, WireEncoding},
+        simple_vote::{QuorumData2, QuorumVote2},
+    };
+
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let (other, other_private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let upgrade_lock = UpgradeLock::<TestTypes, EpochsTestVersions>::new();
+    let data = QuorumData2::<TestTypes> {
+        leaf_commit: committable::Commitment::from_raw([0u8; 32]),
+        epoch: ConsensusTime::new(0),
+    };
+    let vote = QuorumVote2::create_signed_vote(
+        data,
+        ConsensusTime::new(3),
+        &sender,
+        &private_key,
+        &upgrade_lock,
+    )
+    .await
+    .unwrap();
+    let message = Message::<TestTypes> {
+        sender,
+        kind: MessageKind::Consensus(SequencingMessage::General(GeneralConsensusMessage::Vote2(
+            vote,
+        ))),
+        namespace: 0,
+        expires_after: None,
+    };
+    assert!(message.is_vote_of_sender());
+
+    // The vote is not signed again, its message carries an empty signature
+    let sent = upgrade_lock
+        .serialize_signed(&message, WireEncoding::Bincode, &private_key)
+        .await
+        .unwrap();
+    let mut unsigned = upgrade_lock
+        .serialize_as(&message, WireEncoding::Bincode)
+        .await
+        .unwrap();
+    unsigned.extend_from_slice(&0u32.to_le_bytes());
+    assert_eq!(sent, unsigned);
+    let decoded = upgrade_lock.deserialize_signed(sent.into()).await.unwrap();
+    assert_eq!(
+        decoded,
+        (message.clone(), WireEncoding::Bincode, SenderAuth::Vote)
+    );
+
+    // A vote relayed on behalf of another node is signed by the relay as any other message
+    let relayed = Message {
+        sender: other,
+        ..message
+    };
+    assert!(!relayed.is_vote_of_sender());
+    let sent = upgrade_lock
+        .serialize_signed(&relayed, WireEncoding::Bincode, &other_private_key)
+        .await
+        .unwrap();
+    let decoded = upgrade_lock.deserialize_signed(sent.into()).await.unwrap();
+    assert_eq!(decoded.2, SenderAuth::Signed);
+    let mut unsigned = upgrade_lock
+        .serialize_as(&relayed, WireEncoding::Bincode)
+        .await
+        .unwrap();
+    unsigned.extend_from_slice(&0u32.to_le_bytes());
+    assert!(upgrade_lock
+        .deserialize_signed(unsigned.into())
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn messages_expire_after_their_last_view() {
+    use hotshot_example_types::node_types::{EpochsTestVersions, TestVersions};
+    use hotshot_types::message::{UpgradeLock, WireEncoding};
+
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let message = Message::<TestTypes> {
+        sender,
+        kind: MessageKind::External(vec![1, 2, 3]),
+        namespace: 0,
+        expires_after: Some(ConsensusTime::new(5)),
+    };
+    assert!(!message.is_expired(ConsensusTime::new(5)));
+    assert!(message.is_expired(ConsensusTime::new(6)));
+
+    // The expiry is part of what the sender signs, so a relay cannot extend it
+    let upgrade_lock = UpgradeLock::<TestTypes, EpochsTestVersions>::new();
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let signed = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        let (decoded, ..) = upgrade_lock
+            .deserialize_signed(signed.clone().into())
+            .await
+            .unwrap();
+        assert_eq!(decoded.expires_after, Some(ConsensusTime::new(5)));
+
+        let mut extended = signed.clone();
+        let signature_len = u32::from_le_bytes(signed[signed.len() - 4..].try_into().unwrap());
+        let expiry_end = signed.len() - 4 - usize::try_from(signature_len).unwrap();
+        extended[expiry_end - 8..expiry_end].copy_from_slice(&6u64.to_le_bytes());
+        assert!(upgrade_lock
+            .deserialize_signed(extended.into())
+            .await
+            .is_err());
+    }
+
+    let never = Message {
+        expires_after: None,
+        ..message.clone()
+    };
+    assert!(!never.is_expired(ConsensusTime::new(u64::MAX)));
+
+    // Before epochs the expiry is not sent, and messages are encoded as they were before it
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let sent = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        assert_eq!(
+            sent,
+            upgrade_lock.serialize_as(&never, encoding).await.unwrap()
+        );
+        let (decoded, ..) = upgrade_lock.deserialize_signed(sent.into()).await.unwrap();
+        assert_eq!(decoded, never);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn namespace_is_sent_from_epochs_on() {
+    use hotshot_example_types::node_types::{EpochsTestVersions, TestVersions};
+    use hotshot_types::message::{UpgradeLock, WireEncoding};
+
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let message = Message::<TestTypes> {
+        sender,
+        kind: MessageKind::External(vec![1, 2, 3]),
+        namespace: 7,
+        expires_after: None,
+    };
+
+    // The namespace is part of what the sender signs, so a relay cannot move the message to
+    // another instance
+    let upgrade_lock = UpgradeLock::<TestTypes, EpochsTestVersions>::new();
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let signed = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        let (decoded, ..) = upgrade_lock
+            .deserialize_signed(signed.clone().into())
+            .await
+            .unwrap();
+        assert_eq!(decoded.namespace, 7);
+
+        let mut moved = signed.clone();
+        let signature_len = u32::from_le_bytes(signed[signed.len() - 4..].try_into().unwrap());
+        let namespace_end = signed.len() - 4 - usize::try_from(signature_len).unwrap() - 8;
+        moved[namespace_end - 8..namespace_end].copy_from_slice(&0u64.to_le_bytes());
+        assert!(upgrade_lock.deserialize_signed(moved.into()).await.is_err());
+    }
+
+    // Before epochs the namespace is not sent, and messages are encoded as they were before it
+    let default_namespace = Message {
+        namespace: 0,
+        ..message.clone()
+    };
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    for encoding in [WireEncoding::Bincode, WireEncoding::Cbor] {
+        let sent = upgrade_lock
+            .serialize_signed(&message, encoding, &private_key)
+            .await
+            .unwrap();
+        assert_eq!(
+            sent,
+            upgrade_lock
+                .serialize_as(&default_namespace, encoding)
+                .await
+                .unwrap()
+        );
+        let (decoded, ..) = upgrade_lock.deserialize_signed(sent.into()).await.unwrap();
+        assert_eq!(decoded, default_namespace);
+    }
+}
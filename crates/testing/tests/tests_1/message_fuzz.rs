@@ -0,0 +1,197 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Malformed bytes from the network must never panic a node.
+//!
+//! These tests feed random bytes, and random mutations of valid encoded messages, to the decoders
+//! of messages, votes and certificates, and check that each decode returns instead of panicking.
+//! Set `HOTSHOT_FUZZ_SEED` to replay a run, `HOTSHOT_FUZZ_ITERATIONS` to run longer, and
+//! `HOTSHOT_FUZZ_CORPUS` to a directory recorded with
+//! [`record_corpus`](hotshot_testing::message_hook::record_corpus) to also mutate real test
+//! traffic.
+
+use std::{panic::catch_unwind, sync::Arc};
+
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    message::{
+        DaConsensusMessage, GeneralConsensusMessage, Message, MessageKind, SequencingMessage,
+    },
+    simple_certificate::{DaCertificate2, QuorumCertificate2},
+    simple_vote::QuorumVote2,
+    traits::node_implementation::Versions,
+};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use vbs::{version::StaticVersionType, BinarySerializer, Serializer};
+
+/// The serializer for the base version of the test versions
+type Base = Serializer<<TestVersions as Versions>::Base>;
+
+/// Read a numeric setting from the environment
+fn env_setting(name: &str) -> Option<u64> {
+    std::env::var(name).ok().map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} must be a u64, got {value}"))
+    })
+}
+
+/// Encode valid messages of every kind we decode, to mutate
+async fn valid_messages() -> Vec<Vec<u8>> {
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+
+    let mut messages = Vec::new();
+    for view in (&mut generator).take(3).collect::<Vec<_>>().await {
+        let vote = view.create_quorum_vote(&handle).await;
+        let da_vote = view
+            .create_da_vote(view.da_certificate.data.clone(), &handle)
+            .await;
+        let kinds = [
+            SequencingMessage::General(GeneralConsensusMessage::Proposal2(
+                view.quorum_proposal.clone(),
+            )),
+            SequencingMessage::General(GeneralConsensusMessage::Vote2(vote.clone())),
+            SequencingMessage::Da(DaConsensusMessage::DaProposal2(view.da_proposal.clone())),
+            SequencingMessage::Da(DaConsensusMessage::DaVote2(da_vote)),
+            SequencingMessage::Da(DaConsensusMessage::DaCertificate2(
+                view.da_certificate.clone(),
+            )),
+        ];
+        for kind in kinds {
+            let message = Message::<TestTypes> {
+                sender: view.leader_public_key,
+                kind: MessageKind::Consensus(kind),
+                namespace: 0,
+                expires_after: None,
+            };
+            messages.push(Base::serialize(&message).unwrap());
+        }
+        messages.push(Base::serialize(&vote).unwrap());
+        messages.push(Base::serialize(&view.quorum_proposal.data.justify_qc).unwrap());
+        messages.push(Base::serialize(&view.da_certificate).unwrap());
+    }
+
+    if let Ok(dir) = std::env::var("HOTSHOT_FUZZ_CORPUS") {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            messages.push(std::fs::read(entry.unwrap().path()).unwrap());
+        }
+    }
+
+    messages
+}
+
+/// Mutate a valid message structurally, keeping most of it intact
+fn mutate(rng: &mut StdRng, seeds: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = seeds[rng.gen_range(0..seeds.len())].clone();
+    for _ in 0..rng.gen_range(1..=4) {
+        let len = bytes.len();
+        match rng.gen_range(0..6) {
+            // flip a bit
+            0 if len > 0 => bytes[rng.gen_range(0..len)] ^= 1 << rng.gen_range(0..8),
+            // overwrite a byte, favouring values which break length prefixes
+            1 if len > 0 => {
+                bytes[rng.gen_range(0..len)] = [0, 1, 0x7f, 0x80, 0xff][rng.gen_range(0..5)];
+            }
+            // truncate
+            2 => bytes.truncate(rng.gen_range(0..=len)),
+            // insert random bytes
+            3 => {
+                let at = rng.gen_range(0..=len);
+                let mut insert = vec![0; rng.gen_range(1..16)];
+                rng.fill_bytes(&mut insert);
+                bytes.splice(at..at, insert);
+            }
+            // splice in the tail of another message
+            4 => {
+                let other = &seeds[rng.gen_range(0..seeds.len())];
+                let from = rng.gen_range(0..=other.len());
+                bytes.truncate(rng.gen_range(0..=len));
+                bytes.extend_from_slice(&other[from..]);
+            }
+            // remove a range
+            _ if len > 0 => {
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..=len);
+                bytes.drain(start..end);
+            }
+            _ => {}
+        }
+    }
+    bytes
+}
+
+/// Random bytes, half of the time behind a valid version prefix
+fn random_bytes(rng: &mut StdRng) -> Vec<u8> {
+    let mut bytes = vec![0; rng.gen_range(0..512)];
+    rng.fill_bytes(&mut bytes);
+    if rng.gen_bool(0.5) {
+        let mut prefixed = Base::serialize(&()).unwrap();
+        prefixed.append(&mut bytes);
+        prefixed
+    } else {
+        bytes
+    }
+}
+
+/// Decode `bytes` with every decoder, returning whether any of them panicked
+fn decoders_panic(bytes: &[u8]) -> bool {
+    catch_unwind(|| {
+        let _ = Message::<TestTypes>::protocol_version(bytes);
+        let _ = Base::deserialize::<Message<TestTypes>>(bytes);
+        let _ = Serializer::<<TestVersions as Versions>::Upgrade>::deserialize::<
+            Message<TestTypes>,
+        >(bytes);
+        let _ = Base::deserialize::<QuorumVote2<TestTypes>>(bytes);
+        let _ = Base::deserialize::<QuorumCertificate2<TestTypes>>(bytes);
+        let _ = Base::deserialize::<DaCertificate2<TestTypes>>(bytes);
+    })
+    .is_err()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_malformed_messages_do_not_panic() {
+    hotshot::helpers::initialize_logging();
+
+    let seed = env_setting("HOTSHOT_FUZZ_SEED").unwrap_or_else(rand::random);
+    let iterations = env_setting("HOTSHOT_FUZZ_ITERATIONS").unwrap_or(2000);
+    tracing::info!("Fuzzing message decoding with seed {seed}");
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let seeds = valid_messages().await;
+    for bytes in &seeds {
+        assert!(!decoders_panic(bytes), "A valid message panicked a decoder");
+    }
+    // the version prefix alone must not be a valid message
+    assert!(Base::deserialize::<Message<TestTypes>>(&Base::serialize(&()).unwrap()).is_err());
+
+    for iteration in 0..iterations {
+        let bytes = if iteration % 4 == 0 {
+            random_bytes(&mut rng)
+        } else {
+            mutate(&mut rng, &seeds)
+        };
+        assert!(
+            !decoders_panic(&bytes),
+            "Decoding panicked with seed {seed} in iteration {iteration} on bytes {bytes:?}"
+        );
+    }
+}
+
+#[test]
+fn test_protocol_version_of_short_messages() {
+    for len in 0..4 {
+        assert!(Message::<TestTypes>::protocol_version(&vec![0xff; len]).is_err());
+    }
+    assert_eq!(
+        Message::<TestTypes>::protocol_version(&Base::serialize(&()).unwrap()).unwrap(),
+        <TestVersions as Versions>::Base::VERSION
+    );
+}
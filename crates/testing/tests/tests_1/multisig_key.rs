@@ -0,0 +1,145 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use bitvec::bitvec;
+use hotshot_types::{
+    multisig_key::{BLSSignature, MultisigPrivKey, MultisigPubKey, ShareSigner},
+    signature_key::{BLSPrivKey, BLSPubKey},
+    traits::signature_key::{PrivateSignatureKey, SignatureKey},
+};
+use jf_signature::SignatureError;
+use primitive_types::U256;
+use tagged_base64::TaggedBase64;
+
+/// A remote signer which can be switched off
+#[derive(Debug)]
+struct RemoteSigner {
+    key: BLSPrivKey,
+    online: bool,
+}
+
+impl ShareSigner for RemoteSigner {
+    fn public_key(&self) -> BLSPubKey {
+        BLSPubKey::from(&self.key)
+    }
+
+    fn sign_share(&self, data: &[u8]) -> Result<BLSSignature, SignatureError> {
+        if self.online {
+            BLSPubKey::sign(&self.key, data)
+        } else {
+            Err(SignatureError::ParameterError("offline".to_string()))
+        }
+    }
+}
+
+/// A 2-of-3 key and the private keys of its members
+fn two_of_three(seed: u8) -> (MultisigPubKey, Vec<BLSPrivKey>) {
+    let (members, shares): (Vec<_>, Vec<_>) = (0..3)
+        .map(|member| BLSPubKey::generated_from_seed_indexed([seed; 32], member))
+        .unzip();
+    (MultisigPubKey::new(2, members).unwrap(), shares)
+}
+
+#[test]
+fn signatures_need_the_threshold_of_members() {
+    let (pk, shares) = two_of_three(1);
+    let msg = [7u8; 32];
+
+    // one local share is not enough
+    let one = MultisigPrivKey::new(pk.clone(), shares[..1].to_vec()).unwrap();
+    assert!(MultisigPubKey::sign(&one, &msg).is_err());
+
+    // a remote signer makes up for the missing share, unless it is offline
+    let offline = one.clone().with_remote_signer(Arc::new(RemoteSigner {
+        key: shares[1].clone(),
+        online: false,
+    }));
+    assert!(MultisigPubKey::sign(&offline.unwrap(), &msg).is_err());
+    let remote = one
+        .with_remote_signer(Arc::new(RemoteSigner {
+            key: shares[2].clone(),
+            online: true,
+        }))
+        .unwrap();
+    let sig = MultisigPubKey::sign(&remote, &msg).unwrap();
+    assert_eq!(MultisigPubKey::from_private(&remote), pk);
+    assert!(pk.validate(&sig, &msg));
+    assert!(!pk.validate(&sig, &[8u8; 32]));
+
+    // dropping or adding a signer from the bitmap invalidates the signature
+    let mut fewer = sig.clone();
+    let first = fewer.signers.iter().position(|signed| *signed).unwrap();
+    fewer.signers[first] = false;
+    assert!(!pk.validate(&fewer, &msg));
+    let mut more = sig;
+    more.signers = vec![true; 3];
+    assert!(!pk.validate(&more, &msg));
+
+    // signers outside the key are refused
+    let (_, others) = two_of_three(2);
+    assert!(MultisigPrivKey::new(pk, others).is_err());
+}
+
+#[test]
+fn policies_are_checked() {
+    let (pk, shares) = two_of_three(1);
+    let members = pk.members().to_vec();
+    assert!(MultisigPubKey::new(0, members.clone()).is_err());
+    assert!(MultisigPubKey::new(4, members.clone()).is_err());
+    assert!(MultisigPubKey::new(2, vec![members[0], members[0], members[1]]).is_err());
+
+    // keys round trip, and malformed policies do not decode
+    assert_eq!(MultisigPubKey::from_bytes(&pk.to_bytes()).unwrap(), pk);
+    let tb64: TaggedBase64 = pk.clone().into();
+    assert_eq!(MultisigPubKey::try_from(&tb64).unwrap(), pk);
+    let mut malformed = MultisigPubKey::new(2, members[..2].to_vec())
+        .unwrap()
+        .to_bytes();
+    malformed[..4].copy_from_slice(&3u32.to_le_bytes());
+    assert!(MultisigPubKey::from_bytes(&malformed).is_err());
+
+    let sk = MultisigPrivKey::new(pk, shares).unwrap();
+    assert_eq!(MultisigPrivKey::from_bytes(&sk.to_bytes()).unwrap(), sk);
+    let tb64 = sk.to_tagged_base64().unwrap();
+    assert_eq!(MultisigPrivKey::try_from(&tb64).unwrap(), sk);
+}
+
+#[test]
+fn quorum_certificates_check_every_policy() {
+    let (pk1, sk1) = MultisigPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let (pk2, sk2) = MultisigPubKey::generated_from_seed_indexed([0u8; 32], 2);
+    let (pk3, _) = MultisigPubKey::generated_from_seed_indexed([0u8; 32], 3);
+    let msg = [7u8; 32];
+    let sig1 = MultisigPubKey::sign(&sk1, &msg).unwrap();
+    let sig2 = MultisigPubKey::sign(&sk2, &msg).unwrap();
+
+    let qc_pp = MultisigPubKey::public_parameter(
+        vec![
+            pk1.stake_table_entry(1),
+            pk2.stake_table_entry(1),
+            pk3.stake_table_entry(1),
+        ],
+        U256::from(2u8),
+    );
+    let signers = bitvec![1, 1, 0];
+    assert!(MultisigPubKey::assemble(&qc_pp, &bitvec![1, 0, 0], &[sig1.clone()]).is_err());
+    assert!(MultisigPubKey::assemble(&qc_pp, &signers, &[sig1.clone()]).is_err());
+    let qc = MultisigPubKey::assemble(&qc_pp, &signers, &[sig1.clone(), sig2.clone()]).unwrap();
+    assert!(MultisigPubKey::check(&qc_pp, &msg, &qc));
+    assert!(!MultisigPubKey::check(&qc_pp, &[8u8; 32], &qc));
+    assert_eq!(MultisigPubKey::sig_proof(&qc).1, signers);
+
+    // a validator below its own threshold cannot be counted
+    let mut short = sig2;
+    let first = short.signers.iter().position(|signed| *signed).unwrap();
+    short.signers[first] = false;
+    assert!(MultisigPubKey::assemble(&qc_pp, &signers, &[sig1, short]).is_err());
+    let (mut tampered, bits) = qc;
+    tampered.signers[first + 3] = false;
+    assert!(!MultisigPubKey::check(&qc_pp, &msg, &(tampered, bits)));
+}
@@ -19,7 +19,7 @@ use hotshot_testing::{
 use hotshot_types::{
     consensus::OuterConsensus,
     data::{EpochNumber, ViewNumber},
-    message::UpgradeLock,
+    message::{UpgradeLock, WireEncodings},
     traits::{
         election::Membership,
         node_implementation::{ConsensusTime, NodeType},
@@ -72,6 +72,11 @@ async fn test_network_task() {
             storage,
             consensus,
             transmit_tasks: BTreeMap::new(),
+            paused: Arc::default(),
+            namespace: 0,
+            wire_encodings: WireEncodings::default(),
+            private_key: validator_config.private_key.clone(),
+            message_ttl: None,
         };
     let (tx, rx) = async_broadcast::broadcast(10);
     let mut task_reg = ConsensusTaskRegistry::new();
@@ -244,6 +249,11 @@ async fn test_network_storage_fail() {
             storage,
             consensus,
             transmit_tasks: BTreeMap::new(),
+            paused: Arc::default(),
+            namespace: 0,
+            wire_encodings: WireEncodings::default(),
+            private_key: validator_config.private_key.clone(),
+            message_ttl: None,
         };
     let (tx, rx) = async_broadcast::broadcast(10);
     let mut task_reg = ConsensusTaskRegistry::new();
@@ -0,0 +1,64 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{data::Leaf2, proposal_cache::ProposalCache};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_proposal_cache_evicts_least_recently_used() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(3).collect::<Vec<_>>().await;
+
+    let cache = ProposalCache::<TestTypes>::new(2, 1);
+    let leaf_commits: Vec<_> = views
+        .iter()
+        .map(|view| Leaf2::from_quorum_proposal(&view.quorum_proposal.data).commit())
+        .collect();
+
+    cache.insert_proposal(leaf_commits[0], views[0].quorum_proposal.clone());
+    cache.insert_proposal(leaf_commits[1], views[1].quorum_proposal.clone());
+    // Looking up the first proposal makes the second one the least recently used
+    assert!(cache.proposal(&leaf_commits[0]).is_some());
+    cache.insert_proposal(leaf_commits[2], views[2].quorum_proposal.clone());
+
+    assert_eq!(
+        cache
+            .proposal(&leaf_commits[0])
+            .map(|proposal| proposal.data),
+        Some(views[0].quorum_proposal.data.clone())
+    );
+    assert!(cache.proposal(&leaf_commits[1]).is_none());
+    assert!(cache.proposal(&leaf_commits[2]).is_some());
+
+    // Payloads are kept apart from proposals, by payload commitment
+    let payload = |index: usize| {
+        (
+            views[index].leaf.payload_commitment(),
+            Arc::clone(&views[index].da_proposal.data.encoded_transactions),
+        )
+    };
+    let (first_commitment, first_payload) = payload(0);
+    cache.insert_payload(first_commitment, Arc::clone(&first_payload));
+    assert_eq!(cache.payload(&first_commitment), Some(first_payload));
+
+    let (second_commitment, second_payload) = payload(1);
+    cache.insert_payload(second_commitment, Arc::clone(&second_payload));
+    if second_commitment != first_commitment {
+        assert!(cache.payload(&first_commitment).is_none());
+    }
+    assert_eq!(cache.payload(&second_commitment), Some(second_payload));
+}
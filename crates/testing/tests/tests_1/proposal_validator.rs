@@ -0,0 +1,210 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use async_broadcast::broadcast;
+use async_trait::async_trait;
+use futures::StreamExt;
+use hotshot::{tasks::task_state::CreateTaskState, traits::implementations::MemoryNetwork};
+use hotshot_example_types::{
+    auction_results_provider_types::TestAuctionResultsProvider,
+    block_types::{TestBlockPayload, TestMetadata},
+    node_types::{TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+    storage_types::TestStorage,
+};
+use hotshot_macros::run_test;
+use hotshot_task::dependency_task::HandleDepOutput;
+use hotshot_task_impls::{
+    da::DaTaskState, events::HotShotEvent::*, quorum_vote::VoteDependencyHandle,
+};
+use hotshot_testing::{
+    helpers::build_system_handle,
+    predicates::{event::exact, Predicate, PredicateResult},
+    script::{Expectations, InputOrder, TaskScript},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    consensus::OuterConsensus,
+    data::{EpochNumber, Leaf2, QuorumProposal2, ViewNumber},
+    signature_key::BLSPubKey,
+    traits::{
+        block_builder_strategy::PriorityOrdered,
+        block_contents::vid_commitment,
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeImplementation},
+        proposal_validator::ProposalValidator,
+    },
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_millis(35);
+
+/// The reason given for rejecting every proposal
+const REJECTION_REASON: &str = "block violates application rules";
+
+/// A proposal validator which rejects every proposal and payload
+struct RejectAllProposals;
+
+#[async_trait]
+impl ProposalValidator<TestTypes> for RejectAllProposals {
+    async fn validate_proposal(
+        _instance_state: &TestInstanceState,
+        _proposal: &QuorumProposal2<TestTypes>,
+        _parent_leaf: &Leaf2<TestTypes>,
+    ) -> Result<(), String> {
+        Err(REJECTION_REASON.to_string())
+    }
+
+    async fn validate_payload(
+        _instance_state: &TestInstanceState,
+        _view: ViewNumber,
+        _payload: &TestBlockPayload,
+        _metadata: &TestMetadata,
+    ) -> Result<(), String> {
+        Err(REJECTION_REASON.to_string())
+    }
+}
+
+/// A node implementation whose application rejects every proposal
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
+struct RejectingImpl;
+
+impl NodeImplementation<TestTypes> for RejectingImpl {
+    type Network = MemoryNetwork<BLSPubKey>;
+    type Storage = TestStorage<TestTypes>;
+    type AuctionResultsProvider = TestAuctionResultsProvider<TestTypes>;
+    type ProposalValidator = RejectAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejected_proposal_is_not_voted_for() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, RejectingImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(membership);
+
+    let mut proposals = Vec::new();
+    let mut leaves = Vec::new();
+    let mut dacs = Vec::new();
+    let mut vids = Vec::new();
+    let consensus = handle.hotshot.consensus().clone();
+    let mut consensus_writer = consensus.write().await;
+    for view in (&mut generator).take(3).collect::<Vec<_>>().await {
+        proposals.push(view.quorum_proposal.clone());
+        leaves.push(view.leaf.clone());
+        dacs.push(view.da_certificate.clone());
+        vids.push(view.vid_proposal.clone());
+        consensus_writer
+            .update_leaf(
+                Leaf2::from_quorum_proposal(&view.quorum_proposal.data),
+                Arc::new(TestValidatedState::default()),
+                None,
+            )
+            .unwrap();
+    }
+    drop(consensus_writer);
+
+    let inputs = vec![
+        QuorumProposalValidated(proposals[1].clone(), leaves[0].clone()),
+        DaCertificateValidated(dacs[1].clone()),
+        VidShareValidated(vids[1].0[0].clone()),
+    ];
+
+    let (event_sender, mut event_receiver) = broadcast(1024);
+    let vote_dependency_handle_state =
+        VoteDependencyHandle::<TestTypes, RejectingImpl, TestVersions> {
+            public_key: handle.public_key(),
+            private_key: handle.private_key().clone(),
+            consensus: OuterConsensus::new(consensus.clone()),
+            consensus_metrics: Arc::clone(&consensus.read().await.metrics),
+            instance_state: handle.hotshot.instance_state(),
+            membership: Arc::clone(&handle.hotshot.memberships),
+            storage: Arc::clone(&handle.storage()),
+            view_number: ViewNumber::new(node_id),
+            sender: event_sender.clone(),
+            receiver: event_receiver.clone().deactivate(),
+            upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            id: handle.hotshot.id,
+            epoch_height: handle.hotshot.config.epoch_height,
+        };
+
+    vote_dependency_handle_state
+        .handle_dep_result(inputs.into_iter().map(Arc::new).collect())
+        .await;
+
+    let mut output_events = vec![];
+    while let Ok(Ok(received_output)) = timeout(TIMEOUT, event_receiver.recv_direct()).await {
+        output_events.push(received_output);
+    }
+
+    // The only output is the rejection: no view change, and no vote.
+    assert_eq!(output_events.len(), 1, "Expected only the rejection event");
+    let check = exact(QuorumProposalRejected(
+        proposals[1].clone(),
+        REJECTION_REASON.to_string(),
+    ));
+    if check.evaluate(&output_events[0]).await == PredicateResult::Fail {
+        panic!(
+            "Output {} did not match expected output {check:?}",
+            output_events[0]
+        );
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejected_da_payload_is_not_voted_for() {
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, RejectingImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let num_nodes = membership.read().await.total_nodes(EpochNumber::new(0));
+
+    let mut generator = TestViewGenerator::generate(membership);
+    let view = (&mut generator).next().await.unwrap();
+    let proposal = view.da_proposal.clone();
+    let payload_commitment = vid_commitment(&proposal.data.encoded_transactions, num_nodes);
+
+    let inputs = vec![InputOrder::Serial(vec![DaProposalRecv(
+        proposal.clone(),
+        view.leader_public_key,
+    )])];
+
+    // The proposal passes the consensus checks, but the application rejects its payload, so no
+    // vote follows.
+    let expectations = vec![Expectations::from_outputs(vec![
+        exact(DaProposalValidated(proposal, view.leader_public_key)),
+        exact(DaPayloadRejected(
+            view.view_number,
+            payload_commitment,
+            REJECTION_REASON.to_string(),
+        )),
+    ])];
+
+    let mut state =
+        DaTaskState::<TestTypes, RejectingImpl, TestVersions>::create_from(&handle).await;
+    state.payload_validation = true;
+    let mut script = TaskScript {
+        timeout: TIMEOUT,
+        state,
+        expectations,
+    };
+    run_test![inputs, script].await;
+}
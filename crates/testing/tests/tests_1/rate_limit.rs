@@ -0,0 +1,30 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::{Duration, Instant};
+
+use hotshot_types::rate_limit::RateLimiter;
+
+#[test]
+fn bursts_then_refills_per_peer() {
+    let mut limiter = RateLimiter::new(2, 3, 2);
+    let start = Instant::now();
+
+    assert!((0..3).all(|_| limiter.allow(&"a", start)));
+    assert!(!limiter.allow(&"a", start));
+    // other peers have their own bucket
+    assert!(limiter.allow(&"b", start));
+
+    // two tokens a second
+    let later = start + Duration::from_millis(500);
+    assert!(limiter.allow(&"a", later));
+    assert!(!limiter.allow(&"a", later));
+
+    // a third peer evicts `b`, seen least recently, which then starts over with a full bucket
+    assert!(limiter.allow(&"c", later));
+    assert!((0..3).all(|_| limiter.allow(&"b", later)));
+    assert!(!limiter.allow(&"b", later));
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use hotshot_types::{error::HotShotConfigError, reconfig::ConfigUpdate};
+
+#[test]
+fn newer_updates_take_precedence() {
+    let mut update = ConfigUpdate {
+        next_view_timeout: Some(10_000),
+        builder_timeout: Some(Duration::from_secs(2)),
+        ..ConfigUpdate::default()
+    };
+    update.merge(ConfigUpdate {
+        next_view_timeout: Some(5_000),
+        log_filter: Some("debug".to_string()),
+        ..ConfigUpdate::default()
+    });
+    assert_eq!(
+        update,
+        ConfigUpdate {
+            next_view_timeout: Some(5_000),
+            view_sync_timeout: None,
+            builder_timeout: Some(Duration::from_secs(2)),
+            log_filter: Some("debug".to_string()),
+        }
+    );
+    assert!(update.validate().is_ok());
+    assert!(!update.is_empty());
+    assert!(ConfigUpdate::default().is_empty());
+
+    update.merge(ConfigUpdate {
+        view_sync_timeout: Some(Duration::ZERO),
+        ..ConfigUpdate::default()
+    });
+    assert_eq!(
+        update.validate(),
+        Err(HotShotConfigError::ZeroTimeout("view sync timeout"))
+    );
+}
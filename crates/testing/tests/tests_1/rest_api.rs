@@ -0,0 +1,125 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+#![cfg(feature = "rest-api")]
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::traits::{
+    election::Membership,
+    node_implementation::{ConsensusTime, NodeType},
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+
+/// GET `path` of the node API at `address`, as JSON
+async fn get(address: SocketAddr, path: &str) -> (StatusCode, Value) {
+    let response = reqwest::Client::new()
+        .get(format!("http://{address}/node/{path}"))
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response.json().await.unwrap_or(Value::Null))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rest_api_serves_retained_views_and_membership() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(2).collect::<Vec<_>>().await;
+    {
+        let consensus = handle.hotshot.consensus();
+        let mut consensus_writer = consensus.write().await;
+        for view in &views {
+            consensus_writer
+                .update_leaf(
+                    view.leaf.clone(),
+                    Arc::new(TestValidatedState::default()),
+                    None,
+                )
+                .unwrap();
+        }
+    }
+
+    let address: SocketAddr = format!(
+        "127.0.0.1:{}",
+        portpicker::pick_unused_port().expect("No free ports")
+    )
+    .parse()
+    .unwrap();
+    let server = handle.serve_rest_api(address);
+    let mut ready = false;
+    for _ in 0..50 {
+        if reqwest::get(format!("http://{address}/node/status"))
+            .await
+            .is_ok()
+        {
+            ready = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(ready, "The node API did not start");
+
+    // The leaf of each retained view
+    for view in &views {
+        let (status, leaf) = get(address, &format!("view/{}", *view.leaf.view_number())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(leaf, serde_json::to_value(&view.leaf).unwrap());
+    }
+
+    // The QC formed in the first view is carried by the leaf of the second
+    let (status, qc) = get(
+        address,
+        &format!("view/{}/qc", *views[0].leaf.view_number()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        qc,
+        serde_json::to_value(views[1].leaf.justify_qc()).unwrap()
+    );
+
+    // Views the node never saw are not found
+    assert_eq!(get(address, "view/1000").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(get(address, "view/1000/qc").await.0, StatusCode::NOT_FOUND);
+
+    // The membership of the current epoch
+    let epoch = <TestTypes as NodeType>::Epoch::new(0);
+    let (stake_table, da_stake_table) = {
+        let memberships = handle.hotshot.memberships.read().await;
+        (
+            memberships.stake_table(epoch),
+            memberships.da_stake_table(epoch),
+        )
+    };
+    let (status, membership) = get(address, "membership").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(membership["epoch"], serde_json::to_value(epoch).unwrap());
+    assert_eq!(
+        membership["stake_table"],
+        serde_json::to_value(&stake_table).unwrap()
+    );
+    assert_eq!(
+        membership["da_stake_table"],
+        serde_json::to_value(&da_stake_table).unwrap()
+    );
+
+    server.abort();
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::traits::election::sampled_committee::{CommitteeSamplingConfig, SampledCommittee};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::{EpochNumber, ViewNumber},
+    drb::sample_committee,
+    signature_key::BLSPubKey,
+    simple_certificate::{ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2},
+    traits::{
+        election::Membership, node_implementation::ConsensusTime, signature_key::SignatureKey,
+    },
+    vote::Certificate,
+    PeerConfig,
+};
+
+#[test]
+fn test_sampled_committees_follow_the_drb_result() {
+    let peers: Vec<PeerConfig<BLSPubKey>> = (0..20)
+        .map(|i| PeerConfig {
+            stake_table_entry: BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .stake_table_entry(1),
+            ..PeerConfig::default()
+        })
+        .collect();
+    let config = CommitteeSamplingConfig {
+        da_committee_size: 5,
+        view_sync_committee_size: 7,
+        success_threshold_percent: 67,
+    };
+    let mut membership = SampledCommittee::<TestTypes>::new(peers.clone(), Vec::new());
+    membership.set_sampling_config(config);
+    let mut other = SampledCommittee::<TestTypes>::new(peers.clone(), Vec::new());
+    other.set_sampling_config(config);
+
+    let epoch = EpochNumber::new(3);
+    let view = ViewNumber::new(1);
+    assert_eq!(membership.total_nodes(epoch), 20);
+    assert_eq!(membership.da_total_nodes(epoch), 5);
+    assert_eq!(membership.view_sync_total_nodes(epoch), 7);
+    // 67% of the sampled stake, rounded up
+    assert_eq!(membership.da_success_threshold(epoch).get(), 4);
+    assert_eq!(membership.view_sync_success_threshold(epoch).get(), 5);
+    // The other 33% of it, for a pre-commit certificate
+    assert_eq!(membership.view_sync_failure_threshold(epoch).get(), 3);
+
+    // View sync certificates are formed by the sampled committee only
+    let view_sync_stake_table = membership.view_sync_stake_table(epoch);
+    assert_eq!(
+        ViewSyncFinalizeCertificate2::<TestTypes>::stake_table(&membership, epoch),
+        view_sync_stake_table
+    );
+    assert_eq!(
+        ViewSyncPreCommitCertificate2::<TestTypes>::threshold(&membership, epoch),
+        3
+    );
+    for peer in &peers {
+        let key = BLSPubKey::public_key(&peer.stake_table_entry);
+        assert_eq!(
+            membership.has_view_sync_stake(&key, epoch),
+            view_sync_stake_table.contains(&peer.stake_table_entry)
+        );
+    }
+
+    let initial_da = membership.da_committee_members(view, epoch);
+    for key in &initial_da {
+        assert!(membership.has_stake(key, epoch));
+        assert!(membership.has_da_stake(key, epoch));
+    }
+
+    // Every node samples the same committee from the same DRB result
+    membership.add_drb_result(epoch, [7; 32]);
+    other.add_drb_result(epoch, [7; 32]);
+    assert_eq!(
+        membership.da_committee_members(view, epoch),
+        other.da_committee_members(view, epoch)
+    );
+    assert_eq!(
+        membership.view_sync_stake_table(epoch),
+        other.view_sync_stake_table(epoch)
+    );
+    assert_ne!(membership.da_committee_members(view, epoch), initial_da);
+
+    // Other epochs are unaffected
+    assert_eq!(
+        membership.da_committee_members(view, EpochNumber::new(4)),
+        initial_da
+    );
+}
+
+#[test]
+fn test_sample_committee_weights_by_stake() {
+    // One member holds almost all of the stake and is always sampled
+    let stake_table: Vec<_> = (0..10)
+        .map(|i| {
+            let stake = if i == 0 { 1_000_000 } else { 1 };
+            BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .stake_table_entry(stake)
+        })
+        .collect();
+
+    for seed in 0..20 {
+        let committee = sample_committee::<TestTypes>(&stake_table, [seed; 32], b"TEST", 2);
+        assert_eq!(committee.len(), 2);
+        assert_eq!(committee[0], stake_table[0]);
+    }
+
+    // Asking for more members than there are returns all of them
+    let committee = sample_committee::<TestTypes>(&stake_table, [0; 32], b"TEST", 20);
+    assert_eq!(committee, stake_table);
+}
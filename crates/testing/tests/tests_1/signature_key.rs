@@ -0,0 +1,50 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bitvec::bitvec;
+use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+use primitive_types::U256;
+
+#[test]
+fn malformed_keys_and_signatures_are_rejected() {
+    let (pk1, sk1) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let (pk2, sk2) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 2);
+    let msg = [7u8; 32];
+    let sig1 = BLSPubKey::sign(&sk1, &msg).unwrap();
+    let sig2 = BLSPubKey::sign(&sk2, &msg).unwrap();
+
+    // keys and signatures which do not decode are errors
+    let mut key_bytes = pk1.to_bytes();
+    key_bytes.truncate(key_bytes.len() / 2);
+    assert!(BLSPubKey::from_bytes(&key_bytes).is_err());
+    assert!(BLSPubKey::from_bytes(&[0xff; 64]).is_err());
+    let mut sig_bytes = vec![];
+    sig1.serialize_compressed(&mut sig_bytes).unwrap();
+    sig_bytes.truncate(sig_bytes.len() - 1);
+    assert!(
+        <BLSPubKey as SignatureKey>::PureAssembledSignatureType::deserialize_compressed(
+            &sig_bytes[..]
+        )
+        .is_err()
+    );
+
+    // signatures which decode but were not made by the key do not validate
+    assert!(pk1.validate(&sig1, &msg));
+    assert!(!pk1.validate(&sig2, &msg));
+    assert!(!pk1.validate(&sig1, &[8u8; 32]));
+
+    // signatures which do not match the signers are an error rather than a panic
+    let qc_pp = BLSPubKey::public_parameter(
+        vec![pk1.stake_table_entry(1), pk2.stake_table_entry(1)],
+        U256::from(2u8),
+    );
+    let signers = bitvec![1, 1];
+    assert!(BLSPubKey::assemble(&qc_pp, &signers, &[sig1.clone()]).is_err());
+    assert!(BLSPubKey::assemble(&qc_pp, &bitvec![1, 0], &[sig1.clone()]).is_err());
+    let qc = BLSPubKey::assemble(&qc_pp, &signers, &[sig1, sig2]).unwrap();
+    assert!(BLSPubKey::check(&qc_pp, &msg, &qc));
+}
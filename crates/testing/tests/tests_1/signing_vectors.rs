@@ -0,0 +1,182 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::path::PathBuf;
+
+use committable::{Commitment, Committable};
+use hotshot_example_types::{
+    node_types::{TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_testing::golden_trace::check_golden;
+use hotshot_types::{
+    data::{EpochNumber, Leaf2, ViewNumber},
+    message::UpgradeLock,
+    request_response::ProposalRequestPayload,
+    signature_key::BLSPubKey,
+    simple_vote::{
+        CheckpointData, QuorumData2, TimeoutData2, UpgradeProposalData, VersionedVoteData,
+        ViewSyncCommitData2, ViewSyncFinalizeData2, ViewSyncPreCommitData2, Voteable,
+    },
+    traits::{
+        network::RequestKind, node_implementation::ConsensusTime, signature_key::SignatureKey,
+    },
+};
+use vbs::version::Version;
+
+/// The digest a node signs for `payload`, as hex
+fn digest<T: Committable>(payload: &T) -> String {
+    payload
+        .commit()
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The digest a node signs when voting for `data` in `view`, as hex
+async fn vote_digest<DATA: Voteable<TestTypes>>(
+    data: DATA,
+    view: ViewNumber,
+    upgrade_lock: &UpgradeLock<TestTypes, TestVersions>,
+) -> String {
+    digest(
+        &VersionedVoteData::new(data, view, upgrade_lock)
+            .await
+            .unwrap(),
+    )
+}
+
+/// Pins the digests of every kind of signed payload for fixed inputs, see
+/// `hotshot_types::signing`. If this fails, nodes running the change would reject the signatures
+/// of nodes without it. The vectors are only recorded when `HOTSHOT_UPDATE_GOLDEN` is set.
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn signing_vectors() {
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    let view = ViewNumber::new(17);
+    let epoch = EpochNumber::new(3);
+    let key = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let leaf = Leaf2::<TestTypes>::genesis(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await;
+    let leaf_commit: Commitment<Leaf2<TestTypes>> = Commitment::from_raw([7u8; 32]);
+
+    let mut vectors = vec![
+        ("genesis leaf", digest(&leaf)),
+        (
+            "quorum vote",
+            vote_digest(QuorumData2 { leaf_commit, epoch }, view, &upgrade_lock).await,
+        ),
+        (
+            "timeout vote",
+            vote_digest(TimeoutData2 { view, epoch }, view, &upgrade_lock).await,
+        ),
+        (
+            "view sync precommit vote",
+            vote_digest(
+                ViewSyncPreCommitData2 {
+                    relay: 2,
+                    round: view,
+                    epoch,
+                },
+                view,
+                &upgrade_lock,
+            )
+            .await,
+        ),
+        (
+            "view sync commit vote",
+            vote_digest(
+                ViewSyncCommitData2 {
+                    relay: 2,
+                    round: view,
+                    epoch,
+                },
+                view,
+                &upgrade_lock,
+            )
+            .await,
+        ),
+        (
+            "view sync finalize vote",
+            vote_digest(
+                ViewSyncFinalizeData2 {
+                    relay: 2,
+                    round: view,
+                    epoch,
+                },
+                view,
+                &upgrade_lock,
+            )
+            .await,
+        ),
+        (
+            "checkpoint vote",
+            vote_digest(
+                CheckpointData {
+                    height: 100,
+                    leaf_commit,
+                    state_digest: [9u8; 32],
+                    epoch,
+                },
+                view,
+                &upgrade_lock,
+            )
+            .await,
+        ),
+        (
+            "upgrade vote",
+            vote_digest(
+                UpgradeProposalData {
+                    old_version: Version { major: 0, minor: 1 },
+                    new_version: Version { major: 0, minor: 2 },
+                    decide_by: ViewNumber::new(20),
+                    new_version_hash: vec![1, 2, 3],
+                    old_version_last_view: ViewNumber::new(25),
+                    new_version_first_view: ViewNumber::new(26),
+                },
+                view,
+                &upgrade_lock,
+            )
+            .await,
+        ),
+        (
+            "proposal request",
+            digest(&ProposalRequestPayload::<TestTypes> {
+                view_number: view,
+                key,
+            }),
+        ),
+        (
+            "VID request",
+            digest(&RequestKind::<TestTypes>::Vid(view, key)),
+        ),
+        (
+            "DA proposal request",
+            digest(&RequestKind::<TestTypes>::DaProposal(view)),
+        ),
+        (
+            "proposal data request",
+            digest(&RequestKind::<TestTypes>::Proposal(view)),
+        ),
+    ];
+    vectors.sort();
+
+    let actual: String = vectors
+        .into_iter()
+        .map(|(name, digest)| format!("{name}: {digest}\n"))
+        .collect();
+    let path = PathBuf::from(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/golden/signing_vectors.txt"
+    ));
+    if let Err(e) = check_golden(&path, &actual) {
+        panic!("{e}");
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use hotshot::traits::implementations::{MasterMap, MemoryNetwork, SplitNetwork};
+use hotshot_example_types::node_types::{SplitImpl, TestTypes, TestVersions};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    test_builder::TestDescription,
+};
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    traits::{
+        network::{BroadcastDelay, ConnectedNetwork, Topic},
+        signature_key::SignatureKey,
+    },
+};
+use tokio::time::timeout;
+
+cross_tests!(
+    TestName: test_success_with_separate_da_network,
+    Impls: [SplitImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            ..TestDescription::default()
+        }
+    },
+);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn da_payloads_go_over_the_da_network() {
+    let (consensus_group, da_group) = (MasterMap::new(), MasterMap::new());
+    let keys: Vec<_> = (0..2)
+        .map(|i| BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0)
+        .collect();
+    let networks: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            let topics = [Topic::Global, Topic::Da];
+            SplitNetwork::new(
+                MemoryNetwork::new(key, &consensus_group, &topics, None),
+                MemoryNetwork::new(key, &da_group, &topics, None),
+            )
+        })
+        .collect();
+
+    let (payload, vote) = (Bytes::from_static(b"payload"), Bytes::from_static(b"vote"));
+    networks[0]
+        .da_broadcast_message(payload.clone(), vec![keys[1]], BroadcastDelay::None)
+        .await
+        .unwrap();
+    assert_eq!(networks[1].da().recv_message().await.unwrap(), payload);
+    networks[0]
+        .direct_message(vote.clone(), keys[1])
+        .await
+        .unwrap();
+    assert_eq!(networks[1].consensus().recv_message().await.unwrap(), vote);
+
+    // With messages waiting on both networks, those on the consensus network are taken first
+    networks[0]
+        .da_broadcast_message(payload.clone(), vec![keys[1]], BroadcastDelay::None)
+        .await
+        .unwrap();
+    networks[0]
+        .direct_message(vote.clone(), keys[1])
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(networks[1].recv_message().await.unwrap(), vote);
+    assert_eq!(networks[1].recv_message().await.unwrap(), payload);
+    assert!(
+        timeout(Duration::from_millis(100), networks[1].recv_message())
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use hotshot::traits::election::{
+    delegated_stake_committee::DelegatedStakeCommittee, static_committee::StaticCommittee,
+};
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestStakeWeightedTypes, TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    invariant_task::{Invariant, InvariantTask},
+    test_builder::{StakeDistribution, TestDescription},
+    test_task::{TestResult, TestTaskState},
+};
+use hotshot_types::{
+    data::{Leaf2, ViewNumber},
+    event::{Event, EventType, LeafInfo},
+    signature_key::BLSPubKey,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    PeerConfig,
+};
+
+cross_tests!(
+    TestName: test_success_with_whale,
+    Impls: [MemoryImpl],
+    Types: [TestStakeWeightedTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        // The whale holds half of the stake, so no certificate forms without it
+        let metadata = TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            invariants: vec![
+                Invariant::NoConflictingDecides,
+                Invariant::MonotonicDecides,
+                Invariant::ThresholdsOverStake,
+            ],
+            ..TestDescription::default_multiple_rounds()
+        }
+        .with_stake_distribution(StakeDistribution::Whale { whale_stake: 9 });
+        assert_eq!(metadata.total_stake(), 18);
+
+        metadata
+    },
+);
+
+/// The peers of a test with stakes `stakes`
+fn peers(stakes: &[u64]) -> Vec<PeerConfig<BLSPubKey>> {
+    stakes
+        .iter()
+        .zip(0..)
+        .map(|(stake, i)| PeerConfig {
+            stake_table_entry: BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .stake_table_entry(*stake),
+            ..PeerConfig::default()
+        })
+        .collect()
+}
+
+/// Whether the invariant task, with `membership`, accepts the decide of the genesis leaf
+async fn thresholds_over_stake<TYPES>(membership: TYPES::Membership) -> bool
+where
+    TYPES: NodeType<
+        View = ViewNumber,
+        ValidatedState = TestValidatedState,
+        InstanceState = TestInstanceState,
+    >,
+{
+    let state = TestValidatedState::default();
+    let instance_state = TestInstanceState::default();
+    let leaf = Leaf2::<TYPES>::genesis(&state, &instance_state).await;
+    let qc = QuorumCertificate2::<TYPES>::genesis::<TestVersions>(&state, &instance_state).await;
+    let decide = Event {
+        view_number: ViewNumber::genesis(),
+        event: EventType::Decide {
+            leaf_chain: Arc::new(vec![LeafInfo::new(leaf, Arc::new(state), None, None)]),
+            qc: Arc::new(qc),
+            block_size: None,
+        },
+    };
+
+    let mut task = InvariantTask::<TYPES>::new(
+        vec![Invariant::ThresholdsOverStake],
+        HashSet::new(),
+        membership,
+    );
+    task.handle_event((decide, 0)).await.unwrap();
+    matches!(task.check().await, TestResult::Pass)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_thresholds_over_stake_invariant() {
+    // One whale and nine small validators, with as much stake as the whale together
+    let mut stakes = vec![9];
+    stakes.extend([1; 9]);
+    let skewed = peers(&stakes);
+
+    let stake_weighted =
+        DelegatedStakeCommittee::<TestStakeWeightedTypes>::new(skewed.clone(), skewed.clone());
+    let epoch = <TestStakeWeightedTypes as NodeType>::Epoch::genesis();
+    assert_eq!(stake_weighted.success_threshold(epoch).get(), 13);
+    assert_eq!(stake_weighted.da_success_threshold(epoch).get(), 13);
+    assert_eq!(stake_weighted.failure_threshold(epoch).get(), 7);
+    assert!(thresholds_over_stake::<TestStakeWeightedTypes>(stake_weighted).await);
+
+    // The static committee computes its thresholds over stake too
+    let static_committee = StaticCommittee::<TestTypes>::new(skewed.clone(), skewed);
+    let epoch = <TestTypes as NodeType>::Epoch::genesis();
+    assert_eq!(static_committee.success_threshold(epoch).get(), 13);
+    assert_eq!(static_committee.da_success_threshold(epoch).get(), 13);
+    assert_eq!(static_committee.failure_threshold(epoch).get(), 7);
+    assert!(thresholds_over_stake::<TestTypes>(static_committee).await);
+
+    let uniform = peers(&[1; 10]);
+    let static_committee = StaticCommittee::<TestTypes>::new(uniform.clone(), uniform);
+    assert_eq!(static_committee.success_threshold(epoch).get(), 7);
+    assert!(thresholds_over_stake::<TestTypes>(static_committee).await);
+}
+
+#[test]
+fn test_stake_distributions() {
+    let whale = TestDescription::<TestTypes, MemoryImpl, TestVersions>::default_multiple_rounds()
+        .with_stake_distribution(StakeDistribution::Whale { whale_stake: 100 });
+    assert_eq!(whale.node_stake(0), 100);
+    assert_eq!(whale.node_stake(9), 1);
+    assert_eq!(whale.total_stake(), 109);
+
+    let weighted =
+        TestDescription::<TestTypes, MemoryImpl, TestVersions>::default_multiple_rounds()
+            .with_stake_distribution(StakeDistribution::Weighted(vec![3, 2]));
+    assert_eq!(weighted.node_stake(0), 3);
+    assert_eq!(weighted.node_stake(1), 2);
+    assert_eq!(weighted.node_stake(2), 1);
+    assert_eq!(weighted.total_stake(), 13);
+
+    let uniform = weighted.with_stake_distribution(StakeDistribution::Uniform);
+    assert_eq!(uniform.total_stake(), 10);
+}
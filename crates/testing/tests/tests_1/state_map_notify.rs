@@ -0,0 +1,70 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::helpers::build_system_handle;
+use hotshot_types::{
+    data::{EpochNumber, ViewNumber},
+    traits::{block_contents::vid_commitment, node_implementation::ConsensusTime},
+};
+use tokio::time::{sleep, timeout};
+
+/// The delay with which the transaction task used to poll for the parent view
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_map_update_wakes_waiting_tasks() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let consensus = handle.hotshot.consensus();
+
+    let state_map_changed = consensus.read().await.state_map_changed();
+    let notified = state_map_changed.notified();
+    tokio::pin!(notified);
+    notified.as_mut().enable();
+
+    let view = ViewNumber::new(5);
+    let writer = {
+        let consensus = Arc::clone(&consensus);
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(5)).await;
+            let updated_at = Instant::now();
+            consensus
+                .write()
+                .await
+                .update_da_view(view, EpochNumber::new(0), vid_commitment(&[], 1))
+                .unwrap();
+            updated_at
+        })
+    };
+
+    timeout(POLL_INTERVAL * 10, notified)
+        .await
+        .expect("Waiting task was not woken by the state map update");
+    let woken_at = Instant::now();
+    let updated_at = writer.await.unwrap();
+
+    // The waiting task should see the new view well before it would have polled again.
+    let latency = woken_at.saturating_duration_since(updated_at);
+    assert!(
+        latency < POLL_INTERVAL,
+        "Waiting task took {latency:?} to see the new view"
+    );
+    assert!(consensus
+        .read()
+        .await
+        .validated_state_map()
+        .contains_key(&view));
+}
@@ -0,0 +1,259 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::state_sync::{StateSync, StateSyncEvent};
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_testing::{
+    helpers::{build_cert, build_system_handle, key_pair_for_id},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    data::{EpochNumber, Leaf2},
+    simple_certificate::{CheckpointCertificate, QuorumCertificate2},
+    simple_vote::{CheckpointData, CheckpointVote},
+    traits::{
+        node_implementation::ConsensusTime,
+        state_sync::{split_snapshot, SnapshotManifest, SnapshotSource},
+        ValidatedState,
+    },
+};
+
+/// A source serving one snapshot from memory, which can fail to serve one of its chunks once
+struct MemorySource {
+    certificate: CheckpointCertificate<TestTypes>,
+    leaf: Leaf2<TestTypes>,
+    qc: QuorumCertificate2<TestTypes>,
+    manifest: SnapshotManifest,
+    chunks: Vec<Vec<u8>>,
+    failing_chunk: Mutex<Option<usize>>,
+}
+
+#[async_trait]
+impl SnapshotSource<TestTypes> for MemorySource {
+    async fn latest_checkpoint(&self) -> Result<CheckpointCertificate<TestTypes>> {
+        Ok(self.certificate.clone())
+    }
+
+    async fn fetch_leaf(&self, _height: u64) -> Result<Leaf2<TestTypes>> {
+        Ok(self.leaf.clone())
+    }
+
+    async fn fetch_qc(&self, _height: u64) -> Result<QuorumCertificate2<TestTypes>> {
+        Ok(self.qc.clone())
+    }
+
+    async fn fetch_manifest(&self, _height: u64) -> Result<SnapshotManifest> {
+        Ok(self.manifest.clone())
+    }
+
+    async fn fetch_chunk(&self, _height: u64, index: usize) -> Result<Vec<u8>> {
+        let mut failing_chunk = self.failing_chunk.lock().unwrap();
+        if *failing_chunk == Some(index) {
+            *failing_chunk = None;
+            bail!("Connection reset");
+        }
+        Ok(self.chunks[index].clone())
+    }
+}
+
+/// A checkpoint of the default state at the first generated leaf, signed by the whole quorum,
+/// with the QC for that leaf and the QC for the one after it
+async fn checkpoint(
+    node_id: u64,
+) -> (
+    hotshot::types::SystemContextHandle<TestTypes, MemoryImpl, TestVersions>,
+    CheckpointCertificate<TestTypes>,
+    Leaf2<TestTypes>,
+    [QuorumCertificate2<TestTypes>; 2],
+    TestValidatedState,
+) {
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&membership));
+    let views = (&mut generator).take(3).collect::<Vec<_>>().await;
+    let leaf = views[0].leaf.clone();
+    let qcs = [
+        views[1].quorum_proposal.data.justify_qc.clone(),
+        views[2].quorum_proposal.data.justify_qc.clone(),
+    ];
+    let state = TestValidatedState::default();
+
+    let (private_key, public_key) = key_pair_for_id::<TestTypes>(node_id);
+    let certificate = build_cert::<
+        TestTypes,
+        TestVersions,
+        CheckpointData<TestTypes>,
+        CheckpointVote<TestTypes>,
+        CheckpointCertificate<TestTypes>,
+    >(
+        CheckpointData {
+            height: leaf.height(),
+            leaf_commit: leaf.commit(),
+            state_digest: <TestValidatedState as ValidatedState<TestTypes>>::state_digest(&state),
+            epoch: EpochNumber::new(0),
+        },
+        &membership,
+        leaf.view_number(),
+        EpochNumber::new(0),
+        &public_key,
+        &private_key,
+        &handle.hotshot.upgrade_lock,
+    )
+    .await;
+
+    (handle, certificate, leaf, qcs, state)
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_sync_resumes_interrupted_download() {
+    hotshot::helpers::initialize_logging();
+
+    let (handle, certificate, leaf, [qc, _], state) = checkpoint(2).await;
+    let (manifest, chunks) = split_snapshot::<TestTypes>(&state, leaf.height(), 8).unwrap();
+    let total = manifest.num_chunks();
+    assert!(total > 2);
+
+    let source = Arc::new(MemorySource {
+        certificate: certificate.clone(),
+        leaf: leaf.clone(),
+        qc,
+        manifest,
+        chunks,
+        failing_chunk: Mutex::new(Some(2)),
+    });
+
+    let dir = std::env::temp_dir().join(format!("hotshot-state-sync-{}", std::process::id()));
+    let sync = StateSync::new(
+        vec![source as Arc<dyn SnapshotSource<TestTypes>>],
+        Arc::clone(&handle.hotshot.memberships),
+        handle.hotshot.upgrade_lock.clone(),
+        &dir,
+    );
+
+    // The first attempt stops at the chunk which fails to download
+    assert!(sync.sync(TestInstanceState::default()).await.is_err());
+
+    let mut progress = sync.progress();
+    sync.sync(TestInstanceState::default())
+        .await
+        .expect("Resumed state sync failed");
+
+    let mut events = Vec::new();
+    while let Ok(event) = progress.try_recv() {
+        events.push(event);
+    }
+    let height = leaf.height();
+    assert_eq!(
+        events.first(),
+        Some(&StateSyncEvent::CheckpointFound { height, epoch: 0 })
+    );
+    assert!(events.contains(&StateSyncEvent::Resumed {
+        height,
+        chunks: 2,
+        total
+    }));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, StateSyncEvent::ChunkDownloaded { .. }))
+            .count(),
+        total - 2
+    );
+    assert!(events.contains(&StateSyncEvent::StateVerified { height }));
+    assert_eq!(
+        events.last(),
+        Some(&StateSyncEvent::Completed {
+            height,
+            view: *leaf.view_number()
+        })
+    );
+
+    // Nothing is left behind once the sync has completed
+    assert!(!dir.exists());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_sync_rejects_uncertified_state() {
+    hotshot::helpers::initialize_logging();
+
+    let (handle, certificate, leaf, [qc, _], _) = checkpoint(3).await;
+
+    // A snapshot of some other state, whose manifest claims the certified state digest
+    let other_state =
+        <TestValidatedState as ValidatedState<TestTypes>>::from_header(leaf.block_header());
+    let (mut manifest, chunks) =
+        split_snapshot::<TestTypes>(&other_state, leaf.height(), 8).unwrap();
+    manifest.state_digest = certificate.data.state_digest;
+
+    let source = Arc::new(MemorySource {
+        certificate,
+        leaf,
+        qc,
+        manifest,
+        chunks,
+        failing_chunk: Mutex::new(None),
+    });
+
+    let dir = std::env::temp_dir().join(format!(
+        "hotshot-state-sync-uncertified-{}",
+        std::process::id()
+    ));
+    let sync = StateSync::new(
+        vec![source as Arc<dyn SnapshotSource<TestTypes>>],
+        Arc::clone(&handle.hotshot.memberships),
+        handle.hotshot.upgrade_lock.clone(),
+        &dir,
+    );
+
+    assert!(sync.sync(TestInstanceState::default()).await.is_err());
+    assert!(!dir.exists());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_sync_rejects_qc_for_another_leaf() {
+    hotshot::helpers::initialize_logging();
+
+    let (handle, certificate, leaf, [_, next_qc], state) = checkpoint(4).await;
+    let (manifest, chunks) = split_snapshot::<TestTypes>(&state, leaf.height(), 8).unwrap();
+
+    // A valid QC, but for the leaf after the checkpointed one
+    let source = Arc::new(MemorySource {
+        certificate,
+        leaf,
+        qc: next_qc,
+        manifest,
+        chunks,
+        failing_chunk: Mutex::new(None),
+    });
+
+    let dir = std::env::temp_dir().join(format!(
+        "hotshot-state-sync-wrong-qc-{}",
+        std::process::id()
+    ));
+    let sync = StateSync::new(
+        vec![source as Arc<dyn SnapshotSource<TestTypes>>],
+        Arc::clone(&handle.hotshot.memberships),
+        handle.hotshot.upgrade_lock.clone(),
+        &dir,
+    );
+
+    assert!(sync.sync(TestInstanceState::default()).await.is_err());
+}
@@ -4,13 +4,13 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use hotshot_example_types::{
     node_types::{
         CombinedImpl, EpochsTestVersions, Libp2pImpl, MemoryImpl, PushCdnImpl,
-        TestConsecutiveLeaderTypes, TestTwoStakeTablesTypes, TestTypes, TestTypesRandomizedLeader,
-        TestVersions,
+        TestConsecutiveLeaderTypes, TestStakeWeightedTypes, TestTwoStakeTablesTypes, TestTypes,
+        TestTypesRandomizedLeader, TestVersions,
     },
     testable_delay::{DelayConfig, DelayOptions, DelaySettings, SupportedTraitTypesForAsyncDelay},
 };
@@ -18,11 +18,14 @@ use hotshot_macros::cross_tests;
 use hotshot_testing::{
     block_builder::SimpleBuilderImplementation,
     completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    golden_trace::GoldenTraceDescription,
+    invariant_task::Invariant,
     overall_safety_task::OverallSafetyPropertiesDescription,
     spinning_task::{ChangeNode, NodeAction, SpinningTaskDescription},
-    test_builder::TestDescription,
+    test_builder::{NodeOverride, TestDescription, TimingData},
     view_sync_task::ViewSyncTaskDescription,
 };
+use hotshot_types::traits::network::SynchronousNetwork;
 
 cross_tests!(
     TestName: test_success,
@@ -43,6 +46,128 @@ cross_tests!(
     },
 );
 
+cross_tests!(
+    TestName: test_success_with_invariants,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            invariants: vec![
+                Invariant::NoConflictingDecides,
+                Invariant::MonotonicDecides,
+                Invariant::AgreeOnBlocks,
+                Invariant::MinThroughput(0.2),
+            ],
+            ..TestDescription::default()
+        }
+    },
+);
+
+// Runs on a virtual clock with seeded randomness, so a failure can be replayed from its seed
+cross_tests!(
+    TestName: test_success_deterministic,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Deterministic: true,
+    Metadata: {
+        TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            unreliable_network: Some(Box::new(SynchronousNetwork {
+                delay_high_ms: 30,
+                delay_low_ms: 4,
+            })),
+            ..TestDescription::default()
+        }
+    },
+);
+
+// The messages exchanged in the first views must match the recorded golden trace
+cross_tests!(
+    TestName: test_success_golden_trace,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Deterministic: true,
+    Metadata: {
+        TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            golden_trace: Some(GoldenTraceDescription {
+                path: PathBuf::from(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/golden/test_success_golden_trace.trace"
+                )),
+                last_view: 5,
+            }),
+            ..TestDescription::default()
+        }
+    },
+);
+
+// Nodes with different stakes, timeouts and storage latencies. The membership weighs votes by
+// stake, so the stake overrides change who can form a certificate.
+cross_tests!(
+    TestName: test_success_with_heterogeneous_nodes,
+    Impls: [MemoryImpl],
+    Types: [TestStakeWeightedTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription {
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            ..TestDescription::default()
+        };
+
+        let mut slow_storage = DelayConfig::default();
+        slow_storage.add_settings_for_all_types(DelaySettings {
+            delay_option: DelayOptions::Fixed,
+            min_time_in_milliseconds: 0,
+            max_time_in_milliseconds: 0,
+            fixed_time_in_milliseconds: 50,
+        });
+        metadata.node_overrides = HashMap::from([
+            (0, NodeOverride {
+                stake: Some(3),
+                ..NodeOverride::default()
+            }),
+            (1, NodeOverride {
+                stake: Some(2),
+                timing_data: Some(TimingData {
+                    next_view_timeout: 6000,
+                    ..TimingData::default()
+                }),
+                ..NodeOverride::default()
+            }),
+            (2, NodeOverride {
+                storage_delay_config: Some(slow_storage),
+                ..NodeOverride::default()
+            }),
+        ]);
+        metadata
+    },
+);
+
 // cross_tests!(
 //     TestName: test_epoch_success,
 //     Impls: [MemoryImpl, Libp2pImpl, PushCdnImpl],
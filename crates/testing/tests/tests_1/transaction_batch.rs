@@ -0,0 +1,68 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+};
+use hotshot_testing::helpers::build_system_handle;
+use hotshot_types::error::{HotShotError, TransactionRejection};
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submit_transactions_admits_a_batch() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let transactions = vec![
+        TestTransaction::new(vec![1]),
+        TestTransaction::new(vec![2]),
+        TestTransaction::new(vec![1]),
+    ];
+
+    let receipts = handle.submit_transactions(transactions.clone()).await;
+    assert_eq!(receipts.len(), 3);
+    assert_eq!(
+        receipts[0].as_ref().unwrap().commitment(),
+        transactions[0].commit()
+    );
+    assert_eq!(
+        receipts[1].as_ref().unwrap().commitment(),
+        transactions[1].commit()
+    );
+    // The same transaction twice in a batch is only admitted once
+    assert!(matches!(
+        receipts[2],
+        Err(HotShotError::TransactionRejected(
+            TransactionRejection::Duplicate
+        ))
+    ));
+
+    // Streamed transactions are admitted in order, whatever batches they arrive in
+    let streamed = vec![
+        TestTransaction::new(vec![3]),
+        TestTransaction::new(vec![2]),
+        TestTransaction::new(vec![4]),
+    ];
+    let receipts: Vec<_> = handle
+        .submit_transaction_stream(futures::stream::iter(streamed.clone()), 2)
+        .collect()
+        .await;
+    assert_eq!(receipts.len(), 3);
+    assert_eq!(
+        receipts[0].as_ref().unwrap().commitment(),
+        streamed[0].commit()
+    );
+    assert!(receipts[1].is_err());
+    assert_eq!(
+        receipts[2].as_ref().unwrap().commitment(),
+        streamed[2].commit()
+    );
+}
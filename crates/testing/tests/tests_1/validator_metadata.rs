@@ -0,0 +1,129 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
+use hotshot_testing::helpers::{build_system_handle, key_pair_for_id};
+use hotshot_types::validator_metadata::{
+    SignedValidatorMetadata, ValidatorMetadata, ValidatorMetadataRegistry, MAX_MONIKER_LEN,
+};
+use tokio::time::{sleep, timeout};
+
+/// The metadata of a validator named `moniker`
+fn metadata(moniker: &str) -> ValidatorMetadata {
+    ValidatorMetadata {
+        moniker: moniker.to_string(),
+        contact: "ops@example.com".to_string(),
+        website: "https://example.com".to_string(),
+    }
+}
+
+/// Record `sequence` of the validator with id `node_id`, naming it `moniker`
+fn signed(node_id: u64, moniker: &str, sequence: u64) -> SignedValidatorMetadata<TestTypes> {
+    let (private_key, public_key) = key_pair_for_id::<TestTypes>(node_id);
+    SignedValidatorMetadata::new(metadata(moniker), sequence, public_key, &private_key).unwrap()
+}
+
+#[test]
+fn test_validator_metadata_registry_keeps_latest_valid_record() {
+    let mut registry = ValidatorMetadataRegistry::<TestTypes>::default();
+    let public_key = key_pair_for_id::<TestTypes>(1).1;
+
+    assert!(registry.insert(signed(1, "first", 5)));
+    assert_eq!(registry.get(&public_key).unwrap().metadata.moniker, "first");
+
+    // Older and replayed records do not replace the latest one
+    assert!(!registry.insert(signed(1, "older", 4)));
+    assert!(!registry.insert(signed(1, "first", 5)));
+    assert!(registry.insert(signed(1, "second", 6)));
+    assert_eq!(
+        registry.get(&public_key).unwrap().metadata.moniker,
+        "second"
+    );
+
+    // Records tampered with, or signed by another validator, are refused
+    let mut tampered = signed(1, "third", 7);
+    tampered.metadata.moniker = "forged".to_string();
+    assert!(!registry.insert(tampered));
+    let mut impersonated = signed(2, "third", 7);
+    impersonated.public_key = public_key;
+    assert!(!registry.insert(impersonated));
+    assert_eq!(
+        registry.get(&public_key).unwrap().metadata.moniker,
+        "second"
+    );
+
+    // Metadata which cannot be displayed as is cannot be signed
+    let (private_key, public_key) = key_pair_for_id::<TestTypes>(3);
+    let too_long = metadata(&"x".repeat(MAX_MONIKER_LEN + 1));
+    assert!(
+        SignedValidatorMetadata::<TestTypes>::new(too_long, 1, public_key, &private_key).is_err()
+    );
+    assert!(SignedValidatorMetadata::<TestTypes>::new(
+        metadata("bad\nname"),
+        1,
+        public_key,
+        &private_key
+    )
+    .is_err());
+
+    assert!(registry.insert(signed(2, "other", 1)));
+    assert_eq!(registry.records().count(), 2);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_validator_metadata_is_kept_for_staked_validators() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+
+    // Our own metadata is signed, stored and kept
+    let own = handle
+        .set_validator_metadata(metadata("node 2"))
+        .await
+        .unwrap();
+    assert!(own.is_valid());
+    let republished = handle
+        .set_validator_metadata(metadata("node 2, renamed"))
+        .await
+        .unwrap();
+    assert!(republished.sequence > own.sequence);
+    let stored = handle
+        .storage()
+        .read()
+        .await
+        .validator_metadata_cloned()
+        .await;
+    assert_eq!(stored.get(&own.public_key), Some(&republished));
+
+    // The metadata of a validator with stake is kept, that of a key without stake is ignored
+    let staked = signed(1, "node 1", 1);
+    let unstaked = signed(1000, "not a validator", 1);
+    for record in [unstaked.clone(), staked.clone()] {
+        broadcast_event(
+            Arc::new(HotShotEvent::ValidatorMetadataRecv(record)),
+            &handle.internal_event_stream_sender(),
+        )
+        .await;
+    }
+    timeout(Duration::from_secs(5), async {
+        while !handle.validator_metadata().await.contains(&staked) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The metadata of a staked validator was not kept");
+
+    let records = handle.validator_metadata().await;
+    assert_eq!(records.len(), 2);
+    assert!(records.contains(&republished));
+    assert!(!records.contains(&unstaked));
+}
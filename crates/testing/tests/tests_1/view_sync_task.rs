@@ -66,3 +66,184 @@ async fn test_view_sync_task() {
     let view_sync_state = ViewSyncTaskState::<TestTypes, TestVersions>::create_from(&handle).await;
     run_harness(input, output, view_sync_state, false).await;
 }
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_view_sync_task_gossips_certificate_once() {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use hotshot_testing::view_generator::TestViewGenerator;
+    use hotshot_types::{
+        data::ViewChangeEvidence, simple_vote::ViewSyncFinalizeData2,
+        traits::consensus_api::ConsensusApi,
+    };
+
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let mut proposals = Vec::new();
+    for view in (&mut generator).take(1).collect::<Vec<_>>().await {
+        proposals.push(view.quorum_proposal.clone());
+    }
+
+    generator.add_view_sync_finalize(ViewSyncFinalizeData2 {
+        relay: 2,
+        round: ViewNumber::new(node_id),
+        epoch: EpochNumber::new(0),
+    });
+    for view in (&mut generator).take(1).collect::<Vec<_>>().await {
+        proposals.push(view.quorum_proposal.clone());
+    }
+
+    let cert = match proposals[1].data.view_change_evidence.clone().unwrap() {
+        ViewChangeEvidence::ViewSync(vsc) => vsc,
+        ViewChangeEvidence::Timeout(_) => {
+            panic!("Found a TC when there should have been a view sync cert")
+        }
+    };
+
+    // We see the certificate twice, but only re-broadcast it the first time.
+    let input = vec![
+        HotShotEvent::ViewSyncFinalizeCertificateRecv(cert.clone()),
+        HotShotEvent::ViewSyncFinalizeCertificateRecv(cert.clone()),
+        HotShotEvent::Shutdown,
+    ];
+
+    let output = vec![
+        HotShotEvent::ViewSyncFinalizeCertificateSend(cert.clone(), handle.public_key()),
+        HotShotEvent::ViewChange(ViewNumber::new(node_id), EpochNumber::new(0)),
+        HotShotEvent::ViewChange(ViewNumber::new(node_id), EpochNumber::new(0)),
+    ];
+
+    let view_sync_state = ViewSyncTaskState::<TestTypes, TestVersions>::create_from(&handle).await;
+    run_harness(input, output, view_sync_state, false).await;
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_view_sync_task_collects_broadcast_votes() {
+    use std::sync::Arc;
+
+    use hotshot_task_impls::view_sync::{broadcasts_votes, ViewSyncPhase};
+    use hotshot_testing::helpers::key_pair_for_id;
+    use hotshot_types::{
+        constants::VIEW_SYNC_BROADCAST_AFTER_RELAYS,
+        simple_vote::ViewSyncPreCommitVote2,
+        traits::{consensus_api::ConsensusApi, election::Membership},
+        vote::HasViewNumber,
+    };
+
+    hotshot::helpers::initialize_logging();
+
+    let node_id = 2;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let round = ViewNumber::new(4);
+    let relay = VIEW_SYNC_BROADCAST_AFTER_RELAYS;
+    assert!(broadcasts_votes(relay));
+    assert!(!broadcasts_votes(0));
+
+    // We are neither relay 0 nor the broadcast relay of the round
+    let memberships = handle.hotshot.memberships.read().await;
+    for relay in [0, relay] {
+        assert_ne!(
+            memberships
+                .leader(round + relay, EpochNumber::new(0))
+                .unwrap(),
+            handle.public_key()
+        );
+    }
+    drop(memberships);
+
+    let vote_from = |voter: u64, relay: u64| {
+        let upgrade_lock = handle.hotshot.upgrade_lock.clone();
+        async move {
+            let (private_key, public_key) = key_pair_for_id::<TestTypes>(voter);
+            ViewSyncPreCommitVote2::<TestTypes>::create_signed_vote(
+                ViewSyncPreCommitData2 {
+                    relay,
+                    round,
+                    epoch: EpochNumber::new(0),
+                },
+                round,
+                &public_key,
+                &private_key,
+                &upgrade_lock,
+            )
+            .await
+            .expect("Failed to create a ViewSyncPreCommitVote!")
+        }
+    };
+
+    let mut state = ViewSyncTaskState::<TestTypes, TestVersions>::create_from(&handle).await;
+    let (tx, mut rx) = async_broadcast::broadcast(1024);
+
+    // Votes for a designated relay are only collected by the relay
+    assert!(state
+        .handle(
+            Arc::new(HotShotEvent::ViewSyncPreCommitVoteRecv(
+                vote_from(0, 0).await
+            )),
+            tx.clone(),
+        )
+        .await
+        .is_err());
+
+    // Our replica task falls back to relay 2, so we collect broadcast votes up to relay 4, and
+    // votes for far higher relays cannot crowd out those for the current one
+    for timed_out_relay in 0..2 {
+        state
+            .handle(
+                Arc::new(HotShotEvent::ViewSyncTimeout(
+                    round,
+                    timed_out_relay,
+                    ViewSyncPhase::None,
+                )),
+                tx.clone(),
+            )
+            .await
+            .unwrap();
+    }
+    for far_relay in [relay + 1, u64::MAX - 1, u64::MAX] {
+        assert!(state
+            .handle(
+                Arc::new(HotShotEvent::ViewSyncPreCommitVoteRecv(
+                    vote_from(1, far_relay).await
+                )),
+                tx.clone(),
+            )
+            .await
+            .is_err());
+    }
+
+    // Votes for a broadcast relay are collected by every node, which forms the certificate
+    for voter in 0..10 {
+        let _ = state
+            .handle(
+                Arc::new(HotShotEvent::ViewSyncPreCommitVoteRecv(
+                    vote_from(voter, relay).await,
+                )),
+                tx.clone(),
+            )
+            .await;
+    }
+
+    let mut certificates = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let HotShotEvent::ViewSyncPreCommitCertificateSend(certificate, sender) = event.as_ref()
+        {
+            assert_eq!(*sender, handle.public_key());
+            certificates.push(certificate.clone());
+        }
+    }
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(certificates[0].view_number(), round);
+    assert_eq!(certificates[0].data.relay, relay);
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_lock::RwLock;
+use futures::StreamExt;
+use hotshot::traits::implementations::MemoryNetwork;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_task::task::{ConsensusTaskRegistry, Task};
+use hotshot_task_impls::{events::HotShotEvent, network::NetworkEventTaskState};
+use hotshot_testing::{
+    helpers::build_system_handle, test_builder::TestDescription,
+    test_task::add_network_message_test_task, view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    consensus::OuterConsensus,
+    data::{EpochNumber, ViewNumber},
+    message::{UpgradeLock, WireEncodings},
+    traits::node_implementation::ConsensusTime,
+    vote::{HasViewNumber, Vote},
+    vote_intent::{VoteIntent, VoteKind},
+};
+use tokio::time::timeout;
+
+// A node restarted with a stale last voted view still refuses to send a vote conflicting with
+// one it intended before the restart
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_vote_conflicting_with_intent_is_not_sent() {
+    hotshot::helpers::initialize_logging();
+
+    let builder: TestDescription<TestTypes, MemoryImpl, TestVersions> =
+        TestDescription::default_multiple_rounds();
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    let node_id = 1;
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(node_id)
+        .await
+        .0;
+    let launcher = builder.gen_launcher(node_id);
+
+    let network = (launcher.resource_generator.channel_generator)(node_id).await;
+    let storage = (launcher.resource_generator.storage)(node_id);
+    let consensus = OuterConsensus::new(handle.hotshot.consensus());
+    let public_key = launcher
+        .resource_generator
+        .validator_config
+        .public_key
+        .clone();
+    let private_key = launcher
+        .resource_generator
+        .validator_config
+        .private_key
+        .clone();
+
+    let network_state: NetworkEventTaskState<TestTypes, TestVersions, MemoryNetwork<_>, _> =
+        NetworkEventTaskState {
+            network: network.clone(),
+            view: ViewNumber::new(0),
+            epoch: EpochNumber::new(0),
+            membership: Arc::clone(&handle.hotshot.memberships),
+            upgrade_lock: upgrade_lock.clone(),
+            storage: Arc::new(RwLock::new(storage.clone())),
+            consensus,
+            transmit_tasks: BTreeMap::new(),
+            paused: Arc::default(),
+            namespace: 0,
+            wire_encodings: WireEncodings::default(),
+            private_key,
+            message_ttl: None,
+        };
+    let (tx, rx) = async_broadcast::broadcast(10);
+    let mut task_reg = ConsensusTaskRegistry::new();
+    task_reg.run_task(Task::new(network_state, tx.clone(), rx));
+
+    let (out_tx_internal, mut out_rx_internal) = async_broadcast::broadcast(10);
+    let (out_tx_external, _) = async_broadcast::broadcast(10);
+    add_network_message_test_task(
+        out_tx_internal,
+        out_tx_external,
+        upgrade_lock,
+        network,
+        public_key,
+    )
+    .await;
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(3).collect::<Vec<_>>().await;
+    let conflicting = views[1].create_quorum_vote(&handle).await;
+    let consistent = views[2].create_quorum_vote(&handle).await;
+
+    // Before the restart, the node intended to vote for another leaf in the view of `conflicting`
+    handle
+        .hotshot
+        .consensus()
+        .write()
+        .await
+        .restore_vote_intents([VoteIntent {
+            view: conflicting.view_number(),
+            kind: VoteKind::Quorum,
+            commitment: [0xff; 32],
+        }]);
+
+    tx.broadcast_direct(Arc::new(HotShotEvent::ExtendedQuorumVoteSend(conflicting)))
+        .await
+        .unwrap();
+    assert!(
+        timeout(Duration::from_millis(200), out_rx_internal.recv_direct())
+            .await
+            .is_err(),
+        "a conflicting vote was sent"
+    );
+    assert!(storage.vote_intents_cloned().await.is_empty());
+
+    // Votes without a conflicting intent are written ahead, then sent
+    tx.broadcast_direct(Arc::new(HotShotEvent::ExtendedQuorumVoteSend(
+        consistent.clone(),
+    )))
+    .await
+    .unwrap();
+    let res: Arc<HotShotEvent<TestTypes>> =
+        timeout(Duration::from_millis(200), out_rx_internal.recv_direct())
+            .await
+            .expect("timed out waiting for the vote")
+            .expect("channel closed");
+    assert!(matches!(res.as_ref(), HotShotEvent::QuorumVoteRecv(_)));
+    assert_eq!(
+        storage.vote_intents_cloned().await,
+        vec![VoteIntent {
+            view: consistent.view_number(),
+            kind: VoteKind::Quorum,
+            commitment: consistent.data_commitment().into(),
+        }]
+    );
+}
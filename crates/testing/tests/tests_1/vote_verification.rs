@@ -0,0 +1,218 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_task_impls::vote_verification::verify_votes;
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    constants::VERIFIED_VOTES_VIEWS,
+    message::{GeneralConsensusMessage, Message, MessageKind, SenderAuth, SequencingMessage},
+    simple_vote::{QuorumVote2, VersionedVoteData},
+    vote::{HasViewNumber, VerifiedVotes, Vote},
+};
+
+/// A message carrying `vote`
+fn vote_message(vote: QuorumVote2<TestTypes>) -> Message<TestTypes> {
+    Message {
+        sender: vote.signing_key(),
+        kind: MessageKind::Consensus(SequencingMessage::General(GeneralConsensusMessage::Vote2(
+            vote,
+        ))),
+        namespace: 0,
+        expires_after: None,
+    }
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_vote_verification_records_valid_votes() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let upgrade_lock = handle.hotshot.upgrade_lock.clone();
+    let verified_votes = VerifiedVotes::default();
+
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let mut votes = Vec::new();
+    for view in (&mut generator).take(2).collect::<Vec<_>>().await {
+        votes.push(view.create_quorum_vote(&handle).await);
+    }
+
+    // A valid vote is handled, and its signature remembered once
+    let vote = votes[0].clone();
+    let current_view = vote.view_number();
+    assert!(
+        verify_votes(
+            &vote_message(vote.clone()),
+            current_view,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Signed
+        )
+        .await
+    );
+    let vote_commitment =
+        VersionedVoteData::new(vote.date().clone(), vote.view_number(), &upgrade_lock)
+            .await
+            .unwrap()
+            .commit();
+    let other_commitment =
+        VersionedVoteData::new(votes[1].date().clone(), vote.view_number(), &upgrade_lock)
+            .await
+            .unwrap()
+            .commit();
+    assert!(
+        !verified_votes
+            .take(
+                vote.view_number(),
+                &vote.signing_key(),
+                other_commitment.as_ref(),
+                &vote.signature()
+            )
+            .await
+    );
+    assert!(
+        verified_votes
+            .take(
+                vote.view_number(),
+                &vote.signing_key(),
+                vote_commitment.as_ref(),
+                &vote.signature()
+            )
+            .await
+    );
+    assert!(
+        !verified_votes
+            .take(
+                vote.view_number(),
+                &vote.signing_key(),
+                vote_commitment.as_ref(),
+                &vote.signature()
+            )
+            .await
+    );
+
+    // A vote with the signature of another vote is dropped, and not remembered
+    let mut forged = votes[0].clone();
+    forged.signature.1 = votes[1].signature();
+    assert!(
+        !verify_votes(
+            &vote_message(forged.clone()),
+            current_view,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Signed
+        )
+        .await
+    );
+    assert!(
+        !verified_votes
+            .take(
+                forged.view_number(),
+                &forged.signing_key(),
+                vote_commitment.as_ref(),
+                &forged.signature()
+            )
+            .await
+    );
+
+    // Messages without votes are always handled
+    let message = Message::<TestTypes> {
+        sender: handle.public_key(),
+        kind: MessageKind::External(vec![1, 2, 3]),
+        namespace: 0,
+        expires_after: None,
+    };
+    assert!(
+        verify_votes(
+            &message,
+            current_view,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Signed
+        )
+        .await
+    );
+
+    // Votes far behind or ahead of the current view are left to their collectors, so even the
+    // forged one is handled here
+    let later_view = current_view + VERIFIED_VOTES_VIEWS + 1;
+    assert!(
+        verify_votes(
+            &vote_message(forged.clone()),
+            later_view,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Signed
+        )
+        .await
+    );
+    let mut ahead = forged.clone();
+    ahead.view_number = later_view;
+    assert!(
+        verify_votes(
+            &vote_message(ahead.clone()),
+            current_view,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Signed
+        )
+        .await
+    );
+
+    // Unless the vote is all that authenticates the sender of the message, in which case it is
+    // checked whatever its view, and only remembered if its view is tracked
+    for (message, view) in [
+        (vote_message(forged.clone()), later_view),
+        (vote_message(ahead), current_view),
+        (message, current_view),
+    ] {
+        assert!(
+            !verify_votes(
+                &message,
+                view,
+                &upgrade_lock,
+                &verified_votes,
+                SenderAuth::Vote
+            )
+            .await
+        );
+    }
+    assert!(
+        verify_votes(
+            &vote_message(votes[1].clone()),
+            later_view + 1,
+            &upgrade_lock,
+            &verified_votes,
+            SenderAuth::Vote
+        )
+        .await
+    );
+    let commitment = VersionedVoteData::new(
+        votes[1].date().clone(),
+        votes[1].view_number(),
+        &upgrade_lock,
+    )
+    .await
+    .unwrap()
+    .commit();
+    assert!(
+        !verified_votes
+            .take(
+                votes[1].view_number(),
+                &votes[1].signing_key(),
+                commitment.as_ref(),
+                &votes[1].signature()
+            )
+            .await
+    );
+}
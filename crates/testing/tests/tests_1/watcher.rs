@@ -0,0 +1,60 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation, helpers::build_system_handle_from_launcher,
+    test_builder::TestDescription,
+};
+use hotshot_types::{
+    error::HotShotConfigError, signature_key::BLSPubKey, traits::signature_key::SignatureKey,
+};
+
+cross_tests!(
+    TestName: test_success_with_watchers,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        // The watchers decide the same leaves as the staked nodes without voting
+        TestDescription {
+            num_watchers: 2,
+            ..TestDescription::default_multiple_rounds()
+        }
+    },
+);
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_watcher_never_participates() {
+    hotshot::helpers::initialize_logging();
+
+    let mut launcher =
+        TestDescription::<TestTypes, MemoryImpl, TestVersions>::default_multiple_rounds()
+            .gen_launcher(0);
+    launcher.resource_generator.config.watcher = true;
+    let config = launcher.resource_generator.config.clone();
+
+    // A watcher may not have stake
+    let staked = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let watcher = BLSPubKey::generated_from_seed_indexed([0u8; 32], 10).0;
+    assert_eq!(
+        config.validate_node(&staked),
+        Err(HotShotConfigError::WatcherWithStake)
+    );
+    assert_eq!(config.validate_node(&watcher), Ok(()));
+
+    // and stays paused even when told to resume
+    let handle =
+        build_system_handle_from_launcher::<TestTypes, MemoryImpl, TestVersions>(10, &launcher)
+            .await
+            .0;
+    assert!(handle.is_paused());
+    handle.resume();
+    assert!(handle.is_paused());
+}
@@ -0,0 +1,94 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use hotshot::traits::implementations::WriteBehindStorage;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    storage_types::TestStorage,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::traits::storage::Storage;
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_behind_storage_applies_writes_by_flush() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(3).collect::<Vec<_>>().await;
+
+    let storage = WriteBehindStorage::new(TestStorage::<TestTypes>::default());
+    for view in &views {
+        storage
+            .append_proposal2(&view.quorum_proposal)
+            .await
+            .unwrap();
+    }
+    storage.flush().await.unwrap();
+
+    let proposals = storage.inner().proposals_cloned().await;
+    assert_eq!(proposals.len(), views.len());
+    for view in &views {
+        assert_eq!(
+            proposals[&view.quorum_proposal.data.view_number].data,
+            view.quorum_proposal.data
+        );
+    }
+
+    // A failed write is reported by the next flush, and only by it
+    let failing = WriteBehindStorage::new(TestStorage::<TestTypes> {
+        should_return_err: true,
+        ..TestStorage::default()
+    });
+    failing
+        .append_proposal2(&views[0].quorum_proposal)
+        .await
+        .unwrap();
+    assert!(failing.flush().await.is_err());
+    assert!(failing.flush().await.is_ok());
+    assert!(failing.inner().proposals_cloned().await.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_behind_storage_writes_lock_and_high_qc_through() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let mut generator = TestViewGenerator::generate(Arc::clone(&handle.hotshot.memberships));
+    let views = (&mut generator).take(2).collect::<Vec<_>>().await;
+
+    // Both are durable as soon as the write returns, without waiting for a flush
+    let storage = WriteBehindStorage::new(TestStorage::<TestTypes>::default());
+    let high_qc = views[1].quorum_proposal.data.justify_qc.clone();
+    storage.update_high_qc2(high_qc.clone()).await.unwrap();
+    storage
+        .update_locked_view(high_qc.view_number)
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.inner().high_qc_cloned().await,
+        Some(high_qc.clone())
+    );
+    assert_eq!(storage.inner().locked_view().await, high_qc.view_number);
+
+    // Writes queued before a write-through are applied before it
+    let storage = WriteBehindStorage::new(TestStorage::<TestTypes>::default());
+    storage
+        .append_proposal2(&views[0].quorum_proposal)
+        .await
+        .unwrap();
+    storage.update_high_qc2(high_qc).await.unwrap();
+    assert_eq!(storage.inner().proposals_cloned().await.len(), 1);
+}
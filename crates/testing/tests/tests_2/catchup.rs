@@ -7,7 +7,7 @@
 use std::time::Duration;
 
 use hotshot_example_types::node_types::{
-    CombinedImpl, PushCdnImpl, TestTypes, TestTypesRandomizedLeader, TestVersions,
+    CombinedImpl, MemoryImpl, PushCdnImpl, TestTypes, TestTypesRandomizedLeader, TestVersions,
 };
 use hotshot_macros::cross_tests;
 use hotshot_testing::{
@@ -474,3 +474,48 @@ cross_tests!(
       metadata
     },
 );
+
+// Crash a DA node and a regular node, and restart them from their storage a few views later.
+// The spinning task checks that both catch up and decide again.
+cross_tests!(
+    TestName: test_crash_and_restart_from_storage,
+    Impls: [MemoryImpl, CombinedImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+      let mut metadata = TestDescription::default();
+      metadata.start_nodes = 10;
+      metadata.num_nodes_with_stake = 10;
+      metadata.da_staked_committee_size = 4;
+
+      let crashed_nodes = vec![
+          ChangeNode {
+              idx: 2,
+              updown: NodeAction::RestartDown(5),
+          },
+          ChangeNode {
+              idx: 7,
+              updown: NodeAction::RestartDown(5),
+          },
+      ];
+      metadata.spinning_properties = SpinningTaskDescription {
+          node_changes: vec![(10, crashed_nodes)],
+      };
+
+      metadata.completion_task_description =
+          CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+              TimeBasedCompletionTaskDescription {
+                  duration: Duration::from_secs(60),
+              },
+          );
+      metadata.overall_safety_properties = OverallSafetyPropertiesDescription {
+          num_successful_views: 22,
+          // the crashed nodes miss the views they lead while down
+          num_failed_views: 4,
+          ..Default::default()
+      };
+
+      metadata
+    },
+);
@@ -15,7 +15,7 @@ use hotshot_testing::{
     block_builder::SimpleBuilderImplementation,
     byzantine::byzantine_behaviour::{
         BadProposalViewDos, DishonestDa, DishonestLeader, DishonestVoter, DishonestVoting,
-        DoubleProposeVote,
+        DoubleProposeVote, EquivocatingLeader, InvalidVoteSignatures, WithholdVotes,
     },
     completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
     test_builder::{Behaviour, TestDescription},
@@ -249,3 +249,90 @@ cross_tests!(
         metadata
     },
 );
+
+// Test where f of the nodes never vote
+cross_tests!(
+    TestName: withhold_votes,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let behaviour = Rc::new(|node_id| { match node_id {
+          2..=4 => Behaviour::Byzantine(Box::new(WithholdVotes)),
+          _ => Behaviour::Standard,
+          } });
+
+        TestDescription {
+            // allow more time to pass in CI
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            behaviour,
+            num_nodes_with_stake: 10,
+            da_staked_committee_size: 10,
+            ..TestDescription::default()
+        }
+    },
+);
+
+// Test where f of the nodes send quorum votes with invalid signatures
+cross_tests!(
+    TestName: invalid_vote_signatures,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let behaviour = Rc::new(|node_id| { match node_id {
+          2..=4 => Behaviour::Byzantine(Box::new(InvalidVoteSignatures)),
+          _ => Behaviour::Standard,
+          } });
+
+        TestDescription {
+            // allow more time to pass in CI
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            behaviour,
+            num_nodes_with_stake: 10,
+            da_staked_committee_size: 10,
+            ..TestDescription::default()
+        }
+    },
+);
+
+// Test where node 2 sends two conflicting, validly signed proposals whenever it leads
+cross_tests!(
+    TestName: equivocating_leader,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let behaviour = Rc::new(|node_id| { match node_id {
+          2 => Behaviour::Byzantine(Box::new(EquivocatingLeader::default())),
+          _ => Behaviour::Standard,
+          } });
+
+        let mut metadata = TestDescription {
+            // allow more time to pass in CI
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            behaviour,
+            num_nodes_with_stake: 10,
+            da_staked_committee_size: 10,
+            ..TestDescription::default()
+        };
+
+        metadata.overall_safety_properties.num_failed_views = 15;
+        metadata
+    },
+);
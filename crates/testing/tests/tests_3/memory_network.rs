@@ -7,6 +7,7 @@
 #![allow(clippy::panic)]
 use std::{sync::Arc, time::Duration};
 
+use bytes::Bytes;
 use hotshot::{
     traits::{
         election::static_committee::StaticCommittee,
@@ -27,13 +28,15 @@ use hotshot_types::{
     message::{DataMessage, Message, MessageKind, UpgradeLock},
     signature_key::{BLSPubKey, BuilderKey},
     traits::{
+        block_builder_strategy::PriorityOrdered,
         network::{BroadcastDelay, ConnectedNetwork, TestableNetworkingImplementation, Topic},
         node_implementation::{ConsensusTime, NodeType},
+        proposal_validator::AcceptAllProposals,
     },
 };
 use rand::{rngs::StdRng, RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
-use tokio::time::timeout;
+use tokio::time::{timeout, Instant};
 use tracing::{instrument, trace};
 
 #[derive(
@@ -74,6 +77,8 @@ impl NodeImplementation<Test> for TestImpl {
     type Network = MemoryNetwork<<Test as NodeType>::SignatureKey>;
     type Storage = TestStorage<Test>;
     type AuctionResultsProvider = TestAuctionResultsProvider<Test>;
+    type ProposalValidator = AcceptAllProposals;
+    type BlockBuilderStrategy = PriorityOrdered;
 }
 
 /// fake Eq
@@ -115,6 +120,8 @@ fn gen_messages(num_messages: u64, seed: u64, pk: BLSPubKey) -> Vec<Message<Test
                 TestTransaction::new(bytes.to_vec()),
                 <ViewNumber as ConsensusTime>::new(0),
             )),
+            namespace: 0,
+            expires_after: None,
         };
         messages.push(message);
     }
@@ -169,7 +176,7 @@ async fn memory_network_direct_queue() {
     // Test 1 -> 2
     // Send messages
     for sent_message in first_messages {
-        let serialized_message = upgrade_lock.serialize(&sent_message).await.unwrap();
+        let serialized_message = Bytes::from(upgrade_lock.serialize(&sent_message).await.unwrap());
         network1
             .direct_message(serialized_message.clone(), pub_key_2)
             .await
@@ -190,7 +197,7 @@ async fn memory_network_direct_queue() {
     // Test 2 -> 1
     // Send messages
     for sent_message in second_messages {
-        let serialized_message = upgrade_lock.serialize(&sent_message).await.unwrap();
+        let serialized_message = Bytes::from(upgrade_lock.serialize(&sent_message).await.unwrap());
         network2
             .direct_message(serialized_message.clone(), pub_key_1)
             .await
@@ -226,7 +233,7 @@ async fn memory_network_broadcast_queue() {
     // Test 1 -> 2
     // Send messages
     for sent_message in first_messages {
-        let serialized_message = upgrade_lock.serialize(&sent_message).await.unwrap();
+        let serialized_message = Bytes::from(upgrade_lock.serialize(&sent_message).await.unwrap());
         network1
             .broadcast_message(serialized_message.clone(), Topic::Da, BroadcastDelay::None)
             .await
@@ -247,7 +254,7 @@ async fn memory_network_broadcast_queue() {
     // Test 2 -> 1
     // Send messages
     for sent_message in second_messages {
-        let serialized_message = upgrade_lock.serialize(&sent_message).await.unwrap();
+        let serialized_message = Bytes::from(upgrade_lock.serialize(&sent_message).await.unwrap());
         network2
             .broadcast_message(
                 serialized_message.clone(),
@@ -296,7 +303,7 @@ async fn memory_network_test_in_flight_message_count() {
     let upgrade_lock = UpgradeLock::<Test, TestVersions>::new();
 
     for (count, message) in messages.iter().enumerate() {
-        let serialized_message = upgrade_lock.serialize(message).await.unwrap();
+        let serialized_message = Bytes::from(upgrade_lock.serialize(message).await.unwrap());
 
         network1
             .direct_message(serialized_message.clone(), pub_key_2)
@@ -354,3 +361,89 @@ async fn memory_network_test_in_flight_message_count() {
         Some(0)
     );
 }
+
+// Check that messages are delivered without copying them
+
+#[tokio::test(flavor = "multi_thread")]
+#[instrument]
+async fn memory_network_delivers_without_copying() {
+    let group: Arc<MasterMap<<Test as NodeType>::SignatureKey>> = MasterMap::new();
+    let pub_key_1 = pubkey();
+    let network1 = MemoryNetwork::new(&pub_key_1, &group.clone(), &[Topic::Global], Option::None);
+    let pub_key_2 = pubkey();
+    let network2 = MemoryNetwork::new(&pub_key_2, &group, &[Topic::Global], Option::None);
+
+    let upgrade_lock = UpgradeLock::<Test, TestVersions>::new();
+    let message = &gen_messages(1, 100, pub_key_1)[0];
+    let serialized_message = Bytes::from(upgrade_lock.serialize(message).await.unwrap());
+
+    network1
+        .broadcast_message(
+            serialized_message.clone(),
+            Topic::Global,
+            BroadcastDelay::None,
+        )
+        .await
+        .unwrap();
+    network1
+        .direct_message(serialized_message.clone(), pub_key_2)
+        .await
+        .unwrap();
+
+    // Every recipient shares the buffer of the sender
+    for network in [&network1, &network2, &network2] {
+        let received = network.recv_message().await.unwrap();
+        assert_eq!(received, serialized_message);
+        assert_eq!(received.as_ptr(), serialized_message.as_ptr());
+    }
+}
+
+// Check that batches of messages are bounded by their size and deadline
+
+#[tokio::test(flavor = "multi_thread")]
+#[instrument]
+async fn memory_network_recv_messages() {
+    hotshot::helpers::initialize_logging();
+
+    let group: Arc<MasterMap<<Test as NodeType>::SignatureKey>> = MasterMap::new();
+    let pub_key_1 = pubkey();
+    let network1 = MemoryNetwork::new(&pub_key_1, &group.clone(), &[Topic::Global], Option::None);
+    let pub_key_2 = pubkey();
+    let network2 = MemoryNetwork::new(&pub_key_2, &group, &[Topic::Global], Option::None);
+
+    let upgrade_lock = UpgradeLock::<Test, TestVersions>::new();
+
+    // Nothing arrives before the deadline
+    let deadline = Instant::now() + Duration::from_millis(100);
+    assert!(network2
+        .recv_messages(10, deadline)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let messages = gen_messages(5, 100, pub_key_1);
+    for message in &messages {
+        let serialized_message = Bytes::from(upgrade_lock.serialize(message).await.unwrap());
+        network1
+            .direct_message(serialized_message, pub_key_2)
+            .await
+            .expect("Failed to message node");
+    }
+
+    // The batches hold at most `max` messages, in the order they were sent
+    let deadline = Instant::now() + Duration::from_secs(1);
+    let mut received = Vec::new();
+    while received.len() < messages.len() {
+        let batch = network2.recv_messages(3, deadline).await.unwrap();
+        assert!(!batch.is_empty() && batch.len() <= 3);
+        received.extend(batch);
+    }
+    for (sent_message, recv_message) in messages.into_iter().zip(received) {
+        let deserialized_message = upgrade_lock.deserialize(&recv_message).await.unwrap();
+        fake_message_eq(sent_message, deserialized_message);
+    }
+    assert_eq!(
+        TestableNetworkingImplementation::<Test>::in_flight_message_count(&network2),
+        Some(0)
+    );
+}
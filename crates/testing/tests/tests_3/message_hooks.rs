@@ -0,0 +1,58 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::{Arc, Mutex};
+
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestVersions},
+    state_types::TestTypes,
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation, message_hook::message_hook,
+    test_builder::TestDescription,
+};
+use hotshot_types::{
+    message::{DaConsensusMessage, MessageKind, SequencingMessage},
+    traits::network::Interception,
+    vote::HasViewNumber,
+};
+
+// Drop the first DA proposal from node 3 to every recipient. The view fails, and consensus
+// recovers in the next one.
+cross_tests!(
+    TestName: test_drop_first_da_proposal,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription::default();
+        let dropped_view = Arc::new(Mutex::new(None));
+        metadata.message_hooks = vec![message_hook::<TestTypes, TestVersions>(
+            metadata.num_nodes_with_stake,
+            move |sender, _recipient, message| {
+                let MessageKind::Consensus(SequencingMessage::Da(
+                    DaConsensusMessage::DaProposal(_) | DaConsensusMessage::DaProposal2(_),
+                )) = &message.kind
+                else {
+                    return Interception::Deliver;
+                };
+                if sender != 3 {
+                    return Interception::Deliver;
+                }
+                let view = message.view_number();
+                if *dropped_view.lock().unwrap().get_or_insert(view) == view {
+                    Interception::Drop
+                } else {
+                    Interception::Deliver
+                }
+            },
+        )];
+        metadata.overall_safety_properties.num_failed_views = 2;
+        metadata
+    }
+);
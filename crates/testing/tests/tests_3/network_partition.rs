@@ -0,0 +1,42 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestVersions},
+    state_types::TestTypes,
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation, spinning_task::PartitionDescription,
+    test_builder::TestDescription,
+};
+
+// Cut f nodes off from the rest of the network, then heal the partition and make sure
+// consensus keeps deciding.
+cross_tests!(
+    TestName: test_network_partition_and_heal,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription::default_more_nodes();
+        metadata.num_bootstrap_nodes = 17;
+        // The isolated nodes are outside of the DA committee, so the majority can still
+        // form both a quorum and a DA certificate.
+        metadata.partitions = vec![PartitionDescription {
+            groups: vec![(0..17).collect(), vec![17, 18, 19]],
+            start_view: 5,
+            heal_view: 15,
+        }];
+
+        // The isolated nodes time out on every view while partitioned, and the views they lead
+        // fail for everyone
+        metadata.overall_safety_properties.num_failed_views = 10;
+        metadata.overall_safety_properties.num_successful_views = 22;
+        metadata
+    }
+);
@@ -0,0 +1,61 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestVersions},
+    state_types::TestTypes,
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    spinning_task::ChurnDescription,
+    test_builder::{TestDescription, TimingData},
+};
+
+// Keep restarting nodes and bring new ones online for a couple hundred views, and make sure
+// consensus stays live and no node's state grows without bound.
+cross_tests!(
+    TestName: test_node_churn,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription::default_more_nodes();
+        metadata.start_nodes = 18;
+        metadata.skip_late = true;
+        metadata.timing_data = TimingData {
+            next_view_timeout: 2000,
+            ..Default::default()
+        };
+        // At most two restarting nodes and the two nodes yet to join are offline at once, well
+        // within the fault tolerance of 20 nodes
+        metadata.churn = Some(ChurnDescription {
+            nodes: (0..18).collect(),
+            max_offline: 2,
+            start_view: 10,
+            end_view: 200,
+            interval: 10,
+            offline_views: 5,
+            seed: 0,
+            max_retained_views: Some(50),
+        });
+        metadata.completion_task_description =
+            CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                TimeBasedCompletionTaskDescription {
+                    duration: Duration::from_secs(600),
+                },
+            );
+
+        // Views led by offline nodes fail
+        metadata.overall_safety_properties.num_failed_views = 60;
+        metadata.overall_safety_properties.num_successful_views = 200;
+        metadata
+    }
+);
@@ -0,0 +1,64 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::collections::HashMap;
+
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestVersions},
+    state_types::TestTypes,
+    testable_delay::{DelayConfig, DelayOptions, DelaySettings},
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    test_builder::{NodeOverride, TestDescription},
+};
+
+// A node which cannot persist a proposal neither votes for it nor sends it, but keeps running.
+// With the nodes whose storage fails within f, the rest of the network keeps deciding.
+cross_tests!(
+    TestName: test_with_storage_failures,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [TestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription::default_more_nodes();
+        metadata.num_bootstrap_nodes = 17;
+
+        let mut slow_storage = DelayConfig::default();
+        slow_storage.add_settings_for_all_types(DelaySettings {
+            delay_option: DelayOptions::Fixed,
+            min_time_in_milliseconds: 0,
+            max_time_in_milliseconds: 0,
+            fixed_time_in_milliseconds: 200,
+        });
+
+        // The failing nodes are outside of the DA committee, so DA certificates still form
+        let mut node_overrides = HashMap::from([
+            (15, NodeOverride {
+                storage_write_failure_probability: Some(0.2),
+                ..NodeOverride::default()
+            }),
+            (16, NodeOverride {
+                storage_delay_config: Some(slow_storage),
+                ..NodeOverride::default()
+            }),
+        ]);
+        for idx in 17..20 {
+            node_overrides.insert(idx, NodeOverride {
+                storage_write_failure_probability: Some(1.0),
+                ..NodeOverride::default()
+            });
+        }
+        metadata.node_overrides = node_overrides;
+
+        // The views led by nodes which cannot store their own proposals fail
+        metadata.overall_safety_properties.num_failed_views = 12;
+        metadata.overall_safety_properties.num_successful_views = 22;
+        metadata
+    }
+);
@@ -4,9 +4,12 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-use hotshot_example_types::node_types::{Libp2pImpl, TestTypes, TestVersions};
+use hotshot_example_types::node_types::{Libp2pImpl, MemoryImpl, TestTypes, TestVersions};
 use hotshot_testing::{
     block_builder::SimpleBuilderImplementation,
     completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
@@ -14,7 +17,8 @@ use hotshot_testing::{
     test_builder::{TestDescription, TimingData},
 };
 use hotshot_types::traits::network::{
-    AsynchronousNetwork, ChaosNetwork, PartiallySynchronousNetwork, SynchronousNetwork,
+    AsynchronousNetwork, ChaosNetwork, LatencyDistribution, LinkConditions, NetworkReliability,
+    PartiallySynchronousNetwork, SynchronousNetwork,
 };
 use tracing::instrument;
 
@@ -325,3 +329,64 @@ async fn libp2p_network_chaos() {
         .run_test::<SimpleBuilderImplementation>()
         .await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+#[instrument]
+async fn memory_network_wan_links() {
+    hotshot::helpers::initialize_logging();
+
+    // Nodes 0..3 and 3..6 are in two regions, with a slow and lossy link between them
+    let local = LinkConditions {
+        latency: LatencyDistribution::Uniform {
+            low_ms: 1,
+            high_ms: 5,
+        },
+        jitter_ms: 1,
+        ..LinkConditions::default()
+    };
+    let remote = LinkConditions {
+        latency: LatencyDistribution::Normal {
+            mean_ms: 80.0,
+            std_dev_ms: 10.0,
+        },
+        jitter_ms: 10,
+        drop_probability: 0.05,
+        reorder_probability: 0.1,
+        reorder_delay_ms: 50,
+    };
+    let mut network_links: HashMap<(usize, usize), Box<dyn NetworkReliability>> = HashMap::new();
+    for from in 0..6 {
+        for to in 0..6 {
+            let conditions = if (from < 3) == (to < 3) {
+                local
+            } else {
+                remote
+            };
+            network_links.insert((from, to), Box::new(conditions));
+        }
+    }
+
+    let metadata: TestDescription<TestTypes, MemoryImpl, TestVersions> = TestDescription {
+        overall_safety_properties: OverallSafetyPropertiesDescription {
+            num_successful_views: 20,
+            num_failed_views: 5,
+            ..Default::default()
+        },
+        completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+            TimeBasedCompletionTaskDescription {
+                duration: Duration::from_secs(120),
+            },
+        ),
+        timing_data: TimingData {
+            next_view_timeout: 5000,
+            ..Default::default()
+        },
+        network_links,
+        ..TestDescription::default()
+    };
+    metadata
+        .gen_launcher(0)
+        .launch()
+        .run_test::<SimpleBuilderImplementation>()
+        .await;
+}
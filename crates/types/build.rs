@@ -0,0 +1,14 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Generates the protobuf wire encoding with the `protobuf` feature, so that `protoc` is not
+//! needed without it.
+
+fn main() {
+    #[cfg(feature = "protobuf")]
+    prost_build::compile_protos(&["proto/wire.proto"], &["proto"])
+        .expect("Failed to compile the protobuf wire schema");
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Attestations of application messages by the quorum.
+//!
+//! Applications such as bridges need the quorum to vouch for messages which are not part of the
+//! chain, for instance a withdrawal to be paid out on another chain. A node asks the quorum to
+//! attest to a message as of a leaf it has decided; every member which decided the same leaf and
+//! whose application accepts the message signs it, and the requester assembles the signatures
+//! into an [`Attestation`], which anyone with the stake table of the leaf's epoch can verify.
+//!
+//! Every attestation names its requester and a nonce chosen by the requester. Members sign at
+//! most one message for each requester and nonce, and consumers of attestations reject nonces
+//! they have already seen with [`AttestationNonces`], so an attestation can be neither obtained
+//! for a second message under the same nonce nor replayed.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroU64,
+};
+
+use committable::{Commitment, Committable};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    message::UpgradeLock,
+    simple_certificate::AttestationCertificate,
+    simple_vote::{AttestationData, AttestationVote, VersionedVoteData},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+    vote::{Certificate, HasViewNumber, Vote},
+};
+
+/// The digest of `message` which attestations sign.
+#[must_use]
+pub fn message_digest(message: &[u8]) -> [u8; 32] {
+    Sha256::digest(message).into()
+}
+
+/// A request for the quorum to attest to a message, signed by the requester.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct AttestationRequest<TYPES: NodeType> {
+    /// The requester's own vote for the attestation
+    pub vote: AttestationVote<TYPES>,
+    /// The message to attest to
+    pub message: Vec<u8>,
+}
+
+impl<TYPES: NodeType> AttestationRequest<TYPES> {
+    /// Whether the request is signed by its requester, and its message matches the digest voted
+    /// on.
+    pub async fn is_valid<V: Versions>(&self, upgrade_lock: &UpgradeLock<TYPES, V>) -> bool {
+        let key = self.vote.signing_key();
+        if key != self.vote.data.requester
+            || message_digest(&self.message) != self.vote.data.message_digest
+        {
+            return false;
+        }
+
+        let Ok(data) = VersionedVoteData::new(
+            self.vote.data.clone(),
+            self.vote.view_number(),
+            upgrade_lock,
+        )
+        .await
+        else {
+            return false;
+        };
+        key.validate(&self.vote.signature(), data.commit().as_ref())
+    }
+}
+
+/// A message attested to by the quorum of the epoch of a decided leaf.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct Attestation<TYPES: NodeType> {
+    /// The certificate over the attestation data, which binds the message digest to the leaf
+    pub certificate: AttestationCertificate<TYPES>,
+    /// The attested message
+    pub message: Vec<u8>,
+}
+
+impl<TYPES: NodeType> Attestation<TYPES> {
+    /// The data the quorum signed.
+    #[must_use]
+    pub fn data(&self) -> &AttestationData<TYPES> {
+        &self.certificate.data
+    }
+
+    /// Check that the attestation is for its message, and is signed by at least `threshold` of
+    /// the stake in `stake_table`.
+    ///
+    /// This does not check the nonce, which consumers should record in their
+    /// [`AttestationNonces`] to reject replays.
+    pub async fn verify<V: Versions>(
+        &self,
+        stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+        threshold: NonZeroU64,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        // Certificates for the genesis view are valid without signatures
+        self.certificate.view_number() != TYPES::View::genesis()
+            && message_digest(&self.message) == self.certificate.data.message_digest
+            && self
+                .certificate
+                .is_valid_cert(stake_table, threshold, upgrade_lock)
+                .await
+    }
+
+    /// Check the attestation against the quorum stake table of `membership` for the epoch of the
+    /// attested leaf.
+    pub async fn verify_with_membership<V: Versions>(
+        &self,
+        membership: &TYPES::Membership,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        let epoch = self.certificate.data.epoch;
+        self.verify(
+            membership.stake_table(epoch),
+            membership.success_threshold(epoch),
+            upgrade_lock,
+        )
+        .await
+    }
+}
+
+/// How a nonce was used, relative to the uses recorded before.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonceUse {
+    /// The requester had not used the nonce before
+    Fresh,
+    /// The requester used the nonce before, for the same data
+    Repeated,
+    /// The requester used the nonce before, for other data
+    Reused,
+    /// The nonce is older than every nonce still remembered for the requester
+    Expired,
+}
+
+/// The nonces used by each requester of attestations, to reject replays.
+///
+/// Only the latest `capacity` nonces of each requester are remembered, and older nonces are
+/// refused from then on, so requesters should use increasing nonces.
+#[derive(Clone, Debug)]
+pub struct AttestationNonces<TYPES: NodeType> {
+    /// The attested data, by requester and nonce
+    used: HashMap<TYPES::SignatureKey, BTreeMap<u64, Commitment<AttestationData<TYPES>>>>,
+
+    /// The number of nonces remembered for each requester
+    capacity: usize,
+}
+
+impl<TYPES: NodeType> AttestationNonces<TYPES> {
+    /// Remember the latest `capacity` nonces of each requester.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            used: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record the use of the nonce of `data`, unless it was used for other data or has expired.
+    ///
+    /// A signer should only sign data whose nonce is [`NonceUse::Fresh`] or
+    /// [`NonceUse::Repeated`], while a consumer of attestations should only accept
+    /// [`NonceUse::Fresh`] ones.
+    pub fn record(&mut self, data: &AttestationData<TYPES>) -> NonceUse {
+        let nonces = self.used.entry(data.requester.clone()).or_default();
+        let commit = data.commit();
+
+        if let Some(used) = nonces.get(&data.nonce) {
+            return if *used == commit {
+                NonceUse::Repeated
+            } else {
+                NonceUse::Reused
+            };
+        }
+        if nonces.len() >= self.capacity
+            && nonces
+                .first_key_value()
+                .is_some_and(|(oldest, _)| data.nonce < *oldest)
+        {
+            return NonceUse::Expired;
+        }
+
+        nonces.insert(data.nonce, commit);
+        if nonces.len() > self.capacity {
+            nonces.pop_first();
+        }
+        NonceUse::Fresh
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A cache of certificate verification outcomes, by certificate commitment.
+//!
+//! The same QC is attached to several proposals, and checking its aggregate signature is
+//! expensive. A node remembers whether each certificate it checked was valid against the stake
+//! table and threshold of an epoch, and forgets the outcomes for past epochs when its epoch
+//! changes. The stake table and threshold are part of the key, so an outcome is only reused for a
+//! check against the same ones.
+
+use std::{
+    num::{NonZeroU64, NonZeroUsize},
+    sync::{Arc, Mutex},
+};
+
+use committable::Committable;
+use lru::LruCache;
+
+use crate::{
+    constants::CERTIFICATE_CACHE_CAPACITY,
+    message::UpgradeLock,
+    proposal_cache::lock,
+    stake_table::stake_table_commitment,
+    traits::{
+        node_implementation::{NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+    vote::Certificate,
+};
+
+/// What a certificate was checked against: its commitment, the epoch, the threshold and a
+/// commitment to the stake table
+type OutcomeKey<TYPES> = ([u8; 32], <TYPES as NodeType>::Epoch, u64, [u8; 32]);
+
+/// Whether recently checked certificates were valid, by certificate commitment and the epoch,
+/// threshold and stake table they were checked against, evicting the least recently used outcomes
+#[derive(derive_more::Debug, Clone)]
+pub struct CertificateCache<TYPES: NodeType> {
+    /// the verification outcomes
+    #[debug(skip)]
+    outcomes: Arc<Mutex<LruCache<OutcomeKey<TYPES>, bool>>>,
+}
+
+impl<TYPES: NodeType> Default for CertificateCache<TYPES> {
+    fn default() -> Self {
+        Self::new(CERTIFICATE_CACHE_CAPACITY)
+    }
+}
+
+impl<TYPES: NodeType> CertificateCache<TYPES> {
+    /// A cache of up to `capacity` outcomes, at least one
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            outcomes: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            ))),
+        }
+    }
+
+    /// Whether `cert` is valid against `stake_table` and `threshold` of `epoch`, checking it only
+    /// if no outcome for it is cached for that epoch, stake table and threshold
+    pub async fn is_valid_cert<V: Versions, T, CERT: Certificate<TYPES, T> + Committable>(
+        &self,
+        cert: &CERT,
+        epoch: TYPES::Epoch,
+        stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+        threshold: NonZeroU64,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        let key: OutcomeKey<TYPES> = (
+            cert.commit().into(),
+            epoch,
+            threshold.get(),
+            stake_table_commitment::<TYPES::SignatureKey>(&stake_table),
+        );
+        let cached = lock(&self.outcomes).get(&key).copied();
+        if let Some(valid) = cached {
+            return valid;
+        }
+
+        let valid = cert
+            .is_valid_cert(stake_table, threshold, upgrade_lock)
+            .await;
+        lock(&self.outcomes).put(key, valid);
+
+        valid
+    }
+
+    /// Forget the outcomes for epochs before `epoch`, whose stake tables are no longer current
+    pub fn invalidate_before(&self, epoch: TYPES::Epoch) {
+        let mut outcomes = lock(&self.outcomes);
+        let stale: Vec<_> = outcomes
+            .iter()
+            .filter(|((_, outcome_epoch, _, _), _)| *outcome_epoch < epoch)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in stale {
+            outcomes.pop(&key);
+        }
+    }
+
+    /// The number of cached outcomes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        lock(&self.outcomes).len()
+    }
+
+    /// Whether no outcomes are cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
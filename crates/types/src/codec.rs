@@ -0,0 +1,211 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! The formats messages can be encoded in on the wire.
+//!
+//! Every encoding starts with the version the message was encoded for, so that receivers can
+//! check it, and every encoding but bincode starts with a magic prefix, so that receivers can
+//! tell the encodings apart. [`UpgradeLock::serialize_as`](crate::message::UpgradeLock::serialize_as)
+//! and [`UpgradeLock::deserialize_message`](crate::message::UpgradeLock::deserialize_message)
+//! pick the codec of a [`WireEncoding`].
+
+use bincode::Options;
+use utils::anytrace::*;
+use vbs::version::Version;
+
+use crate::{
+    message::{Message, WireEncoding},
+    traits::node_implementation::NodeType,
+};
+
+/// A format messages are encoded in on the wire
+pub trait WireCodec<TYPES: NodeType> {
+    /// The name of the format, for logs and benchmarks
+    const NAME: &'static str;
+
+    /// Whether `bytes` look like they are in this format
+    fn is_encoded(bytes: &[u8]) -> bool;
+
+    /// Encode `message` for `version`
+    ///
+    /// # Errors
+    /// if the message cannot be encoded, or its encoding is longer than `limit` bytes
+    fn encode(message: &Message<TYPES>, version: Version, limit: usize) -> Result<Vec<u8>>;
+
+    /// Decode a message encoded by [`Self::encode`], with the version it was encoded for
+    ///
+    /// # Errors
+    /// if `bytes` are longer than `limit`, in which case they are not decoded, or malformed
+    fn decode(bytes: &[u8], limit: usize) -> Result<(Version, Message<TYPES>)>;
+}
+
+/// Fail if `len` bytes exceed `limit`
+fn check_limit(len: usize, limit: usize) -> Result<()> {
+    ensure!(
+        len <= limit,
+        info!("Message of {len} bytes exceeds the limit of {limit} bytes")
+    );
+    Ok(())
+}
+
+/// The version at the start of `bytes`, and the rest of them
+fn split_version(bytes: &[u8]) -> Result<(Version, &[u8])> {
+    Version::deserialize(bytes)
+        .wrap()
+        .context(info!("Failed to read message version!"))
+}
+
+/// The versioned bincode encoding every node understands
+pub struct BincodeCodec;
+
+impl BincodeCodec {
+    /// The options of `bincode::serialize`, limited to `limit` bytes
+    fn options(limit: usize) -> impl Options {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(u64::try_from(limit).unwrap_or(u64::MAX))
+    }
+}
+
+impl<TYPES: NodeType> WireCodec<TYPES> for BincodeCodec {
+    const NAME: &'static str = "bincode";
+
+    fn is_encoded(bytes: &[u8]) -> bool {
+        WireEncoding::of::<TYPES>(bytes) == WireEncoding::Bincode
+    }
+
+    fn encode(message: &Message<TYPES>, version: Version, limit: usize) -> Result<Vec<u8>> {
+        let mut bytes = version.serialize();
+        Self::options(limit)
+            .serialize_into(&mut bytes, message)
+            .wrap()
+            .context(info!("Failed to serialize message!"))?;
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8], limit: usize) -> Result<(Version, Message<TYPES>)> {
+        check_limit(bytes.len(), limit)?;
+        let (version, rest) = split_version(bytes)?;
+        let message = Self::options(limit)
+            .deserialize(rest)
+            .wrap()
+            .context(info!("Failed to deserialize message!"))?;
+        Ok((version, message))
+    }
+}
+
+/// The CBOR encoding, preceded by [`CborCodec::MAGIC`] and the version
+pub struct CborCodec;
+
+impl CborCodec {
+    /// The bytes every CBOR encoded message starts with
+    pub const MAGIC: &'static [u8; 4] = b"HSCB";
+}
+
+impl<TYPES: NodeType> WireCodec<TYPES> for CborCodec {
+    const NAME: &'static str = "cbor";
+
+    fn is_encoded(bytes: &[u8]) -> bool {
+        bytes.starts_with(Self::MAGIC)
+    }
+
+    fn encode(message: &Message<TYPES>, version: Version, limit: usize) -> Result<Vec<u8>> {
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.extend(version.serialize());
+        ciborium::into_writer(message, &mut bytes)
+            .wrap()
+            .context(info!("Failed to encode message in CBOR"))?;
+        check_limit(bytes.len(), limit)?;
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8], limit: usize) -> Result<(Version, Message<TYPES>)> {
+        check_limit(bytes.len(), limit)?;
+        let bytes = bytes
+            .strip_prefix(Self::MAGIC)
+            .context(info!("Message is not in CBOR"))?;
+        let (version, rest) = split_version(bytes)?;
+        let message = ciborium::from_reader(rest)
+            .wrap()
+            .context(info!("Failed to decode CBOR message"))?;
+        Ok((version, message))
+    }
+}
+
+/// The protobuf encoding of `proto/wire.proto`, see [`crate::protobuf`]
+#[cfg(feature = "protobuf")]
+pub struct ProtobufCodec;
+
+#[cfg(feature = "protobuf")]
+impl<TYPES: NodeType> WireCodec<TYPES> for ProtobufCodec {
+    const NAME: &'static str = "protobuf";
+
+    fn is_encoded(bytes: &[u8]) -> bool {
+        crate::protobuf::is_protobuf(bytes)
+    }
+
+    fn encode(message: &Message<TYPES>, version: Version, limit: usize) -> Result<Vec<u8>> {
+        let bytes = crate::protobuf::encode(message, version)?;
+        check_limit(bytes.len(), limit)?;
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8], limit: usize) -> Result<(Version, Message<TYPES>)> {
+        check_limit(bytes.len(), limit)?;
+        crate::protobuf::decode(bytes)
+    }
+}
+
+impl WireEncoding {
+    /// The encoding `bytes` are in
+    #[must_use]
+    pub fn of<TYPES: NodeType>(bytes: &[u8]) -> Self {
+        #[cfg(feature = "protobuf")]
+        if <ProtobufCodec as WireCodec<TYPES>>::is_encoded(bytes) {
+            return Self::Protobuf;
+        }
+        if <CborCodec as WireCodec<TYPES>>::is_encoded(bytes) {
+            return Self::Cbor;
+        }
+        Self::Bincode
+    }
+
+    /// Encode `message` for `version` in this encoding
+    ///
+    /// # Errors
+    /// if the message cannot be encoded, or its encoding is longer than `limit` bytes
+    pub fn encode<TYPES: NodeType>(
+        self,
+        message: &Message<TYPES>,
+        version: Version,
+        limit: usize,
+    ) -> Result<Vec<u8>> {
+        match self {
+            Self::Bincode => <BincodeCodec as WireCodec<TYPES>>::encode(message, version, limit),
+            Self::Cbor => <CborCodec as WireCodec<TYPES>>::encode(message, version, limit),
+            #[cfg(feature = "protobuf")]
+            Self::Protobuf => <ProtobufCodec as WireCodec<TYPES>>::encode(message, version, limit),
+        }
+    }
+
+    /// Decode a message in this encoding, with the version it was encoded for
+    ///
+    /// # Errors
+    /// if `bytes` are longer than `limit`, in which case they are not decoded, or malformed
+    pub fn decode<TYPES: NodeType>(
+        self,
+        bytes: &[u8],
+        limit: usize,
+    ) -> Result<(Version, Message<TYPES>)> {
+        match self {
+            Self::Bincode => <BincodeCodec as WireCodec<TYPES>>::decode(bytes, limit),
+            Self::Cbor => <CborCodec as WireCodec<TYPES>>::decode(bytes, limit),
+            #[cfg(feature = "protobuf")]
+            Self::Protobuf => <ProtobufCodec as WireCodec<TYPES>>::decode(bytes, limit),
+        }
+    }
+}
@@ -9,27 +9,37 @@
 use std::{
     collections::{BTreeMap, HashMap},
     mem::ManuallyDrop,
+    num::NonZeroU64,
     ops::{Deref, DerefMut},
     sync::Arc,
 };
 
 use async_lock::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
 use committable::{Commitment, Committable};
+use tokio::sync::Notify;
 use tracing::instrument;
 use utils::anytrace::*;
 use vec1::Vec1;
 
 pub use crate::utils::{View, ViewInner};
 use crate::{
-    data::{Leaf2, QuorumProposal2, VidDisperse, VidDisperseShare2},
+    certificate_cache::CertificateCache,
+    data::{DaChunk, Leaf2, QuorumProposal2, VidDisperse, VidDisperseShare2},
+    drb::{DrbResult, INITIAL_DRB_RESULT},
     error::HotShotError,
     event::{HotShotAction, LeafInfo},
-    message::Proposal,
-    simple_certificate::{DaCertificate2, NextEpochQuorumCertificate2, QuorumCertificate2},
+    fork_tree::ForkTree,
+    liveness::LivenessTracker,
+    memory_budget::{MemoryBudget, MemoryComponent},
+    message::{Proposal, UpgradeLock},
+    proposal_cache::ProposalCache,
+    simple_certificate::{
+        CheckpointCertificate, DaCertificate2, NextEpochQuorumCertificate2, QuorumCertificate2,
+    },
     traits::{
         block_contents::BuilderFee,
         metrics::{Counter, Gauge, Histogram, Metrics, NoMetrics},
-        node_implementation::{ConsensusTime, NodeType},
+        node_implementation::{ConsensusTime, NodeType, Versions},
         signature_key::SignatureKey,
         BlockPayload, ValidatedState,
     },
@@ -39,6 +49,7 @@ use crate::{
     },
     vid::VidCommitment,
     vote::{Certificate, HasViewNumber},
+    vote_intent::{IntentCheck, VoteIntent, VoteIntentLog},
 };
 
 /// A type alias for `HashMap<Commitment<T>, T>`
@@ -122,6 +133,24 @@ impl<TYPES: NodeType> OuterConsensus<TYPES> {
             None
         }
     }
+
+    /// Whether `cert` is valid against `stake_table` and `threshold` of `epoch`, reusing the
+    /// outcome in the certificate cache if it was checked before.
+    ///
+    /// The read lock is only held to get the cache, not while the signature is checked.
+    pub async fn is_valid_cert<V: Versions, T, CERT: Certificate<TYPES, T> + Committable>(
+        &self,
+        cert: &CERT,
+        epoch: TYPES::Epoch,
+        stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+        threshold: NonZeroU64,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        let certificate_cache = self.read().await.certificate_cache().clone();
+        certificate_cache
+            .is_valid_cert(cert, epoch, stake_table, threshold, upgrade_lock)
+            .await
+    }
 }
 
 /// A thin wrapper around `RwLockReadGuard` for `Consensus` that leaves debug traces when the lock is freed
@@ -310,17 +339,45 @@ pub struct Consensus<TYPES: NodeType> {
     /// for DA and Quorum
     last_actions: HotShotActionViews<TYPES::View>,
 
+    /// The votes we were about to send in recent views, so that we never send a conflicting one
+    vote_intents: VoteIntentLog<TYPES>,
+
     /// Saved payloads.
     ///
     /// Encoded transactions for every view if we got a payload for that view.
     saved_payloads: BTreeMap<TYPES::View, Arc<[u8]>>,
 
+    /// Our chunk of the payload of every view in which the DA leader sent us a chunk rather than
+    /// the whole payload
+    da_chunks: BTreeMap<TYPES::View, Proposal<TYPES, DaChunk<TYPES>>>,
+
     /// the highqc per spec
     high_qc: QuorumCertificate2<TYPES>,
 
     /// The high QC for the next epoch
     next_epoch_high_qc: Option<NextEpochQuorumCertificate2<TYPES>>,
 
+    /// The certificate of the latest checkpoint, the trust root for snapshot sync
+    checkpoint_certificate: Option<CheckpointCertificate<TYPES>>,
+
+    /// Wakes up tasks waiting for a view to be added to the validated state map
+    state_map_changed: Arc<Notify>,
+
+    /// How reliably each validator has participated in certificates and view sync
+    liveness: LivenessTracker<TYPES::SignatureKey>,
+
+    /// Recently seen proposals and payloads, kept after garbage collection
+    proposal_cache: ProposalCache<TYPES>,
+
+    /// Outcomes of recently checked certificates, for the current and later epochs
+    certificate_cache: CertificateCache<TYPES>,
+
+    /// The DRB results we computed, by the epoch they are for
+    drb_results: BTreeMap<TYPES::Epoch, DrbResult>,
+
+    /// Accounts for the memory of the retained leaves and payloads
+    memory_budget: MemoryBudget,
+
     /// A reference to the metrics trait
     pub metrics: Arc<ConsensusMetricsValue>,
 
@@ -353,14 +410,66 @@ pub struct ConsensusMetricsValue {
     pub outstanding_transactions: Box<dyn Gauge>,
     /// Memory size in bytes of the serialized transactions still outstanding
     pub outstanding_transactions_memory_size: Box<dyn Gauge>,
+    /// Number of views decided
+    pub number_of_views_decided: Box<dyn Counter>,
     /// Number of views that timed out
     pub number_of_timeouts: Box<dyn Counter>,
     /// Number of views that timed out as leader
     pub number_of_timeouts_as_leader: Box<dyn Counter>,
+    /// Number of views left with a quorum certificate
+    pub number_of_view_changes_by_qc: Box<dyn Counter>,
+    /// Number of views left after a timeout, although their leader proposed
+    pub number_of_view_changes_by_timeout: Box<dyn Counter>,
+    /// Number of views left through view sync
+    pub number_of_view_changes_by_view_sync: Box<dyn Counter>,
+    /// Number of views left after a timeout without a proposal from their leader
+    pub number_of_view_changes_by_leader_offline: Box<dyn Counter>,
     /// The number of empty blocks that have been proposed
     pub number_of_empty_blocks_proposed: Box<dyn Counter>,
+    /// Number of quorum, DA and view sync votes sent
+    pub number_of_votes_sent: Box<dyn Counter>,
+    /// Duration of the storage writes on the voting path, in seconds
+    pub storage_write_duration: Box<dyn Histogram>,
+    /// Time from entering a view to sending our proposal for it as leader, in seconds
+    pub proposal_build_duration: Box<dyn Histogram>,
+    /// Time from the DA proposal of a view to its DA certificate, in seconds
+    pub da_certificate_duration: Box<dyn Histogram>,
+    /// Time from the first quorum vote for a view to its QC as next leader, in seconds
+    pub vote_collection_duration: Box<dyn Histogram>,
+    /// Time from entering a view to deciding its leaf, in seconds
+    pub decide_duration: Box<dyn Histogram>,
     /// Number of events in the hotshot event queue
     pub internal_event_queue_len: Box<dyn Gauge>,
+    /// Number of leaves retained in memory after the last garbage collection
+    pub retained_leaves: Box<dyn Gauge>,
+    /// Number of views retained in the validated state map after the last garbage collection
+    pub retained_states: Box<dyn Gauge>,
+    /// Number of payloads retained in memory after the last garbage collection
+    pub retained_payloads: Box<dyn Gauge>,
+    /// Number of views with VID shares retained in memory after the last garbage collection
+    pub retained_vid_shares: Box<dyn Gauge>,
+    /// Number of DA certificates retained in memory after the last garbage collection
+    pub retained_da_certs: Box<dyn Gauge>,
+    /// Number of proposals retained in memory after the last garbage collection
+    pub retained_proposals: Box<dyn Gauge>,
+    /// Number of proposals found in the proposal cache
+    pub proposal_cache_hits: Box<dyn Counter>,
+    /// Number of proposals looked up in the proposal cache but not found
+    pub proposal_cache_misses: Box<dyn Counter>,
+    /// Number of block payloads found in the proposal cache
+    pub payload_cache_hits: Box<dyn Counter>,
+    /// Number of block payloads looked up in the proposal cache but not found
+    pub payload_cache_misses: Box<dyn Counter>,
+    /// Approximate bytes of network messages received but not handled yet
+    pub network_queue_memory: Box<dyn Gauge>,
+    /// Number of network messages dropped on receipt because they expired
+    pub expired_messages: Box<dyn Counter>,
+    /// Approximate bytes of transactions submitted to this node and still pending
+    pub pending_transactions_memory: Box<dyn Gauge>,
+    /// Approximate bytes of vote signatures checked on arrival and not accumulated yet
+    pub verified_votes_memory: Box<dyn Gauge>,
+    /// Approximate bytes of the leaves and payloads retained in memory
+    pub leaf_store_memory: Box<dyn Gauge>,
 }
 
 impl ConsensusMetricsValue {
@@ -385,13 +494,76 @@ impl ConsensusMetricsValue {
                 .create_gauge(String::from("outstanding_transactions"), None),
             outstanding_transactions_memory_size: metrics
                 .create_gauge(String::from("outstanding_transactions_memory_size"), None),
+            number_of_views_decided: metrics
+                .create_counter(String::from("number_of_views_decided"), None),
             number_of_timeouts: metrics.create_counter(String::from("number_of_timeouts"), None),
             number_of_timeouts_as_leader: metrics
                 .create_counter(String::from("number_of_timeouts_as_leader"), None),
+            number_of_view_changes_by_qc: metrics
+                .create_counter(String::from("number_of_view_changes_by_qc"), None),
+            number_of_view_changes_by_timeout: metrics
+                .create_counter(String::from("number_of_view_changes_by_timeout"), None),
+            number_of_view_changes_by_view_sync: metrics
+                .create_counter(String::from("number_of_view_changes_by_view_sync"), None),
+            number_of_view_changes_by_leader_offline: metrics.create_counter(
+                String::from("number_of_view_changes_by_leader_offline"),
+                None,
+            ),
             number_of_empty_blocks_proposed: metrics
                 .create_counter(String::from("number_of_empty_blocks_proposed"), None),
+            number_of_votes_sent: metrics
+                .create_counter(String::from("number_of_votes_sent"), None),
+            storage_write_duration: metrics.create_histogram(
+                String::from("storage_write_duration"),
+                Some(String::from("seconds")),
+            ),
+            proposal_build_duration: metrics.create_histogram(
+                String::from("proposal_build_duration"),
+                Some(String::from("seconds")),
+            ),
+            da_certificate_duration: metrics.create_histogram(
+                String::from("da_certificate_duration"),
+                Some(String::from("seconds")),
+            ),
+            vote_collection_duration: metrics.create_histogram(
+                String::from("vote_collection_duration"),
+                Some(String::from("seconds")),
+            ),
+            decide_duration: metrics.create_histogram(
+                String::from("decide_duration"),
+                Some(String::from("seconds")),
+            ),
             internal_event_queue_len: metrics
                 .create_gauge(String::from("internal_event_queue_len"), None),
+            retained_leaves: metrics.create_gauge(String::from("retained_leaves"), None),
+            retained_states: metrics.create_gauge(String::from("retained_states"), None),
+            retained_payloads: metrics.create_gauge(String::from("retained_payloads"), None),
+            retained_vid_shares: metrics.create_gauge(String::from("retained_vid_shares"), None),
+            retained_da_certs: metrics.create_gauge(String::from("retained_da_certs"), None),
+            retained_proposals: metrics.create_gauge(String::from("retained_proposals"), None),
+            proposal_cache_hits: metrics.create_counter(String::from("proposal_cache_hits"), None),
+            proposal_cache_misses: metrics
+                .create_counter(String::from("proposal_cache_misses"), None),
+            payload_cache_hits: metrics.create_counter(String::from("payload_cache_hits"), None),
+            payload_cache_misses: metrics
+                .create_counter(String::from("payload_cache_misses"), None),
+            network_queue_memory: metrics.create_gauge(
+                String::from("network_queue_memory"),
+                Some(String::from("bytes")),
+            ),
+            expired_messages: metrics.create_counter(String::from("expired_messages"), None),
+            pending_transactions_memory: metrics.create_gauge(
+                String::from("pending_transactions_memory"),
+                Some(String::from("bytes")),
+            ),
+            verified_votes_memory: metrics.create_gauge(
+                String::from("verified_votes_memory"),
+                Some(String::from("bytes")),
+            ),
+            leaf_store_memory: metrics.create_gauge(
+                String::from("leaf_store_memory"),
+                Some(String::from("bytes")),
+            ),
         }
     }
 }
@@ -429,11 +601,20 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             last_decided_view,
             last_proposals,
             last_actions: HotShotActionViews::from_view(last_actioned_view),
+            vote_intents: VoteIntentLog::default(),
             locked_view,
             saved_leaves,
             saved_payloads,
+            da_chunks: BTreeMap::new(),
             high_qc,
             next_epoch_high_qc,
+            checkpoint_certificate: None,
+            state_map_changed: Arc::new(Notify::new()),
+            liveness: LivenessTracker::default(),
+            proposal_cache: ProposalCache::default(),
+            certificate_cache: CertificateCache::default(),
+            drb_results: BTreeMap::new(),
+            memory_budget: MemoryBudget::default(),
             metrics,
             epoch_height,
         }
@@ -449,6 +630,69 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         self.cur_epoch
     }
 
+    /// Get the notifier which is woken whenever a view is added to the validated state map.
+    ///
+    /// To avoid missing an update, enable a `Notified` future before checking the state map and
+    /// await it afterwards.
+    pub fn state_map_changed(&self) -> Arc<Notify> {
+        Arc::clone(&self.state_map_changed)
+    }
+
+    /// Get the cache of certificate verification outcomes.
+    pub fn certificate_cache(&self) -> &CertificateCache<TYPES> {
+        &self.certificate_cache
+    }
+
+    /// Store the DRB result we computed for `epoch`.
+    pub fn insert_drb_result(&mut self, epoch: TYPES::Epoch, drb_result: DrbResult) {
+        self.drb_results.insert(epoch, drb_result);
+    }
+
+    /// The DRB result a proposal for block `block_number` must carry.
+    ///
+    /// The last block of an epoch carries the result for the next epoch, so that it is decided
+    /// before the next epoch starts; every other block carries [`INITIAL_DRB_RESULT`]. So does the
+    /// last block if we have not computed the result, in which case we cannot check the one a
+    /// leader proposes.
+    pub fn drb_result_for_block(&self, block_number: u64) -> DrbResult {
+        if self.epoch_height == 0 || !is_last_block_in_epoch(block_number, self.epoch_height) {
+            return INITIAL_DRB_RESULT;
+        }
+
+        let next_epoch =
+            TYPES::Epoch::new(epoch_from_block_number(block_number, self.epoch_height) + 1);
+        self.drb_results
+            .get(&next_epoch)
+            .copied()
+            .unwrap_or(INITIAL_DRB_RESULT)
+    }
+
+    /// Account the memory of the retained leaves and payloads to `memory_budget` from now on.
+    pub fn set_memory_budget(&mut self, memory_budget: MemoryBudget) {
+        self.memory_budget = memory_budget;
+        self.account_leaf_store();
+    }
+
+    /// Get the liveness scores of the validators.
+    pub fn liveness(&self) -> &LivenessTracker<TYPES::SignatureKey> {
+        &self.liveness
+    }
+
+    /// Record which members of `stake_table` signed a certificate.
+    pub fn record_certificate_participation(
+        &mut self,
+        stake_table: &[<TYPES::SignatureKey as SignatureKey>::StakeTableEntry],
+        signatures: &<TYPES::SignatureKey as SignatureKey>::QcType,
+    ) {
+        let (_, signers) = TYPES::SignatureKey::sig_proof(signatures);
+        self.liveness.record_certificate(stake_table, &signers);
+    }
+
+    /// Record whether a view sync relay completed its round.
+    pub fn record_relay_outcome(&mut self, relay: &TYPES::SignatureKey, succeeded: bool) {
+        self.liveness.record_relay(relay, succeeded);
+    }
+
     /// Get the last decided view.
     pub fn last_decided_view(&self) -> TYPES::View {
         self.last_decided_view
@@ -469,11 +713,27 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         self.next_epoch_high_qc.as_ref()
     }
 
+    /// Get the certificate of the latest checkpoint.
+    pub fn checkpoint_certificate(&self) -> Option<&CheckpointCertificate<TYPES>> {
+        self.checkpoint_certificate.as_ref()
+    }
+
     /// Get the validated state map.
     pub fn validated_state_map(&self) -> &BTreeMap<TYPES::View, View<TYPES>> {
         &self.validated_state_map
     }
 
+    /// Get a snapshot of the tree of leaves in memory, and of which branch is preferred.
+    #[must_use]
+    pub fn fork_tree(&self) -> ForkTree<TYPES> {
+        ForkTree::new(
+            &self.saved_leaves,
+            &self.high_qc,
+            self.locked_view,
+            self.last_decided_view,
+        )
+    }
+
     /// Get the saved leaves.
     pub fn saved_leaves(&self) -> &CommitmentMap<Leaf2<TYPES>> {
         &self.saved_leaves
@@ -484,6 +744,11 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         &self.saved_payloads
     }
 
+    /// Get our chunks of the payloads of the views in which we only got a chunk.
+    pub fn da_chunks(&self) -> &BTreeMap<TYPES::View, Proposal<TYPES, DaChunk<TYPES>>> {
+        &self.da_chunks
+    }
+
     /// Get the vid shares.
     pub fn vid_shares(&self) -> &VidShares<TYPES> {
         &self.vid_shares
@@ -543,7 +808,8 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         })
     }
 
-    /// Update the current epoch.
+    /// Update the current epoch, forgetting the certificate verification outcomes and DRB results
+    /// of past epochs.
     /// # Errors
     /// Can return an error when the new epoch_number is not higher than the existing epoch number.
     pub fn update_epoch(&mut self, epoch_number: TYPES::Epoch) -> Result<()> {
@@ -553,6 +819,8 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         );
         tracing::trace!("Updating epoch from {} to {}", self.cur_epoch, epoch_number);
         self.cur_epoch = epoch_number;
+        self.certificate_cache.invalidate_before(epoch_number);
+        self.drb_results = self.drb_results.split_off(&epoch_number);
         Ok(())
     }
 
@@ -583,6 +851,26 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         false
     }
 
+    /// Record that we are about to send the vote of `intent`, unless it conflicts with a vote we
+    /// intended before
+    pub fn record_vote_intent(&mut self, intent: &VoteIntent<TYPES>) -> IntentCheck {
+        self.vote_intents.insert(intent)
+    }
+
+    /// Restore the vote intents persisted before a restart.
+    ///
+    /// Should they conflict with each other, the first one of each view and kind is kept.
+    pub fn restore_vote_intents(&mut self, intents: impl IntoIterator<Item = VoteIntent<TYPES>>) {
+        for intent in intents {
+            self.vote_intents.insert(&intent);
+        }
+    }
+
+    /// Get the vote intents of recent views.
+    pub fn vote_intents(&self) -> &VoteIntentLog<TYPES> {
+        &self.vote_intents
+    }
+
     /// reset last actions to genesis so we can resend events in tests
     pub fn reset_actions(&mut self) {
         self.last_actions = HotShotActionViews::default();
@@ -596,6 +884,10 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         &mut self,
         proposal: Proposal<TYPES, QuorumProposal2<TYPES>>,
     ) -> Result<()> {
+        self.cache_proposal(
+            Leaf2::from_quorum_proposal(&proposal.data).commit(),
+            proposal.clone(),
+        );
         ensure!(
             proposal.data.view_number()
                 > self
@@ -712,12 +1004,27 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             }
         }
         self.validated_state_map.insert(view_number, new_view);
+        self.state_map_changed.notify_waiters();
         Ok(())
     }
 
     /// Update the saved leaves with a new leaf.
     fn update_saved_leaves(&mut self, leaf: Leaf2<TYPES>) {
         self.saved_leaves.insert(leaf.commit(), leaf);
+        self.account_leaf_store();
+    }
+
+    /// Report the approximate memory of the retained leaves and payloads. Leaves which are not
+    /// decided yet cannot be dropped, so going over the budget is only reported.
+    fn account_leaf_store(&self) {
+        let bytes = self.saved_leaves.len() * std::mem::size_of::<Leaf2<TYPES>>()
+            + self
+                .saved_payloads
+                .values()
+                .map(|payload| payload.len())
+                .sum::<usize>();
+        self.memory_budget
+            .set_usage(MemoryComponent::LeafStore, bytes);
     }
 
     /// Update the saved payloads with a new encoded transaction.
@@ -734,9 +1041,78 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             "Payload with the same view already exists."
         );
         self.saved_payloads.insert(view_number, encoded_transaction);
+        self.account_leaf_store();
         Ok(())
     }
 
+    /// Save our chunk of the payload of a view, which we serve to those recovering the payload.
+    ///
+    /// # Errors
+    /// Can return an error when there's an existing chunk corresponding to the same view number.
+    pub fn update_da_chunk(&mut self, chunk: Proposal<TYPES, DaChunk<TYPES>>) -> Result<()> {
+        let view_number = chunk.data.view_number();
+        ensure!(
+            !self.da_chunks.contains_key(&view_number),
+            "Chunk with the same view already exists."
+        );
+        self.da_chunks.insert(view_number, chunk);
+        Ok(())
+    }
+
+    /// Remember `proposal`, proposing the leaf with commitment `leaf_commit`, for after its view
+    /// is garbage collected.
+    pub fn cache_proposal(
+        &self,
+        leaf_commit: LeafCommitment<TYPES>,
+        proposal: Proposal<TYPES, QuorumProposal2<TYPES>>,
+    ) {
+        self.proposal_cache.insert_proposal(leaf_commit, proposal);
+    }
+
+    /// Remember the encoded transactions of the payload with commitment `payload_commitment`, for
+    /// after the view they were proposed in is garbage collected.
+    pub fn cache_payload(
+        &self,
+        payload_commitment: VidCommitment,
+        encoded_transactions: Arc<[u8]>,
+    ) {
+        self.proposal_cache
+            .insert_payload(payload_commitment, encoded_transactions);
+    }
+
+    /// The proposal of the leaf with commitment `leaf_commit`, if it was seen recently.
+    pub fn cached_proposal(
+        &self,
+        leaf_commit: &LeafCommitment<TYPES>,
+    ) -> Option<Proposal<TYPES, QuorumProposal2<TYPES>>> {
+        let proposal = self.proposal_cache.proposal(leaf_commit);
+        if proposal.is_some() {
+            self.metrics.proposal_cache_hits.add(1);
+        } else {
+            self.metrics.proposal_cache_misses.add(1);
+        }
+        proposal
+    }
+
+    /// The encoded transactions of the payload for `view`, with commitment `payload_commitment`,
+    /// from the saved payloads or, if the view was garbage collected, the proposal cache.
+    pub fn payload(
+        &self,
+        view: TYPES::View,
+        payload_commitment: &VidCommitment,
+    ) -> Option<Arc<[u8]>> {
+        if let Some(encoded_transactions) = self.saved_payloads.get(&view) {
+            return Some(Arc::clone(encoded_transactions));
+        }
+        let encoded_transactions = self.proposal_cache.payload(payload_commitment);
+        if encoded_transactions.is_some() {
+            self.metrics.payload_cache_hits.add(1);
+        } else {
+            self.metrics.payload_cache_misses.add(1);
+        }
+        encoded_transactions
+    }
+
     /// Update the high QC if given a newer one.
     /// # Errors
     /// Can return an error when the provided high_qc is not newer than the existing entry.
@@ -751,6 +1127,29 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         Ok(())
     }
 
+    /// Update the checkpoint certificate if given a newer one.
+    /// # Errors
+    /// Can return an error when the provided certificate is not for a later checkpoint than the
+    /// existing one.
+    pub fn update_checkpoint_certificate(
+        &mut self,
+        checkpoint_certificate: CheckpointCertificate<TYPES>,
+    ) -> Result<()> {
+        if let Some(current) = self.checkpoint_certificate() {
+            ensure!(
+                checkpoint_certificate.data.height > current.data.height,
+                debug!("A checkpoint certificate with an equal or higher height exists.")
+            );
+        }
+        tracing::debug!(
+            "Updating checkpoint certificate to height {}",
+            checkpoint_certificate.data.height
+        );
+        self.checkpoint_certificate = Some(checkpoint_certificate);
+
+        Ok(())
+    }
+
     /// Update the next epoch high QC if given a newer one.
     /// # Errors
     /// Can return an error when the provided high_qc is not newer than the existing entry.
@@ -870,17 +1269,52 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         }
         // perform gc
         self.saved_da_certs
-            .retain(|view_number, _| *view_number >= old_anchor_view);
+            .retain(|view_number, _| *view_number >= gc_view);
         self.validated_state_map
             .range(old_anchor_view..gc_view)
             .filter_map(|(_view_number, view)| view.leaf_commitment())
             .for_each(|leaf| {
                 self.saved_leaves.remove(&leaf);
             });
+        // Leaves on abandoned forks never make it into the state map, so drop them by view
+        self.saved_leaves
+            .retain(|_, leaf| leaf.view_number() >= gc_view);
         self.validated_state_map = self.validated_state_map.split_off(&gc_view);
         self.saved_payloads = self.saved_payloads.split_off(&gc_view);
+        self.da_chunks = self.da_chunks.split_off(&gc_view);
         self.vid_shares = self.vid_shares.split_off(&gc_view);
         self.last_proposals = self.last_proposals.split_off(&gc_view);
+        self.vote_intents.prune(gc_view);
+        self.update_retained_metrics();
+        self.account_leaf_store();
+        if self
+            .memory_budget
+            .is_over_budget(MemoryComponent::LeafStore)
+        {
+            tracing::warn!(
+                "The retained leaves and payloads take {} bytes after garbage collection, over the budget of {:?}",
+                self.memory_budget.usage(MemoryComponent::LeafStore),
+                self.memory_budget.limit(MemoryComponent::LeafStore)
+            );
+        }
+    }
+
+    /// Report the sizes of the per-view maps retained in memory.
+    fn update_retained_metrics(&self) {
+        self.metrics.retained_leaves.set(self.saved_leaves.len());
+        self.metrics
+            .retained_states
+            .set(self.validated_state_map.len());
+        self.metrics
+            .retained_payloads
+            .set(self.saved_payloads.len());
+        self.metrics.retained_vid_shares.set(self.vid_shares.len());
+        self.metrics
+            .retained_da_certs
+            .set(self.saved_da_certs.len());
+        self.metrics
+            .retained_proposals
+            .set(self.last_proposals.len());
     }
 
     /// Gets the last decided leaf.
@@ -941,14 +1375,24 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         membership: Arc<RwLock<TYPES::Membership>>,
         private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
     ) -> Option<()> {
-        let txns = Arc::clone(consensus.read().await.saved_payloads().get(&view)?);
-        let epoch = consensus
-            .read()
-            .await
+        let consensus_reader = consensus.read().await;
+        let view_inner = &consensus_reader
             .validated_state_map()
             .get(&view)?
-            .view_inner
-            .epoch()?;
+            .view_inner;
+        let epoch = view_inner.epoch()?;
+        let payload_commitment = match view_inner {
+            ViewInner::Da {
+                payload_commitment, ..
+            } => *payload_commitment,
+            ViewInner::Leaf { leaf, .. } => consensus_reader
+                .saved_leaves()
+                .get(leaf)?
+                .payload_commitment(),
+            ViewInner::Failed => return None,
+        };
+        let txns = consensus_reader.payload(view, &payload_commitment)?;
+        drop(consensus_reader);
         let vid =
             VidDisperse::calculate_vid_disperse(txns, &membership, view, epoch, None, None).await;
         let shares = VidDisperseShare2::from_vid_disperse(vid);
@@ -41,6 +41,76 @@ pub const EVENT_CHANNEL_SIZE: usize = 100_000;
 /// Default channel size for HotShot -> application communication
 pub const EXTERNAL_EVENT_CHANNEL_SIZE: usize = 100_000;
 
+/// The number of finalized leaves kept in memory for replay by finality streams
+pub const FINALITY_STREAM_CAPACITY: usize = 10_000;
+
+/// The most finalized leaves an archival node returns for a single range request
+pub const ARCHIVE_MAX_RANGE: u64 = 256;
+
+/// The number of range requests per second an archival node serves to each peer, on average
+pub const ARCHIVE_REQUESTS_PER_SECOND: u32 = 2;
+
+/// The number of range requests a peer may send an archival node at once after a quiet period
+pub const ARCHIVE_REQUEST_BURST: u32 = 10;
+
+/// The number of peers whose request rate an archival node tracks, forgetting the least recently
+/// seen one beyond that
+pub const ARCHIVE_RATE_LIMITED_PEERS: usize = 1024;
+
+/// The most inbound messages buffered for a single peer before its further messages are dropped
+pub const INBOUND_MESSAGES_PER_PEER: usize = 200;
+
+/// The most inbound messages buffered for all peers together
+pub const INBOUND_MESSAGES_TOTAL: usize = 5000;
+
+/// The number of the latest view changes kept in memory, with the reason for each
+pub const VIEW_CHANGE_LOG_CAPACITY: usize = 1000;
+
+/// How often a validator gossips its identity again, so that nodes which joined since learn it
+pub const VALIDATOR_METADATA_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The largest message, in bytes, a node encodes or decodes, matching the largest gossip message
+/// libp2p transmits
+pub const MAX_MESSAGE_SIZE: usize = 2_000_000_000;
+
+/// The number of votes whose signatures are checked at once as they arrive from the network
+pub const VOTE_VERIFICATION_CONCURRENCY: usize = 32;
+
+/// The number of views before and after the current one for which votes are checked ahead of
+/// their vote collectors and remembered
+pub const VERIFIED_VOTES_VIEWS: u64 = 10;
+
+/// The number of recently seen quorum proposals kept in the proposal cache
+pub const PROPOSAL_CACHE_CAPACITY: usize = 1000;
+
+/// The number of recently seen block payloads kept in the proposal cache
+pub const PAYLOAD_CACHE_CAPACITY: usize = 100;
+
+/// The number of certificate verification outcomes kept in the certificate cache
+pub const CERTIFICATE_CACHE_CAPACITY: usize = 1000;
+
+/// The number of writes a write-behind storage queues before writers wait for it to catch up
+pub const STORAGE_WRITE_QUEUE_CAPACITY: usize = 1000;
+
+/// The maximum exponent for the view sync round timeout backoff; the timeout for relay `r` is
+/// `view_sync_timeout * 2^min(r, VIEW_SYNC_MAX_TIMEOUT_BACKOFF)`
+pub const VIEW_SYNC_MAX_TIMEOUT_BACKOFF: u32 = 5;
+
+/// The weight of the newest observation in a validator's liveness score
+pub const LIVENESS_SCORE_WEIGHT: f64 = 0.1;
+
+/// The first view sync relay whose votes are broadcast to every node rather than sent to the
+/// relay, so that a round completes even if all earlier relays are unreachable
+pub const VIEW_SYNC_BROADCAST_AFTER_RELAYS: u64 = 4;
+
+/// The maximum number of broadcast relays a node collects view sync votes for at once, per round
+/// and phase
+pub const VIEW_SYNC_MAX_BROADCAST_RELAYS: usize = 2;
+
+/// How many relays past the one it is on itself a node collects broadcast view sync votes for, as
+/// other nodes' relay timers may run ahead of its own
+pub const VIEW_SYNC_BROADCAST_RELAY_SLACK: u64 = 2;
+
 /// The offset for how far in the future we will send out a `QuorumProposal` with an `UpgradeCertificate` we form. This is also how far in advance of sending a `QuorumProposal` we begin collecting votes on an `UpgradeProposal`.
 pub const UPGRADE_PROPOSE_OFFSET: u64 = 5;
 
@@ -57,6 +127,10 @@ pub const UPGRADE_BEGIN_OFFSET: u64 = UPGRADE_DECIDE_BY_OFFSET + 5;
 /// The offset for how far in the future the upgrade ends.
 pub const UPGRADE_FINISH_OFFSET: u64 = UPGRADE_BEGIN_OFFSET + 5;
 
+/// The number of views on either side of the first view of a new version during which messages
+/// serialized with either the old or the new version are accepted.
+pub const UPGRADE_TRANSITION_WINDOW: u64 = 5;
+
 /// For `STAKE_TABLE_CAPACITY=200`, the light client prover (a.k.a. `hotshot-state-prover`)
 /// would need to generate proof for a circuit of slightly below 2^20 gates.
 /// Thus we need to support this upperbounded degree in our Structured Reference String (SRS),
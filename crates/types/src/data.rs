@@ -10,7 +10,7 @@
 //! `HotShot`'s version of a block, and proposals, messages upon which to reach the consensus.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashSet},
     fmt::{Debug, Display},
     hash::Hash,
     marker::PhantomData,
@@ -50,7 +50,10 @@ use crate::{
         BlockPayload,
     },
     utils::{bincode_opts, epoch_from_block_number},
-    vid::{vid_scheme, VidCommitment, VidCommon, VidPrecomputeData, VidSchemeType, VidShare},
+    vid::{
+        vid_recovery_threshold, vid_scheme, VidCommitment, VidCommon, VidPrecomputeData,
+        VidSchemeType, VidShare,
+    },
     vote::{Certificate, HasViewNumber},
 };
 
@@ -548,6 +551,198 @@ impl<TYPES: NodeType> VidDisperseShare2<TYPES> {
     }
 }
 
+/// A DA committee member's chunk of a block: some of the erasure-coded shares of the payload,
+/// sent to the member instead of the whole payload.
+///
+/// The payload is dispersed to the quorum, under the payload commitment the DA committee votes
+/// on, and its shares are dealt out to the DA committee members in turn. A member checks each of
+/// its shares against the commitment before voting, so the shares the committee holds are bound
+/// to the commitment it certified, and enough of its members can recover the payload together.
+#[derive(derive_more::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[serde(bound = "TYPES: NodeType")]
+pub struct DaChunk<TYPES: NodeType> {
+    /// View this chunk applies to
+    pub view_number: TYPES::View,
+    /// Epoch this chunk applies to
+    pub epoch: TYPES::Epoch,
+    /// Commitment to the payload, which the DA committee votes on and the shares are checked
+    /// against
+    pub payload_commitment: VidCommitment,
+    /// Metadata of the block
+    pub metadata: <TYPES::BlockPayload as BlockPayload<TYPES>>::Metadata,
+    /// The shares of the recipient
+    #[debug(skip)]
+    pub shares: Vec<VidShare>,
+    /// Data common to the shares of all members
+    #[debug(skip)]
+    pub common: VidCommon,
+    /// The DA committee member this chunk is for
+    pub recipient_key: TYPES::SignatureKey,
+}
+
+impl<TYPES: NodeType> DaChunk<TYPES> {
+    /// The number of shares of a payload dispersed to `num_nodes` nodes which the member at
+    /// `member_index` of a DA committee of `da_committee_size` members is dealt
+    #[must_use]
+    pub fn share_count(member_index: usize, da_committee_size: usize, num_nodes: usize) -> usize {
+        num_nodes / da_committee_size + usize::from(member_index < num_nodes % da_committee_size)
+    }
+
+    /// Split `payload` into a chunk for each member of `da_committee`, dispersing it to a quorum
+    /// of `num_nodes` nodes.
+    ///
+    /// # Errors
+    /// If the committee is empty, or the payload cannot be dispersed
+    pub fn from_payload(
+        payload: &[u8],
+        metadata: &<TYPES::BlockPayload as BlockPayload<TYPES>>::Metadata,
+        view_number: TYPES::View,
+        epoch: TYPES::Epoch,
+        da_committee: &BTreeSet<TYPES::SignatureKey>,
+        num_nodes: usize,
+    ) -> Result<Vec<Self>> {
+        ensure!(!da_committee.is_empty(), "The DA committee is empty");
+
+        let disperse = vid_scheme(num_nodes)
+            .disperse(payload)
+            .wrap()
+            .context(warn!("Failed to disperse the payload"))?;
+        ensure!(
+            disperse.shares.len() == num_nodes,
+            warn!("Dispersal produced a share count different from the number of nodes")
+        );
+
+        // Deal the shares out to the members in turn
+        let mut member_shares = vec![Vec::new(); da_committee.len()];
+        for (i, share) in disperse.shares.into_iter().enumerate() {
+            member_shares[i % da_committee.len()].push(share);
+        }
+
+        Ok(da_committee
+            .iter()
+            .cloned()
+            .zip(member_shares)
+            .map(|(recipient_key, shares)| Self {
+                view_number,
+                epoch,
+                payload_commitment: disperse.commit,
+                metadata: metadata.clone(),
+                shares,
+                common: disperse.common.clone(),
+                recipient_key,
+            })
+            .collect())
+    }
+
+    /// The message the leader signs, which binds the view and the commitment
+    #[must_use]
+    pub fn signed_message(&self) -> Vec<u8> {
+        [
+            &self.view_number.u64().to_le_bytes()[..],
+            self.payload_commitment.as_ref(),
+        ]
+        .concat()
+    }
+
+    /// Consume `self` and return a `Proposal`
+    pub fn to_proposal(
+        self,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    ) -> Option<Proposal<TYPES, Self>> {
+        let Ok(signature) = TYPES::SignatureKey::sign(private_key, &self.signed_message()) else {
+            error!("DA: failed to sign chunk");
+            return None;
+        };
+        Some(Proposal {
+            signature,
+            _pd: PhantomData,
+            data: self,
+        })
+    }
+
+    /// Whether `leader` signed the chunk, and it holds as many distinct shares as its recipient
+    /// is dealt from `da_committee`, each consistent with the payload commitment for a quorum of
+    /// `num_nodes` nodes. Checking the shares is expensive, so this should run on a blocking
+    /// worker.
+    #[must_use]
+    pub fn is_valid(
+        proposal: &Proposal<TYPES, Self>,
+        leader: &TYPES::SignatureKey,
+        da_committee: &BTreeSet<TYPES::SignatureKey>,
+        num_nodes: usize,
+    ) -> bool {
+        let chunk = &proposal.data;
+        if num_nodes == 0 {
+            return false;
+        }
+        let Some(member_index) = da_committee
+            .iter()
+            .position(|member| *member == chunk.recipient_key)
+        else {
+            return false;
+        };
+        let distinct_shares = chunk.shares.iter().collect::<HashSet<_>>().len();
+        let vid = vid_scheme(num_nodes);
+
+        distinct_shares == chunk.shares.len()
+            && chunk.shares.len() == Self::share_count(member_index, da_committee.len(), num_nodes)
+            && leader.validate(&proposal.signature, &chunk.signed_message())
+            && chunk.shares.iter().all(|share| {
+                matches!(
+                    vid.verify_share(share, &chunk.common, &chunk.payload_commitment),
+                    Ok(Ok(()))
+                )
+            })
+    }
+
+    /// Whether `chunks` of different members hold enough shares to recover a payload dispersed
+    /// to `num_nodes` nodes
+    pub fn can_recover<'a>(chunks: impl IntoIterator<Item = &'a Self>, num_nodes: usize) -> bool
+    where
+        TYPES: 'a,
+    {
+        num_nodes > 0
+            && chunks
+                .into_iter()
+                .map(|chunk| chunk.shares.len())
+                .sum::<usize>()
+                >= vid_recovery_threshold(num_nodes)
+    }
+
+    /// Recover the payload from the chunks of different members, for a quorum of `num_nodes`
+    /// nodes, and check it against the payload commitment.
+    ///
+    /// # Errors
+    /// If the chunks are not of the same payload, there are too few of them, or the recovered
+    /// payload does not match its commitment
+    pub fn recover_payload(chunks: &[Self], num_nodes: usize) -> Result<Vec<u8>> {
+        let Some(first) = chunks.first() else {
+            bail!("No chunks to recover the payload from");
+        };
+        ensure!(
+            chunks
+                .iter()
+                .all(|chunk| chunk.payload_commitment == first.payload_commitment),
+            warn!("The chunks are not of the same payload")
+        );
+
+        let shares: Vec<_> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.shares.iter().cloned())
+            .collect();
+        let payload = vid_scheme(num_nodes)
+            .recover_payload(&shares, &first.common)
+            .wrap()
+            .context(warn!("Failed to recover the payload from its chunks"))?;
+        ensure!(
+            vid_commitment(&payload, num_nodes) == first.payload_commitment,
+            warn!("The recovered payload does not match the commitment voted on")
+        );
+
+        Ok(payload)
+    }
+}
+
 /// Proposal to append a block.
 #[derive(derive_more::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 #[serde(bound(deserialize = ""))]
@@ -599,9 +794,11 @@ pub struct QuorumProposal2<TYPES: NodeType> {
     #[serde(with = "serde_bytes")]
     pub drb_seed: DrbSeedInput,
 
-    /// The DRB result for the current epoch.
+    /// The DRB result for the next epoch, carried by the last block of an epoch and
+    /// [`INITIAL_DRB_RESULT`] in every other block.
     ///
-    /// The DRB computation with this result was started two epochs ago.
+    /// The DRB computation with this result was started in the previous epoch. It is part of the
+    /// leaf commitment unless it is [`INITIAL_DRB_RESULT`].
     #[serde(with = "serde_bytes")]
     pub drb_result: DrbResult,
 }
@@ -683,6 +880,12 @@ impl<TYPES: NodeType> HasViewNumber<TYPES> for VidDisperseShare2<TYPES> {
     }
 }
 
+impl<TYPES: NodeType> HasViewNumber<TYPES> for DaChunk<TYPES> {
+    fn view_number(&self) -> TYPES::View {
+        self.view_number
+    }
+}
+
 impl<TYPES: NodeType> HasViewNumber<TYPES> for QuorumProposal<TYPES> {
     fn view_number(&self) -> TYPES::View {
         self.view_number
@@ -703,6 +906,7 @@ impl<TYPES: NodeType> HasViewNumber<TYPES> for UpgradeProposal<TYPES> {
 
 impl_has_epoch!(
     DaProposal2<TYPES>,
+    DaChunk<TYPES>,
     VidDisperse<TYPES>,
     VidDisperseShare2<TYPES>
 );
@@ -801,9 +1005,11 @@ pub struct Leaf2<TYPES: NodeType> {
     #[serde(with = "serde_bytes")]
     pub drb_seed: DrbSeedInput,
 
-    /// The DRB result for the current epoch.
+    /// The DRB result for the next epoch, carried by the last block of an epoch and
+    /// [`INITIAL_DRB_RESULT`] in every other block.
     ///
-    /// The DRB computation with this result was started two epochs ago.
+    /// The DRB computation with this result was started in the previous epoch. It is part of the
+    /// leaf commitment unless it is [`INITIAL_DRB_RESULT`].
     #[serde(with = "serde_bytes")]
     pub drb_result: DrbResult,
 }
@@ -983,13 +1189,21 @@ impl<TYPES: NodeType> Leaf2<TYPES> {
 
 impl<TYPES: NodeType> Committable for Leaf2<TYPES> {
     fn commit(&self) -> committable::Commitment<Self> {
-        RawCommitmentBuilder::new("leaf commitment")
+        let builder = RawCommitmentBuilder::new("leaf commitment")
             .u64_field("view number", *self.view_number)
             .field("parent leaf commitment", self.parent_commitment)
             .field("block header", self.block_header.commit())
             .field("justify qc", self.justify_qc.commit())
-            .optional("upgrade certificate", &self.upgrade_certificate)
-            .finalize()
+            .optional("upgrade certificate", &self.upgrade_certificate);
+
+        // Leaves without a DRB result keep the commitment they had before it was added
+        if self.drb_result == INITIAL_DRB_RESULT {
+            builder.finalize()
+        } else {
+            builder
+                .fixed_size_field("next epoch drb result", &self.drb_result)
+                .finalize()
+        }
     }
 }
 
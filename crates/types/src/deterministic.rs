@@ -0,0 +1,51 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Seeded randomness for deterministic simulation runs.
+//!
+//! Randomness which only simulates the environment of a node, such as network faults, injected
+//! delays and test transactions, is drawn through [`with_rng`]. Once a thread is seeded with
+//! [`seed_thread_rng`], every draw on that thread comes from a single generator seeded with the
+//! given seed, so a run on a single-threaded runtime can be replayed exactly from its seed.
+//! Threads which were never seeded draw from [`rand::thread_rng`].
+
+use std::cell::RefCell;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+thread_local! {
+    /// The generator of the current thread, if it was seeded
+    static SEEDED_RNG: RefCell<Option<ChaCha20Rng>> = const { RefCell::new(None) };
+}
+
+/// Seed the generator used by [`with_rng`] on the current thread.
+pub fn seed_thread_rng(seed: u64) {
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = Some(ChaCha20Rng::seed_from_u64(seed)));
+}
+
+/// Remove the seed of the current thread, so that [`with_rng`] draws from [`rand::thread_rng`]
+/// again.
+pub fn clear_thread_rng() {
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = None);
+}
+
+/// Whether the generator of the current thread is seeded
+#[must_use]
+pub fn is_thread_rng_seeded() -> bool {
+    SEEDED_RNG.with(|rng| rng.borrow().is_some())
+}
+
+/// Run `f` with the generator of the current thread.
+///
+/// # Panics
+/// If called from within `f`.
+pub fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    SEEDED_RNG.with(|rng| match &mut *rng.borrow_mut() {
+        Some(rng) => f(rng),
+        None => f(&mut rand::thread_rng()),
+    })
+}
@@ -6,9 +6,13 @@
 
 use std::hash::{DefaultHasher, Hash, Hasher};
 
+use primitive_types::U256;
 use sha2::{Digest, Sha256};
 
-use crate::traits::{node_implementation::NodeType, signature_key::SignatureKey};
+use crate::traits::{
+    node_implementation::NodeType,
+    signature_key::{SignatureKey, StakeTableEntryType},
+};
 
 // TODO: Add the following consts once we bench the hash time.
 // <https://github.com/EspressoSystems/HotShot/issues/3880>
@@ -86,3 +90,60 @@ pub fn leader<TYPES: NodeType>(
     let entry = stake_table[index].clone();
     TYPES::SignatureKey::public_key(&entry)
 }
+
+/// Sample a committee of `size` distinct members from `stake_table`, weighting each member by
+/// its stake.
+///
+/// The sample is derived from the DRB result only, so every node computes the same committee.
+/// `domain` separates the samples drawn for different purposes in the same epoch. If the stake
+/// table has at most `size` members with stake, all of them are returned. Members are returned in
+/// stake table order.
+#[must_use]
+pub fn sample_committee<TYPES: NodeType>(
+    stake_table: &[<TYPES::SignatureKey as SignatureKey>::StakeTableEntry],
+    drb_result: DrbResult,
+    domain: &[u8],
+    size: usize,
+) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+    let eligible: Vec<usize> = (0..stake_table.len())
+        .filter(|&index| stake_table[index].stake() > U256::zero())
+        .collect();
+    if eligible.len() <= size {
+        return eligible
+            .into_iter()
+            .map(|index| stake_table[index].clone())
+            .collect();
+    }
+
+    let mut chosen = vec![false; stake_table.len()];
+    let mut remaining_stake = eligible.iter().fold(U256::zero(), |total, &index| {
+        total + stake_table[index].stake()
+    });
+    for draw in 0..size as u64 {
+        let digest = Sha256::new()
+            .chain_update(drb_result)
+            .chain_update(domain)
+            .chain_update(draw.to_le_bytes())
+            .finalize();
+        // Pick the member whose stake interval contains the target, among those not chosen yet
+        let mut target = U256::from_big_endian(&digest) % remaining_stake;
+        for &index in &eligible {
+            if chosen[index] {
+                continue;
+            }
+            let stake = stake_table[index].stake();
+            if target < stake {
+                chosen[index] = true;
+                remaining_stake -= stake;
+                break;
+            }
+            target -= stake;
+        }
+    }
+
+    stake_table
+        .iter()
+        .zip(chosen)
+        .filter_map(|(entry, chosen)| chosen.then(|| entry.clone()))
+        .collect()
+}
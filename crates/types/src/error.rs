@@ -8,12 +8,25 @@
 //!
 //! This module provides [`HotShotError`], which is an enum representing possible faults that can
 //! occur while interacting with this crate.
+//!
+//! Every error has a stable numeric code, which does not change between releases, and tells
+//! whether it is worth retrying the operation which failed. Codes are grouped by the layer the
+//! error comes from:
+//!
+//! * `1xxx`: consensus;
+//! * `2xxx`: networking, see [`NetworkError::code`];
+//! * `3xxx`: storage;
+//! * `4xxx`: configuration;
+//! * `5xxx`: transaction submission.
 
 use committable::Commitment;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{data::Leaf2, traits::node_implementation::NodeType};
+use crate::{
+    data::Leaf2,
+    traits::{network::NetworkError, node_implementation::NodeType},
+};
 
 /// Error type for `HotShot`
 #[derive(Debug, Error)]
@@ -35,6 +48,22 @@ pub enum HotShotError<TYPES: NodeType> {
     #[error("Failed to deserialize: {0}")]
     FailedToDeserialize(String),
 
+    /// The configuration of the node is inconsistent
+    #[error("Invalid config: {0}")]
+    InvalidConfig(#[from] HotShotConfigError),
+
+    /// The network failed
+    #[error("Network error: {0}")]
+    Network(#[from] NetworkError),
+
+    /// The storage failed
+    #[error("Storage error: {0}")]
+    Storage(#[source] anyhow::Error),
+
+    /// A submitted transaction was not accepted
+    #[error("Transaction rejected: {0}")]
+    TransactionRejected(#[from] TransactionRejection),
+
     /// The view timed out
     #[error("View {view_number} timed out: {state:?}")]
     ViewTimedOut {
@@ -45,6 +74,139 @@ pub enum HotShotError<TYPES: NodeType> {
     },
 }
 
+impl<TYPES: NodeType> HotShotError<TYPES> {
+    /// The stable numeric code of the error
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::InvalidState(_) => 1001,
+            Self::MissingLeaf(_) => 1002,
+            Self::FailedToSerialize(_) => 1003,
+            Self::FailedToDeserialize(_) => 1004,
+            Self::ViewTimedOut { .. } => 1005,
+            Self::Network(e) => e.code(),
+            Self::Storage(_) => 3001,
+            Self::InvalidConfig(e) => e.code(),
+            Self::TransactionRejected(e) => e.code(),
+        }
+    }
+
+    /// Whether the operation which failed may succeed if retried
+    ///
+    /// Missing leaves may still arrive, views time out when the network is partitioned, and
+    /// storage errors are assumed to be transient, e.g. I/O errors. Transactions rejected because
+    /// too many are pending may be accepted later. Invalid states, malformed data and invalid
+    /// configs fail again on retry.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::MissingLeaf(_) | Self::ViewTimedOut { .. } | Self::Storage(_) => true,
+            Self::Network(e) => e.is_retryable(),
+            Self::TransactionRejected(e) => matches!(e, TransactionRejection::Full(_)),
+            Self::InvalidState(_)
+            | Self::FailedToSerialize(_)
+            | Self::FailedToDeserialize(_)
+            | Self::InvalidConfig(_) => false,
+        }
+    }
+}
+
+/// An inconsistency in a [`HotShotConfig`](crate::HotShotConfig)
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum HotShotConfigError {
+    /// The stake table is empty
+    #[error("No nodes with stake are known")]
+    EmptyStakeTable,
+    /// The stake table does not have as many nodes as the committee
+    #[error("{known} nodes with stake are known, but the committee has {expected}")]
+    StakeTableSizeMismatch {
+        /// the number of known nodes with stake
+        known: usize,
+        /// the number of nodes with stake in the committee
+        expected: usize,
+    },
+    /// The DA committee is larger than the committee
+    #[error("The DA committee has {da} nodes, more than the {committee} nodes with stake")]
+    DaCommitteeTooLarge {
+        /// the size of the DA committee
+        da: usize,
+        /// the number of nodes with stake
+        committee: usize,
+    },
+    /// The start threshold is not a fraction between 0 and 1
+    #[error("The start threshold {0}/{1} is not between 0 and 1")]
+    InvalidStartThreshold(u64, u64),
+    /// A timeout is zero
+    #[error("The {0} is zero")]
+    ZeroTimeout(&'static str),
+    /// There are more bootstrap nodes than nodes
+    #[error("{bootstrap} bootstrap nodes are configured, but there are only {committee} nodes")]
+    TooManyBootstrapNodes {
+        /// the number of bootstrap nodes
+        bootstrap: usize,
+        /// the number of nodes with stake
+        committee: usize,
+    },
+    /// The log filter directives are invalid
+    #[error("Invalid log filter `{0}`")]
+    InvalidLogFilter(String),
+    /// A watcher is configured with a key in the stake table
+    #[error("A watcher cannot have stake, but its key is in the stake table")]
+    WatcherWithStake,
+}
+
+impl HotShotConfigError {
+    /// The stable numeric code of the error
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::EmptyStakeTable => 4001,
+            Self::StakeTableSizeMismatch { .. } => 4002,
+            Self::DaCommitteeTooLarge { .. } => 4003,
+            Self::InvalidStartThreshold(..) => 4004,
+            Self::ZeroTimeout(_) => 4005,
+            Self::TooManyBootstrapNodes { .. } => 4006,
+            Self::InvalidLogFilter(_) => 4007,
+            Self::WatcherWithStake => 4008,
+        }
+    }
+}
+
+/// Why a submitted transaction was not accepted
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum TransactionRejection {
+    /// The message carrying the transaction is larger than the limit
+    #[error("The transaction takes {size} bytes, more than the limit of {max}")]
+    TooLarge {
+        /// the size of the message carrying the transaction
+        size: usize,
+        /// the largest size accepted
+        max: usize,
+    },
+    /// The transaction was already submitted and is still pending
+    #[error("The transaction is already pending")]
+    Duplicate,
+    /// As many transactions as allowed are pending already
+    #[error("{0} transactions are pending already")]
+    Full(usize),
+    /// The pending transactions use up their memory budget
+    #[error("The pending transactions use up their memory budget")]
+    OverMemoryBudget,
+}
+
+impl TransactionRejection {
+    /// The stable numeric code of the error
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::TooLarge { .. } => 5001,
+            Self::Duplicate => 5002,
+            Self::Full(_) => 5003,
+            Self::OverMemoryBudget => 5004,
+        }
+    }
+}
+
 /// Contains information about what the state of the hotshot-consensus was when a round timed out
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -13,9 +13,13 @@ use serde::{Deserialize, Serialize};
 use crate::{
     data::{DaProposal2, Leaf2, QuorumProposal2, UpgradeProposal, VidDisperseShare2},
     error::HotShotError,
+    evidence::SignedEvidence,
     message::Proposal,
-    simple_certificate::QuorumCertificate2,
+    reconfig::ConfigUpdate,
+    simple_certificate::{CheckpointCertificate, QuorumCertificate2},
     traits::{node_implementation::NodeType, ValidatedState},
+    vid::VidCommitment,
+    watchdog::Alert,
 };
 
 /// A status event emitted by a `HotShot` instance
@@ -65,6 +69,21 @@ impl<TYPES: NodeType> LeafInfo<TYPES> {
 /// The chain of decided leaves with its corresponding state and VID info.
 pub type LeafChain<TYPES> = Vec<LeafInfo<TYPES>>;
 
+/// Consecutive decides merged into one contiguous segment of the chain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = "TYPES: NodeType"))]
+pub struct DecideBatch<TYPES: NodeType> {
+    /// The decided leaves in increasing view order, each the parent of the leaf after it
+    ///
+    /// Unlike the chain of a [`EventType::Decide`], the oldest leaf comes first, so the leaves
+    /// can be applied in order.
+    pub leaf_chain: LeafChain<TYPES>,
+    /// The QC signing the newest leaf in `leaf_chain`
+    pub qc: Arc<QuorumCertificate2<TYPES>>,
+    /// The number of decide events merged into this batch
+    pub decides: usize,
+}
+
 /// Utilities for converting between HotShotError and a string.
 pub mod error_adaptor {
     use serde::{de::Deserializer, ser::Serializer};
@@ -164,6 +183,22 @@ pub enum EventType<TYPES: NodeType> {
         /// Public key of the leader submitting the proposal
         sender: TYPES::SignatureKey,
     },
+    /// A quorum proposal was rejected by the application's proposal validator, so this node did
+    /// not vote for it
+    QuorumProposalRejected {
+        /// Contents of the proposal
+        proposal: Proposal<TYPES, QuorumProposal2<TYPES>>,
+        /// The reason given by the validator
+        reason: String,
+    },
+    /// The payload proposed to the DA committee was rejected by the application's proposal
+    /// validator, so this node did not vote for it
+    DaPayloadRejected {
+        /// Commitment to the rejected payload
+        payload_commitment: VidCommitment,
+        /// The reason given by the validator
+        reason: String,
+    },
     /// Upgrade proposal was received from the network
     /// or submitted to the network by us
     UpgradeProposal {
@@ -180,6 +215,30 @@ pub enum EventType<TYPES: NodeType> {
         /// Serialized data of the message
         data: Vec<u8>,
     },
+
+    /// We observed a protocol violation by another node
+    ByzantineEvidence {
+        /// The signed evidence of the violation
+        evidence: Arc<SignedEvidence<TYPES>>,
+    },
+
+    /// A checkpoint of the application state was certified by a quorum
+    CheckpointCertified {
+        /// The certificate over the checkpointed state
+        certificate: Arc<CheckpointCertificate<TYPES>>,
+    },
+
+    /// The watchdog of this node observed consensus stalling
+    Alert {
+        /// What the watchdog observed
+        alert: Alert,
+    },
+
+    /// Parameters of this node were changed at runtime, from the view of the event on
+    ConfigUpdated {
+        /// The parameters which changed, and their new values
+        update: ConfigUpdate,
+    },
 }
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 /// A list of actions that we track for nodes
@@ -0,0 +1,143 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Evidence of Byzantine behaviour observed by a replica.
+//!
+//! A replica that observes a protocol violation (an equivocating leader, a leader proposing
+//! with an invalid certificate, or a replica voting twice in the same view) packages the
+//! offending messages into an [`Evidence`] object. The evidence is then signed by the
+//! reporting replica, persisted, and surfaced to the application so that it can be used for
+//! slashing or alerting.
+
+use std::sync::Arc;
+
+use async_lock::RwLock;
+use committable::{Commitment, Committable, RawCommitmentBuilder};
+use serde::{Deserialize, Serialize};
+use utils::anytrace::*;
+
+use crate::{
+    data::{Leaf2, QuorumProposal2},
+    message::Proposal,
+    simple_vote::QuorumVote2,
+    traits::{node_implementation::NodeType, signature_key::SignatureKey},
+};
+
+/// A protocol violation, together with the signed messages that prove it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub enum Violation<TYPES: NodeType> {
+    /// The leader signed two different proposals for the same view.
+    EquivocatingProposal {
+        /// The first proposal we saw for the view
+        first: Proposal<TYPES, QuorumProposal2<TYPES>>,
+        /// A conflicting proposal for the same view
+        second: Proposal<TYPES, QuorumProposal2<TYPES>>,
+    },
+    /// The leader signed a proposal whose justify QC does not verify.
+    InvalidQuorumCertificate {
+        /// The proposal carrying the invalid certificate
+        proposal: Proposal<TYPES, QuorumProposal2<TYPES>>,
+    },
+    /// A replica signed two quorum votes for different leaves in the same view.
+    DoubleVote {
+        /// The first vote we saw from the replica
+        first: QuorumVote2<TYPES>,
+        /// A conflicting vote from the same replica
+        second: QuorumVote2<TYPES>,
+    },
+}
+
+impl<TYPES: NodeType> Violation<TYPES> {
+    /// A short, human readable name for the kind of violation.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::EquivocatingProposal { .. } => "equivocating proposal",
+            Self::InvalidQuorumCertificate { .. } => "invalid quorum certificate",
+            Self::DoubleVote { .. } => "double vote",
+        }
+    }
+}
+
+/// Evidence that a particular node violated the protocol in a particular view.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct Evidence<TYPES: NodeType> {
+    /// The node which violated the protocol
+    pub offender: TYPES::SignatureKey,
+    /// The view in which the violation occurred
+    pub view_number: TYPES::View,
+    /// The violation, including the offending messages
+    pub violation: Violation<TYPES>,
+}
+
+impl<TYPES: NodeType> Committable for Evidence<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        let builder = RawCommitmentBuilder::new("Evidence")
+            .var_size_bytes(&self.offender.to_bytes())
+            .u64_field("view number", *self.view_number)
+            .constant_str(self.violation.kind());
+
+        match &self.violation {
+            Violation::EquivocatingProposal { first, second } => builder
+                .field("first", Leaf2::from_quorum_proposal(&first.data).commit())
+                .field("second", Leaf2::from_quorum_proposal(&second.data).commit()),
+            Violation::InvalidQuorumCertificate { proposal } => builder.field(
+                "proposal",
+                Leaf2::from_quorum_proposal(&proposal.data).commit(),
+            ),
+            Violation::DoubleVote { first, second } => builder
+                .field("first", first.data.leaf_commit)
+                .field("second", second.data.leaf_commit),
+        }
+        .finalize()
+    }
+}
+
+/// Evidence, signed by the replica which observed it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct SignedEvidence<TYPES: NodeType> {
+    /// The evidence being reported
+    pub evidence: Evidence<TYPES>,
+    /// The replica reporting the evidence
+    pub reporter: TYPES::SignatureKey,
+    /// The reporter's signature over the commitment of the evidence
+    pub signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+}
+
+impl<TYPES: NodeType> SignedEvidence<TYPES> {
+    /// Sign `evidence` with the reporter's private key.
+    ///
+    /// # Errors
+    /// Returns an error if signing fails.
+    pub fn sign(
+        evidence: Evidence<TYPES>,
+        reporter: TYPES::SignatureKey,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    ) -> Result<Self> {
+        let signature = TYPES::SignatureKey::sign(private_key, evidence.commit().as_ref())
+            .wrap()
+            .context(error!("Failed to sign evidence"))?;
+
+        Ok(Self {
+            evidence,
+            reporter,
+            signature,
+        })
+    }
+
+    /// Check that the evidence was signed by the reporter.
+    #[must_use]
+    pub fn is_signed_by_reporter(&self) -> bool {
+        self.reporter
+            .validate(&self.signature, self.evidence.commit().as_ref())
+    }
+}
+
+/// Evidence collected by a node, shared between the evidence task and the node's handle.
+pub type EvidenceLog<TYPES> = Arc<RwLock<Vec<SignedEvidence<TYPES>>>>;
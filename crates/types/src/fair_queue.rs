@@ -0,0 +1,115 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A queue of inbound messages which is fair between the peers they came from.
+//!
+//! Each peer gets its own bounded buffer, and the buffers are drained round-robin, one message
+//! at a time, so a peer sending far more than the others only delays and drops its own messages.
+//! When all buffers together are full, room is made by dropping the newest message of the peer
+//! with the most buffered, so a quiet peer can always get a message in.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// Buffers items per peer and hands them out round-robin between the peers
+#[derive(Debug)]
+pub struct FairQueue<K, T> {
+    /// the most items buffered for a single peer
+    max_per_peer: usize,
+    /// the most items buffered for all peers together
+    max_total: usize,
+    /// the items buffered for each peer with any, oldest first
+    buffers: HashMap<K, VecDeque<T>>,
+    /// the peers with buffered items, in the order they are next served
+    order: VecDeque<K>,
+    /// the number of items buffered for all peers together
+    len: usize,
+}
+
+impl<K: Clone + Eq + Hash, T> FairQueue<K, T> {
+    /// A queue buffering at most `max_per_peer` items for each peer and `max_total` in all
+    #[must_use]
+    pub fn new(max_per_peer: usize, max_total: usize) -> Self {
+        Self {
+            max_per_peer: max_per_peer.max(1),
+            max_total: max_total.max(1),
+            buffers: HashMap::new(),
+            order: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    /// Buffer `item` from `peer`. Returns whether it was buffered, which it is not if the buffer
+    /// of `peer` is full, or if the queue is full and no peer has more buffered than `peer`.
+    pub fn push(&mut self, peer: K, item: T) -> bool {
+        let buffered = self.buffers.get(&peer).map_or(0, VecDeque::len);
+        if buffered >= self.max_per_peer {
+            return false;
+        }
+        if self.len >= self.max_total && !self.drop_newest_of_longest_above(buffered) {
+            return false;
+        }
+
+        if buffered == 0 {
+            self.order.push_back(peer.clone());
+        }
+        self.buffers.entry(peer).or_default().push_back(item);
+        self.len += 1;
+        true
+    }
+
+    /// Take the oldest item of the peer whose turn it is, along with that peer
+    pub fn pop(&mut self) -> Option<(K, T)> {
+        let peer = self.order.pop_front()?;
+        let buffer = self.buffers.get_mut(&peer)?;
+        let item = buffer.pop_front()?;
+        self.len -= 1;
+        if buffer.is_empty() {
+            self.buffers.remove(&peer);
+        } else {
+            self.order.push_back(peer.clone());
+        }
+        Some((peer, item))
+    }
+
+    /// The number of items buffered for all peers together
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items are buffered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drop the newest item of the peer with the most buffered, if that is more than `than`.
+    /// Returns whether an item was dropped.
+    fn drop_newest_of_longest_above(&mut self, than: usize) -> bool {
+        let Some(peer) = self
+            .buffers
+            .iter()
+            .filter(|(_, buffer)| buffer.len() > than)
+            .max_by_key(|(_, buffer)| buffer.len())
+            .map(|(peer, _)| peer.clone())
+        else {
+            return false;
+        };
+
+        if let Some(buffer) = self.buffers.get_mut(&peer) {
+            buffer.pop_back();
+            self.len -= 1;
+            if buffer.is_empty() {
+                self.buffers.remove(&peer);
+                self.order.retain(|queued| *queued != peer);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Compact, independently verifiable proofs that a leaf was certified by a quorum.
+//!
+//! A [`FinalityProof`] carries only what a light client needs to check a quorum certificate
+//! against a stake table: the commitment of the certified leaf, the view and epoch of the
+//! certificate, and the aggregated signature. It does not require the full leaf, the vote
+//! commitment, or any other consensus state.
+
+use committable::{Commitment, Committable};
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_certificate::QuorumCertificate2,
+    simple_vote::{QuorumData2, VersionedVoteData},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+};
+
+/// A finalized leaf, the quorum certificate which certifies it, and a compact proof of that
+/// certificate.
+pub type FinalizedLeaf<TYPES> = (
+    Leaf2<TYPES>,
+    QuorumCertificate2<TYPES>,
+    FinalityProof<TYPES>,
+);
+
+/// A compact proof that a quorum signed a leaf.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct FinalityProof<TYPES: NodeType> {
+    /// Commitment to the certified leaf
+    pub leaf_commit: Commitment<Leaf2<TYPES>>,
+    /// The epoch whose stake table signed the leaf
+    pub epoch: TYPES::Epoch,
+    /// The view of the certificate
+    pub view_number: TYPES::View,
+    /// The aggregated signature of the quorum
+    pub signatures: Option<<TYPES::SignatureKey as SignatureKey>::QcType>,
+}
+
+impl<TYPES: NodeType> FinalityProof<TYPES> {
+    /// Extract the compact proof from a quorum certificate.
+    #[must_use]
+    pub fn from_qc(qc: &QuorumCertificate2<TYPES>) -> Self {
+        Self {
+            leaf_commit: qc.data.leaf_commit,
+            epoch: qc.data.epoch,
+            view_number: qc.view_number,
+            signatures: qc.signatures.clone(),
+        }
+    }
+
+    /// Check that `leaf` is the leaf this proof is for, and that the proof is signed by at least
+    /// `threshold` of the stake in `stake_table`.
+    ///
+    /// Only the genesis leaf, whose commitment is `genesis_leaf`, is final without signatures.
+    pub async fn verify<V: Versions>(
+        &self,
+        leaf: &Leaf2<TYPES>,
+        genesis_leaf: Commitment<Leaf2<TYPES>>,
+        stake_table: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+        threshold: u64,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        if leaf.commit() != self.leaf_commit {
+            return false;
+        }
+        if self.view_number == TYPES::View::genesis() {
+            return self.leaf_commit == genesis_leaf;
+        }
+        let Some(signatures) = self.signatures.as_ref() else {
+            return false;
+        };
+
+        let Ok(data) = VersionedVoteData::new(
+            QuorumData2 {
+                leaf_commit: self.leaf_commit,
+                epoch: self.epoch,
+            },
+            self.view_number,
+            upgrade_lock,
+        )
+        .await
+        else {
+            return false;
+        };
+
+        let public_parameter = <TYPES::SignatureKey as SignatureKey>::public_parameter(
+            stake_table,
+            U256::from(threshold),
+        );
+
+        <TYPES::SignatureKey as SignatureKey>::check(
+            &public_parameter,
+            data.commit().as_ref(),
+            signatures,
+        )
+    }
+
+    /// Check the proof against the quorum stake table of `membership` for the proof's epoch.
+    pub async fn verify_with_membership<V: Versions>(
+        &self,
+        leaf: &Leaf2<TYPES>,
+        genesis_leaf: Commitment<Leaf2<TYPES>>,
+        membership: &TYPES::Membership,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> bool {
+        self.verify(
+            leaf,
+            genesis_leaf,
+            membership.stake_table(self.epoch),
+            u64::from(membership.success_threshold(self.epoch)),
+            upgrade_lock,
+        )
+        .await
+    }
+}
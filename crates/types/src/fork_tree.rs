@@ -0,0 +1,160 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A snapshot of the in-memory tree of leaves, for inspecting fork choice.
+//!
+//! A [`ForkTree`] is built from the leaves consensus currently holds in memory. Each node records
+//! the quorum certificate it carries, whether any known certificate certifies it, and whether it
+//! is on the branch consensus currently prefers, so operators and debugging tools can see why a
+//! branch was or wasn't extended.
+
+use std::collections::{HashMap, HashSet};
+
+use committable::{Commitment, Committable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    consensus::CommitmentMap, data::Leaf2, simple_certificate::QuorumCertificate2,
+    traits::node_implementation::NodeType,
+};
+
+/// A leaf in the [`ForkTree`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct ForkTreeNode<TYPES: NodeType> {
+    /// Commitment to the leaf
+    pub leaf_commit: Commitment<Leaf2<TYPES>>,
+    /// The view the leaf was proposed in
+    pub view_number: TYPES::View,
+    /// The block height of the leaf
+    pub height: u64,
+    /// Commitment to the parent leaf
+    pub parent_commit: Commitment<Leaf2<TYPES>>,
+    /// The QC carried by the leaf, which certifies its parent
+    pub justify_qc: QuorumCertificate2<TYPES>,
+    /// The view of the QC certifying this leaf, if we know of one
+    pub certified_in_view: Option<TYPES::View>,
+    /// Commitments to the known children of the leaf, in view order
+    pub children: Vec<Commitment<Leaf2<TYPES>>>,
+    /// Whether the leaf is on the branch ending at the high QC's leaf
+    pub preferred: bool,
+    /// Whether the leaf has been decided
+    pub decided: bool,
+}
+
+/// The tree of leaves known to consensus.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct ForkTree<TYPES: NodeType> {
+    /// The leaves, in view order
+    pub nodes: Vec<ForkTreeNode<TYPES>>,
+    /// The leaves whose parents are not in memory, in view order
+    pub roots: Vec<Commitment<Leaf2<TYPES>>>,
+    /// The leaf certified by the high QC, the tip of the preferred branch
+    pub preferred_tip: Commitment<Leaf2<TYPES>>,
+    /// The locked view; branches which do not extend the leaf of this view cannot be voted for
+    pub locked_view: TYPES::View,
+    /// The last decided view
+    pub last_decided_view: TYPES::View,
+}
+
+impl<TYPES: NodeType> ForkTree<TYPES> {
+    /// Build the tree from the saved leaves of consensus.
+    #[must_use]
+    pub fn new(
+        saved_leaves: &CommitmentMap<Leaf2<TYPES>>,
+        high_qc: &QuorumCertificate2<TYPES>,
+        locked_view: TYPES::View,
+        last_decided_view: TYPES::View,
+    ) -> Self {
+        let preferred_tip = high_qc.data.leaf_commit;
+
+        // Every leaf certifies its parent through its justify QC, and the high QC certifies the
+        // tip. Keep the earliest certificate seen for each leaf.
+        let mut certified_in_view: HashMap<Commitment<Leaf2<TYPES>>, TYPES::View> = HashMap::new();
+        for qc in saved_leaves
+            .values()
+            .map(Leaf2::justify_qc)
+            .chain(std::iter::once(high_qc.clone()))
+        {
+            certified_in_view
+                .entry(qc.data.leaf_commit)
+                .and_modify(|view| *view = std::cmp::min(*view, qc.view_number))
+                .or_insert(qc.view_number);
+        }
+
+        // The preferred branch is the chain of ancestors of the high QC's leaf
+        let mut preferred = HashSet::new();
+        let mut next = Some(preferred_tip);
+        while let Some(commit) = next {
+            let Some(leaf) = saved_leaves.get(&commit) else {
+                break;
+            };
+            preferred.insert(commit);
+            next = Some(leaf.parent_commitment()).filter(|parent| !preferred.contains(parent));
+        }
+
+        let mut leaves: Vec<&Leaf2<TYPES>> = saved_leaves.values().collect();
+        leaves.sort_by_key(|leaf| (leaf.view_number(), leaf.height()));
+
+        let mut children: HashMap<Commitment<Leaf2<TYPES>>, Vec<Commitment<Leaf2<TYPES>>>> =
+            HashMap::new();
+        let mut roots = Vec::new();
+        for leaf in &leaves {
+            let parent = leaf.parent_commitment();
+            if saved_leaves.contains_key(&parent) {
+                children.entry(parent).or_default().push(leaf.commit());
+            } else {
+                roots.push(leaf.commit());
+            }
+        }
+
+        let nodes = leaves
+            .into_iter()
+            .map(|leaf| {
+                let leaf_commit = leaf.commit();
+                ForkTreeNode {
+                    leaf_commit,
+                    view_number: leaf.view_number(),
+                    height: leaf.height(),
+                    parent_commit: leaf.parent_commitment(),
+                    justify_qc: leaf.justify_qc(),
+                    certified_in_view: certified_in_view.get(&leaf_commit).copied(),
+                    children: children.remove(&leaf_commit).unwrap_or_default(),
+                    preferred: preferred.contains(&leaf_commit),
+                    decided: leaf.view_number() <= last_decided_view
+                        && preferred.contains(&leaf_commit),
+                }
+            })
+            .collect();
+
+        Self {
+            nodes,
+            roots,
+            preferred_tip,
+            locked_view,
+            last_decided_view,
+        }
+    }
+
+    /// Get the node for a leaf, if the leaf is in the tree.
+    #[must_use]
+    pub fn node(&self, leaf_commit: &Commitment<Leaf2<TYPES>>) -> Option<&ForkTreeNode<TYPES>> {
+        self.nodes
+            .iter()
+            .find(|node| node.leaf_commit == *leaf_commit)
+    }
+
+    /// The leaves on the preferred branch, oldest first.
+    pub fn preferred_branch(&self) -> impl Iterator<Item = &ForkTreeNode<TYPES>> {
+        self.nodes.iter().filter(|node| node.preferred)
+    }
+
+    /// The tips of all branches, i.e. the leaves without known children, in view order.
+    pub fn tips(&self) -> impl Iterator<Item = &ForkTreeNode<TYPES>> {
+        self.nodes.iter().filter(|node| node.children.is_empty())
+    }
+}
@@ -10,8 +10,10 @@ use url::Url;
 use vec1::Vec1;
 
 use crate::{
-    constants::REQUEST_DATA_DELAY, traits::signature_key::SignatureKey,
-    upgrade_config::UpgradeConfig, HotShotConfig, PeerConfig, ValidatorConfig,
+    constants::REQUEST_DATA_DELAY, error::HotShotConfigError, journal::JournalConfig,
+    memory_budget::MemoryBudgetConfig, traits::signature_key::SignatureKey,
+    upgrade_config::UpgradeConfig, watchdog::WatchdogConfig, HotShotConfig, PeerConfig,
+    ValidatorConfig,
 };
 
 /// Default builder URL, used as placeholder
@@ -55,6 +57,52 @@ pub struct HotShotConfigFile<KEY: SignatureKey> {
     pub upgrade: UpgradeConfig,
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+    /// Number of blocks between checkpoints, zero means there are no checkpoints
+    #[serde(default)]
+    pub checkpoint_interval: u64,
+    /// Number of the latest decided leaves this node attests to messages for, zero means it
+    /// attests to none
+    #[serde(default)]
+    pub attestation_window: u64,
+    /// Where to keep a journal of the consensus decisions of this node, if anywhere
+    #[serde(default)]
+    pub journal: Option<JournalConfig>,
+    /// When to raise alerts about stalled consensus, if at all
+    #[serde(default)]
+    pub watchdog: Option<WatchdogConfig>,
+    /// The consensus instance this node takes part in, which lets several instances share one
+    /// network; messages of other instances are ignored
+    #[serde(default)]
+    pub namespace: u64,
+    /// Whether the DA leader sends each DA committee member an erasure-coded chunk of the payload
+    /// rather than the whole payload
+    #[serde(default)]
+    pub da_chunked_dispersal: bool,
+    /// Whether DA committee members check the payload against the application's rules before
+    /// voting for it, first recovering it from the other members' chunks under chunked dispersal
+    #[serde(default)]
+    pub da_payload_validation: bool,
+    /// The number of threads dedicated to consensus-critical tasks, or zero to run them on the
+    /// shared runtime with every other task
+    #[serde(default)]
+    pub consensus_task_threads: usize,
+    /// The most memory, in bytes, the network receive queue, pending transactions, pre-verified
+    /// votes and leaf store may each use; unlimited by default
+    #[serde(default)]
+    pub memory_budget: MemoryBudgetConfig,
+    /// Whether this node only follows consensus: it verifies and stores what the committee
+    /// decides and serves it, but never votes or proposes. Its key, which then only identifies
+    /// it on the network, must not be in the stake table.
+    #[serde(default)]
+    pub watcher: bool,
+    /// Whether this node keeps every finalized leaf instead of only the latest ones, indexed by
+    /// block height and by transaction, and serves ranges of them to nodes catching up
+    #[serde(default)]
+    pub archival: bool,
+    /// The number of views past the view it is sent in after which the receivers of a message
+    /// we send drop it as stale, or `None` for our messages never to expire
+    #[serde(default)]
+    pub message_ttl: Option<u64>,
 }
 
 impl<KEY: SignatureKey> From<HotShotConfigFile<KEY>> for HotShotConfig<KEY> {
@@ -83,11 +131,34 @@ impl<KEY: SignatureKey> From<HotShotConfigFile<KEY>> for HotShotConfig<KEY> {
             start_voting_time: val.upgrade.start_voting_time,
             stop_voting_time: val.upgrade.stop_voting_time,
             epoch_height: val.epoch_height,
+            checkpoint_interval: val.checkpoint_interval,
+            attestation_window: val.attestation_window,
+            journal: val.journal,
+            watchdog: val.watchdog,
+            namespace: val.namespace,
+            da_chunked_dispersal: val.da_chunked_dispersal,
+            da_payload_validation: val.da_payload_validation,
+            consensus_task_threads: val.consensus_task_threads,
+            memory_budget: val.memory_budget,
+            watcher: val.watcher,
+            archival: val.archival,
+            message_ttl: val.message_ttl,
         }
     }
 }
 
 impl<KEY: SignatureKey> HotShotConfigFile<KEY> {
+    /// Check that the fields of the config are consistent with each other
+    ///
+    /// The stake table is not part of the file, so it is only checked once known, by
+    /// [`HotShotConfig::validate`].
+    ///
+    /// # Errors
+    /// the first inconsistency found
+    pub fn validate(&self) -> Result<(), HotShotConfigError> {
+        HotShotConfig::from(self.clone()).validate_parameters()
+    }
+
     /// Creates a new `HotShotConfigFile` with 5 nodes and 10 DA nodes.
     ///
     /// # Panics
@@ -128,6 +199,18 @@ impl<KEY: SignatureKey> HotShotConfigFile<KEY> {
             builder_urls: default_builder_urls(),
             upgrade: UpgradeConfig::default(),
             epoch_height: 0,
+            checkpoint_interval: 0,
+            attestation_window: 0,
+            journal: None,
+            watchdog: None,
+            namespace: 0,
+            da_chunked_dispersal: false,
+            da_payload_validation: false,
+            consensus_task_threads: 0,
+            memory_budget: MemoryBudgetConfig::default(),
+            watcher: false,
+            archival: false,
+            message_ttl: None,
         }
     }
 }
@@ -0,0 +1,242 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! An auditable on-disk journal of the consensus decisions of a node.
+//!
+//! The journal is kept apart from the debug logs. Each [`JournalRecord`] is appended as one line
+//! of JSON to `journal.jsonl` in the configured directory. Once that file grows past
+//! [`JournalConfig::max_file_bytes`], it is rotated to `journal.1.jsonl`, the previous
+//! `journal.1.jsonl` to `journal.2.jsonl`, and so on, keeping at most
+//! [`JournalConfig::max_files`] files. [`read_journal`] reads the records back, oldest first.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the journal file currently written to
+const CURRENT_FILE: &str = "journal.jsonl";
+
+/// Configuration of the consensus journal
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalConfig {
+    /// The directory the journal files are written to
+    pub dir: PathBuf,
+    /// The size at which the current journal file is rotated
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// The number of journal files to keep, including the current one
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+/// Rotate journal files at 64 MiB by default
+fn default_max_file_bytes() -> u64 {
+    64 << 20
+}
+
+/// Keep 8 journal files by default
+fn default_max_files() -> usize {
+    8
+}
+
+impl JournalConfig {
+    /// A journal in `dir`, with the default rotation settings
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_file_bytes: default_max_file_bytes(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+/// A consensus decision of a node
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// We sent a quorum proposal
+    ProposalSent {
+        /// commitment to the proposed leaf
+        leaf_commit: String,
+        /// height of the proposed block
+        height: u64,
+    },
+    /// We received a quorum proposal
+    ProposalReceived {
+        /// the key of the leader who sent it
+        sender: String,
+        /// commitment to the proposed leaf
+        leaf_commit: String,
+        /// height of the proposed block
+        height: u64,
+    },
+    /// We sent a vote
+    VoteSent {
+        /// the kind of vote, e.g. `quorum`, `da` or `timeout`
+        kind: String,
+    },
+    /// We received a vote as a leader
+    VoteReceived {
+        /// the kind of vote, e.g. `quorum`, `da` or `timeout`
+        kind: String,
+        /// the key of the voter
+        voter: String,
+    },
+    /// We formed a certificate from the votes we received
+    CertificateFormed {
+        /// the kind of certificate, e.g. `quorum`, `da` or `timeout`
+        kind: String,
+    },
+    /// A leaf was decided
+    Decided {
+        /// commitment to the decided leaf
+        leaf_commit: String,
+        /// height of the decided block
+        height: u64,
+    },
+    /// The view timed out
+    ViewTimeout,
+}
+
+/// A journal entry, with the view and time it was recorded in
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    /// milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// the view the entry belongs to
+    pub view: u64,
+    /// what happened
+    #[serde(flatten)]
+    pub entry: JournalEntry,
+}
+
+impl JournalRecord {
+    /// A record of `entry` in `view`, stamped with the current time
+    #[must_use]
+    pub fn now(view: u64, entry: JournalEntry) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+            .unwrap_or_default();
+        Self {
+            timestamp_ms,
+            view,
+            entry,
+        }
+    }
+}
+
+/// The path of the `index`th journal file, where 0 is the current one
+fn journal_file(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(CURRENT_FILE)
+    } else {
+        dir.join(format!("journal.{index}.jsonl"))
+    }
+}
+
+/// Appends records to the journal, rotating its files
+#[derive(Debug)]
+pub struct JournalWriter {
+    /// the configuration of the journal
+    config: JournalConfig,
+    /// the current journal file
+    file: File,
+    /// the size of the current journal file
+    file_bytes: u64,
+}
+
+impl JournalWriter {
+    /// Open the journal described by `config`, appending to its current file
+    ///
+    /// # Errors
+    /// if the directory cannot be created or the current file cannot be opened
+    pub fn open(config: JournalConfig) -> io::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(journal_file(&config.dir, 0))?;
+        let file_bytes = file.metadata()?.len();
+        Ok(Self {
+            config,
+            file,
+            file_bytes,
+        })
+    }
+
+    /// Append `record` to the journal, rotating the current file first if it is full
+    ///
+    /// # Errors
+    /// if the record cannot be written, or the files cannot be rotated
+    pub fn append(&mut self, record: &JournalRecord) -> io::Result<()> {
+        if self.file_bytes >= self.config.max_file_bytes {
+            self.rotate()?;
+        }
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file_bytes += u64::try_from(line.len()).unwrap_or(u64::MAX);
+        Ok(())
+    }
+
+    /// Shift every journal file one place back, dropping the oldest, and start a new current file
+    fn rotate(&mut self) -> io::Result<()> {
+        let dir = &self.config.dir;
+        let oldest = self.config.max_files.max(1) - 1;
+        if oldest == 0 {
+            self.file.set_len(0)?;
+            self.file_bytes = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(journal_file(dir, oldest));
+        for index in (0..oldest).rev() {
+            let from = journal_file(dir, index);
+            if from.exists() {
+                std::fs::rename(from, journal_file(dir, index + 1))?;
+            }
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(journal_file(dir, 0))?;
+        self.file_bytes = 0;
+        Ok(())
+    }
+}
+
+/// Read all records of the journal in `dir`, oldest first
+///
+/// # Errors
+/// if a journal file cannot be read or holds a line which is not a record
+pub fn read_journal(dir: &Path) -> io::Result<Vec<JournalRecord>> {
+    let mut files = vec![];
+    for index in 0.. {
+        let path = journal_file(dir, index);
+        if !path.exists() {
+            break;
+        }
+        files.push(path);
+    }
+
+    let mut records = vec![];
+    for path in files.iter().rev() {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.is_empty() {
+                records.push(serde_json::from_str(&line)?);
+            }
+        }
+    }
+    Ok(records)
+}
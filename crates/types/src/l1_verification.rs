@@ -0,0 +1,310 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Calldata for verifying a certificate on Ethereum.
+//!
+//! An on-chain verifier checks a certificate with the BN254 precompiles, so it takes the points
+//! of the certificate uncompressed, in the encoding of EIP-196 and EIP-197:
+//!
+//! ```solidity
+//! function verifyQuorumCertificate(
+//!     bytes32 message,
+//!     uint256[2] signature,         // the aggregated signature, a G1 point (x, y)
+//!     uint256[] signerBitmap,       // signer i is bit i % 256 of word i / 256
+//!     uint256[4][] stakeTableKeys,  // G2 points (x_imaginary, x_real, y_imaginary, y_real)
+//!     uint256[] stakes,
+//!     uint256 threshold
+//! )
+//! ```
+//!
+//! The verifier sums the stakes of the signers and checks that it reaches the threshold, adds up
+//! the keys of the signers, and checks the signature over `message` against the sum, hashing
+//! `message` to G1 with Keccak-256 like the signature scheme of the keys does.
+//! [`QcCalldata::verify`] does the same on the decoded calldata, so that the encoding can be
+//! tested without a chain.
+
+use ark_bn254::{Fq, Fq2, G1Affine, G2Affine, G2Projective};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use jf_signature::{bls_over_bn254::BLSOverBN254CurveSignatureScheme, SignatureScheme};
+use primitive_types::U256;
+use sha3::{Digest, Keccak256};
+use utils::anytrace::*;
+
+use crate::{
+    message::UpgradeLock,
+    signature_key::BLSPubKey,
+    simple_certificate::{SimpleCertificate, Threshold},
+    simple_vote::Voteable,
+    stake_table::StakeTableEntry,
+    traits::{
+        node_implementation::{NodeType, Versions},
+        signature_key::SignatureKey,
+    },
+    vote::Certificate,
+};
+
+/// The signature of the function of the verifier
+pub const VERIFIER_FUNCTION_SIGNATURE: &str =
+    "verifyQuorumCertificate(bytes32,uint256[2],uint256[],uint256[4][],uint256[],uint256)";
+
+/// A 256 bit word of calldata
+pub type Word = [u8; 32];
+
+/// The arguments of the verifier for one certificate
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QcCalldata {
+    /// the commitment the votes of the certificate signed
+    pub message: Word,
+    /// the aggregated signature, as the coordinates of a G1 point
+    pub signature: [Word; 2],
+    /// the signers, bit `i % 256` of word `i / 256` being set if the `i`th key signed
+    pub signer_bitmap: Vec<Word>,
+    /// the keys of the stake table, as the coordinates of G2 points
+    pub stake_table_keys: Vec<[Word; 4]>,
+    /// the stakes of the keys
+    pub stakes: Vec<Word>,
+    /// the stake the signers need at least
+    pub threshold: Word,
+}
+
+/// The big endian word of `x`
+fn fq_word(x: Fq) -> Word {
+    let mut word = [0u8; 32];
+    word.copy_from_slice(&x.into_bigint().to_bytes_be());
+    word
+}
+
+/// The field element of the big endian `word`, which must be reduced like the precompiles require
+fn word_fq(word: &Word) -> Result<Fq> {
+    let x = Fq::from_be_bytes_mod_order(word);
+    ensure!(
+        fq_word(x) == *word,
+        info!("Coordinate is not a field element")
+    );
+    Ok(x)
+}
+
+/// The big endian word of `x`
+fn u256_word(x: U256) -> Word {
+    let mut word = [0u8; 32];
+    x.to_big_endian(&mut word);
+    word
+}
+
+/// Decode a point of type `P` from a type `T` of the same canonical serialization
+fn convert<T: CanonicalSerialize, P: CanonicalDeserialize>(value: &T) -> Result<P> {
+    let mut bytes = vec![];
+    value
+        .serialize_compressed(&mut bytes)
+        .wrap()
+        .context(info!("Failed to serialize point"))?;
+    P::deserialize_compressed(&bytes[..])
+        .wrap()
+        .context(info!("Failed to deserialize point"))
+}
+
+/// The coordinates of `point`, zero for the point at infinity
+fn g1_words(point: &G1Affine) -> [Word; 2] {
+    match point.xy() {
+        Some((x, y)) => [fq_word(*x), fq_word(*y)],
+        None => [[0; 32]; 2],
+    }
+}
+
+/// The coordinates of `point`, zero for the point at infinity
+fn g2_words(point: &G2Affine) -> [Word; 4] {
+    match point.xy() {
+        Some((x, y)) => [fq_word(x.c1), fq_word(x.c0), fq_word(y.c1), fq_word(y.c0)],
+        None => [[0; 32]; 4],
+    }
+}
+
+/// The G1 point with coordinates `words`, which must be on the curve
+fn words_g1(words: &[Word; 2]) -> Result<G1Affine> {
+    let (x, y) = (word_fq(&words[0])?, word_fq(&words[1])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(<G1Affine as AffineRepr>::zero());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    ensure!(point.is_on_curve(), info!("Signature is not on the curve"));
+    Ok(point)
+}
+
+/// The G2 point with coordinates `words`, which must be in the subgroup of G2
+fn words_g2(words: &[Word; 4]) -> Result<G2Affine> {
+    let x = Fq2::new(word_fq(&words[1])?, word_fq(&words[0])?);
+    let y = Fq2::new(word_fq(&words[3])?, word_fq(&words[2])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(<G2Affine as AffineRepr>::zero());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    ensure!(
+        point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve(),
+        info!("Key is not in G2")
+    );
+    Ok(point)
+}
+
+impl QcCalldata {
+    /// The calldata for the assembled signature `signatures` over `message`, by keys of
+    /// `stake_table` which need `threshold` stake
+    ///
+    /// # Errors
+    /// if the signers do not match the stake table
+    pub fn new(
+        message: Word,
+        signatures: &<BLSPubKey as SignatureKey>::QcType,
+        stake_table: &[StakeTableEntry<BLSPubKey>],
+        threshold: U256,
+    ) -> Result<Self> {
+        let (signature, signers) = signatures;
+        ensure!(
+            signers.len() == stake_table.len(),
+            info!(
+                "{} signers for a stake table of {} keys",
+                signers.len(),
+                stake_table.len()
+            )
+        );
+
+        let mut signer_bitmap = vec![[0u8; 32]; stake_table.len().div_ceil(256)];
+        for index in signers.iter_ones() {
+            // words are big endian, so bit 0 is the last bit of the last byte
+            signer_bitmap[index / 256][31 - (index % 256) / 8] |= 1 << (index % 8);
+        }
+
+        let stake_table_keys = stake_table
+            .iter()
+            .map(|entry| Ok(g2_words(&convert(&entry.stake_key)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            message,
+            signature: g1_words(&convert(signature)?),
+            signer_bitmap,
+            stake_table_keys,
+            stakes: stake_table
+                .iter()
+                .map(|entry| u256_word(entry.stake_amount))
+                .collect(),
+            threshold: u256_word(threshold),
+        })
+    }
+
+    /// The calldata for `certificate`, whose signers are in the stake table of `membership` in
+    /// `epoch`
+    ///
+    /// # Errors
+    /// if the certificate is not signed, or its signers do not match the stake table
+    pub async fn from_certificate<TYPES, VOTEABLE, THRESHOLD, V>(
+        certificate: &SimpleCertificate<TYPES, VOTEABLE, THRESHOLD>,
+        membership: &TYPES::Membership,
+        epoch: TYPES::Epoch,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> Result<Self>
+    where
+        TYPES: NodeType<SignatureKey = BLSPubKey>,
+        VOTEABLE: Voteable<TYPES>,
+        THRESHOLD: Threshold<TYPES>,
+        V: Versions,
+        SimpleCertificate<TYPES, VOTEABLE, THRESHOLD>:
+            Certificate<TYPES, VOTEABLE, Voteable = VOTEABLE>,
+    {
+        let signatures = certificate
+            .signatures
+            .as_ref()
+            .context(info!("Certificate is not signed"))?;
+        let message = certificate.data_commitment(upgrade_lock).await?.into();
+        let stake_table = <SimpleCertificate<TYPES, VOTEABLE, THRESHOLD> as Certificate<
+            TYPES,
+            VOTEABLE,
+        >>::stake_table(membership, epoch);
+        let threshold = <SimpleCertificate<TYPES, VOTEABLE, THRESHOLD> as Certificate<
+            TYPES,
+            VOTEABLE,
+        >>::threshold(membership, epoch);
+
+        Self::new(message, signatures, &stake_table, U256::from(threshold))
+    }
+
+    /// The ABI encoded call of [`VERIFIER_FUNCTION_SIGNATURE`] with these arguments
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        /// The number of words in the head of the arguments
+        const HEAD_WORDS: usize = 7;
+
+        let selector = &Keccak256::digest(VERIFIER_FUNCTION_SIGNATURE)[..4];
+        let mut head: Vec<Word> = vec![self.message, self.signature[0], self.signature[1]];
+        let mut tail: Vec<Word> = vec![];
+        let mut dynamic = |head: &mut Vec<Word>, words: Vec<Word>, len: usize| {
+            head.push(u256_word(U256::from((HEAD_WORDS + tail.len()) * 32)));
+            tail.push(u256_word(U256::from(len)));
+            tail.extend(words);
+        };
+
+        dynamic(
+            &mut head,
+            self.signer_bitmap.clone(),
+            self.signer_bitmap.len(),
+        );
+        dynamic(
+            &mut head,
+            self.stake_table_keys.iter().flatten().copied().collect(),
+            self.stake_table_keys.len(),
+        );
+        dynamic(&mut head, self.stakes.clone(), self.stakes.len());
+        head.push(self.threshold);
+
+        let mut calldata = selector.to_vec();
+        calldata.extend(head.iter().chain(&tail).flatten());
+        calldata
+    }
+
+    /// Verify the calldata the way the on-chain verifier does
+    ///
+    /// # Errors
+    /// if a point is malformed, the signers do not have enough stake, or the signature does not
+    /// verify
+    pub fn verify(&self) -> Result<()> {
+        let num_keys = self.stake_table_keys.len();
+        ensure!(
+            self.stakes.len() == num_keys && self.signer_bitmap.len() == num_keys.div_ceil(256),
+            info!("Stake table and bitmap lengths do not match")
+        );
+
+        let mut signed_stake = U256::zero();
+        let mut aggregated_key = G2Projective::zero();
+        for (index, (key, stake)) in self.stake_table_keys.iter().zip(&self.stakes).enumerate() {
+            let word = U256::from_big_endian(&self.signer_bitmap[index / 256]);
+            if word.bit(index % 256) {
+                signed_stake = signed_stake
+                    .checked_add(U256::from_big_endian(stake))
+                    .context(info!("Stake overflows"))?;
+                aggregated_key += words_g2(key)?;
+            }
+        }
+        // bits past the last key must be clear
+        for index in num_keys..self.signer_bitmap.len() * 256 {
+            let word = U256::from_big_endian(&self.signer_bitmap[index / 256]);
+            ensure!(
+                !word.bit(index % 256),
+                info!("Bitmap has signers past the stake table")
+            );
+        }
+        ensure!(
+            signed_stake >= U256::from_big_endian(&self.threshold),
+            info!("Signers have {signed_stake} stake, less than the threshold")
+        );
+
+        let aggregated_key: BLSPubKey = convert(&G2Affine::from(aggregated_key))?;
+        let signature = convert(&words_g1(&self.signature)?)?;
+        BLSOverBN254CurveSignatureScheme::verify(&(), &aggregated_key, self.message, &signature)
+            .wrap()
+            .context(info!("Signature does not verify"))
+    }
+}
@@ -11,32 +11,56 @@ use bincode::Options;
 use displaydoc::Display;
 use light_client::StateVerKey;
 use tracing::error;
-use traits::signature_key::SignatureKey;
+use traits::signature_key::{SignatureKey, StakeTableEntryType};
 use url::Url;
 use vec1::Vec1;
 
-use crate::utils::bincode_opts;
+use crate::{
+    error::HotShotConfigError, journal::JournalConfig, memory_budget::MemoryBudgetConfig,
+    utils::bincode_opts, watchdog::WatchdogConfig,
+};
+pub mod attestation;
 pub mod bundle;
+pub mod certificate_cache;
+pub mod codec;
 pub mod consensus;
 pub mod constants;
 pub mod data;
+pub mod deterministic;
 /// Holds the types and functions for DRB computation.
 pub mod drb;
 pub mod error;
 pub mod event;
+/// Holds the types for evidence of Byzantine behaviour.
+pub mod evidence;
+pub mod fair_queue;
+pub mod finality;
+pub mod fork_tree;
 /// Holds the configuration file specification for a HotShot node.
 pub mod hotshot_config_file;
+pub mod journal;
+pub mod l1_verification;
 pub mod light_client;
+pub mod liveness;
+pub mod memory_budget;
 pub mod message;
+pub mod multisig_key;
 
 /// Holds the network configuration specification for HotShot nodes.
 pub mod network;
+pub mod proposal_cache;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
 pub mod qc;
+pub mod rate_limit;
+pub mod reconfig;
 pub mod request_response;
 pub mod signature_key;
+pub mod signing;
 pub mod simple_certificate;
 pub mod simple_vote;
 pub mod stake_table;
+pub mod trace_context;
 pub mod traits;
 
 /// Holds the upgrade configuration specification for HotShot nodes.
@@ -44,8 +68,14 @@ pub mod upgrade_config;
 pub mod utils;
 /// Holds the validator configuration specification for HotShot nodes.
 pub mod validator_config;
+pub mod validator_metadata;
 pub mod vid;
+pub mod view_change;
 pub mod vote;
+pub mod vote_intent;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watchdog;
 
 /// Pinned future that is Send and Sync
 pub type BoxSyncFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + Sync + 'a>>;
@@ -208,9 +238,127 @@ pub struct HotShotConfig<KEY: SignatureKey> {
     pub stop_voting_time: u64,
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+    /// Number of blocks between checkpoints, zero means there are no checkpoints
+    #[serde(default)]
+    pub checkpoint_interval: u64,
+    /// Number of the latest decided leaves this node attests to messages for, zero means it
+    /// attests to none
+    #[serde(default)]
+    pub attestation_window: u64,
+    /// Where to keep a journal of the consensus decisions of this node, if anywhere
+    #[serde(default)]
+    pub journal: Option<JournalConfig>,
+    /// When to raise alerts about stalled consensus, if at all
+    #[serde(default)]
+    pub watchdog: Option<WatchdogConfig>,
+    /// The consensus instance this node takes part in, which lets several instances share one
+    /// network; messages of other instances are ignored
+    ///
+    /// Messages carry it from the epochs version on, so instances can only share a network once
+    /// they run that version.
+    #[serde(default)]
+    pub namespace: u64,
+    /// Whether the DA leader sends each DA committee member an erasure-coded chunk of the payload
+    /// rather than the whole payload
+    #[serde(default)]
+    pub da_chunked_dispersal: bool,
+    /// Whether DA committee members check the payload against the application's rules before
+    /// voting for it, first recovering it from the other members' chunks under chunked dispersal
+    #[serde(default)]
+    pub da_payload_validation: bool,
+    /// The number of threads dedicated to consensus-critical tasks, or zero to run them on the
+    /// shared runtime with every other task
+    #[serde(default)]
+    pub consensus_task_threads: usize,
+    /// The most memory, in bytes, the network receive queue, pending transactions, pre-verified
+    /// votes and leaf store may each use; unlimited by default
+    #[serde(default)]
+    pub memory_budget: MemoryBudgetConfig,
+    /// Whether this node only follows consensus: it verifies and stores what the committee
+    /// decides and serves it, but never votes or proposes. Its key, which then only identifies
+    /// it on the network, must not be in the stake table.
+    #[serde(default)]
+    pub watcher: bool,
+    /// Whether this node keeps every finalized leaf instead of only the latest ones, indexed by
+    /// block height and by transaction, and serves ranges of them to nodes catching up
+    #[serde(default)]
+    pub archival: bool,
+    /// The number of views past the view it is sent in after which the receivers of a message
+    /// we send drop it as stale, or `None` for our messages never to expire. Messages only carry
+    /// an expiry from the epochs version on.
+    #[serde(default)]
+    pub message_ttl: Option<u64>,
 }
 
 impl<KEY: SignatureKey> HotShotConfig<KEY> {
+    /// Check that the fields of the config are consistent with each other
+    ///
+    /// # Errors
+    /// the first inconsistency found
+    pub fn validate(&self) -> Result<(), HotShotConfigError> {
+        let committee = self.num_nodes_with_stake.get();
+        if self.known_nodes_with_stake.is_empty() {
+            return Err(HotShotConfigError::EmptyStakeTable);
+        }
+        if self.known_nodes_with_stake.len() != committee {
+            return Err(HotShotConfigError::StakeTableSizeMismatch {
+                known: self.known_nodes_with_stake.len(),
+                expected: committee,
+            });
+        }
+        self.validate_parameters()
+    }
+
+    /// Check that the node with `public_key` may run with this config
+    ///
+    /// # Errors
+    /// if the node is a watcher but has stake
+    pub fn validate_node(&self, public_key: &KEY) -> Result<(), HotShotConfigError> {
+        let has_stake = self
+            .known_nodes_with_stake
+            .iter()
+            .chain(&self.known_da_nodes)
+            .any(|peer| peer.stake_table_entry.public_key() == *public_key);
+        if self.watcher && has_stake {
+            return Err(HotShotConfigError::WatcherWithStake);
+        }
+        Ok(())
+    }
+
+    /// Check the fields of the config which do not depend on the stake table
+    pub(crate) fn validate_parameters(&self) -> Result<(), HotShotConfigError> {
+        let committee = self.num_nodes_with_stake.get();
+        if self.da_staked_committee_size > committee {
+            return Err(HotShotConfigError::DaCommitteeTooLarge {
+                da: self.da_staked_committee_size,
+                committee,
+            });
+        }
+        let (numerator, denominator) = self.start_threshold;
+        if denominator == 0 || numerator > denominator {
+            return Err(HotShotConfigError::InvalidStartThreshold(
+                numerator,
+                denominator,
+            ));
+        }
+        if self.next_view_timeout == 0 {
+            return Err(HotShotConfigError::ZeroTimeout("next view timeout"));
+        }
+        if self.view_sync_timeout.is_zero() {
+            return Err(HotShotConfigError::ZeroTimeout("view sync timeout"));
+        }
+        if self.builder_timeout.is_zero() {
+            return Err(HotShotConfigError::ZeroTimeout("builder timeout"));
+        }
+        if self.num_bootstrap > committee {
+            return Err(HotShotConfigError::TooManyBootstrapNodes {
+                bootstrap: self.num_bootstrap,
+                committee,
+            });
+        }
+        Ok(())
+    }
+
     /// Update a hotshot config to have a view-based upgrade.
     pub fn set_view_upgrade(&mut self, view: u64) {
         self.start_proposing_view = view;
@@ -0,0 +1,119 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Tracking of how reliably each validator participates in consensus.
+//!
+//! Each observation of a validator, such as whether its vote made it into a certificate or
+//! whether it completed a view sync round as relay, moves its liveness score towards 1 for
+//! success or 0 for failure. Recent observations weigh more than old ones, so a validator which
+//! recovers is trusted again after a while.
+//!
+//! Scores are local to each node, so they are only reported, never used where nodes must agree,
+//! such as in choosing a leader or a view sync relay.
+
+use std::collections::HashMap;
+
+use bitvec::vec::BitVec;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::LIVENESS_SCORE_WEIGHT, traits::signature_key::SignatureKey};
+
+/// The participation observed for one validator.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LivenessRecord {
+    /// Number of certificates which included the validator's vote
+    pub included_votes: u64,
+    /// Number of certificates which did not include the validator's vote
+    pub missed_votes: u64,
+    /// Number of view sync rounds the validator completed as relay
+    pub relay_successes: u64,
+    /// Number of view sync rounds which timed out with the validator as relay
+    pub relay_failures: u64,
+    /// Exponentially weighted average of all observations, between 0 and 1
+    pub score: f64,
+}
+
+impl Default for LivenessRecord {
+    fn default() -> Self {
+        Self {
+            included_votes: 0,
+            missed_votes: 0,
+            relay_successes: 0,
+            relay_failures: 0,
+            score: 1.0,
+        }
+    }
+}
+
+impl LivenessRecord {
+    /// Fold an observation into the score.
+    fn observe(&mut self, success: bool) {
+        let outcome = if success { 1.0 } else { 0.0 };
+        self.score += LIVENESS_SCORE_WEIGHT * (outcome - self.score);
+    }
+}
+
+/// Liveness scores of the validators we have observed.
+#[derive(Clone, Debug)]
+pub struct LivenessTracker<KEY: SignatureKey> {
+    /// The participation of each validator
+    records: HashMap<KEY, LivenessRecord>,
+}
+
+impl<KEY: SignatureKey> Default for LivenessTracker<KEY> {
+    fn default() -> Self {
+        Self {
+            records: HashMap::new(),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> LivenessTracker<KEY> {
+    /// Record which members of `stake_table` signed a certificate.
+    ///
+    /// `signers` is the signer bitmap of the certificate, indexed like `stake_table`.
+    pub fn record_certificate(&mut self, stake_table: &[KEY::StakeTableEntry], signers: &BitVec) {
+        for (index, entry) in stake_table.iter().enumerate() {
+            let included = signers.get(index).as_deref() == Some(&true);
+            let record = self.records.entry(KEY::public_key(entry)).or_default();
+            if included {
+                record.included_votes += 1;
+            } else {
+                record.missed_votes += 1;
+            }
+            record.observe(included);
+        }
+    }
+
+    /// Record whether `relay` completed a view sync round.
+    pub fn record_relay(&mut self, relay: &KEY, succeeded: bool) {
+        let record = self.records.entry(relay.clone()).or_default();
+        if succeeded {
+            record.relay_successes += 1;
+        } else {
+            record.relay_failures += 1;
+        }
+        record.observe(succeeded);
+    }
+
+    /// The liveness score of `key`, between 0 and 1. Validators we know nothing about score 1.
+    #[must_use]
+    pub fn score(&self, key: &KEY) -> f64 {
+        self.records.get(key).map_or(1.0, |record| record.score)
+    }
+
+    /// The participation record of `key`, if we have observed it.
+    #[must_use]
+    pub fn record(&self, key: &KEY) -> Option<&LivenessRecord> {
+        self.records.get(key)
+    }
+
+    /// The participation records of all observed validators.
+    #[must_use]
+    pub fn records(&self) -> &HashMap<KEY, LivenessRecord> {
+        &self.records
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Approximate accounting of the memory held by the network receive queue, pending transactions,
+//! pre-verified votes and the leaf store, against configurable budgets.
+//!
+//! Components react differently when over budget:
+//! - the network receive queue stops taking messages until handling them frees room,
+//! - transactions submitted to the node are rejected,
+//! - vote signatures checked on arrival are no longer remembered, so vote accumulators check
+//!   them again,
+//! - the leaf store is only reported, since leaves which are not decided yet cannot be dropped.
+//!
+//! Usage is exported through the `*_memory` gauges of [`ConsensusMetricsValue`].
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::{consensus::ConsensusMetricsValue, traits::metrics::Gauge};
+
+/// A part of the node whose memory is accounted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemoryComponent {
+    /// messages received from the network which were not handled yet
+    NetworkQueue,
+    /// transactions submitted to this node which were not decided or expired yet
+    PendingTransactions,
+    /// vote signatures checked on arrival, which were not accumulated yet
+    VerifiedVotes,
+    /// the leaves and payloads retained in memory
+    LeafStore,
+}
+
+impl MemoryComponent {
+    /// Every component
+    pub const ALL: [MemoryComponent; 4] = [
+        MemoryComponent::NetworkQueue,
+        MemoryComponent::PendingTransactions,
+        MemoryComponent::VerifiedVotes,
+        MemoryComponent::LeafStore,
+    ];
+
+    /// Index of the component in the usage counters
+    fn index(self) -> usize {
+        match self {
+            MemoryComponent::NetworkQueue => 0,
+            MemoryComponent::PendingTransactions => 1,
+            MemoryComponent::VerifiedVotes => 2,
+            MemoryComponent::LeafStore => 3,
+        }
+    }
+}
+
+/// The most memory each component may use, in bytes, or unlimited if not given
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryBudgetConfig {
+    /// budget of the network receive queue
+    #[serde(default)]
+    pub network_queue: Option<usize>,
+    /// budget of the pending transactions
+    #[serde(default)]
+    pub pending_transactions: Option<usize>,
+    /// budget of the pre-verified votes
+    #[serde(default)]
+    pub verified_votes: Option<usize>,
+    /// budget of the leaf store
+    #[serde(default)]
+    pub leaf_store: Option<usize>,
+}
+
+impl MemoryBudgetConfig {
+    /// The budget of `component`, if it has one
+    #[must_use]
+    pub fn limit(&self, component: MemoryComponent) -> Option<usize> {
+        match component {
+            MemoryComponent::NetworkQueue => self.network_queue,
+            MemoryComponent::PendingTransactions => self.pending_transactions,
+            MemoryComponent::VerifiedVotes => self.verified_votes,
+            MemoryComponent::LeafStore => self.leaf_store,
+        }
+    }
+}
+
+/// Shared state of a [`MemoryBudget`]
+#[derive(derive_more::Debug)]
+struct Accounts {
+    /// the budgets
+    limits: MemoryBudgetConfig,
+    /// the approximate bytes used by each component
+    usage: [AtomicUsize; 4],
+    /// woken whenever memory is released
+    released: Notify,
+    /// where usage is reported
+    #[debug(skip)]
+    metrics: Arc<ConsensusMetricsValue>,
+}
+
+/// The approximate memory used by each [`MemoryComponent`] and its budget, shared by the parts of
+/// the node holding the memory
+#[derive(Clone, Debug)]
+pub struct MemoryBudget {
+    /// the shared accounts
+    accounts: Arc<Accounts>,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self::new(
+            MemoryBudgetConfig::default(),
+            Arc::new(ConsensusMetricsValue::default()),
+        )
+    }
+}
+
+impl MemoryBudget {
+    /// Account memory against `limits`, reporting usage to `metrics`
+    #[must_use]
+    pub fn new(limits: MemoryBudgetConfig, metrics: Arc<ConsensusMetricsValue>) -> Self {
+        Self {
+            accounts: Arc::new(Accounts {
+                limits,
+                usage: Default::default(),
+                released: Notify::new(),
+                metrics,
+            }),
+        }
+    }
+
+    /// The budget of `component`, if it has one
+    #[must_use]
+    pub fn limit(&self, component: MemoryComponent) -> Option<usize> {
+        self.accounts.limits.limit(component)
+    }
+
+    /// The approximate bytes used by `component`
+    #[must_use]
+    pub fn usage(&self, component: MemoryComponent) -> usize {
+        self.counter(component).load(Ordering::Relaxed)
+    }
+
+    /// Whether `component` uses more than its budget
+    #[must_use]
+    pub fn is_over_budget(&self, component: MemoryComponent) -> bool {
+        self.limit(component)
+            .is_some_and(|limit| self.usage(component) > limit)
+    }
+
+    /// Charge `bytes` to `component` if that keeps it within its budget, or if it uses nothing
+    /// yet so that a single item larger than the budget is not refused forever. Returns whether
+    /// the bytes were charged.
+    pub fn try_reserve(&self, component: MemoryComponent, bytes: usize) -> bool {
+        let limit = self.limit(component);
+        let reserved = self
+            .counter(component)
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                let fits = limit.map_or(true, |limit| usage.saturating_add(bytes) <= limit);
+                (fits || usage == 0).then_some(usage.saturating_add(bytes))
+            })
+            .is_ok();
+        if reserved {
+            self.report(component);
+        }
+        reserved
+    }
+
+    /// Charge `bytes` to `component`, whatever its budget
+    pub fn reserve(&self, component: MemoryComponent, bytes: usize) {
+        self.counter(component).fetch_add(bytes, Ordering::Relaxed);
+        self.report(component);
+    }
+
+    /// Release `bytes` charged to `component`
+    pub fn release(&self, component: MemoryComponent, bytes: usize) {
+        let _ =
+            self.counter(component)
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                    Some(usage.saturating_sub(bytes))
+                });
+        self.report(component);
+        self.accounts.released.notify_waiters();
+    }
+
+    /// Set the bytes used by `component`, for components which measure their whole size at once
+    pub fn set_usage(&self, component: MemoryComponent, bytes: usize) {
+        let previous = self.counter(component).swap(bytes, Ordering::Relaxed);
+        self.report(component);
+        if bytes < previous {
+            self.accounts.released.notify_waiters();
+        }
+    }
+
+    /// Wait until `component` is within its budget
+    pub async fn wait_for_room(&self, component: MemoryComponent) {
+        loop {
+            let released = self.accounts.released.notified();
+            if !self.is_over_budget(component) {
+                return;
+            }
+            released.await;
+        }
+    }
+
+    /// The usage counter of `component`
+    fn counter(&self, component: MemoryComponent) -> &AtomicUsize {
+        &self.accounts.usage[component.index()]
+    }
+
+    /// Report the usage of `component` to the metrics
+    fn report(&self, component: MemoryComponent) {
+        let metrics = &self.accounts.metrics;
+        let gauge: &dyn Gauge = match component {
+            MemoryComponent::NetworkQueue => &*metrics.network_queue_memory,
+            MemoryComponent::PendingTransactions => &*metrics.pending_transactions_memory,
+            MemoryComponent::VerifiedVotes => &*metrics.verified_votes_memory,
+            MemoryComponent::LeafStore => &*metrics.leaf_store_memory,
+        };
+        gauge.set(self.usage(component));
+    }
+}
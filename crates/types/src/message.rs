@@ -10,14 +10,17 @@
 //! `HotShot` nodes can send among themselves.
 
 use std::{
+    collections::HashMap,
     fmt::{self, Debug},
     marker::PhantomData,
     sync::Arc,
 };
 
 use async_lock::RwLock;
+use bytes::Bytes;
 use committable::Committable;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::task::spawn_blocking;
 use utils::anytrace::*;
 use vbs::{
     version::{StaticVersionType, Version},
@@ -25,9 +28,11 @@ use vbs::{
 };
 
 use crate::{
+    attestation::AttestationRequest,
+    constants::{MAX_MESSAGE_SIZE, UPGRADE_TRANSITION_WINDOW},
     data::{
-        DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2, UpgradeProposal,
-        VidDisperseShare, VidDisperseShare2,
+        DaChunk, DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2,
+        UpgradeProposal, VidDisperseShare, VidDisperseShare2,
     },
     request_response::ProposalRequestPayload,
     simple_certificate::{
@@ -36,9 +41,9 @@ use crate::{
         ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate, ViewSyncPreCommitCertificate2,
     },
     simple_vote::{
-        DaVote, DaVote2, QuorumVote, QuorumVote2, TimeoutVote, TimeoutVote2, UpgradeVote,
-        ViewSyncCommitVote, ViewSyncCommitVote2, ViewSyncFinalizeVote, ViewSyncFinalizeVote2,
-        ViewSyncPreCommitVote, ViewSyncPreCommitVote2,
+        AttestationVote, CheckpointVote, DaVote, DaVote2, QuorumVote, QuorumVote2, TimeoutVote,
+        TimeoutVote2, UpgradeVote, ViewSyncCommitVote, ViewSyncCommitVote2, ViewSyncFinalizeVote,
+        ViewSyncFinalizeVote2, ViewSyncPreCommitVote, ViewSyncPreCommitVote2,
     },
     traits::{
         block_contents::BlockHeader,
@@ -48,7 +53,8 @@ use crate::{
         signature_key::SignatureKey,
     },
     utils::{epoch_from_block_number, mnemonic},
-    vote::HasViewNumber,
+    validator_metadata::SignedValidatorMetadata,
+    vote::{HasViewNumber, Vote},
 };
 
 /// Incoming message
@@ -60,6 +66,26 @@ pub struct Message<TYPES: NodeType> {
 
     /// The message kind
     pub kind: MessageKind<TYPES>,
+
+    /// The consensus instance the message belongs to, see [`HotShotConfig::namespace`]
+    ///
+    /// Like [`Self::expires_after`], it is not part of the encoding of the message.
+    /// [`UpgradeLock::serialize_signed`] sends it along from the epochs version on, and messages
+    /// of the older versions are received without it.
+    ///
+    /// [`HotShotConfig::namespace`]: crate::HotShotConfig::namespace
+    #[serde(skip)]
+    pub namespace: u64,
+
+    /// The last view the message is of use in, after which its receivers drop it as stale; `None`
+    /// if it never expires, see [`HotShotConfig::message_ttl`]
+    ///
+    /// It is not part of the encoding of the message, which is the same as before it existed.
+    /// [`UpgradeLock::serialize_signed`] sends it along from the epochs version on.
+    ///
+    /// [`HotShotConfig::message_ttl`]: crate::HotShotConfig::message_ttl
+    #[serde(skip)]
+    pub expires_after: Option<TYPES::View>,
 }
 
 impl<TYPES: NodeType> fmt::Debug for Message<TYPES> {
@@ -67,10 +93,59 @@ impl<TYPES: NodeType> fmt::Debug for Message<TYPES> {
         fmt.debug_struct("Message")
             .field("sender", &mnemonic(&self.sender))
             .field("kind", &self.kind)
+            .field("namespace", &self.namespace)
+            .field("expires_after", &self.expires_after)
             .finish()
     }
 }
 
+impl<TYPES: NodeType> Message<TYPES> {
+    /// Whether the message is stale for a receiver in `current_view`
+    #[must_use]
+    pub fn is_expired(&self, current_view: TYPES::View) -> bool {
+        self.expires_after
+            .is_some_and(|expires_after| expires_after < current_view)
+    }
+
+    /// Whether the message is a vote signed by its sender, whose signature then authenticates the
+    /// sender without a signature on the whole message
+    #[must_use]
+    pub fn is_vote_of_sender(&self) -> bool {
+        let MessageKind::Consensus(message) = &self.kind else {
+            return false;
+        };
+
+        let signing_key = match message {
+            SequencingMessage::General(message) => match message {
+                GeneralConsensusMessage::Vote2(vote) => vote.signing_key(),
+                GeneralConsensusMessage::TimeoutVote2(vote) => vote.signing_key(),
+                GeneralConsensusMessage::ViewSyncPreCommitVote2(vote) => vote.signing_key(),
+                GeneralConsensusMessage::ViewSyncCommitVote2(vote) => vote.signing_key(),
+                GeneralConsensusMessage::ViewSyncFinalizeVote2(vote) => vote.signing_key(),
+                GeneralConsensusMessage::UpgradeVote(vote) => vote.signing_key(),
+                GeneralConsensusMessage::CheckpointVote(vote) => vote.signing_key(),
+                GeneralConsensusMessage::AttestationVote(vote) => vote.signing_key(),
+                _ => return false,
+            },
+            SequencingMessage::Da(DaConsensusMessage::DaVote2(vote)) => vote.signing_key(),
+            SequencingMessage::Da(_) => return false,
+        };
+        signing_key == self.sender
+    }
+
+    /// Read the protocol version a serialized message was encoded with, without decoding the rest
+    /// of the message.
+    ///
+    /// # Errors
+    /// Returns an error if the message is too short to contain a version.
+    pub fn protocol_version(message: &[u8]) -> Result<Version> {
+        Ok(Version::deserialize(message)
+            .wrap()
+            .context(info!("Failed to read message version!"))?
+            .0)
+    }
+}
+
 impl<TYPES: NodeType> HasViewNumber<TYPES> for Message<TYPES> {
     /// get the view number out of a message
     fn view_number(&self) -> TYPES::View {
@@ -160,9 +235,13 @@ impl<TYPES: NodeType> ViewMessage<TYPES> for MessageKind<TYPES> {
             MessageKind::Data(DataMessage::RequestData(msg)) => msg.view,
             MessageKind::Data(DataMessage::DataResponse(msg)) => match msg {
                 ResponseMessage::Found(m) => m.view_number(),
-                ResponseMessage::NotFound | ResponseMessage::Denied => TYPES::View::new(1),
+                ResponseMessage::NotFound
+                | ResponseMessage::Denied
+                | ResponseMessage::LeafRange(_) => TYPES::View::new(1),
             },
-            MessageKind::External(_) => TYPES::View::new(1),
+            MessageKind::Data(DataMessage::ValidatorMetadata(_)) | MessageKind::External(_) => {
+                TYPES::View::new(1)
+            }
         }
     }
 }
@@ -245,6 +324,18 @@ pub enum GeneralConsensusMessage<TYPES: NodeType> {
 
     /// Message with a Timeout vote
     TimeoutVote2(TimeoutVote2<TYPES>),
+
+    /// Message with a checkpoint vote
+    CheckpointVote(CheckpointVote<TYPES>),
+
+    /// Message asking the quorum to attest to an application message
+    AttestationRequest(AttestationRequest<TYPES>),
+
+    /// Message with an attestation vote, for the requester of the attestation
+    AttestationVote(AttestationVote<TYPES>),
+
+    /// Message for the next leader containing our highest QC, signed by us
+    HighQc2(SignedHighQc<TYPES>),
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Hash, Eq)]
@@ -278,6 +369,9 @@ pub enum DaConsensusMessage<TYPES: NodeType> {
     ///
     /// Like [`DaProposal`]. Use `Msg` suffix to distinguish from `VidDisperse`.
     VidDisperseMsg2(Proposal<TYPES, VidDisperseShare2<TYPES>>),
+
+    /// A DA committee member's chunk of a block, sent instead of the whole DA proposal
+    DaChunk(Proposal<TYPES, DaChunk<TYPES>>),
 }
 
 /// Messages for sequencing consensus.
@@ -351,6 +445,12 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                     GeneralConsensusMessage::UpgradeProposal(message) => message.data.view_number(),
                     GeneralConsensusMessage::UpgradeVote(message) => message.view_number(),
                     GeneralConsensusMessage::HighQc(qc) => qc.view_number(),
+                    GeneralConsensusMessage::CheckpointVote(message) => message.view_number(),
+                    GeneralConsensusMessage::AttestationRequest(request) => {
+                        request.vote.view_number()
+                    }
+                    GeneralConsensusMessage::AttestationVote(message) => message.view_number(),
+                    GeneralConsensusMessage::HighQc2(high_qc) => high_qc.view,
                 }
             }
             SequencingMessage::Da(da_message) => {
@@ -371,6 +471,7 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                     }
                     DaConsensusMessage::DaVote2(vote_message) => vote_message.view_number(),
                     DaConsensusMessage::DaCertificate2(cert) => cert.view_number,
+                    DaConsensusMessage::DaChunk(chunk) => chunk.data.view_number(),
                 }
             }
         }
@@ -391,6 +492,8 @@ pub enum DataMessage<TYPES: NodeType> {
     RequestData(DataRequest<TYPES>),
     /// A response to a data request
     DataResponse(ResponseMessage<TYPES>),
+    /// The identity a validator publishes, gossiped to every node
+    ValidatorMetadata(SignedValidatorMetadata<TYPES>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
@@ -407,6 +510,54 @@ pub struct Proposal<TYPES: NodeType, PROPOSAL: HasViewNumber<TYPES> + Deserializ
     pub _pd: PhantomData<TYPES>,
 }
 
+/// The highest QC of a replica, sent to the leader of `view` and signed by the replica, so that
+/// the leader only counts responses which are authenticated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct SignedHighQc<TYPES: NodeType> {
+    /// The highest QC of the replica
+    pub qc: QuorumCertificate2<TYPES>,
+    /// The view of the leader the QC is sent to
+    pub view: TYPES::View,
+    /// The signature of the replica over the QC and `view`
+    pub signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+}
+
+impl<TYPES: NodeType> SignedHighQc<TYPES> {
+    /// The bytes signed for `qc` sent to the leader of `view`
+    fn signed_bytes(qc: &QuorumCertificate2<TYPES>, view: TYPES::View) -> Vec<u8> {
+        let mut bytes = qc.commit().as_ref().to_vec();
+        bytes.extend_from_slice(&(*view).to_le_bytes());
+        bytes
+    }
+
+    /// Sign `qc` for the leader of `view`
+    ///
+    /// # Errors
+    /// If signing fails
+    pub fn create(
+        qc: QuorumCertificate2<TYPES>,
+        view: TYPES::View,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    ) -> Result<Self> {
+        let signature = TYPES::SignatureKey::sign(private_key, &Self::signed_bytes(&qc, view))
+            .wrap()
+            .context(error!("Failed to sign the high QC"))?;
+
+        Ok(Self {
+            qc,
+            view,
+            signature,
+        })
+    }
+
+    /// Whether the QC was signed by `key`
+    #[must_use]
+    pub fn is_signed_by(&self, key: &TYPES::SignatureKey) -> bool {
+        key.validate(&self.signature, &Self::signed_bytes(&self.qc, self.view))
+    }
+}
+
 /// Convert a `Proposal` by converting the underlying proposal type
 pub fn convert_proposal<TYPES, PROPOSAL, PROPOSAL2>(
     proposal: Proposal<TYPES, PROPOSAL>,
@@ -557,6 +708,30 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
         }
     }
 
+    /// Check whether `version` is accepted in `view` because `view` lies within
+    /// [`UPGRADE_TRANSITION_WINDOW`] views on either side of the first view of a decided upgrade,
+    /// bounds included, and `version` is either the old or the new version of that upgrade.
+    ///
+    /// This lets nodes which switch their outgoing messages to the new version slightly early or
+    /// late keep participating in consensus while the network upgrades.
+    pub async fn in_transition_window(&self, version: Version, view: TYPES::View) -> bool {
+        let upgrade_certificate = self.decided_upgrade_certificate.read().await;
+
+        let Some(ref cert) = *upgrade_certificate else {
+            return false;
+        };
+
+        let first_view = *cert.data.new_version_first_view;
+        let in_window = (first_view.saturating_sub(UPGRADE_TRANSITION_WINDOW)
+            ..=first_view.saturating_add(UPGRADE_TRANSITION_WINDOW))
+            .contains(&*view);
+        let is_supported = version == V::Base::VERSION || version == V::Upgrade::VERSION;
+
+        in_window
+            && is_supported
+            && (version == cert.data.old_version || version == cert.data.new_version)
+    }
+
     /// Serialize a message with a version number, using `message.view_number()` and an optional decided upgrade certificate to determine the message's version.
     ///
     /// # Errors
@@ -584,7 +759,7 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
             .context(info!("Failed to serialize message!"))
     }
 
-    /// Deserialize a message with a version number, using `message.view_number()` to determine the message's version. This function will fail on improperly versioned messages.
+    /// Deserialize a message with a version number, using `message.view_number()` to determine the message's version. This function will fail on improperly versioned messages, except for messages within the transition window of a decided upgrade, which may use either version.
     ///
     /// # Errors
     ///
@@ -593,10 +768,7 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
         &self,
         message: &[u8],
     ) -> Result<M> {
-        let actual_version = Version::deserialize(message)
-            .wrap()
-            .context(info!("Failed to read message version!"))?
-            .0;
+        let actual_version = Message::<TYPES>::protocol_version(message)?;
 
         let deserialized_message: M = match actual_version {
             v if v == V::Base::VERSION => Serializer::<V::Base>::deserialize(message),
@@ -608,15 +780,317 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
         .wrap()
         .context(info!("Failed to deserialize message!"))?;
 
-        let view = deserialized_message.view_number();
+        self.check_version(actual_version, deserialized_message.view_number())
+            .await?;
 
+        Ok(deserialized_message)
+    }
+
+    /// Check that a message for `view` may be encoded with `actual_version`
+    async fn check_version(&self, actual_version: Version, view: TYPES::View) -> Result<()> {
         let expected_version = self.version(view).await?;
 
         ensure!(
-            actual_version == expected_version,
+            actual_version == expected_version
+                || self.in_transition_window(actual_version, view).await,
             "Message has invalid version number for its view. Expected: {expected_version}, Actual: {actual_version}, View: {view:?}"
         );
 
-        Ok(deserialized_message)
+        Ok(())
+    }
+
+    /// Serialize `message` in `encoding`, with the version [`Self::serialize`] would use
+    ///
+    /// # Errors
+    ///
+    /// Errors if serialization fails.
+    pub async fn serialize_as(
+        &self,
+        message: &Message<TYPES>,
+        encoding: WireEncoding,
+    ) -> Result<Vec<u8>> {
+        let version = self.version(message.view_number()).await?;
+
+        encoding.encode(message, version, MAX_MESSAGE_SIZE)
+    }
+
+    /// Deserialize a message in any encoding this node accepts, checking its version as
+    /// [`Self::deserialize`] does, and return the encoding it was in along with it.
+    ///
+    /// # Errors
+    ///
+    /// Errors if deserialization fails.
+    pub async fn deserialize_message(
+        &self,
+        message: &[u8],
+    ) -> Result<(Message<TYPES>, WireEncoding)> {
+        let (_, deserialized_message, encoding) = self.decode_message(message).await?;
+
+        Ok((deserialized_message, encoding))
+    }
+
+    /// Deserialize a message as [`Self::deserialize_message`] does, also returning the version it
+    /// was encoded with
+    async fn decode_message(
+        &self,
+        message: &[u8],
+    ) -> Result<(Version, Message<TYPES>, WireEncoding)> {
+        let encoding = WireEncoding::of::<TYPES>(message);
+        let (actual_version, deserialized_message) =
+            encoding.decode::<TYPES>(message, MAX_MESSAGE_SIZE)?;
+        self.check_version(actual_version, deserialized_message.view_number())
+            .await?;
+
+        Ok((actual_version, deserialized_message, encoding))
+    }
+
+    /// Serialize `message` in `encoding` as [`Self::serialize_as`] does, and sign it with
+    /// `private_key`, which must be the key of `message.sender`
+    ///
+    /// Messages are signed from the epochs version on, with [`Message::namespace`] and
+    /// [`Message::expires_after`] between the message and the signature. Before it they are sent
+    /// unsigned and without either, as nodes of the older versions expect. A vote signed by its sender is not signed again, it
+    /// carries an empty signature instead, since the signature on the vote authenticates its
+    /// sender.
+    ///
+    /// # Errors
+    ///
+    /// Errors if serialization or signing fails.
+    pub async fn serialize_signed(
+        &self,
+        message: &Message<TYPES>,
+        encoding: WireEncoding,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    ) -> Result<Vec<u8>> {
+        let version = self.version(message.view_number()).await?;
+        let mut serialized_message = encoding.encode(message, version, MAX_MESSAGE_SIZE)?;
+
+        if version < V::Epochs::VERSION {
+            return Ok(serialized_message);
+        }
+        let expires_after = message.expires_after.map_or(NO_EXPIRY, |view| *view);
+        serialized_message.extend_from_slice(&message.namespace.to_le_bytes());
+        serialized_message.extend_from_slice(&expires_after.to_le_bytes());
+        if message.is_vote_of_sender() {
+            serialized_message.extend_from_slice(&0u32.to_le_bytes());
+            return Ok(serialized_message);
+        }
+
+        sign_message_bytes::<TYPES::SignatureKey>(private_key, serialized_message)
+    }
+
+    /// Deserialize a message sent by [`Self::serialize_signed`] as
+    /// [`Self::deserialize_message`] does, and check that it was signed by its sender, so that a
+    /// relay between us and the sender cannot forge or alter it
+    ///
+    /// The signature is checked on a blocking worker. A vote with an empty signature is returned
+    /// as [`SenderAuth::Vote`], and must not be trusted until the signature on the vote is checked.
+    /// Messages encoded with a version from before epochs are returned as [`SenderAuth::Unsigned`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if the signature is missing or not that of the sender, or if deserialization fails.
+    pub async fn deserialize_signed(
+        &self,
+        message: Bytes,
+    ) -> Result<(Message<TYPES>, WireEncoding, SenderAuth)> {
+        if let Some((signed_message, signature, deserialized_message, encoding)) =
+            self.decode_signed(&message).await
+        {
+            let Some(signature) = signature else {
+                ensure!(
+                    deserialized_message.is_vote_of_sender(),
+                    "Message is not signed by its sender {}",
+                    deserialized_message.sender
+                );
+                return Ok((deserialized_message, encoding, SenderAuth::Vote));
+            };
+
+            let sender = deserialized_message.sender.clone();
+            // Shares the received buffer rather than copying it for the worker
+            let signed_message = message.slice_ref(signed_message);
+            let valid = spawn_blocking(move || sender.validate(&signature, &signed_message))
+                .await
+                .unwrap_or(false);
+            ensure!(
+                valid,
+                "Message is not signed by its sender {}",
+                deserialized_message.sender
+            );
+            return Ok((deserialized_message, encoding, SenderAuth::Signed));
+        }
+
+        // Not signed, which only the versions from before epochs may be
+        let (version, deserialized_message, encoding) = self.decode_message(&message).await?;
+        ensure!(
+            version < V::Epochs::VERSION,
+            "Message is not signed by its sender {}",
+            deserialized_message.sender
+        );
+
+        Ok((deserialized_message, encoding, SenderAuth::Unsigned))
+    }
+
+    /// Decode a message sent by [`Self::serialize_signed`] with the epochs version or a later one,
+    /// returning the bytes its signature is over and the signature along with it, or `None` if it
+    /// is not such a message
+    #[allow(clippy::type_complexity)]
+    async fn decode_signed<'a>(
+        &self,
+        message: &'a [u8],
+    ) -> Option<(
+        &'a [u8],
+        Option<<TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType>,
+        Message<TYPES>,
+        WireEncoding,
+    )> {
+        let (signed_message, signature) =
+            split_signed_message::<TYPES::SignatureKey>(message).ok()?;
+        let (serialized_message, envelope) = signed_message
+            .len()
+            .checked_sub(2 * std::mem::size_of::<u64>())
+            .map(|at| signed_message.split_at(at))?;
+        let (namespace, expires_after) = envelope.split_at(std::mem::size_of::<u64>());
+        let (version, mut deserialized_message, encoding) =
+            self.decode_message(serialized_message).await.ok()?;
+        if version < V::Epochs::VERSION {
+            return None;
+        }
+
+        deserialized_message.namespace = u64::from_le_bytes(namespace.try_into().ok()?);
+        let expires_after = u64::from_le_bytes(expires_after.try_into().ok()?);
+        deserialized_message.expires_after =
+            (expires_after != NO_EXPIRY).then(|| TYPES::View::new(expires_after));
+        Some((signed_message, signature, deserialized_message, encoding))
+    }
+}
+
+/// The expiry [`UpgradeLock::serialize_signed`] sends for a message which never expires
+const NO_EXPIRY: u64 = u64::MAX;
+
+/// How the sender of a message returned by [`UpgradeLock::deserialize_signed`] was authenticated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SenderAuth {
+    /// The message is encoded with a version from before epochs, whose messages are not signed
+    Unsigned,
+    /// The message is signed by its sender
+    Signed,
+    /// The message is a vote of its sender, which only the signature on the vote authenticates
+    Vote,
+}
+
+/// Append the signature of `message` with `private_key` to it, followed by the length of the
+/// signature as a little endian `u32`
+///
+/// # Errors
+///
+/// Errors if signing fails.
+pub fn sign_message_bytes<K: SignatureKey>(
+    private_key: &K::PrivateKey,
+    mut message: Vec<u8>,
+) -> Result<Vec<u8>> {
+    let signature = K::sign(private_key, &message)
+        .wrap()
+        .context(info!("Failed to sign message!"))?;
+    let signature = bincode::serialize(&signature)
+        .wrap()
+        .context(info!("Failed to serialize message signature!"))?;
+    let length = u32::try_from(signature.len())
+        .wrap()
+        .context(info!("Message signature is too long!"))?;
+
+    message.extend_from_slice(&signature);
+    message.extend_from_slice(&length.to_le_bytes());
+    Ok(message)
+}
+
+/// Split a message signed by [`sign_message_bytes`] into the message and its signature, which is
+/// `None` if the signature is empty
+///
+/// # Errors
+///
+/// Errors if the message does not end in a signature.
+pub fn split_signed_message<K: SignatureKey>(
+    signed: &[u8],
+) -> Result<(&[u8], Option<K::PureAssembledSignatureType>)> {
+    ensure!(signed.len() >= 4, "Message is too short to be signed");
+    let (rest, length) = signed.split_at(signed.len() - 4);
+    let length: [u8; 4] = length
+        .try_into()
+        .wrap()
+        .context(info!("Message signature length is not 4 bytes"))?;
+    let length = usize::try_from(u32::from_le_bytes(length))
+        .wrap()
+        .context(info!("Message signature length does not fit in memory"))?;
+    ensure!(
+        length <= rest.len(),
+        "Message is shorter than its signature length {length}"
+    );
+
+    let (message, signature) = rest.split_at(rest.len() - length);
+    if signature.is_empty() {
+        return Ok((message, None));
+    }
+    let signature = bincode::deserialize(signature)
+        .wrap()
+        .context(info!("Failed to deserialize message signature"))?;
+    Ok((message, Some(signature)))
+}
+
+/// An encoding messages can be sent in, each implemented by a [`WireCodec`](crate::codec::WireCodec)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WireEncoding {
+    /// The versioned bincode encoding every node understands
+    #[default]
+    Bincode,
+    /// The CBOR encoding of [`CborCodec`](crate::codec::CborCodec)
+    Cbor,
+    /// The protobuf encoding of `proto/wire.proto`
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+/// The encoding each peer wants direct messages in, which is the encoding of the last message it
+/// sent us
+///
+/// Only peers which asked for something else than bincode are kept track of. From epochs on, the
+/// encoding is recorded only from messages verified to be signed by their sender, so no peer can
+/// choose the encoding another is sent.
+#[derive(Clone, Debug)]
+pub struct WireEncodings<K: SignatureKey> {
+    /// the peers which do not want bincode, and what they want instead
+    encodings: Arc<RwLock<HashMap<K, WireEncoding>>>,
+}
+
+impl<K: SignatureKey> Default for WireEncodings<K> {
+    fn default() -> Self {
+        Self {
+            encodings: Arc::default(),
+        }
+    }
+}
+
+impl<K: SignatureKey> WireEncodings<K> {
+    /// Record that `peer` sent us a message in `encoding`
+    pub async fn record(&self, peer: &K, encoding: WireEncoding) {
+        if self.of(peer).await == encoding {
+            return;
+        }
+        let mut encodings = self.encodings.write().await;
+        if encoding == WireEncoding::Bincode {
+            encodings.remove(peer);
+        } else {
+            encodings.insert(peer.clone(), encoding);
+        }
+    }
+
+    /// The encoding `peer` wants direct messages in
+    pub async fn of(&self, peer: &K) -> WireEncoding {
+        self.encodings
+            .read()
+            .await
+            .get(peer)
+            .copied()
+            .unwrap_or_default()
     }
 }
@@ -0,0 +1,523 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Validator keys held jointly by several operators.
+//!
+//! A [`MultisigPubKey`] is a policy of `threshold` out of a set of BLS member keys. A signature of
+//! the composite key is the aggregate BLS signature of at least `threshold` members, together with
+//! a bitmap of the members who signed, so it is checked like a single BLS multi-signature. The key
+//! implements [`SignatureKey`], so a network can use it wherever it would use a [`BLSPubKey`],
+//! quorum certificates included.
+//!
+//! The shares of a [`MultisigPrivKey`] are either held locally as BLS private keys, or by remote
+//! [`ShareSigner`]s such as an HSM or a co-signing service. Remote signers are not part of the
+//! serialized private key and must be attached again after loading it.
+//!
+//! As for the stake table, member keys are trusted to be honestly generated: whoever sets up a
+//! policy must have each member prove possession of its key, otherwise a member could choose a
+//! key cancelling out the keys of the others and sign on their behalf.
+
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use bitvec::{slice::BitSlice, vec::BitVec};
+use jf_signature::{
+    bls_over_bn254::BLSOverBN254CurveSignatureScheme, AggregateableSignatureSchemes,
+    SignatureError, SignatureScheme,
+};
+use primitive_types::U256;
+use tagged_base64::{tagged, TaggedBase64, Tb64Error};
+use tracing::warn;
+
+use crate::{
+    qc::QcParams,
+    signature_key::{BLSPrivKey, BLSPubKey},
+    stake_table::StakeTableEntry,
+    traits::signature_key::{PrivateSignatureKey, SignatureKey},
+};
+
+/// BLS signature of a single member of a multisig key
+pub type BLSSignature = <BLSOverBN254CurveSignatureScheme as SignatureScheme>::Signature;
+
+/// Tag of a [`MultisigPrivKey`] in tagged base64
+const PRIV_KEY_TAG: &str = "MSIG_SIGNING_KEY";
+
+/// Number of members of the keys made by [`SignatureKey::generated_from_seed_indexed`]
+const GENERATED_MEMBERS: u64 = 3;
+
+/// Threshold of the keys made by [`SignatureKey::generated_from_seed_indexed`]
+const GENERATED_THRESHOLD: usize = 2;
+
+/// A public key requiring the signatures of `threshold` of its members
+#[tagged("MSIG_VER_KEY")]
+#[derive(
+    Clone, Debug, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct MultisigPubKey {
+    /// how many members must sign
+    threshold: u32,
+    /// the keys of the members, sorted
+    members: Vec<BLSPubKey>,
+}
+
+impl MultisigPubKey {
+    /// A key requiring the signatures of `threshold` of `members`
+    ///
+    /// # Errors
+    /// if a member is repeated, or `threshold` is zero or more than the number of members
+    pub fn new(threshold: usize, mut members: Vec<BLSPubKey>) -> Result<Self, SignatureError> {
+        members.sort();
+        if members.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(SignatureError::ParameterError(
+                "a multisig key member is repeated".to_string(),
+            ));
+        }
+        if threshold == 0 || threshold > members.len() {
+            return Err(SignatureError::ParameterError(format!(
+                "threshold {threshold} is not between 1 and the number of members {}",
+                members.len()
+            )));
+        }
+        let threshold = u32::try_from(threshold).map_err(|_| {
+            SignatureError::ParameterError(format!("threshold {threshold} is too large"))
+        })?;
+        Ok(Self { threshold, members })
+    }
+
+    /// How many members must sign
+    #[must_use]
+    pub fn threshold(&self) -> usize {
+        self.threshold as usize
+    }
+
+    /// The keys of the members, sorted
+    #[must_use]
+    pub fn members(&self) -> &[BLSPubKey] {
+        &self.members
+    }
+
+    /// Whether the key could have been made by [`new`](Self::new), which keys decoded from an
+    /// untrusted source need not be
+    fn is_well_formed(&self) -> bool {
+        self.threshold != 0
+            && self.threshold() <= self.members.len()
+            && self.members.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    /// The keys of the members marked in `signers`, if they are enough to meet the threshold
+    fn signing_members(&self, signers: &[bool]) -> Option<Vec<BLSPubKey>> {
+        if !self.is_well_formed() || signers.len() != self.members.len() {
+            return None;
+        }
+        let members: Vec<_> = self
+            .members
+            .iter()
+            .zip(signers)
+            .filter(|(_, signed)| **signed)
+            .map(|(member, _)| *member)
+            .collect();
+        (members.len() >= self.threshold()).then_some(members)
+    }
+}
+
+/// A signature of a [`MultisigPubKey`]
+///
+/// In a quorum certificate, `signers` holds the member bitmaps of all signing validators one after
+/// the other, in stake table order.
+#[tagged("MSIG_SIG")]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq, Hash)]
+pub struct MultisigSignature {
+    /// the aggregate signature of the members who signed
+    pub signature: BLSSignature,
+    /// which members signed
+    pub signers: Vec<bool>,
+}
+
+/// Aggregate BLS signatures, which does not involve the keys
+fn aggregate(signatures: &[BLSSignature]) -> Result<BLSSignature, SignatureError> {
+    BLSOverBN254CurveSignatureScheme::aggregate(&(), &[], signatures)
+}
+
+/// A signer of one member of a multisig key
+pub trait ShareSigner: Send + Sync + Debug {
+    /// The key of the member
+    fn public_key(&self) -> BLSPubKey;
+
+    /// Sign `data` as the member
+    ///
+    /// # Errors
+    /// if the signer is unavailable or refuses to sign
+    fn sign_share(&self, data: &[u8]) -> Result<BLSSignature, SignatureError>;
+}
+
+impl ShareSigner for BLSPrivKey {
+    fn public_key(&self) -> BLSPubKey {
+        BLSPubKey::from(self)
+    }
+
+    fn sign_share(&self, data: &[u8]) -> Result<BLSSignature, SignatureError> {
+        BLSPubKey::sign(self, data)
+    }
+}
+
+/// The private half of a [`MultisigPubKey`]: the signers of the members this node can reach
+#[derive(Clone, Debug)]
+pub struct MultisigPrivKey {
+    /// the composite key
+    public_key: MultisigPubKey,
+    /// the private keys of members held by this node
+    local_shares: Vec<BLSPrivKey>,
+    /// the signers of members held elsewhere
+    remote_shares: Vec<Arc<dyn ShareSigner>>,
+}
+
+impl MultisigPrivKey {
+    /// The private key of `public_key` holding the private keys of some of its members
+    ///
+    /// # Errors
+    /// if a share is not the key of a member
+    pub fn new(
+        public_key: MultisigPubKey,
+        local_shares: Vec<BLSPrivKey>,
+    ) -> Result<Self, SignatureError> {
+        let mut key = Self {
+            public_key,
+            local_shares: Vec::new(),
+            remote_shares: Vec::new(),
+        };
+        for share in local_shares {
+            key.check_member(&share)?;
+            key.local_shares.push(share);
+        }
+        Ok(key)
+    }
+
+    /// Sign the share of a member with `signer`, which is asked after the local shares
+    ///
+    /// # Errors
+    /// if `signer` is not for a member
+    pub fn with_remote_signer(
+        mut self,
+        signer: Arc<dyn ShareSigner>,
+    ) -> Result<Self, SignatureError> {
+        self.check_member(signer.as_ref())?;
+        self.remote_shares.push(signer);
+        Ok(self)
+    }
+
+    /// The composite key
+    #[must_use]
+    pub fn public_key(&self) -> &MultisigPubKey {
+        &self.public_key
+    }
+
+    /// Check that `signer` signs for a member
+    fn check_member(&self, signer: &dyn ShareSigner) -> Result<(), SignatureError> {
+        let member = signer.public_key();
+        if self.public_key.members.contains(&member) {
+            Ok(())
+        } else {
+            Err(SignatureError::ParameterError(format!(
+                "{member} is not a member of the multisig key"
+            )))
+        }
+    }
+
+    /// Every signer of the key, local ones first
+    fn signers(&self) -> impl Iterator<Item = &dyn ShareSigner> {
+        self.local_shares
+            .iter()
+            .map(|share| share as &dyn ShareSigner)
+            .chain(self.remote_shares.iter().map(AsRef::as_ref))
+    }
+}
+
+/// Keys are the same if they hold the same local shares and reach the same remote members
+impl PartialEq for MultisigPrivKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.public_key == other.public_key
+            && self.local_shares == other.local_shares
+            && self
+                .remote_shares
+                .iter()
+                .map(|signer| signer.public_key())
+                .eq(other.remote_shares.iter().map(|signer| signer.public_key()))
+    }
+}
+
+impl Eq for MultisigPrivKey {}
+
+impl Hash for MultisigPrivKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.public_key.hash(state);
+        self.local_shares.hash(state);
+        for signer in &self.remote_shares {
+            signer.public_key().hash(state);
+        }
+    }
+}
+
+impl PrivateSignatureKey for MultisigPrivKey {
+    /// Only the local shares are serialized
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.public_key
+            .serialize_compressed(&mut bytes)
+            .expect("Serialization should not fail.");
+        self.local_shares
+            .serialize_compressed(&mut bytes)
+            .expect("Serialization should not fail.");
+        bytes
+    }
+
+    fn from_bytes(mut bytes: &[u8]) -> anyhow::Result<Self> {
+        let public_key = MultisigPubKey::deserialize_compressed(&mut bytes)?;
+        let local_shares = Vec::<BLSPrivKey>::deserialize_compressed(&mut bytes)?;
+        anyhow::ensure!(public_key.is_well_formed(), "Malformed multisig key policy");
+        Ok(Self::new(public_key, local_shares)?)
+    }
+
+    fn to_tagged_base64(&self) -> Result<TaggedBase64, Tb64Error> {
+        TaggedBase64::new(PRIV_KEY_TAG, &self.to_bytes())
+    }
+}
+
+impl TryFrom<&TaggedBase64> for MultisigPrivKey {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TaggedBase64) -> Result<Self, Self::Error> {
+        anyhow::ensure!(
+            value.tag() == PRIV_KEY_TAG,
+            "Expected tag {PRIV_KEY_TAG}, found {}",
+            value.tag()
+        );
+        Self::from_bytes(&value.value())
+    }
+}
+
+impl SignatureKey for MultisigPubKey {
+    type PrivateKey = MultisigPrivKey;
+    type StakeTableEntry = StakeTableEntry<Self>;
+    type QcParams = QcParams<Self, ()>;
+    type PureAssembledSignatureType = MultisigSignature;
+    type QcType = (MultisigSignature, BitVec);
+    type SignError = SignatureError;
+
+    fn validate(&self, signature: &Self::PureAssembledSignatureType, data: &[u8]) -> bool {
+        self.signing_members(&signature.signers)
+            .is_some_and(|members| {
+                BLSOverBN254CurveSignatureScheme::multi_sig_verify(
+                    &(),
+                    &members,
+                    data,
+                    &signature.signature,
+                )
+                .is_ok()
+            })
+    }
+
+    /// Collect shares from the signers of the members in order until the threshold is met,
+    /// skipping signers which fail or return an invalid share
+    fn sign(
+        private_key: &Self::PrivateKey,
+        data: &[u8],
+    ) -> Result<Self::PureAssembledSignatureType, Self::SignError> {
+        let public_key = &private_key.public_key;
+        let mut signers = vec![false; public_key.members.len()];
+        let mut shares = vec![];
+        for (member, signed) in public_key.members.iter().zip(&mut signers) {
+            if shares.len() == public_key.threshold() {
+                break;
+            }
+            let Some(signer) = private_key
+                .signers()
+                .find(|signer| signer.public_key() == *member)
+            else {
+                continue;
+            };
+            match signer.sign_share(data) {
+                Ok(share) if member.validate(&share, data) => {
+                    *signed = true;
+                    shares.push(share);
+                }
+                Ok(_) => warn!("Member {member} of a multisig key returned an invalid share"),
+                Err(e) => warn!("Member {member} of a multisig key failed to sign: {e}"),
+            }
+        }
+        if shares.len() < public_key.threshold() {
+            return Err(SignatureError::ParameterError(format!(
+                "only {} of the {} shares required by the multisig key were signed",
+                shares.len(),
+                public_key.threshold()
+            )));
+        }
+        Ok(MultisigSignature {
+            signature: aggregate(&shares)?,
+            signers,
+        })
+    }
+
+    fn from_private(private_key: &Self::PrivateKey) -> Self {
+        private_key.public_key.clone()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.serialize_compressed(&mut buf)
+            .expect("Serialization should not fail.");
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let key = Self::deserialize_compressed(bytes)?;
+        if key.is_well_formed() {
+            Ok(key)
+        } else {
+            Err(SerializationError::InvalidData)
+        }
+    }
+
+    /// A 2-of-3 key whose private key holds every share
+    fn generated_from_seed_indexed(seed: [u8; 32], index: u64) -> (Self, Self::PrivateKey) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&seed);
+        hasher.update(&index.to_le_bytes());
+        let member_seed = *hasher.finalize().as_bytes();
+        let (members, shares): (Vec<_>, Vec<_>) = (0..GENERATED_MEMBERS)
+            .map(|member| BLSPubKey::generated_from_seed_indexed(member_seed, member))
+            .unzip();
+        let public_key =
+            Self::new(GENERATED_THRESHOLD, members).expect("Generated members should be distinct.");
+        let private_key = MultisigPrivKey::new(public_key.clone(), shares)
+            .expect("Generated shares should be members.");
+        (public_key, private_key)
+    }
+
+    fn stake_table_entry(&self, stake: u64) -> Self::StakeTableEntry {
+        StakeTableEntry {
+            stake_key: self.clone(),
+            stake_amount: U256::from(stake),
+        }
+    }
+
+    fn public_key(entry: &Self::StakeTableEntry) -> Self {
+        entry.stake_key.clone()
+    }
+
+    fn public_parameter(
+        stake_entries: Vec<Self::StakeTableEntry>,
+        threshold: U256,
+    ) -> Self::QcParams {
+        QcParams {
+            stake_entries,
+            threshold,
+            agg_sig_pp: (),
+        }
+    }
+
+    /// Check the stake of the signing validators, that each of them meets its own policy, and the
+    /// aggregate signature against every signing member
+    fn check(real_qc_pp: &Self::QcParams, data: &[u8], qc: &Self::QcType) -> bool {
+        let (signature, signers) = qc;
+        if signers.len() != real_qc_pp.stake_entries.len() {
+            return false;
+        }
+        let mut total_weight = U256::zero();
+        let mut members = vec![];
+        let mut offset = 0;
+        for (entry, signed) in real_qc_pp.stake_entries.iter().zip(signers.iter()) {
+            if !*signed {
+                continue;
+            }
+            total_weight += entry.stake_amount;
+            let key = &entry.stake_key;
+            let Some(key_signers) = signature.signers.get(offset..offset + key.members.len())
+            else {
+                return false;
+            };
+            let Some(key_members) = key.signing_members(key_signers) else {
+                return false;
+            };
+            members.extend(key_members);
+            offset += key.members.len();
+        }
+        offset == signature.signers.len()
+            && total_weight >= real_qc_pp.threshold
+            && BLSOverBN254CurveSignatureScheme::multi_sig_verify(
+                &(),
+                &members,
+                data,
+                &signature.signature,
+            )
+            .is_ok()
+    }
+
+    fn sig_proof(signature: &Self::QcType) -> (Self::PureAssembledSignatureType, BitVec) {
+        signature.clone()
+    }
+
+    fn assemble(
+        real_qc_pp: &Self::QcParams,
+        signers: &BitSlice,
+        sigs: &[Self::PureAssembledSignatureType],
+    ) -> Result<Self::QcType, Self::SignError> {
+        if signers.len() != real_qc_pp.stake_entries.len() {
+            return Err(SignatureError::ParameterError(format!(
+                "bit vector len {} != the number of stake entries {}",
+                signers.len(),
+                real_qc_pp.stake_entries.len(),
+            )));
+        }
+        let signing: Vec<_> = real_qc_pp
+            .stake_entries
+            .iter()
+            .zip(signers.iter())
+            .filter(|(_, signed)| **signed)
+            .map(|(entry, _)| entry)
+            .collect();
+        let total_weight = signing
+            .iter()
+            .fold(U256::zero(), |acc, entry| acc + entry.stake_amount);
+        if total_weight < real_qc_pp.threshold {
+            return Err(SignatureError::ParameterError(format!(
+                "total_weight {} less than threshold {}",
+                total_weight, real_qc_pp.threshold,
+            )));
+        }
+        if signing.len() != sigs.len() {
+            return Err(SignatureError::ParameterError(format!(
+                "the number of signers {} != the number of partial signatures {}",
+                signing.len(),
+                sigs.len(),
+            )));
+        }
+
+        let mut members = vec![];
+        for (entry, sig) in signing.iter().zip(sigs) {
+            if entry.stake_key.signing_members(&sig.signers).is_none() {
+                return Err(SignatureError::ParameterError(format!(
+                    "the signature of {} does not meet its policy",
+                    entry.stake_key
+                )));
+            }
+            members.extend_from_slice(&sig.signers);
+        }
+        let shares: Vec<_> = sigs.iter().map(|sig| sig.signature.clone()).collect();
+        let signature = MultisigSignature {
+            signature: aggregate(&shares)?,
+            signers: members,
+        };
+        Ok((signature, signers.into()))
+    }
+
+    fn genesis_proposer_pk() -> Self {
+        Self::generated_from_seed_indexed([0u8; 32], 0).0
+    }
+}
@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{fs, ops::Range, path::Path, time::Duration, vec};
+use std::{collections::HashMap, fs, ops::Range, path::Path, time::Duration, vec};
 
 use clap::ValueEnum;
 use libp2p_identity::PeerId;
@@ -18,6 +18,7 @@ use crate::{
         ORCHESTRATOR_DEFAULT_NUM_ROUNDS, ORCHESTRATOR_DEFAULT_TRANSACTIONS_PER_ROUND,
         ORCHESTRATOR_DEFAULT_TRANSACTION_SIZE, REQUEST_DATA_DELAY,
     },
+    error::HotShotConfigError,
     hotshot_config_file::HotShotConfigFile,
     light_client::StateVerKey,
     traits::signature_key::SignatureKey,
@@ -29,6 +30,27 @@ use crate::{
 pub struct Libp2pConfig {
     /// The bootstrap nodes to connect to (multiaddress, serialized public key)
     pub bootstrap_nodes: Vec<(PeerId, Multiaddr)>,
+    /// The most peers to stay connected to, preferring those with the lowest round trip times
+    /// while keeping some in every region; unlimited if unset
+    #[serde(default)]
+    pub max_connected_peers: Option<usize>,
+    /// The regions of the peers whose region is known, by which peers are kept spread out
+    #[serde(default)]
+    pub peer_regions: HashMap<PeerId, String>,
+    /// The file to keep known peers and their reputation in across restarts; peers are only
+    /// remembered until shutdown if unset
+    #[serde(default)]
+    pub peer_store_file_path: Option<String>,
+    /// The timeout for connecting to a peer; the libp2p default if unset
+    #[serde(default)]
+    pub connect_timeout: Option<Duration>,
+    /// The timeout for the authentication handshake on a new connection; the libp2p default if
+    /// unset
+    #[serde(default)]
+    pub handshake_timeout: Option<Duration>,
+    /// The timeout for a newly connected peer to identify itself; the libp2p default if unset
+    #[serde(default)]
+    pub identify_timeout: Option<Duration>,
 }
 
 /// configuration for combined network
@@ -56,6 +78,12 @@ pub enum NetworkConfigError {
     /// Failed to recursively create path to NetworkConfig
     #[error("Failed to recursively create path to NetworkConfig")]
     FailedToCreatePath(std::io::Error),
+    /// Failed to parse a NetworkConfigFile
+    #[error("Failed to parse NetworkConfigFile: {0}")]
+    ParseError(toml::de::Error),
+    /// The loaded config is inconsistent
+    #[error("Invalid config: {0}")]
+    InvalidConfig(HotShotConfigError),
 }
 
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, Default, ValueEnum)]
@@ -155,6 +183,9 @@ pub struct NetworkConfig<KEY: SignatureKey> {
     pub random_builder: Option<RandomBuilderConfig>,
     /// The list of public keys that are allowed to connect to the orchestrator
     pub public_keys: Vec<PeerConfigKeys<KEY>>,
+    /// how long after all nodes are ready the run starts, so that nodes learning of the start
+    /// at different times still start together
+    pub start_delay: Duration,
 }
 
 /// the source of the network config
@@ -291,6 +322,7 @@ impl<K: SignatureKey> Default for NetworkConfig<K> {
             builder: BuilderType::default(),
             random_builder: None,
             public_keys: vec![],
+            start_delay: Duration::ZERO,
         }
     }
 }
@@ -354,6 +386,27 @@ pub struct NetworkConfigFile<KEY: SignatureKey> {
     /// If nonempty, this list becomes the stake table and is used to determine DA membership (ignoring the node's request).
     #[serde(default)]
     pub public_keys: Vec<PeerConfigKeys<KEY>>,
+    /// how long after all nodes are ready the run starts
+    #[serde(default)]
+    pub start_delay: Duration,
+}
+
+impl<K: SignatureKey> NetworkConfigFile<K> {
+    /// Loads and validates a `NetworkConfigFile` from the TOML file at `path`
+    ///
+    /// Fields missing from the file take their defaults.
+    ///
+    /// # Errors
+    /// if the file cannot be read or parsed, or the config is inconsistent
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, NetworkConfigError> {
+        let contents = fs::read_to_string(path).map_err(NetworkConfigError::ReadFromFileError)?;
+        let config: Self = toml::from_str(&contents).map_err(NetworkConfigError::ParseError)?;
+        config
+            .config
+            .validate()
+            .map_err(NetworkConfigError::InvalidConfig)?;
+        Ok(config)
+    }
 }
 
 impl<K: SignatureKey> From<NetworkConfigFile<K>> for NetworkConfig<K> {
@@ -376,6 +429,12 @@ impl<K: SignatureKey> From<NetworkConfigFile<K>> for NetworkConfig<K> {
             transaction_size: val.transaction_size,
             libp2p_config: Some(Libp2pConfig {
                 bootstrap_nodes: Vec::new(),
+                max_connected_peers: None,
+                peer_regions: HashMap::new(),
+                peer_store_file_path: None,
+                connect_timeout: None,
+                handshake_timeout: None,
+                identify_timeout: None,
             }),
             config: val.config.into(),
             key_type_name: std::any::type_name::<K>().to_string(),
@@ -385,6 +444,7 @@ impl<K: SignatureKey> From<NetworkConfigFile<K>> for NetworkConfig<K> {
             builder: val.builder,
             random_builder: val.random_builder,
             public_keys: val.public_keys,
+            start_delay: val.start_delay,
         }
     }
 }
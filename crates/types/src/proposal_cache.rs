@@ -0,0 +1,96 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A cache of the quorum proposals and block payloads a node saw recently, by commitment.
+//!
+//! [`Consensus`](crate::consensus::Consensus) keeps proposals and payloads by view, and drops
+//! them when it collects garbage. The cache outlives that, so that a node catching up, or asked
+//! for the data of a view again, can find them without going to storage or the network.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use lru::LruCache;
+
+use crate::{
+    constants::{PAYLOAD_CACHE_CAPACITY, PROPOSAL_CACHE_CAPACITY},
+    data::QuorumProposal2,
+    message::Proposal,
+    traits::node_implementation::NodeType,
+    utils::LeafCommitment,
+    vid::VidCommitment,
+};
+
+/// Recently seen quorum proposals and block payloads, evicting the least recently used ones
+#[derive(derive_more::Debug, Clone)]
+pub struct ProposalCache<TYPES: NodeType> {
+    /// the quorum proposals, by the commitment of the leaf they propose
+    #[debug(skip)]
+    proposals: Arc<Mutex<LruCache<LeafCommitment<TYPES>, Proposal<TYPES, QuorumProposal2<TYPES>>>>>,
+
+    /// the encoded transactions of block payloads, by payload commitment
+    #[debug(skip)]
+    payloads: Arc<Mutex<LruCache<VidCommitment, Arc<[u8]>>>>,
+}
+
+impl<TYPES: NodeType> Default for ProposalCache<TYPES> {
+    fn default() -> Self {
+        Self::new(PROPOSAL_CACHE_CAPACITY, PAYLOAD_CACHE_CAPACITY)
+    }
+}
+
+/// Lock `mutex`, even if a thread panicked while holding it, since a cache is never left
+/// inconsistent
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<TYPES: NodeType> ProposalCache<TYPES> {
+    /// A cache of up to `proposals` quorum proposals and `payloads` block payloads, each at
+    /// least one
+    #[must_use]
+    pub fn new(proposals: usize, payloads: usize) -> Self {
+        let capacity = |capacity: usize| NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            proposals: Arc::new(Mutex::new(LruCache::new(capacity(proposals)))),
+            payloads: Arc::new(Mutex::new(LruCache::new(capacity(payloads)))),
+        }
+    }
+
+    /// Remember `proposal`, proposing the leaf with commitment `leaf_commit`
+    pub fn insert_proposal(
+        &self,
+        leaf_commit: LeafCommitment<TYPES>,
+        proposal: Proposal<TYPES, QuorumProposal2<TYPES>>,
+    ) {
+        lock(&self.proposals).put(leaf_commit, proposal);
+    }
+
+    /// The proposal of the leaf with commitment `leaf_commit`, if it is cached
+    pub fn proposal(
+        &self,
+        leaf_commit: &LeafCommitment<TYPES>,
+    ) -> Option<Proposal<TYPES, QuorumProposal2<TYPES>>> {
+        lock(&self.proposals).get(leaf_commit).cloned()
+    }
+
+    /// Remember the encoded transactions of the payload with commitment `payload_commitment`
+    pub fn insert_payload(
+        &self,
+        payload_commitment: VidCommitment,
+        encoded_transactions: Arc<[u8]>,
+    ) {
+        lock(&self.payloads).put(payload_commitment, encoded_transactions);
+    }
+
+    /// The encoded transactions of the payload with commitment `payload_commitment`, if they are
+    /// cached
+    pub fn payload(&self, payload_commitment: &VidCommitment) -> Option<Arc<[u8]>> {
+        lock(&self.payloads).get(payload_commitment).map(Arc::clone)
+    }
+}
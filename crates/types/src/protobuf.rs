@@ -0,0 +1,208 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! The protobuf encoding of [`Message`], for gateways and monitoring proxies which cannot parse
+//! bincode.
+//!
+//! The schema is `proto/wire.proto`. An encoded message starts with [`MAGIC`], which no bincode
+//! message starts with, so a node accepts either encoding from any peer. Once a peer sent us a
+//! message in protobuf, we send it direct messages in protobuf as well, see
+//! [`WireEncodings`](crate::message::WireEncodings). Broadcasts are always in bincode.
+//!
+//! Besides the header fields, the message kind is carried as JSON. Proposals, votes and
+//! certificates are also summarized in typed fields, so that tooling can route and count them
+//! without parsing the JSON.
+
+use committable::Committable;
+use prost::Message as _;
+use tagged_base64::TaggedBase64;
+use utils::anytrace::*;
+use vbs::version::Version;
+
+use self::proto::message::Summary;
+use crate::{
+    message::{
+        DaConsensusMessage, GeneralConsensusMessage, Message, MessageKind, SequencingMessage,
+    },
+    simple_certificate::{SimpleCertificate, Threshold},
+    simple_vote::{SimpleVote, Voteable},
+    traits::node_implementation::NodeType,
+    vote::HasViewNumber,
+};
+
+/// The messages generated from `proto/wire.proto`
+#[allow(clippy::all, clippy::pedantic, missing_docs)]
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/hotshot.wire.v1.rs"));
+}
+
+/// The bytes every protobuf encoded message starts with
+pub const MAGIC: &[u8; 4] = b"HSPB";
+
+/// Whether `message` is in the protobuf encoding
+#[must_use]
+pub fn is_protobuf(message: &[u8]) -> bool {
+    message.starts_with(MAGIC)
+}
+
+/// The summary of a proposal of kind `kind`
+fn proposal(kind: &str) -> Summary {
+    Summary::Proposal(proto::ProposalSummary {
+        kind: kind.to_string(),
+    })
+}
+
+/// The summary of a vote of kind `kind`
+fn vote<TYPES: NodeType, DATA: Voteable<TYPES>>(
+    kind: &str,
+    vote: &SimpleVote<TYPES, DATA>,
+) -> Summary {
+    Summary::Vote(proto::VoteSummary {
+        kind: kind.to_string(),
+        signing_key: vote.signature.0.to_string(),
+        data_commitment: vote.data.commit().to_string(),
+    })
+}
+
+/// The summary of a certificate of kind `kind`
+fn certificate<TYPES: NodeType, DATA: Voteable<TYPES>, THRESHOLD: Threshold<TYPES>>(
+    kind: &str,
+    certificate: &SimpleCertificate<TYPES, DATA, THRESHOLD>,
+) -> Summary {
+    Summary::Certificate(proto::CertificateSummary {
+        kind: kind.to_string(),
+        data_commitment: certificate.data.commit().to_string(),
+    })
+}
+
+/// The summary of `kind`, if it is a proposal, vote or certificate
+fn summary<TYPES: NodeType>(kind: &MessageKind<TYPES>) -> Option<Summary> {
+    let MessageKind::Consensus(message) = kind else {
+        return None;
+    };
+
+    Some(match message {
+        SequencingMessage::General(message) => match message {
+            GeneralConsensusMessage::Proposal(_) => proposal("Proposal"),
+            GeneralConsensusMessage::Proposal2(_) => proposal("Proposal2"),
+            GeneralConsensusMessage::ProposalResponse(_) => proposal("ProposalResponse"),
+            GeneralConsensusMessage::ProposalResponse2(_) => proposal("ProposalResponse2"),
+            GeneralConsensusMessage::UpgradeProposal(_) => proposal("UpgradeProposal"),
+            GeneralConsensusMessage::Vote(v) => vote("Vote", v),
+            GeneralConsensusMessage::Vote2(v) => vote("Vote2", v),
+            GeneralConsensusMessage::ViewSyncPreCommitVote(v) => vote("ViewSyncPreCommitVote", v),
+            GeneralConsensusMessage::ViewSyncPreCommitVote2(v) => vote("ViewSyncPreCommitVote2", v),
+            GeneralConsensusMessage::ViewSyncCommitVote(v) => vote("ViewSyncCommitVote", v),
+            GeneralConsensusMessage::ViewSyncCommitVote2(v) => vote("ViewSyncCommitVote2", v),
+            GeneralConsensusMessage::ViewSyncFinalizeVote(v) => vote("ViewSyncFinalizeVote", v),
+            GeneralConsensusMessage::ViewSyncFinalizeVote2(v) => vote("ViewSyncFinalizeVote2", v),
+            GeneralConsensusMessage::TimeoutVote(v) => vote("TimeoutVote", v),
+            GeneralConsensusMessage::TimeoutVote2(v) => vote("TimeoutVote2", v),
+            GeneralConsensusMessage::UpgradeVote(v) => vote("UpgradeVote", v),
+            GeneralConsensusMessage::CheckpointVote(v) => vote("CheckpointVote", v),
+            GeneralConsensusMessage::AttestationRequest(r) => vote("AttestationRequest", &r.vote),
+            GeneralConsensusMessage::AttestationVote(v) => vote("AttestationVote", v),
+            GeneralConsensusMessage::ViewSyncPreCommitCertificate(c) => {
+                certificate("ViewSyncPreCommitCertificate", c)
+            }
+            GeneralConsensusMessage::ViewSyncPreCommitCertificate2(c) => {
+                certificate("ViewSyncPreCommitCertificate2", c)
+            }
+            GeneralConsensusMessage::ViewSyncCommitCertificate(c) => {
+                certificate("ViewSyncCommitCertificate", c)
+            }
+            GeneralConsensusMessage::ViewSyncCommitCertificate2(c) => {
+                certificate("ViewSyncCommitCertificate2", c)
+            }
+            GeneralConsensusMessage::ViewSyncFinalizeCertificate(c) => {
+                certificate("ViewSyncFinalizeCertificate", c)
+            }
+            GeneralConsensusMessage::ViewSyncFinalizeCertificate2(c) => {
+                certificate("ViewSyncFinalizeCertificate2", c)
+            }
+            GeneralConsensusMessage::HighQc(c) => certificate("HighQc", c),
+            GeneralConsensusMessage::HighQc2(h) => certificate("HighQc2", &h.qc),
+            GeneralConsensusMessage::ProposalRequested(..) => return None,
+        },
+        SequencingMessage::Da(message) => match message {
+            DaConsensusMessage::DaProposal(_) => proposal("DaProposal"),
+            DaConsensusMessage::DaProposal2(_) => proposal("DaProposal2"),
+            DaConsensusMessage::VidDisperseMsg(_) => proposal("VidDisperseMsg"),
+            DaConsensusMessage::VidDisperseMsg2(_) => proposal("VidDisperseMsg2"),
+            DaConsensusMessage::DaChunk(_) => proposal("DaChunk"),
+            DaConsensusMessage::DaVote(v) => vote("DaVote", v),
+            DaConsensusMessage::DaVote2(v) => vote("DaVote2", v),
+            DaConsensusMessage::DaCertificate(c) => certificate("DaCertificate", c),
+            DaConsensusMessage::DaCertificate2(c) => certificate("DaCertificate2", c),
+        },
+    })
+}
+
+/// Encode `message` in protobuf for `version`, preceded by [`MAGIC`]
+///
+/// # Errors
+/// if the message kind cannot be encoded as JSON
+pub fn encode<TYPES: NodeType>(message: &Message<TYPES>, version: Version) -> Result<Vec<u8>> {
+    let encoded = proto::Message {
+        version_major: version.major.into(),
+        version_minor: version.minor.into(),
+        sender: message.sender.to_string(),
+        namespace: message.namespace,
+        view: *message.view_number(),
+        kind_json: serde_json::to_string(&message.kind)
+            .wrap()
+            .context(info!("Failed to encode the message kind as JSON"))?,
+        summary: summary(&message.kind),
+    };
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + encoded.encoded_len());
+    bytes.extend_from_slice(MAGIC);
+    encoded
+        .encode(&mut bytes)
+        .wrap()
+        .context(info!("Failed to encode message in protobuf"))?;
+
+    Ok(bytes)
+}
+
+/// Decode a message encoded by [`encode`], with the version it was encoded for
+///
+/// # Errors
+/// if `message` is not a well-formed protobuf encoded message
+pub fn decode<TYPES: NodeType>(message: &[u8]) -> Result<(Version, Message<TYPES>)> {
+    let encoded = message
+        .strip_prefix(MAGIC)
+        .context(info!("Message is not in protobuf"))?;
+    let decoded = proto::Message::decode(encoded)
+        .wrap()
+        .context(info!("Failed to decode protobuf message"))?;
+
+    let version = Version {
+        major: u16::try_from(decoded.version_major)
+            .wrap()
+            .context(info!("Invalid major version"))?,
+        minor: u16::try_from(decoded.version_minor)
+            .wrap()
+            .context(info!("Invalid minor version"))?,
+    };
+    let sender = TaggedBase64::parse(&decoded.sender)
+        .ok()
+        .and_then(|sender| TYPES::SignatureKey::try_from(&sender).ok())
+        .context(info!("Invalid sender key {}", decoded.sender))?;
+    let kind = serde_json::from_str(&decoded.kind_json)
+        .wrap()
+        .context(info!("Failed to decode the message kind from JSON"))?;
+
+    Ok((
+        version,
+        Message {
+            sender,
+            kind,
+            namespace: decoded.namespace,
+            expires_after: None,
+        },
+    ))
+}
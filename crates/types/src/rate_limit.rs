@@ -0,0 +1,84 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! A per-peer rate limiter for requests which are expensive to serve.
+//!
+//! Each peer gets a token bucket which refills at a steady rate up to a burst size, and every
+//! request served takes a token. Only a bounded number of peers is tracked, so a flood of
+//! requests from fresh keys cannot exhaust memory; forgetting a peer only resets its bucket.
+
+use std::{collections::HashMap, hash::Hash, time::Instant};
+
+/// The token bucket of a peer
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    /// the tokens left, at most the burst size
+    tokens: f64,
+    /// when `tokens` was last brought up to date, which is also when the peer was last seen
+    updated: Instant,
+}
+
+/// Limits the rate of requests each peer is served
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    /// the tokens each peer gets per second
+    rate: f64,
+    /// the most tokens a peer can save up
+    burst: f64,
+    /// the most peers tracked at once
+    max_peers: usize,
+    /// the bucket of each tracked peer
+    buckets: HashMap<K, Bucket>,
+}
+
+impl<K: Clone + Eq + Hash> RateLimiter<K> {
+    /// A limiter serving each peer `rate` requests per second on average, and up to `burst` at
+    /// once, which tracks at most `max_peers` peers
+    #[must_use]
+    pub fn new(rate: u32, burst: u32, max_peers: usize) -> Self {
+        Self {
+            rate: f64::from(rate),
+            burst: f64::from(burst.max(1)),
+            max_peers: max_peers.max(1),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Whether a request from `peer` at `now` may be served, taking a token from its bucket if so
+    pub fn allow(&mut self, peer: &K, now: Instant) -> bool {
+        if !self.buckets.contains_key(peer) && self.buckets.len() >= self.max_peers {
+            self.forget_least_recently_seen();
+        }
+
+        let (rate, burst) = (self.rate, self.burst);
+        let bucket = self.buckets.entry(peer.clone()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Stop tracking the peer seen least recently
+    fn forget_least_recently_seen(&mut self) {
+        if let Some(peer) = self
+            .buckets
+            .iter()
+            .min_by_key(|(_, bucket)| bucket.updated)
+            .map(|(peer, _)| peer.clone())
+        {
+            self.buckets.remove(&peer);
+        }
+    }
+}
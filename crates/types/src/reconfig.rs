@@ -0,0 +1,66 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Parameters of a node which can be changed while it runs.
+//!
+//! A [`ConfigUpdate`] only names the parameters it changes. It is applied as a whole when the node
+//! next changes view, so every task switches over at the same view boundary.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HotShotConfigError;
+
+/// A change to the parameters of a running node; parameters which are not set are left as they are
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigUpdate {
+    /// The new base duration for next-view timeouts, in milliseconds
+    #[serde(default)]
+    pub next_view_timeout: Option<u64>,
+    /// The new duration of view sync round timeouts
+    #[serde(default)]
+    pub view_sync_timeout: Option<Duration>,
+    /// The new maximum time a leader waits for a block from a builder
+    #[serde(default)]
+    pub builder_timeout: Option<Duration>,
+    /// The new log filter directives, e.g. `info,hotshot_task_impls::consensus=trace`
+    #[serde(default)]
+    pub log_filter: Option<String>,
+}
+
+impl ConfigUpdate {
+    /// Whether the update changes nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fold `newer` into this update, its parameters taking precedence
+    pub fn merge(&mut self, newer: Self) {
+        self.next_view_timeout = newer.next_view_timeout.or(self.next_view_timeout);
+        self.view_sync_timeout = newer.view_sync_timeout.or(self.view_sync_timeout);
+        self.builder_timeout = newer.builder_timeout.or(self.builder_timeout);
+        self.log_filter = newer.log_filter.or(self.log_filter.take());
+    }
+
+    /// Check that the parameters the update sets are usable
+    ///
+    /// # Errors
+    /// if a timeout is zero
+    pub fn validate(&self) -> Result<(), HotShotConfigError> {
+        if self.next_view_timeout == Some(0) {
+            return Err(HotShotConfigError::ZeroTimeout("next view timeout"));
+        }
+        if self.view_sync_timeout == Some(Duration::ZERO) {
+            return Err(HotShotConfigError::ZeroTimeout("view sync timeout"));
+        }
+        if self.builder_timeout == Some(Duration::ZERO) {
+            return Err(HotShotConfigError::ZeroTimeout("builder timeout"));
+        }
+        Ok(())
+    }
+}
@@ -135,9 +135,8 @@ impl SignatureKey for BLSPubKey {
         real_qc_pp: &Self::QcParams,
         signers: &BitSlice,
         sigs: &[Self::PureAssembledSignatureType],
-    ) -> Self::QcType {
+    ) -> Result<Self::QcType, Self::SignError> {
         BitVectorQc::<BLSOverBN254CurveSignatureScheme>::assemble(real_qc_pp, signers, sigs)
-            .expect("this assembling shouldn't fail")
     }
 
     fn genesis_proposer_pk() -> Self {
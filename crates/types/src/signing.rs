@@ -0,0 +1,43 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! The canonical encoding of everything a node signs.
+//!
+//! Nodes never sign the wire encoding of a payload, which depends on the serializer and on how
+//! the types evolve. They sign a 32 byte digest of the payload instead, which for most payloads
+//! is its [`Committable`](committable::Committable) commitment. A commitment hashes a tag followed
+//! by the fields of the payload in a fixed order, with the primitives of
+//! [`RawCommitmentBuilder`](committable::RawCommitmentBuilder), which define the byte layout of
+//! each field independently of any serializer.
+//!
+//! The signed digests are:
+//!
+//! | payload | digest |
+//! |---|---|
+//! | every vote | `"Vote"`, `var_size_bytes(data commitment)`, `u64(view)`; see [`VersionedVoteData`] |
+//! | quorum data | `"Quorum data"`, `var_size_bytes(leaf commitment)` |
+//! | DA data | `"DA data"`, `var_size_bytes(payload commitment)` |
+//! | timeout data | `"Timeout data"`, `u64(view)` |
+//! | view sync data | `"View Sync Precommit"`, `"View Sync Commit"` or `"View Sync Finalize"`, then `u64(round)`, `u64(relay)` |
+//! | checkpoint data | `"Checkpoint data"`, `u64(height)`, `var_size_bytes(leaf commitment)`, `fixed_size_bytes(state digest)`, `u64(epoch)` |
+//! | attestation data | `"Attestation data"`, `u64(height)`, `var_size_bytes(leaf commitment)`, `u64(epoch)`, `var_size_bytes(requester key)`, `u64(nonce)`, `fixed_size_bytes(SHA-256 of the message)` |
+//! | upgrade data | `"Upgrade data"`, `u64` of the decide by, first new and last old views, `var_size_bytes(new version hash)`, then `u16` of the new and old minor and major versions |
+//! | quorum proposal | the commitment of the proposed leaf |
+//! | DA proposal | the SHA-256 hash of the encoded transactions |
+//! | VID share | the payload commitment |
+//! | upgrade proposal | the commitment of the upgrade data |
+//! | proposal request | `"signed proposal request commitment"`, `u64_field("view number", view)`, `var_size_bytes(key)` |
+//! | data request | the commitment of the [`RequestKind`] |
+//! | validator metadata | `"Validator metadata"`, `var_size_bytes(key)`, `u64(sequence)`, then `var_size_bytes` of the moniker, contact and website |
+//!
+//! Keys are encoded with [`SignatureKey::to_bytes`]. The golden vectors in the
+//! `signing_vectors` test of `hotshot-testing`, stored in its `tests/golden/signing_vectors.txt`,
+//! pin these digests for fixed inputs, so that a change to any of them fails the test, and other
+//! implementations can check theirs against them. The test fails if the file is missing.
+//!
+//! [`VersionedVoteData`]: crate::simple_vote::VersionedVoteData
+//! [`RequestKind`]: crate::traits::network::RequestKind
+//! [`SignatureKey::to_bytes`]: crate::traits::signature_key::SignatureKey::to_bytes
@@ -24,10 +24,10 @@ use crate::{
     data::serialize_signature2,
     message::UpgradeLock,
     simple_vote::{
-        DaData, DaData2, NextEpochQuorumData2, QuorumData, QuorumData2, QuorumMarker, TimeoutData,
-        TimeoutData2, UpgradeProposalData, VersionedVoteData, ViewSyncCommitData,
-        ViewSyncCommitData2, ViewSyncFinalizeData, ViewSyncFinalizeData2, ViewSyncPreCommitData,
-        ViewSyncPreCommitData2, Voteable,
+        AttestationData, CheckpointData, DaData, DaData2, NextEpochQuorumData2, QuorumData,
+        QuorumData2, QuorumMarker, TimeoutData, TimeoutData2, UpgradeProposalData,
+        VersionedVoteData, ViewSyncCommitData, ViewSyncCommitData2, ViewSyncFinalizeData,
+        ViewSyncFinalizeData2, ViewSyncPreCommitData, ViewSyncPreCommitData2, Voteable,
     },
     traits::{
         election::Membership,
@@ -44,6 +44,32 @@ pub trait Threshold<TYPES: NodeType> {
         membership: &MEMBERSHIP,
         epoch: TYPES::Epoch,
     ) -> u64;
+
+    /// Get the stake table of the committee the threshold is over, the whole committee unless
+    /// overridden
+    fn stake_table<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.stake_table(epoch)
+    }
+
+    /// Get the stake table entry of `pub_key` in the committee the threshold is over
+    fn stake_table_entry<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        pub_key: &TYPES::SignatureKey,
+        epoch: TYPES::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.stake(pub_key, epoch)
+    }
+
+    /// Get the number of nodes in the committee the threshold is over
+    fn total_nodes<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> usize {
+        membership.total_nodes(epoch)
+    }
 }
 
 /// Defines a threshold which is 2f + 1 (Amount needed for Quorum)
@@ -85,6 +111,76 @@ impl<TYPES: NodeType> Threshold<TYPES> for UpgradeThreshold {
     }
 }
 
+/// Defines a threshold which is 2f + 1 of the view sync committee
+#[derive(Serialize, Deserialize, Eq, Hash, PartialEq, Debug, Clone)]
+pub struct ViewSyncSuccessThreshold {}
+
+impl<TYPES: NodeType> Threshold<TYPES> for ViewSyncSuccessThreshold {
+    fn threshold<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> u64 {
+        membership.view_sync_success_threshold(epoch).into()
+    }
+
+    fn stake_table<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.view_sync_stake_table(epoch)
+    }
+
+    fn stake_table_entry<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        pub_key: &TYPES::SignatureKey,
+        epoch: TYPES::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.view_sync_stake(pub_key, epoch)
+    }
+
+    fn total_nodes<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> usize {
+        membership.view_sync_total_nodes(epoch)
+    }
+}
+
+/// Defines a threshold which is f + 1 of the view sync committee
+#[derive(Serialize, Deserialize, Eq, Hash, PartialEq, Debug, Clone)]
+pub struct ViewSyncOneHonestThreshold {}
+
+impl<TYPES: NodeType> Threshold<TYPES> for ViewSyncOneHonestThreshold {
+    fn threshold<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> u64 {
+        membership.view_sync_failure_threshold(epoch).into()
+    }
+
+    fn stake_table<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.view_sync_stake_table(epoch)
+    }
+
+    fn stake_table_entry<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        pub_key: &TYPES::SignatureKey,
+        epoch: TYPES::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        membership.view_sync_stake(pub_key, epoch)
+    }
+
+    fn total_nodes<MEMBERSHIP: Membership<TYPES>>(
+        membership: &MEMBERSHIP,
+        epoch: TYPES::Epoch,
+    ) -> usize {
+        membership.view_sync_total_nodes(epoch)
+    }
+}
+
 /// A certificate which can be created by aggregating many simple votes on the commitment.
 #[derive(Serialize, Deserialize, Eq, Hash, PartialEq, Debug, Clone)]
 pub struct SimpleCertificate<
@@ -180,11 +276,10 @@ impl<TYPES: NodeType, THRESHOLD: Threshold<TYPES>> Certificate<TYPES, DaData>
         let Ok(commit) = self.data_commitment(upgrade_lock).await else {
             return false;
         };
-        <TYPES::SignatureKey as SignatureKey>::check(
-            &real_qc_pp,
-            commit.as_ref(),
-            self.signatures.as_ref().unwrap(),
-        )
+        let Some(signatures) = self.signatures.as_ref() else {
+            return false;
+        };
+        <TYPES::SignatureKey as SignatureKey>::check(&real_qc_pp, commit.as_ref(), signatures)
     }
     /// Proxy's to `Membership.stake`
     fn stake_table_entry<MEMBERSHIP: Membership<TYPES>>(
@@ -268,11 +363,10 @@ impl<TYPES: NodeType, THRESHOLD: Threshold<TYPES>> Certificate<TYPES, DaData2<TY
         let Ok(commit) = self.data_commitment(upgrade_lock).await else {
             return false;
         };
-        <TYPES::SignatureKey as SignatureKey>::check(
-            &real_qc_pp,
-            commit.as_ref(),
-            self.signatures.as_ref().unwrap(),
-        )
+        let Some(signatures) = self.signatures.as_ref() else {
+            return false;
+        };
+        <TYPES::SignatureKey as SignatureKey>::check(&real_qc_pp, commit.as_ref(), signatures)
     }
     /// Proxy's to `Membership.stake`
     fn stake_table_entry<MEMBERSHIP: Membership<TYPES>>(
@@ -359,11 +453,10 @@ impl<
         let Ok(commit) = self.data_commitment(upgrade_lock).await else {
             return false;
         };
-        <TYPES::SignatureKey as SignatureKey>::check(
-            &real_qc_pp,
-            commit.as_ref(),
-            self.signatures.as_ref().unwrap(),
-        )
+        let Some(signatures) = self.signatures.as_ref() else {
+            return false;
+        };
+        <TYPES::SignatureKey as SignatureKey>::check(&real_qc_pp, commit.as_ref(), signatures)
     }
     fn threshold<MEMBERSHIP: Membership<TYPES>>(
         membership: &MEMBERSHIP,
@@ -377,22 +470,22 @@ impl<
         pub_key: &TYPES::SignatureKey,
         epoch: TYPES::Epoch,
     ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
-        membership.stake(pub_key, epoch)
+        THRESHOLD::stake_table_entry(membership, pub_key, epoch)
     }
 
     fn stake_table<MEMBERSHIP: Membership<TYPES>>(
         membership: &MEMBERSHIP,
         epoch: TYPES::Epoch,
     ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
-        membership.stake_table(epoch)
+        THRESHOLD::stake_table(membership, epoch)
     }
 
-    /// Proxy's to `Membership.total_nodes`
+    /// Proxy's to the committee of the threshold
     fn total_nodes<MEMBERSHIP: Membership<TYPES>>(
         membership: &MEMBERSHIP,
         epoch: TYPES::Epoch,
     ) -> usize {
-        membership.total_nodes(epoch)
+        THRESHOLD::total_nodes(membership, epoch)
     }
 
     fn data(&self) -> &Self::Voteable {
@@ -755,22 +848,28 @@ pub type TimeoutCertificate2<TYPES> =
     SimpleCertificate<TYPES, TimeoutData2<TYPES>, SuccessThreshold>;
 /// Type alias for a `ViewSyncPreCommit` certificate over a view number
 pub type ViewSyncPreCommitCertificate<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncPreCommitData<TYPES>, OneHonestThreshold>;
+    SimpleCertificate<TYPES, ViewSyncPreCommitData<TYPES>, ViewSyncOneHonestThreshold>;
 /// Type alias for a `ViewSyncPreCommitCertificate2`, which is a `SimpleCertificate` over `ViewSyncPreCommitData2`
 pub type ViewSyncPreCommitCertificate2<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncPreCommitData2<TYPES>, OneHonestThreshold>;
+    SimpleCertificate<TYPES, ViewSyncPreCommitData2<TYPES>, ViewSyncOneHonestThreshold>;
 /// Type alias for a `ViewSyncCommit` certificate over a view number
 pub type ViewSyncCommitCertificate<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncCommitData<TYPES>, SuccessThreshold>;
+    SimpleCertificate<TYPES, ViewSyncCommitData<TYPES>, ViewSyncSuccessThreshold>;
 /// Type alias for a `ViewSyncCommitCertificate2`, which is a `SimpleCertificate` over `ViewSyncCommitData2`
 pub type ViewSyncCommitCertificate2<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncCommitData2<TYPES>, SuccessThreshold>;
+    SimpleCertificate<TYPES, ViewSyncCommitData2<TYPES>, ViewSyncSuccessThreshold>;
 /// Type alias for a `ViewSyncFinalize` certificate over a view number
 pub type ViewSyncFinalizeCertificate<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncFinalizeData<TYPES>, SuccessThreshold>;
+    SimpleCertificate<TYPES, ViewSyncFinalizeData<TYPES>, ViewSyncSuccessThreshold>;
 /// Type alias for a `ViewSyncFinalizeCertificate2`, which is a `SimpleCertificate` over `ViewSyncFinalizeData2`
 pub type ViewSyncFinalizeCertificate2<TYPES> =
-    SimpleCertificate<TYPES, ViewSyncFinalizeData2<TYPES>, SuccessThreshold>;
+    SimpleCertificate<TYPES, ViewSyncFinalizeData2<TYPES>, ViewSyncSuccessThreshold>;
 /// Type alias for a `UpgradeCertificate`, which is a `SimpleCertificate` of `UpgradeProposalData`
 pub type UpgradeCertificate<TYPES> =
     SimpleCertificate<TYPES, UpgradeProposalData<TYPES>, UpgradeThreshold>;
+/// Type alias for a `CheckpointCertificate`, which is a `SimpleCertificate` over `CheckpointData`
+pub type CheckpointCertificate<TYPES> =
+    SimpleCertificate<TYPES, CheckpointData<TYPES>, SuccessThreshold>;
+/// Type alias for an `AttestationCertificate`, which is a `SimpleCertificate` over `AttestationData`
+pub type AttestationCertificate<TYPES> =
+    SimpleCertificate<TYPES, AttestationData<TYPES>, SuccessThreshold>;
@@ -164,6 +164,39 @@ pub struct UpgradeData2<TYPES: NodeType> {
     pub epoch: TYPES::Epoch,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
+/// Data used for a checkpoint vote.
+#[serde(bound(deserialize = ""))]
+pub struct CheckpointData<TYPES: NodeType> {
+    /// Height of the checkpointed block
+    pub height: u64,
+    /// Commitment to the checkpointed leaf
+    pub leaf_commit: Commitment<Leaf2<TYPES>>,
+    /// Digest of the application state after applying the checkpointed block
+    pub state_digest: [u8; 32],
+    /// The epoch of the checkpointed block
+    pub epoch: TYPES::Epoch,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
+/// Data used for an attestation vote, by which the quorum co-signs an application message
+/// bound to a decided leaf.
+#[serde(bound(deserialize = ""))]
+pub struct AttestationData<TYPES: NodeType> {
+    /// Height of the decided leaf the message is bound to
+    pub height: u64,
+    /// Commitment to the decided leaf the message is bound to
+    pub leaf_commit: Commitment<Leaf2<TYPES>>,
+    /// The epoch of the decided leaf, whose quorum signs the attestation
+    pub epoch: TYPES::Epoch,
+    /// The node which requested the attestation
+    pub requester: TYPES::SignatureKey,
+    /// Number chosen by the requester, which it never uses for another message
+    pub nonce: u64,
+    /// SHA-256 digest of the message
+    pub message_digest: [u8; 32],
+}
+
 /// Marker trait for data or commitments that can be voted on.
 /// Only structs in this file can implement voteable.  This is enforced with the `Sealed` trait
 /// Sealing this trait prevents creating new vote types outside this file.
@@ -205,6 +238,8 @@ impl<T: NodeType> QuorumMarker for ViewSyncPreCommitData2<T> {}
 impl<T: NodeType> QuorumMarker for ViewSyncCommitData2<T> {}
 impl<T: NodeType> QuorumMarker for ViewSyncFinalizeData2<T> {}
 impl<T: NodeType + DeserializeOwned> QuorumMarker for UpgradeProposalData<T> {}
+impl<T: NodeType> QuorumMarker for CheckpointData<T> {}
+impl<T: NodeType> QuorumMarker for AttestationData<T> {}
 
 /// A simple yes vote over some votable type.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
@@ -449,6 +484,46 @@ impl<TYPES: NodeType> Committable for UpgradeData2<TYPES> {
     }
 }
 
+impl<TYPES: NodeType> Committable for CheckpointData<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        let CheckpointData {
+            height,
+            leaf_commit,
+            state_digest,
+            epoch,
+        } = self;
+
+        committable::RawCommitmentBuilder::new("Checkpoint data")
+            .u64(*height)
+            .var_size_bytes(leaf_commit.as_ref())
+            .fixed_size_bytes(state_digest)
+            .u64(**epoch)
+            .finalize()
+    }
+}
+
+impl<TYPES: NodeType> Committable for AttestationData<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        let AttestationData {
+            height,
+            leaf_commit,
+            epoch,
+            requester,
+            nonce,
+            message_digest,
+        } = self;
+
+        committable::RawCommitmentBuilder::new("Attestation data")
+            .u64(*height)
+            .var_size_bytes(leaf_commit.as_ref())
+            .u64(**epoch)
+            .var_size_bytes(&requester.to_bytes())
+            .u64(*nonce)
+            .fixed_size_bytes(message_digest)
+            .finalize()
+    }
+}
+
 /// This implements commit for all the types which contain a view and relay public key.
 fn view_and_relay_commit<TYPES: NodeType, T: Committable>(
     view: TYPES::View,
@@ -540,7 +615,9 @@ impl_has_epoch!(
     TimeoutData2<TYPES>,
     ViewSyncPreCommitData2<TYPES>,
     ViewSyncCommitData2<TYPES>,
-    ViewSyncFinalizeData2<TYPES>
+    ViewSyncFinalizeData2<TYPES>,
+    CheckpointData<TYPES>,
+    AttestationData<TYPES>
 );
 
 impl<TYPES: NodeType, DATA: Voteable<TYPES> + HasEpoch<TYPES>> HasEpoch<TYPES>
@@ -832,6 +909,10 @@ pub type ViewSyncCommitVote2<TYPES> = SimpleVote<TYPES, ViewSyncCommitData2<TYPE
 pub type UpgradeVote<TYPES> = SimpleVote<TYPES, UpgradeProposalData<TYPES>>;
 /// Upgrade proposal 2 vote
 pub type UpgradeVote2<TYPES> = SimpleVote<TYPES, UpgradeData2<TYPES>>;
+/// Checkpoint vote type alias
+pub type CheckpointVote<TYPES> = SimpleVote<TYPES, CheckpointData<TYPES>>;
+/// Attestation vote type alias
+pub type AttestationVote<TYPES> = SimpleVote<TYPES, AttestationData<TYPES>>;
 
 impl<TYPES: NodeType> Deref for NextEpochQuorumData2<TYPES> {
     type Target = QuorumData2<TYPES>;
@@ -40,4 +40,34 @@ impl<K: SignatureKey> StakeTableEntry<K> {
     }
 }
 
+/// A token holder's delegation of voting weight to an operator key.
+///
+/// The delegated amount is added to the operator's own stake when computing its effective stake.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Hash, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct Delegation<K: SignatureKey> {
+    /// The account of the token holder, as identified by the application
+    pub delegator: Vec<u8>,
+    /// The operator key the weight is delegated to
+    pub operator: K,
+    /// The delegated amount. Setting it to zero removes the delegation.
+    pub amount: u64,
+}
+
+/// A commitment to `stake_table`, which changes with any of its keys, stakes or their order.
+///
+/// Headers commit to the stake table of the next epoch with it, so that the quorum certifying the
+/// last block of an epoch also vouches for the quorum taking over.
+#[must_use]
+pub fn stake_table_commitment<K: SignatureKey>(stake_table: &[K::StakeTableEntry]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    for entry in stake_table {
+        // Serializing a stake table entry does not fail; hash nothing for it if it does
+        let bytes = bincode::serialize(entry).unwrap_or_default();
+        hasher.update(&(bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    *hasher.finalize().as_bytes()
+}
+
 // TODO(Chengyu): add stake table snapshot here
@@ -0,0 +1,55 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Distributed traces of views across nodes.
+//!
+//! With the `otel` feature, the spans of every node which send or receive a message of a view
+//! are joined into one trace per view, so the proposal, the votes and the certificate of a view
+//! can be followed across the leader and the replicas.
+//!
+//! The trace context of a view is derived from the view number and the
+//! [`namespace`](crate::HotShotConfig::namespace) of the consensus instance. Every node of an
+//! instance derives the same context, so it does not have to be carried in messages and the wire
+//! format is unchanged, while the traces of instances reporting to the same collector stay apart
+//! as long as their namespaces differ. Without the `otel` feature, [`attach_to_view`] does
+//! nothing.
+
+/// The OpenTelemetry context shared by all spans of `view` in the instance `namespace`
+#[cfg(feature = "otel")]
+#[must_use]
+pub fn view_context(namespace: u64, view: u64) -> opentelemetry::Context {
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"hotshot-view-trace");
+    hasher.update(&namespace.to_le_bytes());
+    hasher.update(&view.to_le_bytes());
+    let hash = hasher.finalize();
+    let bytes = hash.as_bytes();
+
+    let span_context = SpanContext::new(
+        TraceId::from_bytes(bytes[..16].try_into().unwrap()),
+        SpanId::from_bytes(bytes[16..24].try_into().unwrap()),
+        TraceFlags::SAMPLED,
+        true,
+        TraceState::default(),
+    );
+    opentelemetry::Context::new().with_remote_span_context(span_context)
+}
+
+/// Make `span` part of the distributed trace of `view` in the instance `namespace`
+pub fn attach_to_view(span: &tracing::Span, namespace: u64, view: u64) {
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        span.set_parent(view_context(namespace, view));
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, namespace, view);
+}
@@ -6,15 +6,18 @@
 
 //! Common traits for the `HotShot` protocol
 pub mod auction_results_provider;
+pub mod block_builder_strategy;
 pub mod block_contents;
 pub mod consensus_api;
 pub mod election;
 pub mod metrics;
 pub mod network;
 pub mod node_implementation;
+pub mod proposal_validator;
 pub mod qc;
 pub mod signature_key;
 pub mod stake_table;
+pub mod state_sync;
 pub mod states;
 pub mod storage;
 
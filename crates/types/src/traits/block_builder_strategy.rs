@@ -0,0 +1,85 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! This module defines the [`BlockBuilderStrategy`] trait, through which an application decides
+//! which of the blocks offered by the builders the leader proposes.
+//!
+//! When it leads a view, a node asks its builders for the blocks they have available, then claims
+//! the block its strategy prefers, falling back to the next one if claiming fails. The strategy
+//! only chooses among offers, whose contents the builders assemble.
+
+use std::time::Duration;
+
+use super::node_implementation::NodeType;
+
+/// A block offered by a builder to the leader of a view
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockOffer<TYPES: NodeType> {
+    /// the builder offering the block
+    pub builder: TYPES::BuilderSignatureKey,
+    /// the size of the block, in bytes
+    pub block_size: u64,
+    /// the fee the builder pays for the block to be proposed
+    pub offered_fee: u64,
+}
+
+/// Chooses which of the offered blocks the leader proposes.
+pub trait BlockBuilderStrategy<TYPES: NodeType>: Send + Sync + 'static {
+    /// The indices of the `offers` for `view` the leader may claim, most preferred first. Offers
+    /// left out are never claimed, and if none is left the leader proposes an empty block.
+    fn rank_offers(view: TYPES::View, offers: &[BlockOffer<TYPES>]) -> Vec<usize>;
+
+    /// How long the leader waits for offers at most, within the builder timeout. Builders which
+    /// do not answer in time are left out.
+    fn offer_deadline() -> Option<Duration> {
+        None
+    }
+}
+
+/// Prefers the block with the highest fee per byte, which is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PriorityOrdered;
+
+impl<TYPES: NodeType> BlockBuilderStrategy<TYPES> for PriorityOrdered {
+    fn rank_offers(_view: TYPES::View, offers: &[BlockOffer<TYPES>]) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..offers.len()).collect();
+        // Compare `l.offered_fee / l.block_size` to `r.offered_fee / r.block_size` without
+        // floating point math, by multiplying through by the denominators
+        ranking.sort_by(|&l, &r| {
+            let (l, r) = (&offers[l], &offers[r]);
+            (u128::from(r.offered_fee) * u128::from(l.block_size))
+                .cmp(&(u128::from(l.offered_fee) * u128::from(r.block_size)))
+        });
+        ranking
+    }
+}
+
+/// Prefers the largest block, and among blocks of the same size the highest fee.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaxFill;
+
+impl<TYPES: NodeType> BlockBuilderStrategy<TYPES> for MaxFill {
+    fn rank_offers(_view: TYPES::View, offers: &[BlockOffer<TYPES>]) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..offers.len()).collect();
+        ranking.sort_by_key(|&i| std::cmp::Reverse((offers[i].block_size, offers[i].offered_fee)));
+        ranking
+    }
+}
+
+/// Waits at most `MILLIS` milliseconds for offers, and ranks the offers received in time like
+/// [`PriorityOrdered`]. Trades fees for shorter views when some builders are slow.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TimeBoxed<const MILLIS: u64>;
+
+impl<TYPES: NodeType, const MILLIS: u64> BlockBuilderStrategy<TYPES> for TimeBoxed<MILLIS> {
+    fn rank_offers(view: TYPES::View, offers: &[BlockOffer<TYPES>]) -> Vec<usize> {
+        <PriorityOrdered as BlockBuilderStrategy<TYPES>>::rank_offers(view, offers)
+    }
+
+    fn offer_deadline() -> Option<Duration> {
+        Some(Duration::from_millis(MILLIS))
+    }
+}
@@ -248,4 +248,11 @@ pub trait BlockHeader<TYPES: NodeType>:
 
     /// Get the results of the auction for this Header. Only used in post-marketplace versions
     fn get_auction_results(&self) -> Option<TYPES::AuctionResult>;
+
+    /// Get the commitment to the quorum stake table of the next epoch, made with
+    /// [`stake_table_commitment`](crate::stake_table::stake_table_commitment), if this is the last
+    /// block of an epoch. Light clients only accept the stake table of an epoch this commits to.
+    fn next_stake_table_commitment(&self) -> Option<[u8; 32]> {
+        None
+    }
 }
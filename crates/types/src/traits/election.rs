@@ -10,7 +10,7 @@ use std::{collections::BTreeSet, fmt::Debug, num::NonZeroU64};
 use utils::anytrace::Result;
 
 use super::node_implementation::NodeType;
-use crate::{traits::signature_key::SignatureKey, PeerConfig};
+use crate::{drb::DrbResult, traits::signature_key::SignatureKey, PeerConfig};
 
 /// A protocol for determining membership in and participating in a committee.
 pub trait Membership<TYPES: NodeType>: Debug + Send + Sync {
@@ -108,6 +108,13 @@ pub trait Membership<TYPES: NodeType>: Debug + Send + Sync {
         epoch: TYPES::Epoch,
     ) -> std::result::Result<TYPES::SignatureKey, Self::Error>;
 
+    /// Whether the stake table of `epoch` is known, rather than stood in for by the table of an
+    /// earlier epoch until it is fetched. Nodes are only accused of misbehaviour based on a known
+    /// stake table.
+    fn has_stake_table(&self, _epoch: TYPES::Epoch) -> bool {
+        true
+    }
+
     /// Returns the number of total nodes in the committee in an epoch `epoch`
     fn total_nodes(&self, epoch: TYPES::Epoch) -> usize;
 
@@ -125,4 +132,49 @@ pub trait Membership<TYPES: NodeType>: Debug + Send + Sync {
 
     /// Returns the threshold required to upgrade the network protocol
     fn upgrade_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64;
+
+    /// Get the participants in the view sync committee (including their stake) for a specific
+    /// epoch. Defaults to the whole committee.
+    fn view_sync_stake_table(
+        &self,
+        epoch: TYPES::Epoch,
+    ) -> Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.stake_table(epoch)
+    }
+
+    /// Get the view sync stake table entry for a public key, returns `None` if the key is not in
+    /// the view sync committee for a specific epoch
+    fn view_sync_stake(
+        &self,
+        pub_key: &TYPES::SignatureKey,
+        epoch: TYPES::Epoch,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.stake(pub_key, epoch)
+    }
+
+    /// See if a node has stake in the view sync committee in a specific epoch
+    fn has_view_sync_stake(&self, pub_key: &TYPES::SignatureKey, epoch: TYPES::Epoch) -> bool {
+        self.view_sync_stake(pub_key, epoch).is_some()
+    }
+
+    /// Returns the number of nodes in the view sync committee in an epoch `epoch`
+    fn view_sync_total_nodes(&self, epoch: TYPES::Epoch) -> usize {
+        self.total_nodes(epoch)
+    }
+
+    /// Returns the stake needed for a view sync commit or finalize certificate
+    fn view_sync_success_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.success_threshold(epoch)
+    }
+
+    /// Returns the stake needed for a view sync pre-commit certificate
+    fn view_sync_failure_threshold(&self, epoch: TYPES::Epoch) -> NonZeroU64 {
+        self.failure_threshold(epoch)
+    }
+
+    /// Called with the DRB result of `epoch` once it has been decided.
+    ///
+    /// Elections which use the randomness beacon, e.g. to sample committees, should override
+    /// this; it may be called several times with the same result.
+    fn add_drb_result(&mut self, _epoch: TYPES::Epoch, _drb_result: DrbResult) {}
 }
@@ -19,18 +19,30 @@ use std::{
 
 use async_lock::RwLock;
 use async_trait::async_trait;
+use bytes::Bytes;
+use committable::{Commitment, Committable, RawCommitmentBuilder};
 use dyn_clone::DynClone;
-use futures::{future::join_all, Future};
+use futures::{future::join_all, Future, FutureExt};
 use rand::{
     distributions::{Bernoulli, Uniform},
     prelude::Distribution,
+    Rng,
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::{sync::mpsc::error::TrySendError, time::sleep};
+use tokio::{
+    sync::mpsc::error::TrySendError,
+    time::{sleep, timeout_at, Instant},
+};
 
 use super::{node_implementation::NodeType, signature_key::SignatureKey};
-use crate::{data::ViewNumber, message::SequencingMessage, BoxSyncFuture};
+use crate::{
+    data::{Leaf2, ViewNumber},
+    deterministic::with_rng,
+    message::SequencingMessage,
+    simple_certificate::QuorumCertificate2,
+    BoxSyncFuture,
+};
 
 /// Centralized server specific errors
 #[derive(Debug, Error, Serialize, Deserialize)]
@@ -109,6 +121,75 @@ pub enum NetworkError {
     /// Failed to look up a node on the network
     #[error("Node lookup failed: {0}")]
     LookupError(String),
+
+    /// Timed out establishing the connection to a peer, before any handshake
+    #[error("Timed out connecting: {0}")]
+    ConnectTimeout(String),
+
+    /// Timed out in the handshake upgrading a new connection, such as the authentication
+    /// against the stake table
+    #[error("Timed out in the connection handshake: {0}")]
+    HandshakeTimeout(String),
+
+    /// Timed out waiting for a newly connected peer to identify itself
+    #[error("Timed out waiting for the peer to identify itself: {0}")]
+    IdentifyTimeout(String),
+}
+
+impl NetworkError {
+    /// The stable numeric code of the error
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Multiple(_) => 2001,
+            Self::ConfigError(_) => 2002,
+            Self::MessageSendError(_) => 2003,
+            Self::MessageReceiveError(_) => 2004,
+            Self::Unimplemented => 2005,
+            Self::ListenError(_) => 2006,
+            Self::ChannelSendError(_) => 2007,
+            Self::ChannelReceiveError(_) => 2008,
+            Self::ShutDown => 2009,
+            Self::FailedToSerialize(_) => 2010,
+            Self::FailedToDeserialize(_) => 2011,
+            Self::Timeout(_) => 2012,
+            Self::RequestCancelled => 2013,
+            Self::NotReadyYet => 2014,
+            Self::LookupError(_) => 2015,
+            Self::ConnectTimeout(_) => 2016,
+            Self::HandshakeTimeout(_) => 2017,
+            Self::IdentifyTimeout(_) => 2018,
+        }
+    }
+
+    /// Whether the operation which failed may succeed if retried
+    ///
+    /// Sends, receives and lookups fail while peers come and go, so they are worth retrying, as
+    /// is a network which is not ready yet. A combination of errors is only worth retrying if
+    /// each of them is.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Multiple(errors) => !errors.is_empty() && errors.iter().all(Self::is_retryable),
+            Self::MessageSendError(_)
+            | Self::MessageReceiveError(_)
+            | Self::Timeout(_)
+            | Self::RequestCancelled
+            | Self::NotReadyYet
+            | Self::LookupError(_)
+            | Self::ConnectTimeout(_)
+            | Self::HandshakeTimeout(_)
+            | Self::IdentifyTimeout(_) => true,
+            Self::ConfigError(_)
+            | Self::Unimplemented
+            | Self::ListenError(_)
+            | Self::ChannelSendError(_)
+            | Self::ChannelReceiveError(_)
+            | Self::ShutDown
+            | Self::FailedToSerialize(_)
+            | Self::FailedToDeserialize(_) => false,
+        }
+    }
 }
 
 /// Trait that bundles what we need from a request ID
@@ -128,7 +209,7 @@ pub struct DataRequest<TYPES: NodeType> {
     pub request: RequestKind<TYPES>,
     /// View this message is for
     pub view: TYPES::View,
-    /// signature of the Sha256 hash of the data so outsiders can't use know
+    /// signature of the commitment of the request so outsiders can't use know
     /// public keys with stake.
     pub signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
 }
@@ -142,6 +223,37 @@ pub enum RequestKind<TYPES: NodeType> {
     DaProposal(TYPES::View),
     /// Request for quorum proposal for a view
     Proposal(TYPES::View),
+    /// Request a DA committee member's chunk of the payload of a view
+    DaChunk(TYPES::View),
+    /// Request up to the given number of finalized leaves from an archival node, starting at the
+    /// given block height
+    LeafRange(u64, u64),
+}
+
+impl<TYPES: NodeType> Committable for RequestKind<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        match self {
+            RequestKind::Vid(view, key) => RawCommitmentBuilder::new("VID request")
+                .u64_field("view number", **view)
+                .var_size_bytes(&key.to_bytes())
+                .finalize(),
+            RequestKind::DaProposal(view) => RawCommitmentBuilder::new("DA proposal request")
+                .u64_field("view number", **view)
+                .finalize(),
+            RequestKind::Proposal(view) => RawCommitmentBuilder::new("proposal request")
+                .u64_field("view number", **view)
+                .finalize(),
+            RequestKind::DaChunk(view) => RawCommitmentBuilder::new("DA chunk request")
+                .u64_field("view number", **view)
+                .finalize(),
+            RequestKind::LeafRange(from_height, count) => {
+                RawCommitmentBuilder::new("leaf range request")
+                    .u64_field("from height", *from_height)
+                    .u64_field("count", *count)
+                    .finalize()
+            }
+        }
+    }
 }
 
 /// A response for a request.  `SequencingMessage` is the same as other network messages
@@ -157,6 +269,9 @@ pub enum ResponseMessage<TYPES: NodeType> {
     NotFound,
     /// The Request was denied
     Denied,
+    /// An archival node returned finalized leaves, oldest first, each with the certificate
+    /// which finalized it
+    LeafRange(Vec<(Leaf2<TYPES>, QuorumCertificate2<TYPES>)>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -195,7 +310,7 @@ pub trait ConnectedNetwork<K: SignatureKey + 'static>: Clone + Send + Sync + 'st
     /// blocking
     async fn broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         topic: Topic,
         broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError>;
@@ -204,17 +319,14 @@ pub trait ConnectedNetwork<K: SignatureKey + 'static>: Clone + Send + Sync + 'st
     /// blocking
     async fn da_broadcast_message(
         &self,
-        message: Vec<u8>,
+        message: Bytes,
         recipients: Vec<K>,
         broadcast_delay: BroadcastDelay,
     ) -> Result<(), NetworkError>;
 
     /// send messages with vid shares to its recipients
     /// blocking
-    async fn vid_broadcast_message(
-        &self,
-        messages: HashMap<K, Vec<u8>>,
-    ) -> Result<(), NetworkError> {
+    async fn vid_broadcast_message(&self, messages: HashMap<K, Bytes>) -> Result<(), NetworkError> {
         let future_results = messages
             .into_iter()
             .map(|(recipient_key, message)| self.direct_message(message, recipient_key));
@@ -237,13 +349,45 @@ pub trait ConnectedNetwork<K: SignatureKey + 'static>: Clone + Send + Sync + 'st
 
     /// Sends a direct message to a specific node
     /// blocking
-    async fn direct_message(&self, message: Vec<u8>, recipient: K) -> Result<(), NetworkError>;
+    async fn direct_message(&self, message: Bytes, recipient: K) -> Result<(), NetworkError>;
 
     /// Receive one or many messages from the underlying network.
     ///
     /// # Errors
     /// If there is a network-related failure.
-    async fn recv_message(&self) -> Result<Vec<u8>, NetworkError>;
+    async fn recv_message(&self) -> Result<Bytes, NetworkError>;
+
+    /// Receive up to `max` messages: wait until `deadline` for the first one, then take those
+    /// which already arrived without waiting again.
+    ///
+    /// Broadcast and direct messages arrive in the same queue. Unlike taking everything which
+    /// already arrived, this bounds both the size of a batch and the time spent waiting for it, so
+    /// a caller can share its time fairly between the network and its other work. The batch is
+    /// empty if no message arrived by `deadline`.
+    ///
+    /// # Errors
+    /// If receiving the first message fails. A failure after that ends the batch.
+    async fn recv_messages(
+        &self,
+        max: usize,
+        deadline: Instant,
+    ) -> Result<Vec<Bytes>, NetworkError> {
+        let mut messages = Vec::new();
+        if max == 0 {
+            return Ok(messages);
+        }
+        match timeout_at(deadline, self.recv_message()).await {
+            Ok(message) => messages.push(message?),
+            Err(_) => return Ok(messages),
+        }
+        while messages.len() < max {
+            let Some(Ok(message)) = self.recv_message().now_or_never() else {
+                break;
+            };
+            messages.push(message);
+        }
+        Ok(messages)
+    }
 
     /// queues lookup of a node
     ///
@@ -273,6 +417,19 @@ pub trait ConnectedNetwork<K: SignatureKey + 'static>: Clone + Send + Sync + 'st
     fn is_primary_down(&self) -> bool {
         false
     }
+
+    /// The number of peers this node is connected to, if the network can tell
+    fn num_connected_peers(&self) -> Option<usize> {
+        None
+    }
+
+    /// Drop the connection to the node with key `peer`. The peer may reconnect later.
+    ///
+    /// # Errors
+    /// If the peer cannot be found, or the network cannot disconnect peers
+    async fn disconnect_peer(&self, _peer: &K) -> Result<(), NetworkError> {
+        Err(NetworkError::Unimplemented)
+    }
 }
 
 /// A channel generator for types that need asynchronous execution
@@ -299,8 +456,61 @@ where
     ///
     /// Some implementations will not be able to tell how many messages there are in-flight. These implementations should return `None`.
     fn in_flight_message_count(&self) -> Option<usize>;
+
+    /// Partition the network, so that nodes can only reach the nodes in their own group. Nodes
+    /// which are not in any group form one more group.
+    ///
+    /// Implementations which cannot simulate partitions ignore this.
+    fn partition(&self, _groups: Vec<Vec<TYPES::SignatureKey>>) {
+        tracing::warn!("This network cannot simulate partitions, ignoring partition");
+    }
+
+    /// Remove the partition of the network, if any.
+    fn heal_partition(&self) {}
+
+    /// Apply `reliability` to the messages sent from `from` to `to`, instead of the reliability
+    /// configured for the whole network.
+    ///
+    /// Implementations which cannot simulate individual links ignore this.
+    fn set_link_reliability(
+        &self,
+        _from: TYPES::SignatureKey,
+        _to: TYPES::SignatureKey,
+        _reliability: Box<dyn NetworkReliability>,
+    ) {
+        tracing::warn!("This network cannot simulate individual links, ignoring link reliability");
+    }
+
+    /// Add a hook which sees every message sent over the network, and decides what happens to it.
+    ///
+    /// Implementations which cannot intercept messages ignore this.
+    fn add_message_hook(&self, _hook: MessageHook<TYPES::SignatureKey>) {
+        tracing::warn!("This network cannot intercept messages, ignoring message hook");
+    }
 }
 
+/// What to do with a message seen by a [`MessageHook`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Interception<M = Bytes> {
+    /// deliver the message as usual
+    Deliver,
+    /// drop the message
+    Drop,
+    /// deliver the message after a delay
+    Delay(Duration),
+    /// deliver the message this many times
+    Duplicate(usize),
+    /// deliver this message instead
+    Mutate(M),
+}
+
+/// A hook called with the sender, recipient and contents of every message sent over a test
+/// network.
+///
+/// Hooks are called in the order they were added, and the first one which does not return
+/// [`Interception::Deliver`] decides what happens to the message.
+pub type MessageHook<K> = Arc<dyn Fn(&K, &K, &[u8]) -> Interception + Send + Sync>;
+
 /// Changes that can occur in the network
 #[derive(Debug)]
 pub enum NetworkChange<P: SignatureKey> {
@@ -331,7 +541,7 @@ pub trait NetworkReliability: Debug + Sync + std::marker::Send + DynClone + 'sta
     }
 
     /// scramble the packet
-    fn scramble(&self, msg: Vec<u8>) -> Vec<u8> {
+    fn scramble(&self, msg: Bytes) -> Bytes {
         msg
     }
 
@@ -351,8 +561,8 @@ pub trait NetworkReliability: Debug + Sync + std::marker::Send + DynClone + 'sta
     /// then return a future that does the sending and delaying
     fn chaos_send_msg(
         &self,
-        msg: Vec<u8>,
-        send_fn: Arc<dyn Send + Sync + 'static + Fn(Vec<u8>) -> BoxSyncFuture<'static, ()>>,
+        msg: Bytes,
+        send_fn: Arc<dyn Send + Sync + 'static + Fn(Bytes) -> BoxSyncFuture<'static, ()>>,
     ) -> BoxSyncFuture<'static, ()> {
         let sample_keep = self.sample_keep();
         let delay = self.sample_delay();
@@ -399,10 +609,8 @@ impl NetworkReliability for SynchronousNetwork {
         true
     }
     fn sample_delay(&self) -> Duration {
-        Duration::from_millis(
-            Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms)
-                .sample(&mut rand::thread_rng()),
-        )
+        let delay = Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms);
+        Duration::from_millis(with_rng(|rng| delay.sample(rng)))
     }
 }
 
@@ -425,15 +633,12 @@ pub struct AsynchronousNetwork {
 
 impl NetworkReliability for AsynchronousNetwork {
     fn sample_keep(&self) -> bool {
-        Bernoulli::from_ratio(self.keep_numerator, self.keep_denominator)
-            .unwrap()
-            .sample(&mut rand::thread_rng())
+        let keep = Bernoulli::from_ratio(self.keep_numerator, self.keep_denominator).unwrap();
+        with_rng(|rng| keep.sample(rng))
     }
     fn sample_delay(&self) -> Duration {
-        Duration::from_millis(
-            Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms)
-                .sample(&mut rand::thread_rng()),
-        )
+        let delay = Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms);
+        Duration::from_millis(with_rng(|rng| delay.sample(rng)))
     }
 }
 
@@ -563,20 +768,121 @@ pub struct ChaosNetwork {
 
 impl NetworkReliability for ChaosNetwork {
     fn sample_keep(&self) -> bool {
-        Bernoulli::from_ratio(self.keep_numerator, self.keep_denominator)
-            .unwrap()
-            .sample(&mut rand::thread_rng())
+        let keep = Bernoulli::from_ratio(self.keep_numerator, self.keep_denominator).unwrap();
+        with_rng(|rng| keep.sample(rng))
     }
 
     fn sample_delay(&self) -> Duration {
-        Duration::from_millis(
-            Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms)
-                .sample(&mut rand::thread_rng()),
-        )
+        let delay = Uniform::new_inclusive(self.delay_low_ms, self.delay_high_ms);
+        Duration::from_millis(with_rng(|rng| delay.sample(rng)))
     }
 
     fn sample_repeat(&self) -> usize {
-        Uniform::new_inclusive(self.repeat_low, self.repeat_high).sample(&mut rand::thread_rng())
+        let repeat = Uniform::new_inclusive(self.repeat_low, self.repeat_high);
+        with_rng(|rng| repeat.sample(rng))
+    }
+}
+
+/// A distribution of the latency of a link
+#[derive(Debug, Clone, Copy)]
+pub enum LatencyDistribution {
+    /// every message takes the same time
+    Fixed {
+        /// latency in milliseconds
+        latency_ms: u64,
+    },
+    /// latency sampled uniformly between `low_ms` and `high_ms`, inclusive
+    Uniform {
+        /// lowest latency in milliseconds
+        low_ms: u64,
+        /// highest latency in milliseconds
+        high_ms: u64,
+    },
+    /// latency sampled from a normal distribution, clamped at zero
+    Normal {
+        /// mean latency in milliseconds
+        mean_ms: f64,
+        /// standard deviation in milliseconds
+        std_dev_ms: f64,
+    },
+}
+
+impl LatencyDistribution {
+    /// sample a latency in milliseconds
+    #[allow(clippy::cast_precision_loss)]
+    fn sample_ms(&self) -> f64 {
+        match *self {
+            Self::Fixed { latency_ms } => latency_ms as f64,
+            Self::Uniform { low_ms, high_ms } => {
+                let latency = Uniform::new_inclusive(low_ms, high_ms);
+                with_rng(|rng| latency.sample(rng)) as f64
+            }
+            Self::Normal {
+                mean_ms,
+                std_dev_ms,
+            } => {
+                // Box-Muller transform
+                let (u1, u2): (f64, f64) = with_rng(|rng| (rng.gen(), rng.gen()));
+                let z = (-2.0 * (1.0 - u1).ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                (mean_ms + std_dev_ms * z).max(0.0)
+            }
+        }
+    }
+}
+
+/// The conditions of a single link, modelled on a WAN connection
+///
+/// Each message is dropped with probability `drop_probability`. Messages which are kept are
+/// delayed by the latency sampled from `latency`, plus up to `jitter_ms` in either direction.
+/// With probability `reorder_probability`, a message is held back by a further
+/// `reorder_delay_ms`, so that it arrives after messages sent later.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkConditions {
+    /// distribution of the base latency
+    pub latency: LatencyDistribution,
+    /// maximum deviation from the base latency in milliseconds
+    pub jitter_ms: u64,
+    /// probability that a message is dropped
+    pub drop_probability: f64,
+    /// probability that a message is held back
+    pub reorder_probability: f64,
+    /// how long a held back message is delayed by in milliseconds
+    pub reorder_delay_ms: u64,
+}
+
+impl Default for LinkConditions {
+    // a perfect link
+    fn default() -> Self {
+        LinkConditions {
+            latency: LatencyDistribution::Fixed { latency_ms: 0 },
+            jitter_ms: 0,
+            drop_probability: 0.0,
+            reorder_probability: 0.0,
+            reorder_delay_ms: 0,
+        }
+    }
+}
+
+impl NetworkReliability for LinkConditions {
+    fn sample_keep(&self) -> bool {
+        let keep = Bernoulli::new(1.0 - self.drop_probability).unwrap();
+        with_rng(|rng| keep.sample(rng))
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn sample_delay(&self) -> Duration {
+        let jitter = self.jitter_ms as f64;
+        let jitter = with_rng(|rng| rng.gen_range(-jitter..=jitter));
+        let mut delay_ms = (self.latency.sample_ms() + jitter).max(0.0);
+        let reorder = Bernoulli::new(self.reorder_probability).unwrap();
+        if with_rng(|rng| reorder.sample(rng)) {
+            delay_ms += self.reorder_delay_ms as f64;
+        }
+        Duration::from_millis(delay_ms as u64)
     }
 }
 
@@ -25,10 +25,13 @@ use vbs::version::StaticVersionType;
 
 use super::{
     auction_results_provider::AuctionResultsProvider,
+    block_builder_strategy::BlockBuilderStrategy,
     block_contents::{BlockHeader, TestableBlock, Transaction},
     network::{
-        AsyncGenerator, ConnectedNetwork, NetworkReliability, TestableNetworkingImplementation,
+        AsyncGenerator, ConnectedNetwork, MessageHook, NetworkReliability,
+        TestableNetworkingImplementation,
     },
+    proposal_validator::ProposalValidator,
     signature_key::BuilderSignatureKey,
     states::TestableState,
     storage::Storage,
@@ -68,6 +71,12 @@ pub trait NodeImplementation<TYPES: NodeType>:
 
     /// The auction results type for Solver interactions
     type AuctionResultsProvider: AuctionResultsProvider<TYPES>;
+
+    /// Application-level checks on quorum proposals, run before voting
+    type ProposalValidator: ProposalValidator<TYPES>;
+
+    /// How the leader chooses among the blocks offered by the builders
+    type BlockBuilderStrategy: BlockBuilderStrategy<TYPES>;
 }
 
 /// extra functions required on a node implementation to be usable by hotshot-testing
@@ -106,6 +115,25 @@ pub trait TestableNodeImplementation<TYPES: NodeType>: NodeImplementation<TYPES>
         reliability_config: Option<Box<dyn NetworkReliability>>,
         secondary_network_delay: Duration,
     ) -> AsyncGenerator<Arc<Self::Network>>;
+
+    /// Partition the test network, see [`TestableNetworkingImplementation::partition`]
+    fn partition_network(network: &Self::Network, groups: Vec<Vec<TYPES::SignatureKey>>);
+
+    /// Heal a partition of the test network
+    fn heal_network_partition(network: &Self::Network);
+
+    /// Set the reliability of a link of the test network, see
+    /// [`TestableNetworkingImplementation::set_link_reliability`]
+    fn set_network_link_reliability(
+        network: &Self::Network,
+        from: TYPES::SignatureKey,
+        to: TYPES::SignatureKey,
+        reliability: Box<dyn NetworkReliability>,
+    );
+
+    /// Add a message hook to the test network, see
+    /// [`TestableNetworkingImplementation::add_message_hook`]
+    fn add_network_message_hook(network: &Self::Network, hook: MessageHook<TYPES::SignatureKey>);
 }
 
 #[async_trait]
@@ -157,6 +185,32 @@ where
             secondary_network_delay,
         )
     }
+
+    fn partition_network(network: &Self::Network, groups: Vec<Vec<TYPES::SignatureKey>>) {
+        <I::Network as TestableNetworkingImplementation<TYPES>>::partition(network, groups);
+    }
+
+    fn heal_network_partition(network: &Self::Network) {
+        <I::Network as TestableNetworkingImplementation<TYPES>>::heal_partition(network);
+    }
+
+    fn set_network_link_reliability(
+        network: &Self::Network,
+        from: TYPES::SignatureKey,
+        to: TYPES::SignatureKey,
+        reliability: Box<dyn NetworkReliability>,
+    ) {
+        <I::Network as TestableNetworkingImplementation<TYPES>>::set_link_reliability(
+            network,
+            from,
+            to,
+            reliability,
+        );
+    }
+
+    fn add_network_message_hook(network: &Self::Network, hook: MessageHook<TYPES::SignatureKey>) {
+        <I::Network as TestableNetworkingImplementation<TYPES>>::add_message_hook(network, hook);
+    }
 }
 
 /// Trait for time compatibility needed for reward collection
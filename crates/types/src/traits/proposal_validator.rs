@@ -0,0 +1,89 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! This module defines the [`ProposalValidator`] trait, through which an application can reject
+//! quorum proposals which violate its own rules before the replica votes for them, check payloads
+//! before DA committee members vote for them, and decide which messages the replica attests to.
+
+use async_trait::async_trait;
+
+use super::{block_contents::BlockPayload, node_implementation::NodeType};
+use crate::data::{Leaf2, QuorumProposal2};
+
+/// Application-level checks on a quorum proposal, run by a replica before it votes.
+///
+/// The validator is consulted only once the proposal has passed all of the consensus checks, so
+/// it only needs to enforce the rules of the application. If it rejects a proposal, the replica
+/// withholds its vote and reports the rejection on the event stream.
+#[async_trait]
+pub trait ProposalValidator<TYPES: NodeType>: Send + Sync + 'static {
+    /// Check `proposal`, which extends `parent_leaf`.
+    ///
+    /// # Errors
+    /// Returns the reason for rejecting the proposal if it violates the application's rules.
+    async fn validate_proposal(
+        instance_state: &TYPES::InstanceState,
+        proposal: &QuorumProposal2<TYPES>,
+        parent_leaf: &Leaf2<TYPES>,
+    ) -> Result<(), String>;
+
+    /// Check `payload`, with its `metadata`, which the leader of `view` proposed to the DA
+    /// committee. Only consulted by DA committee members which validate payloads before voting
+    /// for them, so that a DA certificate implies the payload passed these checks.
+    ///
+    /// Replicas accept every payload unless the application overrides this.
+    ///
+    /// # Errors
+    /// Returns the reason for rejecting the payload if it violates the application's rules.
+    async fn validate_payload(
+        _instance_state: &TYPES::InstanceState,
+        _view: TYPES::View,
+        _payload: &TYPES::BlockPayload,
+        _metadata: &<TYPES::BlockPayload as BlockPayload<TYPES>>::Metadata,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Check `message`, which another node asked the quorum to attest to as of the decided
+    /// `leaf`, after which the application state is `state`.
+    ///
+    /// Replicas refuse every attestation unless the application overrides this.
+    ///
+    /// # Errors
+    /// Returns the reason for refusing to attest to the message.
+    async fn validate_attestation(
+        _instance_state: &TYPES::InstanceState,
+        _leaf: &Leaf2<TYPES>,
+        _state: &TYPES::ValidatedState,
+        _message: &[u8],
+    ) -> Result<(), String> {
+        Err("The application does not attest to messages".to_string())
+    }
+}
+
+/// A [`ProposalValidator`] which accepts every proposal and payload, and attests to every message.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AcceptAllProposals;
+
+#[async_trait]
+impl<TYPES: NodeType> ProposalValidator<TYPES> for AcceptAllProposals {
+    async fn validate_proposal(
+        _instance_state: &TYPES::InstanceState,
+        _proposal: &QuorumProposal2<TYPES>,
+        _parent_leaf: &Leaf2<TYPES>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn validate_attestation(
+        _instance_state: &TYPES::InstanceState,
+        _leaf: &Leaf2<TYPES>,
+        _state: &TYPES::ValidatedState,
+        _message: &[u8],
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
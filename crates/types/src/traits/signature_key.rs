@@ -56,6 +56,12 @@ pub trait PrivateSignatureKey:
 
 /// Trait for abstracting public key signatures
 /// Self is the public key type
+///
+/// Quorum certificates are aggregated multi-signatures rather than threshold signatures: every
+/// node generates its own key pair, and [`SignatureKey::assemble`] combines the individual
+/// signatures with a bitmap of the signers. A certificate is checked against the stake table
+/// entries of its signers, so there is no group key to set up, and no dealer or distributed key
+/// generation is needed when the committee changes.
 pub trait SignatureKey:
     Send
     + Sync
@@ -161,11 +167,18 @@ pub trait SignatureKey:
     fn sig_proof(signature: &Self::QcType) -> (Self::PureAssembledSignatureType, BitVec);
 
     /// assemble the signature from the partial signature and the indication of signers in `BitVec`
+    ///
+    /// The result is verified with the signers' own public keys from `real_qc_pp`; no shared
+    /// group key is involved.
+    ///
+    /// # Errors
+    /// if `signers` does not match the stake table of `real_qc_pp` or the signatures, or the
+    /// signers do not reach the threshold
     fn assemble(
         real_qc_pp: &Self::QcParams,
         signers: &BitSlice,
         sigs: &[Self::PureAssembledSignatureType],
-    ) -> Self::QcType;
+    ) -> Result<Self::QcType, Self::SignError>;
 
     /// generates the genesis public key. Meant to be dummy/filler
     #[must_use]
@@ -0,0 +1,105 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Snapshots of the application state, which let a new node start from the latest checkpoint
+//! instead of replaying the chain from genesis.
+//!
+//! A snapshot is the serialized [`ValidatedState`] at a certified checkpoint, split into chunks so
+//! that it can be downloaded piecewise and resumed. The [`SnapshotManifest`] lists the digest of
+//! every chunk; it is not trusted by itself, but the assembled state must hash to the state digest
+//! signed in the checkpoint certificate.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{node_implementation::NodeType, ValidatedState};
+use crate::{
+    data::Leaf2,
+    simple_certificate::{CheckpointCertificate, QuorumCertificate2},
+};
+
+/// Describes how the snapshot of the state at a checkpoint is split into chunks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Height of the checkpointed block
+    pub height: u64,
+    /// Digest of the state the snapshot decodes to, as signed in the checkpoint certificate
+    pub state_digest: [u8; 32],
+    /// Total length of the serialized state, in bytes
+    pub len: u64,
+    /// SHA-256 digest of each chunk, in order
+    pub chunk_digests: Vec<[u8; 32]>,
+}
+
+impl SnapshotManifest {
+    /// Number of chunks in the snapshot
+    #[must_use]
+    pub fn num_chunks(&self) -> usize {
+        self.chunk_digests.len()
+    }
+
+    /// Whether `chunk` is the chunk at `index` of this snapshot.
+    #[must_use]
+    pub fn is_valid_chunk(&self, index: usize, chunk: &[u8]) -> bool {
+        self.chunk_digests
+            .get(index)
+            .is_some_and(|digest| *digest == <[u8; 32]>::from(Sha256::digest(chunk)))
+    }
+}
+
+/// Serialize `state` and split it into chunks of at most `chunk_size` bytes, to be served as the
+/// snapshot of the checkpoint at `height`.
+///
+/// # Errors
+/// If the state cannot be serialized.
+pub fn split_snapshot<TYPES: NodeType>(
+    state: &TYPES::ValidatedState,
+    height: u64,
+    chunk_size: usize,
+) -> Result<(SnapshotManifest, Vec<Vec<u8>>)> {
+    let bytes = bincode::serialize(state)?;
+    let chunks: Vec<Vec<u8>> = bytes
+        .chunks(chunk_size.max(1))
+        .map(<[u8]>::to_vec)
+        .collect();
+
+    let manifest = SnapshotManifest {
+        height,
+        state_digest: state.state_digest(),
+        len: bytes.len() as u64,
+        chunk_digests: chunks
+            .iter()
+            .map(|chunk| Sha256::digest(chunk).into())
+            .collect(),
+    };
+
+    Ok((manifest, chunks))
+}
+
+/// A place snapshots can be downloaded from, such as a peer or an object storage bucket.
+///
+/// Nothing a source returns is trusted: certificates are checked against the stake table, the
+/// leaf and its QC against the certificate, and the assembled state against the certified state
+/// digest.
+#[async_trait]
+pub trait SnapshotSource<TYPES: NodeType>: Send + Sync {
+    /// Fetches the certificate of the latest checkpoint the source has a snapshot for.
+    async fn latest_checkpoint(&self) -> Result<CheckpointCertificate<TYPES>>;
+
+    /// Fetches the leaf decided at the checkpoint at `height`.
+    async fn fetch_leaf(&self, height: u64) -> Result<Leaf2<TYPES>>;
+
+    /// Fetches the QC for the leaf decided at the checkpoint at `height`.
+    async fn fetch_qc(&self, height: u64) -> Result<QuorumCertificate2<TYPES>>;
+
+    /// Fetches the manifest of the snapshot taken at `height`.
+    async fn fetch_manifest(&self, height: u64) -> Result<SnapshotManifest>;
+
+    /// Fetches the chunk at `index` of the snapshot taken at `height`.
+    async fn fetch_chunk(&self, height: u64, index: usize) -> Result<Vec<u8>>;
+}
@@ -87,6 +87,14 @@ pub trait ValidatedState<TYPES: NodeType>:
 
     /// Gets called to notify the persistence backend that this state has been committed
     fn on_commit(&self);
+
+    /// A digest of the state, which replicas sign to certify a checkpoint.
+    ///
+    /// It must be deterministic: every node holding the same state must compute the same digest,
+    /// or checkpoints are never certified. Applications whose state has an authenticated root
+    /// (such as a Merkle tree root) should return that, so that snapshots can be verified
+    /// against it.
+    fn state_digest(&self) -> [u8; 32];
 }
 
 /// extra functions required on state to be usable by hotshot-testing
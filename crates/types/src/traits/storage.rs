@@ -23,14 +23,23 @@ use crate::{
         VidDisperseShare2,
     },
     event::HotShotAction,
+    evidence::SignedEvidence,
     message::Proposal,
     simple_certificate::{
-        NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2, UpgradeCertificate,
+        CheckpointCertificate, NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2,
+        UpgradeCertificate,
     },
+    validator_metadata::SignedValidatorMetadata,
     vid::VidSchemeType,
+    view_change::ViewChangeRecord,
+    vote_intent::VoteIntent,
 };
 
 /// Abstraction for storing a variety of consensus payload datum.
+///
+/// Every method storing consensus data or evidence is required, so that a storage cannot silently
+/// drop it. The records only kept for reporting, and the maintenance hooks at the end, have no-op
+/// defaults.
 #[async_trait]
 pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
     /// Add a proposal to the stored VID proposals.
@@ -60,6 +69,13 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
         &self,
         proposal: &Proposal<TYPES, QuorumProposal2<TYPES>>,
     ) -> Result<()>;
+    /// Add evidence of a protocol violation observed by this node.
+    async fn append_evidence(&self, evidence: &SignedEvidence<TYPES>) -> Result<()>;
+    /// Record that we are about to send the vote of `intent`.
+    ///
+    /// This must be durable when it returns, as the vote is sent right after: the intents
+    /// persisted before a restart keep the node from sending a conflicting vote after it.
+    async fn record_vote_intent(&self, intent: &VoteIntent<TYPES>) -> Result<()>;
     /// Record a HotShotAction taken.
     async fn record_action(&self, view: TYPES::View, action: HotShotAction) -> Result<()>;
     /// Update the current high QC in storage.
@@ -71,6 +87,12 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
         &self,
         next_epoch_high_qc: NextEpochQuorumCertificate2<TYPES>,
     ) -> Result<()>;
+    /// Update the view of the locked QC in storage.
+    ///
+    /// This must be durable before the node votes: a node which forgot its lock after a restart
+    /// could vote for a proposal conflicting with it. The stored lock must only ever move
+    /// forward, a lower view than the stored one is ignored rather than overwriting it.
+    async fn update_locked_view(&self, locked_view: TYPES::View) -> Result<()>;
     /// Update the currently undecided state of consensus.  This includes the undecided leaf chain,
     /// and the undecided state.
     async fn update_undecided_state(
@@ -90,6 +112,11 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
         &self,
         decided_upgrade_certificate: Option<UpgradeCertificate<TYPES>>,
     ) -> Result<()>;
+    /// Update the latest checkpoint certificate in storage, if it is newer than the stored one.
+    async fn update_checkpoint_certificate(
+        &self,
+        checkpoint_certificate: &CheckpointCertificate<TYPES>,
+    ) -> Result<()>;
     /// Migrate leaves from `Leaf` to `Leaf2`, and proposals from `QuorumProposal` to `QuorumProposal2`
     async fn migrate_consensus(
         &self,
@@ -98,4 +125,29 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
             Proposal<TYPES, QuorumProposal<TYPES>>,
         ) -> Proposal<TYPES, QuorumProposal2<TYPES>>,
     ) -> Result<()>;
+    /// Record a view change of this node, with the reason for it. Storages which do not keep the
+    /// history of view changes may ignore it.
+    async fn append_view_change(&self, _record: &ViewChangeRecord) -> Result<()> {
+        Ok(())
+    }
+    /// Keep the latest identity a validator published, replacing its previous one. Storages
+    /// which do not keep validator identities may ignore it.
+    async fn update_validator_metadata(
+        &self,
+        _record: &SignedValidatorMetadata<TYPES>,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Check that the storage is usable, e.g. that its backing store can be reached
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Reclaim space held by data which is no longer needed, if the storage supports it
+    async fn compact(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Make everything written so far durable, e.g. before the node shuts down
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
 }
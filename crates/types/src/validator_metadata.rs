@@ -0,0 +1,180 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Human-readable identities of validators.
+//!
+//! A validator may publish a [`ValidatorMetadata`] record naming its operator, signed with its
+//! consensus key so that nobody else can speak for it. Records are gossiped to every node, which
+//! keeps the latest record of each staked validator in a [`ValidatorMetadataRegistry`], so that
+//! explorers and operators can attribute views and faults to a moniker rather than to a key.
+//!
+//! Records carry a sequence number chosen by the validator; a record only replaces one with a
+//! lower sequence number, so an old record cannot be replayed over a newer one.
+
+use std::collections::BTreeMap;
+
+use committable::{Commitment, Committable, RawCommitmentBuilder};
+use serde::{Deserialize, Serialize};
+use utils::anytrace::*;
+
+use crate::traits::{node_implementation::NodeType, signature_key::SignatureKey};
+
+/// The longest moniker a validator may publish, in bytes
+pub const MAX_MONIKER_LEN: usize = 64;
+
+/// The longest contact or website a validator may publish, in bytes
+pub const MAX_CONTACT_LEN: usize = 256;
+
+/// The identity a validator publishes, each field of which may be left empty
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ValidatorMetadata {
+    /// The name of the validator
+    pub moniker: String,
+    /// How to reach the operator of the validator, e.g. an email address
+    pub contact: String,
+    /// The website of the operator of the validator
+    pub website: String,
+}
+
+impl ValidatorMetadata {
+    /// Check that every field is short enough and free of control characters, so that it can be
+    /// displayed as is
+    ///
+    /// # Errors
+    /// if a field is too long or contains a control character
+    pub fn check(&self) -> Result<()> {
+        for (name, value, max_len) in [
+            ("moniker", &self.moniker, MAX_MONIKER_LEN),
+            ("contact", &self.contact, MAX_CONTACT_LEN),
+            ("website", &self.website, MAX_CONTACT_LEN),
+        ] {
+            ensure!(
+                value.len() <= max_len,
+                info!("The {name} is longer than {max_len} bytes")
+            );
+            ensure!(
+                !value.chars().any(char::is_control),
+                info!("The {name} contains control characters")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A [`ValidatorMetadata`] record signed by the validator it describes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct SignedValidatorMetadata<TYPES: NodeType> {
+    /// The consensus key of the validator
+    pub public_key: TYPES::SignatureKey,
+    /// The identity of the validator
+    pub metadata: ValidatorMetadata,
+    /// The sequence number of the record, higher for newer records
+    pub sequence: u64,
+    /// The signature of the validator over the commitment of the record
+    pub signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+}
+
+impl<TYPES: NodeType> SignedValidatorMetadata<TYPES> {
+    /// Sign `metadata` as record `sequence` of the validator with `private_key`
+    ///
+    /// # Errors
+    /// if the metadata does not [check](ValidatorMetadata::check) out, or signing fails
+    pub fn new(
+        metadata: ValidatorMetadata,
+        sequence: u64,
+        public_key: TYPES::SignatureKey,
+        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    ) -> Result<Self> {
+        metadata.check()?;
+        let commit = commitment::<TYPES>(&public_key, &metadata, sequence);
+        let signature = TYPES::SignatureKey::sign(private_key, commit.as_ref())
+            .wrap()
+            .context(error!("Failed to sign validator metadata"))?;
+        Ok(Self {
+            public_key,
+            metadata,
+            sequence,
+            signature,
+        })
+    }
+
+    /// Whether the record checks out and is signed by the validator it describes
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.metadata.check().is_ok()
+            && self
+                .public_key
+                .validate(&self.signature, self.commit().as_ref())
+    }
+}
+
+/// The commitment of record `sequence` of `public_key`, for `metadata`
+fn commitment<TYPES: NodeType>(
+    public_key: &TYPES::SignatureKey,
+    metadata: &ValidatorMetadata,
+    sequence: u64,
+) -> Commitment<SignedValidatorMetadata<TYPES>> {
+    RawCommitmentBuilder::new("Validator metadata")
+        .var_size_bytes(&public_key.to_bytes())
+        .u64(sequence)
+        .var_size_bytes(metadata.moniker.as_bytes())
+        .var_size_bytes(metadata.contact.as_bytes())
+        .var_size_bytes(metadata.website.as_bytes())
+        .finalize()
+}
+
+/// The commitment of the record, leaving the signature out
+impl<TYPES: NodeType> Committable for SignedValidatorMetadata<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        commitment::<TYPES>(&self.public_key, &self.metadata, self.sequence)
+    }
+}
+
+/// The latest valid [`SignedValidatorMetadata`] record of each validator
+#[derive(Clone, Debug)]
+pub struct ValidatorMetadataRegistry<TYPES: NodeType> {
+    /// The records, by key of the validator
+    records: BTreeMap<TYPES::SignatureKey, SignedValidatorMetadata<TYPES>>,
+}
+
+impl<TYPES: NodeType> Default for ValidatorMetadataRegistry<TYPES> {
+    fn default() -> Self {
+        Self {
+            records: BTreeMap::new(),
+        }
+    }
+}
+
+impl<TYPES: NodeType> ValidatorMetadataRegistry<TYPES> {
+    /// Keep `record` if it is valid and newer than the record of its validator, returning
+    /// whether it was kept
+    ///
+    /// Whether the validator has stake is up to the caller.
+    pub fn insert(&mut self, record: SignedValidatorMetadata<TYPES>) -> bool {
+        if self
+            .records
+            .get(&record.public_key)
+            .is_some_and(|current| current.sequence >= record.sequence)
+            || !record.is_valid()
+        {
+            return false;
+        }
+        self.records.insert(record.public_key.clone(), record);
+        true
+    }
+
+    /// The latest record of the validator with `public_key`, if any
+    #[must_use]
+    pub fn get(&self, public_key: &TYPES::SignatureKey) -> Option<&SignedValidatorMetadata<TYPES>> {
+        self.records.get(public_key)
+    }
+
+    /// The latest record of every validator, by key
+    pub fn records(&self) -> impl Iterator<Item = &SignedValidatorMetadata<TYPES>> {
+        self.records.values()
+    }
+}
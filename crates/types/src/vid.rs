@@ -62,10 +62,7 @@ use crate::{
 #[must_use]
 #[memoize::memoize(SharedCache, Capacity: 10)]
 pub fn vid_scheme(num_storage_nodes: usize) -> VidSchemeType {
-    // recovery_threshold is currently num_storage_nodes rounded down to a power of two
-    // TODO recovery_threshold should be a function of the desired erasure code rate
-    // https://github.com/EspressoSystems/HotShot/issues/2152
-    let recovery_threshold = 1 << num_storage_nodes.ilog2();
+    let recovery_threshold = vid_recovery_threshold(num_storage_nodes);
 
     #[allow(clippy::panic)]
     let num_storage_nodes = u32::try_from(num_storage_nodes).unwrap_or_else(|err| {
@@ -89,7 +86,7 @@ pub fn vid_scheme(num_storage_nodes: usize) -> VidSchemeType {
 #[cfg(feature = "test-srs")]
 #[memoize::memoize(SharedCache, Capacity: 10)]
 pub fn vid_scheme_for_test(num_storage_nodes: usize) -> VidSchemeType {
-    let recovery_threshold = 1 << num_storage_nodes.ilog2();
+    let recovery_threshold = vid_recovery_threshold(num_storage_nodes);
     #[allow(clippy::panic)]
     let num_storage_nodes = u32::try_from(num_storage_nodes).unwrap_or_else(|err| {
         panic!("num_storage_nodes {num_storage_nodes} should fit into u32; error: {err}")
@@ -103,6 +100,18 @@ pub fn vid_scheme_for_test(num_storage_nodes: usize) -> VidSchemeType {
     )
 }
 
+/// The number of shares needed to recover a payload dispersed to `num_storage_nodes` nodes.
+///
+/// # Panics
+/// If `num_storage_nodes` is zero.
+#[must_use]
+pub fn vid_recovery_threshold(num_storage_nodes: usize) -> usize {
+    // recovery_threshold is currently num_storage_nodes rounded down to a power of two
+    // TODO recovery_threshold should be a function of the desired erasure code rate
+    // https://github.com/EspressoSystems/HotShot/issues/2152
+    1 << num_storage_nodes.ilog2()
+}
+
 /// VID commitment type
 pub type VidCommitment = <VidSchemeType as VidScheme>::Commit;
 /// VID common type
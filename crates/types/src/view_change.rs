@@ -0,0 +1,99 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Why a node moved from one view to the next.
+//!
+//! Every view change a node makes is recorded as a [`ViewChangeRecord`], saying whether the view
+//! ended with a quorum certificate, a timeout, view sync, or a timeout without any proposal from
+//! the leader. The latest records are kept in a [`ViewChangeLog`], so that operators can tell why
+//! views fail without digging through the logs.
+
+use std::{collections::VecDeque, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// Why a node left a view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewChangeReason {
+    /// The view ended with a quorum certificate
+    QuorumCertificate,
+    /// The view timed out even though its leader proposed
+    Timeout,
+    /// The node caught up with the network through view sync
+    ViewSync,
+    /// The view timed out without the node receiving a proposal from its leader
+    LeaderOffline,
+}
+
+impl fmt::Display for ViewChangeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::QuorumCertificate => "quorum_certificate",
+            Self::Timeout => "timeout",
+            Self::ViewSync => "view_sync",
+            Self::LeaderOffline => "leader_offline",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// A view change made by a node
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViewChangeRecord {
+    /// the view the node left
+    pub from_view: u64,
+    /// the view the node entered
+    pub to_view: u64,
+    /// the epoch of the view the node entered
+    pub epoch: u64,
+    /// why the node left `from_view`
+    pub reason: ViewChangeReason,
+    /// when the node changed view, in milliseconds since the Unix epoch
+    pub time_ms: u64,
+}
+
+/// A bounded log of the latest view changes of a node, oldest first
+#[derive(Clone, Debug)]
+pub struct ViewChangeLog {
+    /// the view changes
+    records: VecDeque<ViewChangeRecord>,
+    /// the most view changes to keep
+    capacity: usize,
+}
+
+impl ViewChangeLog {
+    /// An empty log which keeps the latest `capacity` view changes
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a view change, evicting the oldest one if the log is full
+    pub fn record(&mut self, record: ViewChangeRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// The latest view change, if any
+    #[must_use]
+    pub fn latest(&self) -> Option<&ViewChangeRecord> {
+        self.records.back()
+    }
+
+    /// The view changes in the log, oldest first
+    pub fn records(&self) -> impl Iterator<Item = &ViewChangeRecord> {
+        self.records.iter()
+    }
+}
@@ -7,7 +7,7 @@
 //! Vote, Accumulator, and Certificate Types
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     marker::PhantomData,
     num::NonZeroU64,
     sync::Arc,
@@ -22,12 +22,14 @@ use tracing::error;
 use utils::anytrace::Result;
 
 use crate::{
+    constants::VERIFIED_VOTES_VIEWS,
+    memory_budget::{MemoryBudget, MemoryComponent},
     message::UpgradeLock,
     simple_certificate::Threshold,
     simple_vote::{VersionedVoteData, Voteable},
     traits::{
         election::Membership,
-        node_implementation::{NodeType, Versions},
+        node_implementation::{ConsensusTime, NodeType, Versions},
         signature_key::{SignatureKey, StakeTableEntryType},
     },
 };
@@ -125,6 +127,128 @@ type SignersMap<COMMITMENT, KEY> = HashMap<
     ),
 >;
 
+/// A vote signature: the signer, the vote commitment signed, and the signature
+type VoteSignature<TYPES> = (
+    <TYPES as NodeType>::SignatureKey,
+    Vec<u8>,
+    <<TYPES as NodeType>::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+);
+
+/// The votes whose signatures were checked as they arrived from the network, so that vote
+/// accumulators do not check them again.
+///
+/// Signatures are remembered with the vote commitment they sign, so a signature replayed on
+/// other data is still checked by the accumulator. Only votes within [`VERIFIED_VOTES_VIEWS`] of
+/// the current view of this node are remembered, so that votes for far future views cannot
+/// push out those being collected. Once the remembered signatures use up their memory budget,
+/// new ones are left for the accumulator to check.
+#[derive(Clone, Debug)]
+pub struct VerifiedVotes<TYPES: NodeType> {
+    /// the valid signatures, by view
+    signatures: Arc<RwLock<BTreeMap<TYPES::View, HashSet<VoteSignature<TYPES>>>>>,
+    /// accounts for the memory of the signatures
+    memory_budget: MemoryBudget,
+}
+
+impl<TYPES: NodeType> Default for VerifiedVotes<TYPES> {
+    fn default() -> Self {
+        Self::new(MemoryBudget::default())
+    }
+}
+
+/// The approximate memory taken by a remembered vote signature
+fn vote_signature_size<TYPES: NodeType>(signature: &VoteSignature<TYPES>) -> usize {
+    std::mem::size_of::<VoteSignature<TYPES>>() + signature.1.len()
+}
+
+impl<TYPES: NodeType> VerifiedVotes<TYPES> {
+    /// Remember valid signatures, within the budget of `memory_budget`
+    #[must_use]
+    pub fn new(memory_budget: MemoryBudget) -> Self {
+        Self {
+            signatures: Arc::default(),
+            memory_budget,
+        }
+    }
+
+    /// Whether votes in `view` are checked ahead of their collectors while this node is in
+    /// `current_view`
+    #[must_use]
+    pub fn is_tracked(current_view: TYPES::View, view: TYPES::View) -> bool {
+        view.saturating_sub(VERIFIED_VOTES_VIEWS) <= *current_view
+            && *view >= current_view.saturating_sub(VERIFIED_VOTES_VIEWS)
+    }
+
+    /// Record that `signature` by `key` over `vote_commitment`, for a vote in `view`, is valid,
+    /// while this node is in `current_view`. The signature is not remembered if the vote is not
+    /// [tracked](Self::is_tracked), or remembering it would exceed the memory budget.
+    pub async fn record(
+        &self,
+        current_view: TYPES::View,
+        view: TYPES::View,
+        key: TYPES::SignatureKey,
+        vote_commitment: &[u8],
+        signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ) {
+        let mut signatures = self.signatures.write().await;
+
+        // Forget the views too old to still be collecting votes
+        let oldest = TYPES::View::new(current_view.saturating_sub(VERIFIED_VOTES_VIEWS));
+        let retained = signatures.split_off(&oldest);
+        let forgotten = std::mem::replace(&mut *signatures, retained);
+        if !forgotten.is_empty() {
+            self.memory_budget.release(
+                MemoryComponent::VerifiedVotes,
+                forgotten
+                    .values()
+                    .flatten()
+                    .map(vote_signature_size::<TYPES>)
+                    .sum(),
+            );
+        }
+        if !Self::is_tracked(current_view, view) {
+            return;
+        }
+
+        let signature = (key, vote_commitment.to_vec(), signature);
+        let size = vote_signature_size::<TYPES>(&signature);
+        if !self
+            .memory_budget
+            .try_reserve(MemoryComponent::VerifiedVotes, size)
+        {
+            return;
+        }
+        if !signatures.entry(view).or_default().insert(signature) {
+            self.memory_budget
+                .release(MemoryComponent::VerifiedVotes, size);
+        }
+    }
+
+    /// Whether `signature` by `key` over `vote_commitment`, for a vote in `view`, was recorded
+    /// as valid. The signature is forgotten, since a vote is only accumulated once.
+    pub async fn take(
+        &self,
+        view: TYPES::View,
+        key: &TYPES::SignatureKey,
+        vote_commitment: &[u8],
+        signature: &<TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ) -> bool {
+        let mut signatures = self.signatures.write().await;
+        let Some(view_signatures) = signatures.get_mut(&view) else {
+            return false;
+        };
+        let signature = (key.clone(), vote_commitment.to_vec(), signature.clone());
+        let removed = view_signatures.remove(&signature);
+        if removed {
+            self.memory_budget.release(
+                MemoryComponent::VerifiedVotes,
+                vote_signature_size::<TYPES>(&signature),
+            );
+        }
+        removed
+    }
+}
+
 #[allow(clippy::type_complexity)]
 /// Accumulates votes until a certificate is formed.  This implementation works for all simple vote and certificate pairs
 pub struct VoteAccumulator<
@@ -149,6 +273,8 @@ pub struct VoteAccumulator<
     pub phantom: PhantomData<(TYPES, VOTE, CERT)>,
     /// version information
     pub upgrade_lock: UpgradeLock<TYPES, V>,
+    /// Votes whose signatures were already checked
+    pub verified_votes: VerifiedVotes<TYPES>,
 }
 
 impl<
@@ -183,7 +309,16 @@ impl<
             }
         };
 
-        if !key.validate(&vote.signature(), vote_commitment.as_ref()) {
+        let verified = self
+            .verified_votes
+            .take(
+                vote.view_number(),
+                &key,
+                vote_commitment.as_ref(),
+                &vote.signature(),
+            )
+            .await;
+        if !verified && !key.validate(&vote.signature(), vote_commitment.as_ref()) {
             error!("Invalid vote! Vote Data {:?}", vote.date());
             return Either::Left(());
         }
@@ -239,11 +374,17 @@ impl<
                     U256::from(threshold),
                 );
 
-            let real_qc_sig = <TYPES::SignatureKey as SignatureKey>::assemble(
+            let real_qc_sig = match <TYPES::SignatureKey as SignatureKey>::assemble(
                 &real_qc_pp,
                 signers.as_bitslice(),
                 &sig_list[..],
-            );
+            ) {
+                Ok(sig) => sig,
+                Err(e) => {
+                    error!("Failed to assemble a certificate from the votes: {e}");
+                    return Either::Left(());
+                }
+            };
 
             let cert = CERT::create_signed_certificate::<V>(
                 vote_commitment,
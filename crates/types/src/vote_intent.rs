@@ -0,0 +1,139 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Write-ahead records of the votes a node is about to send.
+//!
+//! Before sending a quorum or DA vote, a node durably records a [`VoteIntent`] naming the view and
+//! the commitment it votes for, and refuses to send a vote for the same view and a different
+//! commitment. Unlike the last actioned view, which only says how far a node got, the intents say
+//! what it voted for, so a node restarted from stale state may resend a vote it already sent but
+//! never a conflicting one.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    message::{DaConsensusMessage, GeneralConsensusMessage, MessageKind, SequencingMessage},
+    traits::node_implementation::NodeType,
+    vote::{HasViewNumber, Vote},
+};
+
+/// The kinds of votes guarded by a [`VoteIntentLog`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum VoteKind {
+    /// A vote for a quorum proposal
+    Quorum,
+    /// A vote for a DA proposal
+    Da,
+}
+
+/// A vote a node is about to send
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct VoteIntent<TYPES: NodeType> {
+    /// the view of the vote
+    pub view: TYPES::View,
+    /// what the vote is for
+    pub kind: VoteKind,
+    /// the commitment the vote signs
+    pub commitment: [u8; 32],
+}
+
+impl<TYPES: NodeType> VoteIntent<TYPES> {
+    /// The intent behind `message`, if it is a guarded vote
+    #[must_use]
+    pub fn of_message(message: &MessageKind<TYPES>) -> Option<Self> {
+        let MessageKind::Consensus(message) = message else {
+            return None;
+        };
+        let (view, kind, commitment) = match message {
+            SequencingMessage::General(GeneralConsensusMessage::Vote(vote)) => (
+                vote.view_number(),
+                VoteKind::Quorum,
+                vote.data_commitment().into(),
+            ),
+            SequencingMessage::General(GeneralConsensusMessage::Vote2(vote)) => (
+                vote.view_number(),
+                VoteKind::Quorum,
+                vote.data_commitment().into(),
+            ),
+            SequencingMessage::Da(DaConsensusMessage::DaVote(vote)) => (
+                vote.view_number(),
+                VoteKind::Da,
+                vote.data_commitment().into(),
+            ),
+            SequencingMessage::Da(DaConsensusMessage::DaVote2(vote)) => (
+                vote.view_number(),
+                VoteKind::Da,
+                vote.data_commitment().into(),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            view,
+            kind,
+            commitment,
+        })
+    }
+}
+
+/// How a [`VoteIntent`] relates to the intents already recorded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntentCheck {
+    /// No vote of the kind was intended in the view yet
+    New,
+    /// The same vote was intended before, so sending it again is harmless
+    Repeat,
+    /// A vote of the kind for another commitment was intended in the view
+    Conflict,
+}
+
+/// The vote intents of a node, at most one per view and kind of vote
+#[derive(Clone, Debug)]
+pub struct VoteIntentLog<TYPES: NodeType> {
+    /// the commitment of each intended vote, by view and kind
+    intents: BTreeMap<(TYPES::View, VoteKind), [u8; 32]>,
+}
+
+impl<TYPES: NodeType> Default for VoteIntentLog<TYPES> {
+    fn default() -> Self {
+        Self {
+            intents: BTreeMap::new(),
+        }
+    }
+}
+
+impl<TYPES: NodeType> VoteIntentLog<TYPES> {
+    /// Record `intent` unless it conflicts with a recorded one, and say how it relates to them
+    pub fn insert(&mut self, intent: &VoteIntent<TYPES>) -> IntentCheck {
+        match self.intents.get(&(intent.view, intent.kind)) {
+            None => {
+                self.intents
+                    .insert((intent.view, intent.kind), intent.commitment);
+                IntentCheck::New
+            }
+            Some(commitment) if *commitment == intent.commitment => IntentCheck::Repeat,
+            Some(_) => IntentCheck::Conflict,
+        }
+    }
+
+    /// Forget the intents of views before `view`, in which the node will not vote again
+    pub fn prune(&mut self, view: TYPES::View) {
+        self.intents = self.intents.split_off(&(view, VoteKind::Quorum));
+    }
+
+    /// The recorded intents, by view
+    pub fn intents(&self) -> impl Iterator<Item = VoteIntent<TYPES>> + '_ {
+        self.intents
+            .iter()
+            .map(|(&(view, kind), &commitment)| VoteIntent {
+                view,
+                kind,
+                commitment,
+            })
+    }
+}
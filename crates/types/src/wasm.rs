@@ -0,0 +1,101 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Verification of certificates from JavaScript, for light clients running in a browser.
+//!
+//! With the `wasm` feature, this crate builds for `wasm32-unknown-unknown`:
+//!
+//! ```text
+//! cargo build -p hotshot-types --target wasm32-unknown-unknown --features wasm
+//! wasm-bindgen --target web target/wasm32-unknown-unknown/debug/hotshot_types.wasm --out-dir pkg
+//! ```
+//!
+//! Certificates and stake tables are passed in their JSON encoding, as served by a node. A stake
+//! table is a list of `{ "stake_key": "BLS_VER_KEY~...", "stake_amount": "0x..." }` entries.
+
+use primitive_types::U256;
+use serde::Deserialize;
+use tagged_base64::TaggedBase64;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    signature_key::BLSPubKey, stake_table::StakeTableEntry, traits::signature_key::SignatureKey,
+};
+
+/// The fields of a certificate needed to verify it
+#[derive(Deserialize)]
+struct CertificateSignatures {
+    /// the commitment the votes of the certificate signed, as tagged base64
+    vote_commitment: String,
+    /// the view of the certificate
+    view_number: u64,
+    /// the assembled signature, absent for the genesis certificate
+    signatures: Option<<BLSPubKey as SignatureKey>::QcType>,
+}
+
+/// Whether `signatures` are signatures over `commitment` by keys of `stake_table` with at least
+/// `threshold` stake in total
+fn check_signatures(
+    stake_table: Vec<StakeTableEntry<BLSPubKey>>,
+    threshold: u64,
+    commitment: &[u8],
+    signatures: &<BLSPubKey as SignatureKey>::QcType,
+) -> Result<bool, JsError> {
+    if commitment.len() != 32 {
+        return Err(JsError::new("A commitment is 32 bytes long"));
+    }
+    let params = BLSPubKey::public_parameter(stake_table, U256::from(threshold));
+    Ok(BLSPubKey::check(&params, commitment, signatures))
+}
+
+/// Whether the certificate `certificate_json` is signed by keys of the stake table
+/// `stake_table_json` with at least `threshold` stake in total
+///
+/// This checks the signatures against the vote commitment the certificate carries. To know what
+/// was certified, the caller must also check that the vote commitment is the one of the
+/// certified data.
+///
+/// # Errors
+/// if the certificate or stake table is malformed, or the vote commitment is not 32 bytes long
+#[wasm_bindgen(js_name = verifyCertificate)]
+pub fn verify_certificate(
+    certificate_json: &str,
+    stake_table_json: &str,
+    threshold: u64,
+) -> Result<bool, JsError> {
+    let certificate: CertificateSignatures = serde_json::from_str(certificate_json)?;
+    let stake_table = serde_json::from_str(stake_table_json)?;
+
+    // Like nodes, accept the genesis certificate, which is not signed
+    if certificate.view_number == 0 {
+        return Ok(true);
+    }
+    let Some(signatures) = certificate.signatures else {
+        return Ok(false);
+    };
+    let commitment = TaggedBase64::parse(&certificate.vote_commitment)?.value();
+
+    check_signatures(stake_table, threshold, &commitment, &signatures)
+}
+
+/// Whether `signatures_json`, an assembled signature in its JSON encoding, signs the 32 byte
+/// `commitment` by keys of the stake table `stake_table_json` with at least `threshold` stake in
+/// total
+///
+/// # Errors
+/// if the signature or stake table is malformed, or `commitment` is not 32 bytes long
+#[wasm_bindgen(js_name = verifySignatures)]
+pub fn verify_signatures(
+    signatures_json: &str,
+    stake_table_json: &str,
+    threshold: u64,
+    commitment: &[u8],
+) -> Result<bool, JsError> {
+    let signatures = serde_json::from_str(signatures_json)?;
+    let stake_table = serde_json::from_str(stake_table_json)?;
+
+    check_signatures(stake_table, threshold, commitment, &signatures)
+}
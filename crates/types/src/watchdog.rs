@@ -0,0 +1,54 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Alerts raised by the watchdog of a node when consensus stops making progress.
+//!
+//! Each condition of a [`WatchdogConfig`] raises an [`Alert`] once when it starts to hold, and
+//! is re-armed once it stops holding, so a persistent problem yields a single alert rather than
+//! one per view.
+
+use serde::{Deserialize, Serialize};
+
+/// When the watchdog raises alerts; conditions which are not set are not watched
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    /// Alert once this many views in a row have timed out
+    #[serde(default)]
+    pub max_consecutive_timeouts: Option<u64>,
+    /// Alert once nothing has been decided for this many seconds
+    #[serde(default)]
+    pub max_secs_since_decide: Option<u64>,
+    /// Alert once the rest of the network is more than this many views ahead of this node
+    #[serde(default)]
+    pub max_view_lag: Option<u64>,
+}
+
+/// A condition the watchdog alerts on
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Alert {
+    /// Views timed out one after another
+    ConsecutiveTimeouts {
+        /// the number of views in a row which timed out
+        count: u64,
+        /// the view which timed out last
+        view: u64,
+    },
+    /// Nothing was decided for a while
+    DecideStalled {
+        /// the seconds since the last decide, or since the node started
+        secs_since_decide: u64,
+        /// the last decided view
+        last_decided_view: u64,
+    },
+    /// The rest of the network is ahead of this node
+    FallingBehind {
+        /// the view this node is in
+        view: u64,
+        /// the highest view of a validated certificate
+        network_view: u64,
+    },
+}